@@ -0,0 +1,263 @@
+use cjseq::CityJSONFeature;
+use fcb_core::{deserializer::header_transform, error::Error, FcbReader};
+use serde_json::{Map, Value};
+use similar::TextDiff;
+use std::{collections::BTreeMap, fmt, io::Read};
+
+/// Parts of the features left out of the comparison
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffOptions {
+    /// Skip the vertices, the appearance and the `geometry` of the city objects
+    pub ignore_geometry: bool,
+    /// Skip the `attributes` of the city objects
+    pub ignore_attributes: bool,
+}
+
+/// Differences between the features of two FCB files, matched by feature id
+#[derive(Debug, Default)]
+pub struct DiffReport {
+    /// Ids of the features only in the right file
+    pub added: Vec<String>,
+    /// Ids of the features only in the left file
+    pub removed: Vec<String>,
+    /// Id and unified JSON diff of the features that differ
+    pub changed: Vec<(String, String)>,
+    pub identical: u64,
+}
+
+impl DiffReport {
+    pub fn is_identical(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// 0 if the files have the same features, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        if self.is_identical() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for id in self.removed.iter() {
+            writeln!(f, "- feature {} only in left", id)?;
+        }
+        for id in self.added.iter() {
+            writeln!(f, "+ feature {} only in right", id)?;
+        }
+        for (_, diff) in self.changed.iter() {
+            write!(f, "{}", diff)?;
+        }
+        write!(
+            f,
+            "{} identical, {} changed, {} only in left, {} only in right",
+            self.identical,
+            self.changed.len(),
+            self.removed.len(),
+            self.added.len()
+        )
+    }
+}
+
+/// Compare the features of two FCB files by their CityJSON representation.
+///
+/// Features are matched by id, so the two files may store them in a different order. Vertices
+/// are compared in real coordinates, with the transform of each file applied. The features of
+/// the left file are kept in memory while the right file is streamed.
+pub fn diff<L: Read, R: Read>(
+    left: L,
+    right: R,
+    options: DiffOptions,
+) -> Result<DiffReport, Error> {
+    let mut left_iter = FcbReader::open(left)?.select_all_seq()?;
+    let transform = header_transform(&left_iter.header());
+    let mut left_features = BTreeMap::new();
+    while let Some(feat_buf) = left_iter.next()? {
+        let feature = feat_buf.cur_cj_feature()?;
+        left_features.insert(feature.id.clone(), to_json(feature, transform, options)?);
+    }
+
+    let mut report = DiffReport::default();
+    let mut right_iter = FcbReader::open(right)?.select_all_seq()?;
+    let transform = header_transform(&right_iter.header());
+    while let Some(feat_buf) = right_iter.next()? {
+        let feature = feat_buf.cur_cj_feature()?;
+        let id = feature.id.clone();
+        let right_json = to_json(feature, transform, options)?;
+        match left_features.remove(&id) {
+            None => report.added.push(id),
+            Some(left_json) if left_json == right_json => report.identical += 1,
+            Some(left_json) => {
+                let diff = TextDiff::from_lines(&left_json, &right_json)
+                    .unified_diff()
+                    .header(&format!("left/{id}"), &format!("right/{id}"))
+                    .to_string();
+                report.changed.push((id, diff));
+            }
+        }
+    }
+    report.removed = left_features.into_keys().collect();
+    report.added.sort();
+    report.changed.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(report)
+}
+
+/// Pretty JSON of a feature with sorted keys and real coordinates, one value per line so the
+/// line diff points at the values that differ
+fn to_json(
+    mut feature: CityJSONFeature,
+    (scale, translate): ([f64; 3], [f64; 3]),
+    options: DiffOptions,
+) -> Result<String, Error> {
+    let vertices = std::mem::take(&mut feature.vertices);
+    let mut json = serde_json::to_value(&feature)?;
+    let Value::Object(root) = &mut json else {
+        return Ok(serde_json::to_string_pretty(&json)?);
+    };
+
+    if options.ignore_geometry {
+        root.remove("vertices");
+        root.remove("appearance");
+    } else {
+        let vertices = vertices
+            .iter()
+            .map(|v| {
+                (0..3)
+                    .map(|i| v.get(i).copied().unwrap_or_default() as f64 * scale[i] + translate[i])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        root.insert("vertices".to_string(), serde_json::to_value(vertices)?);
+    }
+    if let Some(Value::Object(city_objects)) = root.get_mut("CityObjects") {
+        for co in city_objects.values_mut() {
+            if let Value::Object(co) = co {
+                if options.ignore_geometry {
+                    co.remove("geometry");
+                }
+                if options.ignore_attributes {
+                    co.remove("attributes");
+                }
+            }
+        }
+    }
+    Ok(serde_json::to_string_pretty(&sort_keys(json))?)
+}
+
+/// Rebuilds the objects with their keys in order, whatever the map type of serde_json
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_keys(v)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb_core::{
+        attribute::{AttributeSchema, AttributeSchemaMethods},
+        header_writer::HeaderWriterOptions,
+        read_cityjson_from_reader, CJType, CJTypeKind, CityJSONSeq, FcbWriter,
+    };
+    use std::{fs::File, io::BufReader, path::PathBuf};
+
+    fn write_fcb(seq: &CityJSONSeq) -> Vec<u8> {
+        let mut attr_schema = AttributeSchema::new();
+        for co in seq.features.iter().flat_map(|f| f.city_objects.values()) {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )
+        .unwrap();
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature).unwrap();
+        }
+        let mut buf = Vec::new();
+        fcb.write(&mut buf).unwrap();
+        buf
+    }
+
+    fn read_seq() -> CityJSONSeq {
+        let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../fcb_core/tests/data/small.city.jsonl");
+        let CJType::Seq(seq) =
+            read_cityjson_from_reader(BufReader::new(File::open(input).unwrap()), CJTypeKind::Seq)
+                .unwrap()
+        else {
+            panic!("expected cityjsonseq");
+        };
+        seq
+    }
+
+    #[test]
+    fn diff_features() {
+        let seq = read_seq();
+        let left = write_fcb(&seq);
+
+        let report = diff(left.as_slice(), left.as_slice(), DiffOptions::default()).unwrap();
+        assert!(report.is_identical());
+        assert_eq!(report.identical, seq.features.len() as u64);
+        assert_eq!(report.exit_code(), 0);
+
+        // drop the first feature and change an attribute of the second one
+        let mut changed = read_seq();
+        let removed = changed.features.remove(0).id;
+        let feature = &mut changed.features[0];
+        let changed_id = feature.id.clone();
+        let attributes = feature
+            .city_objects
+            .values_mut()
+            .find_map(|co| match co.attributes.as_mut() {
+                Some(Value::Object(attributes)) if attributes.contains_key("identificatie") => {
+                    Some(attributes)
+                }
+                _ => None,
+            })
+            .unwrap();
+        attributes.insert("identificatie".to_string(), Value::from("changed"));
+        let right = write_fcb(&changed);
+
+        let report = diff(left.as_slice(), right.as_slice(), DiffOptions::default()).unwrap();
+        assert_eq!(report.removed, vec![removed.clone()]);
+        assert!(report.added.is_empty());
+        assert_eq!(report.changed.len(), 1);
+        let (id, unified) = &report.changed[0];
+        assert_eq!(id, &changed_id);
+        assert!(unified.contains(&format!("--- left/{changed_id}")));
+        assert!(unified.contains("+        \"identificatie\": \"changed\""));
+        assert_eq!(report.exit_code(), 1);
+
+        // swapping the sides reports the removed feature as added, attributes are skipped
+        let options = DiffOptions {
+            ignore_attributes: true,
+            ..Default::default()
+        };
+        let report = diff(right.as_slice(), left.as_slice(), options).unwrap();
+        assert_eq!(report.added, vec![removed]);
+        assert!(report.removed.is_empty());
+        assert!(report.changed.is_empty());
+    }
+}
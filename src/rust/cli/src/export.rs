@@ -0,0 +1,182 @@
+use fcb_core::{
+    deserializer::decode_attributes, error::Error, serializer::ObjWriter, FcbReader,
+    FEATURE_ID_COLUMN,
+};
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Write the attributes of every feature as CSV, one row per feature.
+///
+/// The first column is `feature_id`, followed by the given columns or all columns of the header.
+/// When several city objects of a feature have the same attribute, the first one wins. Only the
+/// attribute bytes are decoded and features are streamed, so memory use does not grow with the
+/// dataset. Returns the number of rows written.
+pub fn export_csv<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    columns: Option<&[String]>,
+) -> Result<u64, Error> {
+    let mut iter = FcbReader::open(reader)?.select_all_seq()?;
+
+    let header_columns = iter
+        .header()
+        .columns()
+        .iter()
+        .flat_map(|cols| cols.iter())
+        .filter(|col| col.name() != FEATURE_ID_COLUMN)
+        .map(|col| col.name().to_string())
+        .collect::<Vec<_>>();
+    let columns = match columns {
+        Some(names) => {
+            if let Some(unknown) = names.iter().find(|n| !header_columns.contains(n)) {
+                return Err(Error::IoError(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown attribute '{}'", unknown),
+                )));
+            }
+            names.to_vec()
+        }
+        None => header_columns,
+    };
+
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer
+        .write_record(std::iter::once("feature_id").chain(columns.iter().map(String::as_str)))
+        .map_err(io::Error::from)?;
+
+    let mut rows = 0;
+    let mut values: Vec<Option<Value>> = vec![None; columns.len()];
+    while let Some(feat_buf) = iter.next()? {
+        values.iter_mut().for_each(|v| *v = None);
+        let feature = feat_buf.cur_feature();
+        let root_columns = feat_buf.header().columns();
+        for co in feature.objects().iter().flat_map(|objects| objects.iter()) {
+            let (Some(attributes), Some(co_columns)) =
+                (co.attributes(), co.columns().or(root_columns))
+            else {
+                continue;
+            };
+            let Value::Object(mut map) = decode_attributes(&co_columns, attributes)? else {
+                continue;
+            };
+            for (name, value) in columns.iter().zip(values.iter_mut()) {
+                if value.is_none() {
+                    *value = map.remove(name).filter(|v| !v.is_null());
+                }
+            }
+        }
+
+        let record = std::iter::once(feature.id().to_string()).chain(
+            values
+                .iter()
+                .map(|v| v.as_ref().map(to_csv_field).unwrap_or_default()),
+        );
+        csv_writer.write_record(record).map_err(io::Error::from)?;
+        rows += 1;
+    }
+    csv_writer.flush()?;
+    Ok(rows)
+}
+
+/// Write the geometries of every feature as a Wavefront OBJ model at `output`, with their
+/// materials in an MTL library of the same name next to it.
+///
+/// Features are streamed, only the materials are kept in memory. See
+/// [`fcb_core::serializer::to_obj`] for the layout. Returns the number of features written.
+pub fn export_obj<R: Read>(reader: R, output: &Path) -> Result<u64, Error> {
+    let mut iter = FcbReader::open(reader)?.select_all_seq()?;
+
+    let mtl_path = output.with_extension("mtl");
+    let mut obj = BufWriter::new(File::create(output)?);
+    if let Some(name) = mtl_path.file_name() {
+        writeln!(obj, "mtllib {}", name.to_string_lossy())?;
+    }
+    let mut obj_writer = ObjWriter::new(obj, &iter.header());
+
+    let mut features = 0;
+    while let Some(feat_buf) = iter.next()? {
+        obj_writer.add_feature(&feat_buf.cur_cj_feature()?)?;
+        features += 1;
+    }
+    let (_, mtl) = obj_writer.finish()?;
+    fs::write(mtl_path, mtl)?;
+    Ok(features)
+}
+
+/// Strings are written as is, the csv writer takes care of quoting
+fn to_csv_field(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb_core::{
+        attribute::{AttributeSchema, AttributeSchemaMethods},
+        header_writer::HeaderWriterOptions,
+        read_cityjson_from_reader, CJType, CJTypeKind, FcbWriter,
+    };
+    use std::{fs::File, io::BufReader, path::PathBuf};
+
+    #[test]
+    fn export_selected_columns() {
+        let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../fcb_core/tests/data/small.city.jsonl");
+        let CJType::Seq(seq) =
+            read_cityjson_from_reader(BufReader::new(File::open(input).unwrap()), CJTypeKind::Seq)
+                .unwrap()
+        else {
+            panic!("expected cityjsonseq");
+        };
+        let mut attr_schema = AttributeSchema::new();
+        for co in seq.features.iter().flat_map(|f| f.city_objects.values()) {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )
+        .unwrap();
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature).unwrap();
+        }
+        let mut fcb_buf = Vec::new();
+        fcb.write(&mut fcb_buf).unwrap();
+
+        let columns = vec!["identificatie".to_string(), "b3_h_dak_50p".to_string()];
+        let mut csv_buf = Vec::new();
+        let rows = export_csv(fcb_buf.as_slice(), &mut csv_buf, Some(&columns)).unwrap();
+        assert_eq!(rows, seq.features.len() as u64);
+
+        let csv = String::from_utf8(csv_buf).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("feature_id,identificatie,b3_h_dak_50p"));
+        // features are hilbert sorted by the writer
+        let mut ids = lines
+            .map(|l| l.split(',').next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        let mut expected = seq
+            .features
+            .iter()
+            .map(|f| f.id.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        let unknown = vec!["no_such_column".to_string()];
+        assert!(export_csv(fcb_buf.as_slice(), Vec::new(), Some(&unknown)).is_err());
+    }
+}
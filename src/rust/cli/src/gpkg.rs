@@ -0,0 +1,512 @@
+//! GeoPackage 1.3 export, see <https://www.geopackage.org/spec130/>
+
+use cjseq::{Boundaries, CityJSONFeature, Geometry, GeometryType};
+use fcb_core::{deserializer::header_transform, error::Error, fb::ColumnType, FcbReader, Header};
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection, Transaction};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// `application_id` of GeoPackage files, "GPKG" in ASCII
+const GPKG_APPLICATION_ID: i32 = 0x4750_4B47;
+/// `user_version` of GeoPackage 1.3.0
+const GPKG_USER_VERSION: i32 = 10300;
+/// SRS of datasets without a reference system
+const UNDEFINED_CARTESIAN_SRS_ID: i32 = -1;
+
+/// Columns of every layer, attributes with these names are not exported
+const FID_COLUMN: &str = "fid";
+const GEOMETRY_COLUMN: &str = "geom";
+const OBJECT_ID_COLUMN: &str = "object_id";
+const FEATURE_ID_COLUMN: &str = "feature_id";
+
+const CREATE_GPKG_TABLES: &str = "
+CREATE TABLE gpkg_spatial_ref_sys (
+    srs_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL PRIMARY KEY,
+    organization TEXT NOT NULL,
+    organization_coordsys_id INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE gpkg_contents (
+    table_name TEXT NOT NULL PRIMARY KEY,
+    data_type TEXT NOT NULL,
+    identifier TEXT UNIQUE,
+    description TEXT DEFAULT '',
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    min_x DOUBLE,
+    min_y DOUBLE,
+    max_x DOUBLE,
+    max_y DOUBLE,
+    srs_id INTEGER,
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE gpkg_geometry_columns (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    geometry_type_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL,
+    z TINYINT NOT NULL,
+    m TINYINT NOT NULL,
+    CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+    CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+INSERT INTO gpkg_spatial_ref_sys VALUES
+    ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined',
+     'undefined cartesian coordinate reference system'),
+    ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined',
+     'undefined geographic coordinate reference system'),
+    ('WGS 84 geodetic', 4326, 'EPSG', 4326,
+     'GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563,AUTHORITY[\"EPSG\",\"7030\"]],AUTHORITY[\"EPSG\",\"6326\"]],PRIMEM[\"Greenwich\",0,AUTHORITY[\"EPSG\",\"8901\"]],UNIT[\"degree\",0.0174532925199433,AUTHORITY[\"EPSG\",\"9122\"]],AUTHORITY[\"EPSG\",\"4326\"]]',
+     'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid');
+";
+
+/// Feature table of the city objects of one type
+struct Layer {
+    insert_sql: String,
+    /// 2D extent of the geometries written so far, as `[min_x, min_y, max_x, max_y]`
+    extent: Option<[f64; 4]>,
+}
+
+/// Write every city object as a row of a GeoPackage at `output`, replacing an existing file.
+///
+/// Each city object type becomes a feature table named after the type, with the columns `fid`,
+/// `geom`, `object_id` and `feature_id` followed by one column per attribute of the header,
+/// typed after its column type. The geometry is the first surface geometry of the city object
+/// (e.g. its first LoD), as a MultiPolygon Z with the header transform applied, in the reference
+/// system of the dataset. City objects without surface geometry get a NULL geometry. Returns the
+/// number of rows written.
+pub fn export_gpkg<R: Read>(reader: R, output: &Path) -> Result<u64, Error> {
+    let mut iter = FcbReader::open(reader)?.select_all_seq()?;
+    let header = iter.header();
+    let transform = header_transform(&header);
+    let srs = header.reference_system().and_then(|rs| {
+        (rs.code() > 0).then(|| (rs.authority().unwrap_or("EPSG").to_string(), rs.code()))
+    });
+    let reserved = [
+        FID_COLUMN,
+        GEOMETRY_COLUMN,
+        OBJECT_ID_COLUMN,
+        FEATURE_ID_COLUMN,
+    ];
+    let columns = header
+        .columns()
+        .iter()
+        .flat_map(|cols| cols.iter())
+        .filter(|col| !reserved.contains(&col.name()))
+        .map(|col| (col.name().to_string(), col.type_()))
+        .collect::<Vec<_>>();
+
+    if output.exists() {
+        fs::remove_file(output)?;
+    }
+    let mut conn = Connection::open(output).map_err(sqlite_error)?;
+    let tx = conn.transaction().map_err(sqlite_error)?;
+    let srs_id = create_gpkg_tables(&tx, srs).map_err(sqlite_error)?;
+
+    let mut layers: HashMap<String, Layer> = HashMap::new();
+    let mut rows = 0;
+    while let Some(feat_buf) = iter.next()? {
+        let feature = feat_buf.cur_cj_feature()?;
+        let mut ids = feature.city_objects.keys().collect::<Vec<_>>();
+        ids.sort();
+        for id in ids {
+            let co = &feature.city_objects[id];
+            let table = co.thetype.trim_start_matches('+');
+            if !layers.contains_key(table) {
+                let layer = create_layer(&tx, table, &columns, srs_id).map_err(sqlite_error)?;
+                layers.insert(table.to_string(), layer);
+            }
+            let layer = layers.get_mut(table).expect("layer was just created");
+
+            let polygons = co
+                .geometry
+                .iter()
+                .flatten()
+                .find(|g| is_surface_geometry(&g.thetype))
+                .map(|g| to_polygons(g, &feature, &transform))
+                .transpose()?
+                .filter(|polygons| !polygons.is_empty());
+            let geometry = match polygons {
+                Some(polygons) => {
+                    let (blob, extent) = to_gpkg_geometry(&polygons, srs_id);
+                    layer.extent = Some(match layer.extent {
+                        Some(e) => [
+                            e[0].min(extent[0]),
+                            e[1].min(extent[1]),
+                            e[2].max(extent[2]),
+                            e[3].max(extent[3]),
+                        ],
+                        None => extent,
+                    });
+                    SqlValue::Blob(blob)
+                }
+                None => SqlValue::Null,
+            };
+
+            let attributes = co.attributes.as_ref().and_then(Value::as_object);
+            let values = [
+                geometry,
+                SqlValue::Text(id.clone()),
+                SqlValue::Text(feature.id.clone()),
+            ]
+            .into_iter()
+            .chain(columns.iter().map(|(name, column_type)| {
+                attributes
+                    .and_then(|a| a.get(name))
+                    .map_or(SqlValue::Null, |v| to_sql_value(v, *column_type))
+            }));
+            tx.prepare_cached(&layer.insert_sql)
+                .and_then(|mut stmt| stmt.execute(params_from_iter(values)))
+                .map_err(sqlite_error)?;
+            rows += 1;
+        }
+    }
+
+    for (table, layer) in layers.iter() {
+        if let Some([min_x, min_y, max_x, max_y]) = layer.extent {
+            tx.execute(
+                "UPDATE gpkg_contents SET min_x = ?1, min_y = ?2, max_x = ?3, max_y = ?4
+                 WHERE table_name = ?5",
+                params![min_x, min_y, max_x, max_y, table],
+            )
+            .map_err(sqlite_error)?;
+        }
+    }
+    tx.commit().map_err(sqlite_error)?;
+    Ok(rows)
+}
+
+fn sqlite_error(e: rusqlite::Error) -> Error {
+    Error::IoError(io::Error::other(e))
+}
+
+/// Quotes an SQL identifier
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Creates the GeoPackage metadata tables, returning the SRS id of the dataset
+fn create_gpkg_tables(tx: &Transaction, srs: Option<(String, i32)>) -> rusqlite::Result<i32> {
+    tx.pragma_update(None, "application_id", GPKG_APPLICATION_ID)?;
+    tx.pragma_update(None, "user_version", GPKG_USER_VERSION)?;
+    tx.execute_batch(CREATE_GPKG_TABLES)?;
+    let Some((organization, code)) = srs else {
+        return Ok(UNDEFINED_CARTESIAN_SRS_ID);
+    };
+    // the WKT definition is not part of the FCB header
+    tx.execute(
+        "INSERT OR IGNORE INTO gpkg_spatial_ref_sys
+         (srs_name, srs_id, organization, organization_coordsys_id, definition)
+         VALUES (?1, ?2, ?3, ?2, 'undefined')",
+        params![format!("{organization}:{code}"), code, organization],
+    )?;
+    Ok(code)
+}
+
+/// Creates the feature table `table` and registers it
+fn create_layer(
+    tx: &Transaction,
+    table: &str,
+    columns: &[(String, ColumnType)],
+    srs_id: i32,
+) -> rusqlite::Result<Layer> {
+    let attribute_columns = columns
+        .iter()
+        .map(|(name, column_type)| format!(", {} {}", quote(name), sql_type(*column_type)))
+        .collect::<String>();
+    tx.execute_batch(&format!(
+        "CREATE TABLE {} ({FID_COLUMN} INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, \
+         {GEOMETRY_COLUMN} MULTIPOLYGON, {OBJECT_ID_COLUMN} TEXT NOT NULL, \
+         {FEATURE_ID_COLUMN} TEXT NOT NULL{attribute_columns})",
+        quote(table)
+    ))?;
+    tx.execute(
+        "INSERT INTO gpkg_contents (table_name, data_type, identifier, srs_id)
+         VALUES (?1, 'features', ?1, ?2)",
+        params![table, srs_id],
+    )?;
+    tx.execute(
+        "INSERT INTO gpkg_geometry_columns
+         (table_name, column_name, geometry_type_name, srs_id, z, m)
+         VALUES (?1, ?2, 'MULTIPOLYGON', ?3, 1, 0)",
+        params![table, GEOMETRY_COLUMN, srs_id],
+    )?;
+
+    let names = [GEOMETRY_COLUMN, OBJECT_ID_COLUMN, FEATURE_ID_COLUMN]
+        .into_iter()
+        .map(quote)
+        .chain(columns.iter().map(|(name, _)| quote(name)))
+        .collect::<Vec<_>>();
+    let placeholders = (1..=names.len())
+        .map(|i| format!("?{i}"))
+        .collect::<Vec<_>>();
+    Ok(Layer {
+        insert_sql: format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote(table),
+            names.join(", "),
+            placeholders.join(", ")
+        ),
+        extent: None,
+    })
+}
+
+/// GeoPackage data type of an FCB column
+fn sql_type(column_type: ColumnType) -> &'static str {
+    match column_type {
+        ColumnType::Bool => "BOOLEAN",
+        ColumnType::Byte => "TINYINT",
+        ColumnType::UByte | ColumnType::Short => "SMALLINT",
+        ColumnType::UShort | ColumnType::Int => "MEDIUMINT",
+        ColumnType::UInt | ColumnType::Long | ColumnType::ULong => "INTEGER",
+        ColumnType::Float => "FLOAT",
+        ColumnType::Double => "DOUBLE",
+        ColumnType::DateTime => "DATETIME",
+        _ => "TEXT",
+    }
+}
+
+/// Arrays and objects are stored as JSON text
+fn to_sql_value(value: &Value, column_type: ColumnType) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) if !matches!(column_type, ColumnType::Float | ColumnType::Double) => {
+                SqlValue::Integer(i)
+            }
+            _ => n.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn is_surface_geometry(geometry_type: &GeometryType) -> bool {
+    matches!(
+        geometry_type,
+        GeometryType::MultiSurface
+            | GeometryType::CompositeSurface
+            | GeometryType::Solid
+            | GeometryType::MultiSolid
+            | GeometryType::CompositeSolid
+    )
+}
+
+/// Pushes the rings of every surface of `boundaries`, in boundary order
+fn collect_surfaces<'a>(boundaries: &'a Boundaries, surfaces: &mut Vec<&'a [Boundaries]>) {
+    let Boundaries::Nested(children) = boundaries else {
+        return;
+    };
+    match children.first() {
+        Some(Boundaries::Indices(_)) => surfaces.push(children),
+        _ => {
+            for child in children {
+                collect_surfaces(child, surfaces);
+            }
+        }
+    }
+}
+
+/// Every surface of a surface geometry as a polygon of closed rings of real coordinates
+fn to_polygons(
+    geometry: &Geometry,
+    feature: &CityJSONFeature,
+    (scale, translate): &([f64; 3], [f64; 3]),
+) -> Result<Vec<Vec<Vec<[f64; 3]>>>, Error> {
+    let mut surfaces = Vec::new();
+    collect_surfaces(&geometry.boundaries, &mut surfaces);
+    surfaces
+        .into_iter()
+        .map(|rings| {
+            rings
+                .iter()
+                .filter_map(|ring| match ring {
+                    Boundaries::Indices(indices) if !indices.is_empty() => Some(indices),
+                    _ => None,
+                })
+                .map(|indices| {
+                    let mut points = indices
+                        .iter()
+                        .map(|i| {
+                            let v = feature.vertices.get(*i as usize).ok_or_else(|| {
+                                Error::IoError(io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    format!("vertex {i} of feature {} does not exist", feature.id),
+                                ))
+                            })?;
+                            let c = |axis: usize| {
+                                v.get(axis).copied().unwrap_or_default() as f64 * scale[axis]
+                                    + translate[axis]
+                            };
+                            Ok([c(0), c(1), c(2)])
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    // CityJSON rings are implicitly closed, WKB rings are not
+                    points.push(points[0]);
+                    Ok(points)
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        })
+        .collect()
+}
+
+/// GeoPackageBinary of a little endian WKB MultiPolygon Z with an XYZ envelope, returned with
+/// its 2D extent as `[min_x, min_y, max_x, max_y]`
+fn to_gpkg_geometry(polygons: &[Vec<Vec<[f64; 3]>>], srs_id: i32) -> (Vec<u8>, [f64; 4]) {
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for point in polygons.iter().flatten().flatten() {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(point[axis]);
+            max[axis] = max[axis].max(point[axis]);
+        }
+    }
+
+    let mut buf = Vec::new();
+    buf.extend_from_slice(b"GP");
+    // version 1
+    buf.push(0);
+    // little endian, envelope [min_x, max_x, min_y, max_y, min_z, max_z]
+    buf.push(0b0000_0101);
+    buf.extend_from_slice(&srs_id.to_le_bytes());
+    for axis in 0..3 {
+        buf.extend_from_slice(&min[axis].to_le_bytes());
+        buf.extend_from_slice(&max[axis].to_le_bytes());
+    }
+
+    // ISO WKB type codes of MultiPolygon Z and Polygon Z
+    buf.push(1);
+    buf.extend_from_slice(&1006u32.to_le_bytes());
+    buf.extend_from_slice(&(polygons.len() as u32).to_le_bytes());
+    for rings in polygons {
+        buf.push(1);
+        buf.extend_from_slice(&1003u32.to_le_bytes());
+        buf.extend_from_slice(&(rings.len() as u32).to_le_bytes());
+        for ring in rings {
+            buf.extend_from_slice(&(ring.len() as u32).to_le_bytes());
+            for point in ring {
+                for coordinate in point {
+                    buf.extend_from_slice(&coordinate.to_le_bytes());
+                }
+            }
+        }
+    }
+    (buf, [min[0], min[1], max[0], max[1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb_core::{
+        attribute::{AttributeSchema, AttributeSchemaMethods},
+        header_writer::HeaderWriterOptions,
+        read_cityjson_from_reader, CJType, CJTypeKind, FcbWriter,
+    };
+    use std::{fs::File, io::BufReader, path::PathBuf};
+
+    #[test]
+    fn export_layers_per_type() {
+        let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../fcb_core/tests/data/small.city.jsonl");
+        let CJType::Seq(seq) =
+            read_cityjson_from_reader(BufReader::new(File::open(input).unwrap()), CJTypeKind::Seq)
+                .unwrap()
+        else {
+            panic!("expected cityjsonseq");
+        };
+        let mut attr_schema = AttributeSchema::new();
+        for co in seq.features.iter().flat_map(|f| f.city_objects.values()) {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )
+        .unwrap();
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature).unwrap();
+        }
+        let mut fcb_buf = Vec::new();
+        fcb.write(&mut fcb_buf).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("small.gpkg");
+        let rows = export_gpkg(fcb_buf.as_slice(), &output).unwrap();
+        let city_objects = seq
+            .features
+            .iter()
+            .map(|f| f.city_objects.len() as u64)
+            .sum::<u64>();
+        assert_eq!(rows, city_objects);
+
+        let conn = Connection::open(&output).unwrap();
+        let application_id: i32 = conn
+            .query_row("PRAGMA application_id", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(application_id, GPKG_APPLICATION_ID);
+
+        let tables = conn
+            .prepare("SELECT table_name, srs_id FROM gpkg_contents ORDER BY table_name")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            tables,
+            [
+                ("Building".to_string(), 7415),
+                ("BuildingPart".to_string(), 7415)
+            ]
+        );
+
+        let count: u64 = conn
+            .query_row(
+                "SELECT (SELECT count(*) FROM Building) + (SELECT count(*) FROM BuildingPart)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, city_objects);
+
+        // attributes keep their type, geometries are MultiPolygon Z
+        let (height, geom): (f64, Vec<u8>) = conn
+            .query_row(
+                "SELECT b3_h_dak_50p, geom FROM Building WHERE b3_h_dak_50p IS NOT NULL",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(height > 0.0);
+        assert_eq!(&geom[..2], b"GP");
+        assert_eq!(i32::from_le_bytes(geom[4..8].try_into().unwrap()), 7415);
+        // the WKB follows the 8 byte header and the 48 byte envelope
+        assert_eq!(geom[56], 1);
+        assert_eq!(u32::from_le_bytes(geom[57..61].try_into().unwrap()), 1006);
+
+        let (min_x, max_x): (f64, f64) = conn
+            .query_row(
+                "SELECT min_x, max_x FROM gpkg_contents WHERE table_name = 'BuildingPart'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(min_x > 84000.0 && min_x < max_x && max_x < 86000.0);
+    }
+}
@@ -1,17 +1,29 @@
 use cjseq::{CityJSON, CityJSONFeature, Transform as CjTransform};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use fcb_core::error::Error;
 use fcb_core::{
+    attr_queries_from_expr,
     attribute::{AttributeSchema, AttributeSchemaMethods},
     deserializer,
     header_writer::HeaderWriterOptions,
-    read_cityjson_from_reader, CJType, CJTypeKind, CityJSONSeq, FcbReader, FcbWriter,
+    read_cityjson_from_reader,
+    reader_trait::NotSeekable,
+    reindex, serializer, CJType, CJTypeKind, CityJSONSeq, FcbReader, FcbWriter, FeatureIter,
+    Header, PackedRTree, QueryExpr, TileIndex, FEATURE_ID_COLUMN,
 };
 use std::{
     fs::File,
     io::{self, BufReader, BufWriter, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
+
+mod diff;
+mod export;
+mod gpkg;
+mod schema;
+mod stats;
+mod validate;
+
 #[derive(Parser)]
 #[command(
     name = "fcb",
@@ -24,6 +36,22 @@ struct Cli {
     command: Commands,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Cityjson,
+    Geojson,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// Attributes, one row per feature
+    Csv,
+    /// Wavefront OBJ geometry, with an MTL material library next to it
+    Obj,
+    /// GeoPackage with one layer per city object type, with geometry and typed attributes
+    Gpkg,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Convert CityJSON to FCB
@@ -33,8 +61,8 @@ enum Commands {
         input: String,
 
         /// Output file (use '-' for stdout)
-        #[arg(short = 'o', long)]
-        output: String,
+        #[arg(short = 'o', long, required_unless_present = "dry_run")]
+        output: Option<String>,
 
         /// Comma-separated list of attributes to create index for
         #[arg(short = 'a', long)]
@@ -52,13 +80,23 @@ enum Commands {
         #[arg(long)]
         attr_branching_factor: Option<u16>,
 
-        /// Bounding box filter in format "minx,miny,maxx,maxy"
+        /// Bounding box filter in format "minx,miny,maxx,maxy", or "minx,miny,minz,maxx,maxy,maxz"
+        /// to also filter on Z
         #[arg(short = 'b', long)]
         bbox: Option<String>,
 
         /// Automatically calculate and set geospatial extent in header
         #[arg(short = 'g', long)]
         ge: Option<bool>,
+
+        /// Serialize without writing the output and report the estimated file size
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Add the features to the existing output file, rebuilding its indices. The index
+        /// options are taken from the file.
+        #[arg(long, conflicts_with = "dry_run")]
+        append: bool,
     },
 
     /// Convert FCB to CityJSON
@@ -70,6 +108,10 @@ enum Commands {
         /// Output file (use '-' for stdout)
         #[arg(short, long)]
         output: String,
+
+        /// Output format, CityJSONSeq or a GeoJSON FeatureCollection
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Cityjson)]
+        format: OutputFormat,
     },
 
     /// Convert CityJSON to CBOR
@@ -97,6 +139,174 @@ enum Commands {
         /// Input FCB file
         #[arg(short, long)]
         input: PathBuf,
+
+        /// Print the info as a JSON object
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Query an FCB file and write matching features as CityJSONSeq to stdout
+    Select {
+        /// Input FCB file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Bounding box filter in format "minx,miny,maxx,maxy"
+        #[arg(short, long, conflicts_with = "attr", required_unless_present = "attr")]
+        bbox: Option<String>,
+
+        /// Attribute filter, e.g. "height > 30 AND city_type = 'residential'"
+        #[arg(short, long)]
+        attr: Option<String>,
+    },
+
+    /// Show how many features each condition of an attribute query matches in its index
+    Explain {
+        /// Input FCB file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Attribute filter, e.g. "height > 30 AND city_type = 'residential'"
+        #[arg(short, long)]
+        attr: String,
+    },
+
+    /// Compute per-attribute statistics of an FCB file as JSON
+    Stats {
+        /// Input file (use '-' for stdin)
+        #[arg(short, long)]
+        input: String,
+
+        /// Comma-separated list of columns to compute statistics for (default: all)
+        #[arg(short, long)]
+        columns: Option<String>,
+    },
+
+    /// Add attribute indices to an existing FCB file without re-encoding its features,
+    /// building the spatial index if missing
+    Index {
+        /// Input FCB file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Output FCB file, must differ from the input
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Comma-separated list of attributes to create index for
+        #[arg(short, long)]
+        attr: String,
+
+        /// Branching factor for attribute index
+        #[arg(long)]
+        branching_factor: Option<u16>,
+    },
+
+    /// Add attribute indices to an FCB file with a spatial index, copying its features as they
+    /// are. Same as `index`, which also accepts files without a spatial index
+    UpdateIndex {
+        /// Input FCB file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Comma-separated list of attributes to create index for
+        #[arg(short, long)]
+        attr_index: String,
+
+        /// Output FCB file, must differ from the input
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Branching factor for attribute index
+        #[arg(long)]
+        branching_factor: Option<u16>,
+    },
+
+    /// Merge multiple FCB files into one
+    Merge {
+        /// Input FCB files
+        #[arg(short, long, num_args = 1.., required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Output FCB file
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Re-sort features and rebuild the spatial and attribute indices
+        #[arg(long)]
+        rebuild_index: bool,
+    },
+
+    /// Print the attribute schema of an FCB file as JSON Schema
+    Schema {
+        /// Input FCB file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Infer the schema from up to N features if the header has no column metadata
+        #[arg(long)]
+        scan_features: Option<usize>,
+    },
+
+    /// Export the attributes of every feature as CSV, the geometries as OBJ, or both as a
+    /// GeoPackage
+    Export {
+        /// Input file (use '-' for stdin)
+        #[arg(short, long)]
+        input: String,
+
+        /// Output file (use '-' for stdout, CSV only)
+        #[arg(short, long)]
+        output: String,
+
+        /// Comma-separated list of columns to export (default: all, CSV only)
+        #[arg(short, long)]
+        columns: Option<String>,
+
+        /// Export format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+    },
+
+    /// Compare the features of two FCB files by id. Exits with 1 if they differ
+    Diff {
+        /// Left FCB file
+        #[arg(long)]
+        left: PathBuf,
+
+        /// Right FCB file
+        #[arg(long)]
+        right: PathBuf,
+
+        /// Skip the geometries, vertices and appearances
+        #[arg(long)]
+        ignore_geometry: bool,
+
+        /// Skip the attributes of the city objects
+        #[arg(long)]
+        ignore_attributes: bool,
+    },
+
+    /// Check the integrity of an FCB file. Exits with 1 on warnings and 2 on errors
+    Validate {
+        /// Input FCB file
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Also verify the FlatBuffer of every feature
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Write a JSON tile index of the FCB files in a directory, for reading them as one dataset
+    TileIndex {
+        /// Directory containing the FCB tiles
+        #[arg(long)]
+        input_dir: PathBuf,
+
+        /// Output JSON file
+        #[arg(short, long)]
+        output: PathBuf,
     },
 }
 
@@ -114,25 +324,33 @@ fn get_writer(output: &str) -> Result<Box<dyn Write>, Error> {
     }
 }
 
+fn invalid_input(msg: impl Into<String>) -> Error {
+    Error::IoError(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        msg.into(),
+    ))
+}
+
+/// Serializes `input` to `output`, or only reports the estimated size without an output.
+/// With `append`, the features are added to the existing `output` instead.
+#[allow(clippy::too_many_arguments)]
 fn serialize(
     input: &str,
-    output: &str,
+    output: Option<&str>,
     attr_index: Option<String>,
     index_all_attributes: Option<bool>,
     spatial_index: Option<bool>,
     attr_branching_factor: Option<u16>,
     bbox: Option<String>,
     ge: Option<bool>,
+    append: bool,
 ) -> Result<(), Error> {
     let reader = get_reader(input)?;
-    let writer = get_writer(output)?;
-
     let reader = BufReader::new(reader);
-    let writer = BufWriter::new(writer);
 
     // Parse the bbox if provided
     let bbox_parsed = if let Some(bbox_str) = bbox {
-        Some(parse_bbox(&bbox_str).map_err(|e| {
+        Some(parse_bbox_filter(&bbox_str).map_err(|e| {
             Error::IoError(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
                 format!("failed to parse bbox: {}", e),
@@ -169,6 +387,13 @@ fn serialize(
         eprintln!("warning: no features found within the specified bbox");
     }
 
+    if append {
+        let output = output
+            .filter(|output| *output != "-")
+            .ok_or_else(|| invalid_input("--append needs an output file"))?;
+        return append_features(Path::new(output), &cj.transform, filtered_features);
+    }
+
     let attr_schema = {
         let mut schema = AttributeSchema::new();
         // Limit to max 1000 features for schema building to have faster build time
@@ -251,6 +476,12 @@ fn serialize(
         index_node_size: attr_branching_factor.unwrap_or(16),
         attribute_indices: attr_index_vec,
         geographical_extent: geo_extent,
+        composite_indices: None,
+        coordinate_precision: None,
+        checksum: false,
+        shared_vertices: false,
+        feature_id_index: false,
+        compute_hash: false,
     };
 
     println!("header_options in cli: {:?}", header_options);
@@ -260,7 +491,16 @@ fn serialize(
     for feature in filtered_features.iter() {
         fcb.add_feature(feature)?;
     }
-    fcb.write(writer)?;
+
+    let Some(output) = output else {
+        eprintln!("Estimated size: {} bytes", fcb.estimate_size());
+        return Ok(());
+    };
+    if output == "-" {
+        fcb.write(BufWriter::new(io::stdout()))?;
+    } else {
+        fcb.write_atomic(Path::new(output))?;
+    }
 
     if output != "-" {
         eprintln!("Successfully encoded to FCB");
@@ -297,6 +537,58 @@ fn parse_bbox(bbox_str: &str) -> Result<[f64; 4], String> {
     Ok(bbox)
 }
 
+/// Parse a 3D bounding box string in format "minx,miny,minz,maxx,maxy,maxz"
+fn parse_bbox_3d(bbox_str: &str) -> Result<[f64; 6], String> {
+    let parts: Vec<&str> = bbox_str.split(',').collect();
+    if parts.len() != 6 {
+        return Err(format!(
+            "Invalid bounding box format. Expected 'minx,miny,minz,maxx,maxy,maxz', got '{}'",
+            bbox_str
+        ));
+    }
+
+    let mut bbox = [0.0; 6];
+    for (i, part) in parts.iter().enumerate() {
+        bbox[i] = part
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("Failed to parse bbox component: {}", e))?;
+    }
+
+    // Validate that min <= max
+    if bbox[0] > bbox[3] || bbox[1] > bbox[4] || bbox[2] > bbox[5] {
+        return Err(
+            "Invalid bounding box: min values must be less than or equal to max values".to_string(),
+        );
+    }
+
+    Ok(bbox)
+}
+
+/// Bounding box filter of the `ser` subcommand, Z is only checked for a 3D bbox
+#[derive(Debug, PartialEq)]
+struct BboxFilter {
+    xy: [f64; 4],
+    /// min z and max z
+    z: Option<[f64; 2]>,
+}
+
+/// Parse a 3D bounding box string, falling back to a 2D one
+fn parse_bbox_filter(bbox_str: &str) -> Result<BboxFilter, String> {
+    match parse_bbox_3d(bbox_str) {
+        Ok([min_x, min_y, min_z, max_x, max_y, max_z]) => Ok(BboxFilter {
+            xy: [min_x, min_y, max_x, max_y],
+            z: Some([min_z, max_z]),
+        }),
+        // a 6-coordinate bbox is not reported as a malformed 2D bbox
+        Err(e) if bbox_str.split(',').count() == 6 => Err(e),
+        Err(_) => Ok(BboxFilter {
+            xy: parse_bbox(bbox_str)?,
+            z: None,
+        }),
+    }
+}
+
 /// Get all vertices from a feature
 fn get_vertices_from_feature(feature: &CityJSONFeature, transform: &CjTransform) -> Vec<[f64; 3]> {
     let mut result = Vec::new();
@@ -318,7 +610,7 @@ fn get_vertices_from_feature(feature: &CityJSONFeature, transform: &CjTransform)
 /// Check if a CityJSONFeature intersects with a bounding box
 fn feature_intersects_bbox(
     feature: &CityJSONFeature,
-    bbox: &[f64; 4],
+    bbox: &BboxFilter,
     transform: &CjTransform,
 ) -> bool {
     // Get transformed vertices from the feature
@@ -331,10 +623,13 @@ fn feature_intersects_bbox(
 }
 
 /// Check if a CityObject intersects with a bounding box
-fn city_object_intersects_bbox(bbox: &[f64; 4], feature_vertices: &[[f64; 3]]) -> bool {
+fn city_object_intersects_bbox(bbox: &BboxFilter, feature_vertices: &[[f64; 3]]) -> bool {
     // Check if any of the vertices are within the bbox
     for vertex in feature_vertices {
-        if point_in_bbox_2d(vertex, bbox) {
+        let in_z_range = bbox
+            .z
+            .is_none_or(|[min_z, max_z]| vertex[2] >= min_z && vertex[2] <= max_z);
+        if point_in_bbox_2d(vertex, &bbox.xy) && in_z_range {
             return true;
         }
     }
@@ -377,11 +672,15 @@ fn calculate_geospatial_extent(features: &[CityJSONFeature], transform: &CjTrans
     [min_x, min_y, min_z, max_x, max_y, max_z]
 }
 
-fn deserialize(input: &str, output: &str) -> Result<(), Error> {
+fn deserialize(input: &str, output: &str, format: OutputFormat) -> Result<(), Error> {
     let reader = BufReader::new(get_reader(input)?);
     let mut writer = BufWriter::new(get_writer(output)?);
     let mut fcb_reader = FcbReader::open(reader)?.select_all_seq()?;
 
+    if format == OutputFormat::Geojson {
+        return deserialize_geojson(&mut fcb_reader, &mut writer, output);
+    }
+
     let header = fcb_reader.header();
     let cj = deserializer::to_cj_metadata(&header)?;
 
@@ -407,6 +706,46 @@ fn deserialize(input: &str, output: &str) -> Result<(), Error> {
     Ok(())
 }
 
+/// Writes the features as a GeoJSON FeatureCollection, one feature per line
+fn deserialize_geojson<R: Read>(
+    fcb_reader: &mut FeatureIter<R, NotSeekable>,
+    writer: &mut impl Write,
+    output: &str,
+) -> Result<(), Error> {
+    let header = fcb_reader.header();
+    if let Some(rs) = header.reference_system() {
+        let crs = format!("{}:{}", rs.authority().unwrap_or("EPSG"), rs.code());
+        if crs != "EPSG:4326" {
+            eprintln!(
+                "warning: no re-projection is available, coordinates are written in {} instead of WGS84",
+                crs
+            );
+        }
+    }
+
+    writeln!(writer, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+    let feat_count = header.features_count();
+    let mut feat_num = 0;
+    while let Some(feat_buf) = fcb_reader.next()? {
+        let feature = serializer::to_geojson_feature(feat_buf.cur_feature(), feat_buf.header())?;
+        if feat_num > 0 {
+            writeln!(writer, ",")?;
+        }
+        write!(writer, "{}", serde_json::to_string(&feature)?)?;
+
+        feat_num += 1;
+        if feat_num >= feat_count {
+            break;
+        }
+    }
+    writeln!(writer, "\n]}}")?;
+
+    if output != "-" {
+        eprintln!("Successfully decoded to GeoJSON");
+    }
+    Ok(())
+}
+
 fn encode_cbor(input: &str, output: &str) -> Result<(), Error> {
     let reader = BufReader::new(get_reader(input)?);
     let writer = BufWriter::new(get_writer(output)?);
@@ -456,12 +795,18 @@ fn encode_bson(input: &str, output: &str) -> Result<(), Error> {
     Ok(())
 }
 
-fn show_info(input: PathBuf) -> Result<(), Error> {
+fn show_info(input: PathBuf, json: bool) -> Result<(), Error> {
     let reader = BufReader::new(File::open(input)?);
-    let metadata = reader.get_ref().metadata()?.len() / 1024 / 1024; // show in megabytes
+    let file_size = reader.get_ref().metadata()?.len();
+    let metadata = file_size / 1024 / 1024; // show in megabytes
     let fcb_reader = FcbReader::open(reader)?.select_all()?;
+    if json {
+        let info = info_json(&fcb_reader.header(), file_size);
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
     let raw_attr_index = fcb_reader.header().attribute_index();
-    let attr_index = raw_attr_index.map(|ai_vec| {
+    let indexed_columns = raw_attr_index.map(|ai_vec| {
         ai_vec
             .iter()
             .map(|ai| {
@@ -476,6 +821,15 @@ fn show_info(input: PathBuf) -> Result<(), Error> {
             })
             .collect::<Vec<_>>()
     });
+    let has_feature_id_index = indexed_columns
+        .as_ref()
+        .is_some_and(|names| names.contains(&FEATURE_ID_COLUMN));
+    let attr_index = indexed_columns.map(|names| {
+        names
+            .into_iter()
+            .filter(|name| *name != FEATURE_ID_COLUMN)
+            .collect::<Vec<_>>()
+    });
     let header = fcb_reader.header();
     println!("FCB File Info:");
     println!("    File size: {} MB", metadata);
@@ -483,6 +837,7 @@ fn show_info(input: PathBuf) -> Result<(), Error> {
     println!("  Features count: {}", header.features_count());
     println!("  bbox: {:?}", header.geographical_extent());
     println!("  attr_index: {:?}", attr_index.unwrap_or_default());
+    println!("  feature_id_index: {}", has_feature_id_index);
 
     if let Some(title) = header.title() {
         println!("  Title: {}", title);
@@ -504,6 +859,530 @@ fn show_info(input: PathBuf) -> Result<(), Error> {
         );
     }
 
+    if let Some(extensions) = header.extensions().filter(|e| !e.is_empty()) {
+        println!("  Extensions:");
+        for extension in extensions.iter() {
+            println!(
+                "    {} {} ({})",
+                extension.name().unwrap_or_default(),
+                extension.version().unwrap_or_default(),
+                extension.url().unwrap_or_default()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Machine-readable summary of the header, printed by `info --json`
+fn info_json(header: &Header, file_size: u64) -> serde_json::Value {
+    let columns = header.columns();
+    let mut has_feature_id_index = false;
+    let attribute_indices = header
+        .attribute_index()
+        .iter()
+        .flat_map(|ai_vec| ai_vec.iter())
+        .filter_map(|ai| {
+            let column = columns
+                .iter()
+                .flat_map(|c| c.iter())
+                .find(|c| c.index() == ai.index());
+            if column.is_some_and(|c| c.name() == FEATURE_ID_COLUMN) {
+                has_feature_id_index = true;
+                return None;
+            }
+            Some(serde_json::json!({
+                "name": column.map(|c| c.name()),
+                "column_type": column.map(|c| format!("{:?}", c.type_())),
+                "num_unique_items": ai.num_unique_items(),
+                "branching_factor": ai.branching_factor(),
+            }))
+        })
+        .collect::<Vec<_>>();
+    let geographical_extent = header.geographical_extent().map(|extent| {
+        serde_json::json!({
+            "min": [extent.min().x(), extent.min().y(), extent.min().z()],
+            "max": [extent.max().x(), extent.max().y(), extent.max().z()],
+        })
+    });
+    let extensions = header
+        .extensions()
+        .iter()
+        .flat_map(|ext_vec| ext_vec.iter())
+        .map(|ext| {
+            serde_json::json!({
+                "name": ext.name(),
+                "url": ext.url(),
+                "version": ext.version(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let crs = header.reference_system().map(|rs| {
+        format!(
+            "https://www.opengis.net/def/crs/{}/{}/{}",
+            rs.authority().unwrap_or_default(),
+            rs.version(),
+            rs.code()
+        )
+    });
+
+    serde_json::json!({
+        "file_size_bytes": file_size,
+        "version": header.version(),
+        "features_count": header.features_count(),
+        "geographical_extent": geographical_extent,
+        "title": header.title(),
+        "has_spatial_index": header.index_node_size() > 0,
+        "index_node_size": header.index_node_size(),
+        "attribute_indices": attribute_indices,
+        "has_feature_id_index": has_feature_id_index,
+        "extensions": extensions,
+        "crs": crs,
+    })
+}
+
+/// Parse an attribute filter, a SQL WHERE clause such as
+/// "height > 30 AND city_type = 'residential'"
+fn parse_attr_filter(expr: &str) -> Result<QueryExpr, Error> {
+    QueryExpr::from_sql_where_clause(expr).map_err(|e| invalid_input(e.to_string()))
+}
+
+fn select(input: PathBuf, bbox: Option<String>, attr: Option<String>) -> Result<(), Error> {
+    let reader = BufReader::new(File::open(input)?);
+    let fcb_reader = FcbReader::open(reader)?;
+    let mut writer = BufWriter::new(io::stdout().lock());
+
+    let cj = deserializer::to_cj_metadata(&fcb_reader.header())?;
+    let mut iter = match (bbox, attr) {
+        (Some(bbox), _) => {
+            let [min_x, min_y, max_x, max_y] = parse_bbox(&bbox).map_err(invalid_input)?;
+            fcb_reader.select_bbox(min_x, min_y, max_x, max_y)?
+        }
+        (None, Some(expr)) => fcb_reader.select_where(None, &parse_attr_filter(&expr)?)?,
+        (None, None) => return Err(invalid_input("either --bbox or --attr is required")),
+    };
+
+    writeln!(writer, "{}", serde_json::to_string(&cj)?)?;
+    while let Some(feat_buf) = iter.next()? {
+        let feature = feat_buf.cur_cj_feature()?;
+        writeln!(writer, "{}", serde_json::to_string(&feature)?)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn explain(input: PathBuf, attr: String) -> Result<(), Error> {
+    let reader = BufReader::new(File::open(input)?);
+    let fcb_reader = FcbReader::open(reader)?;
+    let expr = parse_attr_filter(&attr)?;
+    let query = {
+        let header = fcb_reader.header();
+        let columns = header
+            .columns()
+            .ok_or(Error::NoColumnsInHeader)?
+            .iter()
+            .collect::<Vec<_>>();
+        let mut groups = attr_queries_from_expr(&expr, &columns)?;
+        if groups.len() != 1 {
+            return Err(invalid_input(
+                "explain supports only conditions joined by AND",
+            ));
+        }
+        groups.remove(0)
+    };
+    let plan = fcb_reader.explain_attr_query(query)?;
+
+    let mut writer = BufWriter::new(io::stdout().lock());
+    writeln!(
+        writer,
+        "{:<24} {:<8} {:<28} {:>12} {:>12}",
+        "field", "operator", "index", "candidates", "remaining"
+    )?;
+    for condition in &plan.conditions {
+        writeln!(
+            writer,
+            "{:<24} {:<8} {:<28} {:>12} {:>12}",
+            condition.field,
+            format!("{:?}", condition.operator),
+            condition.index_type,
+            condition.candidates_before_intersection,
+            condition.candidates_after_intersection
+        )?;
+    }
+    writeln!(writer, "{} matching features", plan.num_results())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn show_stats(input: &str, columns: Option<String>) -> Result<(), Error> {
+    let reader = BufReader::new(get_reader(input)?);
+    let columns = columns.map(|s| {
+        s.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+    });
+
+    let stats = stats::compute_stats(reader, columns.as_deref())?;
+    let mut writer = BufWriter::new(io::stdout().lock());
+    serde_json::to_writer_pretty(&mut writer, &stats)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn export(
+    input: &str,
+    output: &str,
+    columns: Option<String>,
+    format: ExportFormat,
+) -> Result<(), Error> {
+    match format {
+        ExportFormat::Csv => export_csv(input, output, columns),
+        ExportFormat::Obj => export_obj(input, output),
+        ExportFormat::Gpkg => export_gpkg(input, output),
+    }
+}
+
+fn export_gpkg(input: &str, output: &str) -> Result<(), Error> {
+    if output == "-" {
+        return Err(invalid_input("GeoPackage export needs an output file"));
+    }
+    let reader = BufReader::new(get_reader(input)?);
+    let rows = gpkg::export_gpkg(reader, Path::new(output))?;
+    eprintln!("Exported {} city objects to GeoPackage", rows);
+    Ok(())
+}
+
+fn export_obj(input: &str, output: &str) -> Result<(), Error> {
+    if output == "-" {
+        return Err(Error::IoError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "OBJ export needs an output file, the MTL library is written next to it",
+        )));
+    }
+    let reader = BufReader::new(get_reader(input)?);
+    let features = export::export_obj(reader, Path::new(output))?;
+    eprintln!("Exported {} features to OBJ", features);
+    Ok(())
+}
+
+fn export_csv(input: &str, output: &str, columns: Option<String>) -> Result<(), Error> {
+    let reader = BufReader::new(get_reader(input)?);
+    let writer = BufWriter::new(get_writer(output)?);
+    let columns = columns.map(|s| {
+        s.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+    });
+
+    let rows = export::export_csv(reader, writer, columns.as_deref())?;
+    if output != "-" {
+        eprintln!("Exported {} features to CSV", rows);
+    }
+    Ok(())
+}
+
+/// Rebuild the semantic attribute schema from the column definitions stored in a header
+fn schema_from_columns<'a>(
+    columns: Option<
+        flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<fcb_core::fb::Column<'a>>>,
+    >,
+) -> Option<AttributeSchema> {
+    let schema = columns
+        .iter()
+        .flat_map(|c| c.iter())
+        .map(|c| (c.name().to_string(), (c.index(), c.type_())))
+        .collect::<AttributeSchema>();
+    (!schema.is_empty()).then_some(schema)
+}
+
+/// Add attribute indices to a file without re-encoding its features
+///
+/// Files with an R-tree keep it, their existing indices and their feature section byte for
+/// byte. Files without one get an R-tree first, see [`rebuild_index`]. Attributes that are
+/// indexed already are skipped.
+fn add_index(
+    input: &Path,
+    output: &Path,
+    attr: &str,
+    branching_factor: Option<u16>,
+) -> Result<(), Error> {
+    if input == output {
+        return Err(invalid_input("output must differ from input"));
+    }
+    let branching_factor =
+        branching_factor.unwrap_or(fcb_core::static_btree::DEFAULT_BRANCHING_FACTOR);
+    let names = attr
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+
+    let reader = FcbReader::open(BufReader::new(File::open(input)?))?;
+    let header = reader.header();
+    let attr_schema = AttributeSchema::from_fcb_header(&header);
+    for name in names.iter() {
+        if !attr_schema.contains_key(*name) {
+            return Err(invalid_input(format!("unknown attribute '{}'", name)));
+        }
+    }
+    let indexed = header
+        .attribute_index()
+        .into_iter()
+        .flat_map(|ai| ai.iter())
+        .map(|ai| ai.index())
+        .collect::<Vec<_>>();
+    let columns = names
+        .into_iter()
+        .filter(|name| {
+            attr_schema
+                .get(*name)
+                .is_some_and(|(index, _)| !indexed.contains(index))
+        })
+        .map(|name| (name, branching_factor))
+        .collect::<Vec<_>>();
+
+    if header.index_node_size() == 0 {
+        return rebuild_index(input, output, &columns);
+    }
+    if columns.is_empty() {
+        return Err(invalid_input("the attributes are indexed already"));
+    }
+
+    let original = BufReader::new(File::open(input)?);
+    let mut out = BufWriter::new(File::create(output)?);
+    reindex::append_attribute_index(original, &mut out, &columns)?;
+    out.flush()?;
+
+    eprintln!("Successfully added {} attribute indices", columns.len());
+    Ok(())
+}
+
+/// [`add_index`] for files that have an R-tree, their features are never reordered
+fn update_index(
+    input: &Path,
+    output: &Path,
+    attr_index: &str,
+    branching_factor: Option<u16>,
+) -> Result<(), Error> {
+    let reader = FcbReader::open(BufReader::new(File::open(input)?))?;
+    if reader.header().index_node_size() == 0 {
+        return Err(invalid_input(
+            "the file has no spatial index, use `fcb index` to build one",
+        ));
+    }
+    add_index(input, output, attr_index, branching_factor)
+}
+
+/// Build the R-tree of a file written without one, then add the given attribute indices
+fn rebuild_index(input: &Path, output: &Path, columns: &[(&str, u16)]) -> Result<(), Error> {
+    let original = BufReader::new(File::open(input)?);
+    if columns.is_empty() {
+        FcbWriter::write_with_rebuilt_index(original, BufWriter::new(File::create(output)?))?;
+    } else {
+        let mut indexed = io::Cursor::new(Vec::new());
+        FcbWriter::write_with_rebuilt_index(original, &mut indexed)?;
+        indexed.set_position(0);
+        reindex::append_attribute_index(indexed, BufWriter::new(File::create(output)?), columns)?;
+    }
+
+    eprintln!("Successfully wrote indexed FCB");
+    Ok(())
+}
+
+/// Re-quantize the vertices of a feature from one transform to another
+fn requantize(feature: &mut CityJSONFeature, from: &CjTransform, to: &CjTransform) {
+    for vertex in feature.vertices.iter_mut() {
+        for (i, v) in vertex.iter_mut().enumerate().take(3) {
+            let real = *v as f64 * from.scale[i] + from.translate[i];
+            *v = ((real - to.translate[i]) / to.scale[i]).round() as i64;
+        }
+    }
+}
+
+/// Adds features to an existing FCB file, re-quantized to its transform, and rebuilds its
+/// indices
+fn append_features(
+    output: &Path,
+    transform: &CjTransform,
+    mut features: Vec<CityJSONFeature>,
+) -> Result<(), Error> {
+    let reader = FcbReader::open(BufReader::new(File::open(output)?))?;
+    let target = deserializer::to_cj_metadata(&reader.header())?.transform;
+    if target.scale != transform.scale || target.translate != transform.translate {
+        for feature in features.iter_mut() {
+            requantize(feature, transform, &target);
+        }
+    }
+
+    let mut fcb = FcbWriter::open_for_append(output)?;
+    for feature in features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write_atomic(output)?;
+
+    eprintln!("Successfully appended {} features", features.len());
+    Ok(())
+}
+
+fn merge(inputs: Vec<PathBuf>, output: PathBuf, rebuild_index: bool) -> Result<(), Error> {
+    let mut cj: Option<CityJSON> = None;
+    let mut attr_schema = AttributeSchema::new();
+    let mut semantic_attr_schema = AttributeSchema::new();
+    let mut attribute_indices: Vec<(String, Option<u16>)> = Vec::new();
+    let mut feature_id_index = false;
+    let mut conflicts = Vec::new();
+    let mut features = Vec::new();
+
+    for input in inputs.iter() {
+        let reader = BufReader::new(File::open(input)?);
+        let mut iter = FcbReader::open(reader)?.select_all_seq()?;
+        let header = iter.header();
+
+        let input_cj = deserializer::to_cj_metadata(&header)?;
+        match attr_schema.merge(&AttributeSchema::from_fcb_header(&header)) {
+            Ok(merged) => attr_schema = merged,
+            Err(conflict) => conflicts.push(format!("{}: {}", input.display(), conflict)),
+        }
+        if let Some(schema) = schema_from_columns(header.semantic_columns()) {
+            match semantic_attr_schema.merge(&schema) {
+                Ok(merged) => semantic_attr_schema = merged,
+                Err(conflict) => conflicts.push(format!("{}: {}", input.display(), conflict)),
+            }
+        }
+        for ai in header.attribute_index().iter().flat_map(|ai| ai.iter()) {
+            let name = header
+                .columns()
+                .iter()
+                .flat_map(|c| c.iter())
+                .find(|c| c.index() == ai.index())
+                .map(|c| c.name().to_string());
+            if let Some(name) = name {
+                if name == FEATURE_ID_COLUMN {
+                    feature_id_index = true;
+                } else if !attribute_indices.iter().any(|(n, _)| *n == name) {
+                    attribute_indices.push((name, Some(ai.branching_factor())));
+                }
+            }
+        }
+
+        let target = cj.get_or_insert(input_cj.clone());
+        let same_transform = target.transform.scale == input_cj.transform.scale
+            && target.transform.translate == input_cj.transform.translate;
+        while let Some(feat_buf) = iter.next()? {
+            let mut feature = feat_buf.cur_cj_feature()?;
+            if !same_transform {
+                requantize(&mut feature, &input_cj.transform, &target.transform);
+            }
+            features.push(feature);
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(invalid_input(format!(
+            "incompatible attribute schemas:\n  {}",
+            conflicts.join("\n  ")
+        )));
+    }
+    let Some(cj) = cj else {
+        return Err(invalid_input("no input files given"));
+    };
+
+    let header_options = HeaderWriterOptions {
+        write_index: rebuild_index,
+        feature_count: features.len() as u64,
+        index_node_size: PackedRTree::DEFAULT_NODE_SIZE,
+        attribute_indices: (rebuild_index && !attribute_indices.is_empty())
+            .then_some(attribute_indices),
+        geographical_extent: Some(calculate_geospatial_extent(&features, &cj.transform)),
+        composite_indices: None,
+        coordinate_precision: None,
+        checksum: false,
+        shared_vertices: false,
+        feature_id_index: rebuild_index && feature_id_index,
+        compute_hash: false,
+    };
+    let mut fcb = FcbWriter::new(
+        cj,
+        Some(header_options),
+        (!attr_schema.is_empty()).then_some(attr_schema),
+        (!semantic_attr_schema.is_empty()).then_some(semantic_attr_schema),
+    )?;
+    for feature in features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write_atomic(&output)?;
+
+    eprintln!("Successfully merged {} features", features.len());
+    Ok(())
+}
+
+fn show_schema(input: PathBuf, scan_features: Option<usize>) -> Result<(), Error> {
+    let reader = BufReader::new(File::open(input)?);
+    let mut iter = FcbReader::open(reader)?.select_all_seq()?;
+
+    let header_doc = schema::from_header(&iter.header());
+    let doc = match (header_doc, scan_features) {
+        (Some(doc), _) => doc,
+        (None, Some(limit)) => {
+            let mut attr_schema = AttributeSchema::new();
+            let mut scanned = 0;
+            while scanned < limit {
+                let Some(feat_buf) = iter.next()? else {
+                    break;
+                };
+                for (_, co) in feat_buf.cur_cj_feature()?.city_objects.iter() {
+                    if let Some(attributes) = &co.attributes {
+                        attr_schema.add_attributes(attributes);
+                    }
+                }
+                scanned += 1;
+            }
+            schema::from_attr_schema(&attr_schema)
+        }
+        (None, None) => {
+            return Err(invalid_input(
+                "file has no column metadata, use --scan-features to infer the schema",
+            ))
+        }
+    };
+
+    let mut writer = BufWriter::new(io::stdout().lock());
+    serde_json::to_writer_pretty(&mut writer, &doc)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn validate_file(input: PathBuf, strict: bool) -> Result<(), Error> {
+    let file = File::open(&input)?;
+    let file_len = file.metadata()?.len();
+    let report = validate::validate(file, file_len, strict)?;
+    println!("{}:", input.display());
+    println!("{}", report);
+    std::process::exit(report.exit_code());
+}
+
+fn diff_files(left: PathBuf, right: PathBuf, options: diff::DiffOptions) -> Result<(), Error> {
+    let report = diff::diff(
+        BufReader::new(File::open(&left)?),
+        BufReader::new(File::open(&right)?),
+        options,
+    )?;
+    println!("{}", report);
+    std::process::exit(report.exit_code());
+}
+
+fn write_tile_index(input_dir: PathBuf, output: PathBuf) -> Result<(), Error> {
+    let index = TileIndex::from_dir(&input_dir)?;
+    index.write(&output)?;
+    eprintln!(
+        "indexed {} tiles of {} into {}",
+        index.tiles.len(),
+        input_dir.display(),
+        output.display()
+    );
     Ok(())
 }
 
@@ -520,30 +1399,283 @@ fn main() -> Result<(), Error> {
             attr_branching_factor,
             bbox,
             ge,
+            dry_run,
+            append,
         } => serialize(
             &input,
-            &output,
+            output.as_deref().filter(|_| !dry_run),
             attr_index,
             index_all_attributes,
             spatial_index,
             attr_branching_factor,
             bbox,
             ge,
+            append,
         ),
-        Commands::Deser { input, output } => deserialize(&input, &output),
+        Commands::Deser {
+            input,
+            output,
+            format,
+        } => deserialize(&input, &output, format),
         Commands::Cbor { input, output } => encode_cbor(&input, &output),
         Commands::Bson { input, output } => encode_bson(&input, &output),
-        Commands::Info { input } => show_info(input),
+        Commands::Info { input, json } => show_info(input, json),
+        Commands::Select { input, bbox, attr } => select(input, bbox, attr),
+        Commands::Explain { input, attr } => explain(input, attr),
+        Commands::Stats { input, columns } => show_stats(&input, columns),
+        Commands::Index {
+            input,
+            output,
+            attr,
+            branching_factor,
+        } => add_index(&input, &output, &attr, branching_factor),
+        Commands::UpdateIndex {
+            input,
+            attr_index,
+            output,
+            branching_factor,
+        } => update_index(&input, &output, &attr_index, branching_factor),
+        Commands::Merge {
+            inputs,
+            output,
+            rebuild_index,
+        } => merge(inputs, output, rebuild_index),
+        Commands::Schema {
+            input,
+            scan_features,
+        } => show_schema(input, scan_features),
+        Commands::Export {
+            input,
+            output,
+            columns,
+            format,
+        } => export(&input, &output, columns, format),
+        Commands::Validate { input, strict } => validate_file(input, strict),
+        Commands::Diff {
+            left,
+            right,
+            ignore_geometry,
+            ignore_attributes,
+        } => diff_files(
+            left,
+            right,
+            diff::DiffOptions {
+                ignore_geometry,
+                ignore_attributes,
+            },
+        ),
+        Commands::TileIndex { input_dir, output } => write_tile_index(input_dir, output),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fcb_core::{Literal, Operator};
 
     #[test]
     fn verify_cli() {
         use clap::CommandFactory;
         Cli::command().debug_assert();
     }
+
+    /// Writes the features of `small.city.jsonl` with all their attributes as columns
+    fn write_small_fcb(
+        path: &Path,
+        options: HeaderWriterOptions,
+    ) -> Result<Vec<CityJSONFeature>, Error> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input = File::open(manifest_dir.join("../fcb_core/tests/data/small.city.jsonl"))?;
+        let CJType::Seq(seq) = read_cityjson_from_reader(BufReader::new(input), CJTypeKind::Seq)?
+        else {
+            panic!("expected cityjsonseq");
+        };
+        let mut attr_schema = AttributeSchema::new();
+        for co in seq.features.iter().flat_map(|f| f.city_objects.values()) {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..options
+            }),
+            Some(attr_schema),
+            None,
+        )?;
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        fcb.write_atomic(path)?;
+        Ok(seq.features)
+    }
+
+    /// Sorted ids of the features of `path` selected by the attribute filter `expr`
+    fn select_ids(path: &Path, expr: &str) -> Result<Vec<String>, Error> {
+        let reader = FcbReader::open(BufReader::new(File::open(path)?))?;
+        let mut iter = reader.select_where(None, &parse_attr_filter(expr)?)?;
+        let mut ids = Vec::new();
+        while let Some(feature) = iter.next()? {
+            ids.push(feature.cur_cj_feature()?.id);
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Sorted ids of the features with a city object whose attributes are kept by `keep`
+    fn expected_ids(
+        features: &[CityJSONFeature],
+        keep: &dyn Fn(&serde_json::Value) -> bool,
+    ) -> Vec<String> {
+        let mut ids = features
+            .iter()
+            .filter(|f| {
+                f.city_objects
+                    .values()
+                    .any(|co| co.attributes.as_ref().is_some_and(keep))
+            })
+            .map(|f| f.id.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn index_adds_attribute_index() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let unindexed = dir.path().join("unindexed.fcb");
+        let indexed = dir.path().join("indexed.fcb");
+        let features = write_small_fcb(
+            &unindexed,
+            HeaderWriterOptions {
+                checksum: true,
+                compute_hash: true,
+                ..Default::default()
+            },
+        )?;
+
+        assert!(add_index(&unindexed, &indexed, "b3_h_dak_50p, unknown", None).is_err());
+        assert!(add_index(&unindexed, &unindexed, "b3_h_dak_50p", None).is_err());
+        let partly_indexed = dir.path().join("partly_indexed.fcb");
+        add_index(&unindexed, &partly_indexed, "b3_h_dak_50p", None)?;
+        // the existing index is kept and skipped
+        add_index(&partly_indexed, &indexed, "b3_h_dak_50p,b3_dak_type", None)?;
+
+        let mut reader = FcbReader::open(BufReader::new(File::open(&indexed)?))?;
+        let header = reader.header();
+        assert_eq!(header.attribute_index().map(|ai| ai.len()), Some(2));
+        assert!(header.checksum());
+        assert!(reader.verify_hash()?);
+
+        let tall = select_ids(&indexed, "b3_h_dak_50p > 5")?;
+        assert!(!tall.is_empty());
+        assert_eq!(
+            tall,
+            expected_ids(&features, &|a| a["b3_h_dak_50p"]
+                .as_f64()
+                .is_some_and(|h| h > 5.0))
+        );
+
+        let horizontal = select_ids(&indexed, "b3_dak_type = 'horizontal'")?;
+        assert!(!horizontal.is_empty());
+        assert_eq!(
+            horizontal,
+            expected_ids(&features, &|a| a["b3_dak_type"] == "horizontal")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn update_index_adds_attribute_index() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let unindexed = dir.path().join("unindexed.fcb");
+        let updated = dir.path().join("updated.fcb");
+        let features = write_small_fcb(&unindexed, HeaderWriterOptions::default())?;
+
+        // files without an R-tree are left to `index`
+        let no_rtree = dir.path().join("no_rtree.fcb");
+        write_small_fcb(
+            &no_rtree,
+            HeaderWriterOptions {
+                write_index: false,
+                ..Default::default()
+            },
+        )?;
+        assert!(update_index(&no_rtree, &updated, "b3_h_dak_50p", None).is_err());
+        assert!(update_index(&unindexed, &updated, "unknown", None).is_err());
+
+        update_index(&unindexed, &updated, "b3_h_dak_50p", None)?;
+        let tall = select_ids(&updated, "b3_h_dak_50p > 5")?;
+        assert!(!tall.is_empty());
+        assert_eq!(
+            tall,
+            expected_ids(&features, &|a| a["b3_h_dak_50p"]
+                .as_f64()
+                .is_some_and(|h| h > 5.0))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_attr_filters() {
+        let expr = parse_attr_filter("height >= 30 AND name = 'Bert and Ernie'").unwrap();
+        assert_eq!(
+            expr.and_groups(),
+            vec![vec![
+                ("height".to_string(), Operator::Ge, Some(Literal::Int(30))),
+                (
+                    "name".to_string(),
+                    Operator::Eq,
+                    Some(Literal::String("Bert and Ernie".to_string()))
+                ),
+            ]]
+        );
+
+        let expr = parse_attr_filter("name = 'de ''t Hoek'").unwrap();
+        assert_eq!(
+            expr.and_groups(),
+            vec![vec![(
+                "name".to_string(),
+                Operator::Eq,
+                Some(Literal::String("de 't Hoek".to_string()))
+            )]]
+        );
+
+        assert!(parse_attr_filter("height 30").is_err());
+        assert!(parse_attr_filter("name = 'unterminated").is_err());
+    }
+
+    #[test]
+    fn parse_bbox_filters() {
+        assert_eq!(
+            parse_bbox_filter("0,1,2,3").unwrap(),
+            BboxFilter {
+                xy: [0.0, 1.0, 2.0, 3.0],
+                z: None
+            }
+        );
+        assert_eq!(
+            parse_bbox_filter("0, 1, -5, 2, 3, 10").unwrap(),
+            BboxFilter {
+                xy: [0.0, 1.0, 2.0, 3.0],
+                z: Some([-5.0, 10.0])
+            }
+        );
+        assert!(parse_bbox_3d("0,1,10,2,3,5").is_err());
+        assert!(parse_bbox_filter("0,1,10,2,3,5")
+            .unwrap_err()
+            .contains("min values"));
+        assert!(parse_bbox_filter("0,1,2").is_err());
+
+        let vertices = [[1.0, 2.0, 20.0]];
+        let filter = parse_bbox_filter("0,1,2,3").unwrap();
+        assert!(city_object_intersects_bbox(&filter, &vertices));
+        let filter = parse_bbox_filter("0,1,-5,2,3,10").unwrap();
+        assert!(!city_object_intersects_bbox(&filter, &vertices));
+    }
 }
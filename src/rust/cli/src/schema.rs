@@ -0,0 +1,85 @@
+use fcb_core::{
+    attribute::{column_json_schema, AttributeSchema, AttributeSchemaMethods},
+    fb::ColumnType,
+    Header, FEATURE_ID_COLUMN,
+};
+use serde_json::{json, Map, Value};
+
+fn property(
+    col_type: ColumnType,
+    title: Option<&str>,
+    description: Option<&str>,
+    nullable: bool,
+) -> Value {
+    let mut prop = column_json_schema(col_type);
+    if let Value::Object(obj) = &mut prop {
+        if nullable {
+            if let Some(ty) = obj.get("type").cloned() {
+                obj.insert("type".to_string(), json!([ty, "null"]));
+            }
+        }
+        if let Some(title) = title {
+            obj.insert("title".to_string(), json!(title));
+        }
+        if let Some(description) = description {
+            obj.insert("description".to_string(), json!(description));
+        }
+    }
+    prop
+}
+
+fn document(properties: Map<String, Value>) -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "type": "object",
+        "properties": properties,
+    })
+}
+
+/// Build a JSON Schema from the column definitions of the header, if present
+pub fn from_header(header: &Header) -> Option<Value> {
+    let columns = header.columns()?;
+    let mut columns = columns
+        .iter()
+        .filter(|c| c.name() != FEATURE_ID_COLUMN)
+        .collect::<Vec<_>>();
+    columns.sort_by_key(|c| c.index());
+    let properties = columns
+        .into_iter()
+        .map(|c| {
+            (
+                c.name().to_string(),
+                property(c.type_(), c.title(), c.description(), c.nullable()),
+            )
+        })
+        .collect();
+    Some(document(properties))
+}
+
+/// Build a JSON Schema from a schema inferred from feature attributes
+pub fn from_attr_schema(schema: &AttributeSchema) -> Value {
+    schema.to_json_schema()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inferred_schema_types() {
+        let mut schema = AttributeSchema::new();
+        schema.add_attributes(&json!({ "height": 12.5, "storeys": 3, "name": "a" }));
+
+        let doc = from_attr_schema(&schema);
+        assert_eq!(doc["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(
+            doc["properties"]["height"]["type"],
+            json!(["number", "null"])
+        );
+        assert_eq!(doc["properties"]["name"]["type"], json!(["string", "null"]));
+        assert_eq!(
+            column_json_schema(ColumnType::DateTime),
+            json!({ "type": "string", "format": "date-time" })
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use fcb_core::{
+    error::Error,
+    stats::{compute_attribute_stats, ColumnStats},
+    FcbReader,
+};
+use std::{collections::BTreeMap, io::Read};
+
+/// Compute per-column statistics in a single pass over all features, sorted by column name.
+///
+/// Only the attribute bytes of each city object are decoded, geometries are skipped.
+pub fn compute_stats<R: Read>(
+    reader: R,
+    columns: Option<&[String]>,
+) -> Result<BTreeMap<String, ColumnStats>, Error> {
+    let mut iter = FcbReader::open(reader)?.select_all_seq()?;
+    let columns = columns.map(|names| names.iter().map(String::as_str).collect::<Vec<_>>());
+    let stats = compute_attribute_stats(&mut iter, columns.as_deref())?;
+    Ok(stats.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb_core::{
+        attribute::{AttributeSchema, AttributeSchemaMethods},
+        header_writer::HeaderWriterOptions,
+        read_cityjson_from_reader, CJType, CJTypeKind, FcbWriter,
+    };
+    use std::{collections::HashSet, fs::File, io::BufReader, path::PathBuf};
+
+    #[test]
+    fn stats_of_columns() {
+        let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../fcb_core/tests/data/small.city.jsonl");
+        let CJType::Seq(seq) =
+            read_cityjson_from_reader(BufReader::new(File::open(input).unwrap()), CJTypeKind::Seq)
+                .unwrap()
+        else {
+            panic!("expected cityjsonseq");
+        };
+        let mut attr_schema = AttributeSchema::new();
+        for co in seq.features.iter().flat_map(|f| f.city_objects.values()) {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..Default::default()
+            }),
+            Some(attr_schema.clone()),
+            None,
+        )
+        .unwrap();
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature).unwrap();
+        }
+        let mut fcb_buf = Vec::new();
+        fcb.write(&mut fcb_buf).unwrap();
+
+        let stats = compute_stats(fcb_buf.as_slice(), None).unwrap();
+        let mut names = attr_schema.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(stats.keys().cloned().collect::<Vec<_>>(), names);
+
+        // every city object is a row, with or without the attribute
+        let values = seq
+            .features
+            .iter()
+            .flat_map(|f| f.city_objects.values())
+            .map(|co| {
+                co.attributes
+                    .as_ref()
+                    .and_then(|attributes| attributes.get("identificatie"))
+                    .and_then(|value| value.as_str())
+            })
+            .collect::<Vec<_>>();
+        let present = values.iter().flatten().copied().collect::<Vec<_>>();
+        let ids = &stats["identificatie"];
+        assert_eq!(ids.count, present.len() as u64);
+        assert_eq!(ids.null_count, (values.len() - present.len()) as u64);
+        assert_eq!(
+            ids.distinct_count,
+            Some(present.iter().collect::<HashSet<_>>().len() as u64)
+        );
+        assert_eq!(
+            ids.min.as_ref().and_then(|v| v.as_str()),
+            present.iter().min().copied()
+        );
+        assert_eq!(
+            ids.max.as_ref().and_then(|v| v.as_str()),
+            present.iter().max().copied()
+        );
+
+        let columns = vec!["b3_h_dak_50p".to_string(), "unknown".to_string()];
+        let stats = compute_stats(fcb_buf.as_slice(), Some(&columns)).unwrap();
+        assert_eq!(
+            stats.keys().cloned().collect::<Vec<_>>(),
+            vec!["b3_h_dak_50p".to_string()]
+        );
+        assert!(stats["b3_h_dak_50p"].count > 0);
+    }
+}
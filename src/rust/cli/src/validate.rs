@@ -0,0 +1,385 @@
+use fcb_core::{
+    checksum::{crc32, sha256},
+    error::Error,
+    size_prefixed_root_as_city_feature, size_prefixed_root_as_header, NodeItem, NodeLayout,
+    PackedRTree, CHECKSUM_SIZE, HEADER_MAX_BUFFER_SIZE, HEADER_SIZE_SIZE, MAGIC_BYTES,
+    MAGIC_BYTES_SIZE, MIN_SUPPORTED_VERSION, VERSION,
+};
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{BufReader, Read, Seek, SeekFrom},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Result of validating a single FCB file
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+    pub features_checked: u64,
+}
+
+impl ValidationReport {
+    fn warn(&mut self, message: impl Into<String>) {
+        self.issues.push(Issue {
+            severity: Severity::Warning,
+            message: message.into(),
+        });
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.issues.push(Issue {
+            severity: Severity::Error,
+            message: message.into(),
+        });
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == severity)
+            .count()
+    }
+
+    /// 0 if the file is valid, 1 if only warnings were found, 2 on errors
+    pub fn exit_code(&self) -> i32 {
+        if self.count(Severity::Error) > 0 {
+            2
+        } else if self.count(Severity::Warning) > 0 {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in self.issues.iter() {
+            let label = match issue.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            writeln!(f, "  {}: {}", label, issue.message)?;
+        }
+        let status = match self.exit_code() {
+            0 => "valid",
+            1 => "valid with warnings",
+            _ => "invalid",
+        };
+        write!(
+            f,
+            "{} ({} features checked, {} errors, {} warnings)",
+            status,
+            self.features_checked,
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+/// Check the structural integrity of an FCB file.
+///
+/// Problems are collected into the report. Only I/O failures are returned as errors.
+/// With `strict` every feature buffer is also verified as a FlatBuffer. Files written with
+/// checksums have the CRC-32 of every feature verified, strict or not, and files written with
+/// a content hash have the SHA-256 of their feature section verified.
+pub fn validate<R: Read + Seek>(
+    reader: R,
+    file_len: u64,
+    strict: bool,
+) -> Result<ValidationReport, Error> {
+    let mut reader = BufReader::new(reader);
+    let mut report = ValidationReport::default();
+
+    // magic bytes
+    if file_len < (MAGIC_BYTES_SIZE + HEADER_SIZE_SIZE) as u64 {
+        report.error(format!("file is too small ({} bytes)", file_len));
+        return Ok(report);
+    }
+    let mut magic = [0u8; MAGIC_BYTES_SIZE];
+    reader.read_exact(&mut magic)?;
+    if magic[0..3] != MAGIC_BYTES[0..3] || magic[4..7] != MAGIC_BYTES[4..7] {
+        report.error("missing FCB magic bytes");
+        return Ok(report);
+    }
+    if !(MIN_SUPPORTED_VERSION..=VERSION).contains(&magic[3]) {
+        report.error(format!(
+            "unsupported version {} (supported are {}..={})",
+            magic[3], MIN_SUPPORTED_VERSION, VERSION
+        ));
+        return Ok(report);
+    }
+    let layout = NodeLayout::for_version(magic[3]);
+
+    // header
+    let mut size_buf = [0u8; HEADER_SIZE_SIZE];
+    reader.read_exact(&mut size_buf)?;
+    let header_size = u32::from_le_bytes(size_buf) as usize;
+    if !(8..=HEADER_MAX_BUFFER_SIZE).contains(&header_size) {
+        report.error(format!("header size {} is out of range", header_size));
+        return Ok(report);
+    }
+    let header_end = (MAGIC_BYTES_SIZE + HEADER_SIZE_SIZE + header_size) as u64;
+    if header_end > file_len {
+        report.error(format!(
+            "header size {} exceeds the file length {}",
+            header_size, file_len
+        ));
+        return Ok(report);
+    }
+    let mut header_buf = Vec::with_capacity(HEADER_SIZE_SIZE + header_size);
+    header_buf.extend_from_slice(&size_buf);
+    header_buf.resize(HEADER_SIZE_SIZE + header_size, 0);
+    reader.read_exact(&mut header_buf[HEADER_SIZE_SIZE..])?;
+    let header = match size_prefixed_root_as_header(&header_buf) {
+        Ok(header) => header,
+        Err(e) => {
+            report.error(format!("invalid header: {}", e));
+            return Ok(report);
+        }
+    };
+
+    // spatial index
+    let features_count = header.features_count();
+    let node_size = header.index_node_size();
+    let rtree_size = if features_count == 0 {
+        0
+    } else if node_size == 0 {
+        report.warn("file has no spatial index");
+        0
+    } else if node_size < 2 {
+        report.error(format!("invalid index node size {}", node_size));
+        return Ok(report);
+    } else {
+        PackedRTree::index_size(features_count as usize, node_size, layout) as u64
+    };
+
+    // attribute indices, followed by the composite indices
+    let attr_index_size = header
+        .attribute_index()
+        .map(|ai| ai.iter().map(|ai| ai.length() as u64).sum::<u64>())
+        .unwrap_or(0)
+        + header
+            .composite_index()
+            .map(|ci| ci.iter().map(|ci| ci.length() as u64).sum::<u64>())
+            .unwrap_or(0);
+
+    let features_start = header_end + rtree_size + attr_index_size;
+    if features_start > file_len {
+        report.error(format!(
+            "index sections ({} bytes spatial, {} bytes attribute) exceed the file length {}",
+            rtree_size, attr_index_size, file_len
+        ));
+        return Ok(report);
+    }
+    let features_len = file_len - features_start;
+
+    // leaf nodes are stored last and each points to the start of a feature
+    let mut leaf_offsets = Vec::new();
+    if rtree_size > 0 {
+        let leaf_bytes = features_count * layout.item_size() as u64;
+        reader.seek(SeekFrom::Start(header_end + rtree_size - leaf_bytes))?;
+        for _ in 0..features_count {
+            leaf_offsets.push(NodeItem::from_reader_with_layout(&mut reader, layout)?.offset);
+        }
+        let out_of_bounds = leaf_offsets.iter().filter(|o| **o >= features_len).count();
+        if out_of_bounds > 0 {
+            report.error(format!(
+                "{} spatial index entries point beyond the feature section",
+                out_of_bounds
+            ));
+        }
+    }
+
+    // features
+    let checksum_size = if header.checksum() { CHECKSUM_SIZE } else { 0 };
+    reader.seek(SeekFrom::Start(features_start))?;
+    let mut feature_offsets = HashSet::new();
+    let mut offset = 0u64;
+    let mut feature_buf = Vec::new();
+    while offset < features_len {
+        if features_len - offset < HEADER_SIZE_SIZE as u64 {
+            report.error(format!(
+                "trailing {} bytes after feature {}",
+                features_len - offset,
+                report.features_checked
+            ));
+            break;
+        }
+        reader.read_exact(&mut size_buf)?;
+        let feature_size = u32::from_le_bytes(size_buf) as u64;
+        let feature_end = offset + (HEADER_SIZE_SIZE + checksum_size) as u64 + feature_size;
+        if feature_end > features_len {
+            report.error(format!(
+                "feature {} at offset {} has size {} which exceeds the file length",
+                report.features_checked, offset, feature_size
+            ));
+            break;
+        }
+
+        if strict || checksum_size > 0 {
+            feature_buf.clear();
+            feature_buf.extend_from_slice(&size_buf);
+            feature_buf.resize(HEADER_SIZE_SIZE + feature_size as usize, 0);
+            reader.read_exact(&mut feature_buf[HEADER_SIZE_SIZE..])?;
+            if checksum_size > 0 {
+                let mut crc_buf = [0u8; CHECKSUM_SIZE];
+                reader.read_exact(&mut crc_buf)?;
+                let expected = u32::from_le_bytes(crc_buf);
+                let actual = crc32(&feature_buf);
+                if expected != actual {
+                    let e = Error::ChecksumMismatch {
+                        feature_index: report.features_checked as usize,
+                        expected,
+                        actual,
+                    };
+                    report.error(format!("{} (offset {})", e, offset));
+                }
+            }
+            if strict {
+                if let Err(e) = size_prefixed_root_as_city_feature(&feature_buf) {
+                    report.error(format!(
+                        "feature {} at offset {} is not a valid FlatBuffer: {}",
+                        report.features_checked, offset, e
+                    ));
+                }
+            }
+        } else {
+            reader.seek_relative(feature_size as i64)?;
+        }
+
+        feature_offsets.insert(offset);
+        report.features_checked += 1;
+        offset = feature_end;
+    }
+
+    if report.features_checked != features_count {
+        report.error(format!(
+            "header declares {} features but {} were found",
+            features_count, report.features_checked
+        ));
+    }
+    let dangling = leaf_offsets
+        .iter()
+        .filter(|o| **o < features_len && !feature_offsets.contains(o))
+        .count();
+    if dangling > 0 {
+        report.error(format!(
+            "{} spatial index entries do not point to the start of a feature",
+            dangling
+        ));
+    }
+
+    if let Some(expected) = header.content_hash() {
+        reader.seek(SeekFrom::Start(features_start))?;
+        let actual = sha256(reader.by_ref().take(features_len))?;
+        if expected.bytes() != actual.as_slice() {
+            report.error("content hash does not match the feature section");
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb_core::{
+        attribute::{AttributeSchema, AttributeSchemaMethods},
+        header_writer::HeaderWriterOptions,
+        read_cityjson_from_reader, CJType, CJTypeKind, CityJSONSeq, FcbWriter,
+    };
+    use std::{fs::File, io::Cursor, path::PathBuf};
+
+    fn read_seq() -> CityJSONSeq {
+        let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../fcb_core/tests/data/small.city.jsonl");
+        let CJType::Seq(seq) =
+            read_cityjson_from_reader(BufReader::new(File::open(input).unwrap()), CJTypeKind::Seq)
+                .unwrap()
+        else {
+            panic!("expected cityjsonseq");
+        };
+        seq
+    }
+
+    fn write_fcb(seq: &CityJSONSeq, write_index: bool) -> Vec<u8> {
+        let mut attr_schema = AttributeSchema::new();
+        for co in seq.features.iter().flat_map(|f| f.city_objects.values()) {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                write_index,
+                compute_hash: true,
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )
+        .unwrap()
+        .checksum(true);
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature).unwrap();
+        }
+        let mut buf = Vec::new();
+        fcb.write(&mut buf).unwrap();
+        buf
+    }
+
+    fn validate_buf(buf: &[u8]) -> ValidationReport {
+        validate(Cursor::new(buf), buf.len() as u64, true).unwrap()
+    }
+
+    #[test]
+    fn validate_files() {
+        let seq = read_seq();
+        let valid = write_fcb(&seq, true);
+        let report = validate_buf(&valid);
+        assert!(report.issues.is_empty(), "{report}");
+        assert_eq!(report.features_checked, seq.features.len() as u64);
+        assert_eq!(report.exit_code(), 0);
+
+        // a missing spatial index is only a warning
+        let report = validate_buf(&write_fcb(&seq, false));
+        assert_eq!(report.exit_code(), 1, "{report}");
+
+        // the checksum of the last feature and the content hash no longer match
+        let mut corrupted = valid.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        let report = validate_buf(&corrupted);
+        assert_eq!(report.exit_code(), 2);
+        assert!(report
+            .issues
+            .iter()
+            .any(|issue| issue.message.contains("content hash")));
+
+        let truncated = &valid[..valid.len() - 10];
+        let report = validate_buf(truncated);
+        assert_eq!(report.exit_code(), 2);
+        assert!(report
+            .issues
+            .iter()
+            .any(|issue| issue.message.contains("exceeds the file length")));
+
+        let report = validate_buf(b"not an fcb file");
+        assert_eq!(report.exit_code(), 2);
+        assert_eq!(report.issues[0].message, "missing FCB magic bytes");
+    }
+}
@@ -0,0 +1,83 @@
+#ifndef FCB_H
+#define FCB_H
+
+/* Generated with cbindgen, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ Iterator over the selected features
+ */
+typedef struct FcbIterHandle FcbIterHandle;
+
+/*
+ Reader of an FCB file, each `fcb_reader_select_*` function reopens the file
+ */
+typedef struct FcbReaderHandle FcbReaderHandle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Returns the message of the last error of the calling thread, or NULL if the last call
+ succeeded. The string is valid until the next call into this library on the same thread.
+ */
+const char *fcb_last_error(void);
+
+/*
+ Opens an FCB file and checks its header. Returns NULL on failure.
+
+ # Safety
+ `path` must be a valid NUL-terminated string.
+ */
+FcbReaderHandle *fcb_reader_open(const char *path);
+
+/*
+ Selects the features intersecting the bounding box, using the spatial index.
+ Returns NULL on failure.
+
+ # Safety
+ `handle` must be a pointer returned by `fcb_reader_open` that has not been freed.
+ */
+FcbIterHandle *fcb_reader_select_bbox(const FcbReaderHandle *handle,
+                                      double minx,
+                                      double miny,
+                                      double maxx,
+                                      double maxy);
+
+/*
+ Returns the next feature as a CityJSONFeature JSON string, or NULL at the end of the
+ iteration or on failure, which `fcb_last_error` tells apart. The string is owned by the
+ iterator and valid until the next `fcb_iter_next` or `fcb_iter_free` call.
+
+ # Safety
+ `iter` must be a pointer returned by `fcb_reader_select_bbox` that has not been freed.
+ */
+const char *fcb_iter_next(FcbIterHandle *iter);
+
+/*
+ Frees an iterator, NULL is ignored.
+
+ # Safety
+ `iter` must be NULL or a pointer returned by `fcb_reader_select_bbox` that has not been
+ freed.
+ */
+void fcb_iter_free(FcbIterHandle *iter);
+
+/*
+ Frees a reader, NULL is ignored. Iterators selected from the reader stay valid.
+
+ # Safety
+ `handle` must be NULL or a pointer returned by `fcb_reader_open` that has not been freed.
+ */
+void fcb_reader_free(FcbReaderHandle *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FCB_H */
@@ -0,0 +1,85 @@
+import datetime
+import json
+import pathlib
+import subprocess
+
+import pytest
+
+import flatcitybuf
+
+CRATE_DIR = pathlib.Path(__file__).resolve().parents[2]
+SMALL_CITYJSONSEQ = CRATE_DIR / "tests" / "data" / "small.city.jsonl"
+
+
+@pytest.fixture(scope="session")
+def small_fcb(tmp_path_factory):
+    """small.city.jsonl converted with the fcb CLI"""
+    output = tmp_path_factory.mktemp("fcb") / "small.fcb"
+    subprocess.run(
+        [
+            "cargo", "run", "--quiet", "--bin", "fcb", "--",
+            "ser", "-i", str(SMALL_CITYJSONSEQ), "-o", str(output),
+        ],
+        cwd=CRATE_DIR.parent,
+        check=True,
+    )
+    return output
+
+
+@pytest.fixture(scope="session")
+def original_features():
+    lines = SMALL_CITYJSONSEQ.read_text().splitlines()
+    # the first line holds the metadata
+    return [json.loads(line) for line in lines[1:] if line.strip()]
+
+
+def test_select_all_round_trip(small_fcb, original_features):
+    reader = flatcitybuf.FcbReader(str(small_fcb))
+    features = list(reader.select_all())
+
+    assert len(features) == len(original_features)
+    for feature, original in zip(features, original_features):
+        assert feature["type"] == "CityJSONFeature"
+        assert feature["id"] == original["id"]
+        assert feature["CityObjects"].keys() == original["CityObjects"].keys()
+        for co_id, co in feature["CityObjects"].items():
+            original_co = original["CityObjects"][co_id]
+            assert co["type"] == original_co["type"]
+            for name, value in original_co.get("attributes", {}).items():
+                if isinstance(value, (str, bool)):
+                    read = co["attributes"][name]
+                    if isinstance(read, datetime.datetime):
+                        assert read == datetime.datetime.fromisoformat(value)
+                    else:
+                        assert read == value
+
+
+def test_select_bbox(small_fcb):
+    reader = flatcitybuf.FcbReader(str(small_fcb))
+    all_ids = {feature["id"] for feature in reader.select_all()}
+    bbox_ids = {
+        feature["id"]
+        for feature in reader.select_bbox(84227.77, 445377.33, 85323.23, 446334.69)
+    }
+    assert bbox_ids
+    assert bbox_ids <= all_ids
+    assert not list(reader.select_bbox(0.0, 0.0, 1.0, 1.0))
+
+
+def test_attribute_schema(small_fcb):
+    reader = flatcitybuf.FcbReader(str(small_fcb))
+    schema = reader.attribute_schema
+    assert schema
+    assert set(schema.values()) <= {int, float, str, bool, datetime.datetime, dict, bytes}
+    assert schema["identificatie"] is str
+
+
+def test_metadata(small_fcb):
+    metadata = flatcitybuf.FcbReader(str(small_fcb)).metadata()
+    assert metadata["type"] == "CityJSON"
+    assert "transform" in metadata
+
+
+def test_missing_file(tmp_path):
+    with pytest.raises(OSError):
+        flatcitybuf.FcbReader(str(tmp_path / "missing.fcb"))
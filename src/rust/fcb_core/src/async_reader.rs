@@ -0,0 +1,345 @@
+//! Asynchronous counterpart of [`FcbReader`](crate::FcbReader) for tokio readers such as
+//! `tokio::fs::File`.
+
+use std::io::{Cursor, SeekFrom};
+
+use cjseq::CityJSONFeature;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::checksum::crc32;
+use crate::error::Error;
+use crate::fb::{size_prefixed_root_as_city_feature, size_prefixed_root_as_header, Header};
+use crate::packed_rtree::{NodeLayout, PackedRTree, Query, SearchResultItem};
+use crate::reader::city_buffer::FcbBuffer;
+use crate::reader::{attr_index_size, rtree_index_size};
+use crate::{parse_magic_bytes, CHECKSUM_SIZE, HEADER_MAX_BUFFER_SIZE};
+
+pub struct AsyncFcbReader<R> {
+    reader: R,
+    verify: bool,
+    buffer: FcbBuffer,
+    /// File format version found in the magic bytes
+    version: u8,
+}
+
+pub struct AsyncFeatureIter<R> {
+    reader: R,
+    /// FlatBuffers verification
+    verify: bool,
+    buffer: FcbBuffer,
+    /// Selected features or None if no bbox filter
+    item_filter: Option<Vec<SearchResultItem>>,
+    /// Number of selected features
+    count: usize,
+    /// Current feature number
+    feat_no: usize,
+    /// File offset of the first feature
+    features_start: u64,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncFcbReader<R> {
+    pub async fn open(reader: R) -> Result<AsyncFcbReader<R>, Error> {
+        Self::read_header(reader, true).await
+    }
+
+    /// Open a reader without verifying the FlatBuffers data.
+    ///
+    /// # Safety
+    /// This function skips FlatBuffers verification. The caller must ensure that the input data
+    /// is valid and properly formatted to avoid undefined behavior.
+    pub async unsafe fn open_unchecked(reader: R) -> Result<AsyncFcbReader<R>, Error> {
+        Self::read_header(reader, false).await
+    }
+
+    async fn read_header(mut reader: R, verify: bool) -> Result<AsyncFcbReader<R>, Error> {
+        let mut magic_buf: [u8; 8] = [0; 8];
+        reader.read_exact(&mut magic_buf).await?;
+        let version = parse_magic_bytes(&magic_buf)?;
+
+        let mut size_buf: [u8; 4] = [0; 4];
+        reader.read_exact(&mut size_buf).await?;
+        let header_size = u32::from_le_bytes(size_buf) as usize;
+        if !((8..=HEADER_MAX_BUFFER_SIZE).contains(&header_size)) {
+            return Err(Error::IllegalHeaderSize(header_size));
+        }
+
+        let mut header_buf = Vec::with_capacity(header_size + 4); // 4 bytes for size prefix
+        header_buf.extend_from_slice(&size_buf);
+        header_buf.resize(header_buf.capacity(), 0);
+        reader.read_exact(&mut header_buf[4..]).await?;
+
+        if verify {
+            size_prefixed_root_as_header(&header_buf)?;
+        }
+
+        Ok(AsyncFcbReader {
+            reader,
+            verify,
+            version,
+            buffer: FcbBuffer {
+                header_buf,
+                features_buf: Vec::new(),
+            },
+        })
+    }
+
+    pub fn header(&self) -> Header {
+        self.buffer.header()
+    }
+
+    /// File format version of the file
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn node_layout(&self) -> NodeLayout {
+        NodeLayout::for_version(self.version)
+    }
+
+    fn features_start(&self) -> u64 {
+        let header = self.buffer.header();
+        8 + self.buffer.header_buf.len() as u64
+            + rtree_index_size(&header, self.node_layout())
+            + attr_index_size(&header)
+    }
+
+    pub async fn select_all(mut self) -> Result<AsyncFeatureIter<R>, Error> {
+        let features_start = self.features_start();
+        self.reader.seek(SeekFrom::Start(features_start)).await?;
+        let count = self.buffer.header().features_count() as usize;
+        Ok(AsyncFeatureIter::new(
+            self.reader,
+            self.verify,
+            self.buffer,
+            None,
+            count,
+            features_start,
+        ))
+    }
+
+    /// Select features intersecting the given 2D bounding box using the spatial index
+    pub async fn select_bbox(
+        self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> Result<AsyncFeatureIter<R>, Error> {
+        self.select_query(Query::BBox(min_x, min_y, max_x, max_y))
+            .await
+    }
+
+    pub async fn select_query(mut self, query: Query) -> Result<AsyncFeatureIter<R>, Error> {
+        let header = self.buffer.header();
+        if header.index_node_size() == 0 || header.features_count() == 0 {
+            return Err(Error::NoIndex);
+        }
+        let num_items = header.features_count() as usize;
+        let node_size = header.index_node_size();
+        // the index is read at once and searched in memory
+        let layout = self.node_layout();
+        let mut index_buf = vec![0; rtree_index_size(&header, layout) as usize];
+        self.reader.read_exact(&mut index_buf).await?;
+        let list = PackedRTree::stream_search(
+            &mut Cursor::new(index_buf),
+            num_items,
+            node_size,
+            query,
+            layout,
+        )?;
+        let features_start = self.features_start();
+        let count = list.len();
+        Ok(AsyncFeatureIter::new(
+            self.reader,
+            self.verify,
+            self.buffer,
+            Some(list),
+            count,
+            features_start,
+        ))
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncFeatureIter<R> {
+    fn new(
+        reader: R,
+        verify: bool,
+        buffer: FcbBuffer,
+        item_filter: Option<Vec<SearchResultItem>>,
+        count: usize,
+        features_start: u64,
+    ) -> Self {
+        AsyncFeatureIter {
+            reader,
+            verify,
+            buffer,
+            item_filter,
+            count,
+            feat_no: 0,
+            features_start,
+        }
+    }
+
+    /// Read the next feature, `None` once all selected features have been read
+    pub async fn next(&mut self) -> Result<Option<&FcbBuffer>, Error> {
+        let feat_no = self.feat_no;
+        self.read_next()
+            .await
+            .map_err(|e| e.with_context(format!("feature {feat_no}")))
+    }
+
+    async fn read_next(&mut self) -> Result<Option<&FcbBuffer>, Error> {
+        if self.feat_no >= self.count {
+            return Ok(None);
+        }
+        if let Some(filter) = &self.item_filter {
+            let offset = self.features_start + filter[self.feat_no].offset as u64;
+            self.reader.seek(SeekFrom::Start(offset)).await?;
+        }
+
+        let mut size_buf = [0u8; 4];
+        self.reader.read_exact(&mut size_buf).await?;
+        let feature_size = u32::from_le_bytes(size_buf) as usize;
+        self.buffer.features_buf.resize(feature_size + 4, 0);
+        self.buffer.features_buf[..4].copy_from_slice(&size_buf);
+        self.reader
+            .read_exact(&mut self.buffer.features_buf[4..])
+            .await?;
+
+        if self.buffer.header().checksum() {
+            let mut crc_buf = [0u8; CHECKSUM_SIZE];
+            self.reader.read_exact(&mut crc_buf).await?;
+            let expected = u32::from_le_bytes(crc_buf);
+            let actual = crc32(&self.buffer.features_buf);
+            if expected != actual {
+                return Err(Error::ChecksumMismatch {
+                    feature_index: self.feat_no,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        if self.verify {
+            size_prefixed_root_as_city_feature(&self.buffer.features_buf)?;
+        }
+        self.feat_no += 1;
+        Ok(Some(&self.buffer))
+    }
+
+    /// Return current feature
+    pub fn cur_cj_feature(&self) -> Result<CityJSONFeature, Error> {
+        self.buffer.cj_feature()
+    }
+
+    pub fn header(&self) -> Header {
+        self.buffer.header()
+    }
+
+    /// Number of selected features
+    pub fn features_count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header_writer::HeaderWriterOptions;
+    use crate::{read_cityjson_from_reader, CJType, CJTypeKind, FcbReader, FcbWriter};
+    use anyhow::Result;
+    use fallible_streaming_iterator::FallibleStreamingIterator;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+    use std::io::{BufReader, Seek, Write};
+    use std::path::PathBuf;
+
+    fn write_fcb(checksum: bool) -> Result<tempfile::NamedTempFile> {
+        let input_file =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/small.city.jsonl");
+        let seq = match read_cityjson_from_reader(
+            BufReader::new(File::open(input_file)?),
+            CJTypeKind::Seq,
+        )? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                checksum,
+                ..Default::default()
+            }),
+            None,
+            None,
+        )?;
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut file = tempfile::NamedTempFile::new()?;
+        fcb.write(&mut file)?;
+        file.flush()?;
+        file.rewind()?;
+        Ok(file)
+    }
+
+    fn sync_features(path: &std::path::Path) -> Result<Vec<serde_json::Value>> {
+        let mut iter = FcbReader::open(BufReader::new(File::open(path)?))?.select_all()?;
+        let mut features = Vec::new();
+        while let Some(feature) = iter.next()? {
+            features.push(serde_json::to_value(feature.cur_cj_feature()?)?);
+        }
+        Ok(features)
+    }
+
+    #[tokio::test]
+    async fn test_async_select_all() -> Result<()> {
+        for checksum in [false, true] {
+            let file = write_fcb(checksum)?;
+            let expected = sync_features(file.path())?;
+
+            let reader = AsyncFcbReader::open(tokio::fs::File::open(file.path()).await?).await?;
+            let mut iter = reader.select_all().await?;
+            assert_eq!(expected.len(), iter.features_count());
+            let mut features = Vec::new();
+            while iter.next().await?.is_some() {
+                features.push(serde_json::to_value(iter.cur_cj_feature()?)?);
+            }
+            assert_eq!(expected, features);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_select_bbox() -> Result<()> {
+        let file = write_fcb(false)?;
+        let extent = FcbReader::open(File::open(file.path())?)?
+            .header()
+            .geographical_extent()
+            .copied()
+            .unwrap();
+        let (min_x, min_y, max_x, max_y) = (
+            extent.min().x(),
+            extent.min().y(),
+            (extent.min().x() + extent.max().x()) / 2.0,
+            (extent.min().y() + extent.max().y()) / 2.0,
+        );
+
+        let mut sync_iter =
+            FcbReader::open(File::open(file.path())?)?.select_bbox(min_x, min_y, max_x, max_y)?;
+        let mut expected = Vec::new();
+        while let Some(feature) = sync_iter.next()? {
+            expected.push(serde_json::to_value(feature.cur_cj_feature()?)?);
+        }
+
+        let reader = AsyncFcbReader::open(tokio::fs::File::open(file.path()).await?).await?;
+        let mut iter = reader.select_bbox(min_x, min_y, max_x, max_y).await?;
+        let mut features = Vec::new();
+        while iter.next().await?.is_some() {
+            features.push(serde_json::to_value(iter.cur_cj_feature()?)?);
+        }
+        assert!(!features.is_empty());
+        assert_eq!(expected, features);
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use fcb_core::{
+    attribute::{AttributeSchema, AttributeSchemaMethods},
+    global_vertices::global_vertex_ids,
+    header_writer::HeaderWriterOptions,
+    read_cityjson_from_reader, CJType, CJTypeKind, CityJSONSeq, FcbWriter,
+};
+use prettytable::{Cell, Row, Table};
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "global_vertices_size",
+    about = "Compare the size of FCB files written with and without a global vertex table"
+)]
+struct Args {
+    /// CityJSONSeq files to write (defaults to tests/data/delft.city.jsonl)
+    #[arg(short, long, num_args = 1..)]
+    input: Vec<PathBuf>,
+}
+
+/// Size in bytes of `seq` written as FCB, with a global vertex table if `global` is set
+fn fcb_size(seq: &CityJSONSeq, global: bool) -> Result<usize> {
+    let mut attr_schema = AttributeSchema::new();
+    for feature in seq.features.iter() {
+        for co in feature.city_objects.values() {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+    }
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema),
+        None,
+    )?;
+    if global {
+        fcb = fcb.with_global_vertices(global_vertex_ids(&seq.features, &seq.cj.transform));
+    }
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+    Ok(out.len())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let inputs = if args.input.is_empty() {
+        vec![PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/delft.city.jsonl")]
+    } else {
+        args.input
+    };
+
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("file"),
+        Cell::new("features"),
+        Cell::new("per-feature vertices (B)"),
+        Cell::new("global vertices (B)"),
+        Cell::new("reduction"),
+    ]));
+    for path in inputs {
+        let reader = BufReader::new(File::open(&path)?);
+        let CJType::Seq(seq) = read_cityjson_from_reader(reader, CJTypeKind::Seq)? else {
+            bail!("{} is not a CityJSONSeq file", path.display());
+        };
+        let local = fcb_size(&seq, false)?;
+        let global = fcb_size(&seq, true)?;
+        let reduction = 100.0 * (1.0 - global as f64 / local as f64);
+        table.add_row(Row::new(vec![
+            Cell::new(&path.display().to_string()),
+            Cell::new(&seq.features.len().to_string()),
+            Cell::new(&local.to_string()),
+            Cell::new(&global.to_string()),
+            Cell::new(&format!("{reduction:.1}%")),
+        ]));
+    }
+    table.printstd();
+    Ok(())
+}
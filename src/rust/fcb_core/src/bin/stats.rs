@@ -1,6 +1,6 @@
 use anyhow::{Context, Result};
 use clap::{Parser, ValueEnum};
-use fcb_core::FcbReader;
+use fcb_core::{FcbReader, FEATURE_ID_COLUMN};
 use prettytable::{Cell, Row, Table};
 use std::{
     collections::HashSet,
@@ -89,7 +89,12 @@ fn analyze_fcb(path: &Path) -> Result<(u64, usize, usize, usize, usize, f64)> {
     let feature_count = header.features_count();
 
     // Get attribute count from the header's columns
-    let attribute_count = header.columns().map(|col| col.len()).unwrap_or(0);
+    let attribute_count = header
+        .columns()
+        .iter()
+        .flat_map(|cols| cols.iter())
+        .filter(|col| col.name() != FEATURE_ID_COLUMN)
+        .count();
     let semantic_attribute_count = header.semantic_columns().map(|col| col.len()).unwrap_or(0);
 
     // Count city objects and vertices
@@ -34,6 +34,12 @@ fn write_file() -> Result<(), Box<dyn Error>> {
             index_node_size: 16,
             attribute_indices: Some(attr_indices),
             geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
         });
         let mut attr_schema = AttributeSchema::new();
         for feature in features.iter() {
@@ -0,0 +1,198 @@
+//! C bindings, the header is generated with `cbindgen` into `include/fcb.h`
+//!
+//! ```c
+//! FcbReaderHandle *reader = fcb_reader_open("delft.fcb");
+//! FcbIterHandle *iter = fcb_reader_select_bbox(reader, 84000.0, 445000.0, 86000.0, 447000.0);
+//! const char *feature;
+//! while ((feature = fcb_iter_next(iter)) != NULL) {
+//!     puts(feature);
+//! }
+//! if (fcb_last_error() != NULL) {
+//!     fprintf(stderr, "%s\n", fcb_last_error());
+//! }
+//! fcb_iter_free(iter);
+//! fcb_reader_free(reader);
+//! ```
+//!
+//! Functions returning a pointer return NULL on failure, `fcb_last_error` then describes the
+//! error of the calling thread.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::ptr;
+
+use crate::error::Error;
+use crate::reader::{reader_trait::Seekable, FcbReader, FeatureIter};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Display) {
+    // error messages never contain NUL bytes, strip them anyway rather than losing the error
+    let message = message.to_string().replace('\0', "");
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// Reader of an FCB file, each `fcb_reader_select_*` function reopens the file
+pub struct FcbReaderHandle {
+    path: PathBuf,
+}
+
+impl FcbReaderHandle {
+    fn open(&self) -> Result<FcbReader<BufReader<File>>, Error> {
+        FcbReader::open(BufReader::new(File::open(&self.path)?))
+    }
+}
+
+/// Iterator over the selected features
+pub struct FcbIterHandle {
+    iter: FeatureIter<BufReader<File>, Seekable>,
+    /// JSON of the feature returned by the last `fcb_iter_next` call
+    current: Option<CString>,
+}
+
+impl FcbIterHandle {
+    fn next_json(&mut self) -> Result<Option<CString>, Error> {
+        let Some(feat_buf) = self.iter.next()? else {
+            return Ok(None);
+        };
+        let feature = feat_buf.cur_cj_feature()?;
+        let json = serde_json::to_string(&feature)?;
+        // serde_json escapes NUL characters, so the JSON never contains a NUL byte
+        Ok(Some(CString::new(json).unwrap_or_default()))
+    }
+}
+
+/// Returns the message of the last error of the calling thread, or NULL if the last call
+/// succeeded. The string is valid until the next call into this library on the same thread.
+#[no_mangle]
+pub extern "C" fn fcb_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Opens an FCB file and checks its header. Returns NULL on failure.
+///
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fcb_reader_open(path: *const c_char) -> *mut FcbReaderHandle {
+    if path.is_null() {
+        set_last_error("path is NULL");
+        return ptr::null_mut();
+    }
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => PathBuf::from(path),
+        Err(e) => {
+            set_last_error(format!("path is not UTF-8: {e}"));
+            return ptr::null_mut();
+        }
+    };
+    let handle = FcbReaderHandle { path };
+    // fail early on missing files and invalid headers
+    if let Err(e) = handle.open() {
+        set_last_error(e);
+        return ptr::null_mut();
+    }
+    clear_last_error();
+    Box::into_raw(Box::new(handle))
+}
+
+/// Selects the features intersecting the bounding box, using the spatial index.
+/// Returns NULL on failure.
+///
+/// # Safety
+/// `handle` must be a pointer returned by `fcb_reader_open` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn fcb_reader_select_bbox(
+    handle: *const FcbReaderHandle,
+    minx: f64,
+    miny: f64,
+    maxx: f64,
+    maxy: f64,
+) -> *mut FcbIterHandle {
+    let Some(handle) = (unsafe { handle.as_ref() }) else {
+        set_last_error("reader is NULL");
+        return ptr::null_mut();
+    };
+    match handle
+        .open()
+        .and_then(|reader| reader.select_bbox(minx, miny, maxx, maxy))
+    {
+        Ok(iter) => {
+            clear_last_error();
+            Box::into_raw(Box::new(FcbIterHandle {
+                iter,
+                current: None,
+            }))
+        }
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns the next feature as a CityJSONFeature JSON string, or NULL at the end of the
+/// iteration or on failure, which `fcb_last_error` tells apart. The string is owned by the
+/// iterator and valid until the next `fcb_iter_next` or `fcb_iter_free` call.
+///
+/// # Safety
+/// `iter` must be a pointer returned by `fcb_reader_select_bbox` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn fcb_iter_next(iter: *mut FcbIterHandle) -> *const c_char {
+    let Some(iter) = (unsafe { iter.as_mut() }) else {
+        set_last_error("iterator is NULL");
+        return ptr::null();
+    };
+    match iter.next_json() {
+        Ok(json) => {
+            clear_last_error();
+            iter.current = json;
+            iter.current
+                .as_ref()
+                .map_or(ptr::null(), |json| json.as_ptr())
+        }
+        Err(e) => {
+            set_last_error(e);
+            iter.current = None;
+            ptr::null()
+        }
+    }
+}
+
+/// Frees an iterator, NULL is ignored.
+///
+/// # Safety
+/// `iter` must be NULL or a pointer returned by `fcb_reader_select_bbox` that has not been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn fcb_iter_free(iter: *mut FcbIterHandle) {
+    if !iter.is_null() {
+        drop(unsafe { Box::from_raw(iter) });
+    }
+}
+
+/// Frees a reader, NULL is ignored. Iterators selected from the reader stay valid.
+///
+/// # Safety
+/// `handle` must be NULL or a pointer returned by `fcb_reader_open` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn fcb_reader_free(handle: *mut FcbReaderHandle) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
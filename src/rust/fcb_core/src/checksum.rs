@@ -0,0 +1,45 @@
+//! CRC-32 (IEEE 802.3, as used by zlib and PNG) of the size-prefixed feature buffers, and
+//! SHA-256 content hash of the whole feature section.
+
+use crate::const_vars::CONTENT_HASH_SIZE;
+use sha2::{Digest, Sha256};
+use std::io::{self, Read};
+
+/// CRC-32 of `bytes`
+pub fn crc32(bytes: &[u8]) -> u32 {
+    crc32fast::hash(bytes)
+}
+
+/// SHA-256 of all the bytes of `reader`
+pub fn sha256(mut reader: impl Read) -> io::Result<[u8; CONTENT_HASH_SIZE]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(&b"abc"[..]).unwrap(),
+            [
+                0xBA, 0x78, 0x16, 0xBF, 0x8F, 0x01, 0xCF, 0xEA, 0x41, 0x41, 0x40, 0xDE, 0x5D, 0xAE,
+                0x22, 0x23, 0xB0, 0x03, 0x61, 0xA3, 0x96, 0x17, 0x7A, 0x9C, 0xB4, 0x10, 0xFF, 0x61,
+                0xF2, 0x00, 0x15, 0xAD
+            ]
+        );
+    }
+}
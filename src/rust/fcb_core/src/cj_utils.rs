@@ -16,6 +16,9 @@ pub enum CJType {
 pub enum CJTypeKind {
     Normal,
     Seq,
+    /// Detect the kind from the first line of the input: a `CityJSONFeature`, or a `CityJSON`
+    /// object followed by more lines, is read as a CityJSONSeq
+    Auto,
 }
 
 pub trait CityJSONReader {
@@ -41,9 +44,37 @@ impl CityJSONReader for &str {
     }
 }
 
+/// Kind of a CityJSON input given its first line and whether other lines follow.
+///
+/// A first line holding a complete `CityJSONFeature`, or a complete `CityJSON` object followed
+/// by more lines, starts a CityJSONSeq. Anything else, e.g. a pretty-printed file whose first
+/// line is only `{`, is read as a plain CityJSON file.
+fn detect_kind(first_line: Option<&str>, has_more_lines: bool) -> CJTypeKind {
+    let root_type = first_line
+        .and_then(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .and_then(|value| value.get("type")?.as_str().map(str::to_string));
+    match root_type.as_deref() {
+        Some("CityJSONFeature") => CJTypeKind::Seq,
+        Some("CityJSON") if has_more_lines => CJTypeKind::Seq,
+        _ => CJTypeKind::Normal,
+    }
+}
+
 fn parse_cityjson<T: CityJSONReader>(mut source: T, cj_type: CJTypeKind) -> Result<CJType, Error> {
     let mut lines = source.read_lines();
 
+    let cj_type = match cj_type {
+        CJTypeKind::Auto => {
+            // the first line is put back in front of the remaining ones once the kind is known
+            let first_line = lines.next().transpose()?;
+            let mut rest = lines.peekable();
+            let kind = detect_kind(first_line.as_deref(), rest.peek().is_some());
+            lines = Box::new(first_line.map(Ok).into_iter().chain(rest));
+            kind
+        }
+        kind => kind,
+    };
+
     match cj_type {
         CJTypeKind::Normal => {
             let content = lines.collect::<Result<Vec<_>, Error>>()?.join("\n");
@@ -73,6 +104,8 @@ fn parse_cityjson<T: CityJSONReader>(mut source: T, cj_type: CJTypeKind) -> Resu
                 features: features?,
             }))
         }
+
+        CJTypeKind::Auto => unreachable!("kind is detected above"),
     }
 }
 
@@ -123,4 +156,23 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_read_auto() -> Result<(), Error> {
+        let seq = include_str!("../tests/data/small.city.jsonl");
+        match read_cityjson_from_reader(BufReader::new(seq.as_bytes()), CJTypeKind::Auto)? {
+            CJType::Seq(seq) => assert_eq!(seq.features.len(), 3),
+            CJType::Normal(_) => panic!("Expected Seq type"),
+        }
+
+        let cj = r#"{"type":"CityJSON","version":"2.0","transform":{"scale":[0.001,0.001,0.001],"translate":[0.0,0.0,0.0]},"CityObjects":{},"vertices":[]}"#;
+        let pretty = serde_json::to_string_pretty(&serde_json::from_str::<serde_json::Value>(cj)?)?;
+        for input in [cj.to_string(), pretty] {
+            match read_cityjson_from_reader(BufReader::new(input.as_bytes()), CJTypeKind::Auto)? {
+                CJType::Normal(cj) => assert_eq!(cj.version, "2.0"),
+                CJType::Seq(_) => panic!("Expected Normal type"),
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,5 +1,8 @@
 // Current version of FlatCityBuf
-pub const VERSION: u8 = 1;
+pub const VERSION: u8 = 2;
+
+// Oldest readable version. Version 1 stored 2D R-tree nodes without Z bounds, see `NodeLayout`
+pub const MIN_SUPPORTED_VERSION: u8 = 1;
 
 // Magic bytes for FlatCityBuf
 pub const MAGIC_BYTES: [u8; 8] = [b'f', b'c', b'b', VERSION, b'f', b'c', b'b', 0];
@@ -12,3 +15,12 @@ pub const MAGIC_BYTES_SIZE: usize = 8;
 
 // Size of header size
 pub const HEADER_SIZE_SIZE: usize = 4;
+
+// Size of the CRC-32 following each feature when checksums are enabled
+pub const CHECKSUM_SIZE: usize = 4;
+
+// Size of the SHA-256 content hash of the feature section
+pub const CONTENT_HASH_SIZE: usize = 32;
+
+// Reserved column name of the feature ID index
+pub const FEATURE_ID_COLUMN: &str = "__feature_id__";
@@ -4,6 +4,19 @@ use flatbuffers::InvalidFlatbuffer;
 use serde_json;
 use thiserror::Error;
 
+/// Why the magic bytes at the start of a file could not be accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum MagicBytesError {
+    #[error("Missing magic bytes in FCB file header")]
+    Missing,
+
+    #[error("FCB file format version {found} is newer than the newest supported version {max_supported}, a newer version of the library is needed")]
+    VersionTooNew { found: u8, max_supported: u8 },
+
+    #[error("FCB file format version {found} is older than the oldest supported version {min_supported}")]
+    VersionTooOld { found: u8, min_supported: u8 },
+}
+
 /// The main error type for the FCB Core library.
 /// This enum represents all possible errors that can occur during FCB operations.
 #[derive(Debug, Error)]
@@ -15,6 +28,9 @@ pub enum Error {
     #[error("Required index is missing")]
     NoIndex,
 
+    #[error("File was written without a content hash")]
+    NoContentHash,
+
     #[error("Attribute index not found")]
     AttributeIndexNotFound,
 
@@ -27,12 +43,22 @@ pub enum Error {
     #[error("Missing required field of CityJSON: {0}")]
     MissingRequiredField(String),
 
+    #[error("{0}")]
+    UnsupportedVersion(MagicBytesError),
+
     #[error("Invalid header size {0}, expected size between 8 and 1MB")]
     IllegalHeaderSize(usize),
 
     #[error("Invalid FlatBuffer format: {0}")]
     InvalidFlatbuffer(#[from] InvalidFlatbuffer),
 
+    #[error("Checksum mismatch in feature {feature_index}: expected {expected:#010x}, got {actual:#010x}")]
+    ChecksumMismatch {
+        feature_index: usize,
+        expected: u32,
+        actual: u32,
+    },
+
     // IO and serialization errors
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
@@ -50,6 +76,15 @@ pub enum Error {
     #[error("Invalid attribute value: {msg}")]
     InvalidAttributeValue { msg: String },
 
+    #[error("Invalid geometry in feature {feature_id}: {reason}")]
+    InvalidGeometry { feature_id: String, reason: String },
+
+    #[error("Invalid semantic surface hierarchy: {0}")]
+    InvalidSemanticHierarchy(String),
+
+    #[error("Global vertex table: {0}")]
+    GlobalVertices(String),
+
     // Index and query errors
     #[error("Failed to create index: {0}")]
     IndexCreationError(String),
@@ -57,6 +92,9 @@ pub enum Error {
     #[error("Failed to execute query: {0}")]
     QueryExecutionError(String),
 
+    #[error("Invalid checkpoint: {0}")]
+    InvalidCheckpoint(String),
+
     // HTTP errors (when http feature is enabled)
     #[cfg(feature = "http")]
     #[error("HTTP client error: {0}")]
@@ -80,34 +118,76 @@ pub enum Error {
         #[from]
         source: crate::static_btree::Error,
     },
+
+    // Error wrapped with what was being done, e.g. which feature was read
+    #[error("{context}: {source}")]
+    Context { source: Box<Error>, context: String },
+}
+
+impl From<MagicBytesError> for Error {
+    fn from(e: MagicBytesError) -> Self {
+        match e {
+            MagicBytesError::Missing => Error::MissingMagicBytes,
+            e => Error::UnsupportedVersion(e),
+        }
+    }
 }
 
 impl Error {
+    /// Wraps the error with a description of what was being done when it occurred
+    pub fn with_context(self, ctx: impl Into<String>) -> Error {
+        Error::Context {
+            source: Box::new(self),
+            context: ctx.into(),
+        }
+    }
+
+    /// Wraps the error with the id of the feature that triggered it
+    pub fn with_feature_id(self, id: &str) -> Error {
+        self.with_context(format!("feature {id}"))
+    }
+
+    /// Innermost error, skipping any context added with [`Error::with_context`]
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+
     /// Returns true if the error is related to IO operations
     pub fn is_io_error(&self) -> bool {
-        matches!(self, Error::IoError(_))
+        matches!(self.root_cause(), Error::IoError(_))
     }
 
     /// Returns true if the error is related to data format
     pub fn is_format_error(&self) -> bool {
         matches!(
-            self,
-            Error::MissingMagicBytes | Error::InvalidFlatbuffer(_) | Error::IllegalHeaderSize(_)
+            self.root_cause(),
+            Error::MissingMagicBytes
+                | Error::UnsupportedVersion(_)
+                | Error::InvalidFlatbuffer(_)
+                | Error::IllegalHeaderSize(_)
+                | Error::ChecksumMismatch { .. }
         )
     }
 
     /// Returns true if the error is related to validation
     pub fn is_validation_error(&self) -> bool {
         matches!(
-            self,
-            Error::UnsupportedColumnType(_) | Error::InvalidAttributeValue { .. }
+            self.root_cause(),
+            Error::UnsupportedColumnType(_)
+                | Error::InvalidAttributeValue { .. }
+                | Error::InvalidGeometry { .. }
+                | Error::InvalidSemanticHierarchy(_)
+                | Error::GlobalVertices(_)
         )
     }
 
     /// Returns true if the error is related to index or query operations
     pub fn is_index_error(&self) -> bool {
         matches!(
-            self,
+            self.root_cause(),
             Error::IndexCreationError(_) | Error::QueryExecutionError(_)
         )
     }
@@ -1237,6 +1237,7 @@ impl<'a> Column<'a> {
     pub const VT_UNIQUE: flatbuffers::VOffsetT = 20;
     pub const VT_PRIMARY_KEY: flatbuffers::VOffsetT = 22;
     pub const VT_METADATA: flatbuffers::VOffsetT = 24;
+    pub const VT_NULLABLE_INDEX: flatbuffers::VOffsetT = 26;
 
     #[inline]
     pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
@@ -1263,6 +1264,7 @@ impl<'a> Column<'a> {
             builder.add_name(x);
         }
         builder.add_index(args.index);
+        builder.add_nullable_index(args.nullable_index);
         builder.add_primary_key(args.primary_key);
         builder.add_unique(args.unique);
         builder.add_nullable(args.nullable);
@@ -1380,6 +1382,17 @@ impl<'a> Column<'a> {
                 .get::<flatbuffers::ForwardsUOffset<&str>>(Column::VT_METADATA, None)
         }
     }
+    #[inline]
+    pub fn nullable_index(&self) -> bool {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<bool>(Column::VT_NULLABLE_INDEX, Some(false))
+                .unwrap()
+        }
+    }
 }
 
 impl flatbuffers::Verifiable for Column<'_> {
@@ -1408,6 +1421,7 @@ impl flatbuffers::Verifiable for Column<'_> {
                 Self::VT_METADATA,
                 false,
             )?
+            .visit_field::<bool>("nullable_index", Self::VT_NULLABLE_INDEX, false)?
             .finish();
         Ok(())
     }
@@ -1424,6 +1438,7 @@ pub struct ColumnArgs<'a> {
     pub unique: bool,
     pub primary_key: bool,
     pub metadata: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub nullable_index: bool,
 }
 impl Default for ColumnArgs<'_> {
     #[inline]
@@ -1440,6 +1455,7 @@ impl Default for ColumnArgs<'_> {
             unique: false,
             primary_key: false,
             metadata: None,
+            nullable_index: false,
         }
     }
 }
@@ -1503,6 +1519,11 @@ impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> ColumnBuilder<'a, 'b, A> {
             .push_slot_always::<flatbuffers::WIPOffset<_>>(Column::VT_METADATA, metadata);
     }
     #[inline]
+    pub fn add_nullable_index(&mut self, nullable_index: bool) {
+        self.fbb_
+            .push_slot::<bool>(Column::VT_NULLABLE_INDEX, nullable_index, false);
+    }
+    #[inline]
     pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> ColumnBuilder<'a, 'b, A> {
         let start = _fbb.start_table();
         ColumnBuilder {
@@ -1532,6 +1553,7 @@ impl core::fmt::Debug for Column<'_> {
         ds.field("unique", &self.unique());
         ds.field("primary_key", &self.primary_key());
         ds.field("metadata", &self.metadata());
+        ds.field("nullable_index", &self.nullable_index());
         ds.finish()
     }
 }
@@ -1720,6 +1742,185 @@ impl core::fmt::Debug for ReferenceSystem<'_> {
         ds.finish()
     }
 }
+pub enum CompositeIndexOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct CompositeIndex<'a> {
+    pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for CompositeIndex<'a> {
+    type Inner = CompositeIndex<'a>;
+    #[inline]
+    unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table::new(buf, loc),
+        }
+    }
+}
+
+impl<'a> CompositeIndex<'a> {
+    pub const VT_COLUMNS: flatbuffers::VOffsetT = 4;
+    pub const VT_LENGTH: flatbuffers::VOffsetT = 6;
+    pub const VT_BRANCHING_FACTOR: flatbuffers::VOffsetT = 8;
+    pub const VT_NUM_UNIQUE_ITEMS: flatbuffers::VOffsetT = 10;
+
+    #[inline]
+    pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        CompositeIndex { _tab: table }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+        args: &'args CompositeIndexArgs<'args>,
+    ) -> flatbuffers::WIPOffset<CompositeIndex<'bldr>> {
+        let mut builder = CompositeIndexBuilder::new(_fbb);
+        builder.add_num_unique_items(args.num_unique_items);
+        builder.add_length(args.length);
+        if let Some(x) = args.columns {
+            builder.add_columns(x);
+        }
+        builder.add_branching_factor(args.branching_factor);
+        builder.finish()
+    }
+
+    #[inline]
+    pub fn columns(&self) -> Option<flatbuffers::Vector<'a, u16>> {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u16>>>(
+                    CompositeIndex::VT_COLUMNS,
+                    None,
+                )
+        }
+    }
+    #[inline]
+    pub fn length(&self) -> u32 {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<u32>(CompositeIndex::VT_LENGTH, Some(0))
+                .unwrap()
+        }
+    }
+    #[inline]
+    pub fn branching_factor(&self) -> u16 {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<u16>(CompositeIndex::VT_BRANCHING_FACTOR, Some(0))
+                .unwrap()
+        }
+    }
+    #[inline]
+    pub fn num_unique_items(&self) -> u32 {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<u32>(CompositeIndex::VT_NUM_UNIQUE_ITEMS, Some(0))
+                .unwrap()
+        }
+    }
+}
+
+impl flatbuffers::Verifiable for CompositeIndex<'_> {
+    #[inline]
+    fn run_verifier(
+        v: &mut flatbuffers::Verifier,
+        pos: usize,
+    ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+        v.visit_table(pos)?
+            .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, u16>>>(
+                "columns",
+                Self::VT_COLUMNS,
+                false,
+            )?
+            .visit_field::<u32>("length", Self::VT_LENGTH, false)?
+            .visit_field::<u16>("branching_factor", Self::VT_BRANCHING_FACTOR, false)?
+            .visit_field::<u32>("num_unique_items", Self::VT_NUM_UNIQUE_ITEMS, false)?
+            .finish();
+        Ok(())
+    }
+}
+pub struct CompositeIndexArgs<'a> {
+    pub columns: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u16>>>,
+    pub length: u32,
+    pub branching_factor: u16,
+    pub num_unique_items: u32,
+}
+impl Default for CompositeIndexArgs<'_> {
+    #[inline]
+    fn default() -> Self {
+        CompositeIndexArgs {
+            columns: None,
+            length: 0,
+            branching_factor: 0,
+            num_unique_items: 0,
+        }
+    }
+}
+
+pub struct CompositeIndexBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+    fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+    start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> CompositeIndexBuilder<'a, 'b, A> {
+    #[inline]
+    pub fn add_columns(&mut self, columns: flatbuffers::WIPOffset<flatbuffers::Vector<'b, u16>>) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(CompositeIndex::VT_COLUMNS, columns);
+    }
+    #[inline]
+    pub fn add_length(&mut self, length: u32) {
+        self.fbb_
+            .push_slot::<u32>(CompositeIndex::VT_LENGTH, length, 0);
+    }
+    #[inline]
+    pub fn add_branching_factor(&mut self, branching_factor: u16) {
+        self.fbb_
+            .push_slot::<u16>(CompositeIndex::VT_BRANCHING_FACTOR, branching_factor, 0);
+    }
+    #[inline]
+    pub fn add_num_unique_items(&mut self, num_unique_items: u32) {
+        self.fbb_
+            .push_slot::<u32>(CompositeIndex::VT_NUM_UNIQUE_ITEMS, num_unique_items, 0);
+    }
+    #[inline]
+    pub fn new(
+        _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+    ) -> CompositeIndexBuilder<'a, 'b, A> {
+        let start = _fbb.start_table();
+        CompositeIndexBuilder {
+            fbb_: _fbb,
+            start_: start,
+        }
+    }
+    #[inline]
+    pub fn finish(self) -> flatbuffers::WIPOffset<CompositeIndex<'a>> {
+        let o = self.fbb_.end_table(self.start_);
+        flatbuffers::WIPOffset::new(o.value())
+    }
+}
+
+impl core::fmt::Debug for CompositeIndex<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut ds = f.debug_struct("CompositeIndex");
+        ds.field("columns", &self.columns());
+        ds.field("length", &self.length());
+        ds.field("branching_factor", &self.branching_factor());
+        ds.field("num_unique_items", &self.num_unique_items());
+        ds.finish()
+    }
+}
 pub enum MaterialOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
@@ -2468,6 +2669,117 @@ impl core::fmt::Debug for Appearance<'_> {
         ds.finish()
     }
 }
+pub enum GlobalVerticesOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct GlobalVertices<'a> {
+    pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for GlobalVertices<'a> {
+    type Inner = GlobalVertices<'a>;
+    #[inline]
+    unsafe fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table::new(buf, loc),
+        }
+    }
+}
+
+impl<'a> GlobalVertices<'a> {
+    pub const VT_VERTICES: flatbuffers::VOffsetT = 4;
+
+    #[inline]
+    pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        GlobalVertices { _tab: table }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr, A: flatbuffers::Allocator + 'bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr, A>,
+        args: &'args GlobalVerticesArgs<'args>,
+    ) -> flatbuffers::WIPOffset<GlobalVertices<'bldr>> {
+        let mut builder = GlobalVerticesBuilder::new(_fbb);
+        if let Some(x) = args.vertices {
+            builder.add_vertices(x);
+        }
+        builder.finish()
+    }
+
+    #[inline]
+    pub fn vertices(&self) -> Option<flatbuffers::Vector<'a, i32>> {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, i32>>>(
+                    GlobalVertices::VT_VERTICES,
+                    None,
+                )
+        }
+    }
+}
+
+impl flatbuffers::Verifiable for GlobalVertices<'_> {
+    #[inline]
+    fn run_verifier(
+        v: &mut flatbuffers::Verifier,
+        pos: usize,
+    ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+        v.visit_table(pos)?
+            .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, i32>>>(
+                "vertices",
+                Self::VT_VERTICES,
+                false,
+            )?
+            .finish();
+        Ok(())
+    }
+}
+pub struct GlobalVerticesArgs<'a> {
+    pub vertices: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, i32>>>,
+}
+impl Default for GlobalVerticesArgs<'_> {
+    #[inline]
+    fn default() -> Self {
+        GlobalVerticesArgs { vertices: None }
+    }
+}
+
+pub struct GlobalVerticesBuilder<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> {
+    fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+    start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> GlobalVerticesBuilder<'a, 'b, A> {
+    #[inline]
+    pub fn add_vertices(&mut self, vertices: flatbuffers::WIPOffset<flatbuffers::Vector<'b, i32>>) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(GlobalVertices::VT_VERTICES, vertices);
+    }
+    #[inline]
+    pub fn new(
+        _fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>,
+    ) -> GlobalVerticesBuilder<'a, 'b, A> {
+        let start = _fbb.start_table();
+        GlobalVerticesBuilder {
+            fbb_: _fbb,
+            start_: start,
+        }
+    }
+    #[inline]
+    pub fn finish(self) -> flatbuffers::WIPOffset<GlobalVertices<'a>> {
+        let o = self.fbb_.end_table(self.start_);
+        flatbuffers::WIPOffset::new(o.value())
+    }
+}
+
+impl core::fmt::Debug for GlobalVertices<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut ds = f.debug_struct("GlobalVertices");
+        ds.field("vertices", &self.vertices());
+        ds.finish()
+    }
+}
 pub enum HeaderOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
@@ -2514,6 +2826,11 @@ impl<'a> Header<'a> {
     pub const VT_POC_ADDRESS_COUNTRY: flatbuffers::VOffsetT = 54;
     pub const VT_ATTRIBUTES: flatbuffers::VOffsetT = 56;
     pub const VT_VERSION: flatbuffers::VOffsetT = 58;
+    pub const VT_COMPOSITE_INDEX: flatbuffers::VOffsetT = 60;
+    pub const VT_CHECKSUM: flatbuffers::VOffsetT = 62;
+    pub const VT_SHARED_VERTICES: flatbuffers::VOffsetT = 64;
+    pub const VT_CONTENT_HASH: flatbuffers::VOffsetT = 66;
+    pub const VT_GLOBAL_VERTICES: flatbuffers::VOffsetT = 68;
 
     #[inline]
     pub unsafe fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
@@ -2526,6 +2843,15 @@ impl<'a> Header<'a> {
     ) -> flatbuffers::WIPOffset<Header<'bldr>> {
         let mut builder = HeaderBuilder::new(_fbb);
         builder.add_features_count(args.features_count);
+        if let Some(x) = args.global_vertices {
+            builder.add_global_vertices(x);
+        }
+        if let Some(x) = args.content_hash {
+            builder.add_content_hash(x);
+        }
+        if let Some(x) = args.composite_index {
+            builder.add_composite_index(x);
+        }
         if let Some(x) = args.version {
             builder.add_version(x);
         }
@@ -2605,6 +2931,8 @@ impl<'a> Header<'a> {
             builder.add_transform(x);
         }
         builder.add_index_node_size(args.index_node_size);
+        builder.add_shared_vertices(args.shared_vertices);
+        builder.add_checksum(args.checksum);
         builder.finish()
     }
 
@@ -2916,6 +3244,67 @@ impl<'a> Header<'a> {
                 .unwrap()
         }
     }
+    #[inline]
+    pub fn composite_index(
+        &self,
+    ) -> Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<CompositeIndex<'a>>>> {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab.get::<flatbuffers::ForwardsUOffset<
+                flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<CompositeIndex>>,
+            >>(Header::VT_COMPOSITE_INDEX, None)
+        }
+    }
+    #[inline]
+    pub fn checksum(&self) -> bool {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<bool>(Header::VT_CHECKSUM, Some(false))
+                .unwrap()
+        }
+    }
+    #[inline]
+    pub fn shared_vertices(&self) -> bool {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<bool>(Header::VT_SHARED_VERTICES, Some(false))
+                .unwrap()
+        }
+    }
+    #[inline]
+    pub fn content_hash(&self) -> Option<flatbuffers::Vector<'a, u8>> {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(
+                    Header::VT_CONTENT_HASH,
+                    None,
+                )
+        }
+    }
+    #[inline]
+    pub fn global_vertices(&self) -> Option<GlobalVertices<'a>> {
+        // Safety:
+        // Created from valid Table for this object
+        // which contains a valid value in this slot
+        unsafe {
+            self._tab
+                .get::<flatbuffers::ForwardsUOffset<GlobalVertices>>(
+                    Header::VT_GLOBAL_VERTICES,
+                    None,
+                )
+        }
+    }
 }
 
 impl flatbuffers::Verifiable for Header<'_> {
@@ -3037,6 +3426,21 @@ impl flatbuffers::Verifiable for Header<'_> {
                 false,
             )?
             .visit_field::<flatbuffers::ForwardsUOffset<&str>>("version", Self::VT_VERSION, true)?
+            .visit_field::<flatbuffers::ForwardsUOffset<
+                flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<CompositeIndex>>,
+            >>("composite_index", Self::VT_COMPOSITE_INDEX, false)?
+            .visit_field::<bool>("checksum", Self::VT_CHECKSUM, false)?
+            .visit_field::<bool>("shared_vertices", Self::VT_SHARED_VERTICES, false)?
+            .visit_field::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'_, u8>>>(
+                "content_hash",
+                Self::VT_CONTENT_HASH,
+                false,
+            )?
+            .visit_field::<flatbuffers::ForwardsUOffset<GlobalVertices>>(
+                "global_vertices",
+                Self::VT_GLOBAL_VERTICES,
+                false,
+            )?
             .finish();
         Ok(())
     }
@@ -3080,6 +3484,15 @@ pub struct HeaderArgs<'a> {
     pub poc_address_country: Option<flatbuffers::WIPOffset<&'a str>>,
     pub attributes: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u8>>>,
     pub version: Option<flatbuffers::WIPOffset<&'a str>>,
+    pub composite_index: Option<
+        flatbuffers::WIPOffset<
+            flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<CompositeIndex<'a>>>,
+        >,
+    >,
+    pub checksum: bool,
+    pub shared_vertices: bool,
+    pub content_hash: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a, u8>>>,
+    pub global_vertices: Option<flatbuffers::WIPOffset<GlobalVertices<'a>>>,
 }
 impl Default for HeaderArgs<'_> {
     #[inline]
@@ -3113,6 +3526,11 @@ impl Default for HeaderArgs<'_> {
             poc_address_country: None,
             attributes: None,
             version: None, // required field
+            composite_index: None,
+            checksum: false,
+            shared_vertices: false,
+            content_hash: None,
+            global_vertices: None,
         }
     }
 }
@@ -3340,6 +3758,47 @@ impl<'a: 'b, 'b, A: flatbuffers::Allocator + 'a> HeaderBuilder<'a, 'b, A> {
             .push_slot_always::<flatbuffers::WIPOffset<_>>(Header::VT_VERSION, version);
     }
     #[inline]
+    pub fn add_composite_index(
+        &mut self,
+        composite_index: flatbuffers::WIPOffset<
+            flatbuffers::Vector<'b, flatbuffers::ForwardsUOffset<CompositeIndex<'b>>>,
+        >,
+    ) {
+        self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(
+            Header::VT_COMPOSITE_INDEX,
+            composite_index,
+        );
+    }
+    #[inline]
+    pub fn add_checksum(&mut self, checksum: bool) {
+        self.fbb_
+            .push_slot::<bool>(Header::VT_CHECKSUM, checksum, false);
+    }
+    #[inline]
+    pub fn add_shared_vertices(&mut self, shared_vertices: bool) {
+        self.fbb_
+            .push_slot::<bool>(Header::VT_SHARED_VERTICES, shared_vertices, false);
+    }
+    #[inline]
+    pub fn add_content_hash(
+        &mut self,
+        content_hash: flatbuffers::WIPOffset<flatbuffers::Vector<'b, u8>>,
+    ) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(Header::VT_CONTENT_HASH, content_hash);
+    }
+    #[inline]
+    pub fn add_global_vertices(
+        &mut self,
+        global_vertices: flatbuffers::WIPOffset<GlobalVertices<'b>>,
+    ) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<GlobalVertices>>(
+                Header::VT_GLOBAL_VERTICES,
+                global_vertices,
+            );
+    }
+    #[inline]
     pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a, A>) -> HeaderBuilder<'a, 'b, A> {
         let start = _fbb.start_table();
         HeaderBuilder {
@@ -3392,6 +3851,11 @@ impl core::fmt::Debug for Header<'_> {
         ds.field("poc_address_country", &self.poc_address_country());
         ds.field("attributes", &self.attributes());
         ds.field("version", &self.version());
+        ds.field("composite_index", &self.composite_index());
+        ds.field("checksum", &self.checksum());
+        ds.field("shared_vertices", &self.shared_vertices());
+        ds.field("content_hash", &self.content_hash());
+        ds.field("global_vertices", &self.global_vertices());
         ds.finish()
     }
 }
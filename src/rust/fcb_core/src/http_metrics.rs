@@ -0,0 +1,135 @@
+use bytes::Bytes;
+use http_range_client::{AsyncBufferedHttpRangeClient, AsyncHttpRangeClient};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Counters of the requests issued by an HTTP reader.
+///
+/// Requests are counted by the [`CountingClient`] of the reader, so every range and HEAD
+/// request reaching the server is included, also those of index searches. Reads of the
+/// reader served from the buffer of the HTTP client count as cache hits.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpMetrics {
+    /// Number of range requests sent to the server
+    pub request_count: u64,
+    /// Number of HEAD requests sent to the server
+    pub head_request_count: u64,
+    /// Number of bytes read by the reader
+    pub bytes_requested: u64,
+    /// Number of bytes received from the server
+    pub bytes_received: u64,
+    /// Number of reads served from the client buffer
+    pub cache_hits: u64,
+}
+
+/// Counters shared by a [`CountingClient`] and the reader using it
+#[derive(Debug, Default)]
+pub struct HttpCounters {
+    request_count: AtomicU64,
+    head_request_count: AtomicU64,
+    bytes_requested: AtomicU64,
+    bytes_received: AtomicU64,
+    cache_hits: AtomicU64,
+}
+
+impl HttpCounters {
+    /// Current values of the counters
+    pub fn snapshot(&self) -> HttpMetrics {
+        HttpMetrics {
+            request_count: self.request_count.load(Ordering::Relaxed),
+            head_request_count: self.head_request_count.load(Ordering::Relaxed),
+            bytes_requested: self.bytes_requested.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record_request(&self, bytes_received: usize) {
+        self.request_count.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes_received as u64, Ordering::Relaxed);
+    }
+
+    fn record_head_request(&self) {
+        self.head_request_count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// HTTP client counting the requests sent by the wrapped client, see [`HttpMetrics`]
+pub struct CountingClient<T: AsyncHttpRangeClient> {
+    inner: T,
+    counters: Arc<HttpCounters>,
+}
+
+impl<T: AsyncHttpRangeClient> CountingClient<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            counters: Arc::default(),
+        }
+    }
+
+    /// Counters of the client, shared with the reader to count its reads
+    pub fn counters(&self) -> Arc<HttpCounters> {
+        self.counters.clone()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+impl<T: AsyncHttpRangeClient + Send + Sync> AsyncHttpRangeClient for CountingClient<T> {
+    async fn get_range(&self, url: &str, range: &str) -> http_range_client::Result<Bytes> {
+        let bytes = self.inner.get_range(url, range).await?;
+        self.counters.record_request(bytes.len());
+        Ok(bytes)
+    }
+
+    async fn head_response_header(
+        &self,
+        url: &str,
+        header: &str,
+    ) -> http_range_client::Result<Option<String>> {
+        self.counters.record_head_request();
+        self.inner.head_response_header(url, header).await
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[async_trait::async_trait(?Send)]
+impl<T: AsyncHttpRangeClient> AsyncHttpRangeClient for CountingClient<T> {
+    async fn get_range(&self, url: &str, range: &str) -> http_range_client::Result<Bytes> {
+        let bytes = self.inner.get_range(url, range).await?;
+        self.counters.record_request(bytes.len());
+        Ok(bytes)
+    }
+
+    async fn head_response_header(
+        &self,
+        url: &str,
+        header: &str,
+    ) -> http_range_client::Result<Option<String>> {
+        self.counters.record_head_request();
+        self.inner.head_response_header(url, header).await
+    }
+}
+
+/// Read `length` bytes at `begin` through the buffered `client`, counting the read as a
+/// cache hit if it didn't need a request
+pub async fn counted_get_range<'a, T: AsyncHttpRangeClient>(
+    client: &'a mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+    counters: &HttpCounters,
+    begin: usize,
+    length: usize,
+) -> http_range_client::Result<&'a [u8]> {
+    let requests = counters.request_count.load(Ordering::Relaxed);
+    let bytes = client.get_range(begin, length).await?;
+    counters
+        .bytes_requested
+        .fetch_add(length as u64, Ordering::Relaxed);
+    if counters.request_count.load(Ordering::Relaxed) == requests {
+        counters.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+    Ok(bytes)
+}
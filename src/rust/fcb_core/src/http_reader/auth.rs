@@ -0,0 +1,174 @@
+use super::client::DefaultHttpClient;
+use crate::error::{Error, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use http_range_client::HttpError;
+
+/// Credentials sent with every range request
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// `Authorization: Bearer <token>`
+    Bearer(String),
+    /// `Authorization: Basic <base64(username:password)>`
+    Basic { username: String, password: String },
+}
+
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print credentials
+        match self {
+            Auth::Bearer(_) => f.write_str("Auth::Bearer(***)"),
+            Auth::Basic { username, .. } => write!(f, "Auth::Basic {{ username: {username} }}"),
+        }
+    }
+}
+
+impl Auth {
+    /// Value of the `Authorization` header
+    pub fn header_value(&self) -> String {
+        match self {
+            Auth::Bearer(token) => format!("Bearer {token}"),
+            Auth::Basic { username, password } => {
+                format!("Basic {}", BASE64.encode(format!("{username}:{password}")))
+            }
+        }
+    }
+
+    /// Build a client which sends the credentials as a default header
+    #[cfg(feature = "runtime-tokio")]
+    pub(crate) fn client(&self) -> Result<DefaultHttpClient> {
+        use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+
+        let mut value = HeaderValue::from_str(&self.header_value())
+            .map_err(|e| Error::HttpClient(HttpError::HttpError(e.to_string())))?;
+        value.set_sensitive(true);
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, value);
+        reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .map_err(|e| Error::HttpClient(HttpError::HttpError(e.to_string())))
+    }
+
+    /// Build a client which sends the credentials as a default header
+    #[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+    pub(crate) fn client(&self) -> Result<DefaultHttpClient> {
+        let config = surf::Config::new()
+            .add_header(surf::http::headers::AUTHORIZATION, self.header_value())
+            .map_err(|e| Error::HttpClient(HttpError::HttpError(e.to_string())))?;
+        let client: surf::Client = config
+            .try_into()
+            .map_err(|e| Error::HttpClient(HttpError::HttpError(format!("{e:?}"))))?;
+        Ok(client.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_reader::HttpFcbReader;
+    use crate::{
+        header_writer::HeaderWriterOptions, read_cityjson_from_reader, CJType, CJTypeKind,
+        FcbWriter,
+    };
+    use std::{fs::File, io::BufReader, path::PathBuf};
+
+    #[test]
+    fn header_values() {
+        assert_eq!(Auth::Bearer("abc".into()).header_value(), "Bearer abc");
+        let basic = Auth::Basic {
+            username: "Aladdin".into(),
+            password: "open sesame".into(),
+        };
+        assert_eq!(basic.header_value(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+    }
+
+    /// Answers a single request with an empty body, returning its `Authorization` header
+    #[cfg(feature = "runtime-tokio")]
+    fn serve_one_request(
+        listener: std::net::TcpListener,
+    ) -> std::thread::JoinHandle<Option<String>> {
+        use std::io::{BufRead, Write};
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut authorization = None;
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("authorization") {
+                        authorization = Some(value.trim().to_string());
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .unwrap();
+            authorization
+        })
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[tokio::test]
+    async fn client_sends_authorization_header() -> Result<()> {
+        let auth = Auth::Basic {
+            username: "Aladdin".into(),
+            password: "open sesame".into(),
+        };
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/data.fcb", listener.local_addr()?);
+        let server = serve_one_request(listener);
+
+        let response = auth.client()?.get(&url).send().await.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(
+            server.join().unwrap().as_deref(),
+            Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requests_carry_authorization_header() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input = File::open(manifest_dir.join("tests/data/small.city.jsonl"))?;
+        let CJType::Seq(seq) = read_cityjson_from_reader(BufReader::new(input), CJTypeKind::Seq)?
+        else {
+            panic!("expected cityjsonseq");
+        };
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..Default::default()
+            }),
+            None,
+            None,
+        )?;
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let tmp = tempfile::NamedTempFile::new()?;
+        fcb.write(tmp.reopen()?)?;
+
+        let auth = Auth::Bearer("secret".into());
+        let (reader, stats) =
+            HttpFcbReader::mock_from_file_with_auth(tmp.path().to_str().unwrap(), auth.clone())
+                .await?;
+        let mut iter = reader.select_all().await?;
+        while iter.next().await?.is_some() {}
+
+        let stats = stats.read().unwrap();
+        assert!(stats.request_count > 0);
+        assert!(stats
+            .authorization_headers
+            .iter()
+            .all(|h| h.as_deref() == Some("Bearer secret")));
+        Ok(())
+    }
+}
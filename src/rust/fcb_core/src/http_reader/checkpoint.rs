@@ -0,0 +1,99 @@
+//! Serializable iteration state, to resume an [`AsyncFeatureIter`](super::AsyncFeatureIter)
+//! e.g. after a browser tab was suspended
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::crc32;
+use crate::error::{Error, Result};
+
+/// Version of the checkpoint format, checkpoints of other versions are rejected
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// State of an [`AsyncFeatureIter`](super::AsyncFeatureIter), created with
+/// [`AsyncFeatureIter::checkpoint`](super::AsyncFeatureIter::checkpoint) and resumed with
+/// [`HttpFcbReader::resume_from_checkpoint`](super::HttpFcbReader::resume_from_checkpoint).
+///
+/// The spatial and attribute indices are fully searched when the selection is made, so the
+/// byte ranges of the features not read yet are all that is needed to continue a query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    /// CRC-32 of the dataset URL, `None` if the reader was created without a URL
+    pub url_hash: Option<u32>,
+    /// CRC-32 of the header, so that a dataset replaced at the same URL is detected
+    pub header_hash: u32,
+    /// Number of features already returned
+    pub features_read: usize,
+    /// Number of feature bytes already returned
+    pub bytes_read: u64,
+    /// Features not returned yet, including the prefetched ones
+    pub remaining: RemainingFeatures,
+}
+
+/// Features left to read, as `(start, end)` byte ranges in the file. The end is unknown for
+/// the last feature of the file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemainingFeatures {
+    /// All features, read one after the other from `pos`
+    All { features_left: u64, pos: usize },
+    /// Features of a spatial query, grouped in the batches fetched with combined requests
+    Batches {
+        batches: Vec<Vec<(usize, Option<usize>)>>,
+    },
+    /// Features of an attribute query
+    Ranges { ranges: Vec<(usize, Option<usize>)> },
+}
+
+impl RemainingFeatures {
+    /// Number of features left
+    pub fn len(&self) -> usize {
+        match self {
+            RemainingFeatures::All { features_left, .. } => *features_left as usize,
+            RemainingFeatures::Batches { batches } => batches.iter().map(Vec::len).sum(),
+            RemainingFeatures::Ranges { ranges } => ranges.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Checkpoint {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Checkpoint> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Checks that the checkpoint was made by this version of the library, for the dataset at
+    /// `url` with the given header
+    pub(super) fn verify(&self, url: Option<&str>, header_buf: &[u8]) -> Result<()> {
+        if self.version != CHECKPOINT_VERSION {
+            return Err(Error::InvalidCheckpoint(format!(
+                "version {} is not supported, expected {}",
+                self.version, CHECKPOINT_VERSION
+            )));
+        }
+        if let (Some(expected), Some(url)) = (self.url_hash, url) {
+            if url_hash(url) != expected {
+                return Err(Error::InvalidCheckpoint(format!(
+                    "made for another dataset than {url}"
+                )));
+            }
+        }
+        if crc32(header_buf) != self.header_hash {
+            return Err(Error::InvalidCheckpoint(
+                "the dataset has changed since the checkpoint was made".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn url_hash(url: &str) -> u32 {
+    crc32(url.as_bytes())
+}
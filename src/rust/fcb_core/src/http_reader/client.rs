@@ -0,0 +1,118 @@
+//! HTTP client and timer of the async runtime, selected by the `runtime-tokio` (default) or
+//! `runtime-async-std` feature. With both enabled, tokio is used.
+
+use std::time::Duration;
+
+#[cfg(not(any(feature = "runtime-tokio", feature = "runtime-async-std")))]
+compile_error!("the `http` feature requires either `runtime-tokio` or `runtime-async-std`");
+
+/// Client sending the range requests of [`HttpFcbReader::open`](super::HttpFcbReader::open)
+/// and the other constructors taking a URL
+#[cfg(feature = "runtime-tokio")]
+pub type DefaultHttpClient = reqwest::Client;
+
+/// Client sending the range requests of [`HttpFcbReader::open`](super::HttpFcbReader::open)
+/// and the other constructors taking a URL
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub type DefaultHttpClient = surf_client::SurfClient;
+
+#[cfg(feature = "runtime-tokio")]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub(crate) async fn sleep(duration: Duration) {
+    async_std::task::sleep(duration).await
+}
+
+#[cfg(feature = "runtime-async-std")]
+pub mod surf_client {
+    use crate::http_reader::etag::{conditional_range, ConditionalHttpClient, NotModified};
+    use bytes::Bytes;
+    use either::Either;
+    use http_range_client::{AsyncHttpRangeClient, HttpError};
+
+    /// Range requests sent with a `surf::Client`, which runs on async-std
+    #[derive(Debug, Clone)]
+    pub struct SurfClient(surf::Client);
+
+    impl SurfClient {
+        pub fn new() -> Self {
+            Self(surf::Client::new())
+        }
+    }
+
+    impl Default for SurfClient {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl From<surf::Client> for SurfClient {
+        fn from(client: surf::Client) -> Self {
+            Self(client)
+        }
+    }
+
+    fn http_error(err: surf::Error) -> HttpError {
+        HttpError::HttpError(err.to_string())
+    }
+
+    #[async_trait::async_trait]
+    impl AsyncHttpRangeClient for SurfClient {
+        async fn get_range(&self, url: &str, range: &str) -> http_range_client::Result<Bytes> {
+            let mut response = self
+                .0
+                .get(url)
+                .header("Range", range)
+                .await
+                .map_err(http_error)?;
+            if !response.status().is_success() {
+                return Err(HttpError::HttpStatus(response.status() as u16));
+            }
+            let body = response.body_bytes().await.map_err(http_error)?;
+            Ok(Bytes::from(body))
+        }
+
+        async fn head_response_header(
+            &self,
+            url: &str,
+            header: &str,
+        ) -> http_range_client::Result<Option<String>> {
+            let response = self.0.head(url).await.map_err(http_error)?;
+            if !response.status().is_success() {
+                return Err(HttpError::HttpStatus(response.status() as u16));
+            }
+            Ok(response
+                .header(header)
+                .map(|values| values.as_str().to_string()))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ConditionalHttpClient for SurfClient {
+        async fn revalidate(
+            &self,
+            url: &str,
+            cached_etag: Option<&str>,
+        ) -> http_range_client::Result<Either<Option<String>, NotModified>> {
+            let mut request = self.0.get(url).header("Range", conditional_range());
+            if let Some(etag) = cached_etag {
+                request = request.header("If-None-Match", etag);
+            }
+            let response = request.await.map_err(http_error)?;
+            if response.status() == surf::StatusCode::NotModified {
+                return Ok(Either::Right(NotModified));
+            }
+            if !response.status().is_success() {
+                return Err(HttpError::HttpStatus(response.status() as u16));
+            }
+            Ok(Either::Left(
+                response
+                    .header("ETag")
+                    .map(|values| values.as_str().to_string()),
+            ))
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use super::client::DefaultHttpClient;
+use crate::error::{Error, Result};
+use http_range_client::HttpError;
+use std::time::Duration;
+
+/// Connection settings of the client sending the range requests.
+///
+/// A reader sends all its requests through one client, which keeps connections to the server
+/// alive and reuses them, so traversing the indices doesn't pay a new handshake per request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HttpConfig {
+    max_idle_connections: Option<usize>,
+    connection_timeout: Option<Duration>,
+}
+
+impl HttpConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of idle connections kept alive per host for reuse. `0` disables reuse.
+    ///
+    /// This doesn't limit the number of concurrent requests, only how many connections are
+    /// kept open once they are done.
+    pub fn max_idle_connections(mut self, n: usize) -> Self {
+        self.max_idle_connections = Some(n);
+        self
+    }
+
+    /// Timeout for establishing a connection, requests themselves are not limited
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = Some(timeout);
+        self
+    }
+
+    /// Build a client with these settings, e.g. to pass to
+    /// [`HttpFcbReader::with_retry`](super::HttpFcbReader::with_retry)
+    #[cfg(feature = "runtime-tokio")]
+    pub fn build_client(&self) -> Result<DefaultHttpClient> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(n) = self.max_idle_connections {
+            builder = builder.pool_max_idle_per_host(n);
+        }
+        if let Some(timeout) = self.connection_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        builder
+            .build()
+            .map_err(|e| Error::HttpClient(HttpError::HttpError(e.to_string())))
+    }
+
+    /// Build a client with these settings, e.g. to pass to
+    /// [`HttpFcbReader::with_retry`](super::HttpFcbReader::with_retry).
+    ///
+    /// surf limits the number of connections per host rather than the idle ones, so
+    /// `max_idle_connections` also limits the concurrent requests. Its timeout covers the whole
+    /// request rather than only establishing the connection.
+    #[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+    pub fn build_client(&self) -> Result<DefaultHttpClient> {
+        let mut config = surf::Config::new();
+        if let Some(n) = self.max_idle_connections {
+            config = config.set_max_connections_per_host(n);
+        }
+        if self.connection_timeout.is_some() {
+            config = config.set_timeout(self.connection_timeout);
+        }
+        let client: surf::Client = config
+            .try_into()
+            .map_err(|e| Error::HttpClient(HttpError::HttpError(format!("{e:?}"))))?;
+        Ok(client.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_client_with_config() -> Result<()> {
+        let config = HttpConfig::new()
+            .max_idle_connections(4)
+            .connection_timeout(Duration::from_secs(5));
+        assert_eq!(config.max_idle_connections, Some(4));
+        assert_eq!(config.connection_timeout, Some(Duration::from_secs(5)));
+        config.build_client()?;
+        HttpConfig::default().build_client()?;
+        Ok(())
+    }
+
+    /// Serves `ok` to every request on 127.0.0.1, returning the URL of the server and the
+    /// number of connections it accepted
+    #[cfg(feature = "runtime-tokio")]
+    fn serve_keep_alive() -> Result<(String, std::sync::Arc<std::sync::atomic::AtomicUsize>)> {
+        use std::io::{BufRead, BufReader, Write};
+        use std::sync::{atomic::Ordering, Arc};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/data.fcb", listener.local_addr()?);
+        let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let accepted = connections.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                accepted.fetch_add(1, Ordering::SeqCst);
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    loop {
+                        // headers of the next request on this connection, until the blank line
+                        let mut line = String::new();
+                        loop {
+                            line.clear();
+                            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                                return;
+                            }
+                            if line.trim_end().is_empty() {
+                                break;
+                            }
+                        }
+                        let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok";
+                        if stream.write_all(response).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        Ok((url, connections))
+    }
+
+    #[cfg(feature = "runtime-tokio")]
+    #[tokio::test]
+    async fn connections_are_reused() -> Result<()> {
+        use std::sync::atomic::Ordering;
+
+        let send = |client: DefaultHttpClient, url: String| async move {
+            for _ in 0..3 {
+                let body = client.get(&url).send().await.unwrap().text().await.unwrap();
+                assert_eq!(body, "ok");
+            }
+        };
+
+        let (url, connections) = serve_keep_alive()?;
+        send(HttpConfig::new().build_client()?, url).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        let (url, connections) = serve_keep_alive()?;
+        send(
+            HttpConfig::new().max_idle_connections(0).build_client()?,
+            url,
+        )
+        .await;
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+}
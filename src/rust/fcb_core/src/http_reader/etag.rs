@@ -0,0 +1,64 @@
+//! Conditional requests revalidating a cached copy of a dataset, see
+//! [`HttpFcbReader::open_with_etag`](super::HttpFcbReader::open_with_etag)
+
+use crate::MAGIC_BYTES_SIZE;
+use either::Either;
+use http_range_client::{AsyncHttpRangeClient, HttpError};
+
+/// The dataset did not change since the cached ETag was received, the cached results are
+/// still valid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotModified;
+
+/// Range of the conditional request, only the magic bytes are fetched
+pub(crate) fn conditional_range() -> String {
+    format!("bytes=0-{}", MAGIC_BYTES_SIZE - 1)
+}
+
+/// Client able to send a conditional range request with `If-None-Match`
+#[async_trait::async_trait]
+pub trait ConditionalHttpClient: AsyncHttpRangeClient {
+    /// Requests the magic bytes of `url`, with `If-None-Match: <cached_etag>` when an ETag is
+    /// given. Returns `Right(NotModified)` on `304 Not Modified`, otherwise the ETag of the
+    /// current version of the dataset, if the server sends one.
+    async fn revalidate(
+        &self,
+        url: &str,
+        cached_etag: Option<&str>,
+    ) -> http_range_client::Result<Either<Option<String>, NotModified>>;
+}
+
+#[cfg(feature = "runtime-tokio")]
+#[async_trait::async_trait]
+impl ConditionalHttpClient for reqwest::Client {
+    async fn revalidate(
+        &self,
+        url: &str,
+        cached_etag: Option<&str>,
+    ) -> http_range_client::Result<Either<Option<String>, NotModified>> {
+        use reqwest::header::{ETAG, IF_NONE_MATCH, RANGE};
+        use reqwest::StatusCode;
+
+        let mut request = self.get(url).header(RANGE, conditional_range());
+        if let Some(etag) = cached_etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| HttpError::HttpError(e.to_string()))?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Either::Right(NotModified));
+        }
+        if !response.status().is_success() {
+            return Err(HttpError::HttpStatus(response.status().as_u16()));
+        }
+        Ok(Either::Left(
+            response
+                .headers()
+                .get(ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+        ))
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::Result;
-use crate::http_reader::HttpFcbReader;
+use crate::http_reader::{Auth, ConditionalHttpClient, HttpFcbReader, NotModified};
 use bytes::Bytes;
+use either::Either;
 use http_range_client::{self, AsyncHttpRangeClient};
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
@@ -21,8 +22,40 @@ impl HttpFcbReader<MockHttpRangeClient> {
 
         let stats = Arc::new(RwLock::new(RequestStats::new()));
         let http_client = MockHttpRangeClient::new(path, stats.clone());
-        let client = http_range_client::AsyncBufferedHttpRangeClient::with(http_client, path);
-        Ok((Self::_open(client).await?, stats))
+        Ok((Self::_open(http_client, path).await?, stats))
+    }
+
+    /// Like `mock_from_file`, but every request carries the `Authorization` header of `auth`.
+    pub async fn mock_from_file_with_auth(
+        path: &str,
+        auth: Auth,
+    ) -> Result<(
+        HttpFcbReader<MockHttpRangeClient>,
+        Arc<RwLock<RequestStats>>,
+    )> {
+        let stats = Arc::new(RwLock::new(RequestStats::new()));
+        let mut http_client = MockHttpRangeClient::new(path, stats.clone());
+        http_client.authorization = Some(auth.header_value());
+        Ok((Self::_open(http_client, path).await?, stats))
+    }
+
+    /// Like `mock_from_file`, but opened with `new_with_etag` from a server whose current
+    /// version of the file has `server_etag`.
+    pub async fn mock_from_file_with_etag(
+        path: &str,
+        server_etag: &str,
+        cached_etag: Option<&str>,
+    ) -> Result<(
+        Either<HttpFcbReader<MockHttpRangeClient>, NotModified>,
+        Arc<RwLock<RequestStats>>,
+    )> {
+        let stats = Arc::new(RwLock::new(RequestStats::new()));
+        let mut http_client = MockHttpRangeClient::new(path, stats.clone());
+        http_client.etag = Some(server_etag.to_string());
+        Ok((
+            Self::new_with_etag(http_client, path, cached_etag).await?,
+            stats,
+        ))
     }
 }
 
@@ -30,18 +63,27 @@ impl HttpFcbReader<MockHttpRangeClient> {
 pub(crate) struct MockHttpRangeClient {
     path: PathBuf,
     stats: Arc<RwLock<RequestStats>>,
+    /// `Authorization` header sent with each request
+    authorization: Option<String>,
+    /// ETag of the file, compared with `If-None-Match`
+    etag: Option<String>,
 }
 
 pub(crate) struct RequestStats {
     pub request_count: u64,
+    pub head_request_count: u64,
     pub bytes_requested: u64,
+    /// `Authorization` header of each request, in request order
+    pub authorization_headers: Vec<Option<String>>,
 }
 
 impl RequestStats {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             request_count: 0,
+            head_request_count: 0,
             bytes_requested: 0,
+            authorization_headers: Vec::new(),
         }
     }
 }
@@ -66,8 +108,10 @@ impl AsyncHttpRangeClient for MockHttpRangeClient {
             start..(end + 1)
         }
 
+        // Like a server, serve the part of the range that is in the file
         let range = parse_range_header(range);
-        let request_length = range.end - range.start;
+        let file_len = std::fs::metadata(&self.path).unwrap().len();
+        let request_length = range.end.min(file_len).saturating_sub(range.start);
 
         let mut stats = self
             .stats
@@ -76,6 +120,7 @@ impl AsyncHttpRangeClient for MockHttpRangeClient {
 
         stats.request_count += 1;
         stats.bytes_requested += request_length;
+        stats.authorization_headers.push(self.authorization.clone());
 
         let mut file_reader = BufReader::new(File::open(&self.path).unwrap());
         file_reader
@@ -90,18 +135,49 @@ impl AsyncHttpRangeClient for MockHttpRangeClient {
 
     async fn head_response_header(
         &self,
-        _url: &str,
-        _header: &str,
+        url: &str,
+        header: &str,
     ) -> http_range_client::Result<Option<String>> {
-        unimplemented!()
+        assert_eq!(url, self.path.to_str().unwrap());
+        self.stats
+            .write()
+            .expect("test code does not handle actual concurrency")
+            .head_request_count += 1;
+        let value = match header.to_ascii_lowercase().as_str() {
+            "content-length" => Some(std::fs::metadata(&self.path).unwrap().len().to_string()),
+            "etag" => self.etag.clone(),
+            _ => None,
+        };
+        Ok(value)
+    }
+}
+
+#[async_trait::async_trait]
+impl ConditionalHttpClient for MockHttpRangeClient {
+    async fn revalidate(
+        &self,
+        url: &str,
+        cached_etag: Option<&str>,
+    ) -> http_range_client::Result<Either<Option<String>, NotModified>> {
+        assert_eq!(url, self.path.to_str().unwrap());
+        self.stats
+            .write()
+            .expect("test code does not handle actual concurrency")
+            .request_count += 1;
+        if cached_etag.is_some() && cached_etag == self.etag.as_deref() {
+            return Ok(Either::Right(NotModified));
+        }
+        Ok(Either::Left(self.etag.clone()))
     }
 }
 
 impl MockHttpRangeClient {
-    fn new(path: &str, stats: Arc<RwLock<RequestStats>>) -> Self {
+    pub(crate) fn new(path: &str, stats: Arc<RwLock<RequestStats>>) -> Self {
         Self {
             path: path.into(),
             stats,
+            authorization: None,
+            etag: None,
         }
     }
 }
@@ -4,38 +4,64 @@
 //! derived from FlatGeobuf (https://github.com/flatgeobuf/flatgeobuf)
 //! Licensed under BSD 2-Clause License, Copyright (c) 2018-2024, Björn Harrtell and contributors
 
-use crate::deserializer::to_cj_feature;
+use crate::deserializer::DeserializeOptions;
 use crate::{add_indices_to_multi_memory_index, build_query, fb::*, AttrQuery};
 
+use crate::checksum::crc32;
 use crate::error::{Error, Result};
+use crate::http_metrics::{counted_get_range, CountingClient, HttpCounters, HttpMetrics};
 use crate::packed_rtree::Query;
+use crate::progress::{ProgressCallback, ProgressEvent};
 use crate::reader::city_buffer::FcbBuffer;
+use crate::retry::RetryPolicy;
 use crate::static_btree::{FixedStringKey, Float, KeyType, Operator};
 use crate::{
-    check_magic_bytes, size_prefixed_root_as_city_feature, HEADER_MAX_BUFFER_SIZE,
+    parse_magic_bytes, size_prefixed_root_as_city_feature, CHECKSUM_SIZE, HEADER_MAX_BUFFER_SIZE,
     HEADER_SIZE_SIZE, MAGIC_BYTES_SIZE,
 };
 use byteorder::{ByteOrder, LittleEndian};
 use bytes::{BufMut, Bytes, BytesMut};
 use chrono::{DateTime, Utc};
 use cjseq::CityJSONFeature;
-use http_range_client::BufferedHttpRangeClient;
 use http_range_client::{AsyncBufferedHttpRangeClient, AsyncHttpRangeClient};
 use log::debug;
-use reqwest;
 
-use crate::packed_rtree::{http::HttpRange, http::HttpSearchResultItem, NodeItem, PackedRTree};
+use crate::packed_rtree::{
+    http::HttpRange, http::HttpSearchResultItem, NodeItem, NodeLayout, PackedRTree,
+};
 use crate::static_btree::{
     http::HttpRange as AttrHttpRange, http::HttpSearchResultItem as AttrHttpSearchResultItem,
 };
-use crate::static_btree::{HttpIndex, HttpMultiIndex};
+use crate::static_btree::{HttpIndex, HttpMultiIndex, Key, Nullable, TypedHttpSearchIndex};
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::ops::Range;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 use tracing::trace;
 
+mod auth;
+mod checkpoint;
+mod client;
+mod config;
+mod etag;
 #[cfg(test)]
 mod mock_http_range_client;
+mod retry_client;
+#[cfg(feature = "s3")]
+mod s3_client;
+
+pub use auth::Auth;
+pub use checkpoint::{Checkpoint, RemainingFeatures, CHECKPOINT_VERSION};
+#[cfg(feature = "runtime-async-std")]
+pub use client::surf_client::SurfClient;
+pub use client::DefaultHttpClient;
+pub use config::HttpConfig;
+pub use either::Either;
+pub use etag::{ConditionalHttpClient, NotModified};
+pub use retry_client::RetryClient;
+#[cfg(feature = "s3")]
+pub use s3_client::{S3Config, S3RangeClient};
 
 // The largest request we'll speculatively make.
 // If a single huge feature requires, we'll necessarily exceed this limit.
@@ -43,14 +69,22 @@ const DEFAULT_HTTP_FETCH_SIZE: usize = 1_048_576; // 1MB
 
 /// FlatCityBuf dataset HTTP reader
 pub struct HttpFcbReader<T: AsyncHttpRangeClient + Send + Sync> {
-    client: AsyncBufferedHttpRangeClient<T>,
+    client: AsyncBufferedHttpRangeClient<CountingClient<T>>,
     // feature reading requires header access, therefore
     // header_buf is included in the FcbBuffer struct.
     fbs: FcbBuffer,
+    /// Request counters, shared with the client
+    counters: Arc<HttpCounters>,
+    /// File format version found in the magic bytes
+    version: u8,
+    /// URL of the dataset, recorded in checkpoints
+    url: Option<String>,
+    /// ETag of the dataset, when opened with [`HttpFcbReader::open_with_etag`]
+    etag: Option<String>,
 }
 
 pub struct AsyncFeatureIter<T: AsyncHttpRangeClient + Send + Sync> {
-    client: AsyncBufferedHttpRangeClient<T>,
+    client: AsyncBufferedHttpRangeClient<CountingClient<T>>,
     // feature reading requires header access, therefore
     // header_buf is included in the FcbBuffer struct.
     fbs: FcbBuffer,
@@ -58,22 +92,128 @@ pub struct AsyncFeatureIter<T: AsyncHttpRangeClient + Send + Sync> {
     selection: FeatureSelection,
     /// Number of selected features
     count: usize,
+    /// Optional callback reporting progress after each feature is read
+    progress: Option<ProgressCallback>,
+    /// Number of features read so far
+    features_read: usize,
+    /// Number of feature bytes read so far
+    bytes_read: u64,
+    /// Request counters, carried over from the reader
+    counters: Arc<HttpCounters>,
+    /// Feature buffers fetched ahead by [`AsyncFeatureIter::prefetch`], not yet returned, with
+    /// their position in the file
+    prefetched: VecDeque<(usize, Bytes)>,
+    /// Token stopping the iteration once cancelled
+    cancellation: Option<CancellationToken>,
+    /// URL of the dataset, recorded in checkpoints
+    url: Option<String>,
+    /// LODs of the geometries to decode or None to decode all geometries
+    filter_lod: Option<Vec<String>>,
 }
 
-impl HttpFcbReader<reqwest::Client> {
-    pub async fn open(url: &str) -> Result<HttpFcbReader<reqwest::Client>> {
+impl HttpFcbReader<DefaultHttpClient> {
+    pub async fn open(url: &str) -> Result<HttpFcbReader<DefaultHttpClient>> {
         trace!("starting: opening http reader, reading header");
-        let client = BufferedHttpRangeClient::new(url);
-        Self::_open(client).await
+        Self::_open(DefaultHttpClient::new(), url).await
+    }
+
+    /// Open a dataset which requires authentication.
+    ///
+    /// The credentials are sent with every range request.
+    pub async fn open_with_auth(url: &str, auth: Auth) -> Result<HttpFcbReader<DefaultHttpClient>> {
+        trace!("starting: opening http reader with auth, reading header");
+        Self::_open(auth.client()?, url).await
+    }
+
+    /// Open a dataset with a client built from `config`, e.g. to limit the connection pool.
+    pub async fn open_with_config(
+        url: &str,
+        config: &HttpConfig,
+    ) -> Result<HttpFcbReader<DefaultHttpClient>> {
+        trace!("starting: opening http reader with config, reading header");
+        Self::_open(config.build_client()?, url).await
+    }
+
+    /// Open a dataset unless it is unchanged since `cached_etag` was received.
+    ///
+    /// The first request carries `If-None-Match: <cached_etag>`. On `304 Not Modified` no
+    /// further request is made and `Right(NotModified)` is returned, so that the caller keeps
+    /// using its cached results. Otherwise the dataset is opened and its new ETag is available
+    /// with [`HttpFcbReader::etag`].
+    pub async fn open_with_etag(
+        url: &str,
+        cached_etag: Option<&str>,
+    ) -> Result<Either<HttpFcbReader<DefaultHttpClient>, NotModified>> {
+        Self::new_with_etag(DefaultHttpClient::new(), url, cached_etag).await
+    }
+}
+
+impl HttpFcbReader<RetryClient<DefaultHttpClient>> {
+    /// Open a dataset, retrying transient request failures with exponential backoff.
+    pub async fn open_with_retry(
+        url: &str,
+        max_retries: u32,
+        initial_backoff: std::time::Duration,
+    ) -> Result<HttpFcbReader<RetryClient<DefaultHttpClient>>> {
+        HttpFcbReader::with_retry(DefaultHttpClient::new(), url, max_retries, initial_backoff).await
+    }
+}
+
+impl HttpFcbReader<DefaultHttpClient> {
+    /// Open the dataset at `url` and continue the iteration saved in `checkpoint`.
+    ///
+    /// Fails with `Error::InvalidCheckpoint` if the checkpoint was made for another URL, if
+    /// the dataset changed since, or if it has an unsupported version.
+    pub async fn resume_from_checkpoint(
+        url: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<AsyncFeatureIter<DefaultHttpClient>> {
+        Self::open(url).await?.resume(checkpoint)
     }
 }
 
 impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
-    pub async fn new(client: AsyncBufferedHttpRangeClient<T>) -> Result<HttpFcbReader<T>> {
-        Self::_open(client).await
+    pub async fn new(client: T, url: &str) -> Result<HttpFcbReader<T>> {
+        Self::_open(client, url).await
+    }
+
+    /// Open a dataset with `client`, retrying failed range requests up to `max_retries`
+    /// times. Only transient failures (429, 5xx, connection errors) are retried.
+    pub async fn with_retry(
+        client: T,
+        url: &str,
+        max_retries: u32,
+        initial_backoff: std::time::Duration,
+    ) -> Result<HttpFcbReader<RetryClient<T>>> {
+        let client = RetryClient::new(client, RetryPolicy::new(max_retries, initial_backoff));
+        HttpFcbReader::_open(client, url).await
     }
 
-    async fn _open(mut client: AsyncBufferedHttpRangeClient<T>) -> Result<HttpFcbReader<T>> {
+    /// Open a dataset with `client` unless it is unchanged since `cached_etag` was received,
+    /// see [`HttpFcbReader::open_with_etag`]
+    pub async fn new_with_etag(
+        client: T,
+        url: &str,
+        cached_etag: Option<&str>,
+    ) -> Result<Either<HttpFcbReader<T>, NotModified>>
+    where
+        T: ConditionalHttpClient,
+    {
+        trace!("starting: revalidating etag {cached_etag:?}");
+        let etag = match client.revalidate(url, cached_etag).await? {
+            Either::Left(etag) => etag,
+            Either::Right(not_modified) => return Ok(Either::Right(not_modified)),
+        };
+        let mut reader = Self::_open(client, url).await?;
+        reader.etag = etag;
+        Ok(Either::Left(reader))
+    }
+
+    /// Open the dataset at `url` with `client`, wrapped to count its requests
+    async fn _open(client: T, url: &str) -> Result<HttpFcbReader<T>> {
+        let client = CountingClient::new(client);
+        let counters = client.counters();
+        let mut client = AsyncBufferedHttpRangeClient::with(client, url);
         // Because we use a buffered HTTP reader, anything extra we fetch here can
         // be utilized to skip subsequent fetches.
         // Immediately following the header is the optional spatial index, we deliberately fetch
@@ -99,13 +239,13 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
         client.set_min_req_size(min_req_size);
         debug!("fetching header. min_req_size: {min_req_size} (assumed_header_size: {assumed_header_size}, prefetched_index_bytes: {prefetch_index_bytes})");
         let mut read_bytes = 0;
-        let bytes = client.get_range(read_bytes, MAGIC_BYTES_SIZE).await?; // to get magic bytes
-        if !check_magic_bytes(bytes) {
-            return Err(Error::MissingMagicBytes);
-        }
+        let bytes = counted_get_range(&mut client, &counters, read_bytes, MAGIC_BYTES_SIZE).await?; // to get magic bytes
+        let version = parse_magic_bytes(bytes)?;
 
         read_bytes += MAGIC_BYTES_SIZE;
-        let mut bytes = BytesMut::from(client.get_range(read_bytes, HEADER_SIZE_SIZE).await?);
+        let mut bytes = BytesMut::from(
+            counted_get_range(&mut client, &counters, read_bytes, HEADER_SIZE_SIZE).await?,
+        );
         read_bytes += HEADER_SIZE_SIZE;
 
         let header_size = LittleEndian::read_u32(&bytes) as usize;
@@ -114,7 +254,7 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
             return Err(Error::IllegalHeaderSize(header_size));
         }
 
-        bytes.put(client.get_range(read_bytes, header_size).await?);
+        bytes.put(counted_get_range(&mut client, &counters, read_bytes, header_size).await?);
         read_bytes += header_size;
 
         let header_buf = bytes.to_vec();
@@ -129,21 +269,84 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
                 header_buf,
                 features_buf: Vec::new(),
             },
+            counters,
+            version,
+            url: Some(url.to_string()),
+            etag: None,
         })
     }
 
+    /// Continue the iteration saved in `checkpoint` with this reader, see
+    /// [`HttpFcbReader::resume_from_checkpoint`]
+    pub fn resume(self, checkpoint: &Checkpoint) -> Result<AsyncFeatureIter<T>> {
+        checkpoint.verify(self.url.as_deref(), &self.fbs.header_buf)?;
+        let selection = match &checkpoint.remaining {
+            RemainingFeatures::All { features_left, pos } => {
+                FeatureSelection::SelectAll(SelectAll {
+                    features_left: *features_left,
+                    pos: *pos,
+                })
+            }
+            RemainingFeatures::Batches { batches } => FeatureSelection::SelectBbox(SelectBbox {
+                feature_batches: batches
+                    .iter()
+                    .rev()
+                    .map(|batch| FeatureBatch::new(batch.iter().map(to_http_range).collect()))
+                    .collect(),
+            }),
+            RemainingFeatures::Ranges { ranges } => FeatureSelection::SelectAttr(SelectAttr {
+                ranges: ranges.iter().map(to_http_range).collect(),
+                range_pos: 0,
+            }),
+        };
+        let count = checkpoint.features_read + checkpoint.remaining.len();
+        let mut iter = AsyncFeatureIter::new(
+            self.client,
+            self.fbs,
+            self.counters,
+            self.url,
+            selection,
+            count,
+        );
+        iter.features_read = checkpoint.features_read;
+        iter.bytes_read = checkpoint.bytes_read;
+        Ok(iter)
+    }
+
     pub fn header(&self) -> Header {
         self.fbs.header()
     }
+
+    /// File format version of the dataset
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// ETag of the dataset sent by the server, to be passed to
+    /// [`HttpFcbReader::open_with_etag`] to revalidate the cached results later
+    pub fn etag(&self) -> Option<String> {
+        self.etag.clone()
+    }
+
+    /// Counters of the requests issued so far
+    pub fn metrics(&self) -> HttpMetrics {
+        self.counters.snapshot()
+    }
+
     fn header_len(&self) -> usize {
         MAGIC_BYTES_SIZE + self.fbs.header_buf.len()
     }
 
+    /// Layout of the R-tree nodes, which changed with version 2
+    fn node_layout(&self) -> NodeLayout {
+        NodeLayout::for_version(self.version)
+    }
+
     fn rtree_index_size(&self) -> usize {
         let header = self.fbs.header();
         let feat_count = header.features_count() as usize;
         if header.index_node_size() > 0 && feat_count > 0 {
-            PackedRTree::index_size(feat_count, header.index_node_size())
+            PackedRTree::index_size(feat_count, header.index_node_size(), self.node_layout())
         } else {
             0
         }
@@ -151,21 +354,25 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
 
     fn attr_index_size(&self) -> usize {
         let header = self.fbs.header();
+        // composite indices are stored right after the single-column ones
+        let composite_lengths = header
+            .composite_index()
+            .into_iter()
+            .flat_map(|ci| ci.iter())
+            .map(|ci| ci.length() as usize);
         header
             .attribute_index()
-            .map(|attr_index| {
-                attr_index
-                    .iter()
-                    .try_fold(0, |acc, ai| {
-                        let len = ai.length() as usize;
-                        if len > usize::MAX - acc {
-                            Err(Error::AttributeIndexSizeOverflow)
-                        } else {
-                            Ok(acc + len)
-                        }
-                    }) // sum of all attribute index lengths
-                    .unwrap_or(0)
-            })
+            .into_iter()
+            .flat_map(|ai| ai.iter())
+            .map(|ai| ai.length() as usize)
+            .chain(composite_lengths)
+            .try_fold(0, |acc, len| {
+                if len > usize::MAX - acc {
+                    Err(Error::AttributeIndexSizeOverflow)
+                } else {
+                    Ok(acc + len)
+                }
+            }) // sum of all attribute index lengths
             .unwrap_or(0)
     }
 
@@ -180,16 +387,81 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
         let index_size = self.index_size() as usize;
         // Skip index
         let feature_base = self.header_len() + index_size;
-        Ok(AsyncFeatureIter {
-            client: self.client,
-            fbs: self.fbs,
-            selection: FeatureSelection::SelectAll(SelectAll {
+        Ok(AsyncFeatureIter::new(
+            self.client,
+            self.fbs,
+            self.counters,
+            self.url,
+            FeatureSelection::SelectAll(SelectAll {
                 features_left: count,
                 pos: feature_base,
             }),
-            count: count as usize,
-        })
+            count as usize,
+        ))
     }
+
+    /// Select features intersecting the given 3D bounding box.
+    pub async fn select_bbox_3d(
+        self,
+        min_x: f64,
+        min_y: f64,
+        min_z: f64,
+        max_x: f64,
+        max_y: f64,
+        max_z: f64,
+    ) -> Result<AsyncFeatureIter<T>> {
+        self.select_query(Query::BBox3D(min_x, min_y, min_z, max_x, max_y, max_z))
+            .await
+    }
+
+    /// Select the `k` features nearest to the point by centroid distance, in file order.
+    ///
+    /// R-tree nodes are fetched best-first, so only the branches that can hold one of the
+    /// nearest features are requested.
+    pub async fn select_nearest(self, x: f64, y: f64, k: usize) -> Result<AsyncFeatureIter<T>> {
+        self.select_query(Query::KNearest(x, y, k)).await
+    }
+
+    /// Count the features intersecting the given bounding box, fetching only R-tree nodes.
+    pub async fn count_bbox(
+        &mut self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> Result<usize> {
+        self.count_query(Query::BBox(min_x, min_y, max_x, max_y))
+            .await
+    }
+
+    /// Count the features matching a spatial query without fetching any feature.
+    pub async fn count_query(&mut self, query: Query) -> Result<usize> {
+        let header = self.fbs.header();
+        if header.index_node_size() == 0 {
+            return Err(Error::NoIndex);
+        }
+        if header.features_count() == 0 {
+            return Ok(0);
+        }
+        let count = header.features_count() as usize;
+        let node_size = header.index_node_size();
+        let header_len = self.header_len();
+
+        // request up to this many extra bytes if it means we can eliminate an extra request
+        let combine_request_threshold = 256 * 1024;
+        let layout = self.node_layout();
+        Ok(PackedRTree::http_stream_count(
+            &mut self.client,
+            header_len,
+            count,
+            node_size,
+            query,
+            combine_request_threshold,
+            layout,
+        )
+        .await?)
+    }
+
     /// Select features within a bounding box.
     pub async fn select_query(mut self, query: Query) -> Result<AsyncFeatureIter<T>> {
         trace!("starting: select_bbox, traversing index");
@@ -204,14 +476,16 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
         // request up to this many extra bytes if it means we can eliminate an extra request
         let combine_request_threshold = 256 * 1024;
         let attr_index_size = self.attr_index_size() as usize;
+        let layout = self.node_layout();
         let list = PackedRTree::http_stream_search(
             &mut self.client,
             header_len,
             attr_index_size,
             count,
-            PackedRTree::DEFAULT_NODE_SIZE,
+            header.index_node_size(),
             query,
             combine_request_threshold,
+            layout,
         )
         .await?;
         debug_assert!(
@@ -224,18 +498,18 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
         let feature_batches = FeatureBatch::make_batches(list, combine_request_threshold).await?;
         let selection = FeatureSelection::SelectBbox(SelectBbox { feature_batches });
         trace!("completed: select_bbox");
-        Ok(AsyncFeatureIter {
-            client: self.client,
-            fbs: self.fbs,
+        Ok(AsyncFeatureIter::new(
+            self.client,
+            self.fbs,
+            self.counters,
+            self.url,
             selection,
             count,
-        })
+        ))
     }
 
-    /// This method uses the attribute index section to find matching feature offsets.
-    /// It then groups (batches) the remote feature ranges in order to reduce IO overhead.
-    pub async fn select_attr_query(mut self, query: &AttrQuery) -> Result<AsyncFeatureIter<T>> {
-        trace!("starting: select_attr_query via http reader");
+    /// Multi-index over all attribute indices of the file, fetched with range requests
+    fn attr_query_multi_index(&self) -> Result<HttpMultiIndex<T>> {
         let header = self.fbs.header();
         let header_len = self.header_len();
         // Assume the header provides rtree and attribute index sizes.
@@ -258,9 +532,6 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
             .collect();
         attr_index_entries.sort_by_key(|attr_info| attr_info.index());
 
-        // Build the query
-        let query = build_query(&query);
-
         // Create a StreamableMultiIndex from HTTP range requests
         let mut http_multi_index = HttpMultiIndex::new();
 
@@ -275,7 +546,28 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
             )?;
             current_index_begin += attr_info.length() as usize;
         }
+        Ok(http_multi_index)
+    }
 
+    /// Count the features matching an attribute query without fetching any feature.
+    ///
+    /// Only the attribute index section is requested, e.g. to show the total number of
+    /// results before loading the first page.
+    pub async fn count_attr_query(&mut self, query: &AttrQuery) -> Result<usize> {
+        let http_multi_index = self.attr_query_multi_index()?;
+        let query = build_query(query);
+        let result = http_multi_index
+            .query(&mut self.client, &query.conditions)
+            .await?;
+        Ok(result.len())
+    }
+
+    /// This method uses the attribute index section to find matching feature offsets.
+    /// It then groups (batches) the remote feature ranges in order to reduce IO overhead.
+    pub async fn select_attr_query(mut self, query: &AttrQuery) -> Result<AsyncFeatureIter<T>> {
+        trace!("starting: select_attr_query via http reader");
+        let http_multi_index = self.attr_query_multi_index()?;
+        let query = build_query(query);
         let result = http_multi_index
             .query(&mut self.client, &query.conditions)
             .await?;
@@ -298,15 +590,17 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
         );
 
         println!("http_ranges: {:?}", http_ranges);
-        Ok(AsyncFeatureIter {
-            client: self.client,
-            fbs: self.fbs,
-            selection: FeatureSelection::SelectAttr(SelectAttr {
+        Ok(AsyncFeatureIter::new(
+            self.client,
+            self.fbs,
+            self.counters,
+            self.url,
+            FeatureSelection::SelectAttr(SelectAttr {
                 ranges: http_ranges,
                 range_pos: 0,
             }),
             count,
-        })
+        ))
     }
 
     pub fn add_indices_to_multi_http_index<C: AsyncHttpRangeClient + Send + Sync>(
@@ -318,6 +612,15 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
     ) -> Result<()> {
         if let Some(col) = columns.iter().find(|col| col.index() == attr_info.index()) {
             // TODO: now it assuming to add all indices to the multi_index. However, we should only add the indices that are used in the query. To do that, we need to change the implementation of StreamMultiIndex. Current StreamMultiIndex's `add_index` method assumes that all indices are added to the multi_index. We'll change it to take Range<usize> as an argument.
+            if col.nullable_index() {
+                return Self::add_nullable_http_index(
+                    multi_index,
+                    col,
+                    attr_info,
+                    index_begin,
+                    feature_begin,
+                );
+            }
             match col.type_() {
                 ColumnType::Int => {
                     let index = HttpIndex::<i32>::new(
@@ -329,6 +632,16 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
                     );
                     multi_index.add_index(col.name().to_string(), index);
                 }
+                ColumnType::Long => {
+                    let index = HttpIndex::<i64>::new(
+                        attr_info.num_unique_items() as usize,
+                        attr_info.branching_factor(),
+                        index_begin,
+                        feature_begin,
+                        1024 * 1024, // combine_request_threshold
+                    );
+                    multi_index.add_index(col.name().to_string(), index);
+                }
                 ColumnType::Float => {
                     let index = HttpIndex::<Float<f32>>::new(
                         attr_info.num_unique_items() as usize,
@@ -449,12 +762,153 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpFcbReader<T> {
         }
         Ok(())
     }
+
+    /// Adds an index whose keys carry a null marker, see [`Nullable`]
+    fn add_nullable_http_index<C: AsyncHttpRangeClient + Send + Sync>(
+        multi_index: &mut HttpMultiIndex<C>,
+        col: &Column,
+        attr_info: &AttributeIndex,
+        index_begin: usize,
+        feature_begin: usize,
+    ) -> Result<()> {
+        fn add<K: Key + 'static, C: AsyncHttpRangeClient + Send + Sync>(
+            multi_index: &mut HttpMultiIndex<C>,
+            col: &Column,
+            attr_info: &AttributeIndex,
+            index_begin: usize,
+            feature_begin: usize,
+        ) -> Result<()>
+        where
+            HttpIndex<Nullable<K>>: TypedHttpSearchIndex<C>,
+        {
+            let index = HttpIndex::<Nullable<K>>::new(
+                attr_info.num_unique_items() as usize,
+                attr_info.branching_factor(),
+                index_begin,
+                feature_begin,
+                1024 * 1024, // combine_request_threshold
+            );
+            multi_index.add_index(col.name().to_string(), index);
+            Ok(())
+        }
+
+        match col.type_() {
+            ColumnType::Int => {
+                add::<i32, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::Long => {
+                add::<i64, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::Float => {
+                add::<Float<f32>, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::Double => {
+                add::<Float<f64>, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::String => add::<FixedStringKey<50>, _>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+            ),
+            ColumnType::Bool => {
+                add::<bool, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::DateTime => {
+                add::<DateTime<Utc>, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::Short => {
+                add::<i16, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::UShort => {
+                add::<u16, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::UInt => {
+                add::<u32, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::ULong => {
+                add::<u64, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::Byte => {
+                add::<i8, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            ColumnType::UByte => {
+                add::<u8, _>(multi_index, col, attr_info, index_begin, feature_begin)
+            }
+            _ => Err(Error::UnsupportedColumnType(col.name().to_string())),
+        }
+    }
 }
 
 impl<T: AsyncHttpRangeClient + Send + Sync> AsyncFeatureIter<T> {
+    fn new(
+        client: AsyncBufferedHttpRangeClient<CountingClient<T>>,
+        fbs: FcbBuffer,
+        counters: Arc<HttpCounters>,
+        url: Option<String>,
+        selection: FeatureSelection,
+        count: usize,
+    ) -> Self {
+        Self {
+            client,
+            fbs,
+            selection,
+            count,
+            progress: None,
+            features_read: 0,
+            bytes_read: 0,
+            counters,
+            prefetched: VecDeque::new(),
+            cancellation: None,
+            url,
+            filter_lod: None,
+        }
+    }
+
+    /// Registers a callback that is invoked after each call to [`AsyncFeatureIter::next`]
+    /// that yields a feature.
+    pub fn with_progress(mut self, cb: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(cb));
+        self
+    }
+
+    /// Stops the iteration once `token` is cancelled: [`AsyncFeatureIter::next`] then returns
+    /// `Ok(None)`.
+    ///
+    /// The token is checked before each feature request, so a request already in flight
+    /// completes and the iterator stays usable with a new token.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Only decode geometries of the given LODs in [`AsyncFeatureIter::cur_cj_feature`],
+    /// e.g. `&["2.2"]`.
+    ///
+    /// Other geometries are skipped before decoding, city objects without a matching geometry
+    /// get an empty `geometry` array. Geometry instances are always kept, see
+    /// [`DeserializeOptions::lods`].
+    pub fn with_lod_filter(mut self, lods: &[&str]) -> Self {
+        self.filter_lod = Some(lods.iter().map(|lod| lod.to_string()).collect());
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
     pub fn header(&self) -> Header {
         self.fbs.header()
     }
+
+    /// Counters of the requests issued so far, including those of the reader
+    pub fn metrics(&self) -> HttpMetrics {
+        self.counters.snapshot()
+    }
+
     /// Number of selected features (might be unknown)
     pub fn features_count(&self) -> Option<usize> {
         if self.count > 0 {
@@ -463,16 +917,161 @@ impl<T: AsyncHttpRangeClient + Send + Sync> AsyncFeatureIter<T> {
             None
         }
     }
+    fn checksum_size(&self) -> usize {
+        if self.fbs.header().checksum() {
+            CHECKSUM_SIZE
+        } else {
+            0
+        }
+    }
+
+    /// Fetches the next `n` selected features ahead of time, so that the following calls to
+    /// [`AsyncFeatureIter::next`] return them without any request.
+    ///
+    /// The buffers are verified when they are returned by `next`.
+    pub async fn prefetch(&mut self, n: usize) -> Result<()> {
+        let checksum_size = self.checksum_size();
+        for _ in 0..n {
+            if self.is_cancelled() {
+                break;
+            }
+            let Some(buffer) = self
+                .selection
+                .next_feature_buffer(&mut self.client, &self.counters, checksum_size)
+                .await?
+            else {
+                break;
+            };
+            self.prefetched.push_back(buffer);
+        }
+        Ok(())
+    }
+
+    /// Number of features fetched by [`AsyncFeatureIter::prefetch`] and not yet returned
+    pub fn prefetched_count(&self) -> usize {
+        self.prefetched.len()
+    }
+
+    /// Saves the state of the iteration, to continue it later with
+    /// [`HttpFcbReader::resume_from_checkpoint`]. Prefetched features that were not returned
+    /// yet are fetched again after resuming.
+    pub fn checkpoint(&self) -> Checkpoint {
+        let prefetched = self
+            .prefetched
+            .iter()
+            .map(|(start, buffer)| (*start, Some(start + buffer.len())))
+            .collect::<Vec<_>>();
+        let remaining = match &self.selection {
+            FeatureSelection::SelectAll(select_all) => RemainingFeatures::All {
+                features_left: select_all.features_left + prefetched.len() as u64,
+                pos: prefetched
+                    .first()
+                    .map_or(select_all.pos, |(start, _)| *start),
+            },
+            FeatureSelection::SelectBbox(select_bbox) => {
+                // the batches are popped from the back
+                let batches = select_bbox
+                    .feature_batches
+                    .iter()
+                    .rev()
+                    .map(|batch| batch.feature_ranges.iter().map(range_bounds).collect());
+                RemainingFeatures::Batches {
+                    batches: std::iter::once(prefetched)
+                        .filter(|batch| !batch.is_empty())
+                        .chain(batches)
+                        .collect(),
+                }
+            }
+            FeatureSelection::SelectAttr(select_attr) => RemainingFeatures::Ranges {
+                ranges: prefetched
+                    .into_iter()
+                    .chain(
+                        select_attr.ranges[select_attr.range_pos..]
+                            .iter()
+                            .map(range_bounds),
+                    )
+                    .collect(),
+            },
+        };
+        Checkpoint {
+            version: CHECKPOINT_VERSION,
+            url_hash: self.url.as_deref().map(checkpoint::url_hash),
+            header_hash: crc32(&self.fbs.header_buf),
+            features_read: self.features_read,
+            bytes_read: self.bytes_read,
+            remaining,
+        }
+    }
+
     /// Read next feature
     pub async fn next(&mut self) -> Result<Option<&FcbBuffer>> {
-        let Some(buffer) = self.selection.next_feature_buffer(&mut self.client).await? else {
+        let feature_index = self.features_read;
+        self.read_next()
+            .await
+            .map_err(|e| e.with_context(format!("feature {feature_index}")))
+    }
+
+    /// Read next feature and decode it into `buf`, reusing its allocations
+    ///
+    /// Returns `false` once all the selected features have been read, `buf` is then left as is.
+    pub async fn next_into(&mut self, buf: &mut CityJSONFeature) -> Result<bool> {
+        match self.next().await? {
+            Some(feature) => {
+                feature.cj_feature_into(buf)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn read_next(&mut self) -> Result<Option<&FcbBuffer>> {
+        if self.is_cancelled() {
             return Ok(None);
+        }
+        let checksum_size = self.checksum_size();
+        let (_, buffer) = match self.prefetched.pop_front() {
+            Some(buffer) => buffer,
+            None => {
+                let Some(buffer) = self
+                    .selection
+                    .next_feature_buffer(&mut self.client, &self.counters, checksum_size)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                buffer
+            }
         };
 
         // Not zero-copy
         self.fbs.features_buf = buffer.to_vec();
+        if checksum_size > 0 {
+            let crc_buf = self
+                .fbs
+                .features_buf
+                .split_off(self.fbs.features_buf.len() - checksum_size);
+            let expected = LittleEndian::read_u32(&crc_buf);
+            let actual = crc32(&self.fbs.features_buf);
+            if expected != actual {
+                return Err(Error::ChecksumMismatch {
+                    feature_index: self.features_read,
+                    expected,
+                    actual,
+                });
+            }
+        }
         // verify flatbuffer
         let _feature = size_prefixed_root_as_city_feature(&self.fbs.features_buf)?;
+
+        self.features_read += 1;
+        self.bytes_read += self.fbs.features_buf.len() as u64;
+        if let Some(progress) = &self.progress {
+            progress(ProgressEvent {
+                features_written: self.features_read,
+                bytes_written: self.bytes_read,
+                total_features: self.features_count(),
+            });
+        }
         Ok(Some(&self.fbs))
     }
     /// Return current feature
@@ -481,12 +1080,21 @@ impl<T: AsyncHttpRangeClient + Send + Sync> AsyncFeatureIter<T> {
     }
 
     pub fn cur_cj_feature(&self) -> Result<CityJSONFeature> {
-        let cj_feature = to_cj_feature(
-            self.cur_feature().feature(),
-            self.header().columns(),
-            self.header().semantic_columns(),
-        )?;
-        Ok(cj_feature)
+        let options = DeserializeOptions {
+            lods: self.filter_lod.as_deref(),
+        };
+        self.cur_feature().cj_feature_with_options(&options)
+    }
+}
+
+fn range_bounds(range: &HttpRange) -> (usize, Option<usize>) {
+    (range.start(), range.end())
+}
+
+fn to_http_range(&(start, end): &(usize, Option<usize>)) -> HttpRange {
+    match end {
+        Some(end) => HttpRange::Range(start..end),
+        None => HttpRange::RangeFrom(start..),
     }
 }
 
@@ -499,12 +1107,26 @@ enum FeatureSelection {
 impl FeatureSelection {
     async fn next_feature_buffer<T: AsyncHttpRangeClient>(
         &mut self,
-        client: &mut AsyncBufferedHttpRangeClient<T>,
-    ) -> Result<Option<Bytes>> {
+        client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+        counters: &HttpCounters,
+        checksum_size: usize,
+    ) -> Result<Option<(usize, Bytes)>> {
         match self {
-            FeatureSelection::SelectAll(select_all) => select_all.next_buffer(client).await,
-            FeatureSelection::SelectBbox(select_bbox) => select_bbox.next_buffer(client).await,
-            FeatureSelection::SelectAttr(select_attr) => select_attr.next_buffer(client).await,
+            FeatureSelection::SelectAll(select_all) => {
+                select_all
+                    .next_buffer(client, counters, checksum_size)
+                    .await
+            }
+            FeatureSelection::SelectBbox(select_bbox) => {
+                select_bbox
+                    .next_buffer(client, counters, checksum_size)
+                    .await
+            }
+            FeatureSelection::SelectAttr(select_attr) => {
+                select_attr
+                    .next_buffer(client, counters, checksum_size)
+                    .await
+            }
         }
     }
 }
@@ -520,8 +1142,10 @@ struct SelectAll {
 impl SelectAll {
     async fn next_buffer<T: AsyncHttpRangeClient>(
         &mut self,
-        client: &mut AsyncBufferedHttpRangeClient<T>,
-    ) -> Result<Option<Bytes>> {
+        client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+        counters: &HttpCounters,
+        checksum_size: usize,
+    ) -> Result<Option<(usize, Bytes)>> {
         client.min_req_size(DEFAULT_HTTP_FETCH_SIZE);
 
         if self.features_left == 0 {
@@ -529,13 +1153,16 @@ impl SelectAll {
         }
         self.features_left -= 1;
 
-        let mut feature_buffer = BytesMut::from(client.get_range(self.pos, 4).await?);
+        let start = self.pos;
+        let mut feature_buffer =
+            BytesMut::from(counted_get_range(client, counters, self.pos, 4).await?);
         self.pos += 4;
         let feature_size = LittleEndian::read_u32(&feature_buffer) as usize;
-        feature_buffer.put(client.get_range(self.pos, feature_size).await?);
-        self.pos += feature_size;
+        let read_size = feature_size + checksum_size;
+        feature_buffer.put(counted_get_range(client, counters, self.pos, read_size).await?);
+        self.pos += read_size;
 
-        Ok(Some(feature_buffer.freeze()))
+        Ok(Some((start, feature_buffer.freeze())))
     }
 }
 
@@ -547,14 +1174,19 @@ struct SelectBbox {
 impl SelectBbox {
     async fn next_buffer<T: AsyncHttpRangeClient>(
         &mut self,
-        client: &mut AsyncBufferedHttpRangeClient<T>,
-    ) -> Result<Option<Bytes>> {
+        client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+        counters: &HttpCounters,
+        checksum_size: usize,
+    ) -> Result<Option<(usize, Bytes)>> {
         let mut next_buffer = None;
         while next_buffer.is_none() {
             let Some(feature_batch) = self.feature_batches.last_mut() else {
                 break;
             };
-            let Some(buffer) = feature_batch.next_buffer(client).await? else {
+            let Some(buffer) = feature_batch
+                .next_buffer(client, counters, checksum_size)
+                .await?
+            else {
                 // done with this batch
                 self.feature_batches
                     .pop()
@@ -651,21 +1283,25 @@ impl FeatureBatch {
 
     async fn next_buffer<T: AsyncHttpRangeClient>(
         &mut self,
-        client: &mut AsyncBufferedHttpRangeClient<T>,
-    ) -> Result<Option<Bytes>> {
+        client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+        counters: &HttpCounters,
+        checksum_size: usize,
+    ) -> Result<Option<(usize, Bytes)>> {
         let request_size = self.request_size();
         client.set_min_req_size(request_size);
         let Some(feature_range) = self.feature_ranges.pop_front() else {
             return Ok(None);
         };
 
-        let mut pos = feature_range.start();
-        let mut feature_buffer = BytesMut::from(client.get_range(pos, 4).await?);
+        let start = feature_range.start();
+        let mut pos = start;
+        let mut feature_buffer = BytesMut::from(counted_get_range(client, counters, pos, 4).await?);
         pos += 4;
         let feature_size = LittleEndian::read_u32(&feature_buffer) as usize;
-        feature_buffer.put(client.get_range(pos, feature_size).await?);
+        let read_size = feature_size + checksum_size;
+        feature_buffer.put(counted_get_range(client, counters, pos, read_size).await?);
 
-        Ok(Some(feature_buffer.freeze()))
+        Ok(Some((start, feature_buffer.freeze())))
     }
 }
 
@@ -678,18 +1314,313 @@ struct SelectAttr {
 impl SelectAttr {
     async fn next_buffer<T: AsyncHttpRangeClient>(
         &mut self,
-        client: &mut AsyncBufferedHttpRangeClient<T>,
-    ) -> Result<Option<Bytes>> {
+        client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+        counters: &HttpCounters,
+        checksum_size: usize,
+    ) -> Result<Option<(usize, Bytes)>> {
         println!("self.range_pos: {:?}", self.range_pos);
         let Some(range) = self.ranges.get(self.range_pos) else {
             return Ok(None);
         };
-        let mut feature_buffer = BytesMut::from(client.get_range(range.start(), 4).await?);
+        let mut feature_buffer =
+            BytesMut::from(counted_get_range(client, counters, range.start(), 4).await?);
         let feature_size = LittleEndian::read_u32(&feature_buffer) as usize;
         println!("feature_size: {:?}", feature_size);
-        feature_buffer.put(client.get_range(range.start() + 4, feature_size).await?);
+        let read_size = feature_size + checksum_size;
+        feature_buffer
+            .put(counted_get_range(client, counters, range.start() + 4, read_size).await?);
         self.range_pos += 1;
-        Ok(Some(feature_buffer.freeze()))
+        Ok(Some((range.start(), feature_buffer.freeze())))
+    }
+}
+
+#[cfg(test)]
+mod iter_tests {
+    use super::*;
+    use crate::{
+        header_writer::HeaderWriterOptions, read_cityjson_from_reader, CJType, CJTypeKind,
+        FcbWriter,
+    };
+    use mock_http_range_client::{MockHttpRangeClient, RequestStats};
+    use std::{fs::File, io::BufReader, path::PathBuf};
+
+    /// Writes `small.city.jsonl` with checksums, returning the file and its number of features
+    fn write_small_fcb() -> Result<(tempfile::NamedTempFile, usize)> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input = File::open(manifest_dir.join("tests/data/small.city.jsonl"))?;
+        let CJType::Seq(seq) = read_cityjson_from_reader(BufReader::new(input), CJTypeKind::Seq)?
+        else {
+            panic!("expected cityjsonseq");
+        };
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..Default::default()
+            }),
+            None,
+            None,
+        )?
+        .checksum(true);
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let tmp = tempfile::NamedTempFile::new()?;
+        fcb.write(tmp.reopen()?)?;
+        Ok((tmp, seq.features.len()))
+    }
+
+    #[tokio::test]
+    async fn count_bbox_matches_selection() -> Result<()> {
+        let (tmp, feature_count) = write_small_fcb()?;
+        let path = tmp.path().to_str().unwrap();
+
+        let (mut reader, _) = HttpFcbReader::mock_from_file(path).await?;
+        let (min, max) = (f64::MIN, f64::MAX);
+        assert_eq!(reader.count_bbox(min, min, max, max).await?, feature_count);
+        assert_eq!(reader.count_bbox(-1.0, -1.0, 0.0, 0.0).await?, 0);
+
+        let mut iter = reader.select_query(Query::BBox(min, min, max, max)).await?;
+        let mut count = 0;
+        while iter.next().await?.is_some() {
+            count += 1;
+        }
+        assert_eq!(count, feature_count);
+
+        // an indexed file without features has nothing to count
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input = File::open(manifest_dir.join("tests/data/small.city.jsonl"))?;
+        let CJType::Seq(seq) = read_cityjson_from_reader(BufReader::new(input), CJTypeKind::Seq)?
+        else {
+            panic!("expected cityjsonseq");
+        };
+        let empty = tempfile::NamedTempFile::new()?;
+        FcbWriter::new(seq.cj, None, None, None)?.write(empty.reopen()?)?;
+        let (mut reader, _) = HttpFcbReader::mock_from_file(empty.path().to_str().unwrap()).await?;
+        assert!(reader.header().index_node_size() > 0);
+        assert_eq!(reader.count_bbox(min, min, max, max).await?, 0);
+        Ok(())
+    }
+
+    async fn all_ids(path: &str) -> Result<Vec<String>> {
+        let (reader, _) = HttpFcbReader::mock_from_file(path).await?;
+        let mut iter = reader.select_all().await?;
+        let mut ids = Vec::new();
+        while let Some(feature) = iter.next().await? {
+            ids.push(feature.cj_feature()?.id);
+        }
+        Ok(ids)
+    }
+
+    #[tokio::test]
+    async fn prefetched_features_need_no_request() -> Result<()> {
+        let (tmp, feature_count) = write_small_fcb()?;
+        let path = tmp.path().to_str().unwrap();
+        let expected = all_ids(path).await?;
+
+        let n = 3.min(feature_count);
+        let (reader, stats) = HttpFcbReader::mock_from_file(path).await?;
+        let mut iter = reader.select_all().await?;
+        iter.prefetch(n).await?;
+        assert_eq!(iter.prefetched_count(), n);
+        let requests = stats.read().unwrap().request_count;
+
+        let mut ids = Vec::new();
+        for _ in 0..n {
+            let feature = iter.next().await?.expect("prefetched feature");
+            ids.push(feature.cj_feature()?.id);
+        }
+        assert_eq!(stats.read().unwrap().request_count, requests);
+        assert_eq!(iter.prefetched_count(), 0);
+
+        while let Some(feature) = iter.next().await? {
+            ids.push(feature.cj_feature()?.id);
+        }
+        assert_eq!(ids, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn metrics_count_requests_of_the_client() -> Result<()> {
+        let (tmp, feature_count) = write_small_fcb()?;
+        let path = tmp.path().to_str().unwrap();
+
+        let (reader, stats) = HttpFcbReader::mock_from_file(path).await?;
+        let metrics = reader.metrics();
+        assert_eq!(metrics.request_count, stats.read().unwrap().request_count);
+        assert_eq!(
+            metrics.bytes_received,
+            stats.read().unwrap().bytes_requested
+        );
+
+        let mut iter = reader.select_all().await?;
+        let mut count = 0;
+        while iter.next().await?.is_some() {
+            count += 1;
+        }
+        assert_eq!(count, feature_count);
+        let metrics = iter.metrics();
+        let stats = stats.read().unwrap();
+        assert_eq!(metrics.request_count, stats.request_count);
+        assert_eq!(metrics.head_request_count, stats.head_request_count);
+        assert_eq!(metrics.bytes_received, stats.bytes_requested);
+        assert!(metrics.cache_hits > 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn metrics_count_head_requests() -> Result<()> {
+        let (tmp, _) = write_small_fcb()?;
+        let path = tmp.path().to_str().unwrap();
+        let stats = Arc::new(std::sync::RwLock::new(RequestStats::new()));
+        let client = CountingClient::new(MockHttpRangeClient::new(path, stats.clone()));
+        let counters = client.counters();
+
+        let len = client.head_response_header(path, "content-length").await?;
+        assert_eq!(len, Some(std::fs::metadata(path)?.len().to_string()));
+        client.get_range(path, "bytes=0-7").await?;
+        assert_eq!(stats.read().unwrap().head_request_count, 1);
+        assert_eq!(
+            counters.snapshot(),
+            HttpMetrics {
+                request_count: 1,
+                head_request_count: 1,
+                bytes_requested: 0,
+                bytes_received: 8,
+                cache_hits: 0,
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cancellation_stops_iteration() -> Result<()> {
+        let (tmp, feature_count) = write_small_fcb()?;
+        assert!(feature_count > 2);
+        let path = tmp.path().to_str().unwrap();
+        let expected = all_ids(path).await?;
+
+        let token = CancellationToken::new();
+        let (reader, stats) = HttpFcbReader::mock_from_file(path).await?;
+        let mut iter = reader.select_all().await?.with_cancellation(token.clone());
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let feature = iter.next().await?.expect("feature before cancellation");
+            ids.push(feature.cj_feature()?.id);
+        }
+
+        token.cancel();
+        let requests = stats.read().unwrap().request_count;
+        assert!(iter.next().await?.is_none());
+        iter.prefetch(1).await?;
+        assert_eq!(iter.prefetched_count(), 0);
+        assert_eq!(stats.read().unwrap().request_count, requests);
+
+        // the client is left in a consistent state, iteration resumes with a new token
+        let mut iter = iter.with_cancellation(CancellationToken::new());
+        while let Some(feature) = iter.next().await? {
+            ids.push(feature.cj_feature()?.id);
+        }
+        assert_eq!(ids, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lod_filter_skips_geometries() -> Result<()> {
+        let (tmp, _) = write_small_fcb()?;
+        let path = tmp.path().to_str().unwrap();
+        let (reader, _) = HttpFcbReader::mock_from_file(path).await?;
+        let mut iter = reader.select_all().await?.with_lod_filter(&["2.2"]);
+        let mut lod22_cnt = 0;
+        while iter.next().await?.is_some() {
+            let feature = iter.cur_cj_feature()?;
+            for co in feature.city_objects.values() {
+                let geometries = co.geometry.as_ref().expect("filtered geometry is an array");
+                assert!(geometries.iter().all(|g| g.lod.as_deref() == Some("2.2")));
+                lod22_cnt += geometries.len();
+            }
+        }
+        assert!(lod22_cnt > 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn open_with_etag_revalidates_cache() -> Result<()> {
+        let (tmp, feature_count) = write_small_fcb()?;
+        let path = tmp.path().to_str().unwrap();
+
+        // nothing cached yet
+        let (reader, _) = HttpFcbReader::mock_from_file_with_etag(path, "\"v1\"", None).await?;
+        let Either::Left(reader) = reader else {
+            panic!("expected a reader");
+        };
+        let etag = reader.etag().expect("etag of the response");
+        assert_eq!(etag, "\"v1\"");
+        assert_eq!(reader.header().features_count() as usize, feature_count);
+
+        // unchanged, only the conditional request is sent
+        let (reader, stats) =
+            HttpFcbReader::mock_from_file_with_etag(path, "\"v1\"", Some(&etag)).await?;
+        assert_eq!(reader.right(), Some(NotModified));
+        assert_eq!(stats.read().unwrap().request_count, 1);
+
+        // regenerated on the server
+        let (reader, _) =
+            HttpFcbReader::mock_from_file_with_etag(path, "\"v2\"", Some(&etag)).await?;
+        let reader = reader.left().expect("expected a reader");
+        assert_eq!(reader.etag().as_deref(), Some("\"v2\""));
+        let mut iter = reader.select_all().await?;
+        let mut count = 0;
+        while iter.next().await?.is_some() {
+            count += 1;
+        }
+        assert_eq!(count, feature_count);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume_from_checkpoint() -> Result<()> {
+        let (tmp, feature_count) = write_small_fcb()?;
+        assert!(feature_count > 3);
+        let path = tmp.path().to_str().unwrap();
+        let expected = all_ids(path).await?;
+
+        let (reader, _) = HttpFcbReader::mock_from_file(path).await?;
+        let mut iter = reader.select_all().await?;
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            ids.push(iter.next().await?.expect("feature").cj_feature()?.id);
+        }
+        // prefetched features are not lost
+        iter.prefetch(1).await?;
+        let checkpoint = Checkpoint::from_json(&iter.checkpoint().to_json()?)?;
+        assert_eq!(checkpoint.features_read, 2);
+        assert_eq!(checkpoint.remaining.len(), feature_count - 2);
+
+        let (reader, _) = HttpFcbReader::mock_from_file(path).await?;
+        let mut iter = reader.resume(&checkpoint)?;
+        while let Some(feature) = iter.next().await? {
+            ids.push(feature.cj_feature()?.id);
+        }
+        assert_eq!(ids, expected);
+
+        for tampered in [
+            Checkpoint {
+                version: CHECKPOINT_VERSION + 1,
+                ..checkpoint.clone()
+            },
+            Checkpoint {
+                header_hash: checkpoint.header_hash ^ 1,
+                ..checkpoint.clone()
+            },
+        ] {
+            let (reader, _) = HttpFcbReader::mock_from_file(path).await?;
+            assert!(matches!(
+                reader.resume(&tampered),
+                Err(Error::InvalidCheckpoint(_))
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,145 @@
+use super::client::sleep;
+use crate::retry::RetryPolicy;
+use bytes::Bytes;
+use http_range_client::AsyncHttpRangeClient;
+use log::debug;
+use std::future::Future;
+
+/// HTTP client retrying transient failures of the wrapped client with exponential backoff
+pub struct RetryClient<T: AsyncHttpRangeClient> {
+    inner: T,
+    policy: RetryPolicy,
+}
+
+impl<T: AsyncHttpRangeClient> RetryClient<T> {
+    pub fn new(inner: T, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Sends `request` until it succeeds, fails with an error that is not retryable or the
+    /// retries of the policy are used up. `what` names the request in the log.
+    async fn with_retry<R, F: Future<Output = http_range_client::Result<R>>>(
+        &self,
+        what: &str,
+        request: impl Fn() -> F,
+    ) -> http_range_client::Result<R> {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Err(e) if attempt < self.policy.max_retries && RetryPolicy::is_retryable(&e) => {
+                    let delay = self.policy.backoff(attempt, rand::random::<f64>());
+                    attempt += 1;
+                    debug!(
+                        "{what} failed: {e}, retry {attempt}/{} in {delay:?}",
+                        self.policy.max_retries
+                    );
+                    sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: AsyncHttpRangeClient + Send + Sync> AsyncHttpRangeClient for RetryClient<T> {
+    async fn get_range(&self, url: &str, range: &str) -> http_range_client::Result<Bytes> {
+        self.with_retry(&format!("range request {range}"), || {
+            self.inner.get_range(url, range)
+        })
+        .await
+    }
+
+    async fn head_response_header(
+        &self,
+        url: &str,
+        header: &str,
+    ) -> http_range_client::Result<Option<String>> {
+        self.with_retry("head request", || {
+            self.inner.head_response_header(url, header)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_range_client::HttpError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// Fails with `status` for the first `failures` requests
+    struct FlakyClient {
+        failures: u32,
+        status: u16,
+        calls: AtomicU32,
+    }
+
+    #[async_trait::async_trait]
+    impl AsyncHttpRangeClient for FlakyClient {
+        async fn get_range(&self, _url: &str, _range: &str) -> http_range_client::Result<Bytes> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(HttpError::HttpStatus(self.status))
+            } else {
+                Ok(Bytes::from_static(b"ok"))
+            }
+        }
+
+        async fn head_response_header(
+            &self,
+            _url: &str,
+            _header: &str,
+        ) -> http_range_client::Result<Option<String>> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(HttpError::HttpStatus(self.status))
+            } else {
+                Ok(Some("2".to_string()))
+            }
+        }
+    }
+
+    fn flaky(failures: u32, status: u16) -> RetryClient<FlakyClient> {
+        RetryClient::new(
+            FlakyClient {
+                failures,
+                status,
+                calls: AtomicU32::new(0),
+            },
+            RetryPolicy::new(3, Duration::from_millis(1)),
+        )
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors() {
+        let client = flaky(2, 503);
+        assert!(client.get_range("url", "bytes=0-1").await.is_ok());
+        assert_eq!(client.inner.calls.load(Ordering::SeqCst), 3);
+
+        let client = flaky(10, 502);
+        assert!(client.get_range("url", "bytes=0-1").await.is_err());
+        assert_eq!(client.inner.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn retries_head_requests() {
+        let client = flaky(2, 503);
+        let length = client.head_response_header("url", "content-length").await;
+        assert_eq!(length.unwrap(), Some("2".to_string()));
+        assert_eq!(client.inner.calls.load(Ordering::SeqCst), 3);
+
+        let client = flaky(1, 404);
+        assert!(client
+            .head_response_header("url", "content-length")
+            .await
+            .is_err());
+        assert_eq!(client.inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let client = flaky(1, 404);
+        assert!(client.get_range("url", "bytes=0-1").await.is_err());
+        assert_eq!(client.inner.calls.load(Ordering::SeqCst), 1);
+    }
+}
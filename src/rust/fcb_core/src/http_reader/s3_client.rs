@@ -0,0 +1,128 @@
+use crate::error::Result;
+use crate::http_reader::HttpFcbReader;
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{DisplayErrorContext, SdkError};
+use bytes::Bytes;
+use http_range_client::{AsyncHttpRangeClient, HttpError};
+use tracing::trace;
+
+/// Connection settings for S3-compatible object storage.
+///
+/// Credentials are resolved with the default AWS credential chain (environment,
+/// profile, web identity, instance metadata). Unset fields fall back to the same chain.
+#[derive(Debug, Clone, Default)]
+pub struct S3Config {
+    /// Region of the bucket, e.g. `eu-central-1`
+    pub region: Option<String>,
+    /// Custom endpoint for S3-compatible services such as MinIO
+    pub endpoint_url: Option<String>,
+    /// Use path-style addressing (`endpoint/bucket/key`), required by most MinIO setups
+    pub force_path_style: bool,
+    /// Named profile from the shared AWS config files
+    pub profile: Option<String>,
+}
+
+/// Range client reading a single object with `GetObject` requests
+pub struct S3RangeClient {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    key: String,
+}
+
+impl S3RangeClient {
+    pub async fn new(bucket: &str, key: &str, config: S3Config) -> Self {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = config.region {
+            loader = loader.region(Region::new(region));
+        }
+        if let Some(endpoint_url) = config.endpoint_url {
+            loader = loader.endpoint_url(endpoint_url);
+        }
+        if let Some(profile) = config.profile {
+            loader = loader.profile_name(profile);
+        }
+        let sdk_config = loader.load().await;
+        let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            .force_path_style(config.force_path_style)
+            .build();
+        Self {
+            client: aws_sdk_s3::Client::from_conf(s3_config),
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.key)
+    }
+}
+
+/// Keep the status code of failed responses so transient errors can be told apart
+fn to_http_error<E>(err: SdkError<E, HttpResponse>) -> HttpError
+where
+    E: std::error::Error + 'static,
+{
+    match err.raw_response().map(|r| r.status().as_u16()) {
+        Some(status) if status >= 400 => HttpError::HttpStatus(status),
+        _ => HttpError::HttpError(DisplayErrorContext(&err).to_string()),
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncHttpRangeClient for S3RangeClient {
+    async fn get_range(&self, _url: &str, range: &str) -> http_range_client::Result<Bytes> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .range(range)
+            .send()
+            .await
+            .map_err(to_http_error)?;
+        output
+            .body
+            .collect()
+            .await
+            .map(|data| data.into_bytes())
+            .map_err(|e| HttpError::HttpError(e.to_string()))
+    }
+
+    async fn head_response_header(
+        &self,
+        _url: &str,
+        header: &str,
+    ) -> http_range_client::Result<Option<String>> {
+        let output = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .send()
+            .await
+            .map_err(to_http_error)?;
+        let value = match header.to_ascii_lowercase().as_str() {
+            "content-length" => output.content_length().map(|len| len.to_string()),
+            "content-type" => output.content_type().map(str::to_string),
+            "etag" => output.e_tag().map(str::to_string),
+            "last-modified" => output.last_modified().map(|t| t.to_string()),
+            _ => None,
+        };
+        Ok(value)
+    }
+}
+
+impl HttpFcbReader<S3RangeClient> {
+    /// Open an FCB object stored in S3 or an S3-compatible service.
+    pub async fn open_s3(
+        bucket: &str,
+        key: &str,
+        config: S3Config,
+    ) -> Result<HttpFcbReader<S3RangeClient>> {
+        trace!("starting: opening s3 reader, reading header");
+        let client = S3RangeClient::new(bucket, key, config).await;
+        let url = client.url();
+        Self::_open(client, &url).await
+    }
+}
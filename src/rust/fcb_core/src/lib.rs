@@ -24,30 +24,51 @@
 //! This project is licensed under the MIT License.
 //! FlatGeobuf portions remain under their original BSD 2-Clause License.
 
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub mod async_reader;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod checksum;
 mod cj_utils;
 mod cjerror;
 mod const_vars;
 pub mod error;
 pub mod fb;
+#[cfg(feature = "http")]
+mod http_metrics;
 #[allow(dead_code, unused_imports, clippy::all, warnings)]
 #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
 mod http_reader;
 
 pub mod packed_rtree;
+mod progress;
+#[cfg(feature = "python")]
+mod python;
 mod reader;
+#[cfg(feature = "http")]
+mod retry;
+pub mod semantic_graph;
 pub mod static_btree;
+pub mod stats;
 mod writer;
 
 pub use cj_utils::*;
 pub use const_vars::*;
 pub use error::*;
 pub use fb::*;
+#[cfg(feature = "http")]
+pub use http_metrics::*;
 pub use packed_rtree::Query as SpatialQuery;
 pub use packed_rtree::*;
+pub use progress::*;
 pub use reader::*;
+#[cfg(feature = "http")]
+pub use retry::*;
+pub use semantic_graph::SemanticGraph;
 pub use static_btree::{
-    Entry, FixedStringKey, Float, Key, KeyType, MemoryIndex, MemoryMultiIndex, MultiIndex,
-    Operator, Query, QueryCondition, StreamIndex, StreamMultiIndex,
+    ConditionPlan, Entry, FixedStringKey, Float, Key, KeyType, Literal, LogicalOp, MemoryIndex,
+    MemoryMultiIndex, MultiIndex, Nullable, Operator, ParseError, Query, QueryCacheStats,
+    QueryCondition, QueryExpr, QueryPlan, SharedMemoryMultiIndex, StreamIndex, StreamMultiIndex,
 };
 pub use writer::*;
 
@@ -55,5 +76,79 @@ pub use writer::*;
 pub use http_reader::*;
 
 pub fn check_magic_bytes(bytes: &[u8]) -> bool {
-    bytes[0..3] == MAGIC_BYTES[0..3] && bytes[4..7] == MAGIC_BYTES[4..7] && bytes[3] <= VERSION
+    parse_magic_bytes(bytes).is_ok()
+}
+
+/// Returns the file format version stored in the magic bytes
+///
+/// Versions outside of `MIN_SUPPORTED_VERSION..=VERSION` are rejected with the version found, so
+/// that files written by a newer library are not reported as missing magic bytes.
+pub fn parse_magic_bytes(bytes: &[u8]) -> std::result::Result<u8, MagicBytesError> {
+    if bytes.len() < MAGIC_BYTES_SIZE
+        || bytes[0..3] != MAGIC_BYTES[0..3]
+        || bytes[4..7] != MAGIC_BYTES[4..7]
+    {
+        return Err(MagicBytesError::Missing);
+    }
+    let found = bytes[3];
+    if found > VERSION {
+        return Err(MagicBytesError::VersionTooNew {
+            found,
+            max_supported: VERSION,
+        });
+    }
+    if found < MIN_SUPPORTED_VERSION {
+        return Err(MagicBytesError::VersionTooOld {
+            found,
+            min_supported: MIN_SUPPORTED_VERSION,
+        });
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_magic_bytes() {
+        assert_eq!(parse_magic_bytes(&MAGIC_BYTES), Ok(VERSION));
+        assert!(check_magic_bytes(&MAGIC_BYTES));
+
+        let mut newer = MAGIC_BYTES;
+        newer[3] = VERSION + 1;
+        assert_eq!(
+            parse_magic_bytes(&newer),
+            Err(MagicBytesError::VersionTooNew {
+                found: VERSION + 1,
+                max_supported: VERSION
+            })
+        );
+        assert!(!check_magic_bytes(&newer));
+        assert!(matches!(
+            Error::from(parse_magic_bytes(&newer).unwrap_err()),
+            Error::UnsupportedVersion(_)
+        ));
+
+        let mut older = MAGIC_BYTES;
+        older[3] = MIN_SUPPORTED_VERSION - 1;
+        assert!(matches!(
+            parse_magic_bytes(&older),
+            Err(MagicBytesError::VersionTooOld { .. })
+        ));
+
+        // version 1 files are read with their 2D R-tree nodes
+        let mut v1 = MAGIC_BYTES;
+        v1[3] = 1;
+        assert_eq!(parse_magic_bytes(&v1), Ok(1));
+
+        assert_eq!(
+            parse_magic_bytes(b"fgb\x03fgb\x00"),
+            Err(MagicBytesError::Missing)
+        );
+        assert_eq!(
+            parse_magic_bytes(&MAGIC_BYTES[..4]),
+            Err(MagicBytesError::Missing)
+        );
+    }
 }
@@ -14,10 +14,13 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use core::f64;
 pub use error::Error;
+
+use crate::reader::{deserializer::header_transform, reader_trait::Seekable, FeatureIter};
+use crate::CHECKSUM_SIZE;
 #[cfg(feature = "http")]
 use http_range_client::{AsyncBufferedHttpRangeClient, AsyncHttpRangeClient};
 use std::cmp::min;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 use std::mem::size_of;
 use std::ops::Range;
@@ -32,8 +35,10 @@ mod error;
 pub struct NodeItem {
     pub min_x: f64,
     pub min_y: f64,
+    pub min_z: f64,
     pub max_x: f64,
     pub max_y: f64,
+    pub max_z: f64,
     /// Byte offset in feature data section
     pub offset: u64,
 }
@@ -45,12 +50,27 @@ impl NodeItem {
     pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> NodeItem {
         Self::bounds(min_x, min_y, max_x, max_y)
     }
+
+    /// 2D bounds, unbounded in Z
     pub fn bounds(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> NodeItem {
+        Self::bounds_3d(min_x, min_y, f64::NEG_INFINITY, max_x, max_y, f64::INFINITY)
+    }
+
+    pub fn bounds_3d(
+        min_x: f64,
+        min_y: f64,
+        min_z: f64,
+        max_x: f64,
+        max_y: f64,
+        max_z: f64,
+    ) -> NodeItem {
         NodeItem {
             min_x,
             min_y,
+            min_z,
             max_x,
             max_y,
+            max_z,
             offset: 0,
         }
     }
@@ -59,8 +79,10 @@ impl NodeItem {
         NodeItem {
             min_x: f64::INFINITY,
             min_y: f64::INFINITY,
+            min_z: f64::INFINITY,
             max_x: f64::NEG_INFINITY,
             max_y: f64::NEG_INFINITY,
+            max_z: f64::NEG_INFINITY,
             offset,
         }
     }
@@ -69,21 +91,42 @@ impl NodeItem {
         Ok(NodeItem {
             min_x: rdr.read_f64::<LittleEndian>()?,
             min_y: rdr.read_f64::<LittleEndian>()?,
+            min_z: rdr.read_f64::<LittleEndian>()?,
             max_x: rdr.read_f64::<LittleEndian>()?,
             max_y: rdr.read_f64::<LittleEndian>()?,
+            max_z: rdr.read_f64::<LittleEndian>()?,
             offset: rdr.read_u64::<LittleEndian>()?,
         })
     }
 
-    fn from_bytes(raw: &[u8]) -> Result<Self, Error> {
-        Self::from_reader(&mut Cursor::new(raw))
+    /// Reads a node stored with `layout`, nodes without Z bounds are unbounded in Z
+    pub fn from_reader_with_layout(mut rdr: impl Read, layout: NodeLayout) -> Result<Self, Error> {
+        match layout {
+            NodeLayout::Xyz => Self::from_reader(rdr),
+            NodeLayout::Xy => {
+                let mut node = Self::bounds(
+                    rdr.read_f64::<LittleEndian>()?,
+                    rdr.read_f64::<LittleEndian>()?,
+                    rdr.read_f64::<LittleEndian>()?,
+                    rdr.read_f64::<LittleEndian>()?,
+                );
+                node.offset = rdr.read_u64::<LittleEndian>()?;
+                Ok(node)
+            }
+        }
+    }
+
+    fn from_bytes(raw: &[u8], layout: NodeLayout) -> Result<Self, Error> {
+        Self::from_reader_with_layout(&mut Cursor::new(raw), layout)
     }
 
     pub fn write<W: Write>(&self, wtr: &mut W) -> std::io::Result<()> {
         wtr.write_f64::<LittleEndian>(self.min_x)?;
         wtr.write_f64::<LittleEndian>(self.min_y)?;
+        wtr.write_f64::<LittleEndian>(self.min_z)?;
         wtr.write_f64::<LittleEndian>(self.max_x)?;
         wtr.write_f64::<LittleEndian>(self.max_y)?;
+        wtr.write_f64::<LittleEndian>(self.max_z)?;
         wtr.write_u64::<LittleEndian>(self.offset)?;
         Ok(())
     }
@@ -96,6 +139,10 @@ impl NodeItem {
         self.max_y - self.min_y
     }
 
+    pub fn depth(&self) -> f64 {
+        self.max_z - self.min_z
+    }
+
     pub fn sum(mut a: NodeItem, b: &NodeItem) -> NodeItem {
         a.expand(b);
         a
@@ -114,6 +161,12 @@ impl NodeItem {
         if r.max_y > self.max_y {
             self.max_y = r.max_y;
         }
+        if r.min_z < self.min_z {
+            self.min_z = r.min_z;
+        }
+        if r.max_z > self.max_z {
+            self.max_z = r.max_z;
+        }
     }
 
     pub fn expand_xy(&mut self, x: f64, y: f64) {
@@ -131,6 +184,16 @@ impl NodeItem {
         }
     }
 
+    pub fn expand_xyz(&mut self, x: f64, y: f64, z: f64) {
+        self.expand_xy(x, y);
+        if z < self.min_z {
+            self.min_z = z;
+        }
+        if z > self.max_z {
+            self.max_z = z;
+        }
+    }
+
     pub fn intersects(&self, r: &NodeItem) -> bool {
         if self.max_x < r.min_x {
             return false;
@@ -144,6 +207,12 @@ impl NodeItem {
         if self.min_y > r.max_y {
             return false;
         }
+        if self.max_z < r.min_z {
+            return false;
+        }
+        if self.min_z > r.max_z {
+            return false;
+        }
         true
     }
 
@@ -179,11 +248,45 @@ impl NodeItem {
     }
 }
 
+/// Byte layout of the R-tree nodes, which depends on the file format version
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NodeLayout {
+    /// Version 1: 2D bounds and offset. Nodes are read as unbounded in Z.
+    Xy,
+    /// Version 2 onwards: 3D bounds and offset, the layout of [`NodeItem::write`]
+    #[default]
+    Xyz,
+}
+
+impl NodeLayout {
+    /// Layout of the nodes of a file with format version `version`, as returned by
+    /// [`crate::parse_magic_bytes`]
+    pub fn for_version(version: u8) -> Self {
+        if version < 2 {
+            NodeLayout::Xy
+        } else {
+            NodeLayout::Xyz
+        }
+    }
+
+    /// Size in bytes of a node
+    pub fn item_size(self) -> usize {
+        match self {
+            NodeLayout::Xy => 4 * size_of::<f64>() + size_of::<u64>(),
+            NodeLayout::Xyz => size_of::<NodeItem>(),
+        }
+    }
+}
+
 /// Read full capacity of vec from data stream
-fn read_node_vec(node_items: &mut Vec<NodeItem>, mut data: impl Read) -> Result<(), Error> {
+fn read_node_vec(
+    node_items: &mut Vec<NodeItem>,
+    mut data: impl Read,
+    layout: NodeLayout,
+) -> Result<(), Error> {
     node_items.clear();
     for _ in 0..node_items.capacity() {
-        node_items.push(NodeItem::from_reader(&mut data)?);
+        node_items.push(NodeItem::from_reader_with_layout(&mut data, layout)?);
     }
     Ok(())
 }
@@ -194,12 +297,13 @@ fn read_node_items<R: Read + Seek>(
     base: u64,
     node_index: usize,
     length: usize,
+    layout: NodeLayout,
 ) -> Result<Vec<NodeItem>, Error> {
     let mut node_items = Vec::with_capacity(length);
     data.seek(SeekFrom::Start(
-        base + (node_index * size_of::<NodeItem>()) as u64,
+        base + (node_index * layout.item_size()) as u64,
     ))?;
-    read_node_vec(&mut node_items, data)?;
+    read_node_vec(&mut node_items, data, layout)?;
     Ok(node_items)
 }
 
@@ -209,9 +313,10 @@ async fn read_http_node_items<T: AsyncHttpRangeClient>(
     client: &mut AsyncBufferedHttpRangeClient<T>,
     base: usize,
     node_ids: &Range<usize>,
+    layout: NodeLayout,
 ) -> Result<Vec<NodeItem>, Error> {
-    let begin = base + node_ids.start * size_of::<NodeItem>();
-    let length = node_ids.len() * size_of::<NodeItem>();
+    let begin = base + node_ids.start * layout.item_size();
+    let length = node_ids.len() * layout.item_size();
     let bytes = client
         // we've  already determined precisely which nodes to fetch - no need for extra.
         .min_req_size(0)
@@ -220,8 +325,8 @@ async fn read_http_node_items<T: AsyncHttpRangeClient>(
 
     let mut node_items = Vec::with_capacity(node_ids.len());
     debug_assert_eq!(bytes.len(), length);
-    for node_item_bytes in bytes.chunks(size_of::<NodeItem>()) {
-        node_items.push(NodeItem::from_bytes(node_item_bytes)?);
+    for node_item_bytes in bytes.chunks(layout.item_size()) {
+        node_items.push(NodeItem::from_bytes(node_item_bytes, layout)?);
     }
     Ok(node_items)
 }
@@ -229,8 +334,108 @@ async fn read_http_node_items<T: AsyncHttpRangeClient>(
 #[derive(Debug, Clone, Copy)]
 pub enum Query {
     BBox(f64, f64, f64, f64),
+    /// min_x, min_y, min_z, max_x, max_y, max_z
+    BBox3D(f64, f64, f64, f64, f64, f64),
     PointIntersects(f64, f64),
     PointNearest(f64, f64),
+    /// The `k` features nearest to the point, in file order
+    KNearest(f64, f64, usize),
+}
+
+impl Query {
+    /// Bounds of a bounding box query, `None` for point queries
+    fn bbox(&self) -> Option<NodeItem> {
+        match *self {
+            Query::BBox(min_x, min_y, max_x, max_y) => {
+                Some(NodeItem::bounds(min_x, min_y, max_x, max_y))
+            }
+            Query::BBox3D(min_x, min_y, min_z, max_x, max_y, max_z) => Some(NodeItem::bounds_3d(
+                min_x, min_y, min_z, max_x, max_y, max_z,
+            )),
+            Query::PointIntersects(..) | Query::PointNearest(..) | Query::KNearest(..) => None,
+        }
+    }
+
+    /// Point and number of neighbours of a nearest neighbour query
+    fn nearest(&self) -> Option<(f64, f64, usize)> {
+        match *self {
+            Query::PointNearest(x, y) => Some((x, y, 1)),
+            Query::KNearest(x, y, k) => Some((x, y, k)),
+            _ => None,
+        }
+    }
+
+    /// Whether a node matches a bounding box or point intersection query, always false for
+    /// nearest neighbour queries
+    fn matches(&self, node: &NodeItem) -> bool {
+        match *self {
+            Query::PointIntersects(x, y) => node.contains_point(x, y),
+            _ => self.bbox().is_some_and(|bounds| bounds.intersects(node)),
+        }
+    }
+}
+
+/// The best `k` leaf items of a nearest neighbour search, ranked by centroid distance
+/// with the bounding box distance as tie-breaker
+struct NearestItems<T> {
+    k: usize,
+    /// (centroid distance, bbox distance, feature offset, item)
+    items: Vec<(f64, f64, u64, T)>,
+}
+
+impl<T> NearestItems<T> {
+    fn new(k: usize) -> Self {
+        NearestItems {
+            k,
+            items: Vec::with_capacity(k),
+        }
+    }
+
+    /// Nodes and items farther than this can't improve the result
+    fn max_distance(&self) -> f64 {
+        if self.items.len() < self.k {
+            return f64::INFINITY;
+        }
+        self.items
+            .iter()
+            .map(|(centroid_dist, ..)| *centroid_dist)
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    fn push(&mut self, node_item: &NodeItem, x: f64, y: f64, item: T) {
+        if self.k == 0
+            || self
+                .items
+                .iter()
+                .any(|(.., offset, _)| *offset == node_item.offset)
+        {
+            return;
+        }
+        let rank = (
+            node_item.centroid_distance_squared(x, y),
+            node_item.min_distance_squared(x, y),
+        );
+        if self.items.len() < self.k {
+            self.items.push((rank.0, rank.1, node_item.offset, item));
+            return;
+        }
+        let (worst, worst_rank) = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, (c, b, ..))| (i, (*c, *b)))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .expect("k > 0");
+        if rank < worst_rank {
+            self.items[worst] = (rank.0, rank.1, node_item.offset, item);
+        }
+    }
+
+    /// Items sorted by feature offset, as feature iterators only seek forward
+    fn into_sorted(mut self) -> Vec<T> {
+        self.items.sort_by_key(|(.., offset, _)| *offset);
+        self.items.into_iter().map(|(.., item)| item).collect()
+    }
 }
 
 #[derive(Debug)]
@@ -300,13 +505,66 @@ fn hilbert(x: u32, y: u32) -> u32 {
     (i1 << 1) | i0
 }
 
-fn hilbert_bbox(r: &NodeItem, hilbert_max: u32, extent: &NodeItem) -> u32 {
+/// 3D Hilbert index of 16 bit coordinates, after Skilling's "Programming the Hilbert curve" (2004)
+fn hilbert_3d(x: u32, y: u32, z: u32) -> u64 {
+    const BITS: u32 = 16;
+    let mut p = [x, y, z];
+
+    // inverse undo excess work
+    let mut q = 1 << (BITS - 1);
+    while q > 1 {
+        let r = q - 1;
+        for i in 0..p.len() {
+            if p[i] & q != 0 {
+                p[0] ^= r;
+            } else {
+                let t = (p[0] ^ p[i]) & r;
+                p[0] ^= t;
+                p[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+
+    // gray encode
+    p[1] ^= p[0];
+    p[2] ^= p[1];
+    let mut t = 0;
+    let mut q = 1 << (BITS - 1);
+    while q > 1 {
+        if p[2] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for v in p.iter_mut() {
+        *v ^= t;
+    }
+
+    // interleave the transposed bits, most significant first
+    let mut h = 0u64;
+    for b in (0..BITS).rev() {
+        for v in p {
+            h = (h << 1) | ((v >> b) & 1) as u64;
+        }
+    }
+    h
+}
+
+fn hilbert_bbox(r: &NodeItem, hilbert_max: u32, extent: &NodeItem) -> u64 {
     // calculate bbox center and scale to hilbert_max
     let x = (hilbert_max as f64 * ((r.min_x + r.max_x) / 2.0 - extent.min_x) / extent.width())
         .floor() as u32;
     let y = (hilbert_max as f64 * ((r.min_y + r.max_y) / 2.0 - extent.min_y) / extent.height())
         .floor() as u32;
-    hilbert(x, y)
+    // flat or 2D data keeps the 2D curve
+    let depth = extent.depth();
+    if !depth.is_finite() || depth <= 0.0 {
+        return hilbert(x, y) as u64;
+    }
+    let z =
+        (hilbert_max as f64 * ((r.min_z + r.max_z) / 2.0 - extent.min_z) / depth).floor() as u32;
+    hilbert_3d(x, y, z)
 }
 
 pub fn hilbert_sort(items: &mut [NodeItem], extent: &NodeItem) {
@@ -408,8 +666,8 @@ impl PackedRTree {
         }
     }
 
-    fn read_data(&mut self, data: impl Read) -> Result<(), Error> {
-        read_node_vec(&mut self.node_items, data)?;
+    fn read_data(&mut self, data: impl Read, layout: NodeLayout) -> Result<(), Error> {
+        read_node_vec(&mut self.node_items, data, layout)?;
         for node in &self.node_items {
             self.extent.expand(node)
         }
@@ -429,7 +687,7 @@ impl PackedRTree {
                 .min_req_size(min_req_size)
                 .get_range(pos, size_of::<NodeItem>())
                 .await?;
-            let n = NodeItem::from_bytes(bytes)?;
+            let n = NodeItem::from_bytes(bytes, NodeLayout::Xyz)?;
             self.extent.expand(&n);
             self.node_items[i] = n;
             pos += size_of::<NodeItem>();
@@ -462,10 +720,12 @@ impl PackedRTree {
         Ok(tree)
     }
 
+    /// Reads a whole R-tree whose nodes are stored with `layout`
     pub fn from_buf(
         data: impl Read,
         num_items: usize,
         node_size: u16,
+        layout: NodeLayout,
     ) -> Result<PackedRTree, Error> {
         let node_size = node_size.clamp(2u16, 65535u16);
         let level_bounds = PackedRTree::generate_level_bounds(num_items, node_size);
@@ -480,7 +740,7 @@ impl PackedRTree {
             branching_factor: node_size,
             level_bounds,
         };
-        tree.read_data(data)?;
+        tree.read_data(data, layout)?;
         Ok(tree)
     }
 
@@ -512,9 +772,9 @@ impl PackedRTree {
             .start;
 
         match query {
-            Query::BBox(min_x, min_y, max_x, max_y) => {
+            Query::BBox(..) | Query::BBox3D(..) => {
                 // Standard bounding box query
-                let bounds = NodeItem::bounds(min_x, min_y, max_x, max_y);
+                let bounds = query.bbox().expect("bounding box query");
                 let mut results = Vec::new();
                 let mut queue = VecDeque::new();
                 queue.push_back((0, self.level_bounds.len() - 1));
@@ -581,9 +841,10 @@ impl PackedRTree {
                 }
                 Ok(results)
             }
-            Query::PointNearest(x, y) => {
+            Query::PointNearest(..) | Query::KNearest(..) => {
                 // Nearest neighbor query
                 // We use a priority queue to visit nodes in order of minimum distance
+                let (x, y, k) = query.nearest().expect("nearest query");
                 use std::cmp::Reverse;
                 use std::collections::BinaryHeap;
 
@@ -608,7 +869,7 @@ impl PackedRTree {
                     }
                 }
 
-                let mut nearest: Option<(f64, SearchResultItem)> = None;
+                let mut nearest = NearestItems::new(k);
                 let mut queue = BinaryHeap::new();
 
                 // Start with the root node
@@ -619,12 +880,9 @@ impl PackedRTree {
                 }));
 
                 while let Some(Reverse(next)) = queue.pop() {
-                    // If we have a nearest item and its distance is less than this node's,
-                    // we can stop searching
-                    if let Some((best_dist, _)) = nearest {
-                        if next.distance > best_dist {
-                            break;
-                        }
+                    // Once k items are found, nodes farther than all of them can be skipped
+                    if next.distance > nearest.max_distance() {
+                        break;
                     }
 
                     let node_index = next.node_index;
@@ -641,11 +899,9 @@ impl PackedRTree {
                         let node_item = &self.node_items[pos];
                         let dist = node_item.min_distance_squared(x, y);
 
-                        // If we have a nearest item, only consider this node if it's closer
-                        if let Some((best_dist, _)) = nearest {
-                            if dist >= best_dist {
-                                continue;
-                            }
+                        // only consider nodes that may be closer than the current k items
+                        if dist > nearest.max_distance() {
+                            continue;
                         }
 
                         if is_leaf_node {
@@ -656,15 +912,7 @@ impl PackedRTree {
                             };
 
                             // For leaf nodes, use centroid distance as the final measure
-                            let centroid_dist = node_item.centroid_distance_squared(x, y);
-
-                            match nearest {
-                                None => nearest = Some((centroid_dist, result)),
-                                Some((best_dist, _)) if centroid_dist < best_dist => {
-                                    nearest = Some((centroid_dist, result))
-                                }
-                                _ => {}
-                            }
+                            nearest.push(node_item, x, y, result);
                         } else {
                             // Add this node to the queue with its minimum distance
                             queue.push(Reverse(QueueItem {
@@ -676,18 +924,19 @@ impl PackedRTree {
                     }
                 }
 
-                // Return the nearest item, or empty vector if none found
-                Ok(nearest.map(|(_, item)| vec![item]).unwrap_or_default())
+                Ok(nearest.into_sorted())
             }
         }
     }
 
-    /// Search the R-Tree using a specific query type with streaming
+    /// Search the R-Tree using a specific query type with streaming, reading nodes stored with
+    /// `layout`
     pub fn stream_search<R: Read + Seek>(
         data: &mut R,
         num_items: usize,
         node_size: u16,
         query: Query,
+        layout: NodeLayout,
     ) -> Result<Vec<SearchResultItem>, Error> {
         let level_bounds = PackedRTree::generate_level_bounds(num_items, node_size);
         let Range {
@@ -701,8 +950,8 @@ impl PackedRTree {
         let index_base = data.stream_position()?;
 
         match query {
-            Query::BBox(min_x, min_y, max_x, max_y) => {
-                let bounds = NodeItem::bounds(min_x, min_y, max_x, max_y);
+            Query::BBox(..) | Query::BBox3D(..) => {
+                let bounds = query.bbox().expect("bounding box query");
 
                 // use ordered search queue to make index traversal in sequential order
                 let mut queue = VecDeque::new();
@@ -716,7 +965,7 @@ impl PackedRTree {
                     // find the end index of the node
                     let end = min(node_index + node_size as usize, level_bounds[level].end);
                     let length = end - node_index;
-                    let node_items = read_node_items(data, index_base, node_index, length)?;
+                    let node_items = read_node_items(data, index_base, node_index, length, layout)?;
                     // search through child nodes
                     for pos in node_index..end {
                         let node_pos = pos - node_index;
@@ -738,7 +987,7 @@ impl PackedRTree {
 
                 // Skip rest of index
                 data.seek(SeekFrom::Start(
-                    index_base + (num_nodes * size_of::<NodeItem>()) as u64,
+                    index_base + (num_nodes * layout.item_size()) as u64,
                 ))?;
                 Ok(results)
             }
@@ -755,7 +1004,7 @@ impl PackedRTree {
                     // find the end index of the node
                     let end = min(node_index + node_size as usize, level_bounds[level].end);
                     let length = end - node_index;
-                    let node_items = read_node_items(data, index_base, node_index, length)?;
+                    let node_items = read_node_items(data, index_base, node_index, length, layout)?;
                     // search through child nodes
                     for pos in node_index..end {
                         let node_pos = pos - node_index;
@@ -777,11 +1026,12 @@ impl PackedRTree {
 
                 // Skip rest of index
                 data.seek(SeekFrom::Start(
-                    index_base + (num_nodes * size_of::<NodeItem>()) as u64,
+                    index_base + (num_nodes * layout.item_size()) as u64,
                 ))?;
                 Ok(results)
             }
-            Query::PointNearest(x, y) => {
+            Query::PointNearest(..) | Query::KNearest(..) => {
+                let (x, y, k) = query.nearest().expect("nearest query");
                 use std::cmp::Reverse;
                 use std::collections::BinaryHeap;
 
@@ -806,7 +1056,7 @@ impl PackedRTree {
                     }
                 }
 
-                let mut nearest: Option<(f64, SearchResultItem)> = None;
+                let mut nearest = NearestItems::new(k);
                 let mut queue = BinaryHeap::new();
 
                 // Start with the root node
@@ -817,12 +1067,9 @@ impl PackedRTree {
                 }));
 
                 while let Some(Reverse(next)) = queue.pop() {
-                    // If we have a nearest item and its distance is less than this node's,
-                    // we can stop searching
-                    if let Some((best_dist, _)) = nearest {
-                        if next.distance > best_dist {
-                            break;
-                        }
+                    // Once k items are found, nodes farther than all of them can be skipped
+                    if next.distance > nearest.max_distance() {
+                        break;
                     }
 
                     let node_index = next.node_index;
@@ -832,7 +1079,7 @@ impl PackedRTree {
                     // Get the node items
                     let end = min(node_index + node_size as usize, level_bounds[level].end);
                     let length = end - node_index;
-                    let node_items = read_node_items(data, index_base, node_index, length)?;
+                    let node_items = read_node_items(data, index_base, node_index, length, layout)?;
 
                     // search through child nodes
                     for pos in node_index..end {
@@ -840,11 +1087,9 @@ impl PackedRTree {
                         let node_item = &node_items[node_pos];
                         let dist = node_item.min_distance_squared(x, y);
 
-                        // If we have a nearest item, only consider this node if it's closer
-                        if let Some((best_dist, _)) = nearest {
-                            if dist >= best_dist {
-                                continue;
-                            }
+                        // only consider nodes that may be closer than the current k items
+                        if dist > nearest.max_distance() {
+                            continue;
                         }
 
                         if is_leaf_node {
@@ -854,15 +1099,7 @@ impl PackedRTree {
                             let result = SearchResultItem { offset, index };
 
                             // For leaf nodes, use centroid distance as the final measure
-                            let centroid_dist = node_item.centroid_distance_squared(x, y);
-
-                            match nearest {
-                                None => nearest = Some((centroid_dist, result)),
-                                Some((best_dist, _)) if centroid_dist < best_dist => {
-                                    nearest = Some((centroid_dist, result))
-                                }
-                                _ => {}
-                            }
+                            nearest.push(node_item, x, y, result);
                         } else {
                             // Add this node to the queue with its minimum distance
                             queue.push(Reverse(QueueItem {
@@ -876,20 +1113,71 @@ impl PackedRTree {
 
                 // Skip rest of index
                 data.seek(SeekFrom::Start(
-                    index_base + (num_nodes * size_of::<NodeItem>()) as u64,
+                    index_base + (num_nodes * layout.item_size()) as u64,
                 ))?;
 
-                // Return the nearest item, or empty vector if none found
-                Ok(nearest.map(|(_, item)| vec![item]).unwrap_or_default())
+                Ok(nearest.into_sorted())
             }
         }
     }
 
+    /// Count the items matching a query with streaming, without collecting them
+    ///
+    /// Nearest neighbour queries match `min(k, num_items)` items, the index is not read for them.
+    pub fn stream_count<R: Read + Seek>(
+        data: &mut R,
+        num_items: usize,
+        node_size: u16,
+        query: Query,
+        layout: NodeLayout,
+    ) -> Result<usize, Error> {
+        if num_items == 0 {
+            return Ok(0);
+        }
+        let level_bounds = PackedRTree::generate_level_bounds(num_items, node_size);
+        let num_nodes = level_bounds
+            .first()
+            .expect("RTree has at least one level when node_size >= 2 and num_items > 0")
+            .end;
+
+        // current position must be start of index
+        let index_base = data.stream_position()?;
+
+        let count = match query.nearest() {
+            Some((_, _, k)) => min(k, num_items),
+            None => {
+                let mut queue = VecDeque::new();
+                queue.push_back((0, level_bounds.len() - 1));
+                let mut count = 0;
+                while let Some((node_index, level)) = queue.pop_front() {
+                    let end = min(node_index + node_size as usize, level_bounds[level].end);
+                    let node_items =
+                        read_node_items(data, index_base, node_index, end - node_index, layout)?;
+                    for node_item in node_items.iter().filter(|item| query.matches(item)) {
+                        if level == 0 {
+                            count += 1;
+                        } else {
+                            queue.push_back((node_item.offset as usize, level - 1));
+                        }
+                    }
+                }
+                count
+            }
+        };
+
+        // Skip rest of index
+        data.seek(SeekFrom::Start(
+            index_base + (num_nodes * layout.item_size()) as u64,
+        ))?;
+        Ok(count)
+    }
+
     pub fn size(&self) -> usize {
         self.num_nodes() * size_of::<NodeItem>()
     }
 
-    pub fn index_size(num_items: usize, node_size: u16) -> usize {
+    /// Size in bytes of an R-tree over `num_items` items whose nodes are stored with `layout`
+    pub fn index_size(num_items: usize, node_size: u16, layout: NodeLayout) -> usize {
         assert!(node_size >= 2, "Node size must be at least 2");
         assert!(num_items > 0, "Cannot create empty tree");
         let node_size_min = node_size.clamp(2, 65535) as usize;
@@ -907,7 +1195,7 @@ impl PackedRTree {
                 break;
             }
         }
-        num_nodes * size_of::<NodeItem>()
+        num_nodes * layout.item_size()
     }
 
     /// Write all index nodes
@@ -922,6 +1210,70 @@ impl PackedRTree {
         self.extent.clone()
     }
 
+    /// Leaf items in storage order, each with the offset of its feature
+    pub fn leaf_nodes(&self) -> &[NodeItem] {
+        &self.node_items[self.num_nodes() - self.num_leaf_nodes..]
+    }
+
+    /// Count the items matching a query by fetching only the R-tree nodes, without building
+    /// the feature ranges of [`PackedRTree::http_stream_search`]
+    ///
+    /// Requests for nodes of the same level are merged when the gap between them is at most
+    /// `combine_request_threshold` bytes. Nearest neighbour queries match `min(k, num_items)`
+    /// items without any request.
+    #[cfg(feature = "http")]
+    pub async fn http_stream_count<T: AsyncHttpRangeClient>(
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        index_begin: usize,
+        num_items: usize,
+        branching_factor: u16,
+        query: Query,
+        combine_request_threshold: usize,
+        layout: NodeLayout,
+    ) -> Result<usize, Error> {
+        if num_items == 0 {
+            return Ok(0);
+        }
+        if let Some((_, _, k)) = query.nearest() {
+            return Ok(min(k, num_items));
+        }
+
+        let level_bounds = PackedRTree::generate_level_bounds(num_items, branching_factor);
+        let mut queue: VecDeque<(usize, Range<usize>)> = VecDeque::new();
+        queue.push_back((level_bounds.len() - 1, 0..1));
+        let mut count = 0;
+
+        while let Some((level, nodes)) = queue.pop_front() {
+            let node_items = read_http_node_items(client, index_begin, &nodes, layout).await?;
+            for node_item in node_items.iter().filter(|item| query.matches(item)) {
+                if level == 0 {
+                    count += 1;
+                    continue;
+                }
+                let children_level = level - 1;
+                let start = node_item.offset as usize;
+                let end = min(
+                    start + branching_factor as usize,
+                    level_bounds[children_level].end,
+                );
+                // Nodes in a merged gap belong to parents that didn't match, so they can't match
+                // either and are never counted
+                match queue.back_mut() {
+                    Some((tail_level, tail))
+                        if *tail_level == children_level
+                            && start >= tail.end
+                            && (start - tail.end) * layout.item_size()
+                                <= combine_request_threshold =>
+                    {
+                        tail.end = end;
+                    }
+                    _ => queue.push_back((children_level, start..end)),
+                }
+            }
+        }
+        Ok(count)
+    }
+
     #[cfg(feature = "http")]
     #[allow(clippy::too_many_arguments)]
     pub async fn http_stream_search<T: AsyncHttpRangeClient>(
@@ -932,6 +1284,7 @@ impl PackedRTree {
         branching_factor: u16,
         query: Query,
         combine_request_threshold: usize,
+        layout: NodeLayout,
     ) -> Result<Vec<HttpSearchResultItem>, Error> {
         use tracing::debug;
 
@@ -940,14 +1293,14 @@ impl PackedRTree {
         }
 
         let level_bounds = PackedRTree::generate_level_bounds(num_items, branching_factor);
-        let feature_begin =
-            index_begin + attr_index_size + PackedRTree::index_size(num_items, branching_factor);
+        let feature_begin = index_begin
+            + attr_index_size
+            + PackedRTree::index_size(num_items, branching_factor, layout);
 
         match query {
-            Query::BBox(min_x, min_y, max_x, max_y) => {
-                debug!("http_stream_search - index_begin: {index_begin}, feature_begin: {feature_begin} num_items: {num_items}, branching_factor: {branching_factor}, level_bounds: {level_bounds:?}, GPS bounds:[({min_x}, {min_y}), ({max_x},{max_y})]");
-
-                let bounds = NodeItem::bounds(min_x, min_y, max_x, max_y);
+            Query::BBox(..) | Query::BBox3D(..) => {
+                let bounds = query.bbox().expect("bounding box query");
+                debug!("http_stream_search - index_begin: {index_begin}, feature_begin: {feature_begin} num_items: {num_items}, branching_factor: {branching_factor}, level_bounds: {level_bounds:?}, bounds: {bounds:?}");
 
                 #[derive(Debug, PartialEq, Eq)]
                 struct NodeRange {
@@ -965,7 +1318,8 @@ impl PackedRTree {
                 while let Some(node_range) = queue.pop_front() {
                     debug!("next: {node_range:?}. {} items left in queue", queue.len());
                     let node_items =
-                        read_http_node_items(client, index_begin, &node_range.nodes).await?;
+                        read_http_node_items(client, index_begin, &node_range.nodes, layout)
+                            .await?;
                     for (node_pos, node_item) in node_items.iter().enumerate() {
                         if !bounds.intersects(node_item) {
                             continue;
@@ -1022,7 +1376,7 @@ impl PackedRTree {
                             let wasted_bytes = {
                                 if children_range.nodes.start >= tail.nodes.end {
                                     (children_range.nodes.start - tail.nodes.end)
-                                        * size_of::<NodeItem>()
+                                        * layout.item_size()
                                 } else {
                                     // To compute feature size, we fetch an extra leaf node, but computing
                                     // wasted_bytes for adjacent ranges will overflow in that case, so
@@ -1074,7 +1428,8 @@ impl PackedRTree {
                 while let Some(node_range) = queue.pop_front() {
                     debug!("next: {node_range:?}. {} items left in queue", queue.len());
                     let node_items =
-                        read_http_node_items(client, index_begin, &node_range.nodes).await?;
+                        read_http_node_items(client, index_begin, &node_range.nodes, layout)
+                            .await?;
                     for (node_pos, node_item) in node_items.iter().enumerate() {
                         if !node_item.contains_point(x, y) {
                             continue;
@@ -1124,7 +1479,7 @@ impl PackedRTree {
                             let wasted_bytes = {
                                 if children_range.nodes.start >= tail.nodes.end {
                                     (children_range.nodes.start - tail.nodes.end)
-                                        * size_of::<NodeItem>()
+                                        * layout.item_size()
                                 } else {
                                     debug_assert_eq!(
                                         children_range.nodes.start + 1,
@@ -1150,8 +1505,9 @@ impl PackedRTree {
                 }
                 Ok(results)
             }
-            Query::PointNearest(x, y) => {
-                debug!("http_stream_search nearest neighbor - index_begin: {index_begin}, feature_begin: {feature_begin} num_items: {num_items}, branching_factor: {branching_factor}, level_bounds: {level_bounds:?}, point: ({x}, {y})");
+            Query::PointNearest(..) | Query::KNearest(..) => {
+                let (x, y, k) = query.nearest().expect("nearest query");
+                debug!("http_stream_search nearest neighbor - index_begin: {index_begin}, feature_begin: {feature_begin} num_items: {num_items}, branching_factor: {branching_factor}, level_bounds: {level_bounds:?}, point: ({x}, {y}), k: {k}");
 
                 use std::cmp::Reverse;
                 use std::collections::BinaryHeap;
@@ -1177,7 +1533,7 @@ impl PackedRTree {
                     }
                 }
 
-                let mut nearest: Option<(f64, HttpSearchResultItem)> = None;
+                let mut nearest = NearestItems::new(k);
                 let mut queue = BinaryHeap::new();
 
                 // Start with the root node
@@ -1188,12 +1544,9 @@ impl PackedRTree {
                 }));
 
                 while let Some(Reverse(next)) = queue.pop() {
-                    // If we have a nearest item and its distance is less than this node's,
-                    // we can stop searching
-                    if let Some((best_dist, _)) = nearest {
-                        if next.distance > best_dist {
-                            break;
-                        }
+                    // Once k items are found, nodes farther than all of them can be skipped
+                    if next.distance > nearest.max_distance() {
+                        break;
                     }
 
                     debug!(
@@ -1203,22 +1556,19 @@ impl PackedRTree {
                         next.nodes,
                         queue.len()
                     );
-                    let node_items = read_http_node_items(client, index_begin, &next.nodes).await?;
+                    let node_items =
+                        read_http_node_items(client, index_begin, &next.nodes, layout).await?;
 
                     for (node_pos, node_item) in node_items.iter().enumerate() {
                         let dist = node_item.min_distance_squared(x, y);
 
-                        // If we have a nearest item, only consider this node if it's closer
-                        if let Some((best_dist, _)) = nearest {
-                            if dist >= best_dist {
-                                continue;
-                            }
+                        // only consider nodes that may be closer than the current k items
+                        if dist > nearest.max_distance() {
+                            continue;
                         }
 
                         if next.level == 0 {
                             // Leaf node - use centroid distance as the final measure
-                            let centroid_dist = node_item.centroid_distance_squared(x, y);
-
                             // Create range for the result
                             let start = feature_begin + node_item.offset as usize;
                             let result = if let Some(next_node_item) = &node_items.get(node_pos + 1)
@@ -1234,13 +1584,7 @@ impl PackedRTree {
                                 }
                             };
 
-                            match nearest {
-                                None => nearest = Some((centroid_dist, result)),
-                                Some((best_dist, _)) if centroid_dist < best_dist => {
-                                    nearest = Some((centroid_dist, result))
-                                }
-                                _ => {}
-                            }
+                            nearest.push(node_item, x, y, result);
                         } else {
                             // Not a leaf node - add children to the queue
                             let children_level = next.level - 1;
@@ -1264,10 +1608,65 @@ impl PackedRTree {
                     }
                 }
 
-                // Return the nearest item, or empty vector if none found
-                Ok(nearest.map(|(_, item)| vec![item]).unwrap_or_default())
+                Ok(nearest.into_sorted())
+            }
+        }
+    }
+}
+
+impl PackedRTree {
+    /// Builds an index over the features of a file written without one
+    ///
+    /// Every feature is read once, its bounding box is taken from its vertices with the header
+    /// transform applied. Returns the tree and its leaf items in Hilbert order, each with the
+    /// offset of its feature in the scanned file. The tree itself stores the offsets the
+    /// features get once written in that order. The node size of the header is used, or
+    /// [`PackedRTree::DEFAULT_NODE_SIZE`] if the file has none.
+    pub fn build_from_features<R: Read + Seek>(
+        iter: &mut FeatureIter<R, Seekable>,
+    ) -> crate::error::Result<(PackedRTree, Vec<NodeItem>)> {
+        let header = iter.header();
+        let (scale, translate) = header_transform(&header);
+        let node_size = match header.index_node_size() {
+            0 => PackedRTree::DEFAULT_NODE_SIZE,
+            n => n,
+        };
+        let checksum_size = if header.checksum() { CHECKSUM_SIZE } else { 0 };
+
+        let mut nodes = Vec::new();
+        let mut sizes = HashMap::new();
+        while let Some(feat) = iter.next()? {
+            let mut node = NodeItem::create(feat.current_offset());
+            for v in feat.cur_feature().vertices().iter().flat_map(|v| v.iter()) {
+                node.expand_xyz(
+                    v.x() as f64 * scale[0] + translate[0],
+                    v.y() as f64 * scale[1] + translate[1],
+                    v.z() as f64 * scale[2] + translate[2],
+                );
             }
+            sizes.insert(node.offset, (feat.cur_feature_len() + checksum_size) as u64);
+            nodes.push(node);
+        }
+        if nodes.is_empty() {
+            return Err(crate::error::Error::IndexCreationError(
+                "no features to index".to_string(),
+            ));
         }
+
+        let extent = calc_extent(&nodes);
+        hilbert_sort(&mut nodes, &extent);
+        let mut offset = 0;
+        let index_nodes = nodes
+            .iter()
+            .map(|node| {
+                let mut index_node = node.clone();
+                index_node.offset = offset;
+                offset += sizes[&node.offset];
+                index_node
+            })
+            .collect::<Vec<_>>();
+        let tree = PackedRTree::build(&index_nodes, &extent, node_size)?;
+        Ok((tree, nodes))
     }
 }
 
@@ -1398,6 +1797,7 @@ mod tests {
             nodes.len(),
             PackedRTree::DEFAULT_NODE_SIZE,
             Query::PointIntersects(0.5, 0.5),
+            NodeLayout::Xyz,
         )?;
         assert_eq!(
             results.len(),
@@ -1473,6 +1873,7 @@ mod tests {
             nodes.len(),
             PackedRTree::DEFAULT_NODE_SIZE,
             Query::PointNearest(0.0, 0.0),
+            NodeLayout::Xyz,
         )?;
         assert_eq!(
             results.len(),
@@ -1483,6 +1884,67 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_k_nearest_query() -> Result<()> {
+        // 10x10 grid of unit boxes, jittered to avoid distance ties
+        let mut nodes = Vec::new();
+        for i in 0..100 {
+            let x = (i % 10) as f64 * 3.0 + (i % 7) as f64 * 0.13;
+            let y = (i / 10) as f64 * 2.5 + (i % 3) as f64 * 0.07;
+            nodes.push(NodeItem::bounds(x, y, x + 1.0, y + 1.0));
+        }
+        let extent = calc_extent(&nodes);
+        hilbert_sort(&mut nodes, &extent);
+        let mut offset = 0;
+        for node in &mut nodes {
+            node.offset = offset;
+            offset += size_of::<NodeItem>() as u64;
+        }
+        let node_size = 4;
+        let tree = PackedRTree::build(&nodes, &extent, node_size)?;
+        let mut tree_data: Vec<u8> = Vec::new();
+        tree.stream_write(&mut tree_data)?;
+
+        for (x, y, k) in [
+            (0.0, 0.0, 1),
+            (13.71, 11.23, 5),
+            (-4.1, 30.3, 12),
+            (15.0, 12.0, 0),
+        ] {
+            let mut expected: Vec<usize> = (0..nodes.len()).collect();
+            expected.sort_by(|a, b| {
+                let da = nodes[*a].centroid_distance_squared(x, y);
+                let db = nodes[*b].centroid_distance_squared(x, y);
+                da.partial_cmp(&db).unwrap()
+            });
+            expected.truncate(k);
+            expected.sort_unstable();
+
+            let results = tree.search(Query::KNearest(x, y, k))?;
+            assert!(results.windows(2).all(|w| w[0].offset < w[1].offset));
+            let mut indices: Vec<usize> = results.iter().map(|r| r.index).collect();
+            indices.sort_unstable();
+            assert_eq!(indices, expected, "k={k} nearest to ({x}, {y})");
+
+            let mut reader = Cursor::new(&tree_data);
+            let results = PackedRTree::stream_search(
+                &mut reader,
+                nodes.len(),
+                node_size,
+                Query::KNearest(x, y, k),
+                NodeLayout::Xyz,
+            )?;
+            let mut indices: Vec<usize> = results.iter().map(|r| r.index).collect();
+            indices.sort_unstable();
+            assert_eq!(indices, expected, "stream k={k} nearest to ({x}, {y})");
+        }
+
+        // more neighbours than features
+        let results = tree.search(Query::KNearest(0.0, 0.0, 500))?;
+        assert_eq!(results.len(), nodes.len());
+        Ok(())
+    }
+
     #[test]
     fn test_node_item_helper_methods() -> Result<()> {
         // Test contains_point
@@ -1586,12 +2048,13 @@ mod tests {
         let res = tree.stream_write(&mut tree_data);
         assert!(res.is_ok());
         assert_eq!(tree_data.len(), (nodes.len() + 3) * size_of::<NodeItem>());
-        assert_eq!(size_of::<NodeItem>(), 40);
+        assert_eq!(size_of::<NodeItem>(), 56);
 
         let tree2 = PackedRTree::from_buf(
             &mut &tree_data[..],
             nodes.len(),
             PackedRTree::DEFAULT_NODE_SIZE,
+            NodeLayout::Xyz,
         )?;
         let list = tree2.search(Query::BBox(102.0, 102.0, 103.0, 103.0))?;
         assert_eq!(list.len(), 4);
@@ -1606,6 +2069,7 @@ mod tests {
             nodes.len(),
             PackedRTree::DEFAULT_NODE_SIZE,
             Query::BBox(102.0, 102.0, 103.0, 103.0),
+            NodeLayout::Xyz,
         )?;
         assert_eq!(list.len(), 4);
 
@@ -1613,6 +2077,85 @@ mod tests {
         let expected: Vec<usize> = vec![13, 14, 15, 16];
         assert_eq!(indexes, expected);
 
+        for (query, expected) in [
+            (Query::BBox(102.0, 102.0, 103.0, 103.0), 4),
+            (
+                Query::BBox(f64::MIN, f64::MIN, f64::MAX, f64::MAX),
+                nodes.len(),
+            ),
+            (Query::BBox(-10.0, -10.0, -5.0, -5.0), 0),
+            (Query::PointIntersects(102.5, 102.5), 3),
+            (Query::KNearest(0.0, 0.0, 5), 5),
+            (Query::KNearest(0.0, 0.0, 50), nodes.len()),
+        ] {
+            let mut reader = Cursor::new(&tree_data);
+            let count = PackedRTree::stream_count(
+                &mut reader,
+                nodes.len(),
+                PackedRTree::DEFAULT_NODE_SIZE,
+                query,
+                NodeLayout::Xyz,
+            )?;
+            assert_eq!(count, expected, "{query:?}");
+            // the whole index is skipped, as with stream_search
+            assert_eq!(reader.position() as usize, tree_data.len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn tree_version_1_layout() -> Result<()> {
+        let mut nodes = (0..20)
+            .map(|i| {
+                let min = i as f64 * 10.0;
+                NodeItem::bounds(min, min, min + 5.0, min + 5.0)
+            })
+            .collect::<Vec<_>>();
+        let extent = calc_extent(&nodes);
+        hilbert_sort(&mut nodes, &extent);
+        for (i, node) in nodes.iter_mut().enumerate() {
+            node.offset = i as u64 * 100;
+        }
+        let node_size = 4;
+        let tree = PackedRTree::build(&nodes, &extent, node_size)?;
+
+        // version 1 nodes: min_x, min_y, max_x, max_y, offset
+        let mut tree_data: Vec<u8> = Vec::new();
+        for node in tree.node_items.iter() {
+            for v in [node.min_x, node.min_y, node.max_x, node.max_y] {
+                tree_data.write_f64::<LittleEndian>(v)?;
+            }
+            tree_data.write_u64::<LittleEndian>(node.offset)?;
+        }
+        let layout = NodeLayout::for_version(1);
+        assert_eq!(layout, NodeLayout::Xy);
+        assert_eq!(layout.item_size(), 40);
+        assert_eq!(
+            tree_data.len(),
+            PackedRTree::index_size(nodes.len(), node_size, layout)
+        );
+
+        let tree2 = PackedRTree::from_buf(&mut &tree_data[..], nodes.len(), node_size, layout)?;
+        assert_eq!(tree2.node_items, tree.node_items);
+        assert_eq!(tree2.extent(), extent);
+
+        let query = Query::BBox3D(20.0, 20.0, 1000.0, 42.0, 42.0, 2000.0);
+        let expected = tree.search(query)?;
+        assert_eq!(expected.len(), 3);
+
+        let mut reader = Cursor::new(&tree_data);
+        let list = PackedRTree::stream_search(&mut reader, nodes.len(), node_size, query, layout)?;
+        assert_eq!(
+            list.iter().map(|item| item.offset).collect::<Vec<_>>(),
+            expected.iter().map(|item| item.offset).collect::<Vec<_>>()
+        );
+        assert_eq!(reader.position() as usize, tree_data.len());
+
+        let mut reader = Cursor::new(&tree_data);
+        let count = PackedRTree::stream_count(&mut reader, nodes.len(), node_size, query, layout)?;
+        assert_eq!(count, 3);
+
         Ok(())
     }
 
@@ -1651,6 +2194,7 @@ mod tests {
             nodes.len(),
             PackedRTree::DEFAULT_NODE_SIZE,
             Query::BBox(690407.0, 6063692.0, 811682.0, 6176467.0),
+            NodeLayout::Xyz,
         )?;
         assert_eq!(list2.len(), list.len());
         for i in 0..list2.len() {
@@ -1659,4 +2203,47 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn tree_bbox_3d() -> Result<()> {
+        // buildings stacked at the same footprint, from underground to high-rise
+        let mut nodes = vec![
+            NodeItem::bounds_3d(0.0, 0.0, -20.0, 10.0, 10.0, -5.0),
+            NodeItem::bounds_3d(0.0, 0.0, 0.0, 10.0, 10.0, 30.0),
+            NodeItem::bounds_3d(0.0, 0.0, 100.0, 10.0, 10.0, 500.0),
+            NodeItem::bounds_3d(50.0, 50.0, 0.0, 60.0, 60.0, 20.0),
+        ];
+        let extent = calc_extent(&nodes);
+        assert_eq!(extent.min_z, -20.0);
+        assert_eq!(extent.max_z, 500.0);
+        hilbert_sort(&mut nodes, &extent);
+        let tree = PackedRTree::build(&nodes, &extent, PackedRTree::DEFAULT_NODE_SIZE)?;
+
+        // 2D queries ignore Z
+        let list = tree.search(Query::BBox(0.0, 0.0, 10.0, 10.0))?;
+        assert_eq!(list.len(), 3);
+
+        let query = Query::BBox3D(0.0, 0.0, 10.0, 10.0, 10.0, 200.0);
+        let list = tree.search(query)?;
+        assert_eq!(list.len(), 2);
+        for item in &list {
+            assert!(nodes[item.index].max_z >= 10.0 && nodes[item.index].min_z <= 200.0);
+        }
+
+        let mut tree_data: Vec<u8> = Vec::new();
+        tree.stream_write(&mut tree_data)?;
+        let mut reader = Cursor::new(&tree_data);
+        let list2 = PackedRTree::stream_search(
+            &mut reader,
+            nodes.len(),
+            PackedRTree::DEFAULT_NODE_SIZE,
+            query,
+            NodeLayout::Xyz,
+        )?;
+        assert_eq!(
+            list2.iter().map(|item| item.index).collect::<Vec<_>>(),
+            list.iter().map(|item| item.index).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+/// Progress report emitted by long-running FCB operations.
+///
+/// For writers the counters track what has been written to the output so far.
+/// For readers they track the features and bytes consumed so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProgressEvent {
+    #[serde(rename = "featuresWritten")]
+    pub features_written: usize,
+    #[serde(rename = "bytesWritten")]
+    pub bytes_written: u64,
+    /// Total number of features, if known in advance
+    #[serde(rename = "totalFeatures")]
+    pub total_features: Option<usize>,
+}
+
+/// Callback invoked with a [`ProgressEvent`] after each processed feature
+pub type ProgressCallback = Box<dyn Fn(ProgressEvent) + Send + Sync>;
@@ -0,0 +1,220 @@
+//! Python bindings, built with `maturin` into the `flatcitybuf` module
+//!
+//! ```python
+//! import flatcitybuf
+//!
+//! reader = flatcitybuf.FcbReader("delft.fcb")
+//! for feature in reader.select_bbox(84000.0, 445000.0, 86000.0, 447000.0):
+//!     print(feature["id"])
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString};
+use serde_json::Value;
+
+use crate::deserializer::to_cj_metadata;
+use crate::error::Error;
+use crate::fb::{ColumnType, Header};
+use crate::reader::{reader_trait::Seekable, FcbReader, FeatureIter};
+use crate::FEATURE_ID_COLUMN;
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        match err {
+            Error::IoError(e) => PyIOError::new_err(e.to_string()),
+            e => PyValueError::new_err(e.to_string()),
+        }
+    }
+}
+
+type FileFeatureIter = FeatureIter<BufReader<File>, Seekable>;
+
+/// Reader of an FCB file, each `select_*` method reopens the file
+#[pyclass(name = "FcbReader", module = "flatcitybuf")]
+struct PyFcbReader {
+    path: PathBuf,
+}
+
+impl PyFcbReader {
+    fn open(&self) -> Result<FcbReader<BufReader<File>>, Error> {
+        FcbReader::open(BufReader::new(File::open(&self.path)?))
+    }
+}
+
+#[pymethods]
+impl PyFcbReader {
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let reader = Self { path };
+        // fail early on missing files and invalid headers
+        reader.open()?;
+        Ok(reader)
+    }
+
+    /// CityJSON metadata of the file, i.e. the first line of a CityJSONSeq
+    fn metadata(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let reader = self.open()?;
+        let cj = to_cj_metadata(&reader.header())?;
+        let value = serde_json::to_value(&cj).map_err(Error::from)?;
+        json_to_py(py, &value)
+    }
+
+    /// Attribute columns of the header, mapped to the Python type of their values
+    #[getter]
+    fn attribute_schema(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let reader = self.open()?;
+        let schema = PyDict::new(py);
+        for (name, column_type) in column_types(&reader.header()) {
+            schema.set_item(name, python_type(py, column_type)?)?;
+        }
+        Ok(schema.into_any().unbind())
+    }
+
+    /// Iterates over all features
+    fn select_all(&self) -> PyResult<PyFeatureIter> {
+        let iter = self.open()?.select_all()?;
+        Ok(PyFeatureIter::new(iter))
+    }
+
+    /// Iterates over the features intersecting the bounding box, using the spatial index
+    fn select_bbox(&self, minx: f64, miny: f64, maxx: f64, maxy: f64) -> PyResult<PyFeatureIter> {
+        let iter = self.open()?.select_bbox(minx, miny, maxx, maxy)?;
+        Ok(PyFeatureIter::new(iter))
+    }
+}
+
+/// Iterator yielding the selected features as CityJSONFeature dictionaries
+#[pyclass(name = "FeatureIter", module = "flatcitybuf", unsendable)]
+struct PyFeatureIter {
+    iter: FileFeatureIter,
+    column_types: HashMap<String, ColumnType>,
+}
+
+impl PyFeatureIter {
+    fn new(iter: FileFeatureIter) -> Self {
+        let column_types = column_types(&iter.header());
+        Self { iter, column_types }
+    }
+}
+
+#[pymethods]
+impl PyFeatureIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let Some(feat_buf) = self.iter.next()? else {
+            return Ok(None);
+        };
+        let feature = feat_buf.cur_cj_feature()?;
+        let value = serde_json::to_value(&feature).map_err(Error::from)?;
+        let feature = json_to_py(py, &value)?;
+
+        // datetime attributes are strings in CityJSON, convert them after their column type
+        let city_objects = value
+            .get("CityObjects")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(|objects| objects.iter());
+        let py_city_objects = feature.bind(py).get_item("CityObjects")?;
+        for (id, co) in city_objects {
+            let Some(Value::Object(attributes)) = co.get("attributes") else {
+                continue;
+            };
+            let py_attributes = py_city_objects.get_item(id)?.get_item("attributes")?;
+            for (name, value) in attributes.iter() {
+                if self.column_types.get(name) != Some(&ColumnType::DateTime) {
+                    continue;
+                }
+                let Some(datetime) = value
+                    .as_str()
+                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                else {
+                    continue;
+                };
+                py_attributes.set_item(name, datetime)?;
+            }
+        }
+        Ok(Some(feature))
+    }
+}
+
+fn column_types(header: &Header) -> HashMap<String, ColumnType> {
+    header
+        .columns()
+        .into_iter()
+        .flat_map(|columns| columns.iter())
+        .filter(|column| column.name() != FEATURE_ID_COLUMN)
+        .map(|column| (column.name().to_string(), column.type_()))
+        .collect()
+}
+
+/// Python type of the values of a column type
+fn python_type(py: Python<'_>, column_type: ColumnType) -> PyResult<PyObject> {
+    let py_type = match column_type {
+        ColumnType::Byte
+        | ColumnType::UByte
+        | ColumnType::Short
+        | ColumnType::UShort
+        | ColumnType::Int
+        | ColumnType::UInt
+        | ColumnType::Long
+        | ColumnType::ULong => py.get_type::<PyInt>().into_any(),
+        ColumnType::Float | ColumnType::Double => py.get_type::<PyFloat>().into_any(),
+        ColumnType::Bool => py.get_type::<PyBool>().into_any(),
+        ColumnType::String => py.get_type::<PyString>().into_any(),
+        ColumnType::DateTime => py.import("datetime")?.getattr("datetime")?,
+        ColumnType::Json => py.get_type::<PyDict>().into_any(),
+        ColumnType::Binary => py.get_type::<PyBytes>().into_any(),
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unsupported column type {column_type:?}"
+            )))
+        }
+    };
+    Ok(py_type.unbind())
+}
+
+fn json_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    let object = match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any().unbind(),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any().unbind(),
+            (None, Some(u)) => u.into_pyobject(py)?.into_any().unbind(),
+            (None, None) => n.as_f64().into_pyobject(py)?.into_any().unbind(),
+        },
+        Value::String(s) => PyString::new(py, s).into_any().unbind(),
+        Value::Array(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(json_to_py(py, value)?)?;
+            }
+            list.into_any().unbind()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, json_to_py(py, value)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    };
+    Ok(object)
+}
+
+/// The `flatcitybuf` Python module
+#[pymodule]
+fn flatcitybuf(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFcbReader>()?;
+    m.add_class::<PyFeatureIter>()?;
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use crate::static_btree::{
-    FixedStringKey, Float, KeyType, MemoryIndex, MemoryMultiIndex, MultiIndex, Operator, Query,
-    QueryCondition, StreamIndex, StreamMultiIndex,
+    CompositeKey, FixedStringKey, Float, Key, KeyType, Literal, MemoryIndex, MemoryMultiIndex,
+    MultiIndex, Nullable, Operator, Query, QueryCondition, QueryExpr, QueryPlan, StreamIndex,
+    StreamMultiIndex, TypedSearchIndex, TypedStreamSearchIndex,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io::{self, Cursor, Read, Seek, SeekFrom};
 use std::ops::Range;
 
@@ -10,12 +11,15 @@ use crate::error::{Error, Result};
 
 use chrono::{DateTime, Utc};
 
-use crate::fb::ColumnType;
-use crate::{AttributeIndex, Column, FeatureOffset};
+use crate::fb::{
+    size_prefixed_root_as_city_feature, size_prefixed_root_as_city_feature_unchecked, ColumnType,
+};
+use crate::packed_rtree::{self, PackedRTree};
+use crate::{AttributeIndex, Column, FeatureOffset, FEATURE_ID_COLUMN};
 
 use super::{
     reader_trait::{NotSeekable, Seekable},
-    FcbReader, FeatureIter,
+    FcbBuffer, FcbReader, FeatureIter,
 };
 
 pub type AttrQuery = Vec<(String, Operator, KeyType)>;
@@ -33,6 +37,9 @@ pub fn add_indices_to_multi_memory_index<R: Read>(
     let mut buf = Cursor::new(buf);
     if let Some(col) = columns.iter().find(|col| col.index() == attr_info.index()) {
         if query.iter().any(|(name, _, _)| col.name() == name) {
+            if col.nullable_index() {
+                return add_nullable_memory_index(&mut buf, multi_index, col, attr_info);
+            }
             match col.type_() {
                 ColumnType::Int => {
                     let index = MemoryIndex::<i32>::from_buf(
@@ -42,6 +49,14 @@ pub fn add_indices_to_multi_memory_index<R: Read>(
                     )?;
                     multi_index.add_i32_index(col.name().to_string(), index);
                 }
+                ColumnType::Long => {
+                    let index = MemoryIndex::<i64>::from_buf(
+                        &mut buf,
+                        attr_info.num_unique_items() as usize,
+                        attr_info.branching_factor(),
+                    )?;
+                    multi_index.add_i64_index(col.name().to_string(), index);
+                }
                 ColumnType::Float => {
                     let index = MemoryIndex::<Float<f32>>::from_buf(
                         &mut buf,
@@ -149,6 +164,9 @@ pub fn add_indices_to_multi_stream_index<R: Read + Seek>(
     if let Some(col) = columns.iter().find(|col| col.index() == attr_info.index()) {
         // TODO: now it assuming to add all indices to the multi_index. However, we should only add the indices that are used in the query. To do that, we need to change the implementation of StreamMultiIndex. Current StreamMultiIndex's `add_index` method assumes that all indices are added to the multi_index. We'll change it to take Range<usize> as an argument.
         let index_begin = index_begin as u64;
+        if col.nullable_index() {
+            return add_nullable_stream_index(multi_index, col, attr_info, index_begin);
+        }
         match col.type_() {
             ColumnType::Int => {
                 let index = StreamIndex::<i32>::new(
@@ -159,6 +177,15 @@ pub fn add_indices_to_multi_stream_index<R: Read + Seek>(
                 );
                 multi_index.add_i32_index(col.name().to_string(), index, attr_info.length() as u64);
             }
+            ColumnType::Long => {
+                let index = StreamIndex::<i64>::new(
+                    attr_info.num_unique_items() as usize,
+                    attr_info.branching_factor(),
+                    index_begin,
+                    attr_info.length() as u64,
+                );
+                multi_index.add_i64_index(col.name().to_string(), index, attr_info.length() as u64);
+            }
             ColumnType::Float => {
                 let index = StreamIndex::<Float<f32>>::new(
                     attr_info.num_unique_items() as usize,
@@ -280,6 +307,93 @@ pub fn add_indices_to_multi_stream_index<R: Read + Seek>(
     Ok(())
 }
 
+/// Adds an index whose keys carry a null marker, see [`Nullable`]
+fn add_nullable_memory_index(
+    buf: &mut Cursor<Vec<u8>>,
+    multi_index: &mut MemoryMultiIndex,
+    col: &Column,
+    attr_info: &AttributeIndex,
+) -> Result<()> {
+    fn add<K: Key + 'static>(
+        buf: &mut Cursor<Vec<u8>>,
+        multi_index: &mut MemoryMultiIndex,
+        col: &Column,
+        attr_info: &AttributeIndex,
+    ) -> Result<()>
+    where
+        MemoryIndex<Nullable<K>>: TypedSearchIndex,
+    {
+        let index = MemoryIndex::<Nullable<K>>::from_buf(
+            buf,
+            attr_info.num_unique_items() as usize,
+            attr_info.branching_factor(),
+        )?;
+        multi_index.add_index(col.name().to_string(), index);
+        Ok(())
+    }
+
+    match col.type_() {
+        ColumnType::Int => add::<i32>(buf, multi_index, col, attr_info),
+        ColumnType::Long => add::<i64>(buf, multi_index, col, attr_info),
+        ColumnType::Float => add::<Float<f32>>(buf, multi_index, col, attr_info),
+        ColumnType::Double => add::<Float<f64>>(buf, multi_index, col, attr_info),
+        ColumnType::String => add::<FixedStringKey<50>>(buf, multi_index, col, attr_info),
+        ColumnType::Bool => add::<bool>(buf, multi_index, col, attr_info),
+        ColumnType::DateTime => add::<DateTime<Utc>>(buf, multi_index, col, attr_info),
+        ColumnType::Short => add::<i16>(buf, multi_index, col, attr_info),
+        ColumnType::UShort => add::<u16>(buf, multi_index, col, attr_info),
+        ColumnType::UInt => add::<u32>(buf, multi_index, col, attr_info),
+        ColumnType::ULong => add::<u64>(buf, multi_index, col, attr_info),
+        ColumnType::Byte => add::<i8>(buf, multi_index, col, attr_info),
+        ColumnType::UByte => add::<u8>(buf, multi_index, col, attr_info),
+        _ => Err(Error::UnsupportedColumnType(col.name().to_string())),
+    }
+}
+
+/// Adds an index whose keys carry a null marker, see [`Nullable`]
+fn add_nullable_stream_index(
+    multi_index: &mut StreamMultiIndex,
+    col: &Column,
+    attr_info: &AttributeIndex,
+    index_begin: u64,
+) -> Result<()> {
+    fn add<K: Key + 'static>(
+        multi_index: &mut StreamMultiIndex,
+        col: &Column,
+        attr_info: &AttributeIndex,
+        index_begin: u64,
+    ) -> Result<()>
+    where
+        StreamIndex<Nullable<K>>: TypedStreamSearchIndex,
+    {
+        let index = StreamIndex::<Nullable<K>>::new(
+            attr_info.num_unique_items() as usize,
+            attr_info.branching_factor(),
+            index_begin,
+            attr_info.length() as u64,
+        );
+        multi_index.add_index(col.name().to_string(), index, attr_info.length() as u64);
+        Ok(())
+    }
+
+    match col.type_() {
+        ColumnType::Int => add::<i32>(multi_index, col, attr_info, index_begin),
+        ColumnType::Long => add::<i64>(multi_index, col, attr_info, index_begin),
+        ColumnType::Float => add::<Float<f32>>(multi_index, col, attr_info, index_begin),
+        ColumnType::Double => add::<Float<f64>>(multi_index, col, attr_info, index_begin),
+        ColumnType::String => add::<FixedStringKey<50>>(multi_index, col, attr_info, index_begin),
+        ColumnType::Bool => add::<bool>(multi_index, col, attr_info, index_begin),
+        ColumnType::DateTime => add::<DateTime<Utc>>(multi_index, col, attr_info, index_begin),
+        ColumnType::Short => add::<i16>(multi_index, col, attr_info, index_begin),
+        ColumnType::UShort => add::<u16>(multi_index, col, attr_info, index_begin),
+        ColumnType::UInt => add::<u32>(multi_index, col, attr_info, index_begin),
+        ColumnType::ULong => add::<u64>(multi_index, col, attr_info, index_begin),
+        ColumnType::Byte => add::<i8>(multi_index, col, attr_info, index_begin),
+        ColumnType::UByte => add::<u8>(multi_index, col, attr_info, index_begin),
+        _ => Err(Error::UnsupportedColumnType(col.name().to_string())),
+    }
+}
+
 pub fn build_query(query: &AttrQuery) -> Query {
     let conditions = query
         .iter()
@@ -295,8 +409,72 @@ pub fn build_query(query: &AttrQuery) -> Query {
     Query { conditions }
 }
 
+/// Converts a parsed WHERE clause into attribute queries, one per group of AND-ed conditions,
+/// which are OR-ed together. Each value is typed after the column of its field.
+pub fn attr_queries_from_expr(expr: &QueryExpr, columns: &[Column]) -> Result<Vec<AttrQuery>> {
+    expr.and_groups()
+        .into_iter()
+        .map(|group| {
+            group
+                .into_iter()
+                .map(|(field, operator, value)| {
+                    let col = columns
+                        .iter()
+                        .find(|col| col.name() == field)
+                        .ok_or_else(|| {
+                            Error::QueryExecutionError(format!("unknown attribute '{field}'"))
+                        })?;
+                    let key = match value {
+                        Some(value) => literal_to_key(col, &value)?,
+                        None => KeyType::Null,
+                    };
+                    Ok((field, operator, key))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn literal_to_key(col: &Column, value: &Literal) -> Result<KeyType> {
+    fn int<T: TryFrom<i64>>(v: i64) -> Option<T> {
+        T::try_from(v).ok()
+    }
+
+    let key = match (col.type_(), value) {
+        (ColumnType::Byte, Literal::Int(v)) => int(*v).map(KeyType::Int8),
+        (ColumnType::UByte, Literal::Int(v)) => int(*v).map(KeyType::UInt8),
+        (ColumnType::Short, Literal::Int(v)) => int(*v).map(KeyType::Int16),
+        (ColumnType::UShort, Literal::Int(v)) => int(*v).map(KeyType::UInt16),
+        (ColumnType::Int, Literal::Int(v)) => int(*v).map(KeyType::Int32),
+        (ColumnType::UInt, Literal::Int(v)) => int(*v).map(KeyType::UInt32),
+        (ColumnType::Long, Literal::Int(v)) => Some(KeyType::Int64(*v)),
+        (ColumnType::ULong, Literal::Int(v)) => int(*v).map(KeyType::UInt64),
+        (ColumnType::Float, Literal::Int(v)) => Some(KeyType::Float32(Float(*v as f32))),
+        (ColumnType::Float, Literal::Float(v)) => Some(KeyType::Float32(Float(*v as f32))),
+        (ColumnType::Double, Literal::Int(v)) => Some(KeyType::Float64(Float(*v as f64))),
+        (ColumnType::Double, Literal::Float(v)) => Some(KeyType::Float64(Float(*v))),
+        (ColumnType::Bool, Literal::Bool(v)) => Some(KeyType::Bool(*v)),
+        (ColumnType::String, Literal::String(v)) => {
+            Some(KeyType::StringKey50(FixedStringKey::from_str(v)))
+        }
+        (ColumnType::DateTime, Literal::String(v)) => DateTime::parse_from_rfc3339(v)
+            .ok()
+            .map(|dt| KeyType::DateTime(dt.to_utc())),
+        _ => None,
+    };
+    key.ok_or_else(|| {
+        Error::QueryExecutionError(format!(
+            "value {value} does not fit the {:?} column '{}'",
+            col.type_(),
+            col.name()
+        ))
+    })
+}
+
 impl<R: Read + Seek> FcbReader<R> {
-    pub fn select_attr_query(mut self, query: AttrQuery) -> Result<FeatureIter<R, Seekable>> {
+    /// Loads the attribute indices of the header for a streaming query, the reader is left at
+    /// the start of the attribute indices whose position is returned
+    fn attr_query_multi_index(&mut self, query: &AttrQuery) -> Result<(StreamMultiIndex, u64)> {
         // query: vec<(field_name, operator, value)>
         let header = self.buffer.header();
         let attr_index_entries = header
@@ -349,9 +527,6 @@ impl<R: Read + Seek> FcbReader<R> {
         // Reset reader position to the start of attribute indices
         self.reader.seek(SeekFrom::Start(attr_index_start_pos))?;
 
-        // Create a query from the AttrQuery
-        let query_obj = build_query(&query);
-
         let mut multi_index = StreamMultiIndex::new();
         // iterate over the columens which are used in the query and is in columns and in attr_index_entries
         for attr_info in attr_index_entries.iter() {
@@ -370,12 +545,19 @@ impl<R: Read + Seek> FcbReader<R> {
             add_indices_to_multi_stream_index::<R>(
                 &mut multi_index,
                 &columns,
-                &query,
+                query,
                 attr_info,
                 index_range.start,
             )?;
         }
 
+        Ok((multi_index, attr_index_start_pos))
+    }
+
+    pub fn select_attr_query(mut self, query: AttrQuery) -> Result<FeatureIter<R, Seekable>> {
+        let (multi_index, attr_index_start_pos) = self.attr_query_multi_index(&query)?;
+        let query_obj = build_query(&query);
+
         let result = match multi_index.query(&mut self.reader, &query_obj.conditions) {
             Ok(res) => res,
             Err(e) => {
@@ -416,6 +598,290 @@ impl<R: Read + Seek> FcbReader<R> {
     }
 }
 
+impl<R: Read + Seek> FcbReader<R> {
+    /// Count the features matching an attribute query without reading any feature.
+    ///
+    /// The reader is left at the start of the index, so a `select_*` method can follow.
+    pub fn count_attr_query(&mut self, query: &AttrQuery) -> Result<usize> {
+        let index_start = self.reader.stream_position()?;
+        let (multi_index, _) = self.attr_query_multi_index(query)?;
+        let query_obj = build_query(query);
+        let result = multi_index
+            .query(&mut self.reader, &query_obj.conditions)
+            .map_err(|e| {
+                Error::QueryExecutionError(format!("Failed to execute streaming query: {}", e))
+            })?;
+        self.reader.seek(SeekFrom::Start(index_start))?;
+        Ok(result.len())
+    }
+
+    /// Runs an attribute query on the indices and reports, for each condition, how many
+    /// features its index matched and how many remain after intersecting with the previous
+    /// conditions. No feature is read.
+    pub fn explain_attr_query(mut self, query: AttrQuery) -> Result<QueryPlan> {
+        let (multi_index, _) = self.attr_query_multi_index(&query)?;
+        let query_obj = build_query(&query);
+        multi_index
+            .explain(&mut self.reader, &query_obj.conditions)
+            .map_err(|e| {
+                Error::QueryExecutionError(format!("Failed to explain streaming query: {}", e))
+            })
+    }
+}
+
+impl<R: Read + Seek> FcbReader<R> {
+    /// Selects the features matching a WHERE clause and, if given, a spatial query.
+    ///
+    /// Each group of AND-ed conditions of `expr` is looked up in the attribute indices, the
+    /// groups are OR-ed together and the result is intersected with the spatial index hits.
+    pub fn select_where(
+        mut self,
+        spatial_query: Option<packed_rtree::Query>,
+        expr: &QueryExpr,
+    ) -> Result<FeatureIter<R, Seekable>> {
+        let header = self.buffer.header();
+        let columns = header
+            .columns()
+            .ok_or(Error::NoColumnsInHeader)?
+            .iter()
+            .collect::<Vec<_>>();
+        let groups = attr_queries_from_expr(expr, &columns)?;
+
+        let index_start = self.reader.stream_position()?;
+        let spatial_offsets = match spatial_query {
+            Some(query) => {
+                if header.index_node_size() == 0 || header.features_count() == 0 {
+                    return Err(Error::NoIndex);
+                }
+                let layout = self.node_layout();
+                let items = PackedRTree::stream_search(
+                    &mut self.reader,
+                    header.features_count() as usize,
+                    header.index_node_size(),
+                    query,
+                    layout,
+                )?;
+                self.reader.seek(SeekFrom::Start(index_start))?;
+                Some(
+                    items
+                        .into_iter()
+                        .map(|item| item.offset as u64)
+                        .collect::<HashSet<_>>(),
+                )
+            }
+            None => None,
+        };
+
+        let (multi_index, attr_index_start_pos) = self.attr_query_multi_index(&groups.concat())?;
+        let mut offsets = BTreeSet::new();
+        for group in groups.iter() {
+            let query_obj = build_query(group);
+            let group_offsets = multi_index
+                .query(&mut self.reader, &query_obj.conditions)
+                .map_err(|e| {
+                    Error::QueryExecutionError(format!("Failed to execute streaming query: {}", e))
+                })?;
+            offsets.extend(group_offsets.into_iter().filter(|offset| {
+                spatial_offsets
+                    .as_ref()
+                    .is_none_or(|spatial| spatial.contains(offset))
+            }));
+        }
+        let result_vec = offsets.into_iter().collect::<Vec<_>>();
+
+        let header_size = self.buffer.header_buf.len();
+        let feature_offset = FeatureOffset {
+            magic_bytes: 8,
+            header: header_size as u64,
+            rtree_index: self.rtree_index_size(),
+            attributes: self.attr_index_size(),
+        };
+
+        let total_feat_count = result_vec.len() as u64;
+
+        let attr_index_size = self.attr_index_size();
+        self.reader
+            .seek(SeekFrom::Start(attr_index_start_pos + attr_index_size))?;
+
+        Ok(FeatureIter::<R, Seekable>::new(
+            self.reader,
+            self.verify,
+            self.buffer,
+            None,
+            Some(result_vec),
+            feature_offset,
+            total_feat_count,
+        ))
+    }
+}
+
+impl<R: Read + Seek> FcbReader<R> {
+    /// Selects the features matching all `(field, value)` pairs with a composite index.
+    ///
+    /// The fields must be exactly the columns of a composite index, in the order of
+    /// its definition. Partial key lookups, i.e. a subset or a prefix of the columns,
+    /// are not supported. Each value must use the key type of the column's
+    /// single-column index, e.g. `KeyType::StringKey50` for string columns.
+    pub fn select_composite(
+        mut self,
+        conditions: &[(&str, KeyType)],
+    ) -> Result<FeatureIter<R, Seekable>> {
+        let header = self.buffer.header();
+        let columns = header
+            .columns()
+            .ok_or(Error::NoColumnsInHeader)?
+            .iter()
+            .collect::<Vec<_>>();
+        let column_indices = conditions
+            .iter()
+            .map(|(field, _)| {
+                columns
+                    .iter()
+                    .find(|c| c.name() == *field)
+                    .map(|c| c.index())
+                    .ok_or(Error::AttributeIndexNotFound)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // composite indices are stored after all the single-column indices
+        let mut index_begin = header
+            .attribute_index()
+            .map(|ai| ai.iter().map(|ai| ai.length() as u64).sum::<u64>())
+            .unwrap_or(0);
+        let mut composite_index = None;
+        for ci in header.composite_index().iter().flat_map(|ci| ci.iter()) {
+            if ci
+                .columns()
+                .is_some_and(|c| c.iter().eq(column_indices.iter().copied()))
+            {
+                composite_index = Some(ci);
+                break;
+            }
+            index_begin += ci.length() as u64;
+        }
+        let composite_index = composite_index.ok_or(Error::AttributeIndexNotFound)?;
+
+        let values = conditions
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+        let key = CompositeKey::from_keys(&values)?;
+        let index = StreamIndex::<CompositeKey>::new(
+            composite_index.num_unique_items() as usize,
+            composite_index.branching_factor(),
+            index_begin,
+            composite_index.length() as u64,
+        );
+
+        // Skip the rtree index bytes; we know the correct offset for that
+        let rtree_offset = self.rtree_index_size();
+        self.reader.seek(SeekFrom::Current(rtree_offset as i64))?;
+        let attr_index_start_pos = self.reader.stream_position()?;
+
+        self.reader
+            .seek(SeekFrom::Start(attr_index_start_pos + index_begin))?;
+        let mut result_vec = index.find_exact_with_reader(&mut self.reader, key)?;
+        result_vec.sort();
+
+        let header_size = self.buffer.header_buf.len();
+        let feature_offset = FeatureOffset {
+            magic_bytes: 8,
+            header: header_size as u64,
+            rtree_index: self.rtree_index_size(),
+            attributes: self.attr_index_size(),
+        };
+
+        let total_feat_count = result_vec.len() as u64;
+
+        let attr_index_size = self.attr_index_size();
+        self.reader
+            .seek(SeekFrom::Start(attr_index_start_pos + attr_index_size))?;
+
+        Ok(FeatureIter::<R, Seekable>::new(
+            self.reader,
+            self.verify,
+            self.buffer,
+            None,
+            Some(result_vec),
+            feature_offset,
+            total_feat_count,
+        ))
+    }
+}
+
+impl<R: Read + Seek> FcbReader<R> {
+    /// Looks up a feature by its CityJSON feature ID with the feature ID index.
+    ///
+    /// The file must be written with `HeaderWriterOptions::feature_id_index`. Keys are
+    /// truncated to 50 bytes, so the ID of each hit is compared before returning it. The
+    /// reader is left at the start of the index, so a `select_*` method can follow.
+    pub fn find_by_id(&mut self, id: &str) -> Result<Option<FcbBuffer>> {
+        let header = self.buffer.header();
+        let column = header
+            .columns()
+            .ok_or(Error::NoColumnsInHeader)?
+            .iter()
+            .find(|c| c.name() == FEATURE_ID_COLUMN)
+            .ok_or(Error::AttributeIndexNotFound)?;
+        let mut attr_index_entries = header
+            .attribute_index()
+            .ok_or(Error::AttributeIndexNotFound)?
+            .iter()
+            .collect::<Vec<_>>();
+        attr_index_entries.sort_by_key(|attr| attr.index());
+        let attr_info = attr_index_entries
+            .iter()
+            .find(|attr| attr.index() == column.index())
+            .ok_or(Error::AttributeIndexNotFound)?;
+        // attribute indices are stored in column order
+        let index_begin = attr_index_entries
+            .iter()
+            .take_while(|attr| attr.index() != column.index())
+            .map(|attr| attr.length() as u64)
+            .sum::<u64>();
+        let index = StreamIndex::<FixedStringKey<50>>::new(
+            attr_info.num_unique_items() as usize,
+            attr_info.branching_factor(),
+            index_begin,
+            attr_info.length() as u64,
+        );
+
+        let index_start = self.reader.stream_position()?;
+        let attr_index_start_pos = index_start + self.rtree_index_size();
+        let features_start = attr_index_start_pos + self.attr_index_size();
+        self.reader
+            .seek(SeekFrom::Start(attr_index_start_pos + index_begin))?;
+        let mut offsets =
+            index.find_exact_with_reader(&mut self.reader, FixedStringKey::from_str(id))?;
+        offsets.sort();
+
+        let mut found = None;
+        for offset in offsets {
+            self.reader.seek(SeekFrom::Start(features_start + offset))?;
+            let mut size_buf = [0u8; 4];
+            self.reader.read_exact(&mut size_buf)?;
+            let feature_size = u32::from_le_bytes(size_buf) as usize;
+            let mut features_buf = vec![0; feature_size + 4];
+            features_buf[..4].copy_from_slice(&size_buf);
+            self.reader.read_exact(&mut features_buf[4..])?;
+            let feature_id = if self.verify {
+                size_prefixed_root_as_city_feature(&features_buf)?.id()
+            } else {
+                unsafe { size_prefixed_root_as_city_feature_unchecked(&features_buf) }.id()
+            };
+            if feature_id == id {
+                found = Some(FcbBuffer {
+                    header_buf: self.buffer.header_buf.clone(),
+                    features_buf,
+                });
+                break;
+            }
+        }
+        self.reader.seek(SeekFrom::Start(index_start))?;
+        Ok(found)
+    }
+}
+
 impl<R: Read> FcbReader<R> {
     pub fn select_attr_query_seq(
         mut self,
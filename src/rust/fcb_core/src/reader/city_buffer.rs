@@ -1,4 +1,7 @@
-use crate::deserializer::to_cj_feature;
+use crate::deserializer::{
+    decode_attribute, resolve_global_vertices, to_cj_feature_into, to_cj_feature_with_options,
+    DeserializeOptions,
+};
 use crate::error::Error;
 use crate::fb::*;
 use cjseq::CityJSONFeature;
@@ -6,6 +9,15 @@ use cjseq::CityJSONFeature;
 use super::deserializer::to_meta;
 use super::Meta;
 
+/// Geometries of a feature, one entry per geometry in the order of its city objects
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeometrySummary {
+    pub count: usize,
+    pub lods: Vec<Option<String>>,
+    /// CityJSON geometry type names, e.g. `MultiSurface` or `GeometryInstance`
+    pub types: Vec<String>,
+}
+
 pub struct FcbBuffer {
     pub header_buf: Vec<u8>,
     pub features_buf: Vec<u8>,
@@ -22,10 +34,103 @@ impl FcbBuffer {
 
     // TODO: think well if needed
     pub fn cj_feature(&self) -> Result<CityJSONFeature, Error> {
+        self.cj_feature_with_options(&DeserializeOptions::default())
+    }
+
+    /// Decodes the feature with the given options, copying its vertices from the global vertex
+    /// table of the header if the file has one
+    pub fn cj_feature_with_options(
+        &self,
+        options: &DeserializeOptions,
+    ) -> Result<CityJSONFeature, Error> {
+        let fcb_feature = self.feature();
+        let header = self.header();
+        to_cj_feature_with_options(
+            fcb_feature,
+            header.columns(),
+            header.semantic_columns(),
+            options,
+        )
+        .and_then(|mut cj| {
+            resolve_global_vertices(&mut cj, &header)?;
+            Ok(cj)
+        })
+        .map_err(|e| e.with_feature_id(fcb_feature.id()))
+    }
+
+    /// Decodes the feature into `buf`, reusing its allocations
+    ///
+    /// Same result as [`FcbBuffer::cj_feature`], meant for loops decoding every feature into
+    /// one `CityJSONFeature`.
+    pub fn cj_feature_into(&self, buf: &mut CityJSONFeature) -> Result<(), Error> {
         let fcb_feature = self.feature();
         let root_attr_schema = self.header().columns();
         let semantic_attr_schema = self.header().semantic_columns();
-        to_cj_feature(fcb_feature, root_attr_schema, semantic_attr_schema)
+        to_cj_feature_into(fcb_feature, root_attr_schema, semantic_attr_schema, buf)
+            .and_then(|()| resolve_global_vertices(buf, &self.header()))
+            .map_err(|e| e.with_feature_id(fcb_feature.id()))
+    }
+
+    /// Value of a single attribute of the feature, without decoding the rest of it
+    ///
+    /// The first city object having the attribute is used. Columns are looked up in the
+    /// schema of the city object, or in the header schema when the object has none.
+    pub fn attribute_value(&self, column_name: &str) -> Result<Option<serde_json::Value>, Error> {
+        let header_columns = self.header().columns();
+        for co in self
+            .feature()
+            .objects()
+            .into_iter()
+            .flat_map(|cos| cos.iter())
+        {
+            let (Some(columns), Some(attributes)) =
+                (co.columns().or(header_columns), co.attributes())
+            else {
+                continue;
+            };
+            if let Some(value) = decode_attribute(&columns, attributes, column_name)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Types and LODs of the geometries of the feature, without decoding their boundaries
+    ///
+    /// Only the type and LOD fields of each geometry table are read. Geometry instances come
+    /// after the geometries of their city object, with the LOD of their template.
+    pub fn geometry_summary(&self) -> Result<GeometrySummary, Error> {
+        let templates = self.header().templates();
+        let feature = self.feature();
+        let mut summary = GeometrySummary::default();
+        for co in feature.objects().into_iter().flat_map(|cos| cos.iter()) {
+            for geometry in co.geometry().into_iter().flat_map(|g| g.iter()) {
+                let type_ =
+                    geometry
+                        .type_()
+                        .variant_name()
+                        .ok_or_else(|| Error::InvalidGeometry {
+                            feature_id: feature.id().to_string(),
+                            reason: format!(
+                                "unknown geometry type {} in city object {}",
+                                geometry.type_().0,
+                                co.id()
+                            ),
+                        })?;
+                summary.types.push(type_.to_string());
+                summary.lods.push(geometry.lod().map(str::to_string));
+            }
+            for instance in co.geometry_instances().into_iter().flat_map(|g| g.iter()) {
+                let template = instance.template() as usize;
+                let lod = templates
+                    .filter(|t| template < t.len())
+                    .and_then(|t| t.get(template).lod());
+                summary.types.push("GeometryInstance".to_string());
+                summary.lods.push(lod.map(str::to_string));
+            }
+        }
+        summary.count = summary.types.len();
+        Ok(summary)
     }
 
     pub fn meta(&self) -> Result<Meta, Error> {
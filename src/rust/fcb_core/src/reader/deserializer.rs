@@ -19,14 +19,28 @@ use cjseq::{
 
 use super::meta::{Column as MetaColumn, ColumnType as MetaColumnType, Meta};
 
+/// Scale and translation of the header transform, identity without one
+pub fn header_transform(header: &Header) -> ([f64; 3], [f64; 3]) {
+    match header.transform() {
+        Some(transform) => {
+            let (scale, translate) = (transform.scale(), transform.translate());
+            (
+                [scale.x(), scale.y(), scale.z()],
+                [translate.x(), translate.y(), translate.z()],
+            )
+        }
+        None => ([1.0; 3], [0.0; 3]),
+    }
+}
+
 pub fn to_cj_metadata(header: &Header) -> Result<CityJSON, Error> {
     let mut cj = CityJSON::new();
     let semantic_attr_schema = header.semantic_columns();
-    if let Some(transform) = header.transform() {
-        let (scale, translate) = (transform.scale(), transform.translate());
+    if header.transform().is_some() {
+        let (scale, translate) = header_transform(header);
         cj.transform = CjTransform {
-            scale: vec![scale.x(), scale.y(), scale.z()],
-            translate: vec![translate.x(), translate.y(), translate.z()],
+            scale: scale.to_vec(),
+            translate: translate.to_vec(),
         };
     }
 
@@ -249,9 +263,9 @@ pub(crate) fn to_cj_co_type(co_type: CityObjectType, extension_type: Option<&str
 pub fn decode_attributes(
     columns: &flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>,
     attributes: flatbuffers::Vector<'_, u8>,
-) -> serde_json::Value {
+) -> Result<serde_json::Value, Error> {
     if attributes.is_empty() {
-        return serde_json::Value::Object(serde_json::Map::new());
+        return Ok(serde_json::Value::Object(serde_json::Map::new()));
     }
 
     let mut map = serde_json::Map::new();
@@ -260,121 +274,145 @@ pub fn decode_attributes(
     while offset < bytes.len() {
         let col_index = LittleEndian::read_u16(&bytes[offset..offset + size_of::<u16>()]) as u16;
         offset += size_of::<u16>();
-        if col_index >= columns.len() as u16 {
-            panic!("column index out of range"); //TODO: handle this as an error
+        let column = columns
+            .iter()
+            .find(|c| c.index() == col_index)
+            .ok_or_else(|| Error::InvalidAttributeValue {
+                msg: format!("column index {col_index} not found"),
+            })?;
+        if let Some(value) = decode_attribute_value(column.type_(), &bytes[offset..])? {
+            map.insert(column.name().to_string(), value);
         }
-        let column = columns.iter().find(|c| c.index() == col_index);
-        if column.is_none() {
-            panic!("column not found"); //TODO: handle this as an error
+        offset += attribute_value_size(column.type_(), &bytes[offset..])?;
+    }
+
+    Ok(serde_json::Value::Object(map))
+}
+
+/// Decodes a single attribute, skipping the bytes of the other columns
+///
+/// # Returns
+///
+/// The value of the column `name`, `None` if the attributes don't contain it
+pub fn decode_attribute(
+    columns: &flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>,
+    attributes: flatbuffers::Vector<'_, u8>,
+    name: &str,
+) -> Result<Option<serde_json::Value>, Error> {
+    let Some(target) = columns.iter().find(|c| c.name() == name) else {
+        return Ok(None);
+    };
+    let bytes = attributes.bytes();
+    let mut offset = 0;
+    while offset + size_of::<u16>() <= bytes.len() {
+        let col_index = LittleEndian::read_u16(&bytes[offset..offset + size_of::<u16>()]);
+        offset += size_of::<u16>();
+        if col_index == target.index() {
+            return decode_attribute_value(target.type_(), &bytes[offset..]);
         }
-        let column = column.unwrap();
-        match column.type_() {
-            ColumnType::Int => {
-                map.insert(
-                    column.name().to_string(),
-                    serde_json::Value::Number(serde_json::Number::from(LittleEndian::read_i32(
-                        &bytes[offset..offset + size_of::<i32>()],
-                    ))),
-                );
-                offset += size_of::<i32>();
-            }
-            ColumnType::UInt => {
-                map.insert(
-                    column.name().to_string(),
-                    serde_json::Value::Number(serde_json::Number::from(LittleEndian::read_u32(
-                        &bytes[offset..offset + size_of::<u32>()],
-                    ))),
-                );
-                offset += size_of::<u32>();
-            }
-            ColumnType::Bool => {
-                map.insert(
-                    column.name().to_string(),
-                    serde_json::Value::Bool(bytes[offset] != 0),
-                );
-                offset += size_of::<u8>();
-            }
-            ColumnType::Short => {
-                map.insert(
-                    column.name().to_string(),
-                    serde_json::Value::Number(serde_json::Number::from(LittleEndian::read_i16(
-                        &bytes[offset..offset + size_of::<i16>()],
-                    ))),
-                );
-                offset += size_of::<i16>();
-            }
-            ColumnType::UShort => {
-                map.insert(
-                    column.name().to_string(),
-                    serde_json::Value::Number(serde_json::Number::from(LittleEndian::read_u16(
-                        &bytes[offset..offset + size_of::<u16>()],
-                    ))),
-                );
-                offset += size_of::<u16>();
-            }
-            ColumnType::Long => {
-                map.insert(
-                    column.name().to_string(),
-                    serde_json::Value::Number(serde_json::Number::from(LittleEndian::read_i64(
-                        &bytes[offset..offset + size_of::<i64>()],
-                    ))),
-                );
-                offset += size_of::<i64>();
-            }
-            ColumnType::ULong => {
-                map.insert(
-                    column.name().to_string(),
-                    serde_json::Value::Number(serde_json::Number::from(LittleEndian::read_u64(
-                        &bytes[offset..offset + size_of::<u64>()],
-                    ))),
-                );
-                offset += size_of::<u64>();
-            }
-            ColumnType::Float => {
-                let f = LittleEndian::read_f32(&bytes[offset..offset + size_of::<f32>()]);
-                if let Some(num) = serde_json::Number::from_f64(f as f64) {
-                    map.insert(column.name().to_string(), serde_json::Value::Number(num));
-                }
-                offset += size_of::<f32>();
+        let column = columns
+            .iter()
+            .find(|c| c.index() == col_index)
+            .ok_or_else(|| Error::InvalidAttributeValue {
+                msg: format!("column index {col_index} not found"),
+            })?;
+        offset += attribute_value_size(column.type_(), &bytes[offset..])?;
+    }
+    Ok(None)
+}
+
+fn unsupported_column_type(column_type: ColumnType) -> Error {
+    Error::InvalidAttributeValue {
+        msg: format!("unsupported column type {column_type:?}"),
+    }
+}
+
+/// Number of bytes of the value at the start of `bytes`
+fn attribute_value_size(column_type: ColumnType, bytes: &[u8]) -> Result<usize, Error> {
+    let size = match column_type {
+        ColumnType::Byte | ColumnType::UByte | ColumnType::Bool => size_of::<u8>(),
+        ColumnType::Short | ColumnType::UShort => size_of::<u16>(),
+        ColumnType::Int | ColumnType::UInt | ColumnType::Float => size_of::<u32>(),
+        ColumnType::Long | ColumnType::ULong | ColumnType::Double => size_of::<u64>(),
+        ColumnType::String | ColumnType::DateTime | ColumnType::Json | ColumnType::Binary => {
+            size_of::<u32>() + LittleEndian::read_u32(&bytes[..size_of::<u32>()]) as usize
+        }
+        _ => return Err(unsupported_column_type(column_type)),
+    };
+    Ok(size)
+}
+
+/// Decodes the value at the start of `bytes`
+///
+/// # Returns
+///
+/// `None` for floats that are not valid JSON numbers and malformed JSON
+fn decode_attribute_value(
+    column_type: ColumnType,
+    bytes: &[u8],
+) -> Result<Option<serde_json::Value>, Error> {
+    let value = match column_type {
+        ColumnType::Byte => serde_json::Value::from(bytes[0] as i8),
+        ColumnType::UByte => serde_json::Value::from(bytes[0]),
+        ColumnType::Int => serde_json::Value::from(LittleEndian::read_i32(bytes)),
+        ColumnType::UInt => serde_json::Value::from(LittleEndian::read_u32(bytes)),
+        ColumnType::Bool => serde_json::Value::Bool(bytes[0] != 0),
+        ColumnType::Short => serde_json::Value::from(LittleEndian::read_i16(bytes)),
+        ColumnType::UShort => serde_json::Value::from(LittleEndian::read_u16(bytes)),
+        ColumnType::Long => serde_json::Value::from(LittleEndian::read_i64(bytes)),
+        ColumnType::ULong => serde_json::Value::from(LittleEndian::read_u64(bytes)),
+        ColumnType::Float => {
+            match serde_json::Number::from_f64(LittleEndian::read_f32(bytes) as f64) {
+                Some(n) => n.into(),
+                None => return Ok(None),
             }
-            ColumnType::Double => {
-                let f = LittleEndian::read_f64(&bytes[offset..offset + size_of::<f64>()]);
-                if let Some(num) = serde_json::Number::from_f64(f) {
-                    map.insert(column.name().to_string(), serde_json::Value::Number(num));
+        }
+        ColumnType::Double => match serde_json::Number::from_f64(LittleEndian::read_f64(bytes)) {
+            Some(n) => n.into(),
+            None => return Ok(None),
+        },
+        ColumnType::String | ColumnType::DateTime | ColumnType::Json | ColumnType::Binary => {
+            let len = LittleEndian::read_u32(bytes) as usize;
+            let s = String::from_utf8(bytes[size_of::<u32>()..size_of::<u32>() + len].to_vec())
+                .unwrap_or_default();
+            if column_type == ColumnType::Json {
+                // malformed JSON is dropped like other undecodable values
+                match serde_json::from_str(&s) {
+                    Ok(value) => value,
+                    Err(_) => return Ok(None),
                 }
-                offset += size_of::<f64>();
+            } else {
+                // binary values are written from JSON strings, see `encode_attributes_with_schema`
+                serde_json::Value::String(s)
             }
-            ColumnType::String => {
-                let len = LittleEndian::read_u32(&bytes[offset..offset + size_of::<u32>()]);
-                offset += size_of::<u32>();
-                let s = String::from_utf8(bytes[offset..offset + len as usize].to_vec())
-                    .unwrap_or_default();
-                map.insert(column.name().to_string(), serde_json::Value::String(s));
-                offset += len as usize;
-            }
-            ColumnType::DateTime => {
-                let len = LittleEndian::read_u32(&bytes[offset..offset + size_of::<u32>()]);
-                offset += size_of::<u32>();
-                let s = String::from_utf8(bytes[offset..offset + len as usize].to_vec())
-                    .unwrap_or_default();
-                map.insert(column.name().to_string(), serde_json::Value::String(s));
-                offset += len as usize;
-            }
-            ColumnType::Json => {
-                let len = LittleEndian::read_u32(&bytes[offset..offset + size_of::<u32>()]);
-                offset += size_of::<u32>();
-                let s = String::from_utf8(bytes[offset..offset + len as usize].to_vec())
-                    .unwrap_or_default();
-                map.insert(column.name().to_string(), serde_json::from_str(&s).unwrap());
-                offset += len as usize;
-            }
-
-            // TODO: handle other column types
-            _ => unreachable!(),
         }
+        _ => return Err(unsupported_column_type(column_type)),
+    };
+    Ok(Some(value))
+}
+
+/// Options for decoding features
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeserializeOptions<'a> {
+    /// Only keep geometries whose LOD is one of these, e.g. `["2.2", "1.2"]`. City objects
+    /// without a matching geometry get an empty `geometry` array.
+    ///
+    /// Geometry instances are not filtered and always kept: their LOD is the one of their
+    /// template in the header, which isn't looked up while decoding a feature.
+    pub lods: Option<&'a [String]>,
+}
+
+impl DeserializeOptions<'_> {
+    /// Whether a standard geometry with this LOD is decoded
+    fn keeps_lod(&self, lod: Option<&str>) -> bool {
+        self.lods
+            .is_none_or(|lods| lod.is_some_and(|lod| lods.iter().any(|l| l == lod)))
     }
 
-    serde_json::Value::Object(map)
+    /// Whether geometries are filtered by LOD
+    fn filters_lod(&self) -> bool {
+        self.lods.is_some()
+    }
 }
 
 pub fn to_cj_feature(
@@ -382,88 +420,156 @@ pub fn to_cj_feature(
     root_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
     semantic_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
 ) -> Result<CityJSONFeature, Error> {
-    // Ensure function returns Result
-    let mut cj = CityJSONFeature::new();
-    cj.id = feature.id().to_string();
+    to_cj_feature_with_options(
+        feature,
+        root_attr_schema,
+        semantic_attr_schema,
+        &DeserializeOptions::default(),
+    )
+}
 
-    if let Some(objects) = feature.objects() {
-        let city_objects_result: Result<HashMap<String, CjCityObject>, Error> = objects
-            .iter()
-            .map(|co| {
-                let geographical_extent = co.geographical_extent().map(|extent| {
-                    [
-                        extent.min().x(),
-                        extent.min().y(),
-                        extent.min().z(),
-                        extent.max().x(),
-                        extent.max().y(),
-                        extent.max().z(),
-                    ]
-                });
+pub fn to_cj_feature_with_options(
+    feature: CityFeature,
+    root_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
+    semantic_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
+    options: &DeserializeOptions,
+) -> Result<CityJSONFeature, Error> {
+    let mut cj = CityJSONFeature::new();
+    to_cj_feature_into_with_options(
+        feature,
+        root_attr_schema,
+        semantic_attr_schema,
+        options,
+        &mut cj,
+    )?;
+    Ok(cj)
+}
 
-                let mut all_geometries: Vec<cjseq::Geometry> = Vec::new();
+/// Decodes a feature into `cj`, replacing its content
+///
+/// The id string, the city object map and the vertex arrays of `cj` keep their capacity, so
+/// decoding many features into the same `CityJSONFeature` allocates less than
+/// [`to_cj_feature`].
+pub fn to_cj_feature_into(
+    feature: CityFeature,
+    root_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
+    semantic_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
+    cj: &mut CityJSONFeature,
+) -> Result<(), Error> {
+    to_cj_feature_into_with_options(
+        feature,
+        root_attr_schema,
+        semantic_attr_schema,
+        &DeserializeOptions::default(),
+        cj,
+    )
+}
 
-                // Process standard geometries
-                if let Some(standard_geometries) = co.geometry() {
-                    let decoded_standard = standard_geometries
-                        .iter()
-                        .map(|g| decode_geometry(g, semantic_attr_schema)) // Returns Result<CjGeometry, Error>
-                        .collect::<Result<Vec<_>, _>>()?; // Collect Results, propagate error
-                    all_geometries.extend(decoded_standard);
-                }
+fn to_cj_feature_into_with_options(
+    feature: CityFeature,
+    root_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
+    semantic_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
+    options: &DeserializeOptions,
+    cj: &mut CityJSONFeature,
+) -> Result<(), Error> {
+    if cj.thetype != "CityJSONFeature" {
+        cj.thetype = "CityJSONFeature".to_string();
+    }
+    cj.id.clear();
+    cj.id.push_str(feature.id());
+    cj.city_objects.clear();
 
-                // Process geometry instances
-                if let Some(instances) = co.geometry_instances() {
-                    let decoded_instances = instances
-                        .iter()
-                        .map(|inst| decode_geometry_instance(&inst)) // Use reference, returns Result<CjGeometry, Error>
-                        .collect::<Result<Vec<_>, _>>()?; // Collect Results, propagate error
-                    all_geometries.extend(decoded_instances);
-                }
+    if let Some(objects) = feature.objects() {
+        let city_objects = objects.iter().map(|co| {
+            let geographical_extent = co.geographical_extent().map(|extent| {
+                [
+                    extent.min().x(),
+                    extent.min().y(),
+                    extent.min().z(),
+                    extent.max().x(),
+                    extent.max().y(),
+                    extent.max().z(),
+                ]
+            });
+
+            let mut all_geometries: Vec<cjseq::Geometry> = Vec::new();
+
+            // Process standard geometries
+            if let Some(standard_geometries) = co.geometry() {
+                let decoded_standard = standard_geometries
+                    .iter()
+                    // skip geometries of other LODs before decoding them
+                    .filter(|g| options.keeps_lod(g.lod()))
+                    .map(|g| decode_geometry(g, semantic_attr_schema)) // Returns Result<CjGeometry, Error>
+                    .collect::<Result<Vec<_>, _>>()?; // Collect Results, propagate error
+                all_geometries.extend(decoded_standard);
+            }
 
-                let final_geometries = if all_geometries.is_empty() {
-                    None
-                } else {
-                    Some(all_geometries)
-                };
+            // Process geometry instances
+            if let Some(instances) = co.geometry_instances() {
+                let decoded_instances = instances
+                    .iter()
+                    .map(|inst| decode_geometry_instance(&inst)) // Use reference, returns Result<CjGeometry, Error>
+                    .collect::<Result<Vec<_>, _>>()?; // Collect Results, propagate error
+                all_geometries.extend(decoded_instances);
+            }
 
-                let attributes = if root_attr_schema.is_none() && co.columns().is_none() {
-                    None
-                } else {
-                    co.attributes().map(|a| {
+            let final_geometries = if all_geometries.is_empty() && !options.filters_lod() {
+                None
+            } else {
+                Some(all_geometries)
+            };
+
+            let attributes = if root_attr_schema.is_none() && co.columns().is_none() {
+                None
+            } else {
+                co.attributes()
+                    .map(|a| {
                         decode_attributes(&co.columns().unwrap_or(root_attr_schema.unwrap()), a)
                     })
-                };
-
-                let children_roles = co
-                    .children_roles()
-                    .map(|c| c.iter().map(|s| s.to_string()).collect());
-
-                let cjco = CjCityObject::new(
-                    to_cj_co_type(co.type_(), co.extension_type()),
-                    geographical_extent,
-                    attributes,
-                    final_geometries, // Use the combined list
-                    co.children()
-                        .map(|c| c.iter().map(|s| s.to_string()).collect()),
-                    children_roles,
-                    co.parents()
-                        .map(|p| p.iter().map(|s| s.to_string()).collect()),
-                    None, // Assuming appearance is handled elsewhere or not needed here
-                );
-                Ok((co.id().to_string(), cjco)) // Return Result for map operation
-            })
-            .collect(); // Collect Results from map
-
-        let city_objects = city_objects_result?;
-        cj.city_objects = city_objects;
+                    .transpose()?
+            };
+
+            let children_roles = co
+                .children_roles()
+                .map(|c| c.iter().map(|s| s.to_string()).collect());
+
+            let cjco = CjCityObject::new(
+                to_cj_co_type(co.type_(), co.extension_type()),
+                geographical_extent,
+                attributes,
+                final_geometries, // Use the combined list
+                co.children()
+                    .map(|c| c.iter().map(|s| s.to_string()).collect()),
+                children_roles,
+                co.parents()
+                    .map(|p| p.iter().map(|s| s.to_string()).collect()),
+                None, // Assuming appearance is handled elsewhere or not needed here
+            );
+            Ok::<_, Error>((co.id().to_string(), cjco))
+        });
+        for city_object in city_objects {
+            let (id, cjco) = city_object?;
+            cj.city_objects.insert(id, cjco);
+        }
     }
 
-    cj.vertices = feature
-        .vertices()
-        .map_or(Vec::new(), |v| to_cj_vertices(v.iter().collect()));
+    // vertex arrays are overwritten in place, only missing ones are allocated
+    let vertices = feature.vertices();
+    cj.vertices.truncate(vertices.map_or(0, |v| v.len()));
+    for (i, v) in vertices.iter().flat_map(|v| v.iter()).enumerate() {
+        let xyz = [v.x() as i64, v.y() as i64, v.z() as i64];
+        match cj.vertices.get_mut(i) {
+            Some(vertex) => {
+                vertex.clear();
+                vertex.extend_from_slice(&xyz);
+            }
+            None => cj.vertices.push(xyz.to_vec()),
+        }
+    }
 
     // Decode appearance if present
+    cj.appearance = None;
     if let Some(appearance) = feature.appearance() {
         let mut cj_appearance = CjAppearance {
             materials: None,
@@ -563,7 +669,85 @@ pub fn to_cj_feature(
         cj.appearance = Some(cj_appearance);
     }
 
-    Ok(cj) // Return Result
+    Ok(())
+}
+
+/// Copies the vertices of a decoded feature back from the global vertex table of the header
+///
+/// Features of files written with `FcbWriter::with_global_vertices` have no vertices, their
+/// boundaries index the table. They are remapped to index the vertices of the feature, in the
+/// order of their first use. Features of other files are left as they are.
+pub fn resolve_global_vertices(cj: &mut CityJSONFeature, header: &Header) -> Result<(), Error> {
+    match header.global_vertices() {
+        Some(global_vertices) => localize_vertices(cj, global_vertices),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "global-vertices")]
+fn localize_vertices(
+    cj: &mut CityJSONFeature,
+    global_vertices: GlobalVertices,
+) -> Result<(), Error> {
+    let table = global_vertices.vertices();
+    let table_len = table.map_or(0, |t| t.len() / 3);
+    let mut local_ids: HashMap<u32, u32> = HashMap::new();
+    let mut vertices = Vec::new();
+    let mut localize = |global_id: u32| -> Result<u32, Error> {
+        if let Some(local_id) = local_ids.get(&global_id) {
+            return Ok(*local_id);
+        }
+        let i = 3 * global_id as usize;
+        let table = table
+            .filter(|_| (global_id as usize) < table_len)
+            .ok_or_else(|| {
+                Error::GlobalVertices(format!(
+                    "vertex {global_id} is out of range of the {table_len} global vertices"
+                ))
+            })?;
+        vertices.push(vec![
+            table.get(i) as i64,
+            table.get(i + 1) as i64,
+            table.get(i + 2) as i64,
+        ]);
+        let local_id = (vertices.len() - 1) as u32;
+        local_ids.insert(global_id, local_id);
+        Ok(local_id)
+    };
+    for co in cj.city_objects.values_mut() {
+        for geometry in co.geometry.iter_mut().flatten() {
+            try_remap_boundaries(&mut geometry.boundaries, &mut localize)?;
+        }
+    }
+    cj.vertices = vertices;
+    Ok(())
+}
+
+#[cfg(not(feature = "global-vertices"))]
+fn localize_vertices(_: &mut CityJSONFeature, _: GlobalVertices) -> Result<(), Error> {
+    Err(Error::GlobalVertices(
+        "reading files with a global vertex table needs the `global-vertices` feature".to_string(),
+    ))
+}
+
+#[cfg(feature = "global-vertices")]
+fn try_remap_boundaries(
+    boundaries: &mut CjBoundaries,
+    remap: &mut impl FnMut(u32) -> Result<u32, Error>,
+) -> Result<(), Error> {
+    match boundaries {
+        CjBoundaries::Indices(indices) => {
+            for i in indices.iter_mut() {
+                *i = remap(*i)?;
+            }
+        }
+        CjBoundaries::Nested(nested) => {
+            for b in nested.iter_mut() {
+                try_remap_boundaries(b, remap)?;
+            }
+        }
+    }
+    Ok(())
 }
 
 pub(crate) fn decode_geometry(
@@ -603,7 +787,7 @@ pub(crate) fn decode_geometry(
             semantics_objects,
             semantics,
             semantic_attr_schema,
-        ))
+        )?)
     } else {
         None
     };
@@ -700,13 +884,6 @@ pub(crate) fn decode_geometry_instance(instance: &GeometryInstance) -> Result<Cj
     })
 }
 
-pub(crate) fn to_cj_vertices(vertices: Vec<&Vertex>) -> Vec<Vec<i64>> {
-    vertices
-        .iter()
-        .map(|v| vec![v.x() as i64, v.y() as i64, v.z() as i64])
-        .collect()
-}
-
 /// Convert a FlatBuffer Extension to a CityJSON ExtensionFile
 ///
 /// # Arguments
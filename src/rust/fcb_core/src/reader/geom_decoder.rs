@@ -6,6 +6,7 @@ use cjseq::{
 };
 
 use crate::{
+    error::Error,
     fb::{GeometryType, MaterialMapping, SemanticObject, SemanticSurfaceType, TextureMapping},
     Column,
 };
@@ -175,7 +176,7 @@ pub(crate) fn decode(
 pub(crate) fn decode_semantics_surfaces(
     semantics_objects: &[SemanticObject],
     semantic_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
-) -> Vec<SemanticsSurface> {
+) -> Result<Vec<SemanticsSurface>, Error> {
     let surfaces = semantics_objects.iter().map(|s| {
         let surface_type_str = match s.type_() {
             // For extended semantic surfaces, use the extension_type
@@ -202,23 +203,26 @@ pub(crate) fn decode_semantics_surfaces(
             SemanticSurfaceType::AuxiliaryTrafficArea => "AuxiliaryTrafficArea".to_string(),
             SemanticSurfaceType::TransportationMarking => "TransportationMarking".to_string(),
             SemanticSurfaceType::TransportationHole => "TransportationHole".to_string(),
-            _ => "Unknown".to_string(), // Fallback for unhandled types
+            // Fallback for unhandled types, keeping the raw type string when it was stored
+            _ => s.extension_type().unwrap_or("Unknown").to_string(),
         };
 
         let children = s.children().map(|c| c.iter().collect::<Vec<_>>());
 
         let attributes = if let Some(schema) = &semantic_attr_schema {
-            s.attributes().map(|a| decode_attributes(schema, a))
+            s.attributes()
+                .map(|a| decode_attributes(schema, a))
+                .transpose()?
         } else {
             None
         };
 
-        SemanticsSurface {
+        Ok(SemanticsSurface {
             thetype: surface_type_str,
             parent: s.parent(),
             children,
             other: attributes,
-        }
+        })
     });
     surfaces.collect()
 }
@@ -337,8 +341,8 @@ pub(crate) fn decode_semantics(
     semantics_objects: Vec<SemanticObject>,
     semantics_values: Vec<u32>,
     semantic_attr_schema: Option<flatbuffers::Vector<'_, flatbuffers::ForwardsUOffset<Column<'_>>>>,
-) -> Semantics {
-    let surfaces = decode_semantics_surfaces(&semantics_objects, semantic_attr_schema);
+) -> Result<Semantics, Error> {
+    let surfaces = decode_semantics_surfaces(&semantics_objects, semantic_attr_schema)?;
 
     let mut part_lists = PartLists {
         solids,
@@ -363,18 +367,18 @@ pub(crate) fn decode_semantics(
         for &val in &semantics_values {
             leaf.push(if val == u32::MAX { None } else { Some(val) });
         }
-        return Semantics {
+        return Ok(Semantics {
             surfaces,
             values: SemanticsValues::Indices(leaf),
-        };
+        });
     }
 
     let result = decode_semantics_(d, None, None, &mut part_lists, &semantics_values);
 
-    Semantics {
+    Ok(Semantics {
         surfaces,
         values: result,
-    }
+    })
 }
 
 impl GeometryType {
@@ -1128,7 +1132,7 @@ mod tests {
                 geometry.semantics_objects().unwrap().iter().collect(),
                 geometry.semantics().unwrap().iter().collect(),
                 header.semantic_columns(),
-            );
+            )?;
 
             // Verify decoded surfaces
             assert_eq!(3, decoded.surfaces.len());
@@ -1301,7 +1305,7 @@ mod tests {
                 geometry.semantics_objects().unwrap().iter().collect(),
                 geometry.semantics().unwrap().iter().collect(),
                 None,
-            );
+            )?;
 
             // Verify decoded surfaces
             assert_eq!(decoded.surfaces.len(), 2);
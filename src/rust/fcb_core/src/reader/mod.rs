@@ -3,13 +3,15 @@ pub mod deserializer;
 use crate::static_btree::Offset;
 use city_buffer::*;
 use cjseq::CityJSONFeature;
-use deserializer::to_cj_feature;
+use deserializer::DeserializeOptions;
 
+use crate::checksum::{crc32, sha256};
 use crate::error::Error;
 use crate::fb::{size_prefixed_root_as_city_feature, CityFeature};
-use crate::packed_rtree::{self, PackedRTree, Query};
+use crate::packed_rtree::{self, NodeLayout, PackedRTree, Query};
 use crate::{
-    check_magic_bytes, size_prefixed_root_as_header, Column, Header, HEADER_MAX_BUFFER_SIZE,
+    parse_magic_bytes, size_prefixed_root_as_header, Column, Header, CHECKSUM_SIZE,
+    HEADER_MAX_BUFFER_SIZE,
 };
 use fallible_streaming_iterator::FallibleStreamingIterator;
 use std::io::{self, Read, Seek, SeekFrom, Write};
@@ -19,10 +21,18 @@ pub use attr_query::*;
 use std::marker::PhantomData;
 mod meta;
 pub use meta::*;
+mod polygon;
+pub use polygon::*;
+mod tiled;
+pub use tiled::*;
+mod type_filter;
+pub use type_filter::*;
 pub struct FcbReader<R> {
     reader: R,
     verify: bool,
     buffer: FcbBuffer,
+    /// File format version found in the magic bytes
+    version: u8,
 }
 
 pub struct FeatureIter<R, S> {
@@ -42,12 +52,18 @@ pub struct FeatureIter<R, S> {
     feat_no: usize,
     /// File offset within feature section
     cur_pos: u64,
+    /// Offset of the size prefix of the current feature within feature section
+    cur_feature_offset: u64,
     /// Reading state
     state: State,
     /// Whether or not the underlying reader is Seek
     seekable_marker: PhantomData<S>,
     feature_offset: FeatureOffset,
     total_feat_count: u64,
+    /// Selected city object types or None if no type filter
+    type_filter: Option<CityObjectTypeFilter>,
+    /// LODs of the geometries to decode or None to decode all geometries
+    filter_lod: Option<Vec<String>>,
 }
 
 #[doc(hidden)]
@@ -91,9 +107,7 @@ impl<R: Read> FcbReader<R> {
     fn read_header(mut reader: R, verify: bool) -> Result<FcbReader<R>, Error> {
         let mut magic_buf: [u8; 8] = [0; 8];
         reader.read_exact(&mut magic_buf)?;
-        if !check_magic_bytes(&magic_buf) {
-            return Err(Error::MissingMagicBytes);
-        }
+        let version = parse_magic_bytes(&magic_buf)?;
 
         let mut size_buf: [u8; 4] = [0; 4]; // MEMO: 4 bytes for size prefix. This is comvention for FlatBuffers's size_prefixed_root
         reader.read_exact(&mut size_buf)?;
@@ -114,6 +128,7 @@ impl<R: Read> FcbReader<R> {
         Ok(FcbReader {
             reader,
             verify,
+            version,
             buffer: FcbBuffer {
                 header_buf,
                 features_buf: Vec::new(),
@@ -123,12 +138,12 @@ impl<R: Read> FcbReader<R> {
 
     pub fn select_all_seq(mut self) -> Result<FeatureIter<R, NotSeekable>, Error> {
         let index_size = self.attr_index_size() + self.rtree_index_size();
-        // discard bufer of index
+        // discard the R-tree and the attribute indices, the stream cannot seek past them
         io::copy(&mut (&mut self.reader).take(index_size), &mut io::sink())?;
         let feature_offset = FeatureOffset {
             magic_bytes: 8,
             header: 4 + self.buffer.header_buf.len() as u64,
-            rtree_index: index_size,
+            rtree_index: self.rtree_index_size(),
             attributes: self.attr_index_size(),
         };
         let total_feat_count = self.buffer.header().features_count();
@@ -143,16 +158,32 @@ impl<R: Read> FcbReader<R> {
         ))
     }
 
+    /// Select features intersecting the given 2D bounding box from a non-seekable stream.
+    ///
+    /// The whole spatial index is read into memory, then non-matching features are consumed
+    /// and discarded while streaming the feature section.
+    pub fn select_bbox_seq(
+        self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> Result<FeatureIter<R, NotSeekable>, Error> {
+        self.select_query_seq(Query::BBox(min_x, min_y, max_x, max_y))
+    }
+
     pub fn select_query_seq(mut self, query: Query) -> Result<FeatureIter<R, NotSeekable>, Error> {
         // Read R-Tree index and build filter for features within bbox
         let header = self.buffer.header();
         if header.index_node_size() == 0 || header.features_count() == 0 {
             return Err(Error::NoIndex);
         }
+        let layout = self.node_layout();
         let index = PackedRTree::from_buf(
             &mut self.reader,
             header.features_count() as usize,
             header.index_node_size(),
+            layout,
         )?;
         let list = index.search(query)?;
         debug_assert!(
@@ -204,17 +235,181 @@ impl<R: Read + Seek> FcbReader<R> {
         ))
     }
 
+    /// Extent of all features, read from the root node of the spatial index. The reader stays
+    /// positioned at the start of the index. `None` if the file has no spatial index.
+    pub fn index_extent(&mut self) -> Result<Option<packed_rtree::NodeItem>, Error> {
+        let header = self.buffer.header();
+        if header.index_node_size() == 0 || header.features_count() == 0 {
+            return Ok(None);
+        }
+        // nodes are stored top-down, so the root comes first
+        let layout = self.node_layout();
+        let root = packed_rtree::NodeItem::from_reader_with_layout(&mut self.reader, layout)?;
+        self.reader
+            .seek(SeekFrom::Current(-(layout.item_size() as i64)))?;
+        Ok(Some(root))
+    }
+
+    /// Recomputes the SHA-256 of the feature section and compares it with the content hash of
+    /// the header, `false` if the features were altered. The reader stays positioned at the
+    /// start of the index.
+    ///
+    /// Fails with [`Error::NoContentHash`] for files written without
+    /// `HeaderWriterOptions::compute_hash`.
+    pub fn verify_hash(&mut self) -> Result<bool, Error> {
+        let Some(expected) = self.buffer.header().content_hash() else {
+            return Err(Error::NoContentHash);
+        };
+        let expected = expected.bytes().to_vec();
+        let index_start = self.reader.stream_position()?;
+        let index_size = self.rtree_index_size() + self.attr_index_size();
+        self.reader.seek(SeekFrom::Current(index_size as i64))?;
+        let actual = sha256(&mut self.reader);
+        self.reader.seek(SeekFrom::Start(index_start))?;
+        Ok(actual?[..] == expected[..])
+    }
+
+    /// Select features intersecting the given 2D bounding box using the spatial index
+    pub fn select_bbox(
+        self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> Result<FeatureIter<R, Seekable>, Error> {
+        self.select_query(Query::BBox(min_x, min_y, max_x, max_y))
+    }
+
+    /// Select features intersecting the given 3D bounding box using the spatial index
+    pub fn select_bbox_3d(
+        self,
+        min_x: f64,
+        min_y: f64,
+        min_z: f64,
+        max_x: f64,
+        max_y: f64,
+        max_z: f64,
+    ) -> Result<FeatureIter<R, Seekable>, Error> {
+        self.select_query(Query::BBox3D(min_x, min_y, min_z, max_x, max_y, max_z))
+    }
+
+    /// Select the `k` features nearest to the point by centroid distance, in file order
+    pub fn select_nearest(
+        self,
+        x: f64,
+        y: f64,
+        k: usize,
+    ) -> Result<FeatureIter<R, Seekable>, Error> {
+        self.select_query(Query::KNearest(x, y, k))
+    }
+
+    /// Count the features intersecting the given bounding box, reading only the spatial index
+    pub fn count_bbox(
+        &mut self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> Result<usize, Error> {
+        self.count_query(Query::BBox(min_x, min_y, max_x, max_y))
+    }
+
+    /// Count the features matching a spatial query without reading any feature.
+    ///
+    /// The reader is left at the start of the index, so a `select_*` method can follow.
+    pub fn count_query(&mut self, query: Query) -> Result<usize, Error> {
+        let header = self.buffer.header();
+        if header.index_node_size() == 0 {
+            return Err(Error::NoIndex);
+        }
+        if header.features_count() == 0 {
+            return Ok(0);
+        }
+        let index_start = self.reader.stream_position()?;
+        let layout = self.node_layout();
+        let count = PackedRTree::stream_count(
+            &mut self.reader,
+            header.features_count() as usize,
+            header.index_node_size(),
+            query,
+            layout,
+        )?;
+        self.reader.seek(SeekFrom::Start(index_start))?;
+        Ok(count)
+    }
+
+    /// Select features with at least one vertex inside the given polygon ring.
+    ///
+    /// Candidates are selected by the bounding box of the ring using the spatial index,
+    /// then tested against the ring in real-world coordinates (after applying the transform).
+    pub fn select_polygon(mut self, ring: &[[f64; 2]]) -> Result<FeatureIter<R, Seekable>, Error> {
+        let header = self.buffer.header();
+        if header.index_node_size() == 0 || header.features_count() == 0 {
+            return Err(Error::NoIndex);
+        }
+        let filter = PolygonFilter::new(ring, &header)?;
+        let layout = self.node_layout();
+        let candidates = PackedRTree::stream_search(
+            &mut self.reader,
+            header.features_count() as usize,
+            header.index_node_size(),
+            filter.bbox_query(),
+            layout,
+        )?;
+        // skip index
+        self.reader
+            .seek(SeekFrom::Current(self.attr_index_size() as i64))?;
+        let features_start = self.reader.stream_position()?;
+
+        let mut list = Vec::with_capacity(candidates.len());
+        let mut feature_buf = Vec::new();
+        for item in candidates {
+            self.reader
+                .seek(SeekFrom::Start(features_start + item.offset as u64))?;
+            let mut size_buf = [0u8; 4];
+            self.reader.read_exact(&mut size_buf)?;
+            let feature_size = u32::from_le_bytes(size_buf) as usize;
+            feature_buf.resize(feature_size + 4, 0);
+            feature_buf[..4].copy_from_slice(&size_buf);
+            self.reader.read_exact(&mut feature_buf[4..])?;
+            let feature = size_prefixed_root_as_city_feature(&feature_buf)?;
+            if filter.matches(&feature) {
+                list.push(item);
+            }
+        }
+        self.reader.seek(SeekFrom::Start(features_start))?;
+
+        let feature_offset = FeatureOffset {
+            magic_bytes: 8,
+            header: 4 + self.buffer.header_buf.len() as u64,
+            rtree_index: self.rtree_index_size(),
+            attributes: self.attr_index_size(),
+        };
+        let total_feat_count = list.len() as u64;
+        Ok(FeatureIter::new(
+            self.reader,
+            self.verify,
+            self.buffer,
+            Some(list),
+            None,
+            feature_offset,
+            total_feat_count,
+        ))
+    }
+
     pub fn select_query(mut self, query: Query) -> Result<FeatureIter<R, Seekable>, Error> {
         // Read R-Tree index and build filter for features within bbox
         let header = self.buffer.header();
         if header.index_node_size() == 0 || header.features_count() == 0 {
             return Err(Error::NoIndex);
         }
+        let layout = self.node_layout();
         let list = PackedRTree::stream_search(
             &mut self.reader,
             header.features_count() as usize,
-            PackedRTree::DEFAULT_NODE_SIZE,
+            header.index_node_size(),
             query,
+            layout,
         )?;
         debug_assert!(
             list.windows(2).all(|w| w[0].offset < w[1].offset),
@@ -247,43 +442,65 @@ impl<R: Read> FcbReader<R> {
         self.buffer.header()
     }
 
+    /// File format version of the file, between `MIN_SUPPORTED_VERSION` and `VERSION`
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
     pub fn root_attr_schema(
         &self,
     ) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Column>>> {
         self.buffer.header().columns()
     }
 
+    /// Layout of the R-tree nodes, which changed with version 2
+    fn node_layout(&self) -> NodeLayout {
+        NodeLayout::for_version(self.version)
+    }
+
     fn rtree_index_size(&self) -> u64 {
-        let header = self.buffer.header();
-        let feat_count = header.features_count() as usize;
-        if header.index_node_size() > 0 && feat_count > 0 {
-            PackedRTree::index_size(feat_count, header.index_node_size()) as u64
-        } else {
-            0
-        }
+        rtree_index_size(&self.buffer.header(), self.node_layout())
     }
 
     fn attr_index_size(&self) -> u64 {
-        let header = self.buffer.header();
-        header
-            .attribute_index()
-            .map(|attr_index| {
-                attr_index
-                    .iter()
-                    .try_fold(0u32, |acc, ai| {
-                        let len = ai.length();
-                        if len > u32::MAX - acc {
-                            Err(Error::AttributeIndexSizeOverflow)
-                        } else {
-                            Ok(acc + len)
-                        }
-                    })
-                    .unwrap_or(0)
-            })
-            .unwrap_or(0) as u64
+        attr_index_size(&self.buffer.header())
     }
 }
 
+/// Size in bytes of the R-tree index described by the header, 0 without index
+pub(crate) fn rtree_index_size(header: &Header, layout: NodeLayout) -> u64 {
+    let feat_count = header.features_count() as usize;
+    if header.index_node_size() > 0 && feat_count > 0 {
+        PackedRTree::index_size(feat_count, header.index_node_size(), layout) as u64
+    } else {
+        0
+    }
+}
+
+/// Size in bytes of the attribute and composite indices described by the header
+pub(crate) fn attr_index_size(header: &Header) -> u64 {
+    // composite indices are stored right after the single-column ones
+    let composite_lengths = header
+        .composite_index()
+        .into_iter()
+        .flat_map(|ci| ci.iter())
+        .map(|ci| ci.length());
+    header
+        .attribute_index()
+        .into_iter()
+        .flat_map(|ai| ai.iter())
+        .map(|ai| ai.length())
+        .chain(composite_lengths)
+        .try_fold(0u32, |acc, len| {
+            if len > u32::MAX - acc {
+                Err(Error::AttributeIndexSizeOverflow)
+            } else {
+                Ok(acc + len)
+            }
+        }) // sum of all attribute index lengths
+        .unwrap_or(0) as u64
+}
+
 impl FeatureOffset {
     fn total_size(&self) -> u64 {
         self.magic_bytes + self.header + self.rtree_index + self.attributes
@@ -295,6 +512,25 @@ impl<R: Read> FallibleStreamingIterator for FeatureIter<R, NotSeekable> {
     type Error = Error;
 
     fn advance(&mut self) -> Result<(), Error> {
+        loop {
+            self.advance_one()?;
+            if self.type_filter_matches() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn get(&self) -> Option<&FcbBuffer> {
+        self.iter_get()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter_size_hint()
+    }
+}
+
+impl<R: Read> FeatureIter<R, NotSeekable> {
+    fn advance_one(&mut self) -> Result<(), Error> {
         if self.advance_finished() {
             return Ok(());
         }
@@ -326,6 +562,20 @@ impl<R: Read> FallibleStreamingIterator for FeatureIter<R, NotSeekable> {
 
         self.read_feature()
     }
+}
+
+impl<R: Read + Seek> FallibleStreamingIterator for FeatureIter<R, Seekable> {
+    type Item = FcbBuffer;
+    type Error = Error;
+
+    fn advance(&mut self) -> Result<(), Error> {
+        loop {
+            self.advance_one()?;
+            if self.type_filter_matches() {
+                return Ok(());
+            }
+        }
+    }
 
     fn get(&self) -> Option<&FcbBuffer> {
         self.iter_get()
@@ -336,11 +586,8 @@ impl<R: Read> FallibleStreamingIterator for FeatureIter<R, NotSeekable> {
     }
 }
 
-impl<R: Read + Seek> FallibleStreamingIterator for FeatureIter<R, Seekable> {
-    type Item = FcbBuffer;
-    type Error = Error;
-
-    fn advance(&mut self) -> Result<(), Error> {
+impl<R: Read + Seek> FeatureIter<R, Seekable> {
+    fn advance_one(&mut self) -> Result<(), Error> {
         if self.advance_finished() {
             return Ok(());
         }
@@ -370,14 +617,6 @@ impl<R: Read + Seek> FallibleStreamingIterator for FeatureIter<R, Seekable> {
 
         self.read_feature()
     }
-
-    fn get(&self) -> Option<&FcbBuffer> {
-        self.iter_get()
-    }
-
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.iter_size_hint()
-    }
 }
 
 impl<R: Read> FeatureIter<R, NotSeekable> {
@@ -386,11 +625,8 @@ impl<R: Read> FeatureIter<R, NotSeekable> {
     }
 
     pub fn cur_cj_feature(&self) -> Result<CityJSONFeature, Error> {
-        let fcb_feature = self.buffer.feature();
-        let root_attr_schema = self.buffer.header().columns();
-        let semantic_attr_schema = self.buffer.header().semantic_columns();
-
-        to_cj_feature(fcb_feature, root_attr_schema, semantic_attr_schema)
+        self.buffer
+            .cj_feature_with_options(&self.deserialize_options())
     }
 
     pub fn get_features(&mut self) -> Result<Vec<CityFeature>, Error> {
@@ -400,7 +636,9 @@ impl<R: Read> FeatureIter<R, NotSeekable> {
 
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<&Self>, Error> {
-        self.advance()?;
+        let feat_no = self.feat_no;
+        self.advance()
+            .map_err(|e| e.with_context(format!("feature {feat_no}")))?;
         if self.get().is_some() {
             Ok(Some(self))
         } else {
@@ -418,10 +656,8 @@ impl<R: Read + Seek> FeatureIter<R, Seekable> {
     }
     /// Return current feature
     pub fn cur_cj_feature(&self) -> Result<CityJSONFeature, Error> {
-        let fcb_feature = self.buffer.feature();
-        let root_attr_schema = self.buffer.header().columns();
-        let semantic_attr_schema = self.buffer.header().semantic_columns();
-        to_cj_feature(fcb_feature, root_attr_schema, semantic_attr_schema)
+        self.buffer
+            .cj_feature_with_options(&self.deserialize_options())
     }
 
     pub fn get_features(&mut self, _: impl Write) -> Result<(), Error> {
@@ -434,13 +670,59 @@ impl<R: Read + Seek> FeatureIter<R, Seekable> {
 
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<&Self>, Error> {
-        self.advance()?;
+        let feat_no = self.feat_no;
+        self.advance()
+            .map_err(|e| e.with_context(format!("feature {feat_no}")))?;
         if self.get().is_some() {
             Ok(Some(self))
         } else {
             Ok(None)
         }
     }
+
+    /// Restart the iteration at the first selected feature, keeping the selection and filters.
+    /// Does nothing if no feature has been read yet.
+    pub fn rewind(&mut self) -> Result<(), Error> {
+        if self.feat_no == 0 {
+            return Ok(());
+        }
+        self.reader
+            .seek(SeekFrom::Start(self.feature_offset.total_size()))?;
+        self.feat_no = 0;
+        self.cur_pos = 0;
+        self.cur_feature_offset = 0;
+        self.state = if self.read_feature_size() {
+            State::Finished
+        } else {
+            State::ReadFirstFeatureSize
+        };
+        Ok(())
+    }
+}
+
+impl<R: Read, S> FeatureIter<R, S>
+where
+    Self: FallibleStreamingIterator<Item = FcbBuffer, Error = Error>,
+{
+    /// Read up to `n` features, fewer once the selection is exhausted
+    pub fn batch_next(&mut self, n: usize) -> Result<Vec<CityJSONFeature>, Error> {
+        // the size hint keeps huge batch sizes from over-allocating
+        let capacity = self
+            .iter_size_hint()
+            .1
+            .map_or(n, |remaining| remaining.min(n));
+        let mut features = Vec::with_capacity(capacity);
+        while features.len() < n {
+            let feat_no = self.feat_no;
+            FallibleStreamingIterator::advance(self)
+                .map_err(|e| e.with_context(format!("feature {feat_no}")))?;
+            let Some(buffer) = FallibleStreamingIterator::get(self) else {
+                break;
+            };
+            features.push(buffer.cj_feature_with_options(&self.deserialize_options())?);
+        }
+        Ok(features)
+    }
 }
 
 impl<R: Read, S> FeatureIter<R, S> {
@@ -462,10 +744,13 @@ impl<R: Read, S> FeatureIter<R, S> {
             count: None,
             feat_no: 0,
             cur_pos: 0,
+            cur_feature_offset: 0,
             state: State::Init,
             seekable_marker: PhantomData,
             feature_offset,
             total_feat_count,
+            type_filter: None,
+            filter_lod: None,
         };
 
         if iter.read_feature_size() {
@@ -505,6 +790,47 @@ impl<R: Read, S> FeatureIter<R, S> {
         Some(self.total_feat_count as usize)
     }
 
+    /// Byte offset of the size prefix of the most recently read feature.
+    ///
+    /// The offset is relative to the start of the feature section, like the offsets stored in
+    /// the R-tree leaf nodes and the attribute indices.
+    pub fn current_offset(&self) -> u64 {
+        self.cur_feature_offset
+    }
+
+    /// Only yield features with at least one city object of the given types.
+    ///
+    /// The type is read from the encoded feature, so skipped features are never deserialized.
+    /// `features_count` still reports the number of features selected before type filtering.
+    pub fn filter_by_type(mut self, types: CityObjectTypeFilter) -> Self {
+        self.type_filter = Some(types);
+        self
+    }
+
+    /// Only decode geometries of the given LODs, e.g. `&["2.2"]`.
+    ///
+    /// Other geometries are skipped before decoding, city objects without a matching geometry
+    /// get an empty `geometry` array. Geometry instances are always kept, see
+    /// [`DeserializeOptions::lods`].
+    pub fn with_lod_filter(mut self, lods: &[&str]) -> Self {
+        self.filter_lod = Some(lods.iter().map(|lod| lod.to_string()).collect());
+        self
+    }
+
+    fn deserialize_options(&self) -> DeserializeOptions<'_> {
+        DeserializeOptions {
+            lods: self.filter_lod.as_deref(),
+        }
+    }
+
+    /// True when iteration is finished or the current feature passes the type filter
+    fn type_filter_matches(&self) -> bool {
+        match &self.type_filter {
+            Some(filter) if self.state != State::Finished => filter.matches(&self.buffer.feature()),
+            _ => true,
+        }
+    }
+
     fn advance_finished(&mut self) -> bool {
         if self.state == State::Finished {
             return true;
@@ -555,10 +881,14 @@ impl<R: Read, S> FeatureIter<R, S> {
                 unreachable!("should have read first feature size before reading any features")
             }
         }
+        self.cur_feature_offset = self.cur_pos - 4;
         let sbuf = &self.buffer.features_buf;
         let feature_size = u32::from_le_bytes([sbuf[0], sbuf[1], sbuf[2], sbuf[3]]) as usize;
         self.buffer.features_buf.resize(feature_size + 4, 0);
         self.reader.read_exact(&mut self.buffer.features_buf[4..])?;
+        if self.buffer.header().checksum() {
+            self.verify_checksum()?;
+        }
         if self.verify {
             let _feature = size_prefixed_root_as_city_feature(&self.buffer.features_buf)?;
         }
@@ -568,6 +898,23 @@ impl<R: Read, S> FeatureIter<R, S> {
         Ok(())
     }
 
+    /// Reads the CRC-32 following the current feature and compares it with the feature bytes
+    fn verify_checksum(&mut self) -> Result<(), Error> {
+        let mut crc_buf = [0u8; CHECKSUM_SIZE];
+        self.reader.read_exact(&mut crc_buf)?;
+        self.cur_pos += CHECKSUM_SIZE as u64;
+        let expected = u32::from_le_bytes(crc_buf);
+        let actual = crc32(&self.buffer.features_buf);
+        if expected != actual {
+            return Err(Error::ChecksumMismatch {
+                feature_index: self.feat_no,
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
     fn iter_get(&self) -> Option<&FcbBuffer> {
         if self.state == State::Finished {
             None
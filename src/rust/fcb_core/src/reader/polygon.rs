@@ -0,0 +1,121 @@
+use crate::deserializer::header_transform;
+use crate::error::{Error, Result};
+use crate::fb::CityFeature;
+use crate::packed_rtree::Query;
+use crate::Header;
+
+/// Polygon filter applied to the candidates of a bounding box search.
+///
+/// The ring is given in real-world coordinates, it may be open or closed.
+#[derive(Debug, Clone)]
+pub struct PolygonFilter {
+    ring: Vec<[f64; 2]>,
+    scale: [f64; 3],
+    translate: [f64; 3],
+}
+
+impl PolygonFilter {
+    pub fn new(ring: &[[f64; 2]], header: &Header) -> Result<Self> {
+        if ring.len() < 3 {
+            return Err(Error::QueryExecutionError(format!(
+                "polygon ring needs at least 3 vertices, got {}",
+                ring.len()
+            )));
+        }
+        if header.global_vertices().is_some() {
+            // the features store no vertices to test against the ring
+            return Err(Error::GlobalVertices(
+                "polygon selections are not supported on files with a global vertex table"
+                    .to_string(),
+            ));
+        }
+        let (scale, translate) = header_transform(header);
+        Ok(PolygonFilter {
+            ring: ring.to_vec(),
+            scale,
+            translate,
+        })
+    }
+
+    /// Bounding box query of the ring, used to select candidates from the R-tree
+    pub fn bbox_query(&self) -> Query {
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for [x, y] in &self.ring {
+            min_x = min_x.min(*x);
+            min_y = min_y.min(*y);
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+        Query::BBox(min_x, min_y, max_x, max_y)
+    }
+
+    /// Whether any vertex of the feature lies inside the ring
+    pub fn matches(&self, feature: &CityFeature) -> bool {
+        feature.vertices().is_some_and(|vertices| {
+            vertices.iter().any(|v| {
+                let x = v.x() as f64 * self.scale[0] + self.translate[0];
+                let y = v.y() as f64 * self.scale[1] + self.translate[1];
+                point_in_ring([x, y], &self.ring)
+            })
+        })
+    }
+}
+
+/// Winding number of the ring around the point, non-zero if the point is inside
+pub fn winding_number(point: [f64; 2], ring: &[[f64; 2]]) -> i32 {
+    let mut wn = 0;
+    for (i, a) in ring.iter().enumerate() {
+        let b = &ring[(i + 1) % ring.len()];
+        if a[1] <= point[1] {
+            // upward crossing with the point left of the edge
+            if b[1] > point[1] && is_left(a, b, &point) > 0.0 {
+                wn += 1;
+            }
+        } else if b[1] <= point[1] && is_left(a, b, &point) < 0.0 {
+            // downward crossing with the point right of the edge
+            wn -= 1;
+        }
+    }
+    wn
+}
+
+pub fn point_in_ring(point: [f64; 2], ring: &[[f64; 2]]) -> bool {
+    winding_number(point, ring) != 0
+}
+
+/// > 0 if p is left of the line through a and b, < 0 if right, 0 if on the line
+fn is_left(a: &[f64; 2], b: &[f64; 2], p: &[f64; 2]) -> f64 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (p[0] - a[0]) * (b[1] - a[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_in_ring() {
+        // L-shaped district, counter-clockwise and open
+        let ring = [
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 4.0],
+            [4.0, 4.0],
+            [4.0, 10.0],
+            [0.0, 10.0],
+        ];
+        assert!(point_in_ring([2.0, 2.0], &ring));
+        assert!(point_in_ring([8.0, 2.0], &ring));
+        assert!(point_in_ring([2.0, 8.0], &ring));
+        // inside the bbox but outside the polygon
+        assert!(!point_in_ring([8.0, 8.0], &ring));
+        assert!(!point_in_ring([-1.0, 2.0], &ring));
+
+        // closed and clockwise gives the same answer
+        let mut closed: Vec<[f64; 2]> = ring.iter().rev().copied().collect();
+        closed.push(closed[0]);
+        assert_eq!(winding_number([2.0, 2.0], &closed), -1);
+        assert!(point_in_ring([2.0, 8.0], &closed));
+        assert!(!point_in_ring([8.0, 8.0], &closed));
+    }
+}
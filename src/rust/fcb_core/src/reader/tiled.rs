@@ -0,0 +1,208 @@
+use super::reader_trait::Seekable;
+use super::{FcbReader, FeatureIter};
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// One FCB file of a tiled dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tile {
+    pub path: PathBuf,
+    /// `[min_x, min_y, max_x, max_y]` of the features in the file, in the coordinates of the
+    /// spatial index, i.e. the coordinates expected by [`FcbReader::select_bbox`]
+    pub bbox: [f64; 4],
+}
+
+impl Tile {
+    pub fn intersects(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> bool {
+        self.bbox[0] <= max_x
+            && self.bbox[2] >= min_x
+            && self.bbox[1] <= max_y
+            && self.bbox[3] >= min_y
+    }
+}
+
+/// Maps the bounding boxes of the tiles of a dataset to their files. Stored as a JSON sidecar
+/// next to the tiles, see [`TileIndex::write`] and [`TileIndex::read`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TileIndex {
+    pub tiles: Vec<Tile>,
+}
+
+impl TileIndex {
+    pub fn new(tiles: Vec<Tile>) -> Self {
+        Self { tiles }
+    }
+
+    /// Build the index of all `.fcb` files in `dir`, using the root of each file's spatial
+    /// index as its bounding box. Files without features are left out.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "fcb"));
+        paths.sort();
+
+        let mut tiles = Vec::with_capacity(paths.len());
+        for path in paths {
+            let mut reader = FcbReader::open(BufReader::new(File::open(&path)?))?;
+            if reader.header().features_count() == 0 {
+                continue;
+            }
+            let extent = reader
+                .index_extent()?
+                .ok_or_else(|| Error::NoIndex.with_context(path.display().to_string()))?;
+            tiles.push(Tile {
+                path,
+                bbox: [extent.min_x, extent.min_y, extent.max_x, extent.max_y],
+            });
+        }
+        Ok(Self { tiles })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Read a sidecar written by [`TileIndex::write`]. Relative tile paths are resolved
+    /// against the directory of the sidecar.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut index = Self::from_json(&fs::read_to_string(path)?)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        for tile in index.tiles.iter_mut() {
+            if tile.path.is_relative() {
+                tile.path = base.join(&tile.path);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Write the index as a JSON sidecar. Tiles inside the sidecar's directory are stored with
+    /// relative paths, so the directory can be moved as a whole.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let base = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => fs::canonicalize(p)?,
+            _ => std::env::current_dir()?,
+        };
+        let tiles = self
+            .tiles
+            .iter()
+            .map(|tile| {
+                let path = fs::canonicalize(&tile.path)
+                    .ok()
+                    .and_then(|p| p.strip_prefix(&base).ok().map(Path::to_path_buf))
+                    .unwrap_or_else(|| tile.path.clone());
+                Tile {
+                    path,
+                    bbox: tile.bbox,
+                }
+            })
+            .collect();
+        fs::write(path, Self::new(tiles).to_json()?)?;
+        Ok(())
+    }
+
+    /// Tiles whose bounding box intersects the given one
+    pub fn intersecting(
+        &self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> impl Iterator<Item = &Tile> {
+        self.tiles
+            .iter()
+            .filter(move |t| t.intersects(min_x, min_y, max_x, max_y))
+    }
+}
+
+/// Reads a dataset split into several FCB files as a whole
+pub struct TiledFcbReader {
+    index: TileIndex,
+}
+
+impl TiledFcbReader {
+    pub fn new(tile_index: TileIndex) -> Self {
+        Self { index: tile_index }
+    }
+
+    pub fn tile_index(&self) -> &TileIndex {
+        &self.index
+    }
+
+    /// Select features intersecting the given 2D bounding box in all tiles. Only the tiles
+    /// whose bounding box intersects the query are opened, one after another while iterating.
+    pub fn select_bbox(
+        &self,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) -> Result<TiledFeatureIter, Error> {
+        let tiles = self
+            .index
+            .intersecting(min_x, min_y, max_x, max_y)
+            .map(|t| t.path.clone())
+            .collect();
+        Ok(TiledFeatureIter {
+            tiles,
+            bbox: [min_x, min_y, max_x, max_y],
+            current: None,
+            current_tile: None,
+        })
+    }
+}
+
+/// Chains the [`FeatureIter`]s of the selected tiles
+pub struct TiledFeatureIter {
+    tiles: VecDeque<PathBuf>,
+    bbox: [f64; 4],
+    current: Option<FeatureIter<BufReader<File>, Seekable>>,
+    current_tile: Option<PathBuf>,
+}
+
+impl TiledFeatureIter {
+    /// Advance to the next feature, opening the next tile when the current one is exhausted.
+    /// The returned iterator reads the feature with the header of its own tile.
+    pub fn next(&mut self) -> Result<Option<&FeatureIter<BufReader<File>, Seekable>>, Error> {
+        loop {
+            if let Some(iter) = self.current.as_mut() {
+                if iter.next()?.is_some() {
+                    return Ok(self.current.as_ref());
+                }
+                self.current = None;
+            }
+            let Some(path) = self.tiles.pop_front() else {
+                self.current_tile = None;
+                return Ok(None);
+            };
+            let [min_x, min_y, max_x, max_y] = self.bbox;
+            let iter = File::open(&path)
+                .map_err(Error::from)
+                .and_then(|f| FcbReader::open(BufReader::new(f)))
+                .and_then(|r| r.select_bbox(min_x, min_y, max_x, max_y))
+                .map_err(|e| e.with_context(path.display().to_string()))?;
+            self.current = Some(iter);
+            self.current_tile = Some(path);
+        }
+    }
+
+    /// Path of the tile the current feature was read from
+    pub fn current_tile(&self) -> Option<&Path> {
+        self.current_tile.as_deref()
+    }
+
+    /// Number of selected tiles not opened yet
+    pub fn remaining_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+}
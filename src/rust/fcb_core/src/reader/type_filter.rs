@@ -0,0 +1,83 @@
+use crate::error::{Error, Result};
+use crate::fb::{CityFeature, CityObjectType};
+
+/// Set of city object types, checked against the `type` field of the encoded city objects
+/// so that non-matching features are skipped before deserialization.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CityObjectTypeFilter(u64);
+
+impl CityObjectTypeFilter {
+    pub const fn empty() -> Self {
+        CityObjectTypeFilter(0)
+    }
+
+    pub fn from_types(types: &[CityObjectType]) -> Self {
+        types
+            .iter()
+            .fold(Self::empty(), |filter, t| filter.with(*t))
+    }
+
+    /// Build a filter from CityJSON type names, e.g. `"Building"`.
+    ///
+    /// Extension types (starting with `+`) all map to `ExtensionObject`.
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self> {
+        names.iter().try_fold(Self::empty(), |filter, name| {
+            let name = name.as_ref();
+            let co_type = if name.starts_with('+') {
+                CityObjectType::ExtensionObject
+            } else {
+                *CityObjectType::ENUM_VALUES
+                    .iter()
+                    .find(|t| t.variant_name() == Some(name))
+                    .ok_or_else(|| {
+                        Error::QueryExecutionError(format!("unknown city object type: {name}"))
+                    })?
+            };
+            Ok(filter.with(co_type))
+        })
+    }
+
+    pub fn with(self, co_type: CityObjectType) -> Self {
+        CityObjectTypeFilter(self.0 | (1 << co_type.0))
+    }
+
+    pub fn contains(&self, co_type: CityObjectType) -> bool {
+        co_type.0 < 64 && self.0 & (1 << co_type.0) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether any city object of the feature has one of the selected types
+    pub fn matches(&self, feature: &CityFeature) -> bool {
+        feature
+            .objects()
+            .is_some_and(|objects| objects.iter().any(|co| self.contains(co.type_())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_filter_from_names() -> Result<()> {
+        let filter = CityObjectTypeFilter::from_names(&["Building", "WaterBody", "+NoiseBarrier"])?;
+        assert!(filter.contains(CityObjectType::Building));
+        assert!(filter.contains(CityObjectType::WaterBody));
+        assert!(filter.contains(CityObjectType::ExtensionObject));
+        assert!(!filter.contains(CityObjectType::BuildingPart));
+        assert_eq!(
+            filter,
+            CityObjectTypeFilter::from_types(&[
+                CityObjectType::WaterBody,
+                CityObjectType::ExtensionObject,
+                CityObjectType::Building,
+            ])
+        );
+        assert!(CityObjectTypeFilter::from_names(&["Bulding"]).is_err());
+        assert!(CityObjectTypeFilter::empty().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use http_range_client::HttpError;
+use std::time::Duration;
+
+/// Retry policy for HTTP range requests.
+///
+/// Failed requests are retried with exponential backoff: the n-th retry waits
+/// `initial_backoff * 2^n`, plus up to 50% random jitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, initial_backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_backoff,
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0). `jitter` is a random value in `[0, 1)`.
+    pub fn backoff(&self, attempt: u32, jitter: f64) -> Duration {
+        let base = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.min(16)));
+        base + base.mul_f64(jitter.clamp(0.0, 1.0) / 2.0)
+    }
+
+    /// Whether a failed request should be retried.
+    ///
+    /// Rate limiting and server errors are retried, as are errors without a status
+    /// (connection resets, timeouts). Client errors such as 401, 404 or 416 are not.
+    pub fn is_retryable(err: &HttpError) -> bool {
+        match err {
+            HttpError::HttpStatus(status) => matches!(status, 429 | 500 | 502 | 503 | 504),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_and_retryable() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(100));
+        assert_eq!(policy.backoff(0, 0.0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2, 0.0), Duration::from_millis(400));
+        assert_eq!(policy.backoff(1, 1.0), Duration::from_millis(300));
+
+        assert!(RetryPolicy::is_retryable(&HttpError::HttpStatus(503)));
+        assert!(RetryPolicy::is_retryable(&HttpError::HttpStatus(429)));
+        assert!(!RetryPolicy::is_retryable(&HttpError::HttpStatus(404)));
+        assert!(!RetryPolicy::is_retryable(&HttpError::HttpStatus(416)));
+        assert!(!RetryPolicy::is_retryable(&HttpError::HttpStatus(401)));
+        assert!(RetryPolicy::is_retryable(&HttpError::HttpError(
+            "connection reset".into()
+        )));
+    }
+}
@@ -0,0 +1,193 @@
+//! Parent/children hierarchy of the semantic surfaces of a geometry (e.g. a window being a child
+//! of a wall).
+
+use crate::error::{Error, Result};
+use cjseq::SemanticsSurface;
+
+/// Index-based view of the `parent` and `children` references of a list of semantic surfaces.
+///
+/// References may be given on one side only (a `parent` without the matching `children` entry or
+/// the other way around), the missing side is inferred.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SemanticGraph {
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+impl SemanticGraph {
+    /// Builds the graph, failing on out of range references, on references that contradict each
+    /// other and on cycles.
+    pub fn from_surfaces(surfaces: &[SemanticsSurface]) -> Result<Self> {
+        let len = surfaces.len();
+        let check_index = |from: usize, to: u32| -> Result<usize> {
+            let to = to as usize;
+            if to >= len {
+                return Err(Error::InvalidSemanticHierarchy(format!(
+                    "surface {from} references surface {to}, but there are only {len} surfaces"
+                )));
+            }
+            if to == from {
+                return Err(Error::InvalidSemanticHierarchy(format!(
+                    "surface {from} references itself"
+                )));
+            }
+            Ok(to)
+        };
+
+        let mut parents = vec![None; len];
+        for (i, surface) in surfaces.iter().enumerate() {
+            if let Some(parent) = surface.parent {
+                parents[i] = Some(check_index(i, parent)?);
+            }
+        }
+
+        let mut children = vec![Vec::new(); len];
+        for (i, surface) in surfaces.iter().enumerate() {
+            for child in surface.children.iter().flatten() {
+                let child = check_index(i, *child)?;
+                match parents[child] {
+                    Some(parent) if parent != i => {
+                        return Err(Error::InvalidSemanticHierarchy(format!(
+                            "surface {child} is a child of surface {i}, but its parent is {parent}"
+                        )));
+                    }
+                    _ => parents[child] = Some(i),
+                }
+                if !children[i].contains(&child) {
+                    children[i].push(child);
+                }
+            }
+        }
+        for (i, parent) in parents.iter().enumerate() {
+            let Some(parent) = *parent else { continue };
+            if children[parent].contains(&i) {
+                continue;
+            }
+            if surfaces[parent].children.is_some() {
+                return Err(Error::InvalidSemanticHierarchy(format!(
+                    "surface {i} has parent {parent}, which does not list it as a child"
+                )));
+            }
+            children[parent].push(i);
+        }
+
+        // every surface has at most one parent, so a chain longer than `len` has to loop
+        for start in 0..len {
+            let mut current = start;
+            let mut steps = 0;
+            while let Some(parent) = parents[current] {
+                steps += 1;
+                if steps > len {
+                    return Err(Error::InvalidSemanticHierarchy(format!(
+                        "surface {start} is part of a parent/children cycle"
+                    )));
+                }
+                current = parent;
+            }
+        }
+
+        let roots = (0..len).filter(|i| parents[*i].is_none()).collect();
+        Ok(SemanticGraph {
+            parents,
+            children,
+            roots,
+        })
+    }
+
+    /// Number of surfaces in the graph
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Direct children of a surface, empty for leaves and out of range indices
+    pub fn children_of(&self, index: usize) -> &[usize] {
+        self.children.get(index).map_or(&[], Vec::as_slice)
+    }
+
+    /// Parent of a surface, `None` for roots and out of range indices
+    pub fn parent_of(&self, index: usize) -> Option<usize> {
+        self.parents.get(index).copied().flatten()
+    }
+
+    /// Surfaces without a parent, in ascending order
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Depth-first (pre-order) traversal of a surface and all its descendants
+    pub fn subtree(&self, root: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut stack = if root < self.len() {
+            vec![root]
+        } else {
+            Vec::new()
+        };
+        std::iter::from_fn(move || {
+            let current = stack.pop()?;
+            stack.extend(self.children_of(current).iter().rev());
+            Some(current)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn surfaces(json: serde_json::Value) -> Vec<SemanticsSurface> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_semantic_graph() -> Result<()> {
+        let surfaces = surfaces(serde_json::json!([
+            {"type": "WallSurface", "children": [2, 3]},
+            {"type": "RoofSurface"},
+            {"type": "Window", "parent": 0},
+            {"type": "Door"},
+            {"type": "WallSurface"},
+            {"type": "Window", "parent": 4}
+        ]));
+        let graph = SemanticGraph::from_surfaces(&surfaces)?;
+
+        assert_eq!(vec![0, 1, 4], graph.roots().to_vec());
+        assert_eq!(vec![2, 3], graph.children_of(0).to_vec());
+        // children are inferred from the parent reference
+        assert_eq!(vec![5], graph.children_of(4).to_vec());
+        // and the parent from the children reference
+        assert_eq!(Some(0), graph.parent_of(3));
+        assert_eq!(None, graph.parent_of(1));
+        assert_eq!(None, graph.parent_of(42));
+        assert!(graph.children_of(42).is_empty());
+        assert_eq!(vec![0, 2, 3], graph.subtree(0).collect::<Vec<_>>());
+        assert_eq!(vec![1], graph.subtree(1).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_semantic_graph_invalid() {
+        let cycle = surfaces(serde_json::json!([
+            {"type": "WallSurface", "parent": 1},
+            {"type": "Window", "parent": 0}
+        ]));
+        assert!(SemanticGraph::from_surfaces(&cycle).is_err());
+
+        let out_of_range = surfaces(serde_json::json!([
+            {"type": "WallSurface", "children": [1]}
+        ]));
+        assert!(SemanticGraph::from_surfaces(&out_of_range).is_err());
+
+        let inconsistent = surfaces(serde_json::json!([
+            {"type": "WallSurface", "children": [2]},
+            {"type": "WallSurface"},
+            {"type": "Window", "parent": 1}
+        ]));
+        assert!(SemanticGraph::from_surfaces(&inconsistent).is_err());
+    }
+}
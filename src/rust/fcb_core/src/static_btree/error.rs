@@ -48,4 +48,36 @@ pub enum Error {
     PayloadOffsetNotInCache,
 }
 
+/// Inconsistency found by [`Stree::verify_integrity`](crate::static_btree::Stree::verify_integrity)
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The level bounds don't cover all nodes, from the root at 0 down to the leaves at the end.
+    #[error("invalid level bounds: {0}")]
+    LevelBounds(String),
+
+    /// The number of nodes doesn't match the number of leaves and the branching factor.
+    #[error("expected {expected} nodes, found {actual}")]
+    NodeCount { expected: usize, actual: usize },
+
+    /// Leaf keys are not strictly increasing.
+    #[error("leaf {node} is not greater than the previous leaf")]
+    UnsortedLeaves { node: usize },
+
+    /// An internal node points outside of the level below it.
+    #[error("node {node} points to node {child}, outside of the level below")]
+    ChildOutOfLevel { node: usize, child: usize },
+
+    /// A key of the child node is not below the key of its parent.
+    #[error("keys of node {child} are not covered by the key of its parent {node}")]
+    KeyNotCovered { node: usize, child: usize },
+
+    /// A leaf points past the end of the data section.
+    #[error("leaf {node} points to offset {offset}, past the end of the data section")]
+    OffsetOutOfBounds { node: usize, offset: u64 },
+
+    /// A leaf points to a payload entry that can't be read.
+    #[error("leaf {node} points to an invalid payload entry at {offset}")]
+    InvalidPayload { node: usize, offset: u64 },
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
@@ -2,6 +2,7 @@ use crate::static_btree::error::{Error, Result};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use chrono::{DateTime, TimeZone, Utc};
 use ordered_float::OrderedFloat; // Import OrderedFloat
+use std::borrow::Cow;
 use std::fmt::Debug;
 use std::io::{Read, Write};
 use std::mem;
@@ -29,8 +30,66 @@ pub enum KeyType {
     Bool(bool),
     /// DateTime keys
     DateTime(DateTime<Utc>),
+    /// Absent attribute value, matched by indices with null entries (see [`Nullable`])
+    Null,
 }
 
+impl KeyType {
+    /// Writes the serialized bytes of the wrapped key. `Null` has no serialized form.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<usize> {
+        match self {
+            KeyType::StringKey20(key) => key.write_to(writer),
+            KeyType::StringKey50(key) => key.write_to(writer),
+            KeyType::StringKey100(key) => key.write_to(writer),
+            KeyType::Int32(key) => key.write_to(writer),
+            KeyType::Int64(key) => key.write_to(writer),
+            KeyType::UInt32(key) => key.write_to(writer),
+            KeyType::UInt64(key) => key.write_to(writer),
+            KeyType::Int8(key) => key.write_to(writer),
+            KeyType::UInt8(key) => key.write_to(writer),
+            KeyType::Int16(key) => key.write_to(writer),
+            KeyType::UInt16(key) => key.write_to(writer),
+            KeyType::Float32(key) => key.write_to(writer),
+            KeyType::Float64(key) => key.write_to(writer),
+            KeyType::Bool(key) => key.write_to(writer),
+            KeyType::DateTime(key) => key.write_to(writer),
+            KeyType::Null => Err(Error::KeySerializationError(
+                "null keys have no serialized form".to_string(),
+            )),
+        }
+    }
+}
+
+macro_rules! impl_from_key {
+    ($($key_type:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$key_type> for KeyType {
+                fn from(key: $key_type) -> Self {
+                    KeyType::$variant(key)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_key!(
+    FixedStringKey<20> => StringKey20,
+    FixedStringKey<50> => StringKey50,
+    FixedStringKey<100> => StringKey100,
+    i32 => Int32,
+    i64 => Int64,
+    u32 => UInt32,
+    u64 => UInt64,
+    i8 => Int8,
+    u8 => UInt8,
+    i16 => Int16,
+    u16 => UInt16,
+    OrderedFloat<f32> => Float32,
+    OrderedFloat<f64> => Float64,
+    bool => Bool,
+    DateTime<Utc> => DateTime,
+);
+
 /// Trait for types that have a maximum representable value.
 ///
 /// This trait allows retrieval of the maximum value for a type,
@@ -84,8 +143,82 @@ pub trait Key: Sized + Ord + Clone + Debug + Default + Max + Min {
     /// `Ok(Self)` containing the deserialized key on success.
     /// `Err(Error)` if the bytes are not a valid key.
     fn from_bytes(bytes: &[u8]) -> Result<Self>;
+
+    /// Whether the key is a floating point NaN.
+    ///
+    /// NaN is sorted after all other values but is not ordered against them:
+    /// it never matches range queries, and only matches `Eq` with another NaN.
+    fn is_nan(&self) -> bool {
+        false
+    }
+
+    /// Whether the key represents an absent value. Null keys never match range queries.
+    fn is_null(&self) -> bool {
+        false
+    }
+
+    /// The null key, if this key type can represent absent values.
+    fn null() -> Option<Self> {
+        None
+    }
+
+    /// The string of a string key, matched against `Like` patterns. `None` for other key types.
+    fn as_str(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// Smallest and largest keys starting with `prefix`, `None` if the key type isn't a string.
+    fn prefix_bounds(_prefix: &str) -> Option<(Self, Self)> {
+        None
+    }
+
+    /// Whether the key may have been cut off from a longer value, so that the end of the value
+    /// is unknown. Only string keys are ever truncated.
+    fn may_be_truncated(&self) -> bool {
+        false
+    }
+}
+
+/// Conversion of a query value into the key type of an index
+pub trait FromKeyType: Sized {
+    /// Returns `None` if the query value has a different type than the index
+    fn from_key_type(key: &KeyType) -> Option<Self>;
+}
+
+macro_rules! impl_from_key_type {
+    ($T:ty, $variant:path) => {
+        impl FromKeyType for $T {
+            fn from_key_type(key: &KeyType) -> Option<Self> {
+                match key {
+                    $variant(val) => Some(val.clone()),
+                    _ => None,
+                }
+            }
+        }
+    };
 }
 
+impl_from_key_type!(i8, KeyType::Int8);
+impl_from_key_type!(u8, KeyType::UInt8);
+impl_from_key_type!(i16, KeyType::Int16);
+impl_from_key_type!(u16, KeyType::UInt16);
+impl_from_key_type!(i32, KeyType::Int32);
+impl_from_key_type!(u32, KeyType::UInt32);
+impl_from_key_type!(i64, KeyType::Int64);
+impl_from_key_type!(u64, KeyType::UInt64);
+impl_from_key_type!(OrderedFloat<f32>, KeyType::Float32);
+impl_from_key_type!(OrderedFloat<f64>, KeyType::Float64);
+impl_from_key_type!(bool, KeyType::Bool);
+impl_from_key_type!(DateTime<Utc>, KeyType::DateTime);
+impl_from_key_type!(FixedStringKey<20>, KeyType::StringKey20);
+impl_from_key_type!(FixedStringKey<50>, KeyType::StringKey50);
+impl_from_key_type!(FixedStringKey<100>, KeyType::StringKey100);
+
+/// Bit pattern every f32 NaN key is serialized as
+pub const F32_NAN_SENTINEL: u32 = 0x7fc0_0000;
+/// Bit pattern every f64 NaN key is serialized as
+pub const F64_NAN_SENTINEL: u64 = 0x7ff8_0000_0000_0000;
+
 // Implement Max for primitive integer types
 impl Max for i8 {
     fn max_value() -> Self {
@@ -169,7 +302,7 @@ impl Max for DateTime<Utc> {
 impl<const N: usize> Max for FixedStringKey<N> {
     fn max_value() -> Self {
         // For strings, a byte array filled with 0xFF represents the maximum lexicographical value
-        Self([0xFF; N])
+        Self([0xFF; N], N)
     }
 }
 
@@ -247,7 +380,7 @@ impl Min for DateTime<Utc> {
 
 impl<const N: usize> Min for FixedStringKey<N> {
     fn min_value() -> Self {
-        FixedStringKey([0u8; N])
+        FixedStringKey([0u8; N], 0)
     }
 }
 
@@ -325,7 +458,12 @@ impl Key for OrderedFloat<f32> {
 
     #[inline]
     fn write_to<W: Write>(&self, writer: &mut W) -> Result<usize> {
-        writer.write_f32::<LittleEndian>(self.into_inner())?;
+        // NaN has many bit patterns (sign, payload), store a single canonical one
+        if self.is_nan() {
+            writer.write_u32::<LittleEndian>(F32_NAN_SENTINEL)?;
+        } else {
+            writer.write_f32::<LittleEndian>(self.into_inner())?;
+        }
         Ok(Self::SERIALIZED_SIZE)
     }
 
@@ -342,6 +480,11 @@ impl Key for OrderedFloat<f32> {
         array.copy_from_slice(&bytes[0..Self::SERIALIZED_SIZE]);
         Ok(OrderedFloat::from(f32::from_le_bytes(array)))
     }
+
+    #[inline]
+    fn is_nan(&self) -> bool {
+        self.0.is_nan()
+    }
 }
 
 // Implement Key for OrderedFloat<f64>
@@ -350,7 +493,12 @@ impl Key for OrderedFloat<f64> {
 
     #[inline]
     fn write_to<W: Write>(&self, writer: &mut W) -> Result<usize> {
-        writer.write_f64::<LittleEndian>(self.into_inner())?;
+        // NaN has many bit patterns (sign, payload), store a single canonical one
+        if self.is_nan() {
+            writer.write_u64::<LittleEndian>(F64_NAN_SENTINEL)?;
+        } else {
+            writer.write_f64::<LittleEndian>(self.into_inner())?;
+        }
         Ok(Self::SERIALIZED_SIZE)
     }
 
@@ -367,6 +515,11 @@ impl Key for OrderedFloat<f64> {
         array.copy_from_slice(&bytes[0..Self::SERIALIZED_SIZE]);
         Ok(OrderedFloat::from(f64::from_le_bytes(array)))
     }
+
+    #[inline]
+    fn is_nan(&self) -> bool {
+        self.0.is_nan()
+    }
 }
 
 // Implement Key for bool
@@ -432,14 +585,16 @@ impl Key for DateTime<Utc> {
 ///
 /// It stores the string's bytes in a fixed-size array `[u8; N]`.
 /// If the input string is shorter than `N`, it's padded with null bytes (`\0`).
-/// If the input string is longer than `N`, it's truncated.
+/// If the input string is longer than `N`, it's truncated at the last UTF-8
+/// character boundary at or before `N` bytes, so the stored key is always valid UTF-8.
+/// The number of used bytes is kept alongside the array and is not serialized.
 /// Comparison (`Ord`) is based on the byte array content.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct FixedStringKey<const N: usize>([u8; N]);
+pub struct FixedStringKey<const N: usize>([u8; N], usize);
 
 impl<const N: usize> Default for FixedStringKey<N> {
     fn default() -> Self {
-        Self([0u8; N])
+        Self([0u8; N], 0)
     }
 }
 
@@ -456,21 +611,47 @@ impl<const N: usize> Key for FixedStringKey<N> {
     fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
         let mut bytes = [0u8; N];
         reader.read_exact(&mut bytes)?;
-        Ok(FixedStringKey(bytes))
+        Ok(FixedStringKey::from_array(bytes))
     }
 
     #[inline]
     fn from_bytes(bytes: &[u8]) -> Result<Self> {
         let mut array = [0u8; N];
         array.copy_from_slice(&bytes[0..N]);
-        Ok(FixedStringKey(array))
+        Ok(FixedStringKey::from_array(array))
+    }
+
+    fn as_str(&self) -> Option<Cow<'_, str>> {
+        Some(self.to_str_lossy())
+    }
+
+    fn prefix_bounds(prefix: &str) -> Option<(Self, Self)> {
+        let lower = Self::from_str(prefix);
+        // 0xFF never occurs in UTF-8, so every key with the prefix sorts below the padding
+        let mut upper = lower.0;
+        upper[lower.1..].fill(0xFF);
+        Some((lower, FixedStringKey(upper, N)))
+    }
+
+    fn may_be_truncated(&self) -> bool {
+        // truncating at a character boundary leaves at most 3 bytes of padding
+        self.1 + 3 >= N
     }
 }
 
 impl<const N: usize> FixedStringKey<N> {
+    /// Wraps a serialized key, the used length ends at the first null byte.
+    fn from_array(bytes: [u8; N]) -> Self {
+        let len = bytes.iter().position(|&b| b == 0).unwrap_or(N);
+        FixedStringKey(bytes, len)
+    }
+
     /// Creates a key from a string slice, padding with 0 bytes
     /// or truncating if necessary to fit exactly N bytes.
     ///
+    /// Truncation happens at the last character boundary at or before N bytes,
+    /// so a multi-byte UTF-8 sequence is never split.
+    ///
     /// # Examples
     /// ```
     /// # use static_btree::key::FixedStringKey; // Adjust path if needed
@@ -485,11 +666,28 @@ impl<const N: usize> FixedStringKey<N> {
     /// ```
     pub fn from_str(s: &str) -> Self {
         let mut bytes = [0u8; N];
-        let source_bytes = s.as_bytes();
-        let len_to_copy = std::cmp::min(source_bytes.len(), N);
-        bytes[..len_to_copy].copy_from_slice(&source_bytes[..len_to_copy]);
+        let mut len_to_copy = std::cmp::min(s.len(), N);
+        while !s.is_char_boundary(len_to_copy) {
+            len_to_copy -= 1;
+        }
+        bytes[..len_to_copy].copy_from_slice(&s.as_bytes()[..len_to_copy]);
         // Remaining bytes are already 0 due to initialization.
-        FixedStringKey(bytes)
+        Self::from_array(bytes)
+    }
+
+    /// Number of bytes used by the string, excluding the null padding.
+    pub fn len(&self) -> usize {
+        self.1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.1 == 0
+    }
+
+    /// Borrows the stored string up to the first null byte, replacing invalid
+    /// UTF-8 sequences (e.g. in keys written by older versions) with `U+FFFD`.
+    pub fn to_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0[..self.1])
     }
 
     /// Attempts to convert back to a String, stopping at the first null byte
@@ -512,10 +710,175 @@ impl<const N: usize> FixedStringKey<N> {
     /// assert_eq!(key3.to_string_lossy(), "null"); // Stops at null byte
     /// ```
     pub fn to_string_lossy(&self) -> String {
-        // Find the first null byte, or take the whole array if none exists.
-        let first_null = self.0.iter().position(|&b| b == 0).unwrap_or(N);
-        // Convert the slice up to the null byte (or end) into a String.
-        String::from_utf8_lossy(&self.0[..first_null]).into_owned()
+        self.to_str_lossy().into_owned()
+    }
+}
+
+/// Key of an index over an attribute that some features lack.
+///
+/// Serialized as a one byte prefix followed by the wrapped key: `0x00` and zero
+/// padding for null, `0x01` and the key for values, so null entries sort before
+/// all values. `min_value()` is the smallest *value*, which keeps null entries
+/// out of open-ended range queries.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Nullable<K>(pub Option<K>);
+
+const NULL_PREFIX: u8 = 0x00;
+const VALUE_PREFIX: u8 = 0x01;
+
+impl<K: Key> Max for Nullable<K> {
+    fn max_value() -> Self {
+        Nullable(Some(K::max_value()))
+    }
+}
+
+impl<K: Key> Min for Nullable<K> {
+    fn min_value() -> Self {
+        Nullable(Some(K::min_value()))
+    }
+}
+
+impl<K: Key> Key for Nullable<K> {
+    const SERIALIZED_SIZE: usize = 1 + K::SERIALIZED_SIZE;
+
+    #[inline]
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<usize> {
+        match &self.0 {
+            Some(key) => {
+                writer.write_u8(VALUE_PREFIX)?;
+                key.write_to(writer)?;
+            }
+            None => {
+                writer.write_u8(NULL_PREFIX)?;
+                writer.write_all(&vec![0u8; K::SERIALIZED_SIZE])?;
+            }
+        }
+        Ok(Self::SERIALIZED_SIZE)
+    }
+
+    #[inline]
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = vec![0u8; Self::SERIALIZED_SIZE];
+        reader.read_exact(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        match bytes[0] {
+            NULL_PREFIX => Ok(Nullable(None)),
+            _ => Ok(Nullable(Some(K::from_bytes(
+                &bytes[1..Self::SERIALIZED_SIZE],
+            )?))),
+        }
+    }
+
+    fn is_nan(&self) -> bool {
+        self.0.as_ref().is_some_and(|key| key.is_nan())
+    }
+
+    fn is_null(&self) -> bool {
+        self.0.is_none()
+    }
+
+    fn null() -> Option<Self> {
+        Some(Nullable(None))
+    }
+
+    fn as_str(&self) -> Option<Cow<'_, str>> {
+        self.0.as_ref().and_then(|key| key.as_str())
+    }
+
+    fn prefix_bounds(prefix: &str) -> Option<(Self, Self)> {
+        K::prefix_bounds(prefix)
+            .map(|(lower, upper)| (Nullable(Some(lower)), Nullable(Some(upper))))
+    }
+
+    fn may_be_truncated(&self) -> bool {
+        self.0.as_ref().is_some_and(|key| key.may_be_truncated())
+    }
+}
+
+impl<K: FromKeyType> FromKeyType for Nullable<K> {
+    fn from_key_type(key: &KeyType) -> Option<Self> {
+        match key {
+            KeyType::Null => Some(Nullable(None)),
+            _ => K::from_key_type(key).map(|key| Nullable(Some(key))),
+        }
+    }
+}
+
+/// Size in bytes of a [`CompositeKey`]
+pub const COMPOSITE_KEY_SIZE: usize = 128;
+
+/// Key of a composite index: the serialized values of several columns,
+/// concatenated in the order of the index definition and zero padded.
+///
+/// The byte order of the concatenation does not follow the order of the
+/// column values, so a composite index only supports exact lookups of the
+/// full key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CompositeKey([u8; COMPOSITE_KEY_SIZE]);
+
+impl CompositeKey {
+    /// Concatenates the serialized keys, failing if they exceed [`COMPOSITE_KEY_SIZE`] bytes
+    pub fn from_keys(keys: &[KeyType]) -> Result<Self> {
+        let mut bytes = Vec::with_capacity(COMPOSITE_KEY_SIZE);
+        for key in keys {
+            key.write_to(&mut bytes)?;
+        }
+        if bytes.len() > COMPOSITE_KEY_SIZE {
+            return Err(Error::KeySerializationError(format!(
+                "composite key of {} bytes exceeds {} bytes",
+                bytes.len(),
+                COMPOSITE_KEY_SIZE
+            )));
+        }
+        let mut array = [0u8; COMPOSITE_KEY_SIZE];
+        array[..bytes.len()].copy_from_slice(&bytes);
+        Ok(CompositeKey(array))
+    }
+}
+
+impl Default for CompositeKey {
+    fn default() -> Self {
+        CompositeKey([0; COMPOSITE_KEY_SIZE])
+    }
+}
+
+impl Max for CompositeKey {
+    fn max_value() -> Self {
+        CompositeKey([0xFF; COMPOSITE_KEY_SIZE])
+    }
+}
+
+impl Min for CompositeKey {
+    fn min_value() -> Self {
+        CompositeKey::default()
+    }
+}
+
+impl Key for CompositeKey {
+    const SERIALIZED_SIZE: usize = COMPOSITE_KEY_SIZE;
+
+    #[inline]
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<usize> {
+        writer.write_all(&self.0)?;
+        Ok(Self::SERIALIZED_SIZE)
+    }
+
+    #[inline]
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = [0u8; COMPOSITE_KEY_SIZE];
+        reader.read_exact(&mut bytes)?;
+        Ok(CompositeKey(bytes))
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut array = [0u8; COMPOSITE_KEY_SIZE];
+        array.copy_from_slice(&bytes[0..COMPOSITE_KEY_SIZE]);
+        Ok(CompositeKey(array))
     }
 }
 
@@ -685,6 +1048,72 @@ mod tests {
         assert_eq!(key3.to_string_lossy(), "xyz");
     }
 
+    #[test]
+    fn test_fixed_string_key_utf8_truncation() {
+        // 2-byte sequence: "é" would be split after 4 bytes
+        let key = FixedStringKey::<4>::from_str("caré");
+        assert_eq!(key.len(), 3);
+        assert_eq!(key.to_str_lossy(), "car");
+        assert_eq!(FixedStringKey::<5>::from_str("caré").to_str_lossy(), "caré");
+
+        // 3-byte sequences: Japanese city names
+        let key = FixedStringKey::<7>::from_str("東京都");
+        assert_eq!(key.len(), 6);
+        assert_eq!(key.to_str_lossy(), "東京");
+        let key = FixedStringKey::<8>::from_str("東京都");
+        assert_eq!(key.to_string_lossy(), "東京");
+        assert_eq!(
+            FixedStringKey::<9>::from_str("東京都").to_str_lossy(),
+            "東京都"
+        );
+
+        // 4-byte sequence: emoji
+        for n in 1..4 {
+            let key = FixedStringKey::<4>::from_str(&"a🏙".repeat(n));
+            assert_eq!(key.to_str_lossy(), "a");
+        }
+        assert_eq!(FixedStringKey::<5>::from_str("a🏙").to_str_lossy(), "a🏙");
+
+        // Truncated keys survive serialization and are valid UTF-8
+        let key = FixedStringKey::<6>::from_str("IJsselmonde");
+        assert_eq!(key.to_str_lossy(), "IJssel");
+        let key = FixedStringKey::<4>::from_str("ĳssel");
+        assert_eq!(key.to_str_lossy(), "ĳss");
+        let mut buffer = Vec::new();
+        key.write_to(&mut buffer).unwrap();
+        let read = FixedStringKey::<4>::read_from(&mut Cursor::new(&buffer)).unwrap();
+        assert_eq!(read, key);
+        assert_eq!(read.len(), 4);
+        assert!(std::str::from_utf8(&buffer[..read.len()]).is_ok());
+    }
+
+    #[test]
+    fn test_nullable_keys() {
+        test_key_impl(Nullable(Some(42_i32)));
+        test_key_impl(Nullable::<i32>(None));
+        test_key_impl(Nullable(Some(FixedStringKey::<8>::from_str("delft"))));
+        test_key_impl(Nullable::<FixedStringKey<8>>(None));
+
+        let mut buffer = Vec::new();
+        Nullable::<i64>(None).write_to(&mut buffer).unwrap();
+        assert_eq!(buffer, [0u8; 9]);
+
+        // Null sorts before every value, including the minimum
+        assert!(Nullable::<i32>(None) < Nullable(Some(i32::MIN)));
+        assert!(Nullable::<bool>(None) < Nullable::<bool>::min_value());
+        assert!(Nullable(Some(u8::MAX)) <= Nullable::<u8>::max_value());
+
+        assert_eq!(
+            Nullable::<i32>::from_key_type(&KeyType::Null),
+            Some(Nullable(None))
+        );
+        assert_eq!(
+            Nullable::<i32>::from_key_type(&KeyType::Int32(7)),
+            Some(Nullable(Some(7)))
+        );
+        assert_eq!(i32::from_key_type(&KeyType::Null), None);
+    }
+
     #[test]
     fn test_fixed_string_key_serialization() {
         test_key_impl(FixedStringKey::<8>::from_str("testkey"));
@@ -745,4 +1174,32 @@ mod tests {
             .to_utc();
         test_key_impl(dt);
     }
+
+    #[test]
+    fn test_composite_key() {
+        let key = CompositeKey::from_keys(&[
+            KeyType::StringKey50(FixedStringKey::from_str("Rotterdam")),
+            KeyType::Int32(3),
+        ])
+        .unwrap();
+        let mut bytes = Vec::new();
+        key.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), COMPOSITE_KEY_SIZE);
+        assert_eq!(&bytes[..9], b"Rotterdam");
+        assert_eq!(&bytes[50..54], &3i32.to_le_bytes());
+        assert!(bytes[54..].iter().all(|&b| b == 0));
+        test_key_impl(key);
+
+        // Column order is part of the key
+        let swapped = CompositeKey::from_keys(&[
+            KeyType::Int32(3),
+            KeyType::StringKey50(FixedStringKey::from_str("Rotterdam")),
+        ])
+        .unwrap();
+        assert_ne!(key, swapped);
+
+        let too_long = vec![KeyType::StringKey100(FixedStringKey::from_str("x")); 2];
+        assert!(CompositeKey::from_keys(&too_long).is_err());
+        assert!(CompositeKey::from_keys(&[KeyType::Null]).is_err());
+    }
 }
@@ -0,0 +1,108 @@
+use std::collections::{HashMap, VecDeque};
+use std::mem::{discriminant, Discriminant};
+use std::sync::Arc;
+
+use crate::static_btree::key::KeyType;
+
+use super::types::{Operator, QueryCondition};
+
+/// Identifies a query by its conditions, in order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryCacheKey(Vec<(String, Operator, Discriminant<KeyType>, Vec<u8>)>);
+
+impl QueryCacheKey {
+    pub fn new(conditions: &[QueryCondition]) -> Self {
+        Self(
+            conditions
+                .iter()
+                .map(|condition| {
+                    // Null keys have no serialized form, the discriminant tells them apart
+                    let mut key = Vec::new();
+                    let _ = condition.key.write_to(&mut key);
+                    (
+                        condition.field.clone(),
+                        condition.operator,
+                        discriminant(&condition.key),
+                        key,
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Hit and miss counters of a query cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl QueryCacheStats {
+    /// Share of lookups served from the cache, 0 before any lookup
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Results of the most recently used queries, evicting the least recently used one when full
+#[derive(Debug, Default)]
+pub(crate) struct QueryCache {
+    capacity: usize,
+    results: HashMap<QueryCacheKey, Arc<Vec<u64>>>,
+    /// Keys from least to most recently used
+    recency: VecDeque<QueryCacheKey>,
+    stats: QueryCacheStats,
+}
+
+impl QueryCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn get(&mut self, key: &QueryCacheKey) -> Option<Arc<Vec<u64>>> {
+        let Some(results) = self.results.get(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
+        if let Some(pos) = self.recency.iter().position(|k| k == key) {
+            let key = self.recency.remove(pos).expect("position is in bounds");
+            self.recency.push_back(key);
+        }
+        Some(results.clone())
+    }
+
+    pub(crate) fn insert(&mut self, key: QueryCacheKey, results: Arc<Vec<u64>>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.results.insert(key.clone(), results).is_some() {
+            return;
+        }
+        self.recency.push_back(key);
+        while self.recency.len() > self.capacity {
+            if let Some(evicted) = self.recency.pop_front() {
+                self.results.remove(&evicted);
+            }
+        }
+    }
+
+    /// Drops all cached results, keeping the counters
+    pub(crate) fn clear(&mut self) {
+        self.results.clear();
+        self.recency.clear();
+    }
+
+    pub(crate) fn stats(&self) -> QueryCacheStats {
+        self.stats
+    }
+}
@@ -0,0 +1,47 @@
+//! Constructors for query conditions that expand to several comparisons or use the
+//! [`KeyType::Null`] sentinel
+
+use crate::static_btree::key::KeyType;
+
+use super::types::{Operator, QueryCondition};
+
+fn condition(field: &str, operator: Operator, key: KeyType) -> QueryCondition {
+    QueryCondition {
+        field: field.to_string(),
+        operator,
+        key,
+    }
+}
+
+/// Matches values in `[lo, hi]`, as a `Ge` and a `Le` condition
+pub fn between(
+    field: &str,
+    lo: impl Into<KeyType>,
+    hi: impl Into<KeyType>,
+) -> (QueryCondition, QueryCondition) {
+    (
+        condition(field, Operator::Ge, lo.into()),
+        condition(field, Operator::Le, hi.into()),
+    )
+}
+
+/// Matches values other than `values`, as one `Ne` condition per value
+pub fn not_in<V: Into<KeyType>>(
+    field: &str,
+    values: impl IntoIterator<Item = V>,
+) -> Vec<QueryCondition> {
+    values
+        .into_iter()
+        .map(|value| condition(field, Operator::Ne, value.into()))
+        .collect()
+}
+
+/// Matches features without a value for `field`
+pub fn is_null(field: &str) -> QueryCondition {
+    condition(field, Operator::IsNull, KeyType::Null)
+}
+
+/// Matches features with a value for `field`
+pub fn not_null(field: &str) -> QueryCondition {
+    condition(field, Operator::Ne, KeyType::Null)
+}
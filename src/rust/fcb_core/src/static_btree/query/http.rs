@@ -1,12 +1,15 @@
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::static_btree::error::{Error, Result};
-use crate::static_btree::key::{Key, KeyType, Max, Min};
-use crate::static_btree::query::types::{Operator, QueryCondition};
+use crate::static_btree::key::{FromKeyType, Key, KeyType, Max, Min};
+use crate::static_btree::query::types::{key_type_name, Operator, QueryCondition, QueryPlan};
 use crate::static_btree::stree::http::HttpSearchResultItem;
-use crate::static_btree::stree::Stree;
+use crate::static_btree::stree::{CacheStats, PayloadCache, Stree};
 use async_trait::async_trait;
+
+use super::LikePattern;
 use http_range_client::{AsyncBufferedHttpRangeClient, AsyncHttpRangeClient};
 
 /// HTTP-based index for remote access
@@ -22,6 +25,11 @@ pub struct HttpIndex<K: Key> {
     feature_begin: usize,
     /// threshold for combining HTTP requests to reduce roundtrips
     combine_request_threshold: usize,
+    /// fetch the levels below a node in the same request while they are small enough
+    adaptive_prefetch: bool,
+    /// payload chunks kept between queries. A query takes the cache for its duration, so
+    /// concurrent queries on the same index each start from an empty cache.
+    payload_cache: Arc<Mutex<PayloadCache>>,
     _marker: PhantomData<K>,
 }
 
@@ -40,17 +48,46 @@ impl<K: Key> HttpIndex<K> {
             index_begin,
             feature_begin,
             combine_request_threshold,
+            adaptive_prefetch: false,
+            payload_cache: Arc::new(Mutex::new(PayloadCache::new())),
             _marker: PhantomData,
         }
     }
 
+    /// Hit, miss and eviction counters of the payload cache of this index
+    pub fn cache_stats(&self) -> CacheStats {
+        self.lock_payload_cache().cache_stats()
+    }
+
+    fn lock_payload_cache(&self) -> MutexGuard<'_, PayloadCache> {
+        self.payload_cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take the payload cache for a query, see [`Self::restore_payload_cache`]
+    fn take_payload_cache(&self) -> PayloadCache {
+        std::mem::take(&mut *self.lock_payload_cache())
+    }
+
+    fn restore_payload_cache(&self, cache: PayloadCache) {
+        *self.lock_payload_cache() = cache;
+    }
+
+    /// Toggle adaptive prefetch. When enabled, reading the nodes of a level also fetches the
+    /// levels below them in the same range request, as long as they stay under a few
+    /// megabytes, which saves one roundtrip per level on deep trees.
+    pub fn with_adaptive_prefetch(mut self, enabled: bool) -> Self {
+        self.adaptive_prefetch = enabled;
+        self
+    }
+
     /// Find exact matches for a key via HTTP
     pub async fn find_exact<T: AsyncHttpRangeClient>(
         &self,
         client: &mut AsyncBufferedHttpRangeClient<T>,
         key: K,
     ) -> Result<Vec<HttpSearchResultItem>> {
-        let items: Vec<HttpSearchResultItem> = Stree::http_stream_find_exact(
+        let mut payload_cache = self.take_payload_cache();
+        let items = Stree::http_stream_find_exact_cached(
             client,
             self.index_begin,
             self.feature_begin,
@@ -58,10 +95,12 @@ impl<K: Key> HttpIndex<K> {
             self.branching_factor,
             key.clone(),
             self.combine_request_threshold,
+            self.adaptive_prefetch,
+            &mut payload_cache,
         )
-        .await?;
-
-        Ok(items)
+        .await;
+        self.restore_payload_cache(payload_cache);
+        items
     }
 
     /// Find all items in [start..end] via HTTP. At least one bound is required.
@@ -82,18 +121,52 @@ impl<K: Key> HttpIndex<K> {
             }
         };
 
-        let items: Vec<HttpSearchResultItem> = Stree::http_stream_find_range(
+        let mut payload_cache = self.take_payload_cache();
+        let items = Stree::http_stream_find_range_filtered_cached(
             client,
             self.index_begin,
             self.feature_begin,
             self.num_items,
             self.branching_factor,
-            lower.clone(),
-            upper.clone(),
+            lower,
+            upper,
             self.combine_request_threshold,
+            self.adaptive_prefetch,
+            |_| true,
+            &mut payload_cache,
         )
-        .await?;
+        .await;
+        self.restore_payload_cache(payload_cache);
+        items
+    }
 
+    /// Find all items matching a glob pattern via HTTP. Only the leaves holding keys with the
+    /// literal prefix of the pattern are fetched.
+    pub async fn find_like<T: AsyncHttpRangeClient>(
+        &self,
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        pattern: &LikePattern,
+    ) -> Result<Vec<HttpSearchResultItem>> {
+        let (lower, upper) = pattern.bounds::<K>()?;
+        let filter = pattern.filter();
+        let mut payload_cache = self.take_payload_cache();
+        let items = Stree::http_stream_find_range_filtered_cached(
+            client,
+            self.index_begin,
+            self.feature_begin,
+            self.num_items,
+            self.branching_factor,
+            lower,
+            upper,
+            self.combine_request_threshold,
+            self.adaptive_prefetch,
+            |key| filter.matches(key),
+            &mut payload_cache,
+        )
+        .await;
+        self.restore_payload_cache(payload_cache);
+        let items = items?;
+        filter.finish()?;
         Ok(items)
     }
 }
@@ -110,6 +183,12 @@ pub trait TypedHttpSearchIndex<T: AsyncHttpRangeClient + Send + Sync>:
         client: &mut AsyncBufferedHttpRangeClient<T>,
         condition: &QueryCondition,
     ) -> Result<Vec<HttpSearchResultItem>>;
+
+    /// Name of the key type of the index
+    fn key_type(&self) -> String;
+
+    /// Hit, miss and eviction counters of the payload cache of the index
+    fn cache_stats(&self) -> CacheStats;
 }
 
 /// Wasm-specific version that doesn't require Send + Sync
@@ -122,36 +201,71 @@ pub trait TypedHttpSearchIndex<T: AsyncHttpRangeClient>: std::fmt::Debug {
         client: &mut AsyncBufferedHttpRangeClient<T>,
         condition: &QueryCondition,
     ) -> Result<Vec<HttpSearchResultItem>>;
+
+    /// Name of the key type of the index
+    fn key_type(&self) -> String;
+
+    /// Hit, miss and eviction counters of the payload cache of the index
+    fn cache_stats(&self) -> CacheStats;
 }
 
 /// Implement the TypedHttpSearchIndex trait for each supported key type
 macro_rules! impl_typed_http_search_index {
-    ($key_type:ty, $enum_variant:path) => {
+    ($key_type:ty) => {
         #[cfg(not(target_arch = "wasm32"))]
         #[async_trait]
         impl<T: AsyncHttpRangeClient + Send + Sync> TypedHttpSearchIndex<T>
             for HttpIndex<$key_type>
         {
+            fn key_type(&self) -> String {
+                key_type_name::<$key_type>()
+            }
+
+            fn cache_stats(&self) -> CacheStats {
+                HttpIndex::cache_stats(self)
+            }
+
             async fn execute_query_condition(
                 &self,
                 client: &mut AsyncBufferedHttpRangeClient<T>,
                 condition: &QueryCondition,
             ) -> Result<Vec<HttpSearchResultItem>> {
-                // Extract the key value from the enum variant
-                let key: $key_type = match &condition.key {
-                    $enum_variant(val) => val.clone(),
-                    _ => {
-                        return Err(Error::QueryError(format!(
+                let key = if condition.operator == Operator::IsNull
+                    || matches!(condition.key, KeyType::Null)
+                {
+                    // Indices without null entries have nothing to match, so all of their
+                    // items are not null
+                    match <$key_type>::null() {
+                        Some(null) => null,
+                        None if condition.operator == Operator::Ne => {
+                            return self
+                                .find_range(
+                                    client,
+                                    Some(<$key_type>::min_value()),
+                                    Some(<$key_type>::max_value()),
+                                )
+                                .await;
+                        }
+                        None => return Ok(Vec::new()),
+                    }
+                } else {
+                    <$key_type>::from_key_type(&condition.key).ok_or_else(|| {
+                        Error::QueryError(format!(
                             "key type mismatch: expected {}, got {:?}",
                             stringify!($key_type),
                             condition.key
-                        )))
-                    }
+                        ))
+                    })?
                 };
 
+                // NaN and null are not ordered against other values
+                if (key.is_nan() || key.is_null()) && condition.operator.is_range() {
+                    return Ok(Vec::new());
+                }
+
                 // Dispatch to exact or range methods
                 let results = match condition.operator {
-                    Operator::Eq => self.find_exact(client, key.clone()).await?,
+                    Operator::Eq | Operator::IsNull => self.find_exact(client, key.clone()).await?,
                     Operator::Ne => {
                         let all = self
                             .find_range(
@@ -177,6 +291,10 @@ macro_rules! impl_typed_http_search_index {
                     }
                     Operator::Ge => self.find_range(client, Some(key.clone()), None).await?,
                     Operator::Le => self.find_range(client, None, Some(key.clone())).await?,
+                    Operator::Like => {
+                        self.find_like(client, &LikePattern::from_key(&key)?)
+                            .await?
+                    }
                 };
                 Ok(results)
             }
@@ -185,26 +303,55 @@ macro_rules! impl_typed_http_search_index {
         #[cfg(target_arch = "wasm32")]
         #[async_trait(?Send)]
         impl<T: AsyncHttpRangeClient> TypedHttpSearchIndex<T> for HttpIndex<$key_type> {
+            fn key_type(&self) -> String {
+                key_type_name::<$key_type>()
+            }
+
+            fn cache_stats(&self) -> CacheStats {
+                HttpIndex::cache_stats(self)
+            }
+
             async fn execute_query_condition(
                 &self,
                 client: &mut AsyncBufferedHttpRangeClient<T>,
                 condition: &QueryCondition,
             ) -> Result<Vec<HttpSearchResultItem>> {
-                // Extract the key value from the enum variant
-                let key: $key_type = match &condition.key {
-                    $enum_variant(val) => val.clone(),
-                    _ => {
-                        return Err(Error::QueryError(format!(
+                let key = if condition.operator == Operator::IsNull
+                    || matches!(condition.key, KeyType::Null)
+                {
+                    // Indices without null entries have nothing to match, so all of their
+                    // items are not null
+                    match <$key_type>::null() {
+                        Some(null) => null,
+                        None if condition.operator == Operator::Ne => {
+                            return self
+                                .find_range(
+                                    client,
+                                    Some(<$key_type>::min_value()),
+                                    Some(<$key_type>::max_value()),
+                                )
+                                .await;
+                        }
+                        None => return Ok(Vec::new()),
+                    }
+                } else {
+                    <$key_type>::from_key_type(&condition.key).ok_or_else(|| {
+                        Error::QueryError(format!(
                             "key type mismatch: expected {}, got {:?}",
                             stringify!($key_type),
                             condition.key
-                        )))
-                    }
+                        ))
+                    })?
                 };
 
+                // NaN and null are not ordered against other values
+                if (key.is_nan() || key.is_null()) && condition.operator.is_range() {
+                    return Ok(Vec::new());
+                }
+
                 // Dispatch to exact or range methods
                 let results = match condition.operator {
-                    Operator::Eq => self.find_exact(client, key.clone()).await?,
+                    Operator::Eq | Operator::IsNull => self.find_exact(client, key.clone()).await?,
                     Operator::Ne => {
                         let all = self
                             .find_range(
@@ -230,6 +377,10 @@ macro_rules! impl_typed_http_search_index {
                     }
                     Operator::Ge => self.find_range(client, Some(key.clone()), None).await?,
                     Operator::Le => self.find_range(client, None, Some(key.clone())).await?,
+                    Operator::Like => {
+                        self.find_like(client, &LikePattern::from_key(&key)?)
+                            .await?
+                    }
                 };
                 Ok(results)
             }
@@ -237,31 +388,51 @@ macro_rules! impl_typed_http_search_index {
     };
 }
 
-impl_typed_http_search_index!(i8, KeyType::Int8);
-impl_typed_http_search_index!(u8, KeyType::UInt8);
-impl_typed_http_search_index!(i16, KeyType::Int16);
-impl_typed_http_search_index!(u16, KeyType::UInt16);
-impl_typed_http_search_index!(i32, KeyType::Int32);
-impl_typed_http_search_index!(i64, KeyType::Int64);
-impl_typed_http_search_index!(u32, KeyType::UInt32);
-impl_typed_http_search_index!(u64, KeyType::UInt64);
-impl_typed_http_search_index!(ordered_float::OrderedFloat<f32>, KeyType::Float32);
-impl_typed_http_search_index!(ordered_float::OrderedFloat<f64>, KeyType::Float64);
-impl_typed_http_search_index!(bool, KeyType::Bool);
-impl_typed_http_search_index!(chrono::DateTime<chrono::Utc>, KeyType::DateTime);
+impl_typed_http_search_index!(i8);
+impl_typed_http_search_index!(u8);
+impl_typed_http_search_index!(i16);
+impl_typed_http_search_index!(u16);
+impl_typed_http_search_index!(i32);
+impl_typed_http_search_index!(i64);
+impl_typed_http_search_index!(u32);
+impl_typed_http_search_index!(u64);
+impl_typed_http_search_index!(ordered_float::OrderedFloat<f32>);
+impl_typed_http_search_index!(ordered_float::OrderedFloat<f64>);
+impl_typed_http_search_index!(bool);
+impl_typed_http_search_index!(chrono::DateTime<chrono::Utc>);
+impl_typed_http_search_index!(crate::static_btree::key::FixedStringKey<20>);
+impl_typed_http_search_index!(crate::static_btree::key::FixedStringKey<50>);
+impl_typed_http_search_index!(crate::static_btree::key::FixedStringKey<100>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<i8>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<u8>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<i16>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<u16>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<i32>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<i64>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<u32>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<u64>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<ordered_float::OrderedFloat<f32>>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<ordered_float::OrderedFloat<f64>>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<bool>);
+impl_typed_http_search_index!(crate::static_btree::key::Nullable<chrono::DateTime<chrono::Utc>>);
 impl_typed_http_search_index!(
-    crate::static_btree::key::FixedStringKey<20>,
-    KeyType::StringKey20
+    crate::static_btree::key::Nullable<crate::static_btree::key::FixedStringKey<20>>
 );
 impl_typed_http_search_index!(
-    crate::static_btree::key::FixedStringKey<50>,
-    KeyType::StringKey50
+    crate::static_btree::key::Nullable<crate::static_btree::key::FixedStringKey<50>>
 );
 impl_typed_http_search_index!(
-    crate::static_btree::key::FixedStringKey<100>,
-    KeyType::StringKey100
+    crate::static_btree::key::Nullable<crate::static_btree::key::FixedStringKey<100>>
 );
 
+fn sum_cache_stats(stats: impl Iterator<Item = CacheStats>) -> CacheStats {
+    stats.fold(CacheStats::default(), |sum, stats| CacheStats {
+        hits: sum.hits + stats.hits,
+        misses: sum.misses + stats.misses,
+        evictions: sum.evictions + stats.evictions,
+    })
+}
+
 /// Container for multiple HTTP indices keyed by field name
 #[derive(Debug)]
 #[cfg(not(target_arch = "wasm32"))]
@@ -286,6 +457,11 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpMultiIndex<T> {
         self.indices.insert(field, Box::new(index));
     }
 
+    /// Hit, miss and eviction counters of the payload caches of all indices, summed
+    pub fn cache_stats(&self) -> CacheStats {
+        sum_cache_stats(self.indices.values().map(|idx| idx.cache_stats()))
+    }
+
     /// Execute a multi-condition query by AND-ing all conditions
     pub async fn query(
         &self,
@@ -315,6 +491,64 @@ impl<T: AsyncHttpRangeClient + Send + Sync> HttpMultiIndex<T> {
         }
         Ok(intersection)
     }
+
+    /// Count the features matching all conditions. The index blocks are fetched as for
+    /// [`Self::query`] but only the feature offsets are intersected, no ranges are returned.
+    pub async fn count_query(
+        &self,
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        conditions: &[QueryCondition],
+    ) -> Result<usize> {
+        if conditions.is_empty() {
+            return Err(Error::QueryError("query cannot be empty".to_string()));
+        }
+        let mut result_set: Option<Vec<usize>> = None;
+        for cond in conditions {
+            let idx = self.indices.get(&cond.field).ok_or_else(|| {
+                Error::QueryError(format!("no index found for field '{}'", cond.field))
+            })?;
+            let offsets = idx
+                .execute_query_condition(client, cond)
+                .await?
+                .into_iter()
+                .map(|item| item.range.start())
+                .collect::<Vec<_>>();
+            let result = match result_set.take() {
+                Some(mut result) => {
+                    result.retain(|offset| offsets.contains(offset));
+                    result
+                }
+                None => offsets,
+            };
+            if result.is_empty() {
+                return Ok(0);
+            }
+            result_set = Some(result);
+        }
+        Ok(result_set.map_or(0, |result| result.len()))
+    }
+
+    /// Execute a multi-condition query and report how many candidates each condition's index
+    /// returned, see [`QueryPlan`]
+    pub async fn explain(
+        &self,
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        conditions: &[QueryCondition],
+    ) -> Result<QueryPlan> {
+        if conditions.is_empty() {
+            return Err(Error::QueryError("query cannot be empty".to_string()));
+        }
+        let mut plan = QueryPlan::default();
+        let mut result_set = None;
+        for cond in conditions {
+            let idx = self.indices.get(&cond.field).ok_or_else(|| {
+                Error::QueryError(format!("no index found for field '{}'", cond.field))
+            })?;
+            let items = idx.execute_query_condition(client, cond).await?;
+            plan.add_condition(cond, &idx.key_type(), items, &mut result_set);
+        }
+        Ok(plan)
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -347,6 +581,11 @@ impl<T: AsyncHttpRangeClient> HttpMultiIndex<T> {
     {
         self.indices.insert(field, Box::new(index));
     }
+
+    /// Hit, miss and eviction counters of the payload caches of all indices, summed
+    pub fn cache_stats(&self) -> CacheStats {
+        sum_cache_stats(self.indices.values().map(|idx| idx.cache_stats()))
+    }
     /// Execute a multi-condition query by AND-ing all conditions
     pub async fn query(
         &self,
@@ -379,6 +618,64 @@ impl<T: AsyncHttpRangeClient> HttpMultiIndex<T> {
         }
         Ok(intersection)
     }
+
+    /// Count the features matching all conditions. The index blocks are fetched as for
+    /// [`Self::query`] but only the feature offsets are intersected, no ranges are returned.
+    pub async fn count_query(
+        &self,
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        conditions: &[QueryCondition],
+    ) -> Result<usize> {
+        if conditions.is_empty() {
+            return Err(Error::QueryError("query cannot be empty".to_string()));
+        }
+        let mut result_set: Option<Vec<usize>> = None;
+        for cond in conditions {
+            let idx = self.indices.get(&cond.field).ok_or_else(|| {
+                Error::QueryError(format!("no index found for field '{}'", cond.field))
+            })?;
+            let offsets = idx
+                .execute_query_condition(client, cond)
+                .await?
+                .into_iter()
+                .map(|item| item.range.start())
+                .collect::<Vec<_>>();
+            let result = match result_set.take() {
+                Some(mut result) => {
+                    result.retain(|offset| offsets.contains(offset));
+                    result
+                }
+                None => offsets,
+            };
+            if result.is_empty() {
+                return Ok(0);
+            }
+            result_set = Some(result);
+        }
+        Ok(result_set.map_or(0, |result| result.len()))
+    }
+
+    /// Execute a multi-condition query and report how many candidates each condition's index
+    /// returned, see [`QueryPlan`]
+    pub async fn explain(
+        &self,
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        conditions: &[QueryCondition],
+    ) -> Result<QueryPlan> {
+        if conditions.is_empty() {
+            return Err(Error::QueryError("query cannot be empty".to_string()));
+        }
+        let mut plan = QueryPlan::default();
+        let mut result_set = None;
+        for cond in conditions {
+            let idx = self.indices.get(&cond.field).ok_or_else(|| {
+                Error::QueryError(format!("no index found for field '{}'", cond.field))
+            })?;
+            let items = idx.execute_query_condition(client, cond).await?;
+            plan.add_condition(cond, &idx.key_type(), items, &mut result_set);
+        }
+        Ok(plan)
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
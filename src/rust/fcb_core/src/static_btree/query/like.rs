@@ -0,0 +1,241 @@
+use crate::static_btree::error::{Error, Result};
+use crate::static_btree::key::Key;
+use std::sync::OnceLock;
+
+/// Glob pattern of an [`Operator::Like`](super::Operator::Like) condition: `*` matches any
+/// sequence of characters and `?` exactly one.
+///
+/// String keys only hold the first bytes of long values. A pattern is matched against such a
+/// truncated key when the outcome doesn't depend on the cut-off part, e.g. `NL.IMBAG*`, and
+/// the query fails otherwise, e.g. `*.01` on a key that may have been longer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LikePattern {
+    pattern: String,
+}
+
+impl LikePattern {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+        }
+    }
+
+    /// Pattern of a query key, which must be a string key short enough not to be truncated
+    pub(crate) fn from_key<K: Key>(key: &K) -> Result<Self> {
+        let pattern = key
+            .as_str()
+            .ok_or_else(|| Error::QueryError(format!("LIKE requires a string key, got {key:?}")))?;
+        if key.may_be_truncated() {
+            return Err(Error::QueryError(format!(
+                "LIKE pattern {pattern:?} is too long for the keys of the index"
+            )));
+        }
+        Ok(Self::new(&pattern))
+    }
+
+    /// Literal part before the first wildcard, every matching value starts with it
+    pub fn prefix(&self) -> &str {
+        let end = self.pattern.find(['*', '?']).unwrap_or(self.pattern.len());
+        &self.pattern[..end]
+    }
+
+    /// Whether the whole of `value` matches the pattern
+    pub fn matches(&self, value: &str) -> bool {
+        let pattern = self.pattern.chars().collect::<Vec<_>>();
+        Self::states(&pattern, value)[pattern.len()]
+    }
+
+    /// Positions in `pattern` that can be reached after matching `value`
+    fn states(pattern: &[char], value: &str) -> Vec<bool> {
+        // a `*` matches the empty sequence, so the position after it is reached as well
+        let skip_stars = |states: &mut Vec<bool>| {
+            for p in 0..pattern.len() {
+                if states[p] && pattern[p] == '*' {
+                    states[p + 1] = true;
+                }
+            }
+        };
+        let mut states = vec![false; pattern.len() + 1];
+        states[0] = true;
+        skip_stars(&mut states);
+        for c in value.chars() {
+            let mut next = vec![false; pattern.len() + 1];
+            for p in (0..pattern.len()).filter(|&p| states[p]) {
+                match pattern[p] {
+                    '*' => next[p] = true,
+                    '?' => next[p + 1] = true,
+                    literal if literal == c => next[p + 1] = true,
+                    _ => {}
+                }
+            }
+            states = next;
+            skip_stars(&mut states);
+        }
+        states
+    }
+
+    /// Whether the value of `key` matches, `None` if the key may be truncated and the outcome
+    /// depends on the cut-off part of the value
+    pub(crate) fn matches_key<K: Key>(&self, key: &K) -> Option<bool> {
+        let Some(value) = key.as_str() else {
+            return Some(false);
+        };
+        let pattern = self.pattern.chars().collect::<Vec<_>>();
+        let states = Self::states(&pattern, &value);
+        if !key.may_be_truncated() {
+            return Some(states[pattern.len()]);
+        }
+        if !states.contains(&true) {
+            // no continuation of the value can match
+            return Some(false);
+        }
+        // a reached `*` followed only by `*` matches any continuation
+        let any_continuation =
+            (0..pattern.len()).any(|p| states[p] && pattern[p..].iter().all(|&c| c == '*'));
+        any_continuation.then_some(true)
+    }
+
+    /// Key filter for a range search, see [`LikeFilter`]
+    pub(crate) fn filter(&self) -> LikeFilter<'_> {
+        LikeFilter {
+            pattern: self,
+            undecided: OnceLock::new(),
+        }
+    }
+
+    /// Smallest and largest keys starting with the prefix, the only keys that can match
+    pub(crate) fn bounds<K: Key>(&self) -> Result<(K, K)> {
+        K::prefix_bounds(self.prefix()).ok_or_else(|| {
+            Error::QueryError(format!(
+                "LIKE requires a string index, got {}",
+                std::any::type_name::<K>()
+            ))
+        })
+    }
+}
+
+/// Matches the keys of a range search against a [`LikePattern`], remembering the first key
+/// the pattern can't be decided on
+pub(crate) struct LikeFilter<'a> {
+    pattern: &'a LikePattern,
+    undecided: OnceLock<String>,
+}
+
+impl LikeFilter<'_> {
+    pub(crate) fn matches<K: Key>(&self, key: &K) -> bool {
+        self.pattern.matches_key(key).unwrap_or_else(|| {
+            let _ = self
+                .undecided
+                .set(key.as_str().unwrap_or_default().into_owned());
+            false
+        })
+    }
+
+    /// Fails if the pattern couldn't be decided on a truncated key
+    pub(crate) fn finish(self) -> Result<()> {
+        match self.undecided.into_inner() {
+            Some(key) => Err(Error::QueryError(format!(
+                "LIKE pattern {:?} depends on the end of {key:?}, which is truncated in the index",
+                self.pattern.pattern
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::static_btree::key::{FixedStringKey, Nullable};
+
+    #[test]
+    fn like_matches() {
+        let trailing = LikePattern::new("NL.IMBAG*");
+        assert_eq!(trailing.prefix(), "NL.IMBAG");
+        assert!(trailing.matches("NL.IMBAG"));
+        assert!(trailing.matches("NL.IMBAG.Pand.0503100000000010"));
+        assert!(!trailing.matches("NL.IMBA"));
+        assert!(!trailing.matches("XNL.IMBAG"));
+
+        let leading = LikePattern::new("*straat");
+        assert_eq!(leading.prefix(), "");
+        assert!(leading.matches("straat"));
+        assert!(leading.matches("Kerkstraat"));
+        assert!(!leading.matches("Kerkstraat 1"));
+
+        let interior = LikePattern::new("a*b?c*d");
+        assert_eq!(interior.prefix(), "a");
+        assert!(interior.matches("abxcd"));
+        assert!(interior.matches("a123b-c456d"));
+        assert!(interior.matches("abbxbycd"));
+        assert!(!interior.matches("abcd"));
+        assert!(!interior.matches("abxcde"));
+
+        let single = LikePattern::new("caf?");
+        assert!(single.matches("café"));
+        assert!(!single.matches("caf"));
+
+        let exact = LikePattern::new("delft");
+        assert!(exact.matches("delft"));
+        assert!(!exact.matches("delft2"));
+    }
+
+    #[test]
+    fn like_bounds() -> Result<()> {
+        let pattern = LikePattern::new("ab*");
+        let (lower, upper) = pattern.bounds::<FixedStringKey<4>>()?;
+        for (value, inside) in [("ab", true), ("abzz", true), ("aa", false), ("ac", false)] {
+            let key = FixedStringKey::<4>::from_str(value);
+            assert_eq!(lower <= key && key <= upper, inside, "{value}");
+        }
+
+        let (lower, upper) = pattern.bounds::<Nullable<FixedStringKey<4>>>()?;
+        assert!(lower > Nullable(None) && upper > Nullable(None));
+        assert_eq!(
+            pattern.matches_key(&Nullable(Some(FixedStringKey::<4>::from_str("abc")))),
+            Some(true)
+        );
+        assert_eq!(
+            pattern.matches_key(&Nullable::<FixedStringKey<4>>(None)),
+            Some(false)
+        );
+
+        assert!(pattern.bounds::<i32>().is_err());
+        assert!(LikePattern::from_key(&1i32).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn like_truncated_keys() -> Result<()> {
+        // "abcdefghijk" cut off after 8 bytes
+        let truncated = FixedStringKey::<8>::from_str("abcdefghijk");
+        assert!(truncated.may_be_truncated());
+        assert!(!FixedStringKey::<8>::from_str("abc").may_be_truncated());
+
+        // decided by the first bytes
+        assert_eq!(LikePattern::new("abc*").matches_key(&truncated), Some(true));
+        assert_eq!(
+            LikePattern::new("a?c*e*").matches_key(&truncated),
+            Some(true)
+        );
+        assert_eq!(
+            LikePattern::new("abd*").matches_key(&truncated),
+            Some(false)
+        );
+        assert_eq!(LikePattern::new("abc").matches_key(&truncated), Some(false));
+        // depends on the cut-off part
+        assert_eq!(LikePattern::new("*k").matches_key(&truncated), None);
+        assert_eq!(LikePattern::new("abcdefgh").matches_key(&truncated), None);
+
+        let pattern = LikePattern::new("*k");
+        let filter = pattern.filter();
+        assert!(filter.matches(&FixedStringKey::<8>::from_str("ak")));
+        assert!(!filter.matches(&truncated));
+        assert!(filter.finish().is_err());
+
+        // a pattern filling the key may itself be truncated
+        assert!(LikePattern::from_key(&FixedStringKey::<8>::from_str("abcdefg*")).is_err());
+        assert!(LikePattern::from_key(&FixedStringKey::<8>::from_str("abc*")).is_ok());
+        Ok(())
+    }
+}
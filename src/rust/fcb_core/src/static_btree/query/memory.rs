@@ -2,15 +2,17 @@ use chrono::{DateTime, Utc};
 use ordered_float::OrderedFloat;
 use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
 
 use crate::static_btree::entry::Entry;
 use crate::static_btree::error::{Error, Result};
-use crate::static_btree::key::{FixedStringKey, Key, KeyType, Max, Min};
-use crate::static_btree::query::types::{Operator, SearchIndex};
+use crate::static_btree::key::{FixedStringKey, FromKeyType, Key, KeyType, Max, Min, Nullable};
+use crate::static_btree::query::types::{key_type_name, Operator, QueryPlan, SearchIndex};
 use crate::static_btree::stree::Stree;
 
+use super::cache::{QueryCache, QueryCacheKey, QueryCacheStats};
 use super::types::QueryCondition;
-use super::MultiIndex;
+use super::{LikePattern, MultiIndex};
 
 /// In-memory index implementation that wraps the Stree structure
 // NOTE: This can be type alias for Stree later
@@ -35,6 +37,13 @@ impl<K: Key> MemoryIndex<K> {
         Ok(Self { stree })
     }
 
+    /// Build a memory index from entries already sorted by key, see [`Stree::build_sorted`]
+    pub fn build_sorted(entries: Vec<Entry<K>>, branching_factor: u16) -> Result<Self> {
+        let stree = Stree::<K>::build_sorted(entries, branching_factor)?;
+
+        Ok(Self { stree })
+    }
+
     pub fn from_buf(mut data: impl Read, num_items: usize, branching_factor: u16) -> Result<Self> {
         let stree = Stree::from_buf(&mut data, num_items, branching_factor)?;
 
@@ -60,6 +69,18 @@ impl<K: Key> MemoryIndex<K> {
     pub fn payload_size(&self) -> usize {
         self.stree.payload_size()
     }
+
+    /// Find the offsets of all keys matching a glob pattern. Only the keys starting with the
+    /// literal prefix of the pattern are scanned.
+    pub fn find_like(&self, pattern: &LikePattern) -> Result<Vec<u64>> {
+        let (lower, upper) = pattern.bounds::<K>()?;
+        let filter = pattern.filter();
+        let results = self
+            .stree
+            .find_range_filtered(lower, upper, |key| filter.matches(key))?;
+        filter.finish()?;
+        Ok(results.into_iter().map(|item| item.offset as u64).collect())
+    }
 }
 
 impl<K: Key> SearchIndex<K> for MemoryIndex<K> {
@@ -95,28 +116,53 @@ impl<K: Key> SearchIndex<K> for MemoryIndex<K> {
 pub trait TypedSearchIndex: Send + Sync {
     /// Execute the query condition
     fn execute_query_condition(&self, condition: &QueryCondition) -> Result<Vec<u64>>;
+
+    /// Name of the key type of the index
+    fn key_type(&self) -> String;
 }
 
 // Macro to implement TypedSearchIndex for each key type following the same pattern
 macro_rules! impl_typed_search_index {
-    ($key_type:ty, $enum_variant:path) => {
+    ($key_type:ty) => {
         impl TypedSearchIndex for MemoryIndex<$key_type> {
+            fn key_type(&self) -> String {
+                key_type_name::<$key_type>()
+            }
+
             fn execute_query_condition(&self, condition: &QueryCondition) -> Result<Vec<u64>> {
-                // Extract the key value from the enum variant
-                let key = match &condition.key {
-                    $enum_variant(val) => val.clone(),
-                    _ => {
-                        return Err(Error::QueryError(format!(
+                let key = if condition.operator == Operator::IsNull
+                    || matches!(condition.key, KeyType::Null)
+                {
+                    // Indices without null entries have nothing to match, so all of their
+                    // items are not null
+                    match <$key_type>::null() {
+                        Some(null) => null,
+                        None if condition.operator == Operator::Ne => {
+                            return self.find_range(
+                                Some(<$key_type>::min_value()),
+                                Some(<$key_type>::max_value()),
+                            );
+                        }
+                        None => return Ok(Vec::new()),
+                    }
+                } else {
+                    <$key_type>::from_key_type(&condition.key).ok_or_else(|| {
+                        Error::QueryError(format!(
                             "key type mismatch: expected {}, got {:?}",
                             stringify!($key_type),
                             condition.key
-                        )))
-                    }
+                        ))
+                    })?
                 };
 
+                // NaN and null are not ordered against other values
+                if (key.is_nan() || key.is_null()) && condition.operator.is_range() {
+                    return Ok(Vec::new());
+                }
+
                 // Execute query based on operator
                 match condition.operator {
-                    Operator::Eq => self.find_exact(key),
+                    Operator::Eq | Operator::IsNull => self.find_exact(key),
                     Operator::Ne => {
                         let min = <$key_type>::min_value();
                         let max = <$key_type>::max_value();
@@ -141,6 +187,7 @@ macro_rules! impl_typed_search_index {
                     }
                     Operator::Ge => self.find_range(Some(key), None),
                     Operator::Le => self.find_range(None, Some(key)),
+                    Operator::Like => self.find_like(&LikePattern::from_key(&key)?),
                 }
             }
         }
@@ -148,26 +195,43 @@ macro_rules! impl_typed_search_index {
 }
 
 // Implement TypedSearchIndex for all supported key types
-impl_typed_search_index!(i32, KeyType::Int32);
-impl_typed_search_index!(i64, KeyType::Int64);
-impl_typed_search_index!(i8, KeyType::Int8);
-impl_typed_search_index!(u8, KeyType::UInt8);
-impl_typed_search_index!(i16, KeyType::Int16);
-impl_typed_search_index!(u16, KeyType::UInt16);
-impl_typed_search_index!(u32, KeyType::UInt32);
-impl_typed_search_index!(u64, KeyType::UInt64);
-impl_typed_search_index!(OrderedFloat<f32>, KeyType::Float32);
-impl_typed_search_index!(OrderedFloat<f64>, KeyType::Float64);
-impl_typed_search_index!(bool, KeyType::Bool);
-impl_typed_search_index!(DateTime<Utc>, KeyType::DateTime);
-impl_typed_search_index!(FixedStringKey<20>, KeyType::StringKey20);
-impl_typed_search_index!(FixedStringKey<50>, KeyType::StringKey50);
-impl_typed_search_index!(FixedStringKey<100>, KeyType::StringKey100);
+impl_typed_search_index!(i32);
+impl_typed_search_index!(i64);
+impl_typed_search_index!(i8);
+impl_typed_search_index!(u8);
+impl_typed_search_index!(i16);
+impl_typed_search_index!(u16);
+impl_typed_search_index!(u32);
+impl_typed_search_index!(u64);
+impl_typed_search_index!(OrderedFloat<f32>);
+impl_typed_search_index!(OrderedFloat<f64>);
+impl_typed_search_index!(bool);
+impl_typed_search_index!(DateTime<Utc>);
+impl_typed_search_index!(FixedStringKey<20>);
+impl_typed_search_index!(FixedStringKey<50>);
+impl_typed_search_index!(FixedStringKey<100>);
+impl_typed_search_index!(Nullable<i8>);
+impl_typed_search_index!(Nullable<u8>);
+impl_typed_search_index!(Nullable<i16>);
+impl_typed_search_index!(Nullable<u16>);
+impl_typed_search_index!(Nullable<i32>);
+impl_typed_search_index!(Nullable<i64>);
+impl_typed_search_index!(Nullable<u32>);
+impl_typed_search_index!(Nullable<u64>);
+impl_typed_search_index!(Nullable<OrderedFloat<f32>>);
+impl_typed_search_index!(Nullable<OrderedFloat<f64>>);
+impl_typed_search_index!(Nullable<bool>);
+impl_typed_search_index!(Nullable<DateTime<Utc>>);
+impl_typed_search_index!(Nullable<FixedStringKey<20>>);
+impl_typed_search_index!(Nullable<FixedStringKey<50>>);
+impl_typed_search_index!(Nullable<FixedStringKey<100>>);
 
 /// Container for multiple in-memory indices with different key types
 pub struct MemoryMultiIndex {
     /// Map of field names to typed indices
     indices: HashMap<String, Box<dyn TypedSearchIndex>>,
+    /// Results of recent queries, see [`MemoryMultiIndex::with_cache`]
+    cache: Option<Mutex<QueryCache>>,
 }
 
 impl MemoryMultiIndex {
@@ -175,6 +239,27 @@ impl MemoryMultiIndex {
     pub fn new() -> Self {
         Self {
             indices: HashMap::new(),
+            cache: None,
+        }
+    }
+
+    /// Cache the results of the `capacity` most recently used queries. Adding an index clears
+    /// the cache, since it may replace the index of a field.
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(Mutex::new(QueryCache::new(capacity)));
+        self
+    }
+
+    /// Hits and misses of the query cache, `None` without a cache
+    pub fn cache_stats(&self) -> Option<QueryCacheStats> {
+        let cache = self.cache.as_ref()?;
+        Some(cache.lock().unwrap_or_else(|e| e.into_inner()).stats())
+    }
+
+    fn insert_index(&mut self, field: String, index: Box<dyn TypedSearchIndex>) {
+        self.indices.insert(field, index);
+        if let Some(cache) = self.cache.as_mut() {
+            cache.get_mut().unwrap_or_else(|e| e.into_inner()).clear();
         }
     }
 
@@ -183,7 +268,7 @@ impl MemoryMultiIndex {
     where
         MemoryIndex<K>: TypedSearchIndex,
     {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     pub fn indices(&self) -> &HashMap<String, Box<dyn TypedSearchIndex>> {
@@ -192,83 +277,121 @@ impl MemoryMultiIndex {
 
     /// Add a string index with key size 20
     pub fn add_string_index20(&mut self, field: String, index: MemoryIndex<FixedStringKey<20>>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a string index with key size 50
     pub fn add_string_index50(&mut self, field: String, index: MemoryIndex<FixedStringKey<50>>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a string index with key size 100
     pub fn add_string_index100(&mut self, field: String, index: MemoryIndex<FixedStringKey<100>>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add an i32 index
     pub fn add_i32_index(&mut self, field: String, index: MemoryIndex<i32>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add an i64 index
     pub fn add_i64_index(&mut self, field: String, index: MemoryIndex<i64>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a u32 index
     pub fn add_u32_index(&mut self, field: String, index: MemoryIndex<u32>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a u64 index
     pub fn add_u64_index(&mut self, field: String, index: MemoryIndex<u64>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a float32 index
     pub fn add_f32_index(&mut self, field: String, index: MemoryIndex<OrderedFloat<f32>>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a float64 index
     pub fn add_f64_index(&mut self, field: String, index: MemoryIndex<OrderedFloat<f64>>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a i8 index
     pub fn add_i8_index(&mut self, field: String, index: MemoryIndex<i8>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a u8 index
     pub fn add_u8_index(&mut self, field: String, index: MemoryIndex<u8>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a i16 index
     pub fn add_i16_index(&mut self, field: String, index: MemoryIndex<i16>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a u16 index
     pub fn add_u16_index(&mut self, field: String, index: MemoryIndex<u16>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a boolean index
     pub fn add_bool_index(&mut self, field: String, index: MemoryIndex<bool>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 
     /// Add a datetime index
     pub fn add_datetime_index(&mut self, field: String, index: MemoryIndex<DateTime<Utc>>) {
-        self.indices.insert(field, Box::new(index));
+        self.insert_index(field, Box::new(index));
     }
 }
 
 impl MultiIndex for MemoryMultiIndex {
     /// Execute a heterogeneous query with different key types
     fn query(&self, conditions: &[QueryCondition]) -> Result<Vec<u64>> {
+        let Some(cache) = self.cache.as_ref() else {
+            return self.execute(conditions);
+        };
+        let key = QueryCacheKey::new(conditions);
+        // A poisoned cache only holds complete entries, so it is still safe to use
+        let cached = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key);
+        if let Some(results) = cached {
+            return Ok(results.as_ref().clone());
+        }
+        let results = self.execute(conditions)?;
+        cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, Arc::new(results.clone()));
+        Ok(results)
+    }
+
+    fn explain(&self, conditions: &[QueryCondition]) -> Result<QueryPlan> {
+        if conditions.is_empty() {
+            return Err(Error::QueryError("query cannot be empty".to_string()));
+        }
+
+        let mut plan = QueryPlan::default();
+        let mut result_set = None;
+        for condition in conditions {
+            let index = self.indices.get(&condition.field).ok_or_else(|| {
+                Error::QueryError(format!("no index found for field '{}'", condition.field))
+            })?;
+            let candidates = index.execute_query_condition(condition)?;
+            plan.add_condition(condition, &index.key_type(), candidates, &mut result_set);
+        }
+        Ok(plan)
+    }
+}
+
+impl MemoryMultiIndex {
+    /// Runs a query against the indices, bypassing the cache
+    fn execute(&self, conditions: &[QueryCondition]) -> Result<Vec<u64>> {
         if conditions.is_empty() {
             return Err(Error::QueryError("query cannot be empty".to_string()));
         }
@@ -311,3 +434,45 @@ impl Default for MemoryMultiIndex {
         Self::new()
     }
 }
+
+impl MemoryMultiIndex {
+    /// Wraps the multi-index to share it between threads
+    pub fn into_shared(self) -> SharedMemoryMultiIndex {
+        SharedMemoryMultiIndex {
+            inner: Arc::new(RwLock::new(self)),
+        }
+    }
+}
+
+/// [`MemoryMultiIndex`] that can be queried from several threads at once
+///
+/// Queries take a read lock, so they only wait for a writer adding indices through
+/// [`SharedMemoryMultiIndex::write`]. Clones share the same indices.
+#[derive(Clone)]
+pub struct SharedMemoryMultiIndex {
+    inner: Arc<RwLock<MemoryMultiIndex>>,
+}
+
+impl SharedMemoryMultiIndex {
+    /// Exclusive access to the multi-index, e.g. to add indices
+    pub fn write(&self) -> Result<RwLockWriteGuard<'_, MemoryMultiIndex>> {
+        self.inner.write().map_err(|_| poisoned())
+    }
+}
+
+impl MultiIndex for SharedMemoryMultiIndex {
+    fn query(&self, conditions: &[QueryCondition]) -> Result<Vec<u64>> {
+        self.inner.read().map_err(|_| poisoned())?.query(conditions)
+    }
+
+    fn explain(&self, conditions: &[QueryCondition]) -> Result<QueryPlan> {
+        self.inner
+            .read()
+            .map_err(|_| poisoned())?
+            .explain(conditions)
+    }
+}
+
+fn poisoned() -> Error {
+    Error::QueryError("multi-index lock poisoned by a panicking writer".to_string())
+}
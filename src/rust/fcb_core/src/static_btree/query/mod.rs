@@ -4,7 +4,11 @@
 // static B+trees, including various index implementations and
 // query capabilities.
 
+mod cache;
+pub mod conditions;
+mod like;
 mod memory;
+mod sql;
 mod stream;
 mod types;
 
@@ -14,9 +18,14 @@ mod http;
 #[cfg(test)]
 mod tests;
 
+pub use cache::{QueryCacheKey, QueryCacheStats};
+pub use like::LikePattern;
 pub use memory::*;
+pub use sql::{ExprCondition, Literal, ParseError, QueryExpr};
 pub use stream::*;
-pub use types::{MultiIndex, Operator, Query, QueryCondition, SearchIndex};
+pub use types::{
+    ConditionPlan, LogicalOp, MultiIndex, Operator, Query, QueryCondition, QueryPlan, SearchIndex,
+};
 
 #[cfg(feature = "http")]
 pub use http::*;
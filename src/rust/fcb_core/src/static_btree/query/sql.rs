@@ -0,0 +1,436 @@
+// Parser for SQL WHERE clauses, e.g. `height > 30 AND (roof = 'flat' OR roof IS NULL)`
+
+use super::types::Operator;
+use std::fmt;
+use thiserror::Error;
+
+/// Syntax error in a WHERE clause
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("syntax error at position {position}: {message}")]
+pub struct ParseError {
+    /// Byte offset in the clause
+    pub position: usize,
+    pub message: String,
+}
+
+/// Value of a condition, typed after the column when the query is executed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Int(v) => write!(f, "{v}"),
+            Literal::Float(v) => write!(f, "{v}"),
+            Literal::String(v) => write!(f, "'{v}'"),
+            Literal::Bool(v) => write!(f, "{}", if *v { "TRUE" } else { "FALSE" }),
+        }
+    }
+}
+
+/// A condition of a WHERE clause. The value is `None` for `IS NULL` (`Operator::IsNull`) and
+/// `IS NOT NULL` (`Operator::Ne`).
+pub type ExprCondition = (String, Operator, Option<Literal>);
+
+/// Boolean expression parsed from a WHERE clause
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryExpr {
+    Condition(ExprCondition),
+    /// `field IN (v1, v2, ...)`
+    In {
+        field: String,
+        values: Vec<Literal>,
+    },
+    And(Box<QueryExpr>, Box<QueryExpr>),
+    Or(Box<QueryExpr>, Box<QueryExpr>),
+}
+
+impl QueryExpr {
+    /// Parses a WHERE clause of `field op value` conditions combined with `AND`, `OR` and
+    /// parentheses. `AND` binds tighter than `OR`.
+    ///
+    /// Supported operators are `=`, `!=`, `<`, `<=`, `>`, `>=`, `IS NULL`, `IS NOT NULL` and
+    /// `IN (v1, v2, ...)`. Values are integers, floats, quoted strings, `TRUE` or `FALSE`.
+    /// Keywords are case-insensitive, and fields may be double-quoted.
+    pub fn from_sql_where_clause(sql: &str) -> Result<QueryExpr, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(sql)?,
+            pos: 0,
+            end: sql.len(),
+        };
+        let expr = parser.or_expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some((position, token)) => Err(ParseError {
+                position,
+                message: format!("unexpected {token}"),
+            }),
+        }
+    }
+
+    /// Rewrites the expression as groups of conditions which are AND-ed within a group and
+    /// OR-ed between groups, the form the attribute queries of the readers take.
+    /// `IN` becomes one `=` condition per value.
+    pub fn and_groups(&self) -> Vec<Vec<ExprCondition>> {
+        match self {
+            QueryExpr::Condition(condition) => vec![vec![condition.clone()]],
+            QueryExpr::In { field, values } => values
+                .iter()
+                .map(|value| vec![(field.clone(), Operator::Eq, Some(value.clone()))])
+                .collect(),
+            QueryExpr::Or(left, right) => {
+                let mut groups = left.and_groups();
+                groups.extend(right.and_groups());
+                groups
+            }
+            QueryExpr::And(left, right) => {
+                let right = right.and_groups();
+                left.and_groups()
+                    .into_iter()
+                    .flat_map(|l| {
+                        right.iter().map(move |r| {
+                            let mut group = l.clone();
+                            group.extend(r.iter().cloned());
+                            group
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    /// Quoted text and its quote character
+    Str(String, char),
+    Number(Literal),
+    Op(Operator),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "'{s}'"),
+            Token::Str(s, quote) => write!(f, "string {quote}{s}{quote}"),
+            Token::Number(n) => write!(f, "number {n}"),
+            Token::Op(op) => write!(f, "operator {op:?}"),
+            Token::LParen => f.write_str("'('"),
+            Token::RParen => f.write_str("')'"),
+            Token::Comma => f.write_str("','"),
+        }
+    }
+}
+
+fn tokenize(sql: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        let error = |message: String| ParseError {
+            position: start,
+            message,
+        };
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    _ => Token::Comma,
+                }
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let next = chars.peek().map(|&(_, c)| c);
+                let (operator, two_chars) = match (c, next) {
+                    ('=', Some('=')) => (Operator::Eq, true),
+                    ('=', _) => (Operator::Eq, false),
+                    ('!', Some('=')) | ('<', Some('>')) => (Operator::Ne, true),
+                    ('<', Some('=')) => (Operator::Le, true),
+                    ('>', Some('=')) => (Operator::Ge, true),
+                    ('<', _) => (Operator::Lt, false),
+                    ('>', _) => (Operator::Gt, false),
+                    _ => return Err(error("expected '=' after '!'".to_string())),
+                };
+                if two_chars {
+                    chars.next();
+                }
+                Token::Op(operator)
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // a doubled quote stands for the quote itself
+                        Some((_, q)) if q == c => {
+                            if chars.peek().map(|&(_, n)| n) == Some(c) {
+                                chars.next();
+                                text.push(c);
+                            } else {
+                                break;
+                            }
+                        }
+                        Some((_, other)) => text.push(other),
+                        None => return Err(error("unterminated string".to_string())),
+                    }
+                }
+                Token::Str(text, c)
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    let exponent_sign = (c == '-' || c == '+')
+                        && matches!(sql[..i].chars().last(), Some('e' | 'E'));
+                    if c.is_ascii_alphanumeric() || c == '.' || i == start || exponent_sign {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let text = &sql[start..end];
+                let number = if let Ok(v) = text.parse::<i64>() {
+                    Literal::Int(v)
+                } else if let Ok(v) = text.parse::<f64>() {
+                    Literal::Float(v)
+                } else {
+                    return Err(error(format!("invalid number '{text}'")));
+                };
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' || c == ':' {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Ident(sql[start..end].to_string())
+            }
+            c => return Err(error(format!("unexpected character '{c}'"))),
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// Length of the clause, the position of errors at its end
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, &Token)> {
+        self.tokens.get(self.pos).map(|(p, t)| (*p, t))
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), ParseError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or(ParseError {
+            position: self.end,
+            message: "unexpected end of clause".to_string(),
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    /// Consumes the next token if it is the keyword `keyword`
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some((_, Token::Ident(s))) if s.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ParseError> {
+        let (position, token) = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(ParseError {
+                position,
+                message: format!("expected {what}, found {token}"),
+            })
+        }
+    }
+
+    fn or_expr(&mut self) -> Result<QueryExpr, ParseError> {
+        let mut expr = self.and_expr()?;
+        while self.keyword("OR") {
+            expr = QueryExpr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<QueryExpr, ParseError> {
+        let mut expr = self.primary()?;
+        while self.keyword("AND") {
+            expr = QueryExpr::And(Box::new(expr), Box::new(self.primary()?));
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<QueryExpr, ParseError> {
+        if let Some((_, Token::LParen)) = self.peek() {
+            self.pos += 1;
+            let expr = self.or_expr()?;
+            self.expect(Token::RParen, "')'")?;
+            return Ok(expr);
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<QueryExpr, ParseError> {
+        let field = match self.next()? {
+            (_, Token::Ident(field)) | (_, Token::Str(field, '"')) => field,
+            (position, token) => {
+                return Err(ParseError {
+                    position,
+                    message: format!("expected a field, found {token}"),
+                })
+            }
+        };
+
+        if self.keyword("IS") {
+            let operator = if self.keyword("NOT") {
+                Operator::Ne
+            } else {
+                Operator::IsNull
+            };
+            if !self.keyword("NULL") {
+                let (position, token) = self.next()?;
+                return Err(ParseError {
+                    position,
+                    message: format!("expected NULL, found {token}"),
+                });
+            }
+            return Ok(QueryExpr::Condition((field, operator, None)));
+        }
+
+        if self.keyword("IN") {
+            self.expect(Token::LParen, "'(' after IN")?;
+            let mut values = vec![self.value()?];
+            while let Some((_, Token::Comma)) = self.peek() {
+                self.pos += 1;
+                values.push(self.value()?);
+            }
+            self.expect(Token::RParen, "')'")?;
+            return Ok(QueryExpr::In { field, values });
+        }
+
+        match self.next()? {
+            (_, Token::Op(operator)) => {
+                let value = self.value()?;
+                Ok(QueryExpr::Condition((field, operator, Some(value))))
+            }
+            (position, token) => Err(ParseError {
+                position,
+                message: format!("expected an operator after '{field}', found {token}"),
+            }),
+        }
+    }
+
+    fn value(&mut self) -> Result<Literal, ParseError> {
+        match self.next()? {
+            (_, Token::Number(n)) => Ok(n),
+            (_, Token::Str(s, _)) => Ok(Literal::String(s)),
+            (_, Token::Ident(s)) if s.eq_ignore_ascii_case("TRUE") => Ok(Literal::Bool(true)),
+            (_, Token::Ident(s)) if s.eq_ignore_ascii_case("FALSE") => Ok(Literal::Bool(false)),
+            (position, token) => Err(ParseError {
+                position,
+                message: format!("expected a value, found {token}"),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cond(field: &str, operator: Operator, value: Option<Literal>) -> QueryExpr {
+        QueryExpr::Condition((field.to_string(), operator, value))
+    }
+
+    #[test]
+    fn parse_where_clause() {
+        let expr = QueryExpr::from_sql_where_clause(
+            "height >= 30.5 and (roof = 'it''s flat' OR roof IS NULL) AND \"b3:id\" IN (1, -2)",
+        )
+        .unwrap();
+        let expected = QueryExpr::And(
+            Box::new(QueryExpr::And(
+                Box::new(cond("height", Operator::Ge, Some(Literal::Float(30.5)))),
+                Box::new(QueryExpr::Or(
+                    Box::new(cond(
+                        "roof",
+                        Operator::Eq,
+                        Some(Literal::String("it's flat".to_string())),
+                    )),
+                    Box::new(cond("roof", Operator::IsNull, None)),
+                )),
+            )),
+            Box::new(QueryExpr::In {
+                field: "b3:id".to_string(),
+                values: vec![Literal::Int(1), Literal::Int(-2)],
+            }),
+        );
+        assert_eq!(expr, expected);
+        assert_eq!(expr.and_groups().len(), 4);
+
+        // AND binds tighter than OR
+        let expr =
+            QueryExpr::from_sql_where_clause("a != 1 OR b <> TRUE AND c IS NOT NULL OR d < 1e3")
+                .unwrap();
+        assert_eq!(
+            expr.and_groups(),
+            vec![
+                vec![("a".to_string(), Operator::Ne, Some(Literal::Int(1)))],
+                vec![
+                    ("b".to_string(), Operator::Ne, Some(Literal::Bool(true))),
+                    ("c".to_string(), Operator::Ne, None),
+                ],
+                vec![("d".to_string(), Operator::Lt, Some(Literal::Float(1000.0)))],
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_where_clause_errors() {
+        for (sql, position) in [
+            ("height >", 8),
+            ("height 30", 7),
+            ("(a = 1", 6),
+            ("a = 1 b = 2", 6),
+            ("a = 'open", 4),
+            ("a IS 1", 5),
+            ("a IN 1", 5),
+            ("a ! 1", 2),
+            ("a = 1.2.3", 4),
+        ] {
+            let err = QueryExpr::from_sql_where_clause(sql).unwrap_err();
+            assert_eq!(err.position, position, "{sql}: {err}");
+        }
+    }
+}
@@ -8,10 +8,12 @@ use chrono::{DateTime, Utc};
 use ordered_float::OrderedFloat;
 
 use crate::static_btree::error::{Error, Result};
-use crate::static_btree::key::{FixedStringKey, Key, KeyType, Max, Min};
-use crate::static_btree::query::types::{Operator, QueryCondition};
+use crate::static_btree::key::{FixedStringKey, FromKeyType, Key, KeyType, Max, Min, Nullable};
+use crate::static_btree::query::types::{key_type_name, Operator, QueryCondition, QueryPlan};
 use crate::static_btree::stree::Stree;
 
+use super::LikePattern;
+
 /// Stream-based index for file access
 #[derive(Debug, Clone)]
 pub struct StreamIndex<K: Key> {
@@ -120,6 +122,30 @@ impl<K: Key> StreamIndex<K> {
         reader.seek(SeekFrom::Start(start_position))?;
         results
     }
+
+    /// Find the offsets of all keys matching a glob pattern using a reader, scanning only the
+    /// keys starting with the literal prefix of the pattern
+    pub fn find_like_with_reader<R: Read + Seek + ?Sized>(
+        &self,
+        reader: &mut R,
+        pattern: &LikePattern,
+    ) -> Result<Vec<u64>> {
+        let (lower, upper) = pattern.bounds::<K>()?;
+        let start_position = reader.stream_position()?;
+        let filter = pattern.filter();
+        let results = Stree::stream_find_range_filtered(
+            reader,
+            self.num_items,
+            self.branching_factor,
+            lower,
+            upper,
+            |key| filter.matches(key),
+        );
+        reader.seek(SeekFrom::Start(start_position))?;
+        let results = results?;
+        filter.finish()?;
+        Ok(results.into_iter().map(|item| item.offset as u64).collect())
+    }
 }
 
 /// Trait alias for objects that implement Read and Seek, to allow trait objects
@@ -134,32 +160,61 @@ pub trait TypedStreamSearchIndex: Send + Sync {
         reader: &mut dyn ReadSeek,
         condition: &QueryCondition,
     ) -> Result<Vec<u64>>;
+
+    /// Name of the key type of the index
+    fn key_type(&self) -> String;
 }
 
 // Macro to implement TypedStreamSearchIndex for each supported key type
 macro_rules! impl_typed_stream_search_index {
-    ($key_type:ty, $enum_variant:path) => {
+    ($key_type:ty) => {
         impl TypedStreamSearchIndex for StreamIndex<$key_type> {
+            fn key_type(&self) -> String {
+                key_type_name::<$key_type>()
+            }
+
             fn execute_query_condition(
                 &self,
                 reader: &mut dyn ReadSeek,
                 condition: &QueryCondition,
             ) -> Result<Vec<u64>> {
                 let start_position = reader.stream_position()?;
-                // Extract the key value from the enum variant
-                let key = match &condition.key {
-                    $enum_variant(val) => val.clone(),
-                    _ => {
-                        return Err(Error::QueryError(format!(
+                let key = if condition.operator == Operator::IsNull
+                    || matches!(condition.key, KeyType::Null)
+                {
+                    // Indices without null entries have nothing to match, so all of their
+                    // items are not null
+                    match <$key_type>::null() {
+                        Some(null) => null,
+                        None if condition.operator == Operator::Ne => {
+                            let items = self.find_range_with_reader(
+                                reader,
+                                Some(<$key_type>::min_value()),
+                                Some(<$key_type>::max_value()),
+                            )?;
+                            reader.seek(SeekFrom::Start(start_position))?;
+                            return Ok(items);
+                        }
+                        None => return Ok(Vec::new()),
+                    }
+                } else {
+                    <$key_type>::from_key_type(&condition.key).ok_or_else(|| {
+                        Error::QueryError(format!(
                             "key type mismatch: expected {}, got {:?}",
                             stringify!($key_type),
                             condition.key
-                        )))
-                    }
+                        ))
+                    })?
                 };
+
+                // NaN and null are not ordered against other values
+                if (key.is_nan() || key.is_null()) && condition.operator.is_range() {
+                    return Ok(Vec::new());
+                }
+
                 // Execute query based on operator
                 let items = match condition.operator {
-                    Operator::Eq => self.find_exact_with_reader(reader, key)?,
+                    Operator::Eq | Operator::IsNull => self.find_exact_with_reader(reader, key)?,
                     Operator::Ne => {
                         let all_items = self.find_range_with_reader(
                             reader,
@@ -188,6 +243,9 @@ macro_rules! impl_typed_stream_search_index {
                     }
                     Operator::Ge => self.find_range_with_reader(reader, Some(key), None)?,
                     Operator::Le => self.find_range_with_reader(reader, None, Some(key))?,
+                    Operator::Like => {
+                        self.find_like_with_reader(reader, &LikePattern::from_key(&key)?)?
+                    }
                 };
                 reader.seek(SeekFrom::Start(start_position))?;
                 Ok(items)
@@ -197,21 +255,36 @@ macro_rules! impl_typed_stream_search_index {
 }
 
 // Implement TypedStreamSearchIndex for all supported key types
-impl_typed_stream_search_index!(i8, KeyType::Int8);
-impl_typed_stream_search_index!(u8, KeyType::UInt8);
-impl_typed_stream_search_index!(i16, KeyType::Int16);
-impl_typed_stream_search_index!(u16, KeyType::UInt16);
-impl_typed_stream_search_index!(i32, KeyType::Int32);
-impl_typed_stream_search_index!(i64, KeyType::Int64);
-impl_typed_stream_search_index!(u32, KeyType::UInt32);
-impl_typed_stream_search_index!(u64, KeyType::UInt64);
-impl_typed_stream_search_index!(OrderedFloat<f32>, KeyType::Float32);
-impl_typed_stream_search_index!(OrderedFloat<f64>, KeyType::Float64);
-impl_typed_stream_search_index!(bool, KeyType::Bool);
-impl_typed_stream_search_index!(DateTime<Utc>, KeyType::DateTime);
-impl_typed_stream_search_index!(FixedStringKey<20>, KeyType::StringKey20);
-impl_typed_stream_search_index!(FixedStringKey<50>, KeyType::StringKey50);
-impl_typed_stream_search_index!(FixedStringKey<100>, KeyType::StringKey100);
+impl_typed_stream_search_index!(i8);
+impl_typed_stream_search_index!(u8);
+impl_typed_stream_search_index!(i16);
+impl_typed_stream_search_index!(u16);
+impl_typed_stream_search_index!(i32);
+impl_typed_stream_search_index!(i64);
+impl_typed_stream_search_index!(u32);
+impl_typed_stream_search_index!(u64);
+impl_typed_stream_search_index!(OrderedFloat<f32>);
+impl_typed_stream_search_index!(OrderedFloat<f64>);
+impl_typed_stream_search_index!(bool);
+impl_typed_stream_search_index!(DateTime<Utc>);
+impl_typed_stream_search_index!(FixedStringKey<20>);
+impl_typed_stream_search_index!(FixedStringKey<50>);
+impl_typed_stream_search_index!(FixedStringKey<100>);
+impl_typed_stream_search_index!(Nullable<i8>);
+impl_typed_stream_search_index!(Nullable<u8>);
+impl_typed_stream_search_index!(Nullable<i16>);
+impl_typed_stream_search_index!(Nullable<u16>);
+impl_typed_stream_search_index!(Nullable<i32>);
+impl_typed_stream_search_index!(Nullable<i64>);
+impl_typed_stream_search_index!(Nullable<u32>);
+impl_typed_stream_search_index!(Nullable<u64>);
+impl_typed_stream_search_index!(Nullable<OrderedFloat<f32>>);
+impl_typed_stream_search_index!(Nullable<OrderedFloat<f64>>);
+impl_typed_stream_search_index!(Nullable<bool>);
+impl_typed_stream_search_index!(Nullable<DateTime<Utc>>);
+impl_typed_stream_search_index!(Nullable<FixedStringKey<20>>);
+impl_typed_stream_search_index!(Nullable<FixedStringKey<50>>);
+impl_typed_stream_search_index!(Nullable<FixedStringKey<100>>);
 
 /// Container for multiple stream indices with different key types
 pub struct StreamMultiIndex {
@@ -229,11 +302,12 @@ impl StreamMultiIndex {
     }
 
     /// Generic method to add an index for any supported key type
-    pub fn add_index<K: Key + 'static>(&mut self, field: String, index: StreamIndex<K>)
+    pub fn add_index<K: Key + 'static>(&mut self, field: String, index: StreamIndex<K>, length: u64)
     where
         StreamIndex<K>: TypedStreamSearchIndex,
     {
-        self.indices.insert(field, Box::new(index));
+        self.indices.insert(field.clone(), Box::new(index));
+        self.add_index_offset(field, length);
     }
 
     fn add_index_offset(&mut self, field: String, length: u64) {
@@ -425,6 +499,44 @@ impl StreamMultiIndex {
         reader.seek(SeekFrom::Start(start_position))?;
         Ok(result_set)
     }
+
+    /// Count the offsets matching a query using a reader, without returning them
+    pub fn count_query(
+        &self,
+        reader: &mut dyn ReadSeek,
+        conditions: &[QueryCondition],
+    ) -> Result<usize> {
+        Ok(self.query(reader, conditions)?.len())
+    }
+
+    /// Execute a query using a reader and report how many candidates each condition's index
+    /// returned, see [`QueryPlan`]
+    pub fn explain(
+        &self,
+        reader: &mut dyn ReadSeek,
+        conditions: &[QueryCondition],
+    ) -> Result<QueryPlan> {
+        if conditions.is_empty() {
+            return Err(Error::QueryError("query cannot be empty".to_string()));
+        }
+
+        let start_position = reader.stream_position()?;
+        let mut plan = QueryPlan::default();
+        let mut result_set = None;
+        for cond in conditions {
+            let indexer = self.indices.get(&cond.field).ok_or_else(|| {
+                Error::QueryError(format!("no index found for field '{}'", cond.field))
+            })?;
+            let index_range = self.index_offsets.get(&cond.field).ok_or_else(|| {
+                Error::QueryError(format!("no index range found for field '{}'", cond.field))
+            })?;
+            reader.seek(SeekFrom::Start(start_position + index_range.start as u64))?;
+            let candidates = indexer.execute_query_condition(reader, cond)?;
+            plan.add_condition(cond, &indexer.key_type(), candidates, &mut result_set);
+        }
+        reader.seek(SeekFrom::Start(start_position))?;
+        Ok(plan)
+    }
 }
 
 impl Default for StreamMultiIndex {
@@ -10,7 +10,7 @@ use std::str::FromStr;
 
 use super::*;
 use crate::static_btree::entry::Entry;
-use crate::static_btree::key::{FixedStringKey, KeyType};
+use crate::static_btree::key::{FixedStringKey, Key, KeyType, Nullable, F64_NAN_SENTINEL};
 
 #[test]
 fn test_memory_index_with_complex_data() -> Result<()> {
@@ -299,6 +299,62 @@ fn test_cases() -> Vec<(Vec<QueryCondition>, Vec<u64>)> {
     ]
 }
 
+#[test]
+fn test_memory_multi_index_explain() -> Result<()> {
+    let multi_index = create_test_multi_index()?;
+
+    for (query, expected_results) in &test_cases() {
+        let plan = multi_index.explain(query)?;
+        assert_eq!(plan.conditions.len(), query.len());
+        for (i, (condition, stats)) in query.iter().zip(&plan.conditions).enumerate() {
+            assert_eq!(stats.field, condition.field);
+            assert_eq!(stats.operator, condition.operator);
+            let candidates = multi_index.query(std::slice::from_ref(condition))?;
+            assert_eq!(stats.candidates_before_intersection, candidates.len());
+            let matching = multi_index.query(&query[..=i])?;
+            assert_eq!(stats.candidates_after_intersection, matching.len());
+        }
+        assert_eq!(plan.num_results(), expected_results.len());
+    }
+
+    let plan = multi_index.explain(&test_cases()[1].0)?;
+    assert_eq!(plan.conditions[0].index_type, "FixedStringKey<20>");
+    assert_eq!(plan.conditions[1].index_type, "OrderedFloat<f32>");
+    Ok(())
+}
+
+#[test]
+fn test_default_explain() -> Result<()> {
+    // A multi-index only implementing `query`
+    struct QueryOnly(MemoryMultiIndex);
+    impl MultiIndex for QueryOnly {
+        fn query(&self, query: &[QueryCondition]) -> crate::static_btree::Result<Vec<u64>> {
+            self.0.query(query)
+        }
+    }
+
+    let multi_index = QueryOnly(create_test_multi_index()?);
+    for (query, expected_results) in &test_cases() {
+        let plan = multi_index.explain(query)?;
+        let expected = multi_index.0.explain(query)?;
+        assert_eq!(plan.conditions.len(), expected.conditions.len());
+        for (stats, expected) in plan.conditions.iter().zip(&expected.conditions) {
+            assert_eq!(stats.index_type, "unknown");
+            assert_eq!(
+                stats.candidates_before_intersection,
+                expected.candidates_before_intersection
+            );
+            assert_eq!(
+                stats.candidates_after_intersection,
+                expected.candidates_after_intersection
+            );
+        }
+        assert_eq!(plan.num_results(), expected_results.len());
+    }
+    assert!(multi_index.explain(&[]).is_err());
+    Ok(())
+}
+
 #[test]
 fn test_memory_stream_multi_index() -> Result<()> {
     // Simply test with multi_index
@@ -487,8 +543,105 @@ fn test_memory_stream_multi_index() -> Result<()> {
     for (query, expected_results) in &test_cases {
         let results = stream_multi_index.query(&mut index_buffer_for_stream, query)?;
         assert_eq!(results, *expected_results);
+        let count = stream_multi_index.count_query(&mut index_buffer_for_stream, query)?;
+        assert_eq!(count, results.len());
+        assert_eq!(multi_index.count_query(query)?, results.len());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_nan_keys_are_excluded_from_range_queries() -> Result<()> {
+    let mut entries = (0..20)
+        .map(|i| Entry::new(OrderedFloat(i as f64), i as u64))
+        .collect::<Vec<_>>();
+    // NaN with different payloads and signs
+    entries.push(Entry::new(OrderedFloat(f64::NAN), 100));
+    entries.push(Entry::new(OrderedFloat(-f64::NAN), 101));
+    entries.push(Entry::new(
+        OrderedFloat(f64::from_bits(0x7ff8_0000_0000_0001)),
+        102,
+    ));
+    let index = MemoryIndex::build(&entries, 4)?;
+
+    // Serialize, all NaN keys share the sentinel bit pattern
+    let mut buffer = Cursor::new(Vec::new());
+    index.serialize(&mut buffer)?;
+    let nan_bytes = F64_NAN_SENTINEL.to_le_bytes();
+    let mut sentinel = Vec::new();
+    OrderedFloat(-f64::NAN).write_to(&mut sentinel)?;
+    assert_eq!(sentinel, nan_bytes);
+
+    let num_items = index.num_items();
+    let branching_factor = index.branching_factor();
+    let payload_size = index.payload_size();
+    buffer.set_position(0);
+    let memory_index = MemoryIndex::<OrderedFloat<f64>>::from_buf(
+        &mut buffer.clone(),
+        num_items,
+        branching_factor,
+    )?;
+    let mut memory_multi_index = MemoryMultiIndex::new();
+    memory_multi_index.add_f64_index("height".to_string(), memory_index);
+
+    let stream_index = StreamIndex::<OrderedFloat<f64>>::new(
+        num_items,
+        branching_factor,
+        0,
+        Stree::<OrderedFloat<f64>>::index_size(num_items, branching_factor, payload_size) as u64,
+    );
+    let length = stream_index.length();
+    let mut stream_multi_index = StreamMultiIndex::new();
+    stream_multi_index.add_f64_index("height".to_string(), stream_index, length);
+
+    let condition = |operator: Operator, value: f64| {
+        vec![QueryCondition {
+            field: "height".to_string(),
+            operator,
+            key: KeyType::Float64(OrderedFloat(value)),
+        }]
+    };
+    let nan_offsets = [100_u64, 101, 102];
+
+    for operator in [Operator::Gt, Operator::Ge, Operator::Lt, Operator::Le] {
+        // Range queries over a finite value never match NaN entries
+        let mut memory_results = memory_multi_index.query(&condition(operator, 10.0))?;
+        let mut stream_results =
+            stream_multi_index.query(&mut buffer, &condition(operator, 10.0))?;
+        memory_results.sort();
+        stream_results.sort();
+        assert!(!memory_results.is_empty());
+        assert!(memory_results.iter().all(|o| !nan_offsets.contains(o)));
+        assert_eq!(memory_results, stream_results);
+
+        // Range queries with NaN as bound match nothing
+        assert!(memory_multi_index
+            .query(&condition(operator, f64::NAN))?
+            .is_empty());
+        assert!(stream_multi_index
+            .query(&mut buffer, &condition(operator, f64::NAN))?
+            .is_empty());
     }
 
+    // Finite values are still found after the NaN entries
+    assert_eq!(
+        memory_multi_index.query(&condition(Operator::Eq, 19.0))?,
+        vec![19]
+    );
+    assert_eq!(
+        stream_multi_index.query(&mut buffer, &condition(Operator::Eq, 19.0))?,
+        vec![19]
+    );
+
+    // NaN only equals NaN
+    let mut results = memory_multi_index.query(&condition(Operator::Eq, f64::NAN))?;
+    results.sort();
+    assert_eq!(results, nan_offsets);
+    let mut results = stream_multi_index.query(&mut buffer, &condition(Operator::Eq, f64::NAN))?;
+    results.sort();
+    assert_eq!(results, nan_offsets);
+
     Ok(())
 }
 
@@ -615,8 +768,393 @@ mod http_tests {
             let mut sorted_results = results.clone();
             sorted_results.sort_by_key(|item| item.range.start());
             assert_eq!(sorted_results, offset_adjusted_expected_results);
+            let count = multi_index.count_query(&mut client, query).await?;
+            assert_eq!(count, results.len());
         }
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_http_multi_index_payload_cache() -> Result<()> {
+        // duplicate keys are resolved through the payload section
+        let entries = (0..20_i64)
+            .flat_map(|i| [Entry::new(i, i as u64), Entry::new(i, 100 + i as u64)])
+            .collect::<Vec<_>>();
+        let index = MemoryIndex::build(&entries, 4)?;
+        let mut index_buffer = Vec::new();
+        index.serialize(&mut index_buffer)?;
+        let feature_begin = index_buffer.len();
+
+        let stats = std::sync::Arc::new(std::sync::RwLock::new(
+            crate::static_btree::mocked_http_range_client::RequestStats::new(),
+        ));
+        let client = MockHttpRangeClient::new_with_bytes(
+            "in-memory",
+            Bytes::from(index_buffer),
+            stats.clone(),
+        );
+        let mut client = http_range_client::AsyncBufferedHttpRangeClient::with(client, "in-memory");
+        client.set_min_req_size(0);
+        let request_count = || stats.read().unwrap().request_count;
+
+        let mut multi_index = HttpMultiIndex::new();
+        multi_index.add_index(
+            "id".to_string(),
+            HttpIndex::<i64>::new(
+                index.num_items(),
+                index.branching_factor(),
+                0,
+                feature_begin,
+                1024,
+            ),
+        );
+        let query = vec![QueryCondition {
+            field: "id".to_string(),
+            operator: Operator::Eq,
+            key: KeyType::Int64(7),
+        }];
+        let expected = vec![
+            HttpSearchResultItem {
+                range: HttpRange::RangeFrom(feature_begin + 7..),
+            },
+            HttpSearchResultItem {
+                range: HttpRange::RangeFrom(feature_begin + 107..),
+            },
+        ];
+
+        let mut results = multi_index.query(&mut client, &query).await?;
+        results.sort_by_key(|item| item.range.start());
+        assert_eq!(results, expected);
+        let first_requests = request_count();
+        assert_eq!(multi_index.cache_stats().hits, 1);
+
+        // the payload chunk prefetched by the first query is reused by the second
+        let mut results = multi_index.query(&mut client, &query).await?;
+        results.sort_by_key(|item| item.range.start());
+        assert_eq!(results, expected);
+        assert_eq!(request_count() - first_requests, first_requests - 1);
+        let cache_stats = multi_index.cache_stats();
+        assert_eq!(cache_stats.hits, 2);
+        assert_eq!(cache_stats.misses, 0);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_null_keys_match_is_null_only() -> Result<()> {
+    let mut entries = (0..20)
+        .map(|i| Entry::new(Nullable(Some(i as i32)), i as u64))
+        .collect::<Vec<_>>();
+    // Features without a value
+    entries.push(Entry::new(Nullable(None), 100));
+    entries.push(Entry::new(Nullable(None), 101));
+    let index = MemoryIndex::build(&entries, 4)?;
+
+    let mut buffer = Cursor::new(Vec::new());
+    index.serialize(&mut buffer)?;
+    let num_items = index.num_items();
+    let branching_factor = index.branching_factor();
+    let payload_size = index.payload_size();
+    buffer.set_position(0);
+    let memory_index =
+        MemoryIndex::<Nullable<i32>>::from_buf(&mut buffer.clone(), num_items, branching_factor)?;
+    let mut memory_multi_index = MemoryMultiIndex::new();
+    memory_multi_index.add_index("floors".to_string(), memory_index);
+
+    let stream_index = StreamIndex::<Nullable<i32>>::new(
+        num_items,
+        branching_factor,
+        0,
+        Stree::<Nullable<i32>>::index_size(num_items, branching_factor, payload_size) as u64,
+    );
+    let length = stream_index.length();
+    let mut stream_multi_index = StreamMultiIndex::new();
+    stream_multi_index.add_index("floors".to_string(), stream_index, length);
+
+    let condition = |operator: Operator, key: KeyType| {
+        vec![QueryCondition {
+            field: "floors".to_string(),
+            operator,
+            key,
+        }]
+    };
+    let null_offsets = [100_u64, 101];
+
+    let is_null = condition(Operator::IsNull, KeyType::Null);
+    let mut memory_results = memory_multi_index.query(&is_null)?;
+    let mut stream_results = stream_multi_index.query(&mut buffer, &is_null)?;
+    memory_results.sort();
+    stream_results.sort();
+    assert_eq!(memory_results, null_offsets);
+    assert_eq!(stream_results, null_offsets);
+
+    for operator in [
+        Operator::Gt,
+        Operator::Ge,
+        Operator::Lt,
+        Operator::Le,
+        Operator::Ne,
+    ] {
+        // Comparisons with a value never match null entries
+        let mut memory_results =
+            memory_multi_index.query(&condition(operator, KeyType::Int32(10)))?;
+        let mut stream_results =
+            stream_multi_index.query(&mut buffer, &condition(operator, KeyType::Int32(10)))?;
+        memory_results.sort();
+        stream_results.sort();
+        assert!(!memory_results.is_empty());
+        assert!(memory_results.iter().all(|o| !null_offsets.contains(o)));
+        assert_eq!(memory_results, stream_results);
+    }
+
+    // Null is not ordered, so a range query with a null key has no matches
+    assert!(memory_multi_index
+        .query(&condition(Operator::Ge, KeyType::Null))?
+        .is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_like_queries() -> Result<()> {
+    let ids = [
+        "NL.IMBAG.Pand.0503100000000010",
+        "NL.IMBAG.Pand.0503100000000011",
+        "NL.IMBAG.Pand.0503100000000011",
+        "NL.IMBAG.Pand.0518100000000020",
+        "NL.IMBAG.Verblijfsobject.01",
+        "NL.IMBAG.Verblijfsobject.02",
+        "NL.TOP10NL.Gebouw.01",
+        "NL.TOP10NL.Gebouw.02",
+        "BE.GRB.Gebouw.01",
+        "DE.ALKIS.Gebaeude.01",
+    ];
+    let entries = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| Entry::new(FixedStringKey::<50>::from_str(id), i as u64))
+        .collect::<Vec<_>>();
+    let index = MemoryIndex::build(&entries, 4)?;
+
+    let mut buffer = Cursor::new(Vec::new());
+    index.serialize(&mut buffer)?;
+    let stream_index = StreamIndex::<FixedStringKey<50>>::new(
+        index.num_items(),
+        index.branching_factor(),
+        0,
+        Stree::<FixedStringKey<50>>::index_size(
+            index.num_items(),
+            index.branching_factor(),
+            index.payload_size(),
+        ) as u64,
+    );
+    let length = stream_index.length();
+    let mut memory_multi_index = MemoryMultiIndex::new();
+    memory_multi_index.add_index("identificatie".to_string(), index);
+    let mut stream_multi_index = StreamMultiIndex::new();
+    stream_multi_index.add_index("identificatie".to_string(), stream_index, length);
+
+    for (pattern, expected) in [
+        // trailing wildcard, narrowed to the keys with the prefix
+        ("NL.IMBAG*", vec![0, 1, 2, 3, 4, 5]),
+        ("NL.IMBAG.Pand.0503*", vec![0, 1, 2]),
+        // leading wildcard, which has to scan the whole index
+        ("*.01", vec![4, 6, 8, 9]),
+        ("*Gebouw*", vec![6, 7, 8]),
+        // interior wildcards
+        ("NL.*.Gebouw.0?", vec![6, 7]),
+        ("NL.IMBAG.Pand.05?31*1", vec![1, 2]),
+        ("NL*Verblijfsobject*2", vec![5]),
+        // no wildcard behaves like equality
+        ("BE.GRB.Gebouw.01", vec![8]),
+        ("FR*", vec![]),
+    ] {
+        let condition = vec![QueryCondition {
+            field: "identificatie".to_string(),
+            operator: Operator::Like,
+            key: KeyType::StringKey50(FixedStringKey::<50>::from_str(pattern)),
+        }];
+        let mut memory_results = memory_multi_index.query(&condition)?;
+        let mut stream_results = stream_multi_index.query(&mut buffer, &condition)?;
+        memory_results.sort();
+        stream_results.sort();
+        assert_eq!(memory_results, expected, "{pattern}");
+        assert_eq!(stream_results, expected, "{pattern}");
+    }
+
+    // the ids are cut off after 20 bytes, so only patterns decided by their start can be matched
+    let entries = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| Entry::new(FixedStringKey::<20>::from_str(id), i as u64))
+        .collect::<Vec<_>>();
+    let truncated_index = MemoryIndex::build(&entries, 4)?;
+    let mut results = truncated_index.find_like(&LikePattern::new("NL.IMBAG.Pand.*"))?;
+    results.sort();
+    assert_eq!(results, vec![0, 1, 2, 3]);
+    assert!(truncated_index
+        .find_like(&LikePattern::new("*0010"))
+        .is_err());
+
+    // LIKE is only defined on string indices
+    let id_index = create_id_index(4)?;
+    let condition = QueryCondition {
+        field: "id".to_string(),
+        operator: Operator::Like,
+        key: KeyType::Int64(1),
+    };
+    assert!(id_index.execute_query_condition(&condition).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_shared_memory_multi_index() -> Result<()> {
+    let mut multi_index = MemoryMultiIndex::new();
+    multi_index.add_i64_index("id".to_string(), create_id_index(4)?);
+    multi_index.add_string_index20("name".to_string(), create_name_index(4)?);
+    multi_index.add_f32_index("score".to_string(), create_score_index(4)?);
+    let shared = multi_index.into_shared();
+
+    // queries on the fields added so far, from several threads at once
+    let test_cases = test_cases()
+        .into_iter()
+        .filter(|(query, _)| query.iter().all(|c| c.field != "datetime"))
+        .collect::<Vec<_>>();
+    assert!(!test_cases.is_empty());
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            let shared = shared.clone();
+            let test_cases = &test_cases;
+            scope.spawn(move || {
+                for (query, expected_results) in test_cases {
+                    assert_eq!(shared.query(query).unwrap(), *expected_results);
+                }
+            });
+        }
+    });
+
+    // indices added through one clone are visible to the others
+    let other = shared.clone();
+    shared
+        .write()?
+        .add_datetime_index("datetime".to_string(), create_datetime_index(4)?);
+    for (query, expected_results) in &test_cases() {
+        assert_eq!(other.query(query)?, *expected_results);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_multi_index_query_cache() -> Result<()> {
+    let mut multi_index = MemoryMultiIndex::new().with_cache(16);
+    multi_index.add_i64_index("id".to_string(), create_id_index(4)?);
+    multi_index.add_string_index20("name".to_string(), create_name_index(4)?);
+    multi_index.add_f32_index("score".to_string(), create_score_index(4)?);
+    multi_index.add_datetime_index("datetime".to_string(), create_datetime_index(4)?);
+
+    let test_cases = test_cases();
+    let num_queries = test_cases.len() as u64;
+    for _ in 0..3 {
+        for (query, expected_results) in &test_cases {
+            assert_eq!(multi_index.query(query)?, *expected_results);
+        }
+    }
+    let stats = multi_index.cache_stats().expect("cache is enabled");
+    assert_eq!(stats.misses, num_queries);
+    assert_eq!(stats.hits, 2 * num_queries);
+    assert!((stats.hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+
+    // replacing an index drops the cached results computed with the old one
+    let query = vec![QueryCondition {
+        field: "id".to_string(),
+        operator: Operator::Eq,
+        key: KeyType::Int64(3),
+    }];
+    assert_eq!(multi_index.query(&query)?, vec![3]);
+    let shifted = MemoryIndex::<i64>::build(&[Entry::new(3_i64, 103)], 4)?;
+    multi_index.add_i64_index("id".to_string(), shifted);
+    assert_eq!(multi_index.query(&query)?, vec![103]);
+    let stats = multi_index.cache_stats().expect("cache is enabled");
+    assert_eq!(stats.misses, num_queries + 2);
+
+    // the least recently used query is evicted once the cache is full
+    let mut small = MemoryMultiIndex::new().with_cache(1);
+    small.add_i64_index("id".to_string(), create_id_index(4)?);
+    let other = vec![QueryCondition {
+        field: "id".to_string(),
+        operator: Operator::Eq,
+        key: KeyType::Int64(4),
+    }];
+    small.query(&query)?;
+    small.query(&other)?;
+    small.query(&query)?;
+    assert_eq!(small.cache_stats().expect("cache is enabled").hits, 0);
+    assert!(MemoryMultiIndex::new().cache_stats().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_condition_constructors() -> Result<()> {
+    let mut entries = (0..20)
+        .map(|i| Entry::new(Nullable(Some(i as i32)), i as u64))
+        .collect::<Vec<_>>();
+    entries.push(Entry::new(Nullable(None), 100));
+    let floors = MemoryIndex::build(&entries, 4)?;
+    let mut buffer = Cursor::new(Vec::new());
+    floors.serialize(&mut buffer)?;
+    let stream_index = StreamIndex::<Nullable<i32>>::new(
+        floors.num_items(),
+        floors.branching_factor(),
+        0,
+        Stree::<Nullable<i32>>::index_size(
+            floors.num_items(),
+            floors.branching_factor(),
+            floors.payload_size(),
+        ) as u64,
+    );
+    let length = stream_index.length();
+    let mut stream_multi_index = StreamMultiIndex::new();
+    stream_multi_index.add_index("floors".to_string(), stream_index, length);
+
+    let mut multi_index = MemoryMultiIndex::new();
+    multi_index.add_index("floors".to_string(), floors);
+    multi_index.add_i64_index("id".to_string(), create_id_index(4)?);
+
+    let mut query = Query::new();
+    query.add_between("floors", 5, 12);
+    query.add_not_in("floors", [7, 9]);
+    assert_eq!(
+        multi_index.query(&query.conditions)?,
+        vec![5, 6, 8, 10, 11, 12]
+    );
+
+    let mut query = Query::new();
+    query.add_is_null("floors");
+    assert_eq!(multi_index.query(&query.conditions)?, vec![100]);
+    assert_eq!(
+        stream_multi_index.query(&mut buffer, &query.conditions)?,
+        vec![100]
+    );
+
+    let mut query = Query::new();
+    query.add_not_null("floors");
+    let mut memory_results = multi_index.query(&query.conditions)?;
+    let mut stream_results = stream_multi_index.query(&mut buffer, &query.conditions)?;
+    memory_results.sort();
+    stream_results.sort();
+    assert_eq!(memory_results, (0..20).collect::<Vec<u64>>());
+    assert_eq!(stream_results, memory_results);
+
+    // without null entries every item has a value
+    let mut results = multi_index.query(&[conditions::not_null("id")])?;
+    results.sort();
+    assert_eq!(results, (0..19).collect::<Vec<u64>>());
+    assert!(multi_index.query(&[conditions::is_null("id")])?.is_empty());
+
+    let (lower, upper) = conditions::between("id", 3_i64, 5_i64);
+    assert_eq!(multi_index.query(&[lower, upper])?, vec![3, 4, 5]);
+    Ok(())
 }
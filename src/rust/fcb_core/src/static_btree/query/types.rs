@@ -1,9 +1,11 @@
-use crate::static_btree::error::Result;
+use crate::static_btree::error::{Error, Result};
 use crate::static_btree::key::Key;
 use crate::static_btree::key::KeyType;
 
+use super::conditions;
+
 /// Comparison operators for queries
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Operator {
     /// Equal
     Eq,
@@ -17,6 +19,30 @@ pub enum Operator {
     Ge,
     /// Less than or equal
     Le,
+    /// Matches features without a value, only indices with null entries have any
+    IsNull,
+    /// Matches string keys against a glob pattern, see [`LikePattern`](super::LikePattern)
+    Like,
+}
+
+/// Logical connector between query conditions.
+///
+/// `And` binds tighter than `Or`, so `a AND b OR c` reads as `(a AND b) OR c`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogicalOp {
+    #[default]
+    And,
+    Or,
+}
+
+impl Operator {
+    /// Whether the operator compares by order (`Gt`, `Lt`, `Ge`, `Le`)
+    pub fn is_range(&self) -> bool {
+        matches!(
+            self,
+            Operator::Gt | Operator::Lt | Operator::Ge | Operator::Le
+        )
+    }
 }
 
 /// A query condition with an enum key type
@@ -51,6 +77,31 @@ impl Query {
         });
     }
 
+    /// Add conditions matching values of `field` in `[lo, hi]`
+    pub fn add_between(&mut self, field: &str, lo: impl Into<KeyType>, hi: impl Into<KeyType>) {
+        let (lower, upper) = conditions::between(field, lo, hi);
+        self.conditions.extend([lower, upper]);
+    }
+
+    /// Add conditions matching values of `field` other than `values`
+    pub fn add_not_in<V: Into<KeyType>>(
+        &mut self,
+        field: &str,
+        values: impl IntoIterator<Item = V>,
+    ) {
+        self.conditions.extend(conditions::not_in(field, values));
+    }
+
+    /// Add a condition matching features without a value for `field`
+    pub fn add_is_null(&mut self, field: &str) {
+        self.conditions.push(conditions::is_null(field));
+    }
+
+    /// Add a condition matching features with a value for `field`
+    pub fn add_not_null(&mut self, field: &str) {
+        self.conditions.push(conditions::not_null(field));
+    }
+
     /// Create a query with a single condition
     pub fn with_condition(field: String, operator: Operator, key: KeyType) -> Self {
         let mut query = Self::new();
@@ -78,4 +129,105 @@ pub trait SearchIndex<K: Key> {
 pub trait MultiIndex {
     /// Execute a query and return matching offsets
     fn query(&self, query: &[QueryCondition]) -> Result<Vec<u64>>;
+
+    /// Count the offsets matching a query, without returning them
+    fn count_query(&self, query: &[QueryCondition]) -> Result<usize> {
+        Ok(self.query(query)?.len())
+    }
+
+    /// Execute a query and report how many candidates each condition's index returned
+    ///
+    /// The default implementation queries the conditions one at a time and reports an
+    /// `unknown` index type.
+    fn explain(&self, query: &[QueryCondition]) -> Result<QueryPlan> {
+        if query.is_empty() {
+            return Err(Error::QueryError("query cannot be empty".to_string()));
+        }
+
+        let mut plan = QueryPlan::default();
+        let mut result_set = None;
+        for condition in query {
+            let candidates = self.query(std::slice::from_ref(condition))?;
+            plan.add_condition(condition, "unknown", candidates, &mut result_set);
+        }
+        Ok(plan)
+    }
+}
+
+/// Statistics of one condition of an explained query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionPlan {
+    pub field: String,
+    pub operator: Operator,
+    /// Key type of the index consulted for the field, e.g. `Nullable<i32>`
+    pub index_type: String,
+    /// Number of offsets matched by this condition alone
+    pub candidates_before_intersection: usize,
+    /// Number of offsets matching this and all previous conditions
+    pub candidates_after_intersection: usize,
+}
+
+/// Per-condition statistics of a query, in the order the conditions are evaluated
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryPlan {
+    pub conditions: Vec<ConditionPlan>,
+}
+
+impl QueryPlan {
+    /// Records the candidates of `condition` and intersects them into `result_set`, which holds
+    /// the offsets matching the previous conditions, `None` before the first one.
+    ///
+    /// Unlike a query, an explanation does not stop at an empty intersection so every
+    /// condition is reported.
+    pub(crate) fn add_condition<T: PartialEq>(
+        &mut self,
+        condition: &QueryCondition,
+        index_type: &str,
+        candidates: Vec<T>,
+        result_set: &mut Option<Vec<T>>,
+    ) {
+        let candidates_before_intersection = candidates.len();
+        let result = match result_set.take() {
+            Some(mut result) => {
+                result.retain(|offset| candidates.contains(offset));
+                result
+            }
+            None => candidates,
+        };
+        self.conditions.push(ConditionPlan {
+            field: condition.field.clone(),
+            operator: condition.operator,
+            index_type: index_type.to_string(),
+            candidates_before_intersection,
+            candidates_after_intersection: result.len(),
+        });
+        *result_set = Some(result);
+    }
+
+    /// Number of offsets matching all conditions
+    pub fn num_results(&self) -> usize {
+        self.conditions
+            .last()
+            .map_or(0, |c| c.candidates_after_intersection)
+    }
+}
+
+/// Name of a key type without module paths, e.g. `Nullable<FixedStringKey<50>>`
+pub(crate) fn key_type_name<K>() -> String {
+    let name = std::any::type_name::<K>();
+    let mut short = String::with_capacity(name.len());
+    let mut segment_start = 0;
+    for (i, c) in name.char_indices() {
+        if matches!(c, '<' | '>' | ',' | ' ') {
+            short.push_str(last_path_segment(&name[segment_start..i]));
+            short.push(c);
+            segment_start = i + 1;
+        }
+    }
+    short.push_str(last_path_segment(&name[segment_start..]));
+    short
+}
+
+fn last_path_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
 }
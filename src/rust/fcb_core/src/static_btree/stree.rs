@@ -1,5 +1,5 @@
 use crate::static_btree::entry::{Entry, Offset};
-use crate::static_btree::error::{Error, Result};
+use crate::static_btree::error::{Error, IntegrityError, Result};
 use crate::static_btree::key::Key;
 use crate::static_btree::payload::PayloadEntry;
 #[cfg(feature = "http")]
@@ -99,6 +99,69 @@ async fn read_http_node_items<K: Key, T: AsyncHttpRangeClient>(
     Ok(node_items)
 }
 
+/// Upper bound on the size of a single adaptive prefetch request
+#[cfg(feature = "http")]
+const ADAPTIVE_PREFETCH_MAX_SIZE: usize = 4 * 1024 * 1024;
+
+/// Node items fetched ahead of an HTTP traversal
+///
+/// Levels are stored top-down and each one is `branching_factor` times larger than the one
+/// above it. With adaptive prefetch, reading nodes of a level also fetches the levels below
+/// them in the same request, as deep as [`ADAPTIVE_PREFETCH_MAX_SIZE`] allows, so the next
+/// steps of the traversal are served without a request per level.
+#[cfg(feature = "http")]
+struct HttpNodeCache<K: Key> {
+    adaptive_prefetch: bool,
+    nodes: Range<usize>,
+    items: Vec<NodeItem<K>>,
+}
+
+#[cfg(feature = "http")]
+impl<K: Key> HttpNodeCache<K> {
+    fn new(adaptive_prefetch: bool) -> Self {
+        Self {
+            adaptive_prefetch,
+            nodes: 0..0,
+            items: Vec::new(),
+        }
+    }
+
+    /// Reads the items of `node_ids`, which are nodes of `level`
+    async fn read<T: AsyncHttpRangeClient>(
+        &mut self,
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        base: usize,
+        node_ids: &Range<usize>,
+        level: usize,
+        level_bounds: &[Range<usize>],
+    ) -> Result<Vec<NodeItem<K>>> {
+        if node_ids.start >= self.nodes.start && node_ids.end <= self.nodes.end {
+            let start = node_ids.start - self.nodes.start;
+            return Ok(self.items[start..start + node_ids.len()].to_vec());
+        }
+        if !self.adaptive_prefetch {
+            return read_http_node_items(client, base, node_ids).await;
+        }
+
+        let prefetch_end = (0..level)
+            .rev()
+            .map(|lower_level| level_bounds[lower_level].end)
+            .take_while(|end| {
+                (end - node_ids.start) * NodeItem::<K>::SERIALIZED_SIZE
+                    <= ADAPTIVE_PREFETCH_MAX_SIZE
+            })
+            .last();
+        let Some(prefetch_end) = prefetch_end else {
+            return read_http_node_items(client, base, node_ids).await;
+        };
+        let nodes = node_ids.start..prefetch_end;
+        debug!("prefetching nodes {nodes:?} along with level {level}");
+        self.items = read_http_node_items(client, base, &nodes).await?;
+        self.nodes = nodes;
+        Ok(self.items[..node_ids.len()].to_vec())
+    }
+}
+
 #[cfg(feature = "http")]
 async fn read_http_payload_data<T: AsyncHttpRangeClient>(
     client: &mut AsyncBufferedHttpRangeClient<T>,
@@ -117,49 +180,94 @@ async fn read_http_payload_data<T: AsyncHttpRangeClient>(
     Ok(payload_entry)
 }
 
-/// Cache for prefetched payload data to reduce HTTP requests
-#[derive(Debug, Default)]
+/// Hit, miss and eviction counters of a [`PayloadCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// Cache of payload chunks to reduce HTTP requests. Once `capacity` chunks are cached, the
+/// least recently used one is evicted to make room for a new one.
+#[derive(Debug)]
 pub struct PayloadCache {
-    /// Raw bytes of the prefetched payload section
-    data: Vec<u8>,
-    /// Start offset of the cached data
-    start_offset: usize,
-    /// End offset of the cached data (exclusive)
-    end_offset: usize,
+    /// Start offset and raw bytes of the cached chunks, the most recently used last
+    chunks: VecDeque<(usize, Vec<u8>)>,
+    /// Maximum number of cached chunks
+    capacity: usize,
+    stats: CacheStats,
+}
+
+impl Default for PayloadCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PayloadCache {
+    /// Default number of cached chunks
+    pub const DEFAULT_CAPACITY: usize = 4;
+
     /// Create a new empty payload cache
     pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Create a new empty payload cache holding at most `capacity` chunks (at least 1)
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            data: Vec::new(),
-            start_offset: 0,
-            end_offset: 0,
+            chunks: VecDeque::new(),
+            capacity: capacity.max(1),
+            stats: CacheStats::default(),
         }
     }
 
+    fn chunk_index(&self, offset: usize) -> Option<usize> {
+        self.chunks
+            .iter()
+            .rposition(|(start, data)| offset >= *start && offset < start + data.len())
+    }
+
     /// Check if the given offset is in the cache
     pub fn contains(&self, offset: usize) -> bool {
-        !self.data.is_empty() && offset >= self.start_offset && offset < self.end_offset
+        self.chunk_index(offset).is_some()
     }
 
     /// Get payload entry from the cache at the given offset
-    pub fn get_entry(&self, offset: usize) -> Result<PayloadEntry> {
-        if !self.contains(offset) {
+    pub fn get_entry(&mut self, offset: usize) -> Result<PayloadEntry> {
+        let Some(index) = self.chunk_index(offset) else {
+            self.stats.misses += 1;
             return Err(Error::PayloadOffsetNotInCache);
-        }
-
-        let relative_offset = offset - self.start_offset;
-        let mut cursor = Cursor::new(&self.data[relative_offset..]);
+        };
+        self.stats.hits += 1;
+
+        let chunk = self
+            .chunks
+            .remove(index)
+            .expect("chunk_index returns an index within the chunks");
+        self.chunks.push_back(chunk);
+        let (start, data) = self.chunks.back().expect("chunk was just pushed");
+        let mut cursor = Cursor::new(&data[offset - start..]);
         let (entry, _) = PayloadEntry::deserialize(&mut cursor)?;
         Ok(entry)
     }
 
-    /// Update cache with new data
+    /// Add a chunk of data starting at `start_offset`, evicting the least recently used chunk
+    /// if the cache is full
     pub fn update(&mut self, start_offset: usize, data: Vec<u8>) {
-        self.data = data;
-        self.start_offset = start_offset;
-        self.end_offset = start_offset + self.data.len();
+        if data.is_empty() {
+            return;
+        }
+        if self.chunks.len() >= self.capacity {
+            self.chunks.pop_front();
+            self.stats.evictions += 1;
+        }
+        self.chunks.push_back((start_offset, data));
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.stats
     }
 }
 
@@ -179,20 +287,30 @@ pub async fn prefetch_payload<T: AsyncHttpRangeClient>(
     payload_section_start: usize,
     chunk_size: usize,
 ) -> Result<PayloadCache> {
+    let mut cache = PayloadCache::new();
+    prefetch_payload_into(client, &mut cache, payload_section_start, chunk_size).await?;
+    Ok(cache)
+}
+
+/// Like [`prefetch_payload`], but into an existing cache. Nothing is fetched if the cache
+/// already holds the start of the chunk.
+#[cfg(feature = "http")]
+async fn prefetch_payload_into<T: AsyncHttpRangeClient>(
+    client: &mut AsyncBufferedHttpRangeClient<T>,
+    cache: &mut PayloadCache,
+    payload_section_start: usize,
+    chunk_size: usize,
+) -> Result<()> {
+    if cache.contains(payload_section_start) {
+        return Ok(());
+    }
     debug!(
         "prefetching payload chunk: start={}, size={}",
         payload_section_start, chunk_size
     );
-
-    let mut cache = PayloadCache::new();
-
-    // Fetch the chunk of payload data
     let payload_data = client.get_range(payload_section_start, chunk_size).await?;
-
-    // Store the fetched data in the cache
     cache.update(payload_section_start, payload_data.to_vec());
-
-    Ok(cache)
+    Ok(())
 }
 
 /// Read a payload entry from the payload cache if available, otherwise fetch it from HTTP
@@ -200,12 +318,12 @@ pub async fn prefetch_payload<T: AsyncHttpRangeClient>(
 async fn read_payload_entry<T: AsyncHttpRangeClient>(
     client: &mut AsyncBufferedHttpRangeClient<T>,
     offset: usize,
-    cache: Option<&PayloadCache>,
+    cache: Option<&mut PayloadCache>,
 ) -> Result<PayloadEntry> {
     // Check if the offset is in the cache
     if let Some(cache) = cache {
-        if cache.contains(offset) {
-            return cache.get_entry(offset);
+        if let Ok(entry) = cache.get_entry(offset) {
+            return Ok(entry);
         }
     }
 
@@ -229,7 +347,7 @@ async fn batch_resolve_payloads<T: AsyncHttpRangeClient>(
     payload_refs: Vec<PayloadRef>,
     payload_section_start: usize,
     feature_begin: usize,
-    cache: Option<&PayloadCache>,
+    mut cache: Option<&mut PayloadCache>,
 ) -> Result<Vec<HttpSearchResultItem>> {
     debug!("batch resolving {} payload references", payload_refs.len());
 
@@ -255,33 +373,18 @@ async fn batch_resolve_payloads<T: AsyncHttpRangeClient>(
             PayloadRef::Indirect(rel_offset) => {
                 let abs_offset = payload_section_start + rel_offset;
 
-                // Check if the payload entry is in the cache
-                if let Some(cache) = cache {
-                    if cache.contains(abs_offset) {
-                        // If it's in the cache, resolve it immediately
-                        match cache.get_entry(abs_offset) {
-                            Ok(entry) => {
-                                for offset in entry.offsets {
-                                    let start = feature_begin + offset as usize;
-                                    results.push(HttpSearchResultItem {
-                                        range: HttpRange::RangeFrom(start..),
-                                    });
-                                }
-                                continue;
-                            }
-                            Err(_) => {
-                                // Cache lookup failed, fall back to fetching
-                                payload_offsets_to_fetch.push(abs_offset);
-                            }
-                        }
-                    } else {
-                        // Not in cache, need to fetch
-                        payload_offsets_to_fetch.push(abs_offset);
+                // If the payload entry is in the cache, resolve it immediately
+                if let Some(Ok(entry)) = cache.as_mut().map(|cache| cache.get_entry(abs_offset)) {
+                    for offset in entry.offsets {
+                        let start = feature_begin + offset as usize;
+                        results.push(HttpSearchResultItem {
+                            range: HttpRange::RangeFrom(start..),
+                        });
                     }
-                } else {
-                    // No cache, need to fetch
-                    payload_offsets_to_fetch.push(abs_offset);
+                    continue;
                 }
+                // Not in cache or the lookup failed, need to fetch
+                payload_offsets_to_fetch.push(abs_offset);
             }
         }
     }
@@ -332,6 +435,10 @@ async fn batch_resolve_payloads<T: AsyncHttpRangeClient>(
         let fetch_size = (end - start) + DEFAULT_MIN_REQ_SIZE;
 
         let payload_data = client.get_range(start, fetch_size).await?;
+        // keep the chunk for the entries of later queries
+        if let Some(cache) = cache.as_mut() {
+            cache.update(start, payload_data.to_vec());
+        }
 
         // Process each requested offset within this range
         for &offset in payload_offsets_to_fetch
@@ -495,7 +602,9 @@ impl<K: Key> Stree<K> {
 
     fn generate_nodes(&mut self) -> Result<()> {
         let node_size = self.branching_factor as usize - 1;
-        let mut parent_min_key = HashMap::<usize, K>::new(); // key is the parent node's index, value is the minimum key of the right children node's leaf node
+        // index is the parent node's index, value is the minimum key of the right children node's leaf node.
+        // Only internal nodes are parents and they are stored before the leaves, so a Vec avoids hashing.
+        let mut parent_min_key: Vec<Option<K>> = vec![None; self.num_nodes() - self.num_leaf_nodes];
         for level in 0..self.level_bounds.len() - 1 {
             let children_level = &self.level_bounds[level];
             let parent_level = &self.level_bounds[level + 1];
@@ -535,11 +644,12 @@ impl<K: Key> Stree<K> {
                     let own_min = min(
                         self.node_items[child_idx].key.clone(),
                         parent_min_key
-                            .get(&child_idx)
-                            .unwrap_or(&K::max_value())
-                            .clone(),
+                            .get(child_idx)
+                            .cloned()
+                            .flatten()
+                            .unwrap_or_else(K::max_value),
                     );
-                    parent_min_key.insert(parent_idx, own_min);
+                    parent_min_key[parent_idx] = Some(own_min);
                     parent_idx += 1;
                     child_idx += node_size;
                     continue;
@@ -555,28 +665,25 @@ impl<K: Key> Stree<K> {
                         };
                         let parent_node = NodeItem::<K>::new(parent_key.clone(), child_idx as u64);
                         self.node_items[parent_idx] = parent_node;
-                        parent_min_key.insert(parent_idx, self.node_items[child_idx].key.clone());
+                        parent_min_key[parent_idx] = Some(self.node_items[child_idx].key.clone());
                         parent_idx += 1;
                         child_idx += node_size;
                         continue;
                     }
 
                     let parent_key = if right_node_idx < children_level.end {
-                        parent_min_key
-                            .get(&(child_idx + node_size))
-                            .expect("Parent node's key is the minimum key of the right children node's leaf node")
+                        parent_min_key[child_idx + node_size]
                             .clone()
+                            .expect("Parent node's key is the minimum key of the right children node's leaf node")
                     } else {
                         K::max_value()
                     };
                     let parent_node = NodeItem::<K>::new(parent_key.clone(), child_idx as u64);
                     self.node_items[parent_idx] = parent_node;
-                    parent_min_key.insert(
-                        parent_idx,
-                        parent_min_key
-                            .get(&child_idx)
-                            .expect("Parent node's key is the minimum key of the right children node's leaf node")
-                            .clone(),
+                    parent_min_key[parent_idx] = Some(
+                        parent_min_key[child_idx]
+                            .clone()
+                            .expect("Parent node's key is the minimum key of the right children node's leaf node"),
                     );
                     parent_idx += 1;
                     child_idx += node_size;
@@ -622,10 +729,19 @@ impl<K: Key> Stree<K> {
     }
 
     pub fn build(nodes: &[NodeItem<K>], branching_factor: u16) -> Result<Stree<K>> {
-        let branching_factor = branching_factor.clamp(2u16, 65535u16);
         // sort nodes by key
         let mut nodes = nodes.to_vec();
         nodes.sort_by_key(|item| item.key.clone());
+        Self::build_sorted(nodes, branching_factor)
+    }
+
+    /// Builds the tree from nodes already sorted by key, without copying or sorting them again.
+    /// Returns `Error::BuildError` if the nodes are not sorted.
+    pub fn build_sorted(nodes: Vec<NodeItem<K>>, branching_factor: u16) -> Result<Stree<K>> {
+        if nodes.windows(2).any(|pair| pair[0].key > pair[1].key) {
+            return Err(Error::BuildError("nodes are not sorted by key".to_string()));
+        }
+        let branching_factor = branching_factor.clamp(2u16, 65535u16);
         // Group duplicates into payload entries and build with unique keys
         // Tag bit for payload pointers: MSB of u64
         const TAG_MASK: Offset = 1u64 << 63;
@@ -919,6 +1035,17 @@ impl<K: Key> Stree<K> {
     /// - If lower > upper, returns an empty result (invalid range)
     /// - If lower == upper, delegates to find_exact for consistent behavior
     pub fn find_range(&self, lower: K, upper: K) -> Result<Vec<SearchResultItem>> {
+        self.find_range_filtered(lower, upper, |_| true)
+    }
+
+    /// Finds the items with keys in [lower, upper] for which `filter` holds, e.g. to match a
+    /// pattern within the range of its prefix
+    pub fn find_range_filtered(
+        &self,
+        lower: K,
+        upper: K,
+        filter: impl Fn(&K) -> bool,
+    ) -> Result<Vec<SearchResultItem>> {
         let leaf_nodes_offset = self
             .level_bounds
             .first()
@@ -932,6 +1059,9 @@ impl<K: Key> Stree<K> {
         // Special case for exact matches (when lower == upper)
         // Use find_exact for single-item ranges to ensure consistent behavior
         if lower == upper {
+            if !filter(&lower) {
+                return Ok(Vec::new());
+            }
             return self.find_exact(lower);
         }
 
@@ -959,7 +1089,7 @@ impl<K: Key> Stree<K> {
 
             // Add items that fall within the range
             for (_i, item) in node_items.iter().enumerate() {
-                if item.key >= lower && item.key <= upper {
+                if item.key >= lower && item.key <= upper && filter(&item.key) {
                     let off = item.offset;
                     let idx = current_idx + _i - leaf_nodes_offset;
                     if self.payload_initialized && (off & PAYLOAD_TAG) != 0 {
@@ -993,6 +1123,18 @@ impl<K: Key> Stree<K> {
         branching_factor: u16,
         lower: K,
         upper: K,
+    ) -> Result<Vec<SearchResultItem>> {
+        Self::stream_find_range_filtered(data, num_items, branching_factor, lower, upper, |_| true)
+    }
+
+    /// Streaming version of [`Stree::find_range_filtered`]
+    pub fn stream_find_range_filtered<R: Read + Seek + ?Sized>(
+        data: &mut R,
+        num_items: usize,
+        branching_factor: u16,
+        lower: K,
+        upper: K,
+        filter: impl Fn(&K) -> bool,
     ) -> Result<Vec<SearchResultItem>> {
         let node_size = branching_factor as usize - 1;
         let level_bounds = Stree::<K>::generate_level_bounds(num_items, branching_factor);
@@ -1015,6 +1157,9 @@ impl<K: Key> Stree<K> {
         // Special case for exact matches (when lower == upper)
         // Use find_exact for single-item ranges to ensure consistent behavior
         if lower == upper {
+            if !filter(&lower) {
+                return Ok(Vec::new());
+            }
             return Stree::stream_find_exact(data, num_items, branching_factor, lower);
         }
 
@@ -1046,7 +1191,7 @@ impl<K: Key> Stree<K> {
 
             // Add items that fall within the range
             for (_i, item) in node_items.iter().enumerate() {
-                if item.key >= lower && item.key <= upper {
+                if item.key >= lower && item.key <= upper && filter(&item.key) {
                     let off = item.offset;
                     let idx = current_idx + _i - leaf_nodes_offset;
                     if (off & PAYLOAD_TAG) != 0 {
@@ -1098,9 +1243,9 @@ impl<K: Key> Stree<K> {
             // Find the child node to traverse next using binary search
             match node_items.binary_search_by(|item| item.key.cmp(&key)) {
                 Ok(index) => {
-                    // Exact match found, go to the corresponding child
-                    // For an exact match, we go to the child node pointed to by this entry
-                    node_index = node_items[index].offset as usize;
+                    // Exact match found. The key is the minimum key of the right child,
+                    // so we go to the right child like find_exact does
+                    node_index = node_items[index].offset as usize + node_size;
                 }
                 Err(index) => {
                     // No exact match, determine appropriate child based on comparison
@@ -1153,9 +1298,9 @@ impl<K: Key> Stree<K> {
             // Find the child node to traverse next using binary search
             match node_items.binary_search_by(|item: &Entry<K>| item.key.cmp(&key)) {
                 Ok(index) => {
-                    // Exact match found, go to the corresponding child
-                    // For an exact match, we go to the child node pointed to by this entry
-                    node_index = node_items[index].offset as usize;
+                    // Exact match found. The key is the minimum key of the right child,
+                    // so we go to the right child like find_exact does
+                    node_index = node_items[index].offset as usize + node_size;
                 }
                 Err(index) => {
                     // No exact match, determine appropriate child based on comparison
@@ -1193,6 +1338,35 @@ impl<K: Key> Stree<K> {
         branching_factor: u16,
         key: K,
         combine_request_threshold: usize,
+        adaptive_prefetch: bool,
+    ) -> Result<Vec<HttpSearchResultItem>> {
+        Self::http_stream_find_exact_cached(
+            client,
+            index_begin,
+            feature_begin,
+            num_items,
+            branching_factor,
+            key,
+            combine_request_threshold,
+            adaptive_prefetch,
+            &mut PayloadCache::new(),
+        )
+        .await
+    }
+
+    /// [`Stree::http_stream_find_exact`] resolving the payload entries through `payload_cache`
+    #[cfg(feature = "http")]
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn http_stream_find_exact_cached<T: AsyncHttpRangeClient>(
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        index_begin: usize,
+        feature_begin: usize,
+        num_items: usize,
+        branching_factor: u16,
+        key: K,
+        combine_request_threshold: usize,
+        adaptive_prefetch: bool,
+        payload_cache: &mut PayloadCache,
     ) -> Result<Vec<HttpSearchResultItem>> {
         debug!("http_stream_find_exact starts: index_begin: {index_begin}, feature_begin: {feature_begin}, num_items: {num_items}, branching_factor: {branching_factor}, key: {key:?}");
 
@@ -1244,12 +1418,21 @@ impl<K: Key> Stree<K> {
         debug!("prefetching payload with size: {} bytes", prefetch_size);
 
         // Prefetch a chunk of payload data
-        let payload_cache = prefetch_payload(client, payload_data_start, prefetch_size).await?;
+        prefetch_payload_into(client, payload_cache, payload_data_start, prefetch_size).await?;
 
+        let mut node_cache = HttpNodeCache::new(adaptive_prefetch);
         while let Some(node_range) = queue.pop_front() {
             debug!("next: {node_range:?}. {} items left in queue", queue.len());
             let is_leaf = node_range.level == 0;
-            let node_items = read_http_node_items(client, index_begin, &node_range.nodes).await?;
+            let node_items = node_cache
+                .read(
+                    client,
+                    index_begin,
+                    &node_range.nodes,
+                    node_range.level,
+                    &level_bounds,
+                )
+                .await?;
             if node_items.is_empty() {
                 continue;
             }
@@ -1360,7 +1543,7 @@ impl<K: Key> Stree<K> {
             payload_refs,
             payload_data_start,
             feature_begin,
-            Some(&payload_cache),
+            Some(payload_cache),
         )
         .await?;
 
@@ -1376,6 +1559,28 @@ impl<K: Key> Stree<K> {
         branching_factor: u16,
         key: K,
         _combine_request_threshold: usize,
+        adaptive_prefetch: bool,
+    ) -> Result<usize> {
+        Self::http_find_partition_cached(
+            client,
+            index_begin,
+            num_items,
+            branching_factor,
+            key,
+            &mut HttpNodeCache::new(adaptive_prefetch),
+        )
+        .await
+    }
+
+    /// [`Stree::http_stream_find_partition`] reading the nodes through `node_cache`
+    #[cfg(feature = "http")]
+    async fn http_find_partition_cached<T: AsyncHttpRangeClient>(
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        index_begin: usize,
+        num_items: usize,
+        branching_factor: u16,
+        key: K,
+        node_cache: &mut HttpNodeCache<K>,
     ) -> Result<usize> {
         if num_items == 0 {
             return Ok(0);
@@ -1400,7 +1605,9 @@ impl<K: Key> Stree<K> {
             };
 
             // Read the node items using HTTP
-            let node_items = read_http_node_items(client, index_begin, &node_range).await?;
+            let node_items = node_cache
+                .read(client, index_begin, &node_range, level, &level_bounds)
+                .await?;
 
             if node_items.is_empty() {
                 continue;
@@ -1409,8 +1616,9 @@ impl<K: Key> Stree<K> {
             // Find the child node to traverse next using binary search
             match node_items.binary_search_by(|item: &NodeItem<K>| item.key.cmp(&key)) {
                 Ok(index) => {
-                    // Exact match found, go to the corresponding child
-                    node_index = node_items[index].offset as usize;
+                    // Exact match found. The key is the minimum key of the right child,
+                    // so we go to the right child like find_exact does
+                    node_index = node_items[index].offset as usize + node_size;
                 }
 
                 Err(index) => {
@@ -1515,6 +1723,145 @@ impl<K: Key> Stree<K> {
         self.branching_factor
     }
 
+    /// Checks the consistency of the tree after it was built or read: the level bounds, the
+    /// number of nodes, the ordering of the keys and the child pointers of internal nodes, and
+    /// that the leaves point within a data section of `data_section_size` bytes, if given.
+    /// Payload entries are only checked when the payload section is loaded.
+    ///
+    /// Returns an empty list for a valid tree.
+    pub fn verify_integrity(&self, data_section_size: Option<u64>) -> Result<Vec<IntegrityError>> {
+        let mut errors = Vec::new();
+        let num_nodes = self.num_nodes();
+
+        // the other checks rely on the level bounds
+        if let Some(err) = self.verify_level_bounds() {
+            errors.push(err);
+            return Ok(errors);
+        }
+
+        let expected = Stree::<K>::index_size(self.num_leaf_nodes, self.branching_factor, 0)
+            / NodeItem::<K>::SERIALIZED_SIZE;
+        if expected != num_nodes {
+            errors.push(IntegrityError::NodeCount {
+                expected,
+                actual: num_nodes,
+            });
+        }
+
+        let leaves = &self.level_bounds[0];
+        for node in leaves.start + 1..leaves.end {
+            if self.node_items[node - 1].key >= self.node_items[node].key {
+                errors.push(IntegrityError::UnsortedLeaves { node });
+            }
+        }
+
+        let node_size = self.branching_factor as usize - 1;
+        for level in 1..self.level_bounds.len() {
+            let children = &self.level_bounds[level - 1];
+            for node in self.level_bounds[level].clone() {
+                let item = &self.node_items[node];
+                let child = item.offset as usize;
+                if !children.contains(&child) {
+                    errors.push(IntegrityError::ChildOutOfLevel { node, child });
+                    continue;
+                }
+                if item.key == K::max_value() {
+                    continue;
+                }
+                let child_end = (child + node_size).min(children.end);
+                if self.node_items[child..child_end]
+                    .iter()
+                    .any(|child_item| child_item.key >= item.key)
+                {
+                    errors.push(IntegrityError::KeyNotCovered { node, child });
+                }
+            }
+        }
+
+        for node in leaves.clone() {
+            let offset = self.node_items[node].offset;
+            let offsets = if offset & PAYLOAD_TAG == 0 {
+                vec![offset]
+            } else if self.payload_initialized {
+                // check the entry fits before reading, a corrupted count could be huge
+                let start = (offset & PAYLOAD_MASK) as usize;
+                let entry_end =
+                    self.payload_data
+                        .get(start..start.saturating_add(4))
+                        .map(|count| {
+                            start + 4 + u32::from_le_bytes(count.try_into().unwrap()) as usize * 8
+                        });
+                let entry = match entry_end {
+                    Some(end) if end <= self.payload_data.len() => {
+                        let mut cursor = Cursor::new(&self.payload_data[start..end]);
+                        PayloadEntry::deserialize(&mut cursor).ok()
+                    }
+                    _ => None,
+                };
+                match entry {
+                    Some((entry, _)) => entry.offsets,
+                    None => {
+                        errors.push(IntegrityError::InvalidPayload { node, offset });
+                        continue;
+                    }
+                }
+            } else {
+                continue;
+            };
+            if let Some(size) = data_section_size {
+                if let Some(&offset) = offsets.iter().find(|&&offset| offset >= size) {
+                    errors.push(IntegrityError::OffsetOutOfBounds { node, offset });
+                }
+            }
+        }
+
+        Ok(errors)
+    }
+
+    /// Checks that the levels are contiguous, with the root level first in storage order
+    fn verify_level_bounds(&self) -> Option<IntegrityError> {
+        let invalid = |msg: String| Some(IntegrityError::LevelBounds(msg));
+        let (Some(leaves), Some(root)) = (self.level_bounds.first(), self.level_bounds.last())
+        else {
+            return invalid("no levels".to_string());
+        };
+        if leaves.len() != self.num_leaf_nodes || self.num_leaf_nodes == 0 {
+            return invalid(format!(
+                "leaf level has {} nodes, expected {}",
+                leaves.len(),
+                self.num_leaf_nodes
+            ));
+        }
+        if leaves.end != self.num_nodes() {
+            return invalid(format!(
+                "leaf level ends at {}, expected {}",
+                leaves.end,
+                self.num_nodes()
+            ));
+        }
+        if root.start != 0 {
+            return invalid(format!("root level starts at {}", root.start));
+        }
+        for (level, pair) in self.level_bounds.windows(2).enumerate() {
+            let (children, parents) = (&pair[0], &pair[1]);
+            if parents.end != children.start {
+                return invalid(format!(
+                    "level {} doesn't end where level {level} starts",
+                    level + 1
+                ));
+            }
+            let expected = children.len().div_ceil(self.branching_factor as usize);
+            if parents.len() != expected {
+                return invalid(format!(
+                    "level {} has {} nodes, expected {expected}",
+                    level + 1,
+                    parents.len()
+                ));
+            }
+        }
+        None
+    }
+
     /// Write all index nodes and any payload data
     pub fn stream_write<W: Write>(&self, out: &mut W) -> Result<usize> {
         //returns written bytes
@@ -1542,6 +1889,71 @@ impl<K: Key> Stree<K> {
         lower: K,
         upper: K,
         combine_request_threshold: usize,
+        adaptive_prefetch: bool,
+    ) -> Result<Vec<HttpSearchResultItem>> {
+        Self::http_stream_find_range_filtered(
+            client,
+            index_begin,
+            feature_begin,
+            num_items,
+            branching_factor,
+            lower,
+            upper,
+            combine_request_threshold,
+            adaptive_prefetch,
+            |_| true,
+        )
+        .await
+    }
+
+    /// HTTP version of [`Stree::find_range_filtered`]. Only the leaf nodes of the range are
+    /// fetched, and only the payloads of the keys passing `filter` are resolved.
+    #[cfg(feature = "http")]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn http_stream_find_range_filtered<T: AsyncHttpRangeClient>(
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        index_begin: usize,
+        feature_begin: usize,
+        num_items: usize,
+        branching_factor: u16,
+        lower: K,
+        upper: K,
+        combine_request_threshold: usize,
+        adaptive_prefetch: bool,
+        filter: impl Fn(&K) -> bool,
+    ) -> Result<Vec<HttpSearchResultItem>> {
+        Self::http_stream_find_range_filtered_cached(
+            client,
+            index_begin,
+            feature_begin,
+            num_items,
+            branching_factor,
+            lower,
+            upper,
+            combine_request_threshold,
+            adaptive_prefetch,
+            filter,
+            &mut PayloadCache::new(),
+        )
+        .await
+    }
+
+    /// [`Stree::http_stream_find_range_filtered`] resolving the payload entries through
+    /// `payload_cache`
+    #[cfg(feature = "http")]
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn http_stream_find_range_filtered_cached<T: AsyncHttpRangeClient>(
+        client: &mut AsyncBufferedHttpRangeClient<T>,
+        index_begin: usize,
+        feature_begin: usize,
+        num_items: usize,
+        branching_factor: u16,
+        lower: K,
+        upper: K,
+        combine_request_threshold: usize,
+        adaptive_prefetch: bool,
+        filter: impl Fn(&K) -> bool,
+        payload_cache: &mut PayloadCache,
     ) -> Result<Vec<HttpSearchResultItem>> {
         debug!("http_stream_find_range starts: index_begin: {index_begin}, feature_begin: {feature_begin}, num_items: {num_items}, branching_factor: {branching_factor}, lower: {lower:?}, upper: {upper:?}");
 
@@ -1553,7 +1965,10 @@ impl<K: Key> Stree<K> {
         // Special case for exact matches (when lower == upper)
         // Use find_exact for single-item ranges to ensure consistent behavior
         if lower == upper {
-            return Self::http_stream_find_exact(
+            if !filter(&lower) {
+                return Ok(Vec::new());
+            }
+            return Self::http_stream_find_exact_cached(
                 client,
                 index_begin,
                 feature_begin,
@@ -1561,6 +1976,8 @@ impl<K: Key> Stree<K> {
                 branching_factor,
                 lower,
                 combine_request_threshold,
+                adaptive_prefetch,
+                payload_cache,
             )
             .await;
         }
@@ -1581,7 +1998,7 @@ impl<K: Key> Stree<K> {
         debug!("prefetching payload with size: {} bytes", prefetch_size);
 
         // Prefetch a chunk of payload data
-        let payload_cache = prefetch_payload(client, payload_data_start, prefetch_size).await?;
+        prefetch_payload_into(client, payload_cache, payload_data_start, prefetch_size).await?;
 
         debug!("http_stream_find_range - index_begin: {index_begin}, feature_begin: {feature_begin}, num_items: {num_items}, branching_factor: {branching_factor}, level_bounds: {level_bounds:?}, lower: {lower:?}, upper: {upper:?}");
 
@@ -1593,23 +2010,24 @@ impl<K: Key> Stree<K> {
             .expect("RTree has at least one level when node_size >= 2 and num_items > 0");
 
         // Find partition points for lower and upper bounds to determine the range to scan
-        let upper_idx = Self::http_stream_find_partition(
+        let mut node_cache = HttpNodeCache::new(adaptive_prefetch);
+        let upper_idx = Self::http_find_partition_cached(
             client,
             index_begin,
             num_items,
             branching_factor,
             upper.clone(),
-            combine_request_threshold,
+            &mut node_cache,
         )
         .await?;
 
-        let lower_idx = Self::http_stream_find_partition(
+        let lower_idx = Self::http_find_partition_cached(
             client,
             index_begin,
             num_items,
             branching_factor,
             lower.clone(),
-            combine_request_threshold,
+            &mut node_cache,
         )
         .await?;
 
@@ -1637,11 +2055,13 @@ impl<K: Key> Stree<K> {
             };
 
             // Read the node items for this range with explicit type parameters
-            let node_items = read_http_node_items::<K, T>(client, index_begin, &node_range).await?;
+            let node_items = node_cache
+                .read(client, index_begin, &node_range, leaf_level, &level_bounds)
+                .await?;
 
             // Collect payload references from items that fall within the range
             for item in node_items.iter() {
-                if item.key >= lower && item.key <= upper {
+                if item.key >= lower && item.key <= upper && filter(&item.key) {
                     let off = item.offset;
 
                     println!("off: {:?}", off);
@@ -1667,7 +2087,7 @@ impl<K: Key> Stree<K> {
             payload_refs,
             payload_data_start,
             feature_begin,
-            Some(&payload_cache),
+            Some(payload_cache),
         )
         .await?;
 
@@ -1835,6 +2255,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    /// The least recently used chunk is evicted when the cache is full
+    fn test_payload_cache_lru() -> Result<()> {
+        let mut entry = PayloadEntry::new();
+        entry.add_offset(42);
+        let serialized = entry.serialize();
+
+        let mut cache = PayloadCache::with_capacity(2);
+        for start in [0, 1000, 2000] {
+            assert!(cache.get_entry(start).is_err());
+            cache.update(start, serialized.clone());
+        }
+        assert_eq!(
+            cache.cache_stats(),
+            CacheStats {
+                hits: 0,
+                misses: 3,
+                evictions: 1
+            }
+        );
+        assert!(cache.get_entry(0).is_err());
+        assert_eq!(cache.get_entry(1000)?.offsets, vec![42]);
+
+        // 2000 is now the least recently used chunk
+        cache.update(3000, serialized.clone());
+        assert!(cache.contains(1000));
+        assert!(!cache.contains(2000));
+        assert_eq!(
+            cache.cache_stats(),
+            CacheStats {
+                hits: 1,
+                misses: 4,
+                evictions: 2
+            }
+        );
+        Ok(())
+    }
+
     #[test]
     fn tree_2items() -> Result<()> {
         let mut nodes = Vec::new();
@@ -1953,7 +2411,7 @@ mod tests {
         // Test 1: Full range search
         let list = tree.find_range(0, 18)?;
         // The test expects to find exactly 19 items with indices 0-18
-        assert_eq!(list.len(), 18);
+        assert_eq!(list.len(), 19);
 
         // Update the test to check each found item's key instead
         let keys: Vec<i64> = list
@@ -1964,8 +2422,8 @@ mod tests {
             })
             .collect();
 
-        // We should have found items with keys 0-17 (in any order)
-        for i in 0..=17 {
+        // We should have found items with keys 0-18 (in any order)
+        for i in 0..=18 {
             assert!(keys.contains(&i));
         }
 
@@ -2297,6 +2755,142 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    /// Building from sorted nodes gives the same tree as sorting them first
+    fn test_build_sorted() -> Result<()> {
+        let nodes = vec![
+            NodeItem::new(3, 70),
+            NodeItem::new(1, 10),
+            NodeItem::new(2, 40),
+            NodeItem::new(1, 20),
+            NodeItem::new(5, 110),
+            NodeItem::new(4, 100),
+        ];
+        assert!(matches!(
+            Stree::build_sorted(nodes.clone(), 2),
+            Err(Error::BuildError(_))
+        ));
+
+        let mut sorted = nodes.clone();
+        sorted.sort_by_key(|item| item.key);
+        let mut expected = Vec::new();
+        Stree::build(&nodes, 2)?.stream_write(&mut expected)?;
+        let mut actual = Vec::new();
+        Stree::build_sorted(sorted, 2)?.stream_write(&mut actual)?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    /// A built tree is consistent, and corruptions of it are reported
+    fn test_verify_integrity() -> Result<()> {
+        for num_items in [1, 2, 5, 19, 100] {
+            for branching_factor in [2, 3, 16] {
+                let nodes: Vec<_> = (0..num_items)
+                    .map(|i| NodeItem::new(i as u64 / 2, i as u64 * 10))
+                    .collect();
+                let tree = Stree::build(&nodes, branching_factor)?;
+                let data_size = num_items as u64 * 10;
+                assert_eq!(tree.verify_integrity(Some(data_size))?, vec![]);
+                assert!(!tree.verify_integrity(Some(data_size - 10))?.is_empty());
+            }
+        }
+
+        let nodes: Vec<_> = (0..19u64).map(|i| NodeItem::new(i, i * 10)).collect();
+        let tree = Stree::build(&nodes, 3)?;
+        let num_nodes = tree.num_nodes();
+
+        let mut unsorted = tree.clone();
+        unsorted.node_items.swap(num_nodes - 1, num_nodes - 2);
+        let errors = unsorted.verify_integrity(None)?;
+        assert!(errors.contains(&IntegrityError::UnsortedLeaves {
+            node: num_nodes - 1
+        }));
+
+        let mut out_of_level = tree.clone();
+        out_of_level.node_items[0].offset = 0;
+        assert_eq!(
+            out_of_level.verify_integrity(None)?,
+            vec![IntegrityError::ChildOutOfLevel { node: 0, child: 0 }]
+        );
+
+        let mut not_covered = tree.clone();
+        let (node, item) = not_covered
+            .node_items
+            .iter()
+            .enumerate()
+            .find(|(_, item)| item.key != u64::MAX)
+            .map(|(node, item)| (node, item.clone()))
+            .unwrap();
+        not_covered.node_items[node].key = 0;
+        assert_eq!(
+            not_covered.verify_integrity(None)?,
+            vec![IntegrityError::KeyNotCovered {
+                node,
+                child: item.offset as usize
+            }]
+        );
+
+        let mut bad_bounds = tree.clone();
+        bad_bounds.level_bounds[0].start += 1;
+        assert!(matches!(
+            bad_bounds.verify_integrity(None)?.as_slice(),
+            [IntegrityError::LevelBounds(_)]
+        ));
+
+        let mut bad_payload = Stree::build(
+            &[
+                NodeItem::new(1u64, 10),
+                NodeItem::new(1, 20),
+                NodeItem::new(2, 30),
+            ],
+            2,
+        )?;
+        bad_payload.payload_data.truncate(8);
+        assert!(matches!(
+            bad_payload.verify_integrity(None)?.as_slice(),
+            [IntegrityError::InvalidPayload { .. }]
+        ));
+        Ok(())
+    }
+
+    #[test]
+    /// Searches on a bulk loaded tree give the same results as a linear scan of the entries
+    fn test_build_sorted_search() -> Result<()> {
+        for num_items in [1, 2, 7, 64, 257] {
+            for branching_factor in [2, 3, 16] {
+                // every third key is duplicated
+                let entries: Vec<_> = (0..num_items as u64)
+                    .flat_map(|i| {
+                        let copies = if i % 3 == 0 { 2 } else { 1 };
+                        (0..copies).map(move |c| NodeItem::new(i * 2, i * 100 + c))
+                    })
+                    .collect();
+                let tree = Stree::build_sorted(entries.clone(), branching_factor)?;
+
+                let scan = |lower: u64, upper: u64| -> Vec<usize> {
+                    let mut offsets: Vec<_> = entries
+                        .iter()
+                        .filter(|e| lower <= e.key && e.key <= upper)
+                        .map(|e| e.offset as usize)
+                        .collect();
+                    offsets.sort_unstable();
+                    offsets
+                };
+                let sorted = |results: Vec<SearchResultItem>| -> Vec<usize> {
+                    let mut offsets: Vec<_> = results.iter().map(|r| r.offset).collect();
+                    offsets.sort_unstable();
+                    offsets
+                };
+                for key in 0..num_items as u64 * 2 + 1 {
+                    assert_eq!(sorted(tree.find_exact(key)?), scan(key, key));
+                    assert_eq!(sorted(tree.find_range(key, key + 5)?), scan(key, key + 5));
+                }
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     /// Test range search across duplicates and unique keys
     fn test_duplicates_range() -> Result<()> {
@@ -2502,6 +3096,7 @@ mod tests {
                 branching_factor, // branching_factor
                 query,
                 256 * 1024, // combine_request_threshold
+                false,      // adaptive_prefetch
             )
             .await?;
 
@@ -2623,6 +3218,7 @@ mod tests {
                 branching_factor, // branching_factor
                 query,
                 256 * 1024, // combine_request_threshold
+                false,      // adaptive_prefetch
             )
             .await?;
 
@@ -2737,6 +3333,7 @@ mod tests {
                 lower,
                 upper,
                 256 * 1024, // combine_request_threshold
+                false,      // adaptive_prefetch
             )
             .await?;
 
@@ -2778,6 +3375,79 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_http_adaptive_prefetch() -> Result<()> {
+        use crate::static_btree::mocked_http_range_client::{MockHttpRangeClient, RequestStats};
+        use bytes::Bytes;
+        use http_range_client::AsyncBufferedHttpRangeClient;
+        use std::sync::{Arc, RwLock};
+
+        let nodes = (0..200_000_i64)
+            .map(|i| NodeItem::new(i, i as u64))
+            .collect::<Vec<_>>();
+        let branching_factor = 2;
+        let tree = Stree::<i64>::build(&nodes, branching_factor)?;
+        let mut buf = Vec::new();
+        tree.stream_write(&mut buf)?;
+        let feature_begin = buf.len();
+        let data = Bytes::from(buf);
+
+        let mut results = Vec::new();
+        let mut request_counts = Vec::new();
+        for adaptive_prefetch in [false, true] {
+            let stats = Arc::new(RwLock::new(RequestStats::new()));
+            let client =
+                MockHttpRangeClient::new_with_bytes("in-memory", data.clone(), stats.clone());
+            let mut client = AsyncBufferedHttpRangeClient::with(client, "in-memory");
+            client.set_min_req_size(0);
+
+            let exact = Stree::<i64>::http_stream_find_exact(
+                &mut client,
+                0,
+                feature_begin,
+                tree.num_leaf_nodes,
+                branching_factor,
+                123_456,
+                256 * 1024,
+                adaptive_prefetch,
+            )
+            .await?;
+            let range = Stree::<i64>::http_stream_find_range(
+                &mut client,
+                0,
+                feature_begin,
+                tree.num_leaf_nodes,
+                branching_factor,
+                1_000,
+                1_010,
+                256 * 1024,
+                adaptive_prefetch,
+            )
+            .await?;
+            let mut offsets = exact
+                .iter()
+                .chain(range.iter())
+                .map(|item| item.range.start() - feature_begin)
+                .collect::<Vec<_>>();
+            offsets.sort_unstable();
+            results.push(offsets);
+            request_counts.push(stats.read().unwrap().request_count);
+        }
+
+        let mut expected = (1_000..=1_010).collect::<Vec<usize>>();
+        expected.push(123_456);
+        assert_eq!(results[0], expected);
+        assert_eq!(results[1], expected);
+        assert!(
+            request_counts[1] < request_counts[0],
+            "requests with prefetch: {}, without: {}",
+            request_counts[1],
+            request_counts[0]
+        );
+        Ok(())
+    }
+
     // TODO: fix this test
     // #[cfg(feature = "http")]
     // #[tokio::test]
@@ -0,0 +1,285 @@
+//! Per-column statistics of the attributes of an FCB file
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use fallible_streaming_iterator::FallibleStreamingIterator;
+use hyperloglog::HyperLogLog;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::deserializer::decode_attributes;
+use crate::error::{Error, Result};
+use crate::fb::ColumnType;
+use crate::reader::{city_buffer::FcbBuffer, FeatureIter};
+use crate::FEATURE_ID_COLUMN;
+
+/// Number of distinct values counted exactly, above it the count is estimated
+pub const MAX_EXACT_DISTINCT: usize = 10_000;
+
+/// Relative error of the distinct count estimate
+const HYPERLOGLOG_ERROR_RATE: f64 = 0.01;
+
+/// Statistics of a single attribute column, counted per city object
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnStats {
+    /// Smallest non-null value, numbers compared by value and strings lexicographically
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    /// Number of city objects with a non-null value
+    pub count: u64,
+    /// Number of city objects without a value
+    pub null_count: u64,
+    /// Number of distinct values, estimated above [`MAX_EXACT_DISTINCT`]. `None` for JSON and
+    /// binary columns
+    pub distinct_count: Option<u64>,
+}
+
+/// Values seen so far, switching to an estimate once the exact set grows too large
+enum Distinct {
+    Exact(HashSet<String>),
+    Estimated(HyperLogLog),
+}
+
+impl Distinct {
+    fn insert(&mut self, key: String) {
+        match self {
+            Distinct::Exact(values) => {
+                values.insert(key);
+                if values.len() > MAX_EXACT_DISTINCT {
+                    let mut hll = HyperLogLog::new(HYPERLOGLOG_ERROR_RATE);
+                    for value in values.iter() {
+                        hll.insert(value);
+                    }
+                    *self = Distinct::Estimated(hll);
+                }
+            }
+            Distinct::Estimated(hll) => hll.insert(&key),
+        }
+    }
+
+    fn count(&self) -> u64 {
+        match self {
+            Distinct::Exact(values) => values.len() as u64,
+            Distinct::Estimated(hll) => hll.len().round() as u64,
+        }
+    }
+}
+
+/// Smallest and largest value of a column, compared in the type of the column
+enum Extremes {
+    Numeric {
+        min: (f64, Value),
+        max: (f64, Value),
+    },
+    Text {
+        min: String,
+        max: String,
+    },
+    Boolean {
+        min: bool,
+        max: bool,
+    },
+}
+
+impl Extremes {
+    fn new(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => {
+                let v = n.as_f64()?;
+                Some(Extremes::Numeric {
+                    min: (v, value.clone()),
+                    max: (v, value.clone()),
+                })
+            }
+            Value::String(s) => Some(Extremes::Text {
+                min: s.clone(),
+                max: s.clone(),
+            }),
+            Value::Bool(b) => Some(Extremes::Boolean { min: *b, max: *b }),
+            _ => None,
+        }
+    }
+
+    fn add(&mut self, value: &Value) {
+        match (self, value) {
+            (Extremes::Numeric { min, max }, Value::Number(n)) => {
+                let Some(v) = n.as_f64() else { return };
+                if v < min.0 {
+                    *min = (v, value.clone());
+                }
+                if v > max.0 {
+                    *max = (v, value.clone());
+                }
+            }
+            (Extremes::Text { min, max }, Value::String(s)) => {
+                if *s < *min {
+                    *min = s.clone();
+                }
+                if *s > *max {
+                    *max = s.clone();
+                }
+            }
+            (Extremes::Boolean { min, max }, Value::Bool(b)) => {
+                *min &= *b;
+                *max |= *b;
+            }
+            _ => {}
+        }
+    }
+
+    fn into_values(self) -> (Value, Value) {
+        match self {
+            Extremes::Numeric { min, max } => (min.1, max.1),
+            Extremes::Text { min, max } => (Value::String(min), Value::String(max)),
+            Extremes::Boolean { min, max } => (Value::Bool(min), Value::Bool(max)),
+        }
+    }
+}
+
+struct ColumnAccumulator {
+    count: u64,
+    extremes: Option<Extremes>,
+    distinct: Option<Distinct>,
+}
+
+impl ColumnAccumulator {
+    fn new(column_type: ColumnType) -> Self {
+        let distinct = !matches!(column_type, ColumnType::Json | ColumnType::Binary);
+        Self {
+            count: 0,
+            extremes: None,
+            distinct: distinct.then(|| Distinct::Exact(HashSet::new())),
+        }
+    }
+
+    fn add(&mut self, value: &Value) {
+        self.count += 1;
+        match self.extremes.as_mut() {
+            Some(extremes) => extremes.add(value),
+            None => self.extremes = Extremes::new(value),
+        }
+        if let Some(distinct) = self.distinct.as_mut() {
+            distinct.insert(value.to_string());
+        }
+    }
+
+    fn finish(self, rows: u64) -> ColumnStats {
+        let (min, max) = match self.extremes.map(Extremes::into_values) {
+            Some((min, max)) => (Some(min), Some(max)),
+            None => (None, None),
+        };
+        ColumnStats {
+            min,
+            max,
+            count: self.count,
+            null_count: rows - self.count,
+            distinct_count: self.distinct.as_ref().map(Distinct::count),
+        }
+    }
+}
+
+/// Computes per-column statistics in a single pass over the remaining features of `iter`.
+///
+/// Only the attributes of each city object are decoded, geometries are skipped. `columns`
+/// restricts the statistics to the named columns of the header schema, names missing from it
+/// are left out of the result.
+pub fn compute_attribute_stats<R: Read, S>(
+    iter: &mut FeatureIter<R, S>,
+    columns: Option<&[&str]>,
+) -> Result<HashMap<String, ColumnStats>>
+where
+    FeatureIter<R, S>: FallibleStreamingIterator<Item = FcbBuffer, Error = Error>,
+{
+    let header = iter.header();
+    let mut accumulators = header
+        .columns()
+        .into_iter()
+        .flat_map(|cols| cols.iter())
+        .filter(|col| col.name() != FEATURE_ID_COLUMN)
+        .filter(|col| columns.is_none_or(|names| names.contains(&col.name())))
+        .map(|col| (col.name().to_string(), ColumnAccumulator::new(col.type_())))
+        .collect::<HashMap<_, _>>();
+
+    let mut rows = 0;
+    while let Some(feat_buf) = iter.next()? {
+        let header_columns = feat_buf.header().columns();
+        let feature = feat_buf.feature();
+        for co in feature.objects().iter().flat_map(|objects| objects.iter()) {
+            rows += 1;
+            let (Some(attributes), Some(co_columns)) =
+                (co.attributes(), co.columns().or(header_columns))
+            else {
+                continue;
+            };
+            let Value::Object(map) = decode_attributes(&co_columns, attributes)? else {
+                continue;
+            };
+            for (name, value) in map.iter() {
+                if value.is_null() {
+                    continue;
+                }
+                if let Some(acc) = accumulators.get_mut(name) {
+                    acc.add(value);
+                }
+            }
+        }
+    }
+
+    Ok(accumulators
+        .into_iter()
+        .map(|(name, acc)| (name, acc.finish(rows)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distinct_switches_to_estimate() {
+        let mut distinct = Distinct::Exact(HashSet::new());
+        for i in 0..MAX_EXACT_DISTINCT {
+            distinct.insert(i.to_string());
+            distinct.insert(i.to_string());
+        }
+        assert!(matches!(distinct, Distinct::Exact(_)));
+        assert_eq!(distinct.count(), MAX_EXACT_DISTINCT as u64);
+
+        let total = 3 * MAX_EXACT_DISTINCT;
+        for i in MAX_EXACT_DISTINCT..total {
+            distinct.insert(i.to_string());
+        }
+        assert!(matches!(distinct, Distinct::Estimated(_)));
+        let estimate = distinct.count() as f64;
+        assert!((estimate - total as f64).abs() < total as f64 * 0.05);
+    }
+
+    #[test]
+    fn extremes_by_type() {
+        let mut acc = ColumnAccumulator::new(ColumnType::Long);
+        for v in [3, -2, 10, 3] {
+            acc.add(&Value::from(v));
+        }
+        let stats = acc.finish(6);
+        assert_eq!(stats.min, Some(Value::from(-2)));
+        assert_eq!(stats.max, Some(Value::from(10)));
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.null_count, 2);
+        assert_eq!(stats.distinct_count, Some(3));
+
+        let mut acc = ColumnAccumulator::new(ColumnType::String);
+        for v in ["b", "a", "c"] {
+            acc.add(&Value::from(v));
+        }
+        let stats = acc.finish(3);
+        assert_eq!(stats.min, Some(Value::from("a")));
+        assert_eq!(stats.max, Some(Value::from("c")));
+
+        let mut acc = ColumnAccumulator::new(ColumnType::Json);
+        acc.add(&serde_json::json!({ "a": 1 }));
+        let stats = acc.finish(1);
+        assert_eq!(stats.min, None);
+        assert_eq!(stats.distinct_count, None);
+    }
+}
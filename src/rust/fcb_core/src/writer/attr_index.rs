@@ -2,13 +2,16 @@ use std::collections::HashMap;
 
 use crate::error::{Error, Result};
 use crate::fb::ColumnType;
-use crate::static_btree::{Entry, FixedStringKey, Key, MemoryIndex};
+use crate::static_btree::{
+    CompositeKey, Entry, FixedStringKey, Key, KeyType, MemoryIndex, Nullable, Stree,
+};
 use chrono::{DateTime, Utc};
+use log::warn;
 use ordered_float::OrderedFloat;
 
 use super::{
     attribute::{AttributeIndexEntry, AttributeSchema},
-    serializer::AttributeIndexInfo,
+    serializer::{AttributeIndexInfo, CompositeIndexInfo},
     AttributeFeatureOffset,
 };
 
@@ -23,8 +26,11 @@ where
     F: Fn(&AttributeIndexEntry) -> Option<T>,
 {
     let mut entries: Vec<Entry<T>> = Vec::new();
+    // Offsets of features without a value for this attribute
+    let mut missing: Vec<u64> = Vec::new();
 
     for feature in attribute_entries.values() {
+        let num_entries = entries.len();
         for entry in &feature.index_entries {
             let Some(key) = extract(entry) else {
                 continue;
@@ -34,9 +40,40 @@ where
                 offset: feature.offset as u64,
             });
         }
+        if entries.len() == num_entries {
+            missing.push(feature.offset as u64);
+        }
     }
 
-    let index = MemoryIndex::<T>::build(&entries, branching_factor)?;
+    if missing.is_empty() {
+        return serialize_index(schema_index, entries, branching_factor, false);
+    }
+
+    // Absent values are indexed as null keys so they can be queried with `IsNull`
+    let entries = entries
+        .into_iter()
+        .map(|entry| Entry {
+            key: Nullable(Some(entry.key)),
+            offset: entry.offset,
+        })
+        .chain(missing.into_iter().map(|offset| Entry {
+            key: Nullable(None),
+            offset,
+        }))
+        .collect::<Vec<_>>();
+    serialize_index(schema_index, entries, branching_factor, true)
+}
+
+fn serialize_index<K: Key>(
+    schema_index: u16,
+    mut entries: Vec<Entry<K>>,
+    branching_factor: u16,
+    nullable: bool,
+) -> Result<(Vec<u8>, AttributeIndexInfo)> {
+    // sorted in place, the entries are moved into the tree without another copy. Ties are
+    // broken by offset as the entries are collected from a HashMap
+    entries.sort_by(|a, b| a.key.cmp(&b.key).then(a.offset.cmp(&b.offset)));
+    let index = MemoryIndex::<K>::build_sorted(entries, branching_factor)?;
     let mut buf = Vec::new();
     index.serialize(&mut buf)?;
     let buf_length = buf.len();
@@ -47,6 +84,7 @@ where
             length: buf_length as u32,
             branching_factor: index.branching_factor(),
             num_unique_items: index.num_items() as u32,
+            nullable,
         },
     ))
 }
@@ -269,22 +307,11 @@ pub(super) fn build_attribute_index_for_attr(
             },
             branching_factor,
         ),
-        ColumnType::Json => build_index_generic::<FixedStringKey<100>, _>(
-            *schema_index,
-            attribute_entries,
-            |entry| {
-                if let AttributeIndexEntry::Json { index, val } = entry {
-                    if *index == *schema_index {
-                        Some(FixedStringKey::from_str(val))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            },
-            branching_factor,
-        ),
+        ColumnType::Json => {
+            // JSON values have no meaningful order
+            warn!("attribute {attr_name}: skipping the index of a Json column");
+            Err(Error::UnsupportedColumnType(format!("{:?}", coltype)))
+        }
         ColumnType::Binary => build_index_generic::<FixedStringKey<100>, _>(
             *schema_index,
             attribute_entries,
@@ -307,3 +334,208 @@ pub(super) fn build_attribute_index_for_attr(
         }
     }
 }
+
+/// Key of an index entry of the column at `schema_index`, using the key type of
+/// the column's single-column index
+fn entry_key(entry: &AttributeIndexEntry, schema_index: u16) -> Option<KeyType> {
+    let (index, key) = match entry {
+        AttributeIndexEntry::Bool { index, val } => (index, KeyType::Bool(*val)),
+        AttributeIndexEntry::Int { index, val } => (index, KeyType::Int32(*val)),
+        AttributeIndexEntry::UInt { index, val } => (index, KeyType::UInt32(*val)),
+        AttributeIndexEntry::Long { index, val } => (index, KeyType::Int64(*val)),
+        AttributeIndexEntry::ULong { index, val } => (index, KeyType::UInt64(*val)),
+        AttributeIndexEntry::Float { index, val } => (index, KeyType::Float32(OrderedFloat(*val))),
+        AttributeIndexEntry::Double { index, val } => (index, KeyType::Float64(OrderedFloat(*val))),
+        AttributeIndexEntry::String { index, val } => {
+            (index, KeyType::StringKey50(FixedStringKey::from_str(val)))
+        }
+        AttributeIndexEntry::DateTime { index, val } => (index, KeyType::DateTime(*val)),
+        AttributeIndexEntry::Short { index, val } => (index, KeyType::Int16(*val)),
+        AttributeIndexEntry::UShort { index, val } => (index, KeyType::UInt16(*val)),
+        AttributeIndexEntry::Byte { index, val } => (index, KeyType::UInt8(*val)),
+        AttributeIndexEntry::UByte { index, val } => (index, KeyType::UInt8(*val)),
+        AttributeIndexEntry::Json { index, val } | AttributeIndexEntry::Binary { index, val } => {
+            (index, KeyType::StringKey100(FixedStringKey::from_str(val)))
+        }
+    };
+    (*index == schema_index).then_some(key)
+}
+
+/// Builds an index over several attributes whose keys are the concatenated
+/// serialized values (see [`CompositeKey`]). Features lacking any of the
+/// attributes are not indexed.
+pub(super) fn build_composite_index(
+    attr_names: &[String],
+    schema: &AttributeSchema,
+    attribute_entries: &HashMap<usize, AttributeFeatureOffset>,
+    branching_factor: u16,
+) -> Result<(Vec<u8>, CompositeIndexInfo)> {
+    let schema_indices = attr_names
+        .iter()
+        .map(|name| {
+            schema
+                .get(name)
+                .map(|(index, _)| *index)
+                .ok_or(Error::AttributeIndexNotFound)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut entries: Vec<Entry<CompositeKey>> = Vec::new();
+    for feature in attribute_entries.values() {
+        // Every combination of the feature's values, e.g. from several city objects
+        let mut combinations: Vec<Vec<KeyType>> = vec![Vec::new()];
+        for schema_index in &schema_indices {
+            let values = feature
+                .index_entries
+                .iter()
+                .filter_map(|entry| entry_key(entry, *schema_index))
+                .collect::<Vec<_>>();
+            combinations = combinations
+                .iter()
+                .flat_map(|prefix| {
+                    values.iter().map(move |value| {
+                        let mut keys = prefix.clone();
+                        keys.push(value.clone());
+                        keys
+                    })
+                })
+                .collect();
+        }
+
+        let mut keys = combinations
+            .iter()
+            .map(|keys| CompositeKey::from_keys(keys))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        keys.sort();
+        keys.dedup();
+        entries.extend(keys.into_iter().map(|key| Entry {
+            key,
+            offset: feature.offset as u64,
+        }));
+    }
+
+    entries.sort_by(|a, b| a.key.cmp(&b.key).then(a.offset.cmp(&b.offset)));
+    let index = MemoryIndex::<CompositeKey>::build_sorted(entries, branching_factor)?;
+    let mut buf = Vec::new();
+    index.serialize(&mut buf)?;
+    let buf_length = buf.len();
+    Ok((
+        buf,
+        CompositeIndexInfo {
+            columns: schema_indices,
+            length: buf_length as u32,
+            branching_factor: index.branching_factor(),
+            num_unique_items: index.num_items() as u32,
+        },
+    ))
+}
+
+/// Size of an index over `num_items` entries computed from the tree layout, the payload of
+/// duplicate keys is estimated (see [`Stree::estimate_payload_section_size`])
+fn estimate_index_size<K: Key>(num_items: usize, branching_factor: u16, nullable: bool) -> usize {
+    if num_items == 0 {
+        return 0;
+    }
+    let branching_factor = branching_factor.max(2);
+    if nullable {
+        let payload = Stree::<Nullable<K>>::estimate_payload_section_size(num_items, None, None);
+        Stree::<Nullable<K>>::index_size(num_items, branching_factor, payload)
+    } else {
+        let payload = Stree::<K>::estimate_payload_section_size(num_items, None, None);
+        Stree::<K>::index_size(num_items, branching_factor, payload)
+    }
+}
+
+/// Estimates the index of `attr_name` without building it, `None` if the attribute is not
+/// indexed by [`build_attribute_index_for_attr`]
+pub(super) fn estimate_attribute_index(
+    attr_name: &str,
+    schema: &AttributeSchema,
+    attribute_entries: &HashMap<usize, AttributeFeatureOffset>,
+    branching_factor: u16,
+) -> Option<AttributeIndexInfo> {
+    let (schema_index, coltype) = schema.get(attr_name)?;
+
+    let mut num_items = 0;
+    let mut nullable = false;
+    for feature in attribute_entries.values() {
+        let values = feature
+            .index_entries
+            .iter()
+            .filter(|entry| entry_key(entry, *schema_index).is_some())
+            .count();
+        // features without a value get a null entry
+        nullable |= values == 0;
+        num_items += values.max(1);
+    }
+
+    let length = match *coltype {
+        ColumnType::Bool => estimate_index_size::<bool>(num_items, branching_factor, nullable),
+        ColumnType::Int => estimate_index_size::<i32>(num_items, branching_factor, nullable),
+        ColumnType::UInt => estimate_index_size::<u32>(num_items, branching_factor, nullable),
+        ColumnType::Long => estimate_index_size::<i64>(num_items, branching_factor, nullable),
+        ColumnType::ULong => estimate_index_size::<u64>(num_items, branching_factor, nullable),
+        ColumnType::Float => {
+            estimate_index_size::<OrderedFloat<f32>>(num_items, branching_factor, nullable)
+        }
+        ColumnType::Double => {
+            estimate_index_size::<OrderedFloat<f64>>(num_items, branching_factor, nullable)
+        }
+        ColumnType::String => {
+            estimate_index_size::<FixedStringKey<50>>(num_items, branching_factor, nullable)
+        }
+        ColumnType::DateTime => {
+            estimate_index_size::<DateTime<Utc>>(num_items, branching_factor, nullable)
+        }
+        ColumnType::Short => estimate_index_size::<i16>(num_items, branching_factor, nullable),
+        ColumnType::UShort => estimate_index_size::<u16>(num_items, branching_factor, nullable),
+        ColumnType::Byte | ColumnType::UByte => {
+            estimate_index_size::<u8>(num_items, branching_factor, nullable)
+        }
+        ColumnType::Binary => {
+            estimate_index_size::<FixedStringKey<100>>(num_items, branching_factor, nullable)
+        }
+        _ => return None,
+    };
+    Some(AttributeIndexInfo {
+        index: *schema_index,
+        length: length as u32,
+        branching_factor,
+        num_unique_items: num_items as u32,
+        nullable,
+    })
+}
+
+/// Estimates the composite index over `attr_names` without building it, `None` if one of the
+/// attributes is not in the schema
+pub(super) fn estimate_composite_index(
+    attr_names: &[String],
+    schema: &AttributeSchema,
+    attribute_entries: &HashMap<usize, AttributeFeatureOffset>,
+    branching_factor: u16,
+) -> Option<CompositeIndexInfo> {
+    let schema_indices = attr_names
+        .iter()
+        .map(|name| schema.get(name).map(|(index, _)| *index))
+        .collect::<Option<Vec<_>>>()?;
+
+    // one entry per feature having all attributes, ignoring multiple values
+    let num_items = attribute_entries
+        .values()
+        .filter(|feature| {
+            schema_indices.iter().all(|schema_index| {
+                feature
+                    .index_entries
+                    .iter()
+                    .any(|entry| entry_key(entry, *schema_index).is_some())
+            })
+        })
+        .count();
+    let length = estimate_index_size::<CompositeKey>(num_items, branching_factor, false);
+    Some(CompositeIndexInfo {
+        columns: schema_indices,
+        length: length as u32,
+        branching_factor,
+        num_unique_items: num_items as u32,
+    })
+}
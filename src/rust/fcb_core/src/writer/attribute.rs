@@ -1,33 +1,368 @@
-use crate::fb::ColumnType;
+use crate::fb::{Column, ColumnType, Header};
+use crate::FEATURE_ID_COLUMN;
 use byteorder::{ByteOrder, LittleEndian};
 use chrono::{DateTime, Utc};
 use cjseq::CityJSONFeature;
-use serde_json::Value;
-use std::collections::HashMap;
+use flatbuffers::{ForwardsUOffset, Vector};
+use indexmap::IndexMap;
+use log::warn;
+use serde_json::{json, Value};
+use std::fmt;
 
 // Schema for attributes. The key is the attribute name, the value is a tuple of the column index and the column type.
-pub type AttributeSchema = HashMap<String, (u16, ColumnType)>;
+// Columns iterate in insertion order, so the same input always gets the same column indices.
+pub type AttributeSchema = IndexMap<String, (u16, ColumnType)>;
 
 pub trait AttributeSchemaMethods {
     fn add_attributes(&mut self, attrs: &Value);
+    /// Combines two schemas, widening column types where they differ
+    ///
+    /// Columns of `self` keep their index, columns only found in `other` are appended.
+    /// Numeric columns are promoted along `Int` -> `Long` -> `Double` and `Float` -> `Double`,
+    /// any other type difference is reported as a [`SchemaConflict`].
+    fn merge(&self, other: &AttributeSchema) -> Result<AttributeSchema, SchemaConflict>;
+    /// JSON Schema (draft-07) document describing the columns as nullable object properties
+    fn to_json_schema(&self) -> Value;
+    /// Parses the `properties` of a JSON Schema document, indexing columns in property order
+    fn from_json_schema(schema: &Value) -> crate::error::Result<AttributeSchema>;
+    /// Schema stored in the `columns` of an FCB header, with the indices and types the
+    /// features were encoded with. The reserved [`FEATURE_ID_COLUMN`] is left out.
+    fn from_fcb_header(header: &Header) -> AttributeSchema;
 }
 
+const JSON_SCHEMA_DRAFT_07: &str = "http://json-schema.org/draft-07/schema#";
+
+/// JSON Schema of a single column, integer and number widths are kept in `format`
+pub fn column_json_schema(coltype: ColumnType) -> Value {
+    match coltype {
+        ColumnType::Byte => json!({ "type": "integer", "format": "int8" }),
+        ColumnType::UByte => json!({ "type": "integer", "format": "uint8" }),
+        ColumnType::Short => json!({ "type": "integer", "format": "int16" }),
+        ColumnType::UShort => json!({ "type": "integer", "format": "uint16" }),
+        ColumnType::Int => json!({ "type": "integer", "format": "int32" }),
+        ColumnType::UInt => json!({ "type": "integer", "format": "uint32" }),
+        ColumnType::Long => json!({ "type": "integer", "format": "int64" }),
+        ColumnType::ULong => json!({ "type": "integer", "format": "uint64" }),
+        ColumnType::Float => json!({ "type": "number", "format": "float" }),
+        ColumnType::Double => json!({ "type": "number", "format": "double" }),
+        ColumnType::Bool => json!({ "type": "boolean" }),
+        ColumnType::String => json!({ "type": "string" }),
+        ColumnType::DateTime => json!({ "type": "string", "format": "date-time" }),
+        ColumnType::Binary => json!({ "type": "string", "contentEncoding": "base64" }),
+        // Json columns hold arbitrary values
+        _ => json!({}),
+    }
+}
+
+/// Column type of a JSON Schema property, the inverse of [`column_json_schema`]
+fn column_type_from_json_schema(name: &str, prop: &Value) -> crate::error::Result<ColumnType> {
+    let types = match prop.get("type") {
+        None => Vec::new(),
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        Some(other) => {
+            return Err(crate::error::Error::InvalidAttributeValue {
+                msg: format!("invalid type of property {name}: {other}"),
+            })
+        }
+    };
+    let types = types
+        .into_iter()
+        .filter(|ty| *ty != "null")
+        .collect::<Vec<_>>();
+    let format = prop.get("format").and_then(Value::as_str);
+
+    // properties without a single concrete type hold arbitrary values
+    let [ty] = types.as_slice() else {
+        return Ok(ColumnType::Json);
+    };
+    let coltype = match (*ty, format) {
+        ("integer", Some("int8")) => ColumnType::Byte,
+        ("integer", Some("uint8")) => ColumnType::UByte,
+        ("integer", Some("int16")) => ColumnType::Short,
+        ("integer", Some("uint16")) => ColumnType::UShort,
+        ("integer", Some("int32")) => ColumnType::Int,
+        ("integer", Some("uint32")) => ColumnType::UInt,
+        ("integer", Some("uint64")) => ColumnType::ULong,
+        ("integer", _) => ColumnType::Long,
+        ("number", Some("float")) => ColumnType::Float,
+        ("number", _) => ColumnType::Double,
+        ("boolean", _) => ColumnType::Bool,
+        ("string", Some("date-time")) => ColumnType::DateTime,
+        ("string", _) if prop.get("contentEncoding").and_then(Value::as_str) == Some("base64") => {
+            ColumnType::Binary
+        }
+        ("string", _) => ColumnType::String,
+        ("array" | "object", _) => ColumnType::Json,
+        (other, _) => {
+            return Err(crate::error::Error::UnsupportedColumnType(format!(
+                "{other} (property {name})"
+            )))
+        }
+    };
+    Ok(coltype)
+}
+
+/// Columns whose types cannot be reconciled when merging attribute schemas
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaConflict {
+    /// Column name with its type in the receiving and in the merged schema
+    pub columns: Vec<(String, ColumnType, ColumnType)>,
+}
+
+impl fmt::Display for SchemaConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self
+            .columns
+            .iter()
+            .map(|(name, left, right)| format!("{name} ({left:?} vs {right:?})"))
+            .collect::<Vec<_>>();
+        write!(f, "conflicting column types: {}", columns.join(", "))
+    }
+}
+
+impl std::error::Error for SchemaConflict {}
+
 impl AttributeSchemaMethods for AttributeSchema {
     fn add_attributes(&mut self, attrs: &Value) {
         if !attrs.is_object() {
-            self.insert("json".to_string(), (self.len() as u16, ColumnType::Json));
+            self.insert(
+                "json".to_string(),
+                (next_column_index(self), ColumnType::Json),
+            );
             return;
         }
 
         let map = attrs.as_object().unwrap();
         for (key, val) in map.iter() {
-            if !self.contains_key(key) && !val.is_null() {
-                if let Some(coltype) = guess_type(val) {
-                    self.insert(key.clone(), (self.len() as u16, coltype));
+            if val.is_null() {
+                continue;
+            }
+            match self.get_mut(key) {
+                Some((_, coltype)) => {
+                    if let Some(widened) = widen_type(*coltype, val) {
+                        warn!("attribute {key}: promoting column type {coltype:?} to {widened:?} to hold {val}");
+                        *coltype = widened;
+                    }
+                }
+                None => {
+                    if let Some(coltype) = guess_type(val) {
+                        self.insert(key.clone(), (next_column_index(self), coltype));
+                    }
                 }
             }
         }
     }
+
+    fn to_json_schema(&self) -> Value {
+        let mut columns = self.iter().collect::<Vec<_>>();
+        columns.sort_by_key(|(_, (index, _))| *index);
+        let properties = columns
+            .into_iter()
+            .map(|(name, (_, coltype))| {
+                let mut prop = column_json_schema(*coltype);
+                if let Some(ty) = prop.get("type").cloned() {
+                    prop["type"] = json!([ty, "null"]);
+                }
+                (name.clone(), prop)
+            })
+            .collect::<serde_json::Map<_, _>>();
+        json!({
+            "$schema": JSON_SCHEMA_DRAFT_07,
+            "type": "object",
+            "properties": properties,
+        })
+    }
+
+    fn from_json_schema(schema: &Value) -> crate::error::Result<AttributeSchema> {
+        let properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .ok_or_else(|| crate::error::Error::InvalidAttributeValue {
+                msg: "JSON Schema has no properties object".to_string(),
+            })?;
+        let mut attr_schema = AttributeSchema::new();
+        for (name, prop) in properties {
+            let coltype = column_type_from_json_schema(name, prop)?;
+            attr_schema.insert(name.clone(), (next_column_index(&attr_schema), coltype));
+        }
+        Ok(attr_schema)
+    }
+
+    fn from_fcb_header(header: &Header) -> AttributeSchema {
+        let mut schema = schema_from_columns(header.columns());
+        schema.shift_remove(FEATURE_ID_COLUMN);
+        schema
+    }
+
+    fn merge(&self, other: &AttributeSchema) -> Result<AttributeSchema, SchemaConflict> {
+        let mut merged = self.clone();
+        let mut conflicts = Vec::new();
+
+        let mut columns = other.iter().collect::<Vec<_>>();
+        columns.sort_by_key(|(_, (index, _))| *index);
+        for (name, (_, coltype)) in columns {
+            match merged.get_mut(name) {
+                Some((_, existing)) => match promote_type(*existing, *coltype) {
+                    Some(promoted) => *existing = promoted,
+                    None => conflicts.push((name.clone(), *existing, *coltype)),
+                },
+                None => {
+                    merged.insert(name.clone(), (next_column_index(&merged), *coltype));
+                }
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(merged)
+        } else {
+            conflicts.sort_by(|a, b| a.0.cmp(&b.0));
+            Err(SchemaConflict { columns: conflicts })
+        }
+    }
+}
+
+/// Index for a new column, after the largest one in use. Indices aren't always contiguous, e.g.
+/// in a schema read from a header without the [`FEATURE_ID_COLUMN`].
+pub(crate) fn next_column_index(schema: &AttributeSchema) -> u16 {
+    schema
+        .values()
+        .map(|(index, _)| index + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Schema of decoded FCB columns, including the [`FEATURE_ID_COLUMN`], empty without columns
+pub(crate) fn schema_from_columns(
+    columns: Option<Vector<'_, ForwardsUOffset<Column<'_>>>>,
+) -> AttributeSchema {
+    columns
+        .into_iter()
+        .flat_map(|cols| cols.iter())
+        .map(|col| (col.name().to_string(), (col.index(), col.type_())))
+        .collect()
+}
+
+/// Checks that the values of `attrs` can be encoded with the column types of `schema` as they
+/// are, e.g. for features added to an existing file. Attributes that are not a column of
+/// `schema` are ignored.
+pub(crate) fn check_attributes(
+    schema: &AttributeSchema,
+    attrs: &Value,
+) -> Result<(), SchemaConflict> {
+    let Some(map) = attrs.as_object() else {
+        return Ok(());
+    };
+    let mut conflicts = Vec::new();
+    for (key, val) in map.iter().filter(|(_, val)| !val.is_null()) {
+        let Some((_, coltype)) = schema.get(key) else {
+            continue;
+        };
+        let needed = widen_type(*coltype, val)
+            .or_else(|| guess_type(val).filter(|guessed| !holds_kind(*coltype, *guessed)));
+        if let Some(needed) = needed {
+            conflicts.push((key.clone(), *coltype, needed));
+        }
+    }
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        conflicts.sort_by(|a, b| a.0.cmp(&b.0));
+        Err(SchemaConflict { columns: conflicts })
+    }
+}
+
+/// Whether a column of type `column` can hold values guessed as `guessed`, regardless of
+/// their range
+fn holds_kind(column: ColumnType, guessed: ColumnType) -> bool {
+    let numeric = |t: ColumnType| {
+        matches!(
+            t,
+            ColumnType::Byte
+                | ColumnType::UByte
+                | ColumnType::Short
+                | ColumnType::UShort
+                | ColumnType::Int
+                | ColumnType::UInt
+                | ColumnType::Long
+                | ColumnType::ULong
+                | ColumnType::Float
+                | ColumnType::Double
+        )
+    };
+    let text = |t: ColumnType| matches!(t, ColumnType::String | ColumnType::DateTime);
+    column == guessed
+        || column == ColumnType::Json
+        || (numeric(column) && numeric(guessed))
+        || (text(column) && text(guessed))
+}
+
+/// Common type of two column types, `None` if neither can hold the values of the other
+fn promote_type(left: ColumnType, right: ColumnType) -> Option<ColumnType> {
+    match (left, right) {
+        (l, r) if l == r => Some(l),
+        (ColumnType::Int, ColumnType::Long) | (ColumnType::Long, ColumnType::Int) => {
+            Some(ColumnType::Long)
+        }
+        (
+            ColumnType::Int | ColumnType::Long | ColumnType::Float | ColumnType::Double,
+            ColumnType::Int | ColumnType::Long | ColumnType::Float | ColumnType::Double,
+        ) => Some(ColumnType::Double),
+        _ => None,
+    }
+}
+
+/// Wider column type needed to hold `value`, `None` if `current` can already hold it.
+///
+/// Floats promote integer and `Float` columns to `Double`. Integers out of the bounds of the
+/// column promote it to `Long`, or to `ULong` (unsigned columns) and `Double` (signed columns)
+/// when they exceed `i64`. Non-numeric values are left to the encoder.
+fn widen_type(current: ColumnType, value: &Value) -> Option<ColumnType> {
+    let Value::Number(n) = value else {
+        return None;
+    };
+    if n.is_f64() {
+        let v = n.as_f64()?;
+        return match current {
+            ColumnType::Float if (v as f32) as f64 == v => None,
+            ColumnType::Byte
+            | ColumnType::UByte
+            | ColumnType::Short
+            | ColumnType::UShort
+            | ColumnType::Int
+            | ColumnType::UInt
+            | ColumnType::Long
+            | ColumnType::ULong
+            | ColumnType::Float => Some(ColumnType::Double),
+            _ => None,
+        };
+    }
+
+    let fits_i64 = |fits: fn(i64) -> bool| n.as_i64().is_some_and(fits);
+    let fits = match current {
+        ColumnType::Byte => fits_i64(|v| i8::try_from(v).is_ok()),
+        ColumnType::UByte => fits_i64(|v| u8::try_from(v).is_ok()),
+        ColumnType::Short => fits_i64(|v| i16::try_from(v).is_ok()),
+        ColumnType::UShort => fits_i64(|v| u16::try_from(v).is_ok()),
+        ColumnType::Int => fits_i64(|v| i32::try_from(v).is_ok()),
+        ColumnType::UInt => fits_i64(|v| u32::try_from(v).is_ok()),
+        ColumnType::Long => n.is_i64(),
+        ColumnType::ULong => n.is_u64(),
+        // integers are exact in doubles up to 2^53, good enough for attribute values
+        _ => true,
+    };
+    if fits {
+        return None;
+    }
+    let unsigned = matches!(
+        current,
+        ColumnType::UByte | ColumnType::UShort | ColumnType::UInt | ColumnType::ULong
+    );
+    Some(match n.as_i64() {
+        Some(_) => ColumnType::Long,
+        None if unsigned => ColumnType::ULong,
+        None => ColumnType::Double,
+    })
 }
 
 /// Naive type-guessing. You could use your schema or logic as in your Python code.
@@ -218,9 +553,20 @@ pub fn cityfeature_to_index_entries(
     indexing_attr: &[String],
 ) -> Vec<AttributeIndexEntry> {
     let mut index_entries = Vec::new();
+    index_entries.extend(feature_id_index_entry(
+        &cityfeature.id,
+        schema,
+        indexing_attr,
+    ));
+    // the feature ID is not an attribute of the city objects
+    let indexing_attr = indexing_attr
+        .iter()
+        .filter(|name| *name != FEATURE_ID_COLUMN)
+        .cloned()
+        .collect::<Vec<_>>();
     for object in cityfeature.city_objects.values() {
         if let Some(attr) = &object.attributes {
-            let attr_index_entries = attribute_to_index_entries(attr, schema, indexing_attr);
+            let attr_index_entries = attribute_to_index_entries(attr, schema, &indexing_attr);
             index_entries.extend(attr_index_entries);
         }
     }
@@ -228,6 +574,22 @@ pub fn cityfeature_to_index_entries(
     index_entries
 }
 
+/// Entry of the feature ID index for a feature, if [`FEATURE_ID_COLUMN`] is indexed
+pub fn feature_id_index_entry(
+    feature_id: &str,
+    schema: &AttributeSchema,
+    indexing_attr: &[String],
+) -> Option<AttributeIndexEntry> {
+    if !indexing_attr.iter().any(|name| name == FEATURE_ID_COLUMN) {
+        return None;
+    }
+    let (index, _) = schema.get(FEATURE_ID_COLUMN)?;
+    Some(AttributeIndexEntry::String {
+        index: *index,
+        val: feature_id.to_string(),
+    })
+}
+
 // this attr should be a json object with attribute name as key and attribute value as value
 pub fn attribute_to_index_entries(
     attr: &Value,
@@ -260,6 +622,20 @@ pub fn attribute_to_index_entries(
                         val: b,
                     });
                 }
+                ColumnType::Short => {
+                    let i = val.as_i64().unwrap_or(0);
+                    index_entries.push(AttributeIndexEntry::Short {
+                        index: *index,
+                        val: i as i16,
+                    });
+                }
+                ColumnType::UShort => {
+                    let i = val.as_u64().unwrap_or(0);
+                    index_entries.push(AttributeIndexEntry::UShort {
+                        index: *index,
+                        val: i as u16,
+                    });
+                }
                 ColumnType::Int => {
                     let i = val.as_i64().unwrap_or(0);
                     index_entries.push(AttributeIndexEntry::Int {
@@ -384,6 +760,136 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_add_attributes_widening() {
+        let mut attr_schema = AttributeSchema::new();
+        attr_schema.add_attributes(&json!({ "height": 5, "storeys": 3, "id": 1, "name": "a" }));
+        assert_eq!(attr_schema["height"].1, ColumnType::ULong);
+
+        attr_schema.add_attributes(&json!({ "height": 5.7, "storeys": -1, "name": 1 }));
+        assert_eq!(attr_schema["height"].1, ColumnType::Double);
+        assert_eq!(attr_schema["storeys"].1, ColumnType::Long);
+        // non-numeric mismatches are not widened
+        assert_eq!(attr_schema["name"].1, ColumnType::String);
+
+        let mut attr_schema = AttributeSchema::new();
+        attr_schema.insert("count".to_string(), (0, ColumnType::Int));
+        attr_schema.insert("level".to_string(), (1, ColumnType::UByte));
+        attr_schema.insert("ratio".to_string(), (2, ColumnType::Float));
+        attr_schema.add_attributes(&json!({ "count": 42, "level": 200, "ratio": 0.5 }));
+        assert_eq!(attr_schema["count"].1, ColumnType::Int);
+        assert_eq!(attr_schema["level"].1, ColumnType::UByte);
+        assert_eq!(attr_schema["ratio"].1, ColumnType::Float);
+
+        attr_schema.add_attributes(&json!({
+            "count": i32::MAX as i64 + 1,
+            "level": u64::MAX,
+            "ratio": 0.1
+        }));
+        assert_eq!(attr_schema["count"].1, ColumnType::Long);
+        assert_eq!(attr_schema["level"].1, ColumnType::ULong);
+        assert_eq!(attr_schema["ratio"].1, ColumnType::Double);
+    }
+
+    #[test]
+    fn test_add_attributes_after_index_gap() {
+        // e.g. a schema read from a header, without the feature ID column at index 1
+        let mut attr_schema = AttributeSchema::new();
+        attr_schema.insert("height".to_string(), (0, ColumnType::Double));
+        attr_schema.insert("name".to_string(), (2, ColumnType::String));
+        attr_schema.add_attributes(&json!({ "storeys": 3 }));
+        assert_eq!(attr_schema["storeys"].0, 3);
+    }
+
+    #[test]
+    fn test_merge_schema() {
+        let mut left = AttributeSchema::new();
+        left.insert("height".to_string(), (0, ColumnType::Float));
+        left.insert("storeys".to_string(), (1, ColumnType::Int));
+        left.insert("name".to_string(), (2, ColumnType::String));
+
+        let mut right = AttributeSchema::new();
+        right.insert("storeys".to_string(), (0, ColumnType::Long));
+        right.insert("height".to_string(), (1, ColumnType::Double));
+        right.insert("roof".to_string(), (2, ColumnType::String));
+        right.insert("name".to_string(), (3, ColumnType::String));
+
+        let merged = left.merge(&right).unwrap();
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged["height"], (0, ColumnType::Double));
+        assert_eq!(merged["storeys"], (1, ColumnType::Long));
+        assert_eq!(merged["name"], (2, ColumnType::String));
+        assert_eq!(merged["roof"], (3, ColumnType::String));
+
+        let mut other = AttributeSchema::new();
+        other.insert("storeys".to_string(), (0, ColumnType::Double));
+        assert_eq!(left.merge(&other).unwrap()["storeys"].1, ColumnType::Double);
+
+        let mut incompatible = AttributeSchema::new();
+        incompatible.insert("storeys".to_string(), (0, ColumnType::Bool));
+        incompatible.insert("name".to_string(), (1, ColumnType::Int));
+        let conflict = left.merge(&incompatible).unwrap_err();
+        assert_eq!(
+            conflict.columns,
+            vec![
+                ("name".to_string(), ColumnType::String, ColumnType::Int),
+                ("storeys".to_string(), ColumnType::Int, ColumnType::Bool),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_round_trip() -> Result<()> {
+        let mut attr_schema = AttributeSchema::new();
+        for (name, coltype) in [
+            ("height", ColumnType::Double),
+            ("storeys", ColumnType::Int),
+            ("name", ColumnType::String),
+            ("is_public", ColumnType::Bool),
+            ("updated", ColumnType::DateTime),
+            ("extra", ColumnType::Json),
+        ] {
+            attr_schema.insert(name.to_string(), (attr_schema.len() as u16, coltype));
+        }
+
+        let doc = attr_schema.to_json_schema();
+        assert_eq!(doc["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(
+            doc["properties"]["height"]["type"],
+            json!(["number", "null"])
+        );
+        assert_eq!(doc["properties"]["updated"]["format"], "date-time");
+        assert_eq!(doc["properties"]["extra"], json!({}));
+
+        let parsed = AttributeSchema::from_json_schema(&doc)?;
+        assert_eq!(parsed, attr_schema);
+        for (name, (index, coltype)) in attr_schema.iter() {
+            assert_eq!(parsed[name], (*index, *coltype), "column {}", name);
+        }
+
+        let external = json!({
+            "type": "object",
+            "properties": {
+                "d": { "type": "integer" },
+                "b": { "type": "number" },
+                "c": { "type": ["string", "null"] },
+                "a": { "type": "null" },
+            }
+        });
+        let parsed = AttributeSchema::from_json_schema(&external)?;
+        assert_eq!(parsed["d"], (0, ColumnType::Long));
+        assert_eq!(parsed["b"], (1, ColumnType::Double));
+        assert_eq!(parsed["c"], (2, ColumnType::String));
+        assert_eq!(parsed["a"], (3, ColumnType::Json));
+
+        assert!(AttributeSchema::from_json_schema(&json!({ "type": "object" })).is_err());
+        assert!(AttributeSchema::from_json_schema(
+            &json!({ "properties": { "a": { "type": "foo" } } })
+        )
+        .is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_attribute_serialization() -> Result<()> {
         let test_cases = vec![
@@ -487,6 +993,30 @@ mod tests {
                 }),
                 "JSON is empty",
             ),
+            // Case 4: nested objects and arrays
+            (
+                json!({
+                    "measurements": [
+                        {"time": "2024-01-01T00:00:00Z", "values": [1.5, 2.0]},
+                        {"time": "2024-01-02T00:00:00Z", "values": []}
+                    ],
+                    "address": {"street": {"name": "Oude Delft", "numbers": [1, [2, 3]]}, "city": null}
+                }),
+                json!({
+                    "measurements": [
+                        {"time": "2024-01-01T00:00:00Z", "values": [1.5, 2.0]},
+                        {"time": "2024-01-02T00:00:00Z", "values": []}
+                    ],
+                    "address": {"street": {"name": "Oude Delft", "numbers": [1, [2, 3]]}, "city": null}
+                }),
+                json!({
+                    "attributes": {
+                        "measurements": [],
+                        "address": {}
+                    }
+                }),
+                "nested objects and arrays",
+            ),
         ];
 
         for (input, expected, schema, test_name) in test_cases {
@@ -550,7 +1080,7 @@ mod tests {
             let feature_buf = root_as_city_feature(finished_data).unwrap();
             let attributes = feature_buf.objects().unwrap().get(0).attributes().unwrap();
 
-            let decoded = decode_attributes(&header_buf.columns().unwrap(), attributes);
+            let decoded = decode_attributes(&header_buf.columns().unwrap(), attributes).unwrap();
 
             assert_eq!(
                 expected, decoded,
@@ -561,4 +1091,79 @@ mod tests {
 
         Ok(())
     }
+
+    /// Decodes `encoded` attributes with the columns of `schema`
+    fn decode_encoded(
+        schema: &AttributeSchema,
+        encoded: &[u8],
+    ) -> crate::error::Result<serde_json::Value> {
+        let mut header_fbb = FlatBufferBuilder::new();
+        let columns = to_columns(&mut header_fbb, schema);
+        let version = header_fbb.create_string("2.0");
+        let header = Header::create(
+            &mut header_fbb,
+            &HeaderArgs {
+                version: Some(version),
+                columns: Some(columns),
+                ..Default::default()
+            },
+        );
+        header_fbb.finish(header, None);
+        let header = root_as_header(header_fbb.finished_data()).unwrap();
+
+        let mut fbb = FlatBufferBuilder::new();
+        let attributes = fbb.create_vector(encoded);
+        let id = fbb.create_string("co");
+        let city_object = CityObject::create(
+            &mut fbb,
+            &CityObjectArgs {
+                id: Some(id),
+                attributes: Some(attributes),
+                ..Default::default()
+            },
+        );
+        let objects = fbb.create_vector(&[city_object]);
+        let id = fbb.create_string("feature");
+        let feature = CityFeature::create(
+            &mut fbb,
+            &CityFeatureArgs {
+                id: Some(id),
+                objects: Some(objects),
+                ..Default::default()
+            },
+        );
+        fbb.finish(feature, None);
+        let feature = root_as_city_feature(fbb.finished_data()).unwrap();
+        let attributes = feature.objects().unwrap().get(0).attributes().unwrap();
+        decode_attributes(&header.columns().unwrap(), attributes)
+    }
+
+    #[test]
+    fn test_decode_byte_and_binary_columns() -> Result<()> {
+        let mut schema = AttributeSchema::new();
+        schema.insert("byte".to_string(), (0, ColumnType::Byte));
+        schema.insert("ubyte".to_string(), (1, ColumnType::UByte));
+        schema.insert("binary".to_string(), (2, ColumnType::Binary));
+        schema.insert("short".to_string(), (3, ColumnType::Short));
+        let attrs = json!({
+            "byte": -3,
+            "ubyte": 200,
+            "binary": "AQID",
+            "short": 7,
+        });
+
+        let encoded = encode_attributes_with_schema(&attrs, &schema);
+        assert_eq!(decode_encoded(&schema, &encoded)?, attrs);
+
+        // a column type the decoder doesn't know is an error, not a panic
+        let mut unknown = AttributeSchema::new();
+        unknown.insert("unknown".to_string(), (0, ColumnType(200)));
+        let err = decode_encoded(&unknown, &[0, 0, 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::error::Error::InvalidAttributeValue { .. }
+        ));
+
+        Ok(())
+    }
 }
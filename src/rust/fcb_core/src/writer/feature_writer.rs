@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use cjseq::CityJSONFeature;
 
 use crate::serializer::*;
@@ -12,7 +14,7 @@ use crate::packed_rtree::NodeItem;
 /// FlatBuffers representation, which is more efficient for storage and transmission.
 pub struct FeatureWriter<'a> {
     /// The CityJSON feature to be serialized
-    city_feature: &'a CityJSONFeature,
+    city_feature: Cow<'a, CityJSONFeature>,
     /// The FlatBuffers builder instance used for serialization
     fbb: flatbuffers::FlatBufferBuilder<'a>,
     /// The attribute schema to be used for serialization
@@ -46,7 +48,7 @@ impl<'a> FeatureWriter<'a> {
         attr_indices: Option<Vec<String>>,
     ) -> FeatureWriter<'a> {
         FeatureWriter {
-            city_feature,
+            city_feature: Cow::Borrowed(city_feature),
             fbb: flatbuffers::FlatBufferBuilder::new(),
             attr_schema,
             semantic_attr_schema,
@@ -75,7 +77,7 @@ impl<'a> FeatureWriter<'a> {
         let (cf_buf, bbox) = to_fcb_city_feature(
             &mut self.fbb,
             self.city_feature.id.as_str(),
-            self.city_feature,
+            &self.city_feature,
             &self.attr_schema,
             self.semantic_attr_schema.as_ref(),
         );
@@ -92,13 +94,22 @@ impl<'a> FeatureWriter<'a> {
     ///
     /// * `feature` - A reference to the new CityJSON feature
     pub fn add_feature(&mut self, feature: &'a CityJSONFeature) {
-        self.city_feature = feature;
+        self.city_feature = Cow::Borrowed(feature);
+    }
+
+    /// Updates the writer with a new feature owned by the writer, e.g. after quantization
+    ///
+    /// # Arguments
+    ///
+    /// * `feature` - The new CityJSON feature
+    pub fn add_owned_feature(&mut self, feature: CityJSONFeature) {
+        self.city_feature = Cow::Owned(feature);
     }
 
     fn extract_indexable_attributes(&mut self) {
         if let Some(attr_indices) = &self.attr_indices {
             let index_entries =
-                cityfeature_to_index_entries(self.city_feature, &self.attr_schema, attr_indices);
+                cityfeature_to_index_entries(&self.city_feature, &self.attr_schema, attr_indices);
             self.attribute_feature_offsets.index_entries = index_entries;
         }
     }
@@ -5,6 +5,9 @@ use cjseq::{
     TextureReference as CjTextureReference, TextureValues as CjTextureValues,
 };
 use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::reader::geom_decoder::decode;
 
 #[derive(Debug, Clone, Default)]
 pub(crate) struct GMBoundaries {
@@ -60,6 +63,82 @@ pub(crate) struct EncodedGeometry {
     pub(crate) materials: Option<Vec<MaterialMapping>>,
 }
 
+/// Inconsistency found in encoded boundaries
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The counts of a level do not add up to the number of entries of the level below
+    #[error("sum of `{parent}` is {expected}, but `{child}` has {actual} entries")]
+    CountMismatch {
+        parent: &'static str,
+        child: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// Decoding the encoded arrays does not give back the input boundaries
+    #[error("decoded boundaries differ from the input: expected {expected}, got {actual}")]
+    RoundTripMismatch { expected: String, actual: String },
+}
+
+impl GMBoundaries {
+    /// Checks that the count of every level adds up to the number of entries of the level below
+    pub(crate) fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let levels: [(&'static str, &[u32]); 5] = [
+            ("solids", &self.solids),
+            ("shells", &self.shells),
+            ("surfaces", &self.surfaces),
+            ("strings", &self.strings),
+            ("indices", &self.indices),
+        ];
+        let errors = levels
+            .windows(2)
+            .filter(|pair| !pair[0].1.is_empty())
+            .filter_map(|pair| {
+                let ((parent, counts), (child, entries)) = (pair[0], pair[1]);
+                let expected = counts.iter().map(|c| *c as usize).sum::<usize>();
+                (expected != entries.len()).then_some(ValidationError::CountMismatch {
+                    parent,
+                    child,
+                    expected,
+                    actual: entries.len(),
+                })
+            })
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Encodes the boundaries and checks both the consistency of the encoded arrays and that
+/// decoding them gives back the input.
+///
+/// # Returns
+///
+/// Every inconsistency found. The round trip is only checked when the arrays are consistent.
+pub fn validate_encoding(cj_boundaries: &CjBoundaries) -> Result<(), Vec<ValidationError>> {
+    let mut boundaries = GMBoundaries::default();
+    let _ = encode_boundaries(cj_boundaries, &mut boundaries);
+    boundaries.validate()?;
+
+    let decoded = decode(
+        &boundaries.solids,
+        &boundaries.shells,
+        &boundaries.surfaces,
+        &boundaries.strings,
+        &boundaries.indices,
+    );
+    if decoded != *cj_boundaries {
+        return Err(vec![ValidationError::RoundTripMismatch {
+            expected: format!("{cj_boundaries:?}"),
+            actual: format!("{decoded:?}"),
+        }]);
+    }
+    Ok(())
+}
+
 /// Encodes the provided CityJSON boundaries and semantics into flattened arrays.
 ///
 /// # Arguments
@@ -86,6 +165,11 @@ pub(crate) fn encode(
     };
     // Encode the geometric boundaries
     let _ = encode_boundaries(cj_boundaries, &mut boundaries);
+    debug_assert!(
+        boundaries.validate().is_ok(),
+        "inconsistent boundary encoding: {:?}",
+        boundaries.validate()
+    );
 
     // Encode semantics if provided
     let semantics = semantics.map(encode_semantics);
@@ -413,6 +497,101 @@ mod tests {
     use pretty_assertions::assert_eq;
     use serde_json::json;
 
+    #[test]
+    fn test_validate_encoding() -> Result<()> {
+        for boundaries in [
+            json!([2, 44, 0, 7]),
+            json!([[2, 3, 5], [77, 55, 212]]),
+            json!([[[0, 3, 2, 1]], [[4, 5, 6, 7]], [[0, 1, 5, 4]]]),
+            json!([[[[0, 3, 2, 1], [4, 5, 6]]], [[[1, 2, 3]]]]),
+            json!([[[[[0, 3, 2, 1]], [[4, 5, 6, 7]]]], [[[[8, 9, 10]]]]]),
+        ] {
+            let boundaries: CjBoundaries = serde_json::from_value(boundaries)?;
+            assert_eq!(Ok(()), validate_encoding(&boundaries));
+        }
+
+        // rings mixed with surfaces at the same level
+        let ragged: CjBoundaries = serde_json::from_value(json!([[0, 1, 2], [[3, 4, 5]]]))?;
+        let errors = validate_encoding(&ragged).unwrap_err();
+        assert_eq!(
+            vec![
+                ValidationError::CountMismatch {
+                    parent: "shells",
+                    child: "surfaces",
+                    expected: 2,
+                    actual: 1,
+                },
+                ValidationError::CountMismatch {
+                    parent: "surfaces",
+                    child: "strings",
+                    expected: 1,
+                    actual: 2,
+                },
+            ],
+            errors
+        );
+        assert_eq!(
+            "sum of `shells` is 2, but `surfaces` has 1 entries",
+            errors[0].to_string()
+        );
+
+        // an empty collection can't be told apart from an empty ring once encoded
+        let empty: CjBoundaries = serde_json::from_value(json!([]))?;
+        let errors = validate_encoding(&empty).unwrap_err();
+        assert_eq!(
+            vec![ValidationError::RoundTripMismatch {
+                expected: "Nested([])".to_string(),
+                actual: "Indices([])".to_string(),
+            }],
+            errors
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_boundaries() {
+        let valid = GMBoundaries {
+            solids: vec![1],
+            shells: vec![2],
+            surfaces: vec![1, 2],
+            strings: vec![3, 3, 4],
+            indices: (0..10).collect(),
+        };
+        assert_eq!(Ok(()), valid.validate());
+
+        let mismatch = |parent, child, expected, actual| ValidationError::CountMismatch {
+            parent,
+            child,
+            expected,
+            actual,
+        };
+        let invalid = GMBoundaries {
+            solids: vec![2],
+            shells: vec![2],
+            surfaces: vec![1, 1],
+            strings: vec![3, 3, 4],
+            indices: (0..11).collect(),
+        };
+        assert_eq!(
+            Err(vec![
+                mismatch("solids", "shells", 2, 1),
+                mismatch("surfaces", "strings", 2, 3),
+                mismatch("strings", "indices", 10, 11),
+            ]),
+            invalid.validate()
+        );
+
+        // the top-level arrays may be empty, but not the ones below a non-empty level
+        let missing_indices = GMBoundaries {
+            strings: vec![3],
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(vec![mismatch("strings", "indices", 3, 0)]),
+            missing_indices.validate()
+        );
+    }
+
     #[test]
     fn test_encode_boundaries() -> Result<()> {
         // MultiPoint
@@ -0,0 +1,195 @@
+use cjseq::{
+    Boundaries as CjBoundaries, CityJSONFeature, GeometryType as CjGeometryType,
+    Transform as CjTransform,
+};
+
+use crate::error::{Error, Result};
+
+/// Checks that the geometries of a feature can be encoded without producing corrupt output
+///
+/// # Arguments
+///
+/// * `feature` - The CityJSON feature to check
+/// * `transform` - The transform of the dataset, applied to the vertices
+///
+/// # Returns
+///
+/// `Error::InvalidGeometry` describing the first problem found
+pub fn validate_feature(feature: &CityJSONFeature, transform: &CjTransform) -> Result<()> {
+    let invalid = |reason: String| Error::InvalidGeometry {
+        feature_id: feature.id.clone(),
+        reason,
+    };
+
+    for (i, v) in feature.vertices.iter().enumerate() {
+        let finite = v.iter().enumerate().all(|(axis, c)| {
+            let scale = transform.scale.get(axis).copied().unwrap_or(1.0);
+            let translate = transform.translate.get(axis).copied().unwrap_or(0.0);
+            (*c as f64 * scale + translate).is_finite()
+        });
+        if !finite {
+            return Err(invalid(format!("vertex {i} is not finite")));
+        }
+    }
+
+    let num_vertices = feature.vertices.len();
+    for (co_id, co) in feature.city_objects.iter() {
+        for (g, geometry) in co.geometry.iter().flatten().enumerate() {
+            // number of nesting levels above the index arrays
+            let depth = match geometry.thetype {
+                CjGeometryType::MultiSurface | CjGeometryType::CompositeSurface => Some(2),
+                CjGeometryType::Solid => Some(3),
+                CjGeometryType::MultiSolid | CjGeometryType::CompositeSolid => Some(4),
+                _ => None,
+            };
+            let result = match depth {
+                Some(depth) => validate_surfaces(&geometry.boundaries, depth, num_vertices),
+                None => validate_indices(&geometry.boundaries, num_vertices),
+            };
+            result.map_err(|reason| {
+                invalid(format!("city object {co_id}, geometry {g}: {reason}"))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Validates surface-based boundaries, `depth` being the levels of nesting above the rings
+fn validate_surfaces(
+    boundaries: &CjBoundaries,
+    depth: usize,
+    num_vertices: usize,
+) -> std::result::Result<(), String> {
+    match (boundaries, depth) {
+        (CjBoundaries::Indices(ring), 0) => {
+            if ring.len() < 3 {
+                return Err(format!(
+                    "ring has {} vertices, expected at least 3",
+                    ring.len()
+                ));
+            }
+            check_bounds(ring, num_vertices)
+        }
+        // an empty array deserializes as indices, whatever level it is at
+        (CjBoundaries::Indices(indices), depth) if depth > 0 && indices.is_empty() => {
+            Err(empty_level(depth))
+        }
+        (CjBoundaries::Nested(children), depth) if depth > 0 => {
+            if children.is_empty() {
+                return Err(empty_level(depth));
+            }
+            children
+                .iter()
+                .try_for_each(|child| validate_surfaces(child, depth - 1, num_vertices))
+        }
+        _ => Err("unexpected boundary nesting".to_string()),
+    }
+}
+
+fn empty_level(depth: usize) -> String {
+    match depth {
+        1 => "surface has no rings".to_string(),
+        2 => "shell has no surfaces".to_string(),
+        3 => "solid has no shells".to_string(),
+        _ => "empty boundaries".to_string(),
+    }
+}
+
+/// Validates the vertex indices of any boundaries
+fn validate_indices(
+    boundaries: &CjBoundaries,
+    num_vertices: usize,
+) -> std::result::Result<(), String> {
+    match boundaries {
+        CjBoundaries::Indices(indices) => check_bounds(indices, num_vertices),
+        CjBoundaries::Nested(children) => children
+            .iter()
+            .try_for_each(|child| validate_indices(child, num_vertices)),
+    }
+}
+
+fn check_bounds(indices: &[u32], num_vertices: usize) -> std::result::Result<(), String> {
+    match indices.iter().find(|i| **i as usize >= num_vertices) {
+        Some(i) => Err(format!(
+            "vertex index {i} out of bounds ({num_vertices} vertices)"
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn feature(geometry: serde_json::Value, vertices: serde_json::Value) -> CityJSONFeature {
+        serde_json::from_value(serde_json::json!({
+            "type": "CityJSONFeature",
+            "id": "f1",
+            "CityObjects": {
+                "b1": {"type": "Building", "geometry": [geometry]}
+            },
+            "vertices": vertices
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_feature() -> Result<()> {
+        let transform: CjTransform = serde_json::from_value(serde_json::json!({
+            "scale": [0.001, 0.001, 0.001],
+            "translate": [0.0, 0.0, 0.0]
+        }))?;
+        let vertices = serde_json::json!([[0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 0]]);
+
+        let valid = feature(
+            serde_json::json!({"type": "MultiSurface", "lod": "1", "boundaries": [[[0, 1, 2, 3]]]}),
+            vertices.clone(),
+        );
+        validate_feature(&valid, &transform)?;
+
+        let cases = [
+            (
+                serde_json::json!({"type": "MultiSurface", "lod": "1", "boundaries": [[[0, 1]]]}),
+                "ring has 2 vertices",
+            ),
+            (
+                serde_json::json!({"type": "MultiSurface", "lod": "1", "boundaries": [[]]}),
+                "surface has no rings",
+            ),
+            (
+                serde_json::json!({"type": "Solid", "lod": "1", "boundaries": []}),
+                "solid has no shells",
+            ),
+            (
+                serde_json::json!({"type": "MultiSurface", "lod": "1", "boundaries": [[[0, 1, 7]]]}),
+                "vertex index 7 out of bounds",
+            ),
+            (
+                serde_json::json!({"type": "MultiPoint", "lod": "1", "boundaries": [0, 4]}),
+                "vertex index 4 out of bounds",
+            ),
+        ];
+        for (geometry, reason) in cases {
+            let err = validate_feature(&feature(geometry, vertices.clone()), &transform)
+                .expect_err(reason);
+            match err {
+                Error::InvalidGeometry {
+                    feature_id,
+                    reason: msg,
+                } => {
+                    assert_eq!(feature_id, "f1");
+                    assert!(msg.contains(reason), "{msg}");
+                }
+                e => panic!("unexpected error {e}"),
+            }
+        }
+
+        let infinite: CjTransform = serde_json::from_value(serde_json::json!({
+            "scale": [1e308, 1.0, 1.0],
+            "translate": [1e308, 0.0, 0.0]
+        }))?;
+        assert!(validate_feature(&valid, &infinite).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use cjseq::{CityJSONFeature, Transform as CjTransform};
+use ordered_float::OrderedFloat;
+
+use super::shared_vertices::remap_boundaries;
+use crate::error::{Error, Result};
+
+/// Coordinates of a vertex after applying the transform, the key of the global vertex table
+pub type VertexKey = [OrderedFloat<f64>; 3];
+
+fn vertex_key(vertex: &[i64], transform: &CjTransform) -> VertexKey {
+    [0, 1, 2].map(|axis| {
+        OrderedFloat(vertex[axis] as f64 * transform.scale[axis] + transform.translate[axis])
+    })
+}
+
+/// Assigns a global ID to every distinct vertex of the features, in the order of their first
+/// occurrence
+///
+/// The result is meant for [`FcbWriter::with_global_vertices`](super::FcbWriter::with_global_vertices),
+/// `transform` must be the transform of the features.
+pub fn global_vertex_ids<'f>(
+    features: impl IntoIterator<Item = &'f CityJSONFeature>,
+    transform: &CjTransform,
+) -> HashMap<VertexKey, u32> {
+    let mut ids = HashMap::new();
+    for feature in features {
+        for vertex in &feature.vertices {
+            let next_id = ids.len() as u32;
+            ids.entry(vertex_key(vertex, transform)).or_insert(next_id);
+        }
+    }
+    ids
+}
+
+/// Returns a copy of the feature without vertices, whose boundaries index the global vertex
+/// table instead
+///
+/// Vertices are looked up by their coordinates after applying `transform`.
+pub(super) fn globalize_vertices(
+    feature: &CityJSONFeature,
+    global_ids: &HashMap<VertexKey, u32>,
+    transform: &CjTransform,
+) -> Result<CityJSONFeature> {
+    let remap = feature
+        .vertices
+        .iter()
+        .map(|vertex| {
+            let key = vertex_key(vertex, transform);
+            global_ids.get(&key).copied().ok_or_else(|| {
+                Error::GlobalVertices(format!(
+                    "vertex {:?} of feature {} has no global ID",
+                    key.map(OrderedFloat::into_inner),
+                    feature.id
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut global = feature.clone();
+    global.vertices.clear();
+    for co in global.city_objects.values_mut() {
+        for geometry in co.geometry.iter_mut().flatten() {
+            remap_boundaries(&mut geometry.boundaries, &remap);
+        }
+    }
+    Ok(global)
+}
+
+/// Flat x, y, z of the global vertices in the order of their IDs, quantized with `transform`
+///
+/// IDs missing from `global_ids` are left as zeros, no boundary references them.
+pub(super) fn global_vertex_table(
+    global_ids: &HashMap<VertexKey, u32>,
+    transform: &CjTransform,
+) -> Vec<i32> {
+    let len = global_ids.values().max().map_or(0, |max| *max as usize + 1);
+    let mut table = vec![0; 3 * len];
+    for (key, id) in global_ids {
+        for axis in 0..3 {
+            table[3 * *id as usize + axis] = ((key[axis].into_inner() - transform.translate[axis])
+                / transform.scale[axis])
+                .round() as i32;
+        }
+    }
+    table
+}
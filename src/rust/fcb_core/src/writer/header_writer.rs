@@ -1,10 +1,14 @@
+use crate::const_vars::CONTENT_HASH_SIZE;
 use crate::error::Result;
 use crate::packed_rtree::PackedRTree;
 use crate::serializer::to_fcb_header;
 use cjseq::CityJSON;
 use flatbuffers::FlatBufferBuilder;
 
-use super::{attribute::AttributeSchema, serializer::AttributeIndexInfo};
+use super::{
+    attribute::AttributeSchema,
+    serializer::{AttributeIndexInfo, CompositeIndexInfo},
+};
 
 /// Writer for converting CityJSON header information to FlatBuffers format
 pub struct HeaderWriter<'a> {
@@ -22,6 +26,12 @@ pub struct HeaderWriter<'a> {
     pub semantic_attr_schema: Option<AttributeSchema>,
     /// Attribute indices
     pub(super) attribute_indices_info: Option<Vec<AttributeIndexInfo>>,
+    /// Composite indices
+    pub(super) composite_indices_info: Option<Vec<CompositeIndexInfo>>,
+    /// SHA-256 of the feature section
+    pub(super) content_hash: Option<[u8; CONTENT_HASH_SIZE]>,
+    /// Flat x, y, z of the vertices shared by all features, quantized with the transform
+    pub(super) global_vertices: Option<Vec<i32>>,
 }
 
 /// Configuration options for header writing process
@@ -34,8 +44,22 @@ pub struct HeaderWriterOptions {
     pub index_node_size: u16,
     /// Attribute indices
     pub attribute_indices: Option<Vec<(String, Option<u16>)>>, // (field name, branching factor)
+    /// Composite indices over several attributes, for equality queries on all of them at once
+    pub composite_indices: Option<Vec<(Vec<String>, Option<u16>)>>, // (field names in key order, branching factor)
     /// Geographical extent
     pub geographical_extent: Option<[f64; 6]>,
+    /// Number of decimal digits kept in the coordinates, the vertices are quantized to a scale
+    /// of `10^-digits` which is stored in the header transform
+    pub coordinate_precision: Option<u8>,
+    /// Whether to append a CRC-32 after each feature
+    pub checksum: bool,
+    /// Whether to merge identical vertices of a feature, shared by the geometries of all LODs
+    pub shared_vertices: bool,
+    /// Whether to index the feature IDs, for lookups with `FcbReader::find_by_id`
+    pub feature_id_index: bool,
+    /// Whether to store a SHA-256 of the feature section, checked with
+    /// `FcbReader::verify_hash`
+    pub compute_hash: bool,
 }
 
 impl Default for HeaderWriterOptions {
@@ -46,6 +70,12 @@ impl Default for HeaderWriterOptions {
             feature_count: 0,
             attribute_indices: None,
             geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
         }
     }
 }
@@ -97,6 +127,9 @@ impl<'a> HeaderWriter<'a> {
             attr_schema,
             semantic_attr_schema,
             attribute_indices_info: None,
+            composite_indices_info: None,
+            content_hash: None,
+            global_vertices: None,
         }
     }
 
@@ -116,6 +149,12 @@ impl<'a> HeaderWriter<'a> {
                 .as_ref()
                 .filter(|info| !info.is_empty())
                 .map(|info| info.as_slice()),
+            self.composite_indices_info
+                .as_ref()
+                .filter(|info| !info.is_empty())
+                .map(|info| info.as_slice()),
+            self.content_hash.as_ref(),
+            self.global_vertices.as_deref(),
         )?;
         self.fbb.finish_size_prefixed(header, None);
         Ok(self.fbb.finished_data().to_vec())
@@ -1,23 +1,58 @@
-use crate::packed_rtree::{calc_extent, hilbert_sort, NodeItem, PackedRTree};
-use crate::MAGIC_BYTES;
-use attr_index::build_attribute_index_for_attr;
-use attribute::AttributeSchema;
+use crate::checksum::crc32;
+use crate::deserializer::to_cj_metadata;
+use crate::fb::{size_prefixed_root_as_city_feature, size_prefixed_root_as_header, ColumnType};
+use crate::packed_rtree::{calc_extent, hilbert_sort, NodeItem, NodeLayout, PackedRTree};
+use crate::progress::{ProgressCallback, ProgressEvent};
+use crate::{CHECKSUM_SIZE, CONTENT_HASH_SIZE, FEATURE_ID_COLUMN, MAGIC_BYTES};
+use attr_index::{
+    build_attribute_index_for_attr, build_composite_index, estimate_attribute_index,
+    estimate_composite_index,
+};
+use attribute::{check_attributes, AttributeSchema};
 use cjseq::{CityJSON, CityJSONFeature, Transform as CjTransform};
 use feature_writer::{AttributeFeatureOffset, FeatureWriter};
 use header_writer::{HeaderWriter, HeaderWriterOptions};
-use serializer::AttributeIndexInfo;
+use quantizer::Quantizer;
+use serializer::{AttributeIndexInfo, CompositeIndexInfo};
+use sha2::{Digest, Sha256};
+use shared_vertices::share_vertices;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
 mod attr_index;
 pub mod attribute;
 pub mod error;
 pub mod feature_writer;
 pub mod geom_encoder;
+pub mod geom_validator;
+#[cfg(feature = "global-vertices")]
+pub mod global_vertices;
 pub mod header_writer;
+pub mod quantizer;
+pub mod reindex;
 pub mod serializer;
+pub mod shared_vertices;
+
+/// Storage for serialized features before they are sorted into the output.
+/// wasm32 has no filesystem, so features are kept in memory there.
+#[cfg(not(target_arch = "wasm32"))]
+type TempStorage = std::fs::File;
+#[cfg(target_arch = "wasm32")]
+type TempStorage = std::io::Cursor<Vec<u8>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn temp_storage() -> Result<TempStorage> {
+    Ok(tempfile::tempfile()?)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn temp_storage() -> Result<TempStorage> {
+    Ok(std::io::Cursor::new(Vec::new()))
+}
+
 /// Main writer for FlatCityBuf (FCB) format
 ///
 /// FcbWriter handles the serialization of CityJSON data into the FCB binary format.
@@ -25,7 +60,7 @@ pub mod serializer;
 /// before final assembly.
 pub struct FcbWriter<'a> {
     /// Temporary buffer for storing features before final assembly
-    tmpout: BufWriter<File>,
+    tmpout: BufWriter<TempStorage>,
     /// Writer for the FCB header section
     header_writer: HeaderWriter<'a>,
     /// Optional writer for features
@@ -39,6 +74,29 @@ pub struct FcbWriter<'a> {
     semantic_attr_schema: Option<AttributeSchema>,
     // temporary storage for attribute index entries
     attribute_index_entries: HashMap<usize, AttributeFeatureOffset>,
+    /// Optional callback reporting progress while writing features to the output
+    progress: Option<ProgressCallback>,
+    /// Whether to check geometries before adding features
+    validate_geometry: bool,
+    /// Quantizes the vertices of added features when a coordinate precision is set
+    quantizer: Option<Quantizer>,
+    /// Whether the features of an existing file were copied by [`FcbWriter::open_for_append`]
+    appending: bool,
+    /// Global IDs of the vertices of all features, see [`FcbWriter::with_global_vertices`]
+    #[cfg(feature = "global-vertices")]
+    global_vertices: Option<HashMap<global_vertices::VertexKey, u32>>,
+}
+
+/// Adds the [`FEATURE_ID_COLUMN`] string column to the schema and indexes it
+fn add_feature_id_index(options: &mut HeaderWriterOptions, attr_schema: &mut AttributeSchema) {
+    if !attr_schema.contains_key(FEATURE_ID_COLUMN) {
+        let index = attribute::next_column_index(attr_schema);
+        attr_schema.insert(FEATURE_ID_COLUMN.to_string(), (index, ColumnType::String));
+    }
+    let indices = options.attribute_indices.get_or_insert_with(Vec::new);
+    if !indices.iter().any(|(name, _)| name == FEATURE_ID_COLUMN) {
+        indices.push((FEATURE_ID_COLUMN.to_string(), None));
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -66,7 +124,11 @@ impl<'a> FcbWriter<'a> {
         attr_schema: Option<AttributeSchema>,
         semantic_attr_schema: Option<AttributeSchema>,
     ) -> Result<Self> {
-        let attr_schema = attr_schema.unwrap_or_default();
+        let mut header_option = header_option;
+        let mut attr_schema = attr_schema.unwrap_or_default();
+        if let Some(options) = header_option.as_mut().filter(|o| o.feature_id_index) {
+            add_feature_id_index(options, &mut attr_schema);
+        }
 
         let transform = cj.transform.clone();
         let header_writer = HeaderWriter::new(
@@ -75,41 +137,326 @@ impl<'a> FcbWriter<'a> {
             attr_schema.clone(),
             semantic_attr_schema.clone(),
         );
-        Ok(Self {
+        let precision = header_writer.header_options.coordinate_precision;
+        let writer = Self {
             header_writer,
             transform,
             feat_writer: None,
-            tmpout: BufWriter::new(tempfile::tempfile()?),
+            tmpout: BufWriter::new(temp_storage()?),
             attr_schema,
             semantic_attr_schema,
             feat_offsets: Vec::new(),
             feat_nodes: Vec::new(),
             attribute_index_entries: HashMap::new(),
+            progress: None,
+            validate_geometry: false,
+            quantizer: None,
+            appending: false,
+            #[cfg(feature = "global-vertices")]
+            global_vertices: None,
+        };
+        Ok(match precision {
+            Some(digits) => writer.coordinate_precision(digits),
+            None => writer,
         })
     }
 
+    /// Opens an existing FCB file to add features, rebuilding its indices over all features
+    ///
+    /// The existing features are copied into the writer without re-encoding them. Their bounding boxes are read from the R-tree of the file, or
+    /// computed from their vertices if it has none, and the values of the indexed attributes
+    /// are decoded. [`FcbWriter::write`] then sorts the existing and added features along the
+    /// Hilbert curve for a new R-tree and rebuilds the attribute and composite indices of the
+    /// file. Write with [`FcbWriter::write_atomic`] to replace the file itself.
+    ///
+    /// Added features are encoded with the transform, attribute columns, checksum and shared
+    /// vertices settings of the file. Attributes that are not a column of the file are dropped,
+    /// [`FcbWriter::add_feature`] fails on values that don't fit the type of their column.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The FCB file to append to
+    pub fn open_for_append(path: &Path) -> Result<Self> {
+        Self::open_existing(path, true)
+    }
+
+    /// Opens an existing FCB file to add features after the ones it contains, without indices
+    ///
+    /// A variant of [`FcbWriter::open_for_append`] for when the indices are rebuilt later, e.g.
+    /// with `fcb index`: [`FcbWriter::write`] outputs the existing features in file order
+    /// followed by the added features in the order they were added, without the spatial and
+    /// attribute indices, and sets `index_node_size` to 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The FCB file to append to
+    pub fn open_append(path: &Path) -> Result<Self> {
+        Self::open_existing(path, false)
+    }
+
+    /// Copies the features of the FCB file at `path` into a new writer with its settings,
+    /// keeping its indices if `with_indices` is set
+    fn open_existing(path: &Path, with_indices: bool) -> Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let file_len = file.get_ref().metadata()?.len();
+        let (magic_bytes, header_buf) = reindex::read_header_buf(&mut file)?;
+        let header = size_prefixed_root_as_header(&header_buf)?;
+        reindex::check_no_global_vertices(&header)?;
+        let (rtree_size, attr_index_size, composite_index_size) =
+            reindex::index_sizes(&header, &magic_bytes);
+        let index_start = (magic_bytes.len() + header_buf.len()) as u64;
+        let feature_start = index_start + rtree_size + attr_index_size + composite_index_size;
+
+        let cj = to_cj_metadata(&header)?;
+        let attr_schema = attribute::schema_from_columns(header.columns());
+        let semantic_attr_schema = header
+            .semantic_columns()
+            .map(|cols| attribute::schema_from_columns(Some(cols)));
+        let indices = if with_indices {
+            reindex::index_definitions(&header, &attr_schema)?
+        } else {
+            reindex::IndexDefinitions::default()
+        };
+        let header_options = HeaderWriterOptions {
+            write_index: with_indices && header.index_node_size() > 0,
+            feature_count: header.features_count(),
+            attribute_indices: (!indices.attribute_indices.is_empty()).then(|| {
+                indices
+                    .attribute_indices
+                    .iter()
+                    .map(|(_, name, bf)| (name.clone(), Some(*bf)))
+                    .collect()
+            }),
+            composite_indices: (!indices.composite_indices.is_empty()).then(|| {
+                indices
+                    .composite_indices
+                    .iter()
+                    .map(|(columns, bf)| (columns.clone(), Some(*bf)))
+                    .collect()
+            }),
+            checksum: header.checksum(),
+            shared_vertices: header.shared_vertices(),
+            compute_hash: header.content_hash().is_some(),
+            ..Default::default()
+        };
+
+        let mut writer = Self::new(
+            cj,
+            Some(header_options),
+            Some(attr_schema),
+            semantic_attr_schema,
+        )?;
+        writer.appending = true;
+
+        // the leaves of the R-tree are in the order of the features
+        let leaves = if rtree_size > 0 {
+            file.seek(SeekFrom::Start(index_start))?;
+            let tree = PackedRTree::from_buf(
+                &mut file,
+                header.features_count() as usize,
+                header.index_node_size(),
+                NodeLayout::for_version(magic_bytes[3]),
+            )?;
+            Some(tree.leaf_nodes().to_vec())
+        } else {
+            None
+        };
+
+        let attr_names = writer.indexed_attributes().unwrap_or_default();
+        let checksum_size = if header.checksum() { CHECKSUM_SIZE } else { 0 };
+        let mut feature_buf = Vec::with_capacity(2048);
+        file.seek(SeekFrom::Start(feature_start))?;
+        while file.stream_position()? < file_len {
+            reindex::read_feature_buf(&mut file, &mut feature_buf, checksum_size)?;
+            let feature = size_prefixed_root_as_city_feature(
+                &feature_buf[..feature_buf.len() - checksum_size],
+            )?;
+            let temp_feature_id = writer.feat_offsets.len();
+            let mut node = match &leaves {
+                Some(leaves) => leaves.get(temp_feature_id).cloned().ok_or_else(|| {
+                    Error::IndexCreationError(
+                        "the R-tree has fewer items than the file has features".to_string(),
+                    )
+                })?,
+                None => {
+                    let mut bbox = NodeItem::create(0);
+                    for v in feature.vertices().iter().flat_map(|v| v.iter()) {
+                        bbox.expand_xyz(v.x() as f64, v.y() as f64, v.z() as f64);
+                    }
+                    Self::actual_bbox(&writer.transform, &bbox)
+                }
+            };
+            node.offset = temp_feature_id as u64;
+            writer.feat_nodes.push(node);
+
+            let offset = writer.current_feature_offset() as usize;
+            let index_entries = if attr_names.is_empty() {
+                Vec::new()
+            } else {
+                reindex::feature_index_entries(&feature, &header, &writer.attr_schema, &attr_names)?
+            };
+            writer.attribute_index_entries.insert(
+                temp_feature_id,
+                AttributeFeatureOffset {
+                    offset,
+                    size: feature_buf.len(),
+                    index_entries,
+                },
+            );
+            writer.feat_offsets.push(FeatureOffset {
+                temp_feature_id,
+                offset,
+                size: feature_buf.len(),
+            });
+            writer.tmpout.write_all(&feature_buf)?;
+        }
+        Ok(writer)
+    }
+
+    /// Writes a copy of an FCB file with a spatial index built from its features
+    ///
+    /// Meant for files written without `write_index`, e.g. by a streaming ingest. Bounding
+    /// boxes are computed with [`PackedRTree::build_from_features`] and the features are copied
+    /// without re-encoding them, in the Hilbert order of the new R-tree. The attribute and
+    /// composite indices of the file are rebuilt for the new feature offsets, and its content
+    /// hash, if any, is recomputed over the reordered features.
+    ///
+    /// # Arguments
+    ///
+    /// * `original` - The FCB file to index
+    /// * `out` - Destination of the indexed copy
+    pub fn write_with_rebuilt_index(original: impl Read + Seek, out: impl Write) -> Result<()> {
+        reindex::rebuild_spatial_index(original, out)
+    }
+
+    /// Registers a callback that is invoked after each feature is written by [`FcbWriter::write`]
+    ///
+    /// # Arguments
+    ///
+    /// * `cb` - Callback receiving a [`ProgressEvent`]
+    pub fn on_progress(mut self, cb: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(cb));
+        self
+    }
+
+    /// Enables geometry validation in [`FcbWriter::add_feature`]
+    ///
+    /// Features with malformed geometries (short rings, empty surfaces or solids, out of range
+    /// vertex indices, non-finite coordinates) are rejected with `Error::InvalidGeometry`.
+    pub fn validate_geometry(mut self, validate: bool) -> Self {
+        self.validate_geometry = validate;
+        self
+    }
+
+    /// Quantizes the coordinates of the added features to `digits` decimal digits
+    ///
+    /// The scale of the transform becomes `10^-digits`, the translate is kept, and every vertex
+    /// is rounded to the nearest representable integer. Must be set before adding features.
+    ///
+    /// # Arguments
+    ///
+    /// * `digits` - Number of decimal digits to keep, e.g. 3 for millimetres in a metric CRS
+    pub fn coordinate_precision(mut self, digits: u8) -> Self {
+        let source = self
+            .quantizer
+            .as_ref()
+            .map_or(&self.transform, |q| q.source_transform())
+            .clone();
+        let quantizer = Quantizer::new(&source, digits);
+        self.transform = quantizer.target_transform().clone();
+        self.header_writer.cj.transform = self.transform.clone();
+        self.header_writer.header_options.coordinate_precision = Some(digits);
+        self.quantizer = Some(quantizer);
+        self
+    }
+
+    /// Appends a CRC-32 after each feature, verified by the readers
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to write the checksums
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.header_writer.header_options.checksum = enabled;
+        self
+    }
+
+    /// Merges identical vertices of each added feature, so that the geometries of all LODs of
+    /// its city objects index a single vertex array without duplicates
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to merge the vertices, recorded in the header
+    pub fn shared_vertices(mut self, enabled: bool) -> Self {
+        self.header_writer.header_options.shared_vertices = enabled;
+        self
+    }
+
+    /// Stores the vertices of all features once, in a table in the header, instead of in each
+    /// feature
+    ///
+    /// Adjacent features share the vertices of their party walls and common edges. The
+    /// boundaries of added features index the table by global ID and the features are written
+    /// without vertices, the readers copy the vertices of each feature back from the table.
+    /// Every vertex of an added feature must have an ID, [`FcbWriter::add_feature`] fails
+    /// otherwise. Files written this way can't be appended to or selected by polygon.
+    ///
+    /// # Arguments
+    ///
+    /// * `global_vertices` - Global ID of each vertex by its coordinates after applying the
+    ///   transform (and the coordinate precision), e.g. from
+    ///   [`global_vertex_ids`](global_vertices::global_vertex_ids). IDs should be dense from 0,
+    ///   the table is as long as the largest ID.
+    #[cfg(feature = "global-vertices")]
+    pub fn with_global_vertices(
+        mut self,
+        global_vertices: HashMap<global_vertices::VertexKey, u32>,
+    ) -> Self {
+        self.global_vertices = Some(global_vertices);
+        self
+    }
+
+    /// Flat vertex table of [`FcbWriter::with_global_vertices`], quantized with the transform
+    #[cfg(feature = "global-vertices")]
+    fn global_vertex_table(&self) -> Option<Vec<i32>> {
+        self.global_vertices
+            .as_ref()
+            .map(|ids| global_vertices::global_vertex_table(ids, &self.transform))
+    }
+
+    #[cfg(not(feature = "global-vertices"))]
+    fn global_vertex_table(&self) -> Option<Vec<i32>> {
+        None
+    }
+
     /// Writes the current feature to the temporary buffer
     ///
+    /// # Arguments
+    ///
+    /// * `vertex_bbox` - Bounding box of the vertices of the feature in integer coordinates,
+    ///   computed from the encoded vertices when not given
+    ///
     /// # Returns
     ///
     /// A Result indicating success or failure of the write operation
-    fn write_feature(&mut self) -> Result<()> {
+    fn write_feature(&mut self, vertex_bbox: Option<NodeItem>) -> Result<()> {
         let transform = &self.transform;
+        let checksum = self.header_writer.header_options.checksum;
 
         if let Some(feat_writer) = &mut self.feat_writer {
-            let feat_buf = feat_writer.finish_to_feature();
+            let mut feat_buf = feat_writer.finish_to_feature();
+            if checksum {
+                let crc = crc32(&feat_buf);
+                feat_buf.extend_from_slice(&crc.to_le_bytes());
+            }
 
             let mut attr_feature_offset = feat_writer.attribute_feature_offsets.clone();
 
-            let mut node = Self::actual_bbox(transform, &feat_writer.bbox);
+            let mut node =
+                Self::actual_bbox(transform, vertex_bbox.as_ref().unwrap_or(&feat_writer.bbox));
             node.offset = self.feat_offsets.len() as u64;
             self.feat_nodes.push(node);
 
-            let tempoffset = self
-                .feat_offsets
-                .last()
-                .map(|it| it.offset + it.size)
-                .unwrap_or(0);
+            let tempoffset = self.current_feature_offset() as usize;
 
             attr_feature_offset.offset = tempoffset;
             self.attribute_index_entries
@@ -126,16 +473,100 @@ impl<'a> FcbWriter<'a> {
         Ok(())
     }
 
+    /// Byte offset at which the next added feature starts, relative to the feature section
+    ///
+    /// Features are written in the order they are added only without a spatial index, otherwise
+    /// they are sorted along a Hilbert curve by [`FcbWriter::write`] and their final offsets are
+    /// the ones stored in the R-tree.
+    pub fn current_feature_offset(&self) -> u64 {
+        self.feat_offsets
+            .last()
+            .map_or(0, |it| (it.offset + it.size) as u64)
+    }
+
+    /// Estimated size in bytes of the output of [`FcbWriter::write`] for the features added so far
+    ///
+    /// The feature section is the size of the serialized features and the spatial index size is
+    /// exact. The attribute indices are not built, their size is derived from the number of
+    /// indexed values, so the estimate is off when many keys are duplicated.
+    pub fn estimate_size(&self) -> u64 {
+        let options = &self.header_writer.header_options;
+
+        let rtree_size = if options.index_node_size > 0 && !self.feat_nodes.is_empty() {
+            PackedRTree::index_size(
+                self.feat_nodes.len(),
+                options.index_node_size,
+                NodeLayout::Xyz,
+            )
+        } else {
+            0
+        };
+
+        let attr_index_info = options
+            .attribute_indices
+            .iter()
+            .flatten()
+            .filter_map(|(name, bf_opt)| {
+                estimate_attribute_index(
+                    name,
+                    &self.attr_schema,
+                    &self.attribute_index_entries,
+                    bf_opt.unwrap_or(crate::static_btree::DEFAULT_BRANCHING_FACTOR),
+                )
+            })
+            .collect::<Vec<_>>();
+        let composite_index_info = options
+            .composite_indices
+            .iter()
+            .flatten()
+            .filter_map(|(columns, bf_opt)| {
+                estimate_composite_index(
+                    columns,
+                    &self.attr_schema,
+                    &self.attribute_index_entries,
+                    bf_opt.unwrap_or(crate::static_btree::DEFAULT_BRANCHING_FACTOR),
+                )
+            })
+            .collect::<Vec<_>>();
+        let attr_index_size = attr_index_info
+            .iter()
+            .map(|info| info.length as usize)
+            .chain(composite_index_info.iter().map(|info| info.length as usize))
+            .sum::<usize>();
+
+        // the header only depends on the index metadata, so it is serialized as it would be
+        let mut header_writer = HeaderWriter::new(
+            self.header_writer.cj.clone(),
+            Some(options.clone()),
+            self.attr_schema.clone(),
+            self.semantic_attr_schema.clone(),
+        );
+        header_writer.attribute_indices_info = Some(attr_index_info);
+        header_writer.composite_indices_info = Some(composite_index_info);
+        if options.compute_hash {
+            header_writer.content_hash = Some([0; CONTENT_HASH_SIZE]);
+        }
+        header_writer.global_vertices = self.global_vertex_table();
+        let header_size = header_writer.finish_to_header().map_or(0, |buf| buf.len());
+
+        (MAGIC_BYTES.len() + header_size + rtree_size + attr_index_size) as u64
+            + self.current_feature_offset()
+    }
+
     fn actual_bbox(transform: &CjTransform, bbox: &NodeItem) -> NodeItem {
         let scale_x = transform.scale[0];
         let scale_y = transform.scale[1];
+        let scale_z = transform.scale[2];
         let translate_x = transform.translate[0];
         let translate_y = transform.translate[1];
-        NodeItem::new(
+        let translate_z = transform.translate[2];
+        NodeItem::bounds_3d(
             bbox.min_x * scale_x + translate_x,
             bbox.min_y * scale_y + translate_y,
+            bbox.min_z * scale_z + translate_z,
             bbox.max_x * scale_x + translate_x,
             bbox.max_y * scale_y + translate_y,
+            bbox.max_z * scale_z + translate_z,
         )
     }
 
@@ -149,27 +580,90 @@ impl<'a> FcbWriter<'a> {
     ///
     /// A Result indicating success or failure of the operation
     pub fn add_feature(&mut self, feature: &'a CityJSONFeature) -> Result<()> {
+        if self.appending {
+            // existing features are encoded with the columns of the file, they can't be widened
+            let attributes = feature.city_objects.values().flat_map(|co| &co.attributes);
+            for attributes in attributes {
+                check_attributes(&self.attr_schema, attributes).map_err(|conflict| {
+                    Error::InvalidAttributeValue {
+                        msg: format!("feature {}: {conflict}", feature.id),
+                    }
+                })?;
+            }
+        }
+        if self.validate_geometry {
+            let transform = self
+                .quantizer
+                .as_ref()
+                .map_or(&self.transform, |q| q.source_transform());
+            geom_validator::validate_feature(feature, transform)?;
+        }
         if self.feat_writer.is_none() {
             self.feat_writer = Some(FeatureWriter::new(
                 feature,
                 self.attr_schema.clone(),
                 self.semantic_attr_schema.clone(),
-                self.header_writer
-                    .header_options
-                    .attribute_indices
-                    .as_ref()
-                    .map(|a| a.iter().map(|(name, _)| name.clone()).collect()),
+                self.indexed_attributes(),
             ));
         }
 
         if let Some(feat_writer) = &mut self.feat_writer {
-            feat_writer.add_feature(feature);
-            self.write_feature()?;
+            let mut owned = self.quantizer.as_ref().map(|q| q.quantize_feature(feature));
+            if self.header_writer.header_options.shared_vertices {
+                owned = Some(share_vertices(owned.as_ref().unwrap_or(feature)));
+            }
+            #[allow(unused_mut)]
+            let mut vertex_bbox = None;
+            #[cfg(feature = "global-vertices")]
+            if let Some(global_ids) = &self.global_vertices {
+                // the encoded feature has no vertices to compute its bounding box from
+                let local = owned.as_ref().unwrap_or(feature);
+                let mut bbox = NodeItem::create(0);
+                for v in &local.vertices {
+                    bbox.expand_xyz(v[0] as f64, v[1] as f64, v[2] as f64);
+                }
+                vertex_bbox = Some(bbox);
+                owned = Some(global_vertices::globalize_vertices(
+                    local,
+                    global_ids,
+                    &self.transform,
+                )?);
+            }
+            match owned {
+                Some(owned) => feat_writer.add_owned_feature(owned),
+                None => feat_writer.add_feature(feature),
+            }
+            self.write_feature(vertex_bbox)?;
         }
 
         Ok(())
     }
 
+    /// Names of the attributes used by any single-column or composite index
+    fn indexed_attributes(&self) -> Option<Vec<String>> {
+        let options = &self.header_writer.header_options;
+        if options.attribute_indices.is_none() && options.composite_indices.is_none() {
+            return None;
+        }
+        let mut names: Vec<String> = Vec::new();
+        let single = options
+            .attribute_indices
+            .iter()
+            .flatten()
+            .map(|(name, _)| name);
+        let composite = options
+            .composite_indices
+            .iter()
+            .flatten()
+            .flat_map(|(columns, _)| columns);
+        for name in single.chain(composite) {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        Some(names)
+    }
+
     /// Writes the complete FCB dataset to the output
     ///
     /// This method assembles the final FCB file by writing:
@@ -185,6 +679,21 @@ impl<'a> FcbWriter<'a> {
     ///
     /// A Result indicating success or failure of the write operation
     pub fn write(mut self, mut out: impl Write) -> Result<()> {
+        if self.appending && !self.feat_nodes.is_empty() {
+            // the output holds the existing features too
+            let extent = calc_extent(&self.feat_nodes);
+            let options = &mut self.header_writer.header_options;
+            options.feature_count = self.feat_offsets.len() as u64;
+            options.geographical_extent = Some([
+                extent.min_x,
+                extent.min_y,
+                extent.min_z,
+                extent.max_x,
+                extent.max_y,
+                extent.max_z,
+            ]);
+        }
+
         let mut attr_indices = self.header_writer.header_options.attribute_indices.clone();
 
         // sort attribute indices by schema index (ascending)
@@ -220,26 +729,17 @@ impl<'a> FcbWriter<'a> {
             tree.stream_write(&mut rtree_buf)?;
         }
 
-        self.tmpout.rewind()?;
-        let unsorted_feature_output = self.tmpout.into_inner().map_err(|e| e.into_error())?;
-        let mut unsorted_feature_reader = BufReader::new(unsorted_feature_output);
-
-        let mut sorted_feature_buf = Vec::with_capacity(2048);
-
+        // compute the final offsets of the features in sorted order
+        let mut sorted_offset = 0;
         for node in &self.feat_nodes {
             let feat = &self.feat_offsets[node.offset as usize];
-            unsorted_feature_reader.seek(SeekFrom::Start(feat.offset as u64))?;
-
             if let Some(attr_index_entry) =
                 self.attribute_index_entries.get_mut(&feat.temp_feature_id)
             {
-                attr_index_entry.offset = sorted_feature_buf.len();
+                attr_index_entry.offset = sorted_offset;
                 attr_index_entry.size = feat.size;
             }
-
-            let cur_len = sorted_feature_buf.len();
-            sorted_feature_buf.resize(cur_len + feat.size, 0);
-            unsorted_feature_reader.read_exact(&mut sorted_feature_buf[cur_len..])?;
+            sorted_offset += feat.size;
         }
 
         // build attribute index buffers in sorted order
@@ -260,16 +760,95 @@ impl<'a> FcbWriter<'a> {
             }
         }
 
+        // composite indices are stored after the single-column ones
+        let mut composite_index_info: Vec<CompositeIndexInfo> = Vec::new();
+        if let Some(composite_indices) = &self.header_writer.header_options.composite_indices {
+            for (columns, bf_opt) in composite_indices {
+                let bf = bf_opt.unwrap_or(crate::static_btree::DEFAULT_BRANCHING_FACTOR);
+                let (buf, info) = build_composite_index(
+                    columns,
+                    &self.attr_schema,
+                    &self.attribute_index_entries,
+                    bf,
+                )?;
+                composite_index_info.push(info);
+                attr_index_buf.extend(&buf);
+            }
+        }
+
+        self.tmpout.rewind()?;
+        let unsorted_feature_output = self.tmpout.into_inner().map_err(|e| e.into_error())?;
+        let mut unsorted_feature_reader = BufReader::new(unsorted_feature_output);
+        let mut feature_buf = Vec::with_capacity(2048);
+
+        // the hash covers the features in their final order, it goes in the header which is
+        // written before them, so the features are read twice
+        if self.header_writer.header_options.compute_hash {
+            let mut hasher = Sha256::new();
+            for node in self.feat_nodes.iter() {
+                let feat = &self.feat_offsets[node.offset as usize];
+                unsorted_feature_reader.seek(SeekFrom::Start(feat.offset as u64))?;
+                feature_buf.resize(feat.size, 0);
+                unsorted_feature_reader.read_exact(&mut feature_buf)?;
+                hasher.update(&feature_buf);
+            }
+            self.header_writer.content_hash = Some(hasher.finalize().into());
+        }
+
         // write header with attribute indices metadata
+        self.header_writer.global_vertices = self.global_vertex_table();
         self.header_writer.attribute_indices_info = Some(attr_index_info);
+        self.header_writer.composite_indices_info = Some(composite_index_info);
         let header_buf = self.header_writer.finish_to_header()?;
         out.write_all(&header_buf)?;
 
         // write spatial index (if any), attribute index bytes, then feature data
         out.write_all(&rtree_buf)?;
         out.write_all(&attr_index_buf)?;
-        out.write_all(&sorted_feature_buf)?;
 
+        let mut bytes_written =
+            (MAGIC_BYTES.len() + header_buf.len() + rtree_buf.len() + attr_index_buf.len()) as u64;
+        let total_features = Some(self.feat_nodes.len());
+        for (i, node) in self.feat_nodes.iter().enumerate() {
+            let feat = &self.feat_offsets[node.offset as usize];
+            unsorted_feature_reader.seek(SeekFrom::Start(feat.offset as u64))?;
+            feature_buf.resize(feat.size, 0);
+            unsorted_feature_reader.read_exact(&mut feature_buf)?;
+            out.write_all(&feature_buf)?;
+            bytes_written += feat.size as u64;
+
+            if let Some(progress) = &self.progress {
+                progress(ProgressEvent {
+                    features_written: i + 1,
+                    bytes_written,
+                    total_features,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the FCB file to `path` without ever leaving a partially written file behind
+    ///
+    /// The file is first written to a temporary file in the same directory, which is then
+    /// renamed to `path` once complete. On failure the temporary file is deleted and an
+    /// existing file at `path` is left untouched, so a file opened with
+    /// [`FcbWriter::open_for_append`] can be written back to its own path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the output file, replaced if it exists
+    pub fn write_atomic(self, path: &Path) -> Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut out = BufWriter::new(tempfile::NamedTempFile::new_in(dir)?);
+        self.write(&mut out)?;
+        let tmp = out.into_inner().map_err(|e| e.into_error())?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(path).map_err(|e| e.error)?;
         Ok(())
     }
 }
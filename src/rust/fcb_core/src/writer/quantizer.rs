@@ -0,0 +1,96 @@
+use cjseq::{CityJSONFeature, Transform as CjTransform};
+
+/// Re-encodes integer vertices to a fixed number of decimal digits.
+///
+/// The translate of the source transform is kept, only the scale changes to `10^-digits`, so a
+/// vertex `v` becomes `round(v * source_scale / 10^-digits)`.
+#[derive(Debug, Clone)]
+pub struct Quantizer {
+    /// Transform the input vertices are encoded with
+    source: CjTransform,
+    /// Transform of the quantized vertices
+    target: CjTransform,
+    /// Multiplier from source to target integer coordinates, per axis
+    factors: [f64; 3],
+}
+
+impl Quantizer {
+    pub fn new(source: &CjTransform, digits: u8) -> Self {
+        let scale = precision_scale(digits);
+        let mut target = source.clone();
+        target.scale = vec![scale; 3];
+        let factors =
+            std::array::from_fn(|axis| source.scale.get(axis).copied().unwrap_or(1.0) / scale);
+        Quantizer {
+            source: source.clone(),
+            target,
+            factors,
+        }
+    }
+
+    pub fn source_transform(&self) -> &CjTransform {
+        &self.source
+    }
+
+    pub fn target_transform(&self) -> &CjTransform {
+        &self.target
+    }
+
+    pub fn quantize_vertex(&self, vertex: &[i64]) -> Vec<i64> {
+        vertex
+            .iter()
+            .zip(self.factors)
+            .map(|(c, factor)| (*c as f64 * factor).round() as i64)
+            .collect()
+    }
+
+    /// Returns a copy of the feature with quantized vertices
+    pub fn quantize_feature(&self, feature: &CityJSONFeature) -> CityJSONFeature {
+        let mut quantized = feature.clone();
+        quantized.vertices = feature
+            .vertices
+            .iter()
+            .map(|v| self.quantize_vertex(v))
+            .collect();
+        quantized
+    }
+}
+
+/// Scale factor keeping `digits` decimal digits, i.e. `10^-digits`
+pub fn precision_scale(digits: u8) -> f64 {
+    10f64.powi(-(digits as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_quantize_vertex() -> Result<()> {
+        let source: CjTransform = serde_json::from_value(serde_json::json!({
+            "scale": [0.001, 0.001, 0.001],
+            "translate": [85000.0, 446000.0, 0.0]
+        }))?;
+        let quantizer = Quantizer::new(&source, 2);
+        assert_eq!(quantizer.target_transform().scale, vec![0.01; 3]);
+        assert_eq!(quantizer.target_transform().translate, source.translate);
+
+        // 1.001, 1.004 and 0.996 are within 0.01 of each other and share the integer 100
+        let a = quantizer.quantize_vertex(&[1001, 1004, 996]);
+        assert_eq!(a, vec![100, 100, 100]);
+        assert_eq!(
+            quantizer.quantize_vertex(&[1006, -1004, 0]),
+            vec![101, -100, 0]
+        );
+
+        // every quantized vertex stays within half a unit of the target scale
+        for c in [-123_456i64, -7, 0, 3, 49_995, 1_234_567] {
+            let q = quantizer.quantize_vertex(&[c, c, c]);
+            let original = c as f64 * 0.001;
+            let restored = q[0] as f64 * 0.01;
+            assert!((original - restored).abs() <= 0.005 + 1e-9);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,720 @@
+//! Rewriting existing FCB files without re-encoding their features, to add attribute indices
+//! or append features
+
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use flatbuffers::{FlatBufferBuilder, ForwardsUOffset, Vector, WIPOffset};
+use sha2::{Digest, Sha256};
+
+use crate::deserializer::{decode_attributes, decode_geometry};
+use crate::error::{Error, Result};
+use crate::fb::*;
+use crate::packed_rtree::{NodeLayout, PackedRTree};
+use crate::reader::FcbReader;
+use crate::{
+    parse_magic_bytes, CHECKSUM_SIZE, CONTENT_HASH_SIZE, HEADER_MAX_BUFFER_SIZE, MAGIC_BYTES,
+};
+
+use super::attr_index::{build_attribute_index_for_attr, build_composite_index};
+use super::attribute::{
+    attribute_to_index_entries, feature_id_index_entry, schema_from_columns, AttributeIndexEntry,
+    AttributeSchema,
+};
+use super::feature_writer::AttributeFeatureOffset;
+use super::serializer::{to_geometry, AttributeIndexInfo, CompositeIndexInfo};
+
+/// Writes a copy of an FCB file with attribute indices added for the given columns
+///
+/// Features are decoded only to collect the indexed values. The output keeps the
+/// R-tree, the existing indices and the feature section byte for byte, with a rewritten
+/// header and the new index bytes inserted after the existing attribute indices.
+///
+/// # Arguments
+///
+/// * `input` - The FCB file to index
+/// * `output` - Destination of the indexed copy
+/// * `columns` - Attribute names to index, each with the branching factor of its index
+pub fn append_attribute_index(
+    mut input: impl Read + Seek,
+    mut output: impl Write,
+    columns: &[(&str, u16)],
+) -> Result<()> {
+    let (magic_buf, header_buf) = read_header_buf(&mut input)?;
+    let header = size_prefixed_root_as_header(&header_buf)?;
+
+    let schema = schema_from_columns(header.columns());
+    for (name, _) in columns {
+        let (index, _) = schema.get(*name).ok_or(Error::AttributeIndexNotFound)?;
+        let already_indexed = header
+            .attribute_index()
+            .into_iter()
+            .flat_map(|ai| ai.iter())
+            .any(|ai| ai.index() == *index);
+        if already_indexed {
+            return Err(Error::IndexCreationError(format!(
+                "attribute {name} is already indexed"
+            )));
+        }
+    }
+
+    let (rtree_size, attr_index_size, composite_index_size) = index_sizes(&header, &magic_buf);
+    let index_start = (magic_buf.len() + header_buf.len()) as u64;
+    let feature_start = index_start + rtree_size + attr_index_size + composite_index_size;
+
+    // collect the indexed values of every feature, offsets are relative to the feature section
+    let attr_names = columns
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect::<Vec<_>>();
+    let mut attribute_entries: HashMap<usize, AttributeFeatureOffset> = HashMap::new();
+    let mut size_buf: [u8; 4] = [0; 4];
+    let mut feature_buf = Vec::with_capacity(2048);
+    let mut offset = 0;
+    // the checksums are copied along with the features, they only count in the offsets
+    let checksum_size = if header.checksum() { CHECKSUM_SIZE } else { 0 };
+    input.seek(SeekFrom::Start(feature_start))?;
+    loop {
+        match input.read_exact(&mut size_buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let feature_size = u32::from_le_bytes(size_buf) as usize;
+        feature_buf.clear();
+        feature_buf.extend_from_slice(&size_buf);
+        feature_buf.resize(feature_size + 4, 0);
+        input.read_exact(&mut feature_buf[4..])?;
+        input.seek(SeekFrom::Current(checksum_size as i64))?;
+
+        let feature = size_prefixed_root_as_city_feature(&feature_buf)?;
+        let index_entries = feature_index_entries(&feature, &header, &schema, &attr_names)?;
+        attribute_entries.insert(
+            attribute_entries.len(),
+            AttributeFeatureOffset {
+                offset,
+                size: feature_buf.len() + checksum_size,
+                index_entries,
+            },
+        );
+        offset += feature_buf.len() + checksum_size;
+    }
+
+    let mut index_buf = Vec::new();
+    let mut index_info = Vec::new();
+    for (name, branching_factor) in columns {
+        let (buf, info) =
+            build_attribute_index_for_attr(name, &schema, &attribute_entries, *branching_factor)?;
+        index_buf.extend(&buf);
+        index_info.push(info);
+    }
+
+    let mut fbb = FlatBufferBuilder::new();
+    let new_header = copy_header(&mut fbb, &header, HeaderUpdate::AddIndices(&index_info))?;
+    fbb.finish_size_prefixed(new_header, None);
+
+    output.write_all(&magic_buf)?;
+    output.write_all(fbb.finished_data())?;
+
+    // R-tree and existing attribute indices, then the new ones, then the composite indices
+    input.seek(SeekFrom::Start(index_start))?;
+    io::copy(
+        &mut (&mut input).take(rtree_size + attr_index_size),
+        &mut output,
+    )?;
+    output.write_all(&index_buf)?;
+    io::copy(&mut (&mut input).take(composite_index_size), &mut output)?;
+    io::copy(&mut input, &mut output)?;
+
+    Ok(())
+}
+
+/// Writes a copy of an FCB file with an R-tree built from its features
+///
+/// Features are copied byte for byte in the Hilbert order of the new R-tree. Their offsets
+/// change, so the attribute and composite indices of the file are rebuilt for the same columns
+/// and branching factors, and the content hash is recomputed if the file has one.
+pub(super) fn rebuild_spatial_index(
+    mut input: impl Read + Seek,
+    mut output: impl Write,
+) -> Result<()> {
+    let (tree, nodes) = {
+        let reader = FcbReader::open(&mut input)?;
+        check_no_global_vertices(&reader.header())?;
+        let mut iter = reader.select_all()?;
+        PackedRTree::build_from_features(&mut iter)?
+    };
+
+    input.seek(SeekFrom::Start(0))?;
+    let (magic_buf, header_buf) = read_header_buf(&mut input)?;
+    let header = size_prefixed_root_as_header(&header_buf)?;
+    let (rtree_size, attr_index_size, composite_index_size) = index_sizes(&header, &magic_buf);
+    let feature_start = (magic_buf.len() + header_buf.len()) as u64
+        + rtree_size
+        + attr_index_size
+        + composite_index_size;
+    let index_node_size = match header.index_node_size() {
+        0 => PackedRTree::DEFAULT_NODE_SIZE,
+        n => n,
+    };
+    let checksum_size = if header.checksum() { CHECKSUM_SIZE } else { 0 };
+
+    let schema = schema_from_columns(header.columns());
+    let IndexDefinitions {
+        attribute_indices,
+        composite_indices,
+    } = index_definitions(&header, &schema)?;
+    let attr_names = indexed_attribute_names(&attribute_indices, &composite_indices);
+
+    // collect the indexed values of every feature at its offset in the new order, and hash the
+    // features in that order since the hash goes in the header which is written before them
+    let mut attribute_entries: HashMap<usize, AttributeFeatureOffset> = HashMap::new();
+    let mut hasher = header.content_hash().map(|_| Sha256::new());
+    let mut feature_buf = Vec::with_capacity(2048);
+    if !attr_names.is_empty() || hasher.is_some() {
+        let mut offset = 0;
+        for node in nodes.iter() {
+            input.seek(SeekFrom::Start(feature_start + node.offset))?;
+            read_feature_buf(&mut input, &mut feature_buf, checksum_size)?;
+            if let Some(hasher) = &mut hasher {
+                hasher.update(&feature_buf);
+            }
+            if attr_names.is_empty() {
+                continue;
+            }
+            let feature = size_prefixed_root_as_city_feature(
+                &feature_buf[..feature_buf.len() - checksum_size],
+            )?;
+            let index_entries = feature_index_entries(&feature, &header, &schema, &attr_names)?;
+            attribute_entries.insert(
+                attribute_entries.len(),
+                AttributeFeatureOffset {
+                    offset,
+                    size: feature_buf.len(),
+                    index_entries,
+                },
+            );
+            offset += feature_buf.len();
+        }
+    }
+    let content_hash: Option<[u8; CONTENT_HASH_SIZE]> =
+        hasher.map(|hasher| hasher.finalize().into());
+
+    let mut index_buf = Vec::new();
+    let mut index_info = Vec::new();
+    for (_, name, branching_factor) in attribute_indices.iter() {
+        let (buf, info) =
+            build_attribute_index_for_attr(name, &schema, &attribute_entries, *branching_factor)?;
+        index_buf.extend(&buf);
+        index_info.push(info);
+    }
+    let mut composite_info = Vec::new();
+    for (columns, branching_factor) in composite_indices.iter() {
+        let (buf, info) =
+            build_composite_index(columns, &schema, &attribute_entries, *branching_factor)?;
+        index_buf.extend(&buf);
+        composite_info.push(info);
+    }
+
+    let mut fbb = FlatBufferBuilder::new();
+    let new_header = copy_header(
+        &mut fbb,
+        &header,
+        HeaderUpdate::RebuildIndices {
+            index_node_size,
+            attribute_indices: &index_info,
+            composite_indices: &composite_info,
+            content_hash,
+        },
+    )?;
+    fbb.finish_size_prefixed(new_header, None);
+
+    // the new R-tree has the node layout of the current version
+    output.write_all(&MAGIC_BYTES)?;
+    output.write_all(fbb.finished_data())?;
+    tree.stream_write(&mut output)?;
+    output.write_all(&index_buf)?;
+    for node in nodes.iter() {
+        input.seek(SeekFrom::Start(feature_start + node.offset))?;
+        read_feature_buf(&mut input, &mut feature_buf, checksum_size)?;
+        output.write_all(&feature_buf)?;
+    }
+
+    Ok(())
+}
+
+/// Attribute and composite indices of a header, see [`index_definitions`]
+#[derive(Default)]
+pub(super) struct IndexDefinitions {
+    /// Single-column indices as (column index, column name, branching factor), sorted by
+    /// column index
+    pub attribute_indices: Vec<(u16, String, u16)>,
+    /// Composite indices as (column names in key order, branching factor)
+    pub composite_indices: Vec<(Vec<String>, u16)>,
+}
+
+/// Columns and branching factors of the indices of `header`, whose columns are `schema`
+pub(super) fn index_definitions(
+    header: &Header,
+    schema: &AttributeSchema,
+) -> Result<IndexDefinitions> {
+    let column_name = |index: u16| {
+        schema
+            .iter()
+            .find(|(_, (i, _))| *i == index)
+            .map(|(name, _)| name.clone())
+            .ok_or(Error::AttributeIndexNotFound)
+    };
+    let mut attribute_indices = header
+        .attribute_index()
+        .into_iter()
+        .flat_map(|ai| ai.iter())
+        .map(|ai| Ok((ai.index(), column_name(ai.index())?, ai.branching_factor())))
+        .collect::<Result<Vec<_>>>()?;
+    attribute_indices.sort_by_key(|(index, _, _)| *index);
+    let composite_indices = header
+        .composite_index()
+        .into_iter()
+        .flat_map(|ci| ci.iter())
+        .map(|ci| {
+            let columns = ci
+                .columns()
+                .into_iter()
+                .flat_map(|c| c.iter())
+                .map(column_name)
+                .collect::<Result<Vec<_>>>()?;
+            Ok((columns, ci.branching_factor()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(IndexDefinitions {
+        attribute_indices,
+        composite_indices,
+    })
+}
+
+/// Names of the attributes used by any of the indices, without duplicates
+fn indexed_attribute_names(
+    attribute_indices: &[(u16, String, u16)],
+    composite_indices: &[(Vec<String>, u16)],
+) -> Vec<String> {
+    let mut attr_names: Vec<String> = Vec::new();
+    let single = attribute_indices.iter().map(|(_, name, _)| name);
+    let composite = composite_indices.iter().flat_map(|(columns, _)| columns);
+    for name in single.chain(composite) {
+        if !attr_names.contains(name) {
+            attr_names.push(name.clone());
+        }
+    }
+    attr_names
+}
+
+/// Values of the attributes in `attr_names` of an encoded feature, for building indices
+pub(super) fn feature_index_entries(
+    feature: &CityFeature,
+    header: &Header,
+    schema: &AttributeSchema,
+    attr_names: &[String],
+) -> Result<Vec<AttributeIndexEntry>> {
+    let mut index_entries = Vec::new();
+    index_entries.extend(feature_id_index_entry(feature.id(), schema, attr_names));
+    for co in feature.objects().into_iter().flat_map(|cos| cos.iter()) {
+        let (Some(co_columns), Some(attributes)) =
+            (co.columns().or(header.columns()), co.attributes())
+        else {
+            continue;
+        };
+        let attributes = decode_attributes(&co_columns, attributes)?;
+        index_entries.extend(attribute_to_index_entries(&attributes, schema, attr_names));
+    }
+    Ok(index_entries)
+}
+
+/// Reads the size-prefixed feature at the position of `input`, followed by its checksum
+pub(super) fn read_feature_buf(
+    mut input: impl Read,
+    feature_buf: &mut Vec<u8>,
+    checksum_size: usize,
+) -> Result<()> {
+    let mut size_buf: [u8; 4] = [0; 4];
+    input.read_exact(&mut size_buf)?;
+    let feature_size = u32::from_le_bytes(size_buf) as usize;
+    feature_buf.clear();
+    feature_buf.extend_from_slice(&size_buf);
+    feature_buf.resize(feature_size + 4 + checksum_size, 0);
+    input.read_exact(&mut feature_buf[4..])?;
+    Ok(())
+}
+
+/// Reads the magic bytes and the size-prefixed header buffer, leaving `input` at the start of
+/// the indices
+pub(super) fn read_header_buf(mut input: impl Read) -> Result<([u8; 8], Vec<u8>)> {
+    let mut magic_buf: [u8; 8] = [0; 8];
+    input.read_exact(&mut magic_buf)?;
+    parse_magic_bytes(&magic_buf)?;
+
+    let mut size_buf: [u8; 4] = [0; 4];
+    input.read_exact(&mut size_buf)?;
+    let header_size = u32::from_le_bytes(size_buf) as usize;
+    if !((8..=HEADER_MAX_BUFFER_SIZE).contains(&header_size)) {
+        return Err(Error::IllegalHeaderSize(header_size));
+    }
+    let mut header_buf = vec![0; header_size + 4];
+    header_buf[..4].copy_from_slice(&size_buf);
+    input.read_exact(&mut header_buf[4..])?;
+    Ok((magic_buf, header_buf))
+}
+
+/// Fails on files written with a global vertex table, their features store no vertices to
+/// compute bounding boxes from and added features would not index the table
+pub(super) fn check_no_global_vertices(header: &Header) -> Result<()> {
+    match header.global_vertices() {
+        Some(_) => Err(Error::GlobalVertices(
+            "files with a global vertex table can't be appended to or re-indexed spatially"
+                .to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Sizes in bytes of the R-tree, the attribute indices and the composite indices
+///
+/// `magic_buf` are the magic bytes checked by [`read_header_buf`], their version sets the node
+/// layout of the R-tree.
+pub(super) fn index_sizes(header: &Header, magic_buf: &[u8; 8]) -> (u64, u64, u64) {
+    let rtree_size = if header.index_node_size() > 0 && header.features_count() > 0 {
+        PackedRTree::index_size(
+            header.features_count() as usize,
+            header.index_node_size(),
+            NodeLayout::for_version(magic_buf[3]),
+        ) as u64
+    } else {
+        0
+    };
+    let attr_index_size = header
+        .attribute_index()
+        .into_iter()
+        .flat_map(|ai| ai.iter())
+        .map(|ai| ai.length() as u64)
+        .sum::<u64>();
+    let composite_index_size = header
+        .composite_index()
+        .into_iter()
+        .flat_map(|ci| ci.iter())
+        .map(|ci| ci.length() as u64)
+        .sum::<u64>();
+    (rtree_size, attr_index_size, composite_index_size)
+}
+
+/// Change applied to a header copied with [`copy_header`]
+#[derive(Clone, Copy)]
+pub(super) enum HeaderUpdate<'i> {
+    /// Attribute indices appended after the existing ones
+    AddIndices(&'i [AttributeIndexInfo]),
+    /// Features reordered for a new R-tree, all the indices are replaced by rebuilt ones and
+    /// the content hash by the hash of the reordered features
+    RebuildIndices {
+        index_node_size: u16,
+        attribute_indices: &'i [AttributeIndexInfo],
+        composite_indices: &'i [CompositeIndexInfo],
+        content_hash: Option<[u8; CONTENT_HASH_SIZE]>,
+    },
+}
+
+/// Copies `header` with the given update
+pub(super) fn copy_header<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    header: &Header,
+    update: HeaderUpdate<'_>,
+) -> Result<WIPOffset<Header<'a>>> {
+    let (index_info, index_node_size) = match update {
+        HeaderUpdate::AddIndices(index_info) => (index_info, header.index_node_size()),
+        HeaderUpdate::RebuildIndices {
+            index_node_size,
+            attribute_indices,
+            ..
+        } => (attribute_indices, index_node_size),
+    };
+    let nullable_indices = index_info
+        .iter()
+        .filter(|info| info.nullable)
+        .map(|info| info.index)
+        .collect::<Vec<_>>();
+    let columns = header
+        .columns()
+        .map(|cols| copy_columns(fbb, cols, &nullable_indices));
+    let semantic_columns = header
+        .semantic_columns()
+        .map(|cols| copy_columns(fbb, cols, &[]));
+
+    let existing_attribute_index = match update {
+        HeaderUpdate::AddIndices(_) => header.attribute_index(),
+        _ => None,
+    };
+    let attribute_index = existing_attribute_index
+        .into_iter()
+        .flat_map(|ai| ai.iter())
+        .copied()
+        .chain(index_info.iter().map(|info| {
+            AttributeIndex::new(
+                info.index,
+                info.length,
+                info.branching_factor,
+                info.num_unique_items,
+            )
+        }))
+        .collect::<Vec<_>>();
+    let attribute_index = Some(fbb.create_vector(&attribute_index));
+
+    let composite_index = match update {
+        HeaderUpdate::AddIndices(_) => copy_composite_index(fbb, header),
+        HeaderUpdate::RebuildIndices {
+            composite_indices, ..
+        } => {
+            let indices = composite_indices
+                .iter()
+                .map(|info| {
+                    let columns = Some(fbb.create_vector(&info.columns));
+                    CompositeIndex::create(
+                        fbb,
+                        &CompositeIndexArgs {
+                            columns,
+                            length: info.length,
+                            branching_factor: info.branching_factor,
+                            num_unique_items: info.num_unique_items,
+                        },
+                    )
+                })
+                .collect::<Vec<_>>();
+            Some(fbb.create_vector(&indices))
+        }
+    };
+
+    let geographical_extent = header.geographical_extent();
+
+    let content_hash = match update {
+        HeaderUpdate::AddIndices(_) => header
+            .content_hash()
+            .map(|hash| fbb.create_vector(hash.bytes())),
+        HeaderUpdate::RebuildIndices { content_hash, .. } => {
+            content_hash.map(|hash| fbb.create_vector(&hash))
+        }
+    };
+
+    let appearance = header.appearance().map(|app| copy_appearance(fbb, app));
+    let global_vertices = header.global_vertices().map(|global_vertices| {
+        let vertices = global_vertices
+            .vertices()
+            .map(|v| fbb.create_vector(&v.iter().collect::<Vec<_>>()));
+        GlobalVertices::create(fbb, &GlobalVerticesArgs { vertices })
+    });
+
+    let semantic_attr_schema = header
+        .semantic_columns()
+        .map(|cols| schema_from_columns(Some(cols)));
+    let templates = match header.templates() {
+        Some(templates) => {
+            let templates = templates
+                .iter()
+                .map(|g| {
+                    let geometry = decode_geometry(g, header.semantic_columns())?;
+                    Ok(to_geometry(fbb, &geometry, semantic_attr_schema.as_ref()))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Some(fbb.create_vector(&templates))
+        }
+        None => None,
+    };
+    let templates_vertices = header
+        .templates_vertices()
+        .map(|v| fbb.create_vector(&v.iter().copied().collect::<Vec<_>>()));
+
+    let extensions = header.extensions().map(|extensions| {
+        let extensions = extensions
+            .iter()
+            .map(|ext| {
+                let args = ExtensionArgs {
+                    name: ext.name().map(|s| fbb.create_string(s)),
+                    description: ext.description().map(|s| fbb.create_string(s)),
+                    url: ext.url().map(|s| fbb.create_string(s)),
+                    version: ext.version().map(|s| fbb.create_string(s)),
+                    version_cityjson: ext.version_cityjson().map(|s| fbb.create_string(s)),
+                    extra_attributes: ext.extra_attributes().map(|s| fbb.create_string(s)),
+                    extra_city_objects: ext.extra_city_objects().map(|s| fbb.create_string(s)),
+                    extra_root_properties: ext
+                        .extra_root_properties()
+                        .map(|s| fbb.create_string(s)),
+                    extra_semantic_surfaces: ext
+                        .extra_semantic_surfaces()
+                        .map(|s| fbb.create_string(s)),
+                };
+                Extension::create(fbb, &args)
+            })
+            .collect::<Vec<_>>();
+        fbb.create_vector(&extensions)
+    });
+
+    let reference_system = header.reference_system().map(|rs| {
+        let args = ReferenceSystemArgs {
+            authority: rs.authority().map(|s| fbb.create_string(s)),
+            version: rs.version(),
+            code: rs.code(),
+            code_string: rs.code_string().map(|s| fbb.create_string(s)),
+        };
+        ReferenceSystem::create(fbb, &args)
+    });
+
+    let args = HeaderArgs {
+        transform: header.transform(),
+        appearance,
+        columns,
+        semantic_columns,
+        features_count: header.features_count(),
+        index_node_size,
+        attribute_index,
+        geographical_extent,
+        reference_system,
+        identifier: header.identifier().map(|s| fbb.create_string(s)),
+        reference_date: header.reference_date().map(|s| fbb.create_string(s)),
+        title: header.title().map(|s| fbb.create_string(s)),
+        templates,
+        templates_vertices,
+        extensions,
+        poc_contact_name: header.poc_contact_name().map(|s| fbb.create_string(s)),
+        poc_contact_type: header.poc_contact_type().map(|s| fbb.create_string(s)),
+        poc_role: header.poc_role().map(|s| fbb.create_string(s)),
+        poc_phone: header.poc_phone().map(|s| fbb.create_string(s)),
+        poc_email: header.poc_email().map(|s| fbb.create_string(s)),
+        poc_website: header.poc_website().map(|s| fbb.create_string(s)),
+        poc_address_thoroughfare_number: header
+            .poc_address_thoroughfare_number()
+            .map(|s| fbb.create_string(s)),
+        poc_address_thoroughfare_name: header
+            .poc_address_thoroughfare_name()
+            .map(|s| fbb.create_string(s)),
+        poc_address_locality: header.poc_address_locality().map(|s| fbb.create_string(s)),
+        poc_address_postcode: header.poc_address_postcode().map(|s| fbb.create_string(s)),
+        poc_address_country: header.poc_address_country().map(|s| fbb.create_string(s)),
+        attributes: header.attributes().map(|a| fbb.create_vector(a.bytes())),
+        version: Some(fbb.create_string(header.version())),
+        composite_index,
+        checksum: header.checksum(),
+        shared_vertices: header.shared_vertices(),
+        content_hash,
+        global_vertices,
+    };
+    Ok(Header::create(fbb, &args))
+}
+
+/// Copies the composite indices of `header` as they are
+fn copy_composite_index<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    header: &Header,
+) -> Option<WIPOffset<Vector<'a, ForwardsUOffset<CompositeIndex<'a>>>>> {
+    header.composite_index().map(|indices| {
+        let indices = indices
+            .iter()
+            .map(|ci| {
+                let columns = ci
+                    .columns()
+                    .map(|c| fbb.create_vector(&c.iter().collect::<Vec<_>>()));
+                CompositeIndex::create(
+                    fbb,
+                    &CompositeIndexArgs {
+                        columns,
+                        length: ci.length(),
+                        branching_factor: ci.branching_factor(),
+                        num_unique_items: ci.num_unique_items(),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        fbb.create_vector(&indices)
+    })
+}
+
+fn copy_columns<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    columns: Vector<'_, ForwardsUOffset<Column<'_>>>,
+    nullable_indices: &[u16],
+) -> WIPOffset<Vector<'a, ForwardsUOffset<Column<'a>>>> {
+    let columns = columns
+        .iter()
+        .map(|col| {
+            let args = ColumnArgs {
+                index: col.index(),
+                name: Some(fbb.create_string(col.name())),
+                type_: col.type_(),
+                title: col.title().map(|s| fbb.create_string(s)),
+                description: col.description().map(|s| fbb.create_string(s)),
+                precision: col.precision(),
+                scale: col.scale(),
+                nullable: col.nullable(),
+                unique: col.unique(),
+                primary_key: col.primary_key(),
+                metadata: col.metadata().map(|s| fbb.create_string(s)),
+                nullable_index: col.nullable_index() || nullable_indices.contains(&col.index()),
+            };
+            Column::create(fbb, &args)
+        })
+        .collect::<Vec<_>>();
+    fbb.create_vector(&columns)
+}
+
+fn copy_appearance<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    app: Appearance<'_>,
+) -> WIPOffset<Appearance<'a>> {
+    let materials = app.materials().map(|materials| {
+        let materials = materials
+            .iter()
+            .map(|m| {
+                let args = MaterialArgs {
+                    name: Some(fbb.create_string(m.name())),
+                    ambient_intensity: m.ambient_intensity(),
+                    diffuse_color: m
+                        .diffuse_color()
+                        .map(|c| fbb.create_vector(&c.iter().collect::<Vec<_>>())),
+                    emissive_color: m
+                        .emissive_color()
+                        .map(|c| fbb.create_vector(&c.iter().collect::<Vec<_>>())),
+                    specular_color: m
+                        .specular_color()
+                        .map(|c| fbb.create_vector(&c.iter().collect::<Vec<_>>())),
+                    shininess: m.shininess(),
+                    transparency: m.transparency(),
+                    is_smooth: m.is_smooth(),
+                };
+                Material::create(fbb, &args)
+            })
+            .collect::<Vec<_>>();
+        fbb.create_vector(&materials)
+    });
+    let textures = app.textures().map(|textures| {
+        let textures = textures
+            .iter()
+            .map(|t| {
+                let args = TextureArgs {
+                    type_: t.type_(),
+                    image: Some(fbb.create_string(t.image())),
+                    wrap_mode: t.wrap_mode(),
+                    texture_type: t.texture_type(),
+                    border_color: t
+                        .border_color()
+                        .map(|c| fbb.create_vector(&c.iter().collect::<Vec<_>>())),
+                };
+                Texture::create(fbb, &args)
+            })
+            .collect::<Vec<_>>();
+        fbb.create_vector(&textures)
+    });
+    let args = AppearanceArgs {
+        materials,
+        textures,
+        vertices_texture: app
+            .vertices_texture()
+            .map(|v| fbb.create_vector(&v.iter().copied().collect::<Vec<_>>())),
+        default_theme_texture: app.default_theme_texture().map(|s| fbb.create_string(s)),
+        default_theme_material: app.default_theme_material().map(|s| fbb.create_string(s)),
+    };
+    Appearance::create(fbb, &args)
+}
@@ -1,4 +1,5 @@
 use crate::attribute::{encode_attributes_with_schema, AttributeSchema, AttributeSchemaMethods};
+use crate::deserializer::header_transform;
 use crate::fb::{
     Appearance, AppearanceArgs, CityFeature, CityFeatureArgs, CityObject, CityObjectArgs,
     CityObjectType, Geometry, GeometryArgs, GeometryType, Material, MaterialArgs, SemanticObject,
@@ -6,20 +7,21 @@ use crate::fb::{
     WrapMode,
 };
 use crate::fb::{
-    GeographicalExtent, Header, HeaderArgs, ReferenceSystem, ReferenceSystemArgs, Transform, Vector,
+    GeographicalExtent, GlobalVertices, GlobalVerticesArgs, Header, HeaderArgs, ReferenceSystem,
+    ReferenceSystemArgs, Transform, Vector,
 };
 use crate::geom_encoder::encode;
 use crate::{
-    AttributeIndex, Column, ColumnArgs, DoubleVertex, Extension, ExtensionArgs, GeometryInstance,
-    GeometryInstanceArgs, MaterialMapping, MaterialMappingArgs, TextureFormat, TextureMapping,
-    TextureMappingArgs, TransformationMatrix,
+    AttributeIndex, Column, ColumnArgs, CompositeIndex, CompositeIndexArgs, DoubleVertex,
+    Extension, ExtensionArgs, GeometryInstance, GeometryInstanceArgs, MaterialMapping,
+    MaterialMappingArgs, TextureFormat, TextureMapping, TextureMappingArgs, TransformationMatrix,
 };
 use cjseq::{
     Appearance as CjAppearance, Boundaries as CjBoundaries, CityJSON, CityJSONFeature,
     CityObject as CjCityObject, Geometry as CjGeometry, GeometryType as CjGeometryType,
-    PointOfContact as CjPointOfContact, ReferenceSystem as CjReferenceSystem,
-    TextFormat as CjTextFormat, TextType as CjTextType, Transform as CjTransform,
-    WrapMode as CjWrapMode,
+    Material as CjMaterial, MaterialValues as CjMaterialValues, PointOfContact as CjPointOfContact,
+    ReferenceSystem as CjReferenceSystem, TextFormat as CjTextFormat, TextType as CjTextType,
+    Transform as CjTransform, WrapMode as CjWrapMode,
 };
 
 use cjseq::ExtensionFile as CjExtensionFile;
@@ -27,9 +29,12 @@ use cjseq::ExtensionFile as CjExtensionFile;
 use crate::packed_rtree::NodeItem;
 use flatbuffers::FlatBufferBuilder;
 use serde_json::Value;
+use std::collections::HashSet;
+use std::io::{self, Write};
 
 use super::geom_encoder::{GMBoundaries, GMSemantics, MaterialMapping as GMMaterialMapping};
 use super::header_writer::HeaderWriterOptions;
+use crate::const_vars::CONTENT_HASH_SIZE;
 use crate::error::Result;
 
 #[derive(Debug, Clone)]
@@ -38,6 +43,17 @@ pub(super) struct AttributeIndexInfo {
     pub length: u32,
     pub branching_factor: u16,
     pub num_unique_items: u32,
+    /// Whether the index keys carry a null marker for features without a value
+    pub nullable: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct CompositeIndexInfo {
+    /// Schema indices of the columns, in key order
+    pub columns: Vec<u16>,
+    pub length: u32,
+    pub branching_factor: u16,
+    pub num_unique_items: u32,
 }
 /// -----------------------------------
 /// Serializer for Header
@@ -49,6 +65,9 @@ pub(super) struct AttributeIndexInfo {
 /// * `fbb` - FlatBuffers builder instance
 /// * `cj` - CityJSON data containing header information
 /// * `header_metadata` - Additional metadata for the header
+/// * `content_hash` - SHA-256 of the feature section, if computed
+/// * `global_vertices` - Flat x, y, z of the vertices shared by all features, if any
+#[allow(clippy::too_many_arguments)]
 pub(super) fn to_fcb_header<'a>(
     fbb: &mut flatbuffers::FlatBufferBuilder<'a>,
     cj: &CityJSON,
@@ -56,11 +75,31 @@ pub(super) fn to_fcb_header<'a>(
     attr_schema: &AttributeSchema,
     semantic_attr_schema: Option<&AttributeSchema>,
     attribute_indices_info: Option<&[AttributeIndexInfo]>,
+    composite_indices_info: Option<&[CompositeIndexInfo]>,
+    content_hash: Option<&[u8; CONTENT_HASH_SIZE]>,
+    global_vertices: Option<&[i32]>,
 ) -> Result<flatbuffers::WIPOffset<Header<'a>>> {
     let version = Some(fbb.create_string(&cj.version));
     let transform = to_transform(&cj.transform);
     let features_count: u64 = header_options.feature_count;
-    let columns = Some(to_columns(fbb, attr_schema));
+    let checksum = header_options.checksum;
+    let shared_vertices = header_options.shared_vertices;
+    let content_hash = content_hash.map(|hash| fbb.create_vector(hash));
+    let global_vertices = global_vertices.map(|vertices| {
+        let vertices = Some(fbb.create_vector(vertices));
+        GlobalVertices::create(fbb, &GlobalVerticesArgs { vertices })
+    });
+    let nullable_indices = attribute_indices_info
+        .unwrap_or_default()
+        .iter()
+        .filter(|info| info.nullable)
+        .map(|info| info.index)
+        .collect::<Vec<_>>();
+    let columns = Some(to_columns_with_nullable_indices(
+        fbb,
+        attr_schema,
+        &nullable_indices,
+    ));
     let semantic_columns = semantic_attr_schema.map(|schema| to_columns(fbb, schema));
     let index_node_size = header_options.index_node_size;
     let attribute_index = {
@@ -81,6 +120,24 @@ pub(super) fn to_fcb_header<'a>(
             None
         }
     };
+    let composite_index = composite_indices_info.map(|composite_indices_info| {
+        let composite_indices = composite_indices_info
+            .iter()
+            .map(|info| {
+                let columns = Some(fbb.create_vector(&info.columns));
+                CompositeIndex::create(
+                    fbb,
+                    &CompositeIndexArgs {
+                        columns,
+                        length: info.length,
+                        branching_factor: info.branching_factor,
+                        num_unique_items: info.num_unique_items,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        fbb.create_vector(&composite_indices)
+    });
 
     // Handle extensions, if present
     let extensions = if let Some(extensions) = &cj.extensions {
@@ -184,6 +241,7 @@ pub(super) fn to_fcb_header<'a>(
                 reference_system,
                 identifier,
                 attribute_index,
+                composite_index,
                 reference_date,
                 title,
                 poc_contact_name,
@@ -203,6 +261,10 @@ pub(super) fn to_fcb_header<'a>(
                 templates,
                 templates_vertices,
                 extensions,
+                checksum,
+                shared_vertices,
+                content_hash,
+                global_vertices,
             },
         ))
     } else {
@@ -217,7 +279,12 @@ pub(super) fn to_fcb_header<'a>(
                 geographical_extent: geographical_extent_from_options.as_ref(),
                 version,
                 attribute_index,
+                composite_index,
                 extensions,
+                checksum,
+                shared_vertices,
+                content_hash,
+                global_vertices,
                 ..Default::default()
             },
         ))
@@ -416,9 +483,11 @@ pub(super) fn to_fcb_city_feature<'a>(
     semantic_attr_schema: Option<&AttributeSchema>,
 ) -> (flatbuffers::WIPOffset<CityFeature<'a>>, NodeItem) {
     let id = Some(fbb.create_string(id));
-    let city_objects: Vec<_> = city_feature
-        .city_objects
-        .iter()
+    // city objects are kept in a HashMap, sort them for a reproducible output
+    let mut city_objects = city_feature.city_objects.iter().collect::<Vec<_>>();
+    city_objects.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let city_objects: Vec<_> = city_objects
+        .into_iter()
         .map(|(id, co)| to_city_object(fbb, id, co, attr_schema, semantic_attr_schema))
         .collect();
     let objects = Some(fbb.create_vector(&city_objects));
@@ -467,8 +536,20 @@ pub(super) fn to_fcb_city_feature<'a>(
         .map(|v| v[1])
         .max()
         .unwrap_or(0) as f64;
+    let min_z = city_feature
+        .vertices
+        .iter()
+        .map(|v| v[2])
+        .min()
+        .unwrap_or(0) as f64;
+    let max_z = city_feature
+        .vertices
+        .iter()
+        .map(|v| v[2])
+        .max()
+        .unwrap_or(0) as f64;
 
-    let bbox = NodeItem::new(min_x, min_y, max_x, max_y);
+    let bbox = NodeItem::bounds_3d(min_x, min_y, min_z, max_x, max_y, max_z);
     (
         CityFeature::create(
             fbb,
@@ -797,7 +878,13 @@ pub(super) fn to_semantic_surface_type(ss_type: &str) -> (SemanticSurfaceType, O
         "AuxiliaryTrafficArea" => SemanticSurfaceType::AuxiliaryTrafficArea,
         "TransportationMarking" => SemanticSurfaceType::TransportationMarking,
         "TransportationHole" => SemanticSurfaceType::TransportationHole,
-        _ => SemanticSurfaceType::ExtraSemanticSurface,
+        // Keep the raw string of any other user-defined type so it can be restored on decoding
+        _ => {
+            return (
+                SemanticSurfaceType::ExtraSemanticSurface,
+                Some(ss_type.to_string()),
+            )
+        }
     };
 
     // Standard types don't have extension_type
@@ -1010,8 +1097,18 @@ pub(crate) fn to_columns<'a>(
     fbb: &mut FlatBufferBuilder<'a>,
     attr_schema: &AttributeSchema,
 ) -> flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Column<'a>>>> {
+    to_columns_with_nullable_indices(fbb, attr_schema, &[])
+}
+
+/// Like [`to_columns`], flagging the columns whose attribute index has null entries
+fn to_columns_with_nullable_indices<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    attr_schema: &AttributeSchema,
+    nullable_indices: &[u16],
+) -> flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<Column<'a>>>> {
+    // sorted by name, so the header does not depend on how the schema was built
     let mut sorted_schema: Vec<_> = attr_schema.iter().collect();
-    sorted_schema.sort_by_key(|(_, (index, _))| *index);
+    sorted_schema.sort_by(|(a, _), (b, _)| a.cmp(b));
     let columns_vec = sorted_schema
         .iter()
         .map(|(name, (index, column_type))| {
@@ -1022,6 +1119,7 @@ pub(crate) fn to_columns<'a>(
                     name: Some(name),
                     index: *index,
                     type_: *column_type,
+                    nullable_index: nullable_indices.contains(index),
                     ..Default::default()
                 },
             )
@@ -1055,6 +1153,341 @@ pub(super) fn to_fcb_attribute<'a>(
     }
 }
 
+/// Converts a FlatBuffers city feature into a GeoJSON `Feature`.
+///
+/// The geometry is a `MultiPolygon` made of the first surface geometry (MultiSurface,
+/// CompositeSurface or solids, whose shells are flattened) of each city object, with the header
+/// transform applied. Coordinates stay in the CRS of the dataset. The attributes of the city
+/// object sharing the feature id (or of the first one) become the `properties`, along with its
+/// city object `type`.
+pub fn to_geojson_feature(feature: CityFeature, header: Header) -> Result<Value> {
+    let cj_feature =
+        crate::deserializer::to_cj_feature(feature, header.columns(), header.semantic_columns())?;
+    let (scale, translate) = header_transform(&header);
+    let position = |index: u32| -> Option<Value> {
+        let v = cj_feature.vertices.get(index as usize)?;
+        Some(Value::from(
+            (0..3)
+                .map(|i| v.get(i).copied().unwrap_or_default() as f64 * scale[i] + translate[i])
+                .collect::<Vec<_>>(),
+        ))
+    };
+
+    let mut ids = cj_feature.city_objects.keys().collect::<Vec<_>>();
+    ids.sort();
+    // the object sharing the feature id goes first, it provides the properties
+    if let Some(pos) = ids.iter().position(|id| **id == cj_feature.id) {
+        let main = ids.remove(pos);
+        ids.insert(0, main);
+    }
+
+    let mut polygons = Vec::new();
+    for id in &ids {
+        let surface_geometry = cj_feature.city_objects[*id]
+            .geometry
+            .iter()
+            .flatten()
+            .find(|g| is_surface_geometry(&g.thetype));
+        if let Some(geometry) = surface_geometry {
+            collect_geojson_polygons(&geometry.boundaries, &position, &mut polygons);
+        }
+    }
+    let geometry = if polygons.is_empty() {
+        Value::Null
+    } else {
+        serde_json::json!({ "type": "MultiPolygon", "coordinates": polygons })
+    };
+
+    let mut properties = serde_json::Map::new();
+    if let Some(co) = ids.first().map(|id| &cj_feature.city_objects[*id]) {
+        if let Some(Value::Object(attributes)) = &co.attributes {
+            properties.extend(attributes.clone());
+        }
+        properties
+            .entry("type")
+            .or_insert_with(|| Value::from(co.thetype.clone()));
+    }
+
+    Ok(serde_json::json!({
+        "type": "Feature",
+        "id": cj_feature.id,
+        "geometry": geometry,
+        "properties": properties,
+    }))
+}
+
+/// Pushes every surface of `boundaries` as a GeoJSON polygon, closing its rings
+fn collect_geojson_polygons(
+    boundaries: &CjBoundaries,
+    position: &impl Fn(u32) -> Option<Value>,
+    polygons: &mut Vec<Value>,
+) {
+    let CjBoundaries::Nested(children) = boundaries else {
+        return;
+    };
+    if !children
+        .iter()
+        .all(|child| matches!(child, CjBoundaries::Indices(_)))
+    {
+        for child in children {
+            collect_geojson_polygons(child, position, polygons);
+        }
+        return;
+    }
+    let rings = children
+        .iter()
+        .filter_map(|ring| match ring {
+            CjBoundaries::Indices(indices) if !indices.is_empty() => {
+                let mut coords = indices
+                    .iter()
+                    .filter_map(|i| position(*i))
+                    .collect::<Vec<_>>();
+                if let Some(first) = coords.first().cloned() {
+                    coords.push(first);
+                }
+                Some(Value::from(coords))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !rings.is_empty() {
+        polygons.push(Value::from(rings));
+    }
+}
+
+/// Converts city features into a Wavefront OBJ model and its MTL material library, returned
+/// as `(obj_content, mtl_content)`.
+///
+/// Each city object becomes an OBJ group named after its id, with one face per surface of its
+/// surface geometry with the highest LoD. Faces only use the exterior ring of their surface, OBJ
+/// has no holes. The header transform is applied to the vertices, which stay in the CRS of the
+/// dataset. The materials of the feature appearances become MTL entries, taken from the default
+/// material theme or else the first theme of each geometry. The OBJ has no `mtllib` statement,
+/// callers add one referring to wherever they store the MTL content.
+pub fn to_obj(features: &[CityJSONFeature], header: &Header) -> Result<(String, String)> {
+    let mut writer = ObjWriter::new(Vec::new(), header);
+    for feature in features {
+        writer.add_feature(feature)?;
+    }
+    let (obj, mtl) = writer.finish()?;
+    let obj = String::from_utf8(obj).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok((obj, mtl))
+}
+
+/// Streaming version of [`to_obj`], writing the OBJ content feature by feature while the MTL
+/// library is collected in memory
+pub struct ObjWriter<W: Write> {
+    obj: W,
+    mtl: String,
+    scale: [f64; 3],
+    translate: [f64; 3],
+    /// Number of vertices written so far, OBJ indices are global
+    vertex_offset: usize,
+    /// Names of the MTL entries written so far
+    material_names: HashSet<String>,
+    /// Material of the last `usemtl` statement
+    current_material: Option<String>,
+}
+
+/// MTL entry of faces without material
+const OBJ_DEFAULT_MATERIAL: &str = "default";
+
+impl<W: Write> ObjWriter<W> {
+    pub fn new(obj: W, header: &Header) -> Self {
+        let (scale, translate) = header_transform(header);
+        Self {
+            obj,
+            mtl: String::new(),
+            scale,
+            translate,
+            vertex_offset: 0,
+            material_names: HashSet::new(),
+            current_material: None,
+        }
+    }
+
+    pub fn add_feature(&mut self, feature: &CityJSONFeature) -> Result<()> {
+        for v in &feature.vertices {
+            let c = |i: usize| {
+                v.get(i).copied().unwrap_or_default() as f64 * self.scale[i] + self.translate[i]
+            };
+            writeln!(self.obj, "v {} {} {}", c(0), c(1), c(2))?;
+        }
+
+        let appearance = feature.appearance.as_ref();
+        let materials = appearance
+            .and_then(|a| a.materials.as_deref())
+            .unwrap_or_default();
+        let default_theme = appearance.and_then(|a| a.default_theme_material.as_deref());
+
+        let mut ids = feature.city_objects.keys().collect::<Vec<_>>();
+        ids.sort();
+        for id in ids {
+            // the first of the geometries with the highest LoD
+            let geometry = feature.city_objects[id]
+                .geometry
+                .iter()
+                .flatten()
+                .filter(|g| is_surface_geometry(&g.thetype))
+                .rev()
+                .max_by(|a, b| a.lod.cmp(&b.lod));
+            let Some(geometry) = geometry else {
+                continue;
+            };
+            let mut surfaces = Vec::new();
+            collect_exterior_rings(&geometry.boundaries, &mut surfaces);
+            if surfaces.is_empty() {
+                continue;
+            }
+            let surface_materials = geometry
+                .material
+                .as_ref()
+                .and_then(|themes| {
+                    default_theme.and_then(|t| themes.get(t)).or_else(|| {
+                        let mut names = themes.keys().collect::<Vec<_>>();
+                        names.sort();
+                        names.first().map(|t| &themes[*t])
+                    })
+                })
+                .map(|reference| match (reference.value, &reference.values) {
+                    (Some(value), _) => vec![Some(value); surfaces.len()],
+                    (None, Some(values)) => {
+                        let mut flat = Vec::new();
+                        flatten_material_values(values, &mut flat);
+                        flat
+                    }
+                    (None, None) => Vec::new(),
+                })
+                .unwrap_or_default();
+
+            writeln!(self.obj, "g {}", obj_name(id))?;
+            for (i, ring) in surfaces.iter().enumerate() {
+                if ring.len() < 3 {
+                    continue;
+                }
+                let material = surface_materials
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .and_then(|m| Some((m, materials.get(m)?)))
+                    .map(|(m, material)| self.add_material(m, material));
+                self.use_material(material)?;
+                write!(self.obj, "f")?;
+                for index in ring.iter() {
+                    write!(self.obj, " {}", self.vertex_offset + *index as usize + 1)?;
+                }
+                writeln!(self.obj)?;
+            }
+        }
+        self.vertex_offset += feature.vertices.len();
+        Ok(())
+    }
+
+    /// Flush the OBJ writer and return it with the MTL content
+    pub fn finish(mut self) -> Result<(W, String)> {
+        self.obj.flush()?;
+        Ok((self.obj, self.mtl))
+    }
+
+    /// Adds the MTL entry of a material unless one with its name exists, returns its name
+    fn add_material(&mut self, index: usize, material: &CjMaterial) -> String {
+        let name = if material.name.is_empty() {
+            format!("material_{index}")
+        } else {
+            obj_name(&material.name)
+        };
+        if self.material_names.insert(name.clone()) {
+            let mtl = &mut self.mtl;
+            mtl.push_str(&format!("newmtl {name}\n"));
+            if let Some([r, g, b]) = material.diffuse_color {
+                let ambient = material.ambient_intensity.unwrap_or(0.2);
+                mtl.push_str(&format!(
+                    "Ka {} {} {}\n",
+                    r * ambient,
+                    g * ambient,
+                    b * ambient
+                ));
+                mtl.push_str(&format!("Kd {r} {g} {b}\n"));
+            }
+            if let Some([r, g, b]) = material.specular_color {
+                mtl.push_str(&format!("Ks {r} {g} {b}\n"));
+            }
+            if let Some([r, g, b]) = material.emissive_color {
+                mtl.push_str(&format!("Ke {r} {g} {b}\n"));
+            }
+            // CityJSON shininess is in [0, 1], the OBJ exponent in [0, 1000]
+            if let Some(shininess) = material.shininess {
+                mtl.push_str(&format!("Ns {}\n", shininess * 1000.0));
+            }
+            if let Some(transparency) = material.transparency {
+                mtl.push_str(&format!("d {}\n", 1.0 - transparency));
+            }
+            mtl.push('\n');
+        }
+        name
+    }
+
+    /// Writes a `usemtl` statement when the material changes
+    fn use_material(&mut self, material: Option<String>) -> Result<()> {
+        let name = material.unwrap_or_else(|| {
+            if self.material_names.insert(OBJ_DEFAULT_MATERIAL.to_string()) {
+                self.mtl.push_str(&format!(
+                    "newmtl {OBJ_DEFAULT_MATERIAL}\nKd 0.8 0.8 0.8\n\n"
+                ));
+            }
+            OBJ_DEFAULT_MATERIAL.to_string()
+        });
+        if self.current_material.as_ref() != Some(&name) {
+            writeln!(self.obj, "usemtl {name}")?;
+            self.current_material = Some(name);
+        }
+        Ok(())
+    }
+}
+
+fn is_surface_geometry(geometry_type: &CjGeometryType) -> bool {
+    matches!(
+        geometry_type,
+        CjGeometryType::MultiSurface
+            | CjGeometryType::CompositeSurface
+            | CjGeometryType::Solid
+            | CjGeometryType::MultiSolid
+            | CjGeometryType::CompositeSolid
+    )
+}
+
+/// OBJ and MTL names end at whitespace
+fn obj_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Pushes the exterior ring of every surface of `boundaries`, in boundary order
+fn collect_exterior_rings<'a>(boundaries: &'a CjBoundaries, rings: &mut Vec<&'a [u32]>) {
+    let CjBoundaries::Nested(children) = boundaries else {
+        return;
+    };
+    match children.first() {
+        Some(CjBoundaries::Indices(exterior)) => rings.push(exterior),
+        _ => {
+            for child in children {
+                collect_exterior_rings(child, rings);
+            }
+        }
+    }
+}
+
+/// Material index of every surface, in the boundary order of [`collect_exterior_rings`]
+fn flatten_material_values(values: &CjMaterialValues, flat: &mut Vec<Option<usize>>) {
+    match values {
+        CjMaterialValues::Indices(indices) => flat.extend(indices.iter().copied()),
+        CjMaterialValues::Nested(nested) => {
+            for values in nested {
+                flatten_material_values(values, flat);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1066,6 +1499,29 @@ mod tests {
     use flatbuffers::FlatBufferBuilder;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn test_to_semantic_surface_type() {
+        assert_eq!(
+            (SemanticSurfaceType::RoofSurface, None),
+            to_semantic_surface_type("RoofSurface")
+        );
+        assert_eq!(
+            (
+                SemanticSurfaceType::ExtraSemanticSurface,
+                Some("+NoiseSurface".to_string())
+            ),
+            to_semantic_surface_type("+NoiseSurface")
+        );
+        // user-defined types without the `+` prefix keep their raw string as well
+        assert_eq!(
+            (
+                SemanticSurfaceType::ExtraSemanticSurface,
+                Some("SolarPanelSurface".to_string())
+            ),
+            to_semantic_surface_type("SolarPanelSurface")
+        );
+    }
+
     #[test]
     fn test_to_fcb_city_feature() -> Result<()> {
         let cj_city_feature: CityJSONFeature = CityJSONFeature::from_str(
@@ -1196,4 +1652,37 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parents_children_roundtrip() -> Result<()> {
+        let cj_city_feature = CityJSONFeature::from_str(
+            r#"{"type":"CityJSONFeature","id":"b","CityObjects":{"b":{"type":"Building","children":["b-0","b-1"]},"b-0":{"type":"BuildingPart","parents":["b"],"geometry":[{"type":"MultiSurface","lod":"1","boundaries":[[[0,1,2]]]}]},"b-1":{"type":"BuildingPart","parents":["b"],"geometry":[{"type":"MultiSurface","lod":"1","boundaries":[[[1,2,3]]]}]}},"vertices":[[0,0,0],[1,0,0],[1,1,0],[0,1,0]]}"#,
+        )?;
+
+        let mut fbb = FlatBufferBuilder::new();
+        let (city_feature, _) = to_fcb_city_feature(
+            &mut fbb,
+            "b",
+            &cj_city_feature,
+            &AttributeSchema::new(),
+            None,
+        );
+        fbb.finish(city_feature, None);
+        let fb_city_feature = root_as_city_feature(fbb.finished_data()).unwrap();
+        let decoded = crate::deserializer::to_cj_feature(fb_city_feature, None, None)?;
+
+        let building = &decoded.city_objects["b"];
+        assert_eq!(
+            building.children,
+            Some(vec!["b-0".to_string(), "b-1".to_string()])
+        );
+        assert_eq!(building.parents, None);
+        for part in ["b-0", "b-1"] {
+            let part = &decoded.city_objects[part];
+            assert_eq!(part.parents, Some(vec!["b".to_string()]));
+            assert_eq!(part.children, None);
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use cjseq::{Boundaries as CjBoundaries, CityJSONFeature};
+
+/// Returns a copy of the feature in which identical vertices are merged into one.
+///
+/// Geometries of different LODs of a city object often repeat the same corners, e.g. the
+/// footprint of a LoD1 block and of a LoD2 model. Vertices are kept in the order of their first
+/// occurrence and every boundary index of every geometry is remapped accordingly.
+pub fn share_vertices(feature: &CityJSONFeature) -> CityJSONFeature {
+    let mut first_index: HashMap<&[i64], u32> = HashMap::with_capacity(feature.vertices.len());
+    let mut vertices = Vec::with_capacity(feature.vertices.len());
+    let remap = feature
+        .vertices
+        .iter()
+        .map(|v| {
+            *first_index.entry(v.as_slice()).or_insert_with(|| {
+                vertices.push(v.clone());
+                (vertices.len() - 1) as u32
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut shared = feature.clone();
+    if vertices.len() == feature.vertices.len() {
+        return shared;
+    }
+    shared.vertices = vertices;
+    for co in shared.city_objects.values_mut() {
+        for geometry in co.geometry.iter_mut().flatten() {
+            remap_boundaries(&mut geometry.boundaries, &remap);
+        }
+    }
+    shared
+}
+
+pub(super) fn remap_boundaries(boundaries: &mut CjBoundaries, remap: &[u32]) {
+    match boundaries {
+        CjBoundaries::Indices(indices) => {
+            for i in indices.iter_mut() {
+                // out of range indices are left for the geometry validation to report
+                if let Some(new) = remap.get(*i as usize) {
+                    *i = *new;
+                }
+            }
+        }
+        CjBoundaries::Nested(nested) => {
+            for b in nested.iter_mut() {
+                remap_boundaries(b, remap);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_share_vertices() -> Result<()> {
+        let feature: CityJSONFeature = serde_json::from_value(serde_json::json!({
+            "type": "CityJSONFeature",
+            "id": "b",
+            "CityObjects": {
+                "b": {
+                    "type": "Building",
+                    "geometry": [
+                        {"type": "MultiSurface", "lod": "1", "boundaries": [[[0, 1, 2]]]},
+                        {"type": "MultiSurface", "lod": "2", "boundaries": [[[3, 4, 5, 6]]]}
+                    ]
+                }
+            },
+            "vertices": [[0, 0, 0], [10, 0, 0], [10, 10, 0], [0, 0, 0], [10, 0, 0], [10, 10, 0], [0, 10, 0]]
+        }))?;
+        let shared = share_vertices(&feature);
+
+        assert_eq!(
+            vec![
+                vec![0, 0, 0],
+                vec![10, 0, 0],
+                vec![10, 10, 0],
+                vec![0, 10, 0]
+            ],
+            shared.vertices
+        );
+        let geometries = shared.city_objects["b"].geometry.as_ref().unwrap();
+        assert_eq!(
+            serde_json::json!([[[0, 1, 2, 3]]]),
+            serde_json::to_value(&geometries[1].boundaries)?
+        );
+        assert_eq!(
+            serde_json::json!([[[0, 1, 2]]]),
+            serde_json::to_value(&geometries[0].boundaries)?
+        );
+        Ok(())
+    }
+}
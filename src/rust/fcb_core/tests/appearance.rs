@@ -0,0 +1,233 @@
+use anyhow::Result;
+use fcb_core::{
+    header_writer::HeaderWriterOptions, read_cityjson_from_reader, serializer, CJType, CJTypeKind,
+    CityJSONSeq, FcbReader, FcbWriter,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use std::io::{BufReader, Cursor};
+
+/// A feature with an appearance and geometries covering the material and texture mappings
+fn appearance_seq() -> Result<CityJSONSeq> {
+    let header = json!({
+        "type": "CityJSON",
+        "version": "2.0",
+        "transform": {"scale": [0.001, 0.001, 0.001], "translate": [0.0, 0.0, 0.0]},
+        "CityObjects": {},
+        "vertices": []
+    });
+    let feature = json!({
+        "type": "CityJSONFeature",
+        "id": "textured",
+        "CityObjects": {
+            "textured": {
+                "type": "Building",
+                "geometry": [
+                    {
+                        // single material value
+                        "type": "MultiSurface",
+                        "lod": "1",
+                        "boundaries": [[[0, 3, 2, 1]], [[4, 5, 6, 7]]],
+                        "material": {"irradiation": {"value": 0}}
+                    },
+                    {
+                        // per-surface material indices and two texture themes
+                        "type": "Solid",
+                        "lod": "2",
+                        "boundaries": [[
+                            [[0, 3, 2, 1]], [[4, 5, 6, 7]], [[0, 1, 5, 4]],
+                            [[1, 2, 6, 5]], [[2, 3, 7, 6]], [[3, 0, 4, 7]]
+                        ]],
+                        "material": {
+                            "irradiation": {"values": [[0, 1, null, 1, 0, null]]},
+                            "red": {"value": 1}
+                        },
+                        "texture": {
+                            "winter": {"values": [[
+                                [[0, 0, 1, 2, 3]], [[0, 4, 5, 6, 7]], [[null]],
+                                [[null]], [[0, 0, 1, 2, 3]], [[null]]
+                            ]]},
+                            "summer": {"values": [[
+                                [[1, 3, 2, 1, 0]], [[null]], [[1, 7, 6, 5, 4]],
+                                [[null]], [[null]], [[1, 0, 1, 2, 3]]
+                            ]]}
+                        }
+                    },
+                    {
+                        // another LOD with a single theme and null material sentinels
+                        "type": "MultiSurface",
+                        "lod": "2.2",
+                        "boundaries": [[[0, 1, 5, 4]], [[1, 2, 6, 5]]],
+                        "material": {"irradiation": {"values": [null, 1]}},
+                        "texture": {"summer": {"values": [[[1, 4, 5, 6, 7]], [[null]]]}}
+                    }
+                ]
+            }
+        },
+        "vertices": [
+            [0, 0, 0], [1000, 0, 0], [1000, 1000, 0], [0, 1000, 0],
+            [0, 0, 1000], [1000, 0, 1000], [1000, 1000, 1000], [0, 1000, 1000]
+        ],
+        "appearance": {
+            "materials": [
+                {
+                    "name": "irradiation-0.5",
+                    "ambientIntensity": 0.4,
+                    "diffuseColor": [0.9, 0.1, 0.75],
+                    "shininess": 0.2,
+                    "transparency": 0.0,
+                    "isSmooth": false
+                },
+                {"name": "red", "diffuseColor": [1.0, 0.0, 0.0]}
+            ],
+            "textures": [
+                {
+                    "type": "PNG",
+                    "image": "winter.png",
+                    "wrapMode": "wrap",
+                    "textureType": "specific",
+                    "borderColor": [0.0, 0.1, 0.2, 1.0]
+                },
+                {"type": "JPG", "image": "summer.jpg"}
+            ],
+            "vertices-texture": [
+                [0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0],
+                [0.5, 0.5], [0.2, 0.3], [0.3, 0.4], [0.6, 0.7]
+            ],
+            "default-theme-texture": "winter",
+            "default-theme-material": "irradiation"
+        }
+    });
+    let jsonl = format!("{header}\n{feature}\n");
+    match read_cityjson_from_reader(BufReader::new(Cursor::new(jsonl)), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => Ok(seq),
+        _ => panic!("Expected CityJSONSeq"),
+    }
+}
+
+#[test]
+fn test_appearance_serialization_cycle() -> Result<()> {
+    let original_cj_seq = appearance_seq()?;
+
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: original_cj_seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut reader = FcbReader::open(Cursor::new(out))?.select_all()?;
+    let mut deserialized_features = Vec::new();
+    while let Some(feat_buf) = reader.next()? {
+        deserialized_features.push(feat_buf.cur_cj_feature()?);
+    }
+    assert_eq!(original_cj_seq.features.len(), deserialized_features.len());
+
+    for (orig_feat, des_feat) in original_cj_seq
+        .features
+        .iter()
+        .zip(deserialized_features.iter())
+    {
+        assert_eq!(
+            serde_json::to_value(&orig_feat.appearance)?,
+            serde_json::to_value(&des_feat.appearance)?
+        );
+
+        for (id, orig_co) in orig_feat.city_objects.iter() {
+            let des_co = &des_feat.city_objects[id];
+            let orig_geoms = orig_co.geometry.as_ref().unwrap();
+            let des_geoms = des_co.geometry.as_ref().unwrap();
+            assert_eq!(orig_geoms.len(), des_geoms.len());
+            for (orig_geom, des_geom) in orig_geoms.iter().zip(des_geoms.iter()) {
+                assert_eq!(orig_geom.lod, des_geom.lod);
+                assert_eq!(orig_geom.boundaries, des_geom.boundaries);
+                assert_eq!(orig_geom.material, des_geom.material);
+                assert_eq!(orig_geom.texture, des_geom.texture);
+            }
+        }
+    }
+
+    // the null sentinels are decoded as `None`
+    let geoms = deserialized_features[0].city_objects["textured"]
+        .geometry
+        .as_ref()
+        .unwrap();
+    let lod22_material = serde_json::to_value(&geoms[2].material)?;
+    assert_eq!(lod22_material["irradiation"]["values"], json!([null, 1]));
+
+    Ok(())
+}
+
+#[test]
+fn test_obj_export() -> Result<()> {
+    let seq = appearance_seq()?;
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut reader = FcbReader::open(Cursor::new(out))?.select_all()?;
+    let mut features = Vec::new();
+    while let Some(feat_buf) = reader.next()? {
+        features.push(feat_buf.cur_cj_feature()?);
+    }
+    let (obj, mtl) = serializer::to_obj(&features, &reader.header())?;
+
+    let lines = obj.lines().collect::<Vec<_>>();
+    let vertices = lines
+        .iter()
+        .filter(|l| l.starts_with("v "))
+        .collect::<Vec<_>>();
+    assert_eq!(vertices.len(), 8);
+    // the transform is applied
+    let last = vertices[7]
+        .split_whitespace()
+        .skip(1)
+        .map(|c| c.parse::<f64>().unwrap())
+        .collect::<Vec<_>>();
+    for (c, expected) in last.iter().zip([0.0, 1.0, 1.0]) {
+        assert!((c - expected).abs() < 1e-9);
+    }
+
+    // only the geometry with the highest LoD, its first surface has no material
+    let faces = lines
+        .iter()
+        .skip_while(|l| l.starts_with("v "))
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        faces,
+        [
+            "g textured",
+            "usemtl default",
+            "f 1 2 6 5",
+            "usemtl red",
+            "f 2 3 7 6"
+        ]
+    );
+
+    assert!(mtl.contains("newmtl default\n"));
+    assert!(mtl.contains("newmtl red\nKa 0.2 0 0\nKd 1 0 0\n"));
+    assert!(!mtl.contains("irradiation-0.5"));
+    Ok(())
+}
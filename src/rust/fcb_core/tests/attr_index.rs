@@ -3,7 +3,7 @@ use cjseq::CityJSONFeature;
 use fcb_core::{
     attribute::{AttributeSchema, AttributeSchemaMethods},
     header_writer::HeaderWriterOptions,
-    read_cityjson_from_reader, CJType, CJTypeKind, FcbReader, FcbWriter, Operator,
+    read_cityjson_from_reader, reindex, CJType, CJTypeKind, FcbReader, FcbWriter, Operator,
 };
 use std::{
     fs::File,
@@ -16,7 +16,10 @@ mod tests {
     use std::str::FromStr;
 
     use super::*;
-    use fcb_core::{FixedStringKey, Float, KeyType};
+    use fcb_core::{
+        attr_queries_from_expr, fb::ColumnType, reader_trait::Seekable, FeatureIter,
+        FixedStringKey, Float, KeyType, QueryExpr, SpatialQuery,
+    };
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -59,6 +62,12 @@ mod tests {
                 index_node_size: 16,
                 attribute_indices: Some(attr_indices),
                 geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
             }),
             Some(attr_schema),
             None,
@@ -82,7 +91,11 @@ mod tests {
         ];
         memory_buffer.seek(std::io::SeekFrom::Start(0))?;
 
-        let mut reader = FcbReader::open(memory_buffer)?.select_attr_query(query)?;
+        let mut reader = FcbReader::open(memory_buffer)?;
+        assert_eq!(reader.count_attr_query(&query)?, 1);
+        let only_height = query[..1].to_vec();
+        assert!(reader.count_attr_query(&only_height)? > 1);
+        let mut reader = reader.select_attr_query(query)?;
 
         let header = reader.header();
         let mut deserialized_features = Vec::new();
@@ -162,6 +175,12 @@ mod tests {
                 index_node_size: 16,
                 attribute_indices: Some(attr_indices),
                 geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
             }),
             Some(attr_schema),
             None,
@@ -261,6 +280,12 @@ mod tests {
                 index_node_size: 16,
                 attribute_indices: Some(attr_indices),
                 geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
             }),
             Some(attr_schema),
             None,
@@ -432,4 +457,806 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_composite_index() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        let mut attr_schema = AttributeSchema::new();
+        for feature in original_cj_seq.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+            }
+        }
+
+        // Value of the other column for the feature we look up
+        let identificatie = "NL.IMBAG.Pand.0503100000012869";
+        let b3_h_dak_50p = original_cj_seq
+            .features
+            .iter()
+            .flat_map(|f| f.city_objects.values())
+            .filter_map(|co| co.attributes.as_ref())
+            .find(|attrs| {
+                attrs.get("identificatie").and_then(|v| v.as_str()) == Some(identificatie)
+            })
+            .and_then(|attrs| attrs.get("b3_h_dak_50p"))
+            .and_then(|v| v.as_f64())
+            .expect("feature with b3_h_dak_50p");
+
+        let mut memory_buffer = Cursor::new(Vec::new());
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                write_index: true,
+                feature_count: original_cj_seq.features.len() as u64,
+                index_node_size: 16,
+                attribute_indices: Some(vec![("b3_h_dak_50p".to_string(), None)]),
+                geographical_extent: None,
+                composite_indices: Some(vec![(
+                    vec!["identificatie".to_string(), "b3_h_dak_50p".to_string()],
+                    None,
+                )]),
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
+            }),
+            Some(attr_schema),
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        fcb.write(&mut memory_buffer)?;
+
+        // Features are still found after the composite index
+        memory_buffer.seek(SeekFrom::Start(0))?;
+        let mut reader = FcbReader::open(&mut memory_buffer)?.select_all()?;
+        let mut feat_num = 0;
+        while let Some(feat_buf) = reader.next()? {
+            feat_buf.cur_cj_feature()?;
+            feat_num += 1;
+        }
+        assert_eq!(feat_num, original_cj_seq.features.len());
+
+        let conditions = [
+            (
+                "identificatie",
+                KeyType::StringKey50(FixedStringKey::from_str(identificatie)),
+            ),
+            ("b3_h_dak_50p", KeyType::Float64(Float(b3_h_dak_50p))),
+        ];
+        memory_buffer.seek(SeekFrom::Start(0))?;
+        let mut reader = FcbReader::open(&mut memory_buffer)?.select_composite(&conditions)?;
+        let mut ids = Vec::new();
+        while let Some(feat_buf) = reader.next()? {
+            ids.push(feat_buf.cur_cj_feature()?.id);
+        }
+        assert_eq!(ids.len(), 1);
+        let feature = original_cj_seq
+            .features
+            .iter()
+            .find(|f| f.id == ids[0])
+            .unwrap();
+        assert!(feature.city_objects.values().any(|co| co
+            .attributes
+            .as_ref()
+            .and_then(|attrs| attrs.get("identificatie"))
+            .and_then(|v| v.as_str())
+            == Some(identificatie)));
+
+        // A different value of one column matches nothing
+        let conditions = [
+            (
+                "identificatie",
+                KeyType::StringKey50(FixedStringKey::from_str(identificatie)),
+            ),
+            ("b3_h_dak_50p", KeyType::Float64(Float(b3_h_dak_50p + 1.0))),
+        ];
+        memory_buffer.seek(SeekFrom::Start(0))?;
+        let mut reader = FcbReader::open(&mut memory_buffer)?.select_composite(&conditions)?;
+        assert!(reader.next()?.is_none());
+
+        // Key order must match the index definition, and partial keys are not supported
+        let swapped = [
+            ("b3_h_dak_50p", KeyType::Float64(Float(b3_h_dak_50p))),
+            (
+                "identificatie",
+                KeyType::StringKey50(FixedStringKey::from_str(identificatie)),
+            ),
+        ];
+        memory_buffer.seek(SeekFrom::Start(0))?;
+        assert!(FcbReader::open(&mut memory_buffer)?
+            .select_composite(&swapped)
+            .is_err());
+        memory_buffer.seek(SeekFrom::Start(0))?;
+        assert!(FcbReader::open(&mut memory_buffer)?
+            .select_composite(&swapped[1..])
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_attribute_index() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        let mut attr_schema = AttributeSchema::new();
+        for feature in original_cj_seq.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+            }
+        }
+
+        // Write without any attribute index
+        let mut unindexed = Cursor::new(Vec::new());
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                write_index: true,
+                feature_count: original_cj_seq.features.len() as u64,
+                index_node_size: 16,
+                attribute_indices: None,
+                geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
+            }),
+            Some(attr_schema),
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        fcb.write(&mut unindexed)?;
+
+        unindexed.seek(SeekFrom::Start(0))?;
+        let mut indexed = Cursor::new(Vec::new());
+        reindex::append_attribute_index(
+            &mut unindexed,
+            &mut indexed,
+            &[("b3_h_dak_50p", 16), ("identificatie", 16)],
+        )?;
+
+        // The feature section is copied verbatim
+        let unindexed = unindexed.into_inner();
+        let indexed = indexed.into_inner();
+        let features_len = {
+            let mut iter = FcbReader::open(Cursor::new(&unindexed))?.select_all()?;
+            let mut len = 0;
+            while let Some(feat_buf) = iter.next()? {
+                len += feat_buf.cur_feature_len();
+            }
+            len
+        };
+        assert_eq!(
+            indexed[indexed.len() - features_len..],
+            unindexed[unindexed.len() - features_len..]
+        );
+
+        let query: Vec<(String, Operator, KeyType)> = vec![
+            (
+                "b3_h_dak_50p".to_string(),
+                Operator::Gt,
+                KeyType::Float64(Float(2.0)),
+            ),
+            (
+                "identificatie".to_string(),
+                Operator::Eq,
+                KeyType::StringKey50(FixedStringKey::from_str("NL.IMBAG.Pand.0503100000012869")),
+            ),
+        ];
+        let mut reader = FcbReader::open(Cursor::new(&indexed))?.select_attr_query(query)?;
+        let mut ids = Vec::new();
+        while let Some(feat_buf) = reader.next()? {
+            let feature = feat_buf.cur_cj_feature()?;
+            ids.extend(
+                feature
+                    .city_objects
+                    .values()
+                    .filter_map(|co| co.attributes.as_ref())
+                    .filter_map(|attrs| attrs.get("identificatie")?.as_str().map(String::from)),
+            );
+        }
+        assert!(ids.contains(&"NL.IMBAG.Pand.0503100000012869".to_string()));
+
+        // All features remain readable
+        let mut iter = FcbReader::open(Cursor::new(&indexed))?.select_all()?;
+        let mut feat_count = 0;
+        while iter.next()?.is_some() {
+            feat_count += 1;
+        }
+        assert_eq!(feat_count, original_cj_seq.features.len());
+
+        // Indexing an already indexed attribute is rejected
+        let result = reindex::append_attribute_index(
+            Cursor::new(&indexed),
+            &mut Vec::new(),
+            &[("identificatie", 16)],
+        );
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_with_rebuilt_index() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        let mut attr_schema = AttributeSchema::new();
+        for feature in original_cj_seq.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+            }
+        }
+
+        let write = |write_index: bool| -> Result<Vec<u8>> {
+            let mut fcb = FcbWriter::new(
+                original_cj_seq.cj.clone(),
+                Some(HeaderWriterOptions {
+                    write_index,
+                    feature_count: original_cj_seq.features.len() as u64,
+                    index_node_size: if write_index { 16 } else { 0 },
+                    attribute_indices: Some(vec![("identificatie".to_string(), None)]),
+                    compute_hash: true,
+                    ..Default::default()
+                }),
+                Some(attr_schema.clone()),
+                None,
+            )?;
+            for feature in original_cj_seq.features.iter() {
+                fcb.add_feature(feature)?;
+            }
+            let mut out = Vec::new();
+            fcb.write(&mut out)?;
+            Ok(out)
+        };
+        let unindexed = write(false)?;
+        let reference = write(true)?;
+        assert!(FcbReader::open(Cursor::new(&unindexed))?
+            .select_bbox(84000.0, 445000.0, 86000.0, 447000.0)
+            .is_err());
+
+        let mut rebuilt = Vec::new();
+        FcbWriter::write_with_rebuilt_index(Cursor::new(&unindexed), &mut rebuilt)?;
+        // the content hash covers the features in their new order
+        assert!(FcbReader::open(Cursor::new(&rebuilt))?.verify_hash()?);
+
+        let bbox_ids = |buf: &[u8], bbox: [f64; 4]| -> Result<Vec<String>> {
+            let mut iter = FcbReader::open(Cursor::new(buf))?
+                .select_bbox(bbox[0], bbox[1], bbox[2], bbox[3])?;
+            let mut ids = Vec::new();
+            while let Some(feat_buf) = iter.next()? {
+                ids.push(feat_buf.cur_cj_feature()?.id);
+            }
+            ids.sort();
+            Ok(ids)
+        };
+        for bbox in [
+            [84227.77, 445377.33, 85323.23, 446334.69],
+            [84800.0, 445800.0, 85000.0, 446000.0],
+        ] {
+            assert_eq!(bbox_ids(&rebuilt, bbox)?, bbox_ids(&reference, bbox)?);
+        }
+
+        // the attribute index points at the reordered features
+        let query: Vec<(String, Operator, KeyType)> = vec![(
+            "identificatie".to_string(),
+            Operator::Eq,
+            KeyType::StringKey50(FixedStringKey::from_str("NL.IMBAG.Pand.0503100000012869")),
+        )];
+        let mut reader = FcbReader::open(Cursor::new(&rebuilt))?.select_attr_query(query)?;
+        let feat_buf = reader
+            .next()?
+            .expect("feature matching the attribute query");
+        let feature = feat_buf.cur_cj_feature()?;
+        assert!(feature.city_objects.values().any(|co| {
+            co.attributes
+                .as_ref()
+                .and_then(|attrs| attrs.get("identificatie"))
+                .is_some_and(|id| *id == "NL.IMBAG.Pand.0503100000012869")
+        }));
+
+        let mut iter = FcbReader::open(Cursor::new(&rebuilt))?.select_all()?;
+        let mut feat_count = 0;
+        while iter.next()?.is_some() {
+            feat_count += 1;
+        }
+        assert_eq!(feat_count, original_cj_seq.features.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sql_where_clause() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        let mut attr_schema = AttributeSchema::new();
+        // one identificatie per feature
+        let mut identificaties = Vec::new();
+        for feature in original_cj_seq.features.iter() {
+            let mut id = None;
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                    id = id.or(attributes.get("identificatie").and_then(|v| v.as_str()));
+                }
+            }
+            identificaties.extend(id.map(String::from));
+        }
+        let mut memory_buffer = Cursor::new(Vec::new());
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: original_cj_seq.features.len() as u64,
+                attribute_indices: Some(vec![
+                    ("b3_h_dak_50p".to_string(), None),
+                    ("identificatie".to_string(), None),
+                ]),
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        fcb.write(&mut memory_buffer)?;
+        let data = memory_buffer.into_inner();
+
+        let run = |sql: &str| -> Result<Vec<String>> {
+            let expr = QueryExpr::from_sql_where_clause(sql)?;
+            let reader = FcbReader::open(Cursor::new(&data))?;
+            let columns: Vec<_> = reader.header().columns().unwrap().iter().collect();
+            let queries = attr_queries_from_expr(&expr, &columns)?;
+            let mut ids = Vec::new();
+            for query in queries {
+                let mut iter = FcbReader::open(Cursor::new(&data))?.select_attr_query(query)?;
+                while let Some(feat_buf) = iter.next()? {
+                    ids.push(feat_buf.cur_cj_feature()?.id);
+                }
+            }
+            ids.sort();
+            ids.dedup();
+            Ok(ids)
+        };
+
+        let [first, second] = [&identificaties[0], &identificaties[1]];
+        let ids = run(&format!(
+            "identificatie = '{first}' OR (identificatie IN ('{second}', 'none') AND identificatie != 'none')"
+        ))?;
+        assert_eq!(ids.len(), 2);
+        let ids = run(&format!("identificatie = '{first}'"))?;
+        assert_eq!(ids.len(), 1);
+
+        assert!(run("unknown = 1").is_err());
+        assert!(run("identificatie = 5").is_err());
+        let err = QueryExpr::from_sql_where_clause("identificatie = ").unwrap_err();
+        assert_eq!(err.position, "identificatie = ".len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_where_with_spatial_query() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        let mut attr_schema = AttributeSchema::new();
+        for feature in original_cj_seq.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: original_cj_seq.features.len() as u64,
+                attribute_indices: Some(vec![
+                    ("b3_h_dak_50p".to_string(), None),
+                    ("identificatie".to_string(), None),
+                ]),
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut data = Vec::new();
+        fcb.write(&mut data)?;
+
+        fn collect_ids(mut iter: FeatureIter<Cursor<&Vec<u8>>, Seekable>) -> Result<Vec<String>> {
+            let mut ids = Vec::new();
+            while let Some(feat_buf) = iter.next()? {
+                ids.push(feat_buf.cur_cj_feature()?.id);
+            }
+            ids.sort();
+            Ok(ids)
+        }
+
+        let bbox = [84227.77, 445377.33, 85323.23, 446334.69];
+        let bbox_ids = collect_ids(
+            FcbReader::open(Cursor::new(&data))?.select_bbox(bbox[0], bbox[1], bbox[2], bbox[3])?,
+        )?;
+        let mut iter =
+            FcbReader::open(Cursor::new(&data))?.select_bbox(bbox[0], bbox[1], bbox[2], bbox[3])?;
+        let inside = iter.next()?.expect("bbox has features").cur_cj_feature()?;
+        let inside_identificatie = inside
+            .city_objects
+            .values()
+            .find_map(|co| co.attributes.as_ref()?.get("identificatie")?.as_str())
+            .expect("feature has an identificatie")
+            .to_string();
+
+        let sql = format!("identificatie = '{inside_identificatie}' OR b3_h_dak_50p > 2.0");
+        let expr = QueryExpr::from_sql_where_clause(&sql)?;
+        let columns: Vec<_> = FcbReader::open(Cursor::new(&data))?
+            .header()
+            .columns()
+            .unwrap()
+            .iter()
+            .collect();
+        let mut attr_ids = Vec::new();
+        for query in attr_queries_from_expr(&expr, &columns)? {
+            let iter = FcbReader::open(Cursor::new(&data))?.select_attr_query(query)?;
+            attr_ids.extend(collect_ids(iter)?);
+        }
+        attr_ids.sort();
+        attr_ids.dedup();
+        let expected = attr_ids
+            .iter()
+            .filter(|id| bbox_ids.contains(id))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!(expected.contains(&inside.id));
+
+        let iter = FcbReader::open(Cursor::new(&data))?.select_where(
+            Some(SpatialQuery::BBox(bbox[0], bbox[1], bbox[2], bbox[3])),
+            &expr,
+        )?;
+        assert_eq!(collect_ids(iter)?, expected);
+
+        // without a spatial query only the attribute conditions apply
+        let iter = FcbReader::open(Cursor::new(&data))?.select_where(None, &expr)?;
+        assert_eq!(collect_ids(iter)?, attr_ids);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_by_id() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        let mut attr_schema = AttributeSchema::new();
+        for feature in original_cj_seq.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: original_cj_seq.features.len() as u64,
+                attribute_indices: Some(vec![("b3_h_dak_50p".to_string(), None)]),
+                feature_id_index: true,
+                ..Default::default()
+            }),
+            Some(attr_schema.clone()),
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut data = Vec::new();
+        fcb.write(&mut data)?;
+
+        let mut reader = FcbReader::open(Cursor::new(&data))?;
+        // the reserved column of the index isn't an attribute
+        assert_eq!(
+            AttributeSchema::from_fcb_header(&reader.header()),
+            attr_schema
+        );
+        for original in original_cj_seq.features.iter() {
+            let feat_buf = reader
+                .find_by_id(&original.id)?
+                .expect("feature is in the index");
+            assert_eq!(feat_buf.feature().id(), original.id);
+            assert_eq!(
+                feat_buf.cj_feature()?.city_objects.len(),
+                original.city_objects.len()
+            );
+        }
+        assert!(reader.find_by_id("does-not-exist")?.is_none());
+
+        // the reader can still select features after a lookup
+        let mut iter = reader.select_attr_query(vec![(
+            "b3_h_dak_50p".to_string(),
+            Operator::Gt,
+            KeyType::Float64(Float(2.0)),
+        )])?;
+        assert!(iter.next()?.is_some());
+
+        // without the index, lookups fail
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: original_cj_seq.features.len() as u64,
+                ..Default::default()
+            }),
+            None,
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut data = Vec::new();
+        fcb.write(&mut data)?;
+        assert!(FcbReader::open(Cursor::new(&data))?
+            .find_by_id(&original_cj_seq.features[0].id)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_attr_index() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let mut original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        // negative values and values beyond the i32 range, so the column is a Long one
+        let mut expected = Vec::new();
+        for (i, feature) in original_cj_seq.features.iter_mut().enumerate() {
+            let value = if i % 3 == 0 {
+                -(1_i64 << 40) + i as i64
+            } else {
+                i as i64 * 60 - 200
+            };
+            if value > -100 {
+                expected.push(feature.id.clone());
+            }
+            for co in feature.city_objects.values_mut() {
+                let attributes = co
+                    .attributes
+                    .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+                attributes["signed_id"] = serde_json::Value::from(value);
+            }
+        }
+        expected.sort();
+        assert!(!expected.is_empty() && expected.len() < original_cj_seq.features.len());
+
+        let mut attr_schema = AttributeSchema::new();
+        for feature in original_cj_seq.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+            }
+        }
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: original_cj_seq.features.len() as u64,
+                attribute_indices: Some(vec![("signed_id".to_string(), None)]),
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut data = Vec::new();
+        fcb.write(&mut data)?;
+
+        let query = vec![("signed_id".to_string(), Operator::Gt, KeyType::Int64(-100))];
+
+        // stream index
+        let mut iter = FcbReader::open(Cursor::new(&data))?.select_attr_query(query.clone())?;
+        let mut ids = Vec::new();
+        while let Some(feat_buf) = iter.next()? {
+            ids.push(feat_buf.cur_cj_feature()?.id);
+        }
+        ids.sort();
+        assert_eq!(ids, expected);
+
+        // memory index
+        let mut iter = FcbReader::open(Cursor::new(&data))?.select_attr_query_seq(query)?;
+        let mut ids = Vec::new();
+        while let Some(feat_buf) = iter.next()? {
+            ids.push(feat_buf.cur_cj_feature()?.id);
+        }
+        ids.sort();
+        assert_eq!(ids, expected);
+
+        // the smallest values sort first
+        let query = vec![(
+            "signed_id".to_string(),
+            Operator::Lt,
+            KeyType::Int64(-(1_i64 << 39)),
+        )];
+        let count = FcbReader::open(Cursor::new(&data))?.count_attr_query(&query)?;
+        assert_eq!(count, original_cj_seq.features.len().div_ceil(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_short_attr_index() -> Result<()> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = manifest_dir
+            .join("tests")
+            .join("data")
+            .join("small.city.jsonl");
+        let input_reader = BufReader::new(File::open(input_file)?);
+        let mut original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+        // the first year is negative, it must sort before all the others
+        let mut expected_years = Vec::new();
+        let mut expected_levels = Vec::new();
+        for (i, feature) in original_cj_seq.features.iter_mut().enumerate() {
+            let year = if i == 0 { -50 } else { 1900 + i as i64 * 15 };
+            let level = 40_000 + i as u64;
+            if year >= 1950 {
+                expected_years.push(feature.id.clone());
+            }
+            if level > 40_001 {
+                expected_levels.push(feature.id.clone());
+            }
+            for co in feature.city_objects.values_mut() {
+                let attributes = co
+                    .attributes
+                    .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+                attributes["year"] = serde_json::Value::from(year);
+                attributes["level"] = serde_json::Value::from(level);
+            }
+        }
+        expected_years.sort();
+        expected_levels.sort();
+        assert!(!expected_years.is_empty());
+
+        let mut attr_schema = AttributeSchema::new();
+        attr_schema.insert("year".to_string(), (0, ColumnType::Short));
+        attr_schema.insert("level".to_string(), (1, ColumnType::UShort));
+        for feature in original_cj_seq.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+            }
+        }
+        assert_eq!(attr_schema["year"].1, ColumnType::Short);
+        assert_eq!(attr_schema["level"].1, ColumnType::UShort);
+
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: original_cj_seq.features.len() as u64,
+                attribute_indices: Some(vec![
+                    ("year".to_string(), None),
+                    ("level".to_string(), None),
+                ]),
+                ..Default::default()
+            }),
+            Some(attr_schema),
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut data = Vec::new();
+        fcb.write(&mut data)?;
+
+        fn collect_ids(mut iter: FeatureIter<Cursor<&Vec<u8>>, Seekable>) -> Result<Vec<String>> {
+            let mut ids = Vec::new();
+            while let Some(feat_buf) = iter.next()? {
+                ids.push(feat_buf.cur_cj_feature()?.id);
+            }
+            ids.sort();
+            Ok(ids)
+        }
+
+        let query = vec![("year".to_string(), Operator::Gte, KeyType::Int16(1950))];
+        let ids = collect_ids(FcbReader::open(Cursor::new(&data))?.select_attr_query(query)?)?;
+        assert_eq!(ids, expected_years);
+
+        let query = vec![("year".to_string(), Operator::Lt, KeyType::Int16(0))];
+        let ids = collect_ids(FcbReader::open(Cursor::new(&data))?.select_attr_query(query)?)?;
+        assert_eq!(ids, vec![original_cj_seq.features[0].id.clone()]);
+
+        let query = vec![("level".to_string(), Operator::Gt, KeyType::UInt16(40_001))];
+        let ids = collect_ids(FcbReader::open(Cursor::new(&data))?.select_attr_query(query)?)?;
+        assert_eq!(ids, expected_levels);
+
+        // memory index
+        let query = vec![("year".to_string(), Operator::Gte, KeyType::Int16(1950))];
+        let mut iter = FcbReader::open(Cursor::new(&data))?.select_attr_query_seq(query)?;
+        let mut ids = Vec::new();
+        while let Some(feat_buf) = iter.next()? {
+            ids.push(feat_buf.cur_cj_feature()?.id);
+        }
+        ids.sort();
+        assert_eq!(ids, expected_years);
+
+        Ok(())
+    }
 }
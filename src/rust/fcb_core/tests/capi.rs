@@ -0,0 +1,80 @@
+#![cfg(feature = "capi")]
+
+use anyhow::Result;
+use fcb_core::capi::{
+    fcb_iter_free, fcb_iter_next, fcb_last_error, fcb_reader_free, fcb_reader_open,
+    fcb_reader_select_bbox,
+};
+use fcb_core::{
+    header_writer::HeaderWriterOptions, read_cityjson_from_reader, CJType, CJTypeKind, FcbWriter,
+};
+use std::{
+    ffi::{CStr, CString},
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+use tempfile::NamedTempFile;
+
+#[test]
+fn capi_select_bbox() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/delft.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    let temp_fcb = NamedTempFile::new()?;
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write(BufWriter::new(File::create(&temp_fcb)?))?;
+
+    let path = CString::new(temp_fcb.path().to_str().unwrap())?;
+    unsafe {
+        let reader = fcb_reader_open(path.as_ptr());
+        assert!(!reader.is_null());
+        assert!(fcb_last_error().is_null());
+
+        let iter = fcb_reader_select_bbox(reader, 84227.77, 445377.33, 85323.23, 446334.69);
+        assert!(!iter.is_null());
+        // the iterator does not borrow the reader
+        fcb_reader_free(reader);
+
+        let mut count = 0;
+        loop {
+            let feature = fcb_iter_next(iter);
+            if feature.is_null() {
+                break;
+            }
+            let json: serde_json::Value = serde_json::from_str(CStr::from_ptr(feature).to_str()?)?;
+            assert_eq!(json["type"], "CityJSONFeature");
+            count += 1;
+        }
+        assert!(fcb_last_error().is_null());
+        assert!(count > 0 && count < seq.features.len());
+        fcb_iter_free(iter);
+
+        let missing = CString::new("does/not/exist.fcb")?;
+        assert!(fcb_reader_open(missing.as_ptr()).is_null());
+        let error = CStr::from_ptr(fcb_last_error()).to_str()?;
+        assert!(error.starts_with("IO error"));
+
+        assert!(fcb_iter_next(std::ptr::null_mut()).is_null());
+        assert!(!fcb_last_error().is_null());
+        fcb_iter_free(std::ptr::null_mut());
+        fcb_reader_free(std::ptr::null_mut());
+    }
+
+    Ok(())
+}
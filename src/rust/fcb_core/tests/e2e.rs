@@ -4,7 +4,7 @@ use fcb_core::{
     attribute::{AttributeSchema, AttributeSchemaMethods},
     deserializer,
     header_writer::HeaderWriterOptions,
-    read_cityjson_from_reader, CJType, CJTypeKind, FcbReader, FcbWriter,
+    read_cityjson_from_reader, serializer, CJType, CJTypeKind, FcbReader, FcbWriter,
 };
 use pretty_assertions::assert_eq;
 use std::{
@@ -54,6 +54,12 @@ fn test_cityjson_serialization_cycle() -> Result<()> {
                 index_node_size: 16,
                 attribute_indices: None,
                 geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
             }),
             Some(attr_schema),
             None,
@@ -325,6 +331,12 @@ fn test_geometry_template_cycle() -> Result<()> {
                 index_node_size: 16,
                 attribute_indices: None,
                 geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
             }),
             Some(attr_schema),
             None,
@@ -503,6 +515,12 @@ fn test_extension_serialization_cycle() -> Result<()> {
                 index_node_size: 16,
                 attribute_indices: None,
                 geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
             }),
             Some(attr_schema),
             None,
@@ -523,6 +541,16 @@ fn test_extension_serialization_cycle() -> Result<()> {
     let deserialized_cj = deserializer::to_cj_metadata(&header)?;
 
     // Compare extensions
+    let noise = deserialized_cj
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("Noise"))
+        .expect("Noise extension should be decoded from the header");
+    assert_eq!(
+        noise.url,
+        "https://www.cityjson.org/tutorials/files/noise.ext.json"
+    );
+    assert_eq!(noise.version, "1.1");
     if let (Some(orig_ext), Some(des_ext)) =
         (&original_cj_seq.cj.extensions, &deserialized_cj.extensions)
     {
@@ -664,3 +692,165 @@ fn test_extension_serialization_cycle() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_semantic_attributes_cycle() -> Result<()> {
+    let header = serde_json::json!({
+        "type": "CityJSON",
+        "version": "2.0",
+        "transform": {"scale": [0.001, 0.001, 0.001], "translate": [0.0, 0.0, 0.0]},
+        "CityObjects": {},
+        "vertices": []
+    });
+    let feature = serde_json::json!({
+        "type": "CityJSONFeature",
+        "id": "building",
+        "CityObjects": {
+            "building": {
+                "type": "Building",
+                "geometry": [{
+                    "type": "MultiSurface",
+                    "lod": "2",
+                    "boundaries": [[[0, 1, 2, 3]], [[0, 1, 5, 4]], [[1, 2, 6, 5]]],
+                    "semantics": {
+                        "surfaces": [
+                            {"type": "RoofSurface", "slope": 33.4, "colour": "blue", "irradiation": 1203},
+                            {"type": "WallSurface", "colour": "red"},
+                            {"type": "GroundSurface"}
+                        ],
+                        "values": [0, 1, 2]
+                    }
+                }]
+            }
+        },
+        "vertices": [
+            [0, 0, 1000], [1000, 0, 1000], [1000, 1000, 1000], [0, 1000, 1000],
+            [0, 0, 0], [1000, 0, 0], [1000, 1000, 0]
+        ]
+    });
+    let jsonl = format!("{header}\n{feature}\n");
+    let original_cj_seq =
+        match read_cityjson_from_reader(BufReader::new(jsonl.as_bytes()), CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+    let mut semantic_attr_schema = AttributeSchema::new();
+    for feature in original_cj_seq.features.iter() {
+        for co in feature.city_objects.values() {
+            for geom in co.geometry.iter().flatten() {
+                let surfaces = geom.semantics.iter().flat_map(|s| s.surfaces.iter());
+                for other in surfaces.filter_map(|s| s.other.as_ref()) {
+                    semantic_attr_schema.add_attributes(other);
+                }
+            }
+        }
+    }
+
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: original_cj_seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        Some(semantic_attr_schema),
+    )?;
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut reader = FcbReader::open(std::io::Cursor::new(out))?.select_all()?;
+    let feat_buf = reader.next()?.expect("one feature");
+    let deserialized = feat_buf.cur_cj_feature()?;
+
+    let orig_geom = &original_cj_seq.features[0].city_objects["building"]
+        .geometry
+        .as_ref()
+        .unwrap()[0];
+    let des_geom = &deserialized.city_objects["building"]
+        .geometry
+        .as_ref()
+        .unwrap()[0];
+    let orig_surfaces = &orig_geom.semantics.as_ref().unwrap().surfaces;
+    let des_surfaces = &des_geom.semantics.as_ref().unwrap().surfaces;
+    assert_eq!(orig_surfaces.len(), des_surfaces.len());
+    for (orig, des) in orig_surfaces.iter().zip(des_surfaces.iter()) {
+        assert_eq!(orig.thetype, des.thetype);
+        assert_eq!(orig.other, des.other);
+    }
+    assert_eq!(
+        Some(serde_json::json!({"slope": 33.4, "colour": "blue", "irradiation": 1203})),
+        des_surfaces[0].other
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_geojson_feature() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/small.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut attr_schema = AttributeSchema::new();
+    for feature in seq.features.iter() {
+        for (_, co) in feature.city_objects.iter() {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+    }
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema),
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut buf = Vec::new();
+    fcb.write(&mut buf)?;
+
+    let mut reader = FcbReader::open(std::io::Cursor::new(buf))?.select_all()?;
+    let mut count = 0;
+    while let Some(feat_buf) = reader.next()? {
+        let cj_feature = feat_buf.cur_cj_feature()?;
+        let geojson = serializer::to_geojson_feature(feat_buf.cur_feature(), feat_buf.header())?;
+        count += 1;
+
+        assert_eq!("Feature", geojson["type"]);
+        assert_eq!(cj_feature.id, geojson["id"]);
+        let main = &cj_feature.city_objects[&cj_feature.id];
+        assert_eq!(main.thetype, geojson["properties"]["type"]);
+        if let Some(serde_json::Value::Object(attributes)) = &main.attributes {
+            for (key, value) in attributes {
+                if key != "type" {
+                    assert_eq!(value, &geojson["properties"][key]);
+                }
+            }
+        }
+
+        assert_eq!("MultiPolygon", geojson["geometry"]["type"]);
+        let polygons = geojson["geometry"]["coordinates"].as_array().unwrap();
+        assert!(!polygons.is_empty());
+        for ring in polygons.iter().flat_map(|p| p.as_array().unwrap()) {
+            let ring = ring.as_array().unwrap();
+            assert!(ring.len() >= 4);
+            // rings are closed and the transform is applied
+            assert_eq!(ring.first(), ring.last());
+            assert!(ring[0][0].as_f64().unwrap() > 80000.0);
+        }
+    }
+    assert_eq!(seq.features.len(), count);
+    Ok(())
+}
@@ -1,13 +1,20 @@
 use anyhow::Result;
-use fcb_core::packed_rtree::Query;
+use fallible_streaming_iterator::FallibleStreamingIterator;
+use fcb_core::packed_rtree::{NodeLayout, PackedRTree, Query};
 use fcb_core::{
     attribute::{AttributeSchema, AttributeSchemaMethods},
+    deserializer::{to_cj_feature_with_options, DeserializeOptions},
     header_writer::HeaderWriterOptions,
-    read_cityjson_from_reader, CJType, CJTypeKind, FcbReader, FcbWriter,
+    point_in_ring, read_cityjson_from_reader,
+    reader_trait::Seekable,
+    stats::compute_attribute_stats,
+    CJType, CJTypeKind, CityObjectType, CityObjectTypeFilter, FcbReader, FcbWriter, FeatureIter,
+    TileIndex, TiledFcbReader,
 };
 use std::{
+    collections::HashSet,
     fs::File,
-    io::{BufReader, Cursor, Seek},
+    io::{BufReader, Cursor, Read, Seek},
     path::PathBuf,
 };
 
@@ -44,6 +51,12 @@ fn read_bbox() -> Result<()> {
             index_node_size: 16,
             attribute_indices: Some(attr_indices),
             geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
         }),
         Some(attr_schema),
         None,
@@ -62,8 +75,10 @@ fn read_bbox() -> Result<()> {
     let maxx = 85323.23;
     let maxy = 446334.69;
 
-    let mut fcb =
-        FcbReader::open(&mut memory_buffer)?.select_query(Query::BBox(minx, miny, maxx, maxy))?;
+    // counting reads only the index and leaves the reader ready for a selection
+    let mut reader = FcbReader::open(&mut memory_buffer)?;
+    let counted = reader.count_bbox(minx, miny, maxx, maxy)?;
+    let mut fcb = reader.select_query(Query::BBox(minx, miny, maxx, maxy))?;
 
     assert_ne!(fcb.features_count(), None);
     let mut features = Vec::new();
@@ -81,6 +96,7 @@ fn read_bbox() -> Result<()> {
     );
 
     assert!(bbox_cnt < fcb.header().features_count());
+    assert_eq!(counted, bbox_cnt as usize);
 
     let mut count_to_check = 0;
     for feature in features {
@@ -101,6 +117,565 @@ fn read_bbox() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn read_polygon() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    let mut memory_buffer = Cursor::new(Vec::new());
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: true,
+            feature_count: original_cj_seq.features.len() as u64,
+            index_node_size: 16,
+            attribute_indices: None,
+            geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
+        }),
+        None,
+        None,
+    )?;
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write(&mut memory_buffer)?;
+
+    let (minx, miny, maxx, maxy) = (84227.77, 445377.33, 85323.23, 446334.69);
+    // lower left half of the bbox
+    let ring = [[minx, miny], [maxx, miny], [minx, maxy]];
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb =
+        FcbReader::open(&mut memory_buffer)?.select_query(Query::BBox(minx, miny, maxx, maxy))?;
+    let mut bbox_cnt = 0;
+    while fcb.next()?.is_some() {
+        bbox_cnt += 1;
+    }
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?.select_polygon(&ring)?;
+    let polygon_cnt = fcb.features_count().unwrap();
+    let transform = &original_cj_seq.cj.transform;
+    let mut features = Vec::new();
+    while let Some(feature) = fcb.next()? {
+        features.push(feature.cur_cj_feature()?);
+    }
+    assert_eq!(features.len(), polygon_cnt);
+    assert!(polygon_cnt > 0);
+    assert!(polygon_cnt < bbox_cnt);
+
+    for feature in features {
+        assert!(feature.vertices.iter().any(|v| {
+            let x = v[0] as f64 * transform.scale[0] + transform.translate[0];
+            let y = v[1] as f64 * transform.scale[1] + transform.translate[1];
+            point_in_ring([x, y], &ring)
+        }));
+    }
+
+    // degenerate ring
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    assert!(FcbReader::open(&mut memory_buffer)?
+        .select_polygon(&ring[..2])
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn read_custom_node_size() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    let write = |index_node_size: u16| -> Result<Vec<u8>> {
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: original_cj_seq.features.len() as u64,
+                index_node_size,
+                ..Default::default()
+            }),
+            None,
+            None,
+        )?;
+        for feature in original_cj_seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut out = Vec::new();
+        fcb.write(&mut out)?;
+        Ok(out)
+    };
+    let collect_ids = |mut iter: FeatureIter<Cursor<&Vec<u8>>, Seekable>| -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        while let Some(feature) = iter.next()? {
+            ids.push(feature.cur_cj_feature()?.id);
+        }
+        ids.sort();
+        Ok(ids)
+    };
+
+    let (minx, miny, maxx, maxy) = (84227.77, 445377.33, 85323.23, 446334.69);
+    let ring = [[minx, miny], [maxx, miny], [minx, maxy]];
+    let query = Query::BBox(minx, miny, maxx, maxy);
+
+    let reference = write(PackedRTree::DEFAULT_NODE_SIZE)?;
+    let small_nodes = write(4)?;
+    let bbox_ids = collect_ids(FcbReader::open(Cursor::new(&reference))?.select_query(query)?)?;
+    let polygon_ids =
+        collect_ids(FcbReader::open(Cursor::new(&reference))?.select_polygon(&ring)?)?;
+    assert!(!polygon_ids.is_empty());
+    assert!(polygon_ids.len() < bbox_ids.len());
+
+    // the searches use the node size of the file
+    let mut reader = FcbReader::open(Cursor::new(&small_nodes))?;
+    assert_eq!(reader.header().index_node_size(), 4);
+    assert_eq!(reader.count_query(query)?, bbox_ids.len());
+    assert_eq!(collect_ids(reader.select_query(query)?)?, bbox_ids);
+    assert_eq!(
+        collect_ids(FcbReader::open(Cursor::new(&small_nodes))?.select_polygon(&ring)?)?,
+        polygon_ids
+    );
+
+    Ok(())
+}
+
+#[test]
+fn count_empty_indexed_file() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/small.city.jsonl"))?;
+    let CJType::Seq(seq) = read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)?
+    else {
+        panic!("Expected CityJSONSeq");
+    };
+    let mut out = Vec::new();
+    FcbWriter::new(seq.cj, None, None, None)?.write(&mut out)?;
+
+    let mut reader = FcbReader::open(Cursor::new(&out))?;
+    assert!(reader.header().index_node_size() > 0);
+    assert_eq!(
+        reader.count_bbox(f64::MIN, f64::MIN, f64::MAX, f64::MAX)?,
+        0
+    );
+    assert_eq!(reader.count_query(Query::KNearest(0.0, 0.0, 1))?, 0);
+    Ok(())
+}
+
+#[test]
+fn read_nearest() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    let mut memory_buffer = Cursor::new(Vec::new());
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: true,
+            feature_count: original_cj_seq.features.len() as u64,
+            index_node_size: 16,
+            attribute_indices: None,
+            geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
+        }),
+        None,
+        None,
+    )?;
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write(&mut memory_buffer)?;
+
+    // squared distance from the point to the centre of the feature bbox, as stored in the R-tree
+    let transform = &original_cj_seq.cj.transform;
+    let centroid_dist = |vertices: &[Vec<i64>], x: f64, y: f64| {
+        let coord =
+            |v: &Vec<i64>, i: usize| v[i] as f64 * transform.scale[i] + transform.translate[i];
+        let (min_x, max_x) = vertices
+            .iter()
+            .map(|v| coord(v, 0))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), c| {
+                (lo.min(c), hi.max(c))
+            });
+        let (min_y, max_y) = vertices
+            .iter()
+            .map(|v| coord(v, 1))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), c| {
+                (lo.min(c), hi.max(c))
+            });
+        let (dx, dy) = ((min_x + max_x) / 2.0 - x, (min_y + max_y) / 2.0 - y);
+        dx * dx + dy * dy
+    };
+
+    let (x, y) = (84827.77, 445877.33);
+    let closest = original_cj_seq
+        .features
+        .iter()
+        .min_by(|a, b| {
+            centroid_dist(&a.vertices, x, y)
+                .partial_cmp(&centroid_dist(&b.vertices, x, y))
+                .unwrap()
+        })
+        .unwrap();
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?.select_nearest(x, y, 1)?;
+    let nearest = fcb.next()?.unwrap().cur_cj_feature()?;
+    assert_eq!(nearest.id, closest.id);
+    assert!(fcb.next()?.is_none());
+
+    // the k nearest contain the top-1 and are no farther than any feature left out
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?.select_nearest(x, y, 10)?;
+    let mut nearest = Vec::new();
+    while let Some(feature) = fcb.next()? {
+        nearest.push(feature.cur_cj_feature()?);
+    }
+    assert_eq!(nearest.len(), 10);
+    assert!(nearest.iter().any(|f| f.id == closest.id));
+    let farthest = nearest
+        .iter()
+        .map(|f| centroid_dist(&f.vertices, x, y))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let closer_left_out = original_cj_seq
+        .features
+        .iter()
+        .filter(|f| !nearest.iter().any(|n| n.id == f.id))
+        .filter(|f| centroid_dist(&f.vertices, x, y) < farthest)
+        .count();
+    assert_eq!(closer_left_out, 0);
+
+    Ok(())
+}
+
+#[test]
+fn read_all_seq_skips_indices() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut attr_schema = AttributeSchema::new();
+    for co in original_cj_seq
+        .features
+        .iter()
+        .flat_map(|f| f.city_objects.values())
+    {
+        if let Some(attributes) = &co.attributes {
+            attr_schema.add_attributes(attributes);
+        }
+    }
+
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: original_cj_seq.features.len() as u64,
+            attribute_indices: Some(vec![("b3_h_dak_50p".to_string(), None)]),
+            composite_indices: Some(vec![(
+                vec!["identificatie".to_string(), "b3_h_dak_50p".to_string()],
+                None,
+            )]),
+            ..Default::default()
+        }),
+        Some(attr_schema),
+        None,
+    )?;
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let reader = FcbReader::open(Cursor::new(&out))?;
+    let header = reader.header();
+    assert!(header.index_node_size() > 0);
+    assert!(header.attribute_index().is_some_and(|ai| !ai.is_empty()));
+    assert!(header.composite_index().is_some_and(|ci| !ci.is_empty()));
+    let mut iter = reader.select_all()?;
+    let mut expected = Vec::new();
+    while let Some(feature) = iter.next()? {
+        expected.push(feature.cur_cj_feature()?.id);
+    }
+
+    // a byte slice cannot seek, the indices have to be read and discarded
+    let mut iter = FcbReader::open(out.as_slice())?.select_all_seq()?;
+    let mut ids = Vec::new();
+    while let Some(feature) = iter.next()? {
+        ids.push(feature.cur_cj_feature()?.id);
+    }
+    assert_eq!(ids.len(), original_cj_seq.features.len());
+    assert_eq!(ids, expected);
+
+    Ok(())
+}
+
+#[test]
+fn read_filter_by_type() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    let mut memory_buffer = Cursor::new(Vec::new());
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: true,
+            feature_count: original_cj_seq.features.len() as u64,
+            index_node_size: 16,
+            attribute_indices: None,
+            geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
+        }),
+        None,
+        None,
+    )?;
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write(&mut memory_buffer)?;
+
+    // every feature of the Delft dataset is a Building with BuildingParts
+    let buildings = CityObjectTypeFilter::from_names(&["Building"])?;
+    let water = CityObjectTypeFilter::from_types(&[CityObjectType::WaterBody]);
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?
+        .select_all()?
+        .filter_by_type(buildings);
+    let mut cnt = 0;
+    while let Some(feature) = fcb.next()? {
+        let cj_feature = feature.cur_cj_feature()?;
+        assert!(cj_feature
+            .city_objects
+            .values()
+            .any(|co| co.thetype == "Building"));
+        cnt += 1;
+    }
+    assert_eq!(cnt, original_cj_seq.features.len());
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?
+        .select_bbox(84227.77, 445377.33, 85323.23, 446334.69)?
+        .filter_by_type(water);
+    assert!(fcb.next()?.is_none());
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?
+        .select_all_seq()?
+        .filter_by_type(water);
+    assert!(fcb.next()?.is_none());
+
+    // the sequential bbox selection yields the same features as the seekable one
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?
+        .select_bbox(84227.77, 445377.33, 85323.23, 446334.69)?;
+    let mut seekable_ids = Vec::new();
+    while let Some(feature) = fcb.next()? {
+        seekable_ids.push(feature.cur_feature().id().to_string());
+    }
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?
+        .select_bbox_seq(84227.77, 445377.33, 85323.23, 446334.69)?;
+    let mut seq_ids = Vec::new();
+    while let Some(feature) = fcb.next()? {
+        seq_ids.push(feature.cur_feature().id().to_string());
+    }
+    assert!(!seq_ids.is_empty());
+    assert_eq!(seekable_ids, seq_ids);
+
+    Ok(())
+}
+
+#[test]
+fn read_lod_filter() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    let mut memory_buffer = Cursor::new(Vec::new());
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: 10,
+            index_node_size: 16,
+            attribute_indices: None,
+            geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
+        }),
+        None,
+        None,
+    )?;
+    for feature in original_cj_seq.features.iter().take(10) {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write(&mut memory_buffer)?;
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut fcb = FcbReader::open(&mut memory_buffer)?.select_all()?;
+    let lod22_only = ["2.2".to_string()];
+    let lod22 = DeserializeOptions {
+        lods: Some(&lod22_only),
+    };
+    let lod30_only = ["3.0".to_string()];
+    let missing = DeserializeOptions {
+        lods: Some(&lod30_only),
+    };
+    let mut lod22_cnt = 0;
+    while let Some(feature) = fcb.next()? {
+        let header = feature.header();
+        let decode = |options: &DeserializeOptions| {
+            to_cj_feature_with_options(
+                feature.cur_feature(),
+                header.columns(),
+                header.semantic_columns(),
+                options,
+            )
+        };
+
+        let all = feature.cur_cj_feature()?;
+        let filtered = decode(&lod22)?;
+        for (id, co) in filtered.city_objects.iter() {
+            let geometries = co.geometry.as_ref().expect("filtered geometry is an array");
+            assert!(geometries.iter().all(|g| g.lod.as_deref() == Some("2.2")));
+            let expected = all.city_objects[id]
+                .geometry
+                .iter()
+                .flatten()
+                .filter(|g| g.lod.as_deref() == Some("2.2"))
+                .count();
+            assert_eq!(geometries.len(), expected);
+            lod22_cnt += geometries.len();
+        }
+
+        let filtered = decode(&missing)?;
+        assert!(filtered
+            .city_objects
+            .values()
+            .all(|co| co.geometry.as_ref().is_some_and(|g| g.is_empty())));
+    }
+    assert!(lod22_cnt > 0);
+
+    Ok(())
+}
+
+#[test]
+fn read_lod_filter_list() -> Result<()> {
+    let header = serde_json::json!({
+        "type": "CityJSON",
+        "version": "2.0",
+        "transform": {"scale": [0.001, 0.001, 0.001], "translate": [0.0, 0.0, 0.0]},
+        "CityObjects": {},
+        "vertices": []
+    });
+    let feature = serde_json::json!({
+        "type": "CityJSONFeature",
+        "id": "building",
+        "CityObjects": {
+            "building": {
+                "type": "Building",
+                "geometry": [
+                    {"type": "MultiSurface", "lod": "0", "boundaries": [[[0, 1, 2, 3]]]},
+                    {"type": "MultiSurface", "lod": "1", "boundaries": [[[4, 5, 6, 7]]]},
+                    {
+                        "type": "MultiSurface",
+                        "lod": "2",
+                        "boundaries": [[[0, 1, 5, 4]], [[1, 2, 6, 5]]]
+                    }
+                ]
+            }
+        },
+        "vertices": [
+            [0, 0, 0], [1000, 0, 0], [1000, 1000, 0], [0, 1000, 0],
+            [0, 0, 1000], [1000, 0, 1000], [1000, 1000, 1000], [0, 1000, 1000]
+        ]
+    });
+    let jsonl = format!("{header}\n{feature}\n");
+    let original_cj_seq =
+        match read_cityjson_from_reader(BufReader::new(jsonl.as_bytes()), CJTypeKind::Seq)? {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+
+    let mut memory_buffer = Cursor::new(Vec::new());
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: original_cj_seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write(&mut memory_buffer)?;
+    let bytes = memory_buffer.into_inner();
+
+    let lods = |lod_filter: &[&str]| -> Result<Vec<String>> {
+        let mut iter = FcbReader::open(Cursor::new(bytes.as_slice()))?
+            .select_all()?
+            .with_lod_filter(lod_filter);
+        let feature = iter.next()?.expect("one feature").cur_cj_feature()?;
+        let geometries = feature.city_objects["building"]
+            .geometry
+            .clone()
+            .expect("filtered geometry is an array");
+        Ok(geometries.into_iter().filter_map(|g| g.lod).collect())
+    };
+
+    assert_eq!(lods(&["2"])?, vec!["2"]);
+    assert_eq!(lods(&["0", "2"])?, vec!["0", "2"]);
+    assert!(lods(&["3"])?.is_empty());
+
+    Ok(())
+}
+
 #[test]
 fn read_bbox_nonseekable() -> anyhow::Result<()> {
     use std::fs::File;
@@ -154,6 +729,12 @@ fn read_bbox_nonseekable() -> anyhow::Result<()> {
             index_node_size: 16,
             attribute_indices: Some(attr_indices),
             geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
         }),
         Some(attr_schema),
         None,
@@ -214,3 +795,559 @@ fn read_bbox_nonseekable() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn read_current_offset() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/small.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    // without spatial index the features are written in the order they are added
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    let mut written_offsets = Vec::new();
+    for feature in seq.features.iter() {
+        written_offsets.push(fcb.current_feature_offset());
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    let mut read_offsets = Vec::new();
+    while iter.next()?.is_some() {
+        read_offsets.push(iter.current_offset());
+    }
+    assert_eq!(read_offsets, written_offsets);
+
+    // with spatial index the offsets are the ones of the R-tree leaves
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    let header = iter.header();
+    let header_size = u32::from_le_bytes(out[8..12].try_into()?) as usize;
+    let tree = PackedRTree::from_buf(
+        &out[12 + header_size..],
+        header.features_count() as usize,
+        header.index_node_size(),
+        NodeLayout::Xyz,
+    )?;
+    let mut leaf_offsets = tree
+        .search(Query::BBox(
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            f64::INFINITY,
+        ))?
+        .iter()
+        .map(|item| item.offset as u64)
+        .collect::<Vec<_>>();
+    leaf_offsets.sort();
+
+    let mut read_offsets = Vec::new();
+    while iter.next()?.is_some() {
+        read_offsets.push(iter.current_offset());
+    }
+    assert_eq!(read_offsets, leaf_offsets);
+
+    Ok(())
+}
+
+#[test]
+fn read_attribute_value() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/delft.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut attr_schema = AttributeSchema::new();
+    for feature in seq.features.iter() {
+        for (_, co) in feature.city_objects.iter() {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+    }
+
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema),
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    let mut found = 0;
+    while let Some(feat) = iter.next()? {
+        let cj_feature = feat.cur_cj_feature()?;
+        let buffer = feat.get().unwrap();
+        for name in ["b3_h_dak_50p", "identificatie", "b3_volume_lod22"] {
+            let candidates = cj_feature
+                .city_objects
+                .values()
+                .filter_map(|co| co.attributes.as_ref()?.get(name).cloned())
+                .collect::<Vec<_>>();
+            match buffer.attribute_value(name)? {
+                Some(value) => {
+                    assert!(candidates.contains(&value), "{name} of {}", cj_feature.id);
+                    found += 1;
+                }
+                None => assert!(candidates.is_empty(), "{name} of {}", cj_feature.id),
+            }
+        }
+        assert_eq!(buffer.attribute_value("not_a_column")?, None);
+    }
+    assert!(found > 0);
+
+    Ok(())
+}
+
+#[test]
+fn read_attribute_schema() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/delft.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut attr_schema = AttributeSchema::new();
+    for feature in seq.features.iter() {
+        for (_, co) in feature.city_objects.iter() {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+    }
+    assert!(!attr_schema.is_empty());
+
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema.clone()),
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    assert_eq!(
+        AttributeSchema::from_fcb_header(&iter.header()),
+        attr_schema
+    );
+
+    Ok(())
+}
+
+#[test]
+fn read_attribute_stats() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/delft.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut attr_schema = AttributeSchema::new();
+    for feature in seq.features.iter() {
+        for (_, co) in feature.city_objects.iter() {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+    }
+
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema),
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    let columns = ["b3_h_dak_50p", "identificatie", "not_a_column"];
+    let stats = compute_attribute_stats(&mut iter, Some(&columns[..]))?;
+    assert_eq!(stats.len(), 2);
+
+    let city_objects = seq
+        .features
+        .iter()
+        .flat_map(|f| f.city_objects.values())
+        .collect::<Vec<_>>();
+    let values = |name: &str| {
+        city_objects
+            .iter()
+            .filter_map(|co| co.attributes.as_ref()?.get(name).filter(|v| !v.is_null()))
+            .collect::<Vec<_>>()
+    };
+
+    let heights = values("b3_h_dak_50p")
+        .into_iter()
+        .filter_map(|v| v.as_f64())
+        .collect::<Vec<_>>();
+    let height_stats = &stats["b3_h_dak_50p"];
+    assert_eq!(height_stats.count, heights.len() as u64);
+    assert_eq!(
+        height_stats.count + height_stats.null_count,
+        city_objects.len() as u64
+    );
+    let min = heights.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = heights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    assert_eq!(
+        height_stats.min.as_ref().and_then(|v| v.as_f64()),
+        Some(min)
+    );
+    assert_eq!(
+        height_stats.max.as_ref().and_then(|v| v.as_f64()),
+        Some(max)
+    );
+
+    let ids = values("identificatie");
+    let id_stats = &stats["identificatie"];
+    assert_eq!(id_stats.count, ids.len() as u64);
+    let distinct = ids.iter().map(|v| v.to_string()).collect::<HashSet<_>>();
+    assert_eq!(id_stats.distinct_count, Some(distinct.len() as u64));
+
+    Ok(())
+}
+
+#[test]
+fn read_geometry_summary() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/delft.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    let mut geometries = 0;
+    while let Some(feat) = iter.next()? {
+        let cj_feature = feat.cur_cj_feature()?;
+        let summary = feat.get().unwrap().geometry_summary()?;
+        assert_eq!(summary.count, summary.types.len());
+        assert_eq!(summary.count, summary.lods.len());
+
+        // city objects are unordered in the CityJSON feature, compare the sorted pairs
+        let mut expected = cj_feature
+            .city_objects
+            .values()
+            .flat_map(|co| co.geometry.iter().flatten())
+            .map(|g| {
+                let type_ = serde_json::to_value(&g.thetype).unwrap();
+                (type_.as_str().unwrap().to_string(), g.lod.clone())
+            })
+            .collect::<Vec<_>>();
+        let mut actual = summary
+            .types
+            .into_iter()
+            .zip(summary.lods)
+            .collect::<Vec<_>>();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected, "{}", cj_feature.id);
+        geometries += summary.count;
+    }
+    assert!(geometries > 0);
+
+    Ok(())
+}
+
+#[test]
+fn read_cj_feature_into() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/delft.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    // one buffer for every feature, leftovers of the previous feature must not leak through
+    let mut buf = cjseq::CityJSONFeature::new();
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    let mut count = 0;
+    while let Some(feat) = iter.next()? {
+        feat.get().unwrap().cj_feature_into(&mut buf)?;
+        assert_eq!(
+            serde_json::to_value(&buf)?,
+            serde_json::to_value(feat.cur_cj_feature()?)?
+        );
+        count += 1;
+    }
+    assert_eq!(count, seq.features.len());
+
+    Ok(())
+}
+
+#[test]
+fn read_batch_next() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data/delft.city.jsonl"))?;
+    let seq = match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut expected = Vec::new();
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    while let Some(feat) = iter.next()? {
+        expected.push(feat.cur_cj_feature()?.id);
+    }
+    assert!(expected.len() > 7);
+
+    let batch_size = 7;
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    assert!(iter.batch_next(0)?.is_empty());
+    let mut ids = Vec::new();
+    loop {
+        let batch = iter.batch_next(batch_size)?;
+        if batch.is_empty() {
+            break;
+        }
+        // only the last batch is shorter
+        assert!(batch.len() == batch_size || ids.len() + batch.len() == expected.len());
+        ids.extend(batch.into_iter().map(|feature| feature.id));
+    }
+    assert_eq!(ids, expected);
+
+    // a batch larger than the selection returns every remaining feature
+    let mut iter = FcbReader::open(Cursor::new(&out))?.select_all_seq()?;
+    let ids = iter
+        .batch_next(usize::MAX)?
+        .into_iter()
+        .map(|feature| feature.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, expected);
+    assert!(iter.batch_next(batch_size)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn read_tiled_bbox() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    // split the dataset into two tiles
+    let dir = tempfile::tempdir()?;
+    let half = original_cj_seq.features.len() / 2;
+    let parts = [
+        &original_cj_seq.features[..half],
+        &original_cj_seq.features[half..],
+    ];
+    for (i, features) in parts.iter().enumerate() {
+        let mut fcb = FcbWriter::new(
+            original_cj_seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                write_index: true,
+                feature_count: features.len() as u64,
+                index_node_size: 16,
+                attribute_indices: None,
+                geographical_extent: None,
+                composite_indices: None,
+                coordinate_precision: None,
+                checksum: false,
+                shared_vertices: false,
+                feature_id_index: false,
+                compute_hash: false,
+            }),
+            None,
+            None,
+        )?;
+        for feature in features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        fcb.write(File::create(dir.path().join(format!("tile_{i}.fcb")))?)?;
+    }
+
+    let index = TileIndex::from_dir(dir.path())?;
+    assert_eq!(index.tiles.len(), 2);
+    let sidecar = dir.path().join("index.json");
+    index.write(&sidecar)?;
+    assert!(!std::fs::read_to_string(&sidecar)?.contains(&*dir.path().to_string_lossy()));
+    let index = TileIndex::read(&sidecar)?;
+    assert!(index.tiles.iter().all(|t| t.path.exists()));
+
+    let (minx, miny, maxx, maxy) = (84227.77, 445377.33, 85323.23, 446334.69);
+    let mut expected = Vec::new();
+    for tile in index.tiles.iter() {
+        let mut iter = FcbReader::open(BufReader::new(File::open(&tile.path)?))?
+            .select_bbox(minx, miny, maxx, maxy)?;
+        while let Some(feature) = iter.next()? {
+            expected.push(feature.cur_cj_feature()?.id);
+        }
+    }
+    assert!(!expected.is_empty());
+
+    let reader = TiledFcbReader::new(index);
+    let mut iter = reader.select_bbox(minx, miny, maxx, maxy)?;
+    let mut ids = Vec::new();
+    while let Some(feature) = iter.next()? {
+        let id = feature.cur_cj_feature()?.id;
+        assert!(iter.current_tile().is_some());
+        ids.push(id);
+    }
+    assert_eq!(ids, expected);
+    assert_eq!(iter.remaining_tiles(), 0);
+
+    // no tile intersects a bbox far away
+    let mut iter = reader.select_bbox(0.0, 0.0, 1.0, 1.0)?;
+    assert!(iter.next()?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn read_rewind() -> Result<()> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = manifest_dir.join("tests/data/delft.city.jsonl");
+    let input_reader = BufReader::new(File::open(input_file)?);
+    let original_cj_seq = match read_cityjson_from_reader(input_reader, CJTypeKind::Seq)? {
+        CJType::Seq(seq) => seq,
+        _ => panic!("Expected CityJSONSeq"),
+    };
+
+    let mut memory_buffer = Cursor::new(Vec::new());
+    let mut fcb = FcbWriter::new(
+        original_cj_seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: original_cj_seq.features.len() as u64,
+            ..Default::default()
+        }),
+        None,
+        None,
+    )?
+    .checksum(true);
+    for feature in original_cj_seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write(&mut memory_buffer)?;
+
+    fn collect_ids<R: Read + Seek>(iter: &mut FeatureIter<R, Seekable>) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        while let Some(feature) = iter.next()? {
+            ids.push(feature.cur_cj_feature()?.id);
+        }
+        Ok(ids)
+    }
+
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut iter = FcbReader::open(&mut memory_buffer)?.select_all()?;
+    // no-op before reading
+    iter.rewind()?;
+    let first_pass = collect_ids(&mut iter)?;
+    assert_eq!(first_pass.len(), original_cj_seq.features.len());
+    iter.rewind()?;
+    assert_eq!(collect_ids(&mut iter)?, first_pass);
+
+    // rewinding in the middle of a spatial selection restarts at its first feature
+    memory_buffer.seek(std::io::SeekFrom::Start(0))?;
+    let mut iter = FcbReader::open(&mut memory_buffer)?
+        .select_bbox(84227.77, 445377.33, 85323.23, 446334.69)?;
+    let selected = collect_ids(&mut iter)?;
+    assert!(selected.len() > 2);
+    iter.rewind()?;
+    iter.next()?;
+    iter.next()?;
+    iter.rewind()?;
+    assert_eq!(collect_ids(&mut iter)?, selected);
+
+    Ok(())
+}
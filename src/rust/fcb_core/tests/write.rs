@@ -0,0 +1,714 @@
+use anyhow::Result;
+use cjseq::{Boundaries, CityJSONFeature};
+use fcb_core::{
+    attribute::{AttributeSchema, AttributeSchemaMethods},
+    error::Error,
+    header_writer::HeaderWriterOptions,
+    read_cityjson_from_reader, CJType, CJTypeKind, CityJSONSeq, ColumnType, FcbReader, FcbWriter,
+    ProgressEvent,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Cursor},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+fn read_seq(name: &str) -> Result<CityJSONSeq> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(manifest_dir.join("tests/data").join(name))?;
+    match read_cityjson_from_reader(BufReader::new(input_file), CJTypeKind::Seq)? {
+        CJType::Seq(seq) => Ok(seq),
+        _ => panic!("expected cityjsonseq"),
+    }
+}
+
+fn attr_schema_of(seq: &CityJSONSeq) -> AttributeSchema {
+    let mut attr_schema = AttributeSchema::new();
+    for feature in seq.features.iter() {
+        for (_, co) in feature.city_objects.iter() {
+            if let Some(attributes) = &co.attributes {
+                attr_schema.add_attributes(attributes);
+            }
+        }
+    }
+    attr_schema
+}
+
+#[test]
+fn write_reports_progress() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+    let events = Arc::new(Mutex::new(Vec::<ProgressEvent>::new()));
+
+    let events_cb = events.clone();
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema_of(&seq)),
+        None,
+    )?
+    .on_progress(move |event| events_cb.lock().unwrap().push(event));
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), seq.features.len());
+    let last = events.last().unwrap();
+    assert_eq!(last.features_written, seq.features.len());
+    assert_eq!(last.total_features, Some(seq.features.len()));
+    assert_eq!(last.bytes_written, out.len() as u64);
+    assert!(events
+        .windows(2)
+        .all(|w| w[0].bytes_written < w[1].bytes_written));
+
+    Ok(())
+}
+
+#[test]
+fn write_coordinate_precision() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+    let digits = 2;
+
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            coordinate_precision: Some(digits),
+            ..Default::default()
+        }),
+        Some(attr_schema_of(&seq)),
+        None,
+    )?;
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Cursor::new(Vec::new());
+    fcb.write(&mut out)?;
+    out.set_position(0);
+
+    let mut reader = FcbReader::open(&mut out)?.select_all()?;
+    let header = reader.header();
+    let transform = header.transform().unwrap();
+    assert_eq!(transform.scale().x(), 0.01);
+    assert_eq!(transform.scale().z(), 0.01);
+    assert_eq!(transform.translate().x(), seq.cj.transform.translate[0]);
+
+    let originals: HashMap<_, _> = seq.features.iter().map(|f| (f.id.clone(), f)).collect();
+    let mut count = 0;
+    while let Some(feature) = reader.next()? {
+        let feature = feature.cur_cj_feature()?;
+        let original = originals[&feature.id];
+        assert_eq!(feature.vertices.len(), original.vertices.len());
+        for (v, o) in feature.vertices.iter().zip(original.vertices.iter()) {
+            for axis in 0..3 {
+                let restored = v[axis] as f64 * 0.01;
+                let source = o[axis] as f64 * seq.cj.transform.scale[axis];
+                assert!((restored - source).abs() <= 0.005 + 1e-9);
+            }
+        }
+        count += 1;
+    }
+    assert_eq!(count, seq.features.len());
+
+    Ok(())
+}
+
+fn flatten_indices(boundaries: &Boundaries, out: &mut Vec<u32>) {
+    match boundaries {
+        Boundaries::Indices(indices) => out.extend(indices),
+        Boundaries::Nested(nested) => nested.iter().for_each(|b| flatten_indices(b, out)),
+    }
+}
+
+#[test]
+fn write_shared_vertices() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            write_index: false,
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema_of(&seq)),
+        None,
+    )?
+    .shared_vertices(true);
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut reader = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    assert!(reader.header().shared_vertices());
+
+    // every geometry still resolves to the same coordinates, from a vertex array without duplicates
+    let resolve = |vertices: &[Vec<i64>], boundaries: &Boundaries| -> Vec<Vec<i64>> {
+        let mut indices = Vec::new();
+        flatten_indices(boundaries, &mut indices);
+        indices
+            .iter()
+            .map(|i| vertices[*i as usize].clone())
+            .collect()
+    };
+    let originals: HashMap<_, _> = seq.features.iter().map(|f| (f.id.clone(), f)).collect();
+    let mut count = 0;
+    while let Some(feature) = reader.next()? {
+        let feature = feature.cur_cj_feature()?;
+        let original = originals[&feature.id];
+        let mut unique = feature.vertices.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), feature.vertices.len());
+        assert!(feature.vertices.len() <= original.vertices.len());
+
+        for (id, co) in feature.city_objects.iter() {
+            let original_geometries = original.city_objects[id].geometry.iter().flatten();
+            for (g, o) in co.geometry.iter().flatten().zip(original_geometries) {
+                assert_eq!(
+                    resolve(&original.vertices, &o.boundaries),
+                    resolve(&feature.vertices, &g.boundaries)
+                );
+            }
+        }
+        count += 1;
+    }
+    assert_eq!(count, seq.features.len());
+
+    Ok(())
+}
+
+#[cfg(feature = "global-vertices")]
+#[test]
+fn write_global_vertices() -> Result<()> {
+    use fcb_core::global_vertices::global_vertex_ids;
+
+    let seq = read_seq("delft.city.jsonl")?;
+    let write = |global: bool| -> Result<Vec<u8>> {
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                ..Default::default()
+            }),
+            Some(attr_schema_of(&seq)),
+            None,
+        )?;
+        if global {
+            fcb = fcb.with_global_vertices(global_vertex_ids(&seq.features, &seq.cj.transform));
+        }
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut out = Vec::new();
+        fcb.write(&mut out)?;
+        Ok(out)
+    };
+    let local_out = write(false)?;
+    let global_out = write(true)?;
+    // party walls of adjacent buildings are stored once
+    assert!(global_out.len() < local_out.len());
+
+    let resolve = |vertices: &[Vec<i64>], boundaries: &Boundaries| -> Vec<Vec<i64>> {
+        let mut indices = Vec::new();
+        flatten_indices(boundaries, &mut indices);
+        indices
+            .iter()
+            .map(|i| vertices[*i as usize].clone())
+            .collect()
+    };
+    let originals: HashMap<_, _> = seq.features.iter().map(|f| (f.id.clone(), f)).collect();
+    let mut reader = FcbReader::open(Cursor::new(&global_out))?.select_all()?;
+    assert!(reader.header().global_vertices().is_some());
+    let mut count = 0;
+    while let Some(feature) = reader.next()? {
+        assert!(feature
+            .cur_feature()
+            .vertices()
+            .is_none_or(|v| v.is_empty()));
+        let feature = feature.cur_cj_feature()?;
+        let original = originals[&feature.id];
+        for (id, co) in feature.city_objects.iter() {
+            let original_geometries = original.city_objects[id].geometry.iter().flatten();
+            for (g, o) in co.geometry.iter().flatten().zip(original_geometries) {
+                assert_eq!(
+                    resolve(&original.vertices, &o.boundaries),
+                    resolve(&feature.vertices, &g.boundaries)
+                );
+            }
+        }
+        count += 1;
+    }
+    assert_eq!(count, seq.features.len());
+
+    // the R-tree holds the bounding boxes of the original vertices
+    let (min_x, min_y, max_x, max_y) = (84227.77, 445377.33, 85323.23, 446334.69);
+    let mut local_count = 0;
+    let mut local_iter =
+        FcbReader::open(Cursor::new(&local_out))?.select_bbox(min_x, min_y, max_x, max_y)?;
+    while local_iter.next()?.is_some() {
+        local_count += 1;
+    }
+    let mut global_count = 0;
+    let mut global_iter =
+        FcbReader::open(Cursor::new(&global_out))?.select_bbox(min_x, min_y, max_x, max_y)?;
+    while global_iter.next()?.is_some() {
+        global_count += 1;
+    }
+    assert!(local_count > 0);
+    assert_eq!(local_count, global_count);
+
+    // vertices missing from the table are rejected
+    let mut fcb = FcbWriter::new(seq.cj.clone(), None, Some(attr_schema_of(&seq)), None)?
+        .with_global_vertices(global_vertex_ids(&seq.features[..1], &seq.cj.transform));
+    let other = seq
+        .features
+        .iter()
+        .find(|f| {
+            f.vertices
+                .iter()
+                .any(|v| !seq.features[0].vertices.contains(v))
+        })
+        .unwrap();
+    assert!(matches!(
+        fcb.add_feature(other),
+        Err(Error::GlobalVertices(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn write_checksum() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: seq.features.len() as u64,
+            ..Default::default()
+        }),
+        Some(attr_schema_of(&seq)),
+        None,
+    )?
+    .checksum(true);
+    for feature in seq.features.iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut reader = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    assert!(reader.header().checksum());
+    let mut count = 0;
+    while reader.next()?.is_some() {
+        count += 1;
+    }
+    assert_eq!(count, seq.features.len());
+
+    // flip a bit in the last feature, just before its checksum
+    let mut corrupted = out.clone();
+    let len = corrupted.len();
+    corrupted[len - 5] ^= 0x01;
+    let mut reader = FcbReader::open(Cursor::new(&corrupted))?.select_all()?;
+    let err = loop {
+        match reader.next() {
+            Ok(Some(_)) => continue,
+            Ok(None) => panic!("corruption was not detected"),
+            Err(e) => break e,
+        }
+    };
+    match err.root_cause() {
+        Error::ChecksumMismatch { feature_index, .. } => {
+            assert_eq!(*feature_index, seq.features.len() - 1)
+        }
+        e => panic!("unexpected error {e}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn write_content_hash() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+
+    let write = |compute_hash: bool| -> Result<Vec<u8>> {
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                compute_hash,
+                ..Default::default()
+            }),
+            Some(attr_schema_of(&seq)),
+            None,
+        )?;
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut out = Vec::new();
+        fcb.write(&mut out)?;
+        Ok(out)
+    };
+
+    let out = write(true)?;
+    let mut reader = FcbReader::open(Cursor::new(&out))?;
+    assert_eq!(
+        reader.header().content_hash().map(|hash| hash.len()),
+        Some(32)
+    );
+    assert!(reader.verify_hash()?);
+    // the reader is still usable after verifying
+    let mut reader = reader.select_all()?;
+    let mut count = 0;
+    while reader.next()?.is_some() {
+        count += 1;
+    }
+    assert_eq!(count, seq.features.len());
+
+    // flip a bit in the last feature
+    let mut corrupted = out.clone();
+    let len = corrupted.len();
+    corrupted[len - 5] ^= 0x01;
+    assert!(!FcbReader::open(Cursor::new(&corrupted))?.verify_hash()?);
+
+    let out = write(false)?;
+    let mut reader = FcbReader::open(Cursor::new(&out))?;
+    assert!(reader.header().content_hash().is_none());
+    assert!(matches!(reader.verify_hash(), Err(Error::NoContentHash)));
+
+    Ok(())
+}
+
+#[test]
+fn write_estimate_size() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+
+    // without attribute indices the estimate is exact
+    for (attribute_indices, tolerance_pct) in [
+        (None, 0),
+        (
+            Some(vec![
+                ("b3_h_dak_50p".to_string(), None),
+                ("identificatie".to_string(), None),
+            ]),
+            5,
+        ),
+    ] {
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                attribute_indices,
+                ..Default::default()
+            }),
+            Some(attr_schema_of(&seq)),
+            None,
+        )?;
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let estimate = fcb.estimate_size();
+        let mut out = Vec::new();
+        fcb.write(&mut out)?;
+
+        let actual = out.len() as u64;
+        assert!(
+            estimate.abs_diff(actual) * 100 <= actual * tolerance_pct,
+            "estimate {estimate} is more than {tolerance_pct}% off the actual size {actual}"
+        );
+    }
+
+    Ok(())
+}
+
+/// Extent of the vertices of the features in real-world coordinates
+fn extent_of(seq: &CityJSONSeq, features: &[CityJSONFeature]) -> [f64; 6] {
+    let transform = &seq.cj.transform;
+    let mut extent = [
+        f64::INFINITY,
+        f64::INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NEG_INFINITY,
+        f64::NEG_INFINITY,
+    ];
+    for vertex in features.iter().flat_map(|f| f.vertices.iter()) {
+        for axis in 0..3 {
+            let v = vertex[axis] as f64 * transform.scale[axis] + transform.translate[axis];
+            extent[axis] = extent[axis].min(v);
+            extent[axis + 3] = extent[axis + 3].max(v);
+        }
+    }
+    extent
+}
+
+#[test]
+fn write_reproducible() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+
+    let write = || -> Result<Vec<u8>> {
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: seq.features.len() as u64,
+                attribute_indices: Some(vec![
+                    ("b3_h_dak_50p".to_string(), None),
+                    ("identificatie".to_string(), None),
+                ]),
+                ..Default::default()
+            }),
+            Some(attr_schema_of(&seq)),
+            None,
+        )?;
+        for feature in seq.features.iter() {
+            fcb.add_feature(feature)?;
+        }
+        let mut out = Vec::new();
+        fcb.write(&mut out)?;
+        Ok(out)
+    };
+
+    // city objects and schemas are hashed with a random state, which must not leak into
+    // the output
+    let first = write()?;
+    let second = write()?;
+    assert!(first == second, "the two writes differ");
+
+    let reader = FcbReader::open(Cursor::new(&first))?;
+    let names = reader
+        .header()
+        .columns()
+        .unwrap()
+        .iter()
+        .map(|col| col.name().to_string())
+        .collect::<Vec<_>>();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+
+    Ok(())
+}
+
+#[test]
+fn write_append() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+    let attr_schema = attr_schema_of(&seq);
+    let indexed = attr_schema.keys().next().cloned();
+    let half = seq.features.len() / 2;
+
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: half as u64,
+            attribute_indices: indexed.map(|name| vec![(name, None)]),
+            geographical_extent: Some(extent_of(&seq, &seq.features[..half])),
+            ..Default::default()
+        }),
+        Some(attr_schema),
+        None,
+    )?
+    .checksum(true);
+    for feature in seq.features[..half].iter() {
+        fcb.add_feature(feature)?;
+    }
+    let existing = tempfile::NamedTempFile::new()?;
+    fcb.write(File::create(existing.path())?)?;
+
+    let mut fcb = FcbWriter::open_append(existing.path())?;
+    for feature in seq.features[half..].iter() {
+        fcb.add_feature(feature)?;
+    }
+    let mut out = Vec::new();
+    fcb.write(&mut out)?;
+
+    let mut reader = FcbReader::open(Cursor::new(&out))?.select_all()?;
+    let header = reader.header();
+    assert_eq!(header.features_count(), seq.features.len() as u64);
+    assert_eq!(header.index_node_size(), 0);
+    assert!(header.attribute_index().is_none());
+    assert!(header.checksum());
+    // the extent includes the added features
+    let ge = header.geographical_extent().expect("geographical extent");
+    assert_eq!(
+        [
+            ge.min().x(),
+            ge.min().y(),
+            ge.min().z(),
+            ge.max().x(),
+            ge.max().y(),
+            ge.max().z(),
+        ],
+        extent_of(&seq, &seq.features)
+    );
+    let mut ids = Vec::new();
+    while let Some(feature) = reader.next()? {
+        ids.push(feature.cur_cj_feature()?.id);
+    }
+    assert_eq!(ids.len(), seq.features.len());
+    // existing features keep their order, added ones follow in the order they were added
+    let added = seq.features[half..]
+        .iter()
+        .map(|f| f.id.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(ids[half..], added[..]);
+
+    // the spatial index was dropped
+    let err = FcbReader::open(Cursor::new(&out))?
+        .select_bbox(0.0, 0.0, 1.0, 1.0)
+        .err()
+        .expect("no spatial index");
+    assert!(matches!(err.root_cause(), Error::NoIndex));
+
+    Ok(())
+}
+
+#[test]
+fn write_open_for_append() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+    let attr_schema = attr_schema_of(&seq);
+    let half = seq.features.len() / 2;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("small.fcb");
+    let mut fcb = FcbWriter::new(
+        seq.cj.clone(),
+        Some(HeaderWriterOptions {
+            feature_count: half as u64,
+            feature_id_index: true,
+            ..Default::default()
+        }),
+        Some(attr_schema.clone()),
+        None,
+    )?
+    .checksum(true);
+    for feature in seq.features[..half].iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write_atomic(&path)?;
+
+    let mut fcb = FcbWriter::open_for_append(&path)?;
+    for feature in seq.features[half..].iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write_atomic(&path)?;
+
+    let data = std::fs::read(&path)?;
+    let mut reader = FcbReader::open(Cursor::new(&data))?;
+    let header = reader.header();
+    assert_eq!(header.features_count(), seq.features.len() as u64);
+    assert!(header.index_node_size() > 0);
+    assert!(header.checksum());
+    let extent = header
+        .geographical_extent()
+        .expect("extent of all features");
+    let (min, max) = (extent.min(), extent.max());
+    let bbox = (min.x(), min.y(), max.x(), max.y());
+
+    // the rebuilt index of the feature IDs covers the existing and the added features
+    for original in seq.features.iter() {
+        let feat_buf = reader
+            .find_by_id(&original.id)?
+            .expect("feature is in the index");
+        assert_eq!(feat_buf.feature().id(), original.id);
+    }
+
+    // so does the rebuilt R-tree
+    let mut iter = reader.select_bbox(bbox.0, bbox.1, bbox.2, bbox.3)?;
+    let mut ids = Vec::new();
+    while let Some(feature) = iter.next()? {
+        ids.push(feature.cur_cj_feature()?.id);
+    }
+    ids.sort();
+    let mut expected = seq
+        .features
+        .iter()
+        .map(|f| f.id.clone())
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    // values that don't fit the columns of the file are rejected
+    let (name, _) = attr_schema
+        .iter()
+        .find(|(_, (_, coltype))| *coltype == ColumnType::String)
+        .expect("a string column");
+    let mut incompatible = seq.features[0].clone();
+    let co = incompatible
+        .city_objects
+        .values_mut()
+        .find(|co| co.attributes.is_some())
+        .expect("a city object with attributes");
+    co.attributes.as_mut().unwrap()[name.as_str()] = serde_json::json!(42);
+    let mut fcb = FcbWriter::open_for_append(&path)?;
+    let err = fcb.add_feature(&incompatible).unwrap_err();
+    assert!(matches!(err, Error::InvalidAttributeValue { .. }));
+
+    Ok(())
+}
+
+#[test]
+fn write_atomic() -> Result<()> {
+    let seq = read_seq("small.city.jsonl")?;
+    let half = seq.features.len() / 2;
+    let write_first_half = |path: &std::path::Path| -> Result<()> {
+        let mut fcb = FcbWriter::new(
+            seq.cj.clone(),
+            Some(HeaderWriterOptions {
+                feature_count: half as u64,
+                ..Default::default()
+            }),
+            None,
+            None,
+        )?;
+        for feature in seq.features[..half].iter() {
+            fcb.add_feature(feature)?;
+        }
+        Ok(fcb.write_atomic(path)?)
+    };
+    let count = |path: &std::path::Path| -> Result<u64> {
+        Ok(FcbReader::open(BufReader::new(File::open(path)?))?
+            .header()
+            .features_count())
+    };
+    let entries = |dir: &std::path::Path| -> Result<Vec<String>> {
+        let mut names = std::fs::read_dir(dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    };
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("small.fcb");
+    write_first_half(&path)?;
+    assert_eq!(count(&path)?, half as u64);
+    assert_eq!(entries(dir.path())?, ["small.fcb"]);
+
+    // appending can write back to the file it reads
+    let mut fcb = FcbWriter::open_append(&path)?;
+    for feature in seq.features[half..].iter() {
+        fcb.add_feature(feature)?;
+    }
+    fcb.write_atomic(&path)?;
+    assert_eq!(count(&path)?, seq.features.len() as u64);
+
+    // renaming onto a directory fails, the temporary file is removed
+    let occupied = dir.path().join("occupied");
+    std::fs::create_dir(&occupied)?;
+    assert!(write_first_half(&occupied).is_err());
+    assert_eq!(entries(dir.path())?, ["occupied", "small.fcb"]);
+    assert_eq!(count(&path)?, seq.features.len() as u64);
+
+    Ok(())
+}
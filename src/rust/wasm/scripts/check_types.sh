@@ -0,0 +1,26 @@
+#!/bin/bash
+# Builds the bindings with wasm-pack and compares the generated TypeScript
+# declarations with the committed baseline in wasm/types.
+# Pass --update to overwrite the baseline with the generated file.
+set -euo pipefail
+
+WASM_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BASELINE="$WASM_DIR/types/fcb_wasm.d.ts"
+OUT_DIR="$(mktemp -d)"
+trap 'rm -rf "$OUT_DIR"' EXIT
+
+cd "$WASM_DIR"
+wasm-pack build --target bundler --out-dir "$OUT_DIR" --no-pack
+
+if [ "${1:-}" = "--update" ]; then
+    cp "$OUT_DIR/fcb_wasm.d.ts" "$BASELINE"
+    echo "Updated $BASELINE"
+    exit 0
+fi
+
+if ! diff -u "$BASELINE" "$OUT_DIR/fcb_wasm.d.ts"; then
+    echo "Generated TypeScript declarations differ from $BASELINE." >&2
+    echo "Run 'make update-wasm-types' if the change is intended." >&2
+    exit 1
+fi
+echo "TypeScript declarations match the baseline."
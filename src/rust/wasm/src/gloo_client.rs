@@ -1,33 +1,63 @@
 use async_trait::async_trait;
 use bytes::Bytes;
-use http_range_client::{AsyncBufferedHttpRangeClient, AsyncHttpRangeClient, HttpError, Result};
+use fcb_core::{RetryPolicy, MAGIC_BYTES_SIZE};
+use http_range_client::{AsyncHttpRangeClient, HttpError, Result};
+use std::collections::HashMap;
+#[cfg(target_arch = "wasm32")]
+use std::future::Future;
 
 #[cfg(target_arch = "wasm32")]
 use gloo_net::http::RequestBuilder as GlooRequest;
 
-pub struct WasmHttpClient {}
+/// Outcome of a conditional request sent with `If-None-Match`
+pub enum Revalidation {
+    /// `304 Not Modified`, the cached results are still valid
+    NotModified,
+    /// The dataset changed, with the ETag of its current version if the server sends one
+    Modified(Option<String>),
+}
+
+pub struct WasmHttpClient {
+    /// Extra headers sent with every request, e.g. `Authorization`
+    headers: HashMap<String, String>,
+    /// Retry policy for transient request failures
+    retry: RetryPolicy,
+}
 
 #[cfg(target_arch = "wasm32")]
 impl WasmHttpClient {
-    pub fn new(url: &str) -> AsyncBufferedHttpRangeClient<WasmHttpClient> {
-        AsyncBufferedHttpRangeClient::with(WasmHttpClient {}, url)
+    pub fn new(headers: Option<HashMap<String, String>>, retry: RetryPolicy) -> Self {
+        WasmHttpClient {
+            headers: headers.unwrap_or_default(),
+            retry,
+        }
     }
-}
 
-#[cfg(not(target_arch = "wasm32"))]
-impl WasmHttpClient {
-    pub fn new(url: &str) -> AsyncBufferedHttpRangeClient<WasmHttpClient> {
-        // This is a mock implementation for non-wasm targets
-        // It will never be called in production, but enables compilation
-        AsyncBufferedHttpRangeClient::with(WasmHttpClient {}, url)
+    /// Requests the magic bytes of `url`, with `If-None-Match: <cached_etag>` when an ETag is
+    /// given, on top of the extra headers
+    pub async fn revalidate(&self, url: &str, cached_etag: Option<&str>) -> Result<Revalidation> {
+        let mut request = self
+            .request(url)
+            .header("Range", &format!("bytes=0-{}", MAGIC_BYTES_SIZE - 1));
+        if let Some(etag) = cached_etag {
+            request = request.header("If-None-Match", etag);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| HttpError::HttpError(e.to_string()))?;
+        if response.status() == 304 {
+            return Ok(Revalidation::NotModified);
+        }
+        if !response.ok() {
+            return Err(HttpError::HttpStatus(response.status()));
+        }
+        Ok(Revalidation::Modified(response.headers().get("ETag")))
     }
-}
 
-#[cfg(target_arch = "wasm32")]
-#[async_trait(?Send)]
-impl AsyncHttpRangeClient for WasmHttpClient {
-    async fn get_range(&self, url: &str, range: &str) -> Result<Bytes> {
-        let response = GlooRequest::new(url)
+    async fn try_get_range(&self, url: &str, range: &str) -> Result<Bytes> {
+        let response = self
+            .request(url)
             .header("Range", range)
             .send()
             .await
@@ -43,19 +73,81 @@ impl AsyncHttpRangeClient for WasmHttpClient {
             .map_err(|e| HttpError::HttpError(e.to_string()))
     }
 
-    async fn head_response_header(&self, url: &str, header: &str) -> Result<Option<String>> {
-        let response = GlooRequest::new(url)
+    async fn try_head_response_header(&self, url: &str, header: &str) -> Result<Option<String>> {
+        let response = self
+            .request(url)
             .send()
             .await
             .map_err(|e| HttpError::HttpError(format!("failed to send request: {}", e)))?;
-        if let Some(val) = response.headers().get(header) {
-            Ok(Some(val.to_string()))
-        } else {
-            Ok(None)
+        if !response.ok() {
+            return Err(HttpError::HttpStatus(response.status()));
+        }
+        Ok(response.headers().get(header))
+    }
+
+    /// Sends `request` until it succeeds, fails with an error that is not retryable or the
+    /// retries of the policy are used up. `what` names the request in the log.
+    async fn with_retry<R, F: Future<Output = Result<R>>>(
+        &self,
+        what: &str,
+        request: impl Fn() -> F,
+    ) -> Result<R> {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Err(e) if attempt < self.retry.max_retries && RetryPolicy::is_retryable(&e) => {
+                    let delay = self.retry.backoff(attempt, js_sys::Math::random());
+                    attempt += 1;
+                    log::debug!(
+                        "{what} failed: {e}, retry {attempt}/{} in {delay:?}",
+                        self.retry.max_retries
+                    );
+                    gloo_timers::future::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn request(&self, url: &str) -> GlooRequest {
+        self.headers
+            .iter()
+            .fold(GlooRequest::new(url), |req, (name, value)| {
+                req.header(name, value)
+            })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WasmHttpClient {
+    pub fn new(headers: Option<HashMap<String, String>>, retry: RetryPolicy) -> Self {
+        // This is a mock implementation for non-wasm targets
+        // It will never be called in production, but enables compilation
+        WasmHttpClient {
+            headers: headers.unwrap_or_default(),
+            retry,
         }
     }
 }
 
+#[cfg(target_arch = "wasm32")]
+#[async_trait(?Send)]
+impl AsyncHttpRangeClient for WasmHttpClient {
+    async fn get_range(&self, url: &str, range: &str) -> Result<Bytes> {
+        self.with_retry(&format!("range request {range}"), || {
+            self.try_get_range(url, range)
+        })
+        .await
+    }
+
+    async fn head_response_header(&self, url: &str, header: &str) -> Result<Option<String>> {
+        self.with_retry("head request", || {
+            self.try_head_response_header(url, header)
+        })
+        .await
+    }
+}
+
 // Mock implementation for non-wasm targets
 #[cfg(not(target_arch = "wasm32"))]
 #[async_trait]
@@ -1,44 +1,62 @@
 #[cfg(target_arch = "wasm32")]
 mod gloo_client;
 #[cfg(target_arch = "wasm32")]
+mod types;
+#[cfg(target_arch = "wasm32")]
 mod util;
+#[cfg(target_arch = "wasm32")]
+mod writer;
 
 mod wasm {
     #[cfg(target_arch = "wasm32")]
-    use crate::gloo_client::WasmHttpClient;
+    use crate::gloo_client::{Revalidation, WasmHttpClient};
 
-    use fcb_core::{size_prefixed_root_as_header, Header, Operator};
+    use fcb_core::{size_prefixed_root_as_header, Header, LogicalOp, Operator};
     use js_sys::Array;
     use log::Level;
     use log::{debug, info, trace};
     use serde_wasm_bindgen::to_value;
     use wasm_bindgen::prelude::*;
 
-    use fcb_core::static_btree::{FixedStringKey, Float, HttpIndex, HttpMultiIndex, KeyType};
+    use fcb_core::static_btree::{
+        FixedStringKey, Float, HttpIndex, HttpMultiIndex, Key, KeyType, Nullable,
+        TypedHttpSearchIndex,
+    };
 
     use byteorder::{ByteOrder, LittleEndian};
     use bytes::{BufMut, Bytes, BytesMut};
     use chrono::{DateTime, NaiveDateTime, Utc};
+    use cjseq::CityJSONFeature;
     use fcb_core::city_buffer::FcbBuffer;
     use fcb_core::{
-        build_query, check_magic_bytes,
-        deserializer::{to_cj_feature, to_cj_metadata},
+        build_query,
+        checksum::crc32,
+        counted_get_range,
+        deserializer::{to_cj_metadata, DeserializeOptions},
         fb::*,
-        size_prefixed_root_as_city_feature, AttrQuery, HEADER_MAX_BUFFER_SIZE, HEADER_SIZE_SIZE,
-        MAGIC_BYTES_SIZE,
+        parse_magic_bytes,
+        serializer::ObjWriter,
+        size_prefixed_root_as_city_feature, AttrQuery, CityObjectTypeFilter, CountingClient,
+        HttpCounters, PolygonFilter, ProgressEvent, RetryPolicy, CHECKSUM_SIZE,
+        HEADER_MAX_BUFFER_SIZE, HEADER_SIZE_SIZE, MAGIC_BYTES_SIZE,
     };
 
     use std::fmt::Error;
     use std::result::Result;
     use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
 
     use http_range_client::{AsyncBufferedHttpRangeClient, AsyncHttpRangeClient};
 
     use fcb_core::packed_rtree::{
-        http::HttpRange, http::HttpSearchResultItem, NodeItem, PackedRTree, Query as SpatialQuery,
+        http::HttpRange, http::HttpSearchResultItem, NodeItem, NodeLayout, PackedRTree,
+        Query as SpatialQuery,
     };
     use std::collections::VecDeque;
+    use std::collections::{BTreeMap, HashMap};
+    use std::future::Future;
     use std::ops::Range;
+    use std::task::Poll;
 
     // The largest request we'll speculatively make.
     // If a single huge feature requires, we'll necessarily exceed this limit.
@@ -50,15 +68,21 @@ mod wasm {
     /// FlatCityBuf dataset HTTP reader
     #[wasm_bindgen]
     pub struct HttpFcbReader {
-        client: AsyncBufferedHttpRangeClient<WasmHttpClient>,
+        client: AsyncBufferedHttpRangeClient<CountingClient<WasmHttpClient>>,
         // feature reading requires header access, therefore
         // header_buf is included in the FcbBuffer struct.
         fbs: FcbBuffer,
+        /// Request counters, shared with the client
+        counters: Arc<HttpCounters>,
+        /// ETag of the dataset, when opened with `openWithEtag`
+        etag: Option<String>,
+        /// File format version found in the magic bytes
+        version: u8,
     }
 
     #[wasm_bindgen]
     pub struct AsyncFeatureIter {
-        client: AsyncBufferedHttpRangeClient<WasmHttpClient>,
+        client: AsyncBufferedHttpRangeClient<CountingClient<WasmHttpClient>>,
         // feature reading requires header access, therefore
         // header_buf is included in the FcbBuffer struct.
         fbs: FcbBuffer,
@@ -66,12 +90,38 @@ mod wasm {
         selection: FeatureSelection,
         /// Number of selected features
         count: usize,
+        /// Optional JS callback reporting progress after each feature is read
+        progress: Option<js_sys::Function>,
+        /// Number of features read so far
+        features_read: usize,
+        /// Number of feature bytes read so far
+        bytes_read: u64,
+        /// Request counters, carried over from the reader
+        counters: Arc<HttpCounters>,
+        /// Point-in-polygon filter for polygon queries, skipping non-matching candidates
+        polygon: Option<PolygonFilter>,
+        /// City object types to keep, checked before deserializing a feature
+        type_filter: Option<CityObjectTypeFilter>,
+        /// Feature buffers fetched ahead by `prefetch`, not yet returned
+        prefetched: VecDeque<Bytes>,
+        /// `AbortSignal` stopping the iteration once it fires
+        abort: Option<Abort>,
+        /// LODs of the geometries to decode or None to decode all geometries
+        filter_lod: Option<Vec<String>>,
     }
 
     #[wasm_bindgen(start)]
     impl HttpFcbReader {
+        /// `headers` is an optional object of extra request headers, e.g.
+        /// `{ Authorization: "Bearer <token>" }`, sent with every range request.
+        /// Transient request failures are retried up to `max_retries` times (default 3).
         #[wasm_bindgen(constructor, start)]
-        pub async fn new(url: String) -> Result<HttpFcbReader, JsValue> {
+        pub async fn new(
+            url: String,
+            #[wasm_bindgen(unchecked_param_type = "Record<string, string> | undefined")]
+            headers: JsValue,
+            max_retries: Option<u32>,
+        ) -> Result<HttpFcbReader, JsValue> {
             // Only initialize the logger once
             if !LOGGER_INITIALIZED.load(Ordering::SeqCst)
                 && console_log::init_with_level(Level::Trace).is_ok()
@@ -81,14 +131,58 @@ mod wasm {
             }
 
             trace!("starting: opening http reader, reading header");
-            let client = WasmHttpClient::new(&url);
+            let headers: Option<HashMap<String, String>> = serde_wasm_bindgen::from_value(headers)?;
+            let mut retry = RetryPolicy::default();
+            if let Some(max_retries) = max_retries {
+                retry.max_retries = max_retries;
+            }
+            let client = WasmHttpClient::new(headers, retry);
 
-            Self::_open(client).await
+            Self::_open(client, &url).await
         }
 
-        async fn _open(
-            mut client: AsyncBufferedHttpRangeClient<WasmHttpClient>,
-        ) -> Result<HttpFcbReader, JsValue> {
+        /// Opens the dataset unless it is unchanged since `cached_etag` was received, in which
+        /// case `undefined` is returned and the cached results are still valid. The first
+        /// request carries `If-None-Match: <cached_etag>` along with `headers`, the ETag of the
+        /// opened dataset is then available as `etag`.
+        #[wasm_bindgen(js_name = openWithEtag)]
+        pub async fn open_with_etag(
+            url: String,
+            cached_etag: Option<String>,
+            #[wasm_bindgen(unchecked_param_type = "Record<string, string> | undefined")]
+            headers: JsValue,
+            max_retries: Option<u32>,
+        ) -> Result<Option<HttpFcbReader>, JsValue> {
+            let headers: Option<HashMap<String, String>> = serde_wasm_bindgen::from_value(headers)?;
+            let mut retry = RetryPolicy::default();
+            if let Some(max_retries) = max_retries {
+                retry.max_retries = max_retries;
+            }
+            let client = WasmHttpClient::new(headers, retry);
+            let etag = match client
+                .revalidate(&url, cached_etag.as_deref())
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))?
+            {
+                Revalidation::NotModified => return Ok(None),
+                Revalidation::Modified(etag) => etag,
+            };
+            let mut reader = Self::_open(client, &url).await?;
+            reader.etag = etag;
+            Ok(Some(reader))
+        }
+
+        /// ETag of the dataset sent by the server, to pass to `openWithEtag` later
+        #[wasm_bindgen(getter)]
+        pub fn etag(&self) -> Option<String> {
+            self.etag.clone()
+        }
+
+        /// Open the dataset at `url` with `client`, wrapped to count its requests
+        async fn _open(client: WasmHttpClient, url: &str) -> Result<HttpFcbReader, JsValue> {
+            let client = CountingClient::new(client);
+            let counters = client.counters();
+            let mut client = AsyncBufferedHttpRangeClient::with(client, url);
             // Because we use a buffered HTTP reader, anything extra we fetch here can
             // be utilized to skip subsequent fetches.
             // Immediately following the header is the optional spatial index, we deliberately fetch
@@ -114,18 +208,15 @@ mod wasm {
             client.set_min_req_size(min_req_size);
             debug!("fetching header. min_req_size: {min_req_size} (assumed_header_size: {assumed_header_size}, prefetched_index_bytes: {prefetch_index_bytes})");
             let mut read_bytes = 0;
-            let bytes = client
-                .get_range(read_bytes, MAGIC_BYTES_SIZE)
+            let bytes = counted_get_range(&mut client, &counters, read_bytes, MAGIC_BYTES_SIZE)
                 .await
                 .map_err(|e| JsValue::from_str(&e.to_string()))?; // to get magic bytes
-            if !check_magic_bytes(bytes) {
-                return Err(JsValue::from_str("MissingMagicBytes"));
-            }
+            let version =
+                parse_magic_bytes(bytes).map_err(|e| JsValue::from_str(&e.to_string()))?;
 
             read_bytes += MAGIC_BYTES_SIZE;
             let mut bytes = BytesMut::from(
-                client
-                    .get_range(read_bytes, HEADER_SIZE_SIZE)
+                counted_get_range(&mut client, &counters, read_bytes, HEADER_SIZE_SIZE)
                     .await
                     .map_err(|e| JsValue::from_str(&e.to_string()))?,
             );
@@ -140,8 +231,7 @@ mod wasm {
             }
 
             bytes.put(
-                client
-                    .get_range(read_bytes, header_size)
+                counted_get_range(&mut client, &counters, read_bytes, header_size)
                     .await
                     .map_err(|e| JsValue::from_str(&e.to_string()))?,
             );
@@ -158,10 +248,20 @@ mod wasm {
                     header_buf,
                     features_buf: Vec::new(),
                 },
+                counters,
+                etag: None,
+                version,
             })
         }
 
-        #[wasm_bindgen]
+        /// Counters of the requests issued so far, as
+        /// `{ requestCount, headRequestCount, bytesRequested, bytesReceived, cacheHits }`
+        #[wasm_bindgen(js_name = getMetrics, unchecked_return_type = "HttpMetrics")]
+        pub fn get_metrics(&self) -> Result<JsValue, JsValue> {
+            Ok(to_value(&self.counters.snapshot())?)
+        }
+
+        #[wasm_bindgen(unchecked_return_type = "CityJSON")]
         pub fn cityjson(&self) -> Result<JsValue, JsValue> {
             let header = self.fbs.header();
             info!("header in the function: {:?}", to_cj_metadata(&header));
@@ -175,7 +275,7 @@ mod wasm {
             Ok(jsval)
         }
 
-        #[wasm_bindgen]
+        #[wasm_bindgen(unchecked_return_type = "FcbMeta")]
         pub fn meta(&self) -> Result<JsValue, JsValue> {
             let meta = self
                 .fbs
@@ -189,11 +289,16 @@ mod wasm {
             MAGIC_BYTES_SIZE + self.fbs.header_buf.len()
         }
 
+        /// Layout of the R-tree nodes, which changed with version 2
+        fn node_layout(&self) -> NodeLayout {
+            NodeLayout::for_version(self.version)
+        }
+
         fn rtree_index_size(&self) -> usize {
             let header = self.fbs.header();
             let feat_count = header.features_count() as usize;
             if header.index_node_size() > 0 && feat_count > 0 {
-                PackedRTree::index_size(feat_count, header.index_node_size())
+                PackedRTree::index_size(feat_count, header.index_node_size(), self.node_layout())
             } else {
                 0
             }
@@ -201,21 +306,25 @@ mod wasm {
 
         fn attr_index_size(&self) -> usize {
             let header = self.fbs.header();
+            // composite indices are stored right after the single-column ones
+            let composite_lengths = header
+                .composite_index()
+                .into_iter()
+                .flat_map(|ci| ci.iter())
+                .map(|ci| ci.length() as usize);
             header
                 .attribute_index()
-                .map(|attr_index| {
-                    attr_index
-                        .iter()
-                        .try_fold(0, |acc, ai| {
-                            let len = ai.length() as usize;
-                            if len > usize::MAX - acc {
-                                Err(JsValue::from_str("attribute index size overflow"))
-                            } else {
-                                Ok(acc + len)
-                            }
-                        }) // sum of all attribute index lengths
-                        .unwrap_or(0)
-                })
+                .into_iter()
+                .flat_map(|ai| ai.iter())
+                .map(|ai| ai.length() as usize)
+                .chain(composite_lengths)
+                .try_fold(0, |acc, len| {
+                    if len > usize::MAX - acc {
+                        Err(JsValue::from_str("attribute index size overflow"))
+                    } else {
+                        Ok(acc + len)
+                    }
+                }) // sum of all attribute index lengths
                 .unwrap_or(0)
         }
 
@@ -224,30 +333,32 @@ mod wasm {
         }
 
         /// Select all features.
-        #[wasm_bindgen]
-        pub async fn select_all(self) -> Result<AsyncFeatureIter, JsValue> {
+        #[wasm_bindgen(unchecked_return_type = "AsyncFeatureIter")]
+        pub async fn select_all(self) -> Result<JsValue, JsValue> {
             let header = self.fbs.header();
             let count = header.features_count();
             // TODO: support reading with unknown feature count
             let index_size = self.index_size();
             // Skip index
             let feature_base = self.header_len() + index_size;
-            Ok(AsyncFeatureIter {
-                client: self.client,
-                fbs: self.fbs,
-                selection: FeatureSelection::SelectAll(SelectAll {
+            AsyncFeatureIter::new(
+                self.client,
+                self.fbs,
+                self.counters,
+                FeatureSelection::SelectAll(SelectAll {
                     features_left: count,
                     pos: feature_base,
                 }),
-                count: count as usize,
-            })
+                count as usize,
+            )
+            .into_js()
         }
         /// Select features within a bounding box.
-        #[wasm_bindgen]
+        #[wasm_bindgen(unchecked_return_type = "AsyncFeatureIter")]
         pub async fn select_spatial(
             mut self,
             query: &WasmSpatialQuery,
-        ) -> Result<AsyncFeatureIter, JsValue> {
+        ) -> Result<JsValue, JsValue> {
             trace!("starting: select_spatial, traversing index");
             // Read R-Tree index and build filter for features within bbox
             let header = self.fbs.header();
@@ -260,6 +371,7 @@ mod wasm {
             // request up to this many extra bytes if it means we can eliminate an extra request
             let combine_request_threshold = 256 * 1024;
             let attr_index_size = self.attr_index_size();
+            let layout = self.node_layout();
 
             // Clone the inner query value
             let inner_query = query.get_inner();
@@ -269,9 +381,10 @@ mod wasm {
                 header_len,
                 attr_index_size,
                 count,
-                PackedRTree::DEFAULT_NODE_SIZE,
+                header.index_node_size(),
                 inner_query,
                 combine_request_threshold,
+                layout,
             )
             .await
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
@@ -281,25 +394,32 @@ mod wasm {
                 "Since the tree is traversed breadth first, list should be sorted by construction."
             );
 
+            let polygon = match &query.ring {
+                Some(ring) => Some(
+                    PolygonFilter::new(ring, &header)
+                        .map_err(|e| JsValue::from_str(&e.to_string()))?,
+                ),
+                None => None,
+            };
+
             let count = list.len();
             let feature_batches = FeatureBatch::make_batches(list, combine_request_threshold)
                 .await
                 .map_err(|e| JsValue::from_str(&e.to_string()))?;
             let selection = FeatureSelection::SelectSpatial(SelectSpatial { feature_batches });
             trace!("completed: select_spatial");
-            Ok(AsyncFeatureIter {
-                client: self.client,
-                fbs: self.fbs,
-                selection,
-                count,
-            })
+            // for polygon queries the count is the number of bbox candidates
+            let mut iter =
+                AsyncFeatureIter::new(self.client, self.fbs, self.counters, selection, count);
+            iter.polygon = polygon;
+            iter.into_js()
         }
 
-        #[wasm_bindgen]
+        #[wasm_bindgen(unchecked_return_type = "AsyncFeatureIter")]
         pub async fn select_attr_query(
             mut self,
             query: &WasmAttrQuery,
-        ) -> Result<AsyncFeatureIter, JsValue> {
+        ) -> Result<JsValue, JsValue> {
             trace!("starting: select_attr_query via http reader");
             let header = self.fbs.header();
             let header_len = self.header_len();
@@ -324,9 +444,6 @@ mod wasm {
                 .collect();
             attr_index_entries.sort_by_key(|attr_info| attr_info.index());
 
-            // Build the query
-            let query = build_query(&query.inner);
-
             // Create a StreamableMultiIndex from HTTP range requests
             let mut http_multi_index = HttpMultiIndex::new();
             let mut current_index_begin = attr_index_begin;
@@ -346,39 +463,37 @@ mod wasm {
             }
             info!("current index begin: {}", current_index_begin);
             // self.client.set_min_req_size(combine_request_threshold);
-            let result = http_multi_index
-                .query(&mut self.client, &query.conditions)
-                .await
-                .map_err(|e| JsValue::from_str(&format!("failed to query index: {:?}", e)))?;
-
-            info!("result: {:?}", result);
-            let count = result.len();
+            // The index only intersects conditions, so each AND group is queried
+            // separately and the results of the groups are combined.
+            let mut result = Vec::new();
+            for group in query.and_groups() {
+                let group_query = build_query(&group);
+                let items = http_multi_index
+                    .query(&mut self.client, &group_query.conditions)
+                    .await
+                    .map_err(|e| JsValue::from_str(&format!("failed to query index: {:?}", e)))?;
+                result.extend(items);
+            }
 
-            let http_ranges: Vec<HttpRange> = result
-                .into_iter()
-                .map(|item| match item.range {
-                    fcb_core::static_btree::http::HttpRange::Range(range) => {
-                        HttpRange::Range(range.start..range.end)
-                    }
-                    fcb_core::static_btree::http::HttpRange::RangeFrom(range) => {
-                        HttpRange::RangeFrom(range.start..)
-                    }
-                })
-                .collect();
+            let http_ranges = feature_ranges(result);
+            info!("result: {:?}", http_ranges);
+            let count = http_ranges.len();
 
             trace!(
                 "completed: select_attr_query via http reader, matched features: {}",
                 count
             );
-            Ok(AsyncFeatureIter {
-                client: self.client,
-                fbs: self.fbs,
-                selection: FeatureSelection::SelectAttr(SelectAttr {
+            AsyncFeatureIter::new(
+                self.client,
+                self.fbs,
+                self.counters,
+                FeatureSelection::SelectAttr(SelectAttr {
                     ranges: http_ranges,
                     range_pos: 0,
                 }),
                 count,
-            })
+            )
+            .into_js()
         }
 
         fn add_indices_to_multi_http_index(
@@ -397,6 +512,16 @@ mod wasm {
                     col.name(),
                     col.type_()
                 );
+                if col.nullable_index() {
+                    return add_nullable_http_index(
+                        multi_index,
+                        col,
+                        attr_info,
+                        index_begin,
+                        feature_begin,
+                        combine_request_threshold,
+                    );
+                }
                 match col.type_() {
                     ColumnType::Int => {
                         let index = HttpIndex::<i32>::new(
@@ -408,6 +533,16 @@ mod wasm {
                         );
                         multi_index.add_index(col.name().to_string(), index);
                     }
+                    ColumnType::Long => {
+                        let index = HttpIndex::<i64>::new(
+                            attr_info.num_unique_items() as usize,
+                            attr_info.branching_factor(),
+                            index_begin,
+                            feature_begin,
+                            combine_request_threshold,
+                        );
+                        multi_index.add_index(col.name().to_string(), index);
+                    }
                     ColumnType::Float => {
                         let index = HttpIndex::<Float<f32>>::new(
                             attr_info.num_unique_items() as usize,
@@ -533,12 +668,311 @@ mod wasm {
         }
     }
 
+    /// Adds an index whose keys carry a null marker, see [`Nullable`]
+    fn add_nullable_http_index(
+        multi_index: &mut HttpMultiIndex<WasmHttpClient>,
+        col: &Column,
+        attr_info: &AttributeIndex,
+        index_begin: usize,
+        feature_begin: usize,
+        combine_request_threshold: usize,
+    ) -> Result<(), JsValue> {
+        fn add<K: Key + 'static>(
+            multi_index: &mut HttpMultiIndex<WasmHttpClient>,
+            col: &Column,
+            attr_info: &AttributeIndex,
+            index_begin: usize,
+            feature_begin: usize,
+            combine_request_threshold: usize,
+        ) -> Result<(), JsValue>
+        where
+            HttpIndex<Nullable<K>>: TypedHttpSearchIndex<WasmHttpClient>,
+        {
+            let index = HttpIndex::<Nullable<K>>::new(
+                attr_info.num_unique_items() as usize,
+                attr_info.branching_factor(),
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            );
+            multi_index.add_index(col.name().to_string(), index);
+            info!("Added nullable index for column: {:?}", col.name());
+            Ok(())
+        }
+
+        match col.type_() {
+            ColumnType::Int => add::<i32>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::Long => add::<i64>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::Float => add::<Float<f32>>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::Double => add::<Float<f64>>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::String => add::<FixedStringKey<50>>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::Bool => add::<bool>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::DateTime => add::<DateTime<Utc>>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::Short => add::<i16>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::UShort => add::<u16>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::UInt => add::<u32>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::ULong => add::<u64>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::Byte => add::<i8>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            ColumnType::UByte => add::<u8>(
+                multi_index,
+                col,
+                attr_info,
+                index_begin,
+                feature_begin,
+                combine_request_threshold,
+            ),
+            _ => Err(JsValue::from_str(&format!(
+                "Unsupported column type: {:?}",
+                col.type_()
+            ))),
+        }
+    }
+
+    impl AsyncFeatureIter {
+        fn new(
+            client: AsyncBufferedHttpRangeClient<CountingClient<WasmHttpClient>>,
+            fbs: FcbBuffer,
+            counters: Arc<HttpCounters>,
+            selection: FeatureSelection,
+            count: usize,
+        ) -> Self {
+            Self {
+                client,
+                fbs,
+                selection,
+                count,
+                progress: None,
+                features_read: 0,
+                bytes_read: 0,
+                counters,
+                polygon: None,
+                type_filter: None,
+                prefetched: VecDeque::new(),
+                abort: None,
+                filter_lod: None,
+            }
+        }
+
+        /// Convert into a JS object usable with `for await (const feature of iter)`.
+        ///
+        /// wasm-bindgen cannot export symbol-keyed methods, so `[Symbol.asyncIterator]`
+        /// is installed on the class prototype the first time an iterator is created.
+        fn into_js(self) -> Result<JsValue, JsValue> {
+            let iter = JsValue::from(self);
+            let proto = js_sys::Object::get_prototype_of(&iter);
+            let key = js_sys::Symbol::async_iterator();
+            if !js_sys::Reflect::has(&proto, &key)? {
+                let return_this = js_sys::Function::new_no_args("return this;");
+                js_sys::Reflect::set(&proto, &key, &return_this)?;
+            }
+            Ok(iter)
+        }
+
+        /// Iterator result object `{ value, done }`
+        fn iter_result(value: Option<JsValue>) -> Result<JsValue, JsValue> {
+            let result = js_sys::Object::new();
+            let done = value.is_none();
+            js_sys::Reflect::set(
+                &result,
+                &JsValue::from_str("value"),
+                &value.unwrap_or(JsValue::UNDEFINED),
+            )?;
+            js_sys::Reflect::set(
+                &result,
+                &JsValue::from_str("done"),
+                &JsValue::from_bool(done),
+            )?;
+            Ok(result.into())
+        }
+
+        /// Whether the registered `AbortSignal` has fired
+        fn aborted(&self) -> bool {
+            self.abort.as_ref().is_some_and(Abort::aborted)
+        }
+
+        fn report_progress(&self) -> Result<(), JsValue> {
+            let Some(progress) = &self.progress else {
+                return Ok(());
+            };
+            let event = ProgressEvent {
+                features_written: self.features_read,
+                bytes_written: self.bytes_read,
+                total_features: self.features_count(),
+            };
+            progress.call1(&JsValue::NULL, &to_value(&event)?)?;
+            Ok(())
+        }
+
+        /// Fetch, verify and filter the next selected feature, `None` once all are read
+        async fn next_cj_feature(
+            &mut self,
+            options: &DeserializeOptions,
+        ) -> Result<Option<CityJSONFeature>, JsValue> {
+            let checksum_size = if self._header().checksum() {
+                CHECKSUM_SIZE
+            } else {
+                0
+            };
+            loop {
+                if self.aborted() {
+                    return Ok(None);
+                }
+                let buffer = match self.prefetched.pop_front() {
+                    Some(buffer) => buffer,
+                    None => {
+                        let fetch = self.selection.next_feature_buffer(
+                            &mut self.client,
+                            &self.counters,
+                            checksum_size,
+                        );
+                        let fetched = match &self.abort {
+                            Some(abort) => match abort.race(fetch).await {
+                                Some(fetched) => fetched,
+                                None => return Ok(None),
+                            },
+                            None => fetch.await,
+                        };
+                        let Some(buffer) =
+                            fetched.map_err(|e| JsValue::from_str(&e.to_string()))?
+                        else {
+                            return Ok(None);
+                        };
+                        buffer
+                    }
+                };
+
+                // Not zero-copy
+                self.fbs.features_buf = buffer.to_vec();
+                if checksum_size > 0 {
+                    let crc_buf = self
+                        .fbs
+                        .features_buf
+                        .split_off(self.fbs.features_buf.len() - checksum_size);
+                    let expected = LittleEndian::read_u32(&crc_buf);
+                    let actual = crc32(&self.fbs.features_buf);
+                    if expected != actual {
+                        return Err(JsValue::from_str(&format!(
+                            "checksum mismatch: expected {expected:#010x}, got {actual:#010x}"
+                        )));
+                    }
+                }
+                // verify flatbuffer
+                let feature = size_prefixed_root_as_city_feature(&self.fbs.features_buf)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+                if let Some(polygon) = &self.polygon {
+                    if !polygon.matches(&feature) {
+                        continue;
+                    }
+                }
+                if let Some(type_filter) = &self.type_filter {
+                    if !type_filter.matches(&feature) {
+                        continue;
+                    }
+                }
+                let cj_feature = self
+                    .fbs
+                    .cj_feature_with_options(options)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+                self.features_read += 1;
+                self.bytes_read += self.fbs.features_buf.len() as u64;
+                self.report_progress()?;
+
+                return Ok(Some(cj_feature));
+            }
+        }
+    }
+
     #[wasm_bindgen]
     impl AsyncFeatureIter {
         fn _header(&self) -> Header {
             self.fbs.header()
         }
-        #[wasm_bindgen]
+        #[wasm_bindgen(unchecked_return_type = "CityJSON")]
         pub fn header(&self) -> Result<JsValue, JsValue> {
             let header = self.fbs.header();
             let cj = to_cj_metadata(&header).map_err(|e| JsValue::from_str(&e.to_string()))?;
@@ -553,45 +987,216 @@ mod wasm {
                 None
             }
         }
-        /// Read next feature
-        #[wasm_bindgen]
-        pub async fn next(&mut self) -> Result<Option<JsValue>, JsValue> {
-            let Some(buffer) = self
-                .selection
-                .next_feature_buffer(&mut self.client)
-                .await
-                .map_err(|e| JsValue::from_str(&e.to_string()))?
-            else {
-                return Ok(None);
-            };
 
-            // Not zero-copy
-            self.fbs.features_buf = buffer.to_vec();
-            // verify flatbuffer
-            let feature = size_prefixed_root_as_city_feature(&self.fbs.features_buf)
+        /// Counters of the requests issued so far, including those of the reader
+        #[wasm_bindgen(js_name = getMetrics, unchecked_return_type = "HttpMetrics")]
+        pub fn get_metrics(&self) -> Result<JsValue, JsValue> {
+            Ok(to_value(&self.counters.snapshot())?)
+        }
+
+        /// Registers a JS callback that is called after each feature is read.
+        ///
+        /// The callback receives an object `{ featuresWritten, bytesWritten, totalFeatures }`.
+        #[wasm_bindgen(js_name = withProgress)]
+        pub fn with_progress(
+            &mut self,
+            #[wasm_bindgen(unchecked_param_type = "(event: FcbProgressEvent) => void")]
+            cb: js_sys::Function,
+        ) {
+            self.progress = Some(cb);
+        }
+
+        /// Stops the iteration once `signal` fires: `next` then resolves to `done: true`.
+        ///
+        /// A pending `next` or `prefetch` resolves as soon as the signal fires, without waiting
+        /// for the response of the request in flight.
+        #[wasm_bindgen(js_name = withCancellation)]
+        pub fn with_cancellation(
+            &mut self,
+            #[wasm_bindgen(unchecked_param_type = "AbortSignal")] signal: js_sys::Object,
+        ) -> Result<(), JsValue> {
+            self.abort = Some(Abort::new(signal)?);
+            Ok(())
+        }
+
+        /// Only yield features with at least one city object of the given types, e.g. `["Building"]`.
+        ///
+        /// Features of other types are still fetched but skipped without deserialization.
+        #[wasm_bindgen(js_name = filterByType)]
+        pub fn filter_by_type(&mut self, types: Vec<String>) -> Result<(), JsValue> {
+            let filter = CityObjectTypeFilter::from_names(&types)
                 .map_err(|e| JsValue::from_str(&e.to_string()))?;
-            let cj_feature = to_cj_feature(
-                feature,
-                self._header().columns(),
-                self._header().semantic_columns(),
-            )
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            self.type_filter = Some(filter);
+            Ok(())
+        }
 
-            Ok(Some(to_value(&cj_feature)?))
+        /// Only decode geometries of the given LODs, e.g. `["2.2"]`. Call before iterating.
+        ///
+        /// Other geometries are skipped before decoding, city objects without a matching
+        /// geometry get an empty `geometry` array. Geometry instances are always kept.
+        #[wasm_bindgen(js_name = setLodFilter)]
+        pub fn set_lod_filter(&mut self, lods: Vec<String>) {
+            self.filter_lod = Some(lods);
         }
 
+        /// Fetch the next `n` selected features ahead of time, so that the following calls to
+        /// `next` resolve without any request, e.g. to show the first screen of data right away.
         #[wasm_bindgen]
+        pub async fn prefetch(&mut self, n: usize) -> Result<(), JsValue> {
+            let checksum_size = if self._header().checksum() {
+                CHECKSUM_SIZE
+            } else {
+                0
+            };
+            for _ in 0..n {
+                if self.aborted() {
+                    break;
+                }
+                let fetch = self.selection.next_feature_buffer(
+                    &mut self.client,
+                    &self.counters,
+                    checksum_size,
+                );
+                let fetched = match &self.abort {
+                    Some(abort) => match abort.race(fetch).await {
+                        Some(fetched) => fetched,
+                        None => break,
+                    },
+                    None => fetch.await,
+                };
+                let Some(buffer) = fetched.map_err(|e| JsValue::from_str(&e.to_string()))? else {
+                    break;
+                };
+                self.prefetched.push_back(buffer);
+            }
+            Ok(())
+        }
+
+        /// Read next feature.
+        ///
+        /// Follows the JS iterator protocol and resolves to `{ value: feature, done: false }`,
+        /// or `{ value: undefined, done: true }` once all features are read.
+        ///
+        /// With `lod`, only geometries of that LOD are decoded, city objects without one get
+        /// an empty `geometry` array. It narrows the LODs of `setLodFilter`.
+        #[wasm_bindgen(unchecked_return_type = "IteratorResult<CityJSONFeature, undefined>")]
+        pub async fn next(&mut self, lod: Option<String>) -> Result<JsValue, JsValue> {
+            // taken out while iterating, the options borrow it
+            let filter_lod = self.filter_lod.take();
+            let only_lod = lod.map(|lod| {
+                if filter_lod.as_ref().is_none_or(|lods| lods.contains(&lod)) {
+                    vec![lod]
+                } else {
+                    Vec::new()
+                }
+            });
+            let options = DeserializeOptions {
+                lods: only_lod.as_deref().or(filter_lod.as_deref()),
+            };
+            let next = self.next_cj_feature(&options).await;
+            self.filter_lod = filter_lod;
+            match next? {
+                Some(cj_feature) => Self::iter_result(Some(to_value(&cj_feature)?)),
+                None => Self::iter_result(None),
+            }
+        }
+
+        #[wasm_bindgen(unchecked_return_type = "CityJSONFeature")]
         pub fn cur_cj_feature(&self) -> Result<JsValue, JsValue> {
-            let cj_feature = to_cj_feature(
-                self.fbs.feature(),
-                self._header().columns(),
-                self._header().semantic_columns(),
-            )
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            let cj_feature = self
+                .fbs
+                .cj_feature()
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
             Ok(to_value(&cj_feature)?)
         }
     }
 
+    /// Converts the remaining features of an iterator into a Wavefront OBJ model and its MTL
+    /// material library, resolving to `{ obj, mtl }`.
+    ///
+    /// Each city object becomes an OBJ group with the surfaces of its geometry with the highest
+    /// LoD. The OBJ has no `mtllib` statement, add one referring to wherever the MTL is stored.
+    #[wasm_bindgen(js_name = convertFcbToObj, unchecked_return_type = "ObjExport")]
+    pub async fn convert_fcb_to_obj(mut iter: AsyncFeatureIter) -> Result<JsValue, JsValue> {
+        let filter_lod = iter.filter_lod.take();
+        let options = DeserializeOptions {
+            lods: filter_lod.as_deref(),
+        };
+        let mut obj_writer = ObjWriter::new(Vec::new(), &iter._header());
+        while let Some(feature) = iter.next_cj_feature(&options).await? {
+            obj_writer
+                .add_feature(&feature)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
+        let (obj, mtl) = obj_writer
+            .finish()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let obj = String::from_utf8(obj).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        let result = js_sys::Object::new();
+        js_sys::Reflect::set(&result, &JsValue::from_str("obj"), &JsValue::from_str(&obj))?;
+        js_sys::Reflect::set(&result, &JsValue::from_str("mtl"), &JsValue::from_str(&mtl))?;
+        Ok(result.into())
+    }
+
+    /// An `AbortSignal` as a future, to stop waiting for a request once the signal fires
+    struct Abort {
+        signal: js_sys::Object,
+        /// Resolved by the `abort` event of the signal
+        fired: js_sys::Promise,
+    }
+
+    impl Abort {
+        fn new(signal: js_sys::Object) -> Result<Self, JsValue> {
+            let add_event_listener: js_sys::Function =
+                js_sys::Reflect::get(&signal, &JsValue::from_str("addEventListener"))?
+                    .dyn_into()
+                    .map_err(|_| JsValue::from_str("signal is not an AbortSignal"))?;
+            let mut listen = |resolve: js_sys::Function, reject: js_sys::Function| {
+                let options = js_sys::Object::new();
+                let listening =
+                    js_sys::Reflect::set(&options, &JsValue::from_str("once"), &JsValue::TRUE)
+                        .and_then(|_| {
+                            add_event_listener.call3(
+                                &signal,
+                                &JsValue::from_str("abort"),
+                                &resolve,
+                                &options,
+                            )
+                        });
+                if let Err(e) = listening {
+                    let _ = reject.call1(&JsValue::UNDEFINED, &e);
+                }
+            };
+            let fired = js_sys::Promise::new(&mut listen);
+            Ok(Self { signal, fired })
+        }
+
+        /// Whether the signal has fired
+        fn aborted(&self) -> bool {
+            js_sys::Reflect::get(&self.signal, &JsValue::from_str("aborted"))
+                .map(|aborted| aborted.is_truthy())
+                .unwrap_or(false)
+        }
+
+        /// Output of `future`, or None if the signal fires first, dropping `future`
+        async fn race<F: Future>(&self, future: F) -> Option<F::Output> {
+            if self.aborted() {
+                return None;
+            }
+            let mut fired =
+                std::pin::pin!(wasm_bindgen_futures::JsFuture::from(self.fired.clone()));
+            let mut future = std::pin::pin!(future);
+            std::future::poll_fn(|cx| {
+                if fired.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(None);
+                }
+                future.as_mut().poll(cx).map(Some)
+            })
+            .await
+        }
+    }
+
     enum FeatureSelection {
         SelectAll(SelectAll),
         SelectSpatial(SelectSpatial),
@@ -601,14 +1206,26 @@ mod wasm {
     impl FeatureSelection {
         async fn next_feature_buffer<T: AsyncHttpRangeClient>(
             &mut self,
-            client: &mut AsyncBufferedHttpRangeClient<T>,
+            client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+            counters: &HttpCounters,
+            checksum_size: usize,
         ) -> Result<Option<Bytes>, Error> {
             match self {
-                FeatureSelection::SelectAll(select_all) => select_all.next_buffer(client).await,
+                FeatureSelection::SelectAll(select_all) => {
+                    select_all
+                        .next_buffer(client, counters, checksum_size)
+                        .await
+                }
                 FeatureSelection::SelectSpatial(select_spatial) => {
-                    select_spatial.next_buffer(client).await
+                    select_spatial
+                        .next_buffer(client, counters, checksum_size)
+                        .await
+                }
+                FeatureSelection::SelectAttr(select_attr) => {
+                    select_attr
+                        .next_buffer(client, counters, checksum_size)
+                        .await
                 }
-                FeatureSelection::SelectAttr(select_attr) => select_attr.next_buffer(client).await,
             }
         }
     }
@@ -624,7 +1241,9 @@ mod wasm {
     impl SelectAll {
         async fn next_buffer<T: AsyncHttpRangeClient>(
             &mut self,
-            client: &mut AsyncBufferedHttpRangeClient<T>,
+            client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+            counters: &HttpCounters,
+            checksum_size: usize,
         ) -> Result<Option<Bytes>, Error> {
             client.min_req_size(DEFAULT_HTTP_FETCH_SIZE);
 
@@ -633,17 +1252,20 @@ mod wasm {
             }
             self.features_left -= 1;
 
-            let mut feature_buffer =
-                BytesMut::from(client.get_range(self.pos, 4).await.map_err(|_| Error)?);
+            let mut feature_buffer = BytesMut::from(
+                counted_get_range(client, counters, self.pos, 4)
+                    .await
+                    .map_err(|_| Error)?,
+            );
             self.pos += 4;
             let feature_size = LittleEndian::read_u32(&feature_buffer) as usize;
+            let read_size = feature_size + checksum_size;
             feature_buffer.put(
-                client
-                    .get_range(self.pos, feature_size)
+                counted_get_range(client, counters, self.pos, read_size)
                     .await
                     .map_err(|_| Error)?,
             );
-            self.pos += feature_size;
+            self.pos += read_size;
 
             Ok(Some(feature_buffer.freeze()))
         }
@@ -657,14 +1279,19 @@ mod wasm {
     impl SelectSpatial {
         async fn next_buffer<T: AsyncHttpRangeClient>(
             &mut self,
-            client: &mut AsyncBufferedHttpRangeClient<T>,
+            client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+            counters: &HttpCounters,
+            checksum_size: usize,
         ) -> Result<Option<Bytes>, Error> {
             let mut next_buffer = None;
             while next_buffer.is_none() {
                 let Some(feature_batch) = self.feature_batches.last_mut() else {
                     break;
                 };
-                let Some(buffer) = feature_batch.next_buffer(client).await? else {
+                let Some(buffer) = feature_batch
+                    .next_buffer(client, counters, checksum_size)
+                    .await?
+                else {
                     // done with this batch
                     self.feature_batches
                         .pop()
@@ -763,7 +1390,9 @@ mod wasm {
 
         async fn next_buffer<T: AsyncHttpRangeClient>(
             &mut self,
-            client: &mut AsyncBufferedHttpRangeClient<T>,
+            client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+            counters: &HttpCounters,
+            checksum_size: usize,
         ) -> Result<Option<Bytes>, Error> {
             let request_size = self.request_size();
             client.set_min_req_size(request_size);
@@ -772,13 +1401,16 @@ mod wasm {
             };
 
             let mut pos = feature_range.start();
-            let mut feature_buffer =
-                BytesMut::from(client.get_range(pos, 4).await.map_err(|_| Error)?);
+            let mut feature_buffer = BytesMut::from(
+                counted_get_range(client, counters, pos, 4)
+                    .await
+                    .map_err(|_| Error)?,
+            );
             pos += 4;
             let feature_size = LittleEndian::read_u32(&feature_buffer) as usize;
+            let read_size = feature_size + checksum_size;
             feature_buffer.put(
-                client
-                    .get_range(pos, feature_size)
+                counted_get_range(client, counters, pos, read_size)
                     .await
                     .map_err(|_| Error)?,
             );
@@ -796,21 +1428,22 @@ mod wasm {
     impl SelectAttr {
         async fn next_buffer<T: AsyncHttpRangeClient>(
             &mut self,
-            client: &mut AsyncBufferedHttpRangeClient<T>,
+            client: &mut AsyncBufferedHttpRangeClient<CountingClient<T>>,
+            counters: &HttpCounters,
+            checksum_size: usize,
         ) -> Result<Option<Bytes>, Error> {
             let Some(range) = self.ranges.get(self.range_pos) else {
                 return Ok(None);
             };
             let mut feature_buffer = BytesMut::from(
-                client
-                    .get_range(range.start(), 4)
+                counted_get_range(client, counters, range.start(), 4)
                     .await
                     .map_err(|_| Error)?,
             );
             let feature_size = LittleEndian::read_u32(&feature_buffer) as usize;
+            let read_size = feature_size + checksum_size;
             feature_buffer.put(
-                client
-                    .get_range(range.start() + 4, feature_size)
+                counted_get_range(client, counters, range.start() + 4, read_size)
                     .await
                     .map_err(|_| Error)?,
             );
@@ -824,12 +1457,17 @@ mod wasm {
         // Making inner private to hide it from WASM - don't expose SpatialQuery type
         #[wasm_bindgen(skip)]
         inner: SpatialQuery,
+        /// Polygon ring of a polygon query, `inner` then holds its bounding box
+        #[wasm_bindgen(skip)]
+        ring: Option<Vec<[f64; 2]>>,
     }
 
     #[wasm_bindgen]
     impl WasmSpatialQuery {
         #[wasm_bindgen(constructor)]
-        pub fn new(js_value: &JsValue) -> Result<WasmSpatialQuery, JsValue> {
+        pub fn new(
+            #[wasm_bindgen(unchecked_param_type = "SpatialQueryInput")] js_value: &JsValue,
+        ) -> Result<WasmSpatialQuery, JsValue> {
             // Parse the JS object to extract query parameters
             let obj = js_sys::Object::from(js_value.clone());
 
@@ -839,6 +1477,45 @@ mod wasm {
                 .as_string()
                 .ok_or_else(|| JsValue::from_str("Query type must be a string"))?;
 
+            if query_type == "polygon" {
+                // A GeoJSON polygon ring in `coordinates`, or the unclosed `ring`
+                let coordinates = js_sys::Reflect::get(&obj, &JsValue::from_str("coordinates"))?;
+                let ring = if coordinates.is_undefined() {
+                    let ring = get_ring_property(&obj, "ring")?;
+                    if ring.len() < 3 {
+                        return Err(JsValue::from_str(
+                            "'ring' must have at least 3 [x, y] pairs",
+                        ));
+                    }
+                    ring
+                } else {
+                    let ring = get_ring_property(&obj, "coordinates")?;
+                    if ring.len() < 4 {
+                        return Err(JsValue::from_str(
+                            "'coordinates' must have at least 3 distinct [x, y] pairs",
+                        ));
+                    }
+                    if ring.first() != ring.last() {
+                        return Err(JsValue::from_str(
+                            "'coordinates' must be a closed ring, ending with its first point",
+                        ));
+                    }
+                    ring
+                };
+                let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+                let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+                for [x, y] in &ring {
+                    min_x = min_x.min(*x);
+                    min_y = min_y.min(*y);
+                    max_x = max_x.max(*x);
+                    max_y = max_y.max(*y);
+                }
+                return Ok(WasmSpatialQuery {
+                    inner: SpatialQuery::BBox(min_x, min_y, max_x, max_y),
+                    ring: Some(ring),
+                });
+            }
+
             // Build the appropriate query based on type
             let query = match query_type.as_str() {
                 "bbox" => {
@@ -850,6 +1527,17 @@ mod wasm {
 
                     fcb_core::packed_rtree::Query::BBox(min_x, min_y, max_x, max_y)
                 }
+                "bbox3d" => {
+                    // Extract bbox coordinates including Z range
+                    let min_x = get_number_property(&obj, "minX")?;
+                    let min_y = get_number_property(&obj, "minY")?;
+                    let min_z = get_number_property(&obj, "minZ")?;
+                    let max_x = get_number_property(&obj, "maxX")?;
+                    let max_y = get_number_property(&obj, "maxY")?;
+                    let max_z = get_number_property(&obj, "maxZ")?;
+
+                    fcb_core::packed_rtree::Query::BBox3D(min_x, min_y, min_z, max_x, max_y, max_z)
+                }
                 "pointIntersects" => {
                     // Extract point coordinates
                     let x = get_number_property(&obj, "x")?;
@@ -862,7 +1550,19 @@ mod wasm {
                     let x = get_number_property(&obj, "x")?;
                     let y = get_number_property(&obj, "y")?;
 
-                    fcb_core::packed_rtree::Query::PointNearest(x, y)
+                    // Optional number of neighbours, defaults to the single nearest feature
+                    let k = js_sys::Reflect::get(&obj, &JsValue::from_str("k"))?;
+                    if k.is_undefined() {
+                        fcb_core::packed_rtree::Query::PointNearest(x, y)
+                    } else {
+                        let k = k
+                            .as_f64()
+                            .filter(|k| k.fract() == 0.0 && *k >= 0.0)
+                            .ok_or_else(|| {
+                                JsValue::from_str("'k' must be a non-negative integer")
+                            })?;
+                        fcb_core::packed_rtree::Query::KNearest(x, y, k as usize)
+                    }
                 }
                 _ => {
                     return Err(JsValue::from_str(&format!(
@@ -872,23 +1572,34 @@ mod wasm {
                 }
             };
 
-            Ok(WasmSpatialQuery { inner: query })
+            Ok(WasmSpatialQuery {
+                inner: query,
+                ring: None,
+            })
         }
 
         // Instead of exposing inner directly, expose its components via getter methods
         #[wasm_bindgen(getter)]
         pub fn query_type(&self) -> String {
+            if self.ring.is_some() {
+                return "polygon".to_string();
+            }
             match self.inner {
                 SpatialQuery::BBox(_, _, _, _) => "bbox".to_string(),
+                SpatialQuery::BBox3D(_, _, _, _, _, _) => "bbox3d".to_string(),
                 SpatialQuery::PointIntersects(_, _) => "pointIntersects".to_string(),
-                SpatialQuery::PointNearest(_, _) => "pointNearest".to_string(),
+                SpatialQuery::PointNearest(_, _) | SpatialQuery::KNearest(_, _, _) => {
+                    "pointNearest".to_string()
+                }
             }
         }
 
         #[wasm_bindgen(getter)]
         pub fn min_x(&self) -> Option<f64> {
             match self.inner {
-                SpatialQuery::BBox(min_x, _, _, _) => Some(min_x),
+                SpatialQuery::BBox(min_x, _, _, _) | SpatialQuery::BBox3D(min_x, _, _, _, _, _) => {
+                    Some(min_x)
+                }
                 _ => None,
             }
         }
@@ -896,7 +1607,17 @@ mod wasm {
         #[wasm_bindgen(getter)]
         pub fn min_y(&self) -> Option<f64> {
             match self.inner {
-                SpatialQuery::BBox(_, min_y, _, _) => Some(min_y),
+                SpatialQuery::BBox(_, min_y, _, _) | SpatialQuery::BBox3D(_, min_y, _, _, _, _) => {
+                    Some(min_y)
+                }
+                _ => None,
+            }
+        }
+
+        #[wasm_bindgen(getter)]
+        pub fn min_z(&self) -> Option<f64> {
+            match self.inner {
+                SpatialQuery::BBox3D(_, _, min_z, _, _, _) => Some(min_z),
                 _ => None,
             }
         }
@@ -904,7 +1625,9 @@ mod wasm {
         #[wasm_bindgen(getter)]
         pub fn max_x(&self) -> Option<f64> {
             match self.inner {
-                SpatialQuery::BBox(_, _, max_x, _) => Some(max_x),
+                SpatialQuery::BBox(_, _, max_x, _) | SpatialQuery::BBox3D(_, _, _, max_x, _, _) => {
+                    Some(max_x)
+                }
                 _ => None,
             }
         }
@@ -912,7 +1635,17 @@ mod wasm {
         #[wasm_bindgen(getter)]
         pub fn max_y(&self) -> Option<f64> {
             match self.inner {
-                SpatialQuery::BBox(_, _, _, max_y) => Some(max_y),
+                SpatialQuery::BBox(_, _, _, max_y) | SpatialQuery::BBox3D(_, _, _, _, max_y, _) => {
+                    Some(max_y)
+                }
+                _ => None,
+            }
+        }
+
+        #[wasm_bindgen(getter)]
+        pub fn max_z(&self) -> Option<f64> {
+            match self.inner {
+                SpatialQuery::BBox3D(_, _, _, _, _, max_z) => Some(max_z),
                 _ => None,
             }
         }
@@ -920,7 +1653,9 @@ mod wasm {
         #[wasm_bindgen(getter)]
         pub fn x(&self) -> Option<f64> {
             match self.inner {
-                SpatialQuery::PointIntersects(x, _) | SpatialQuery::PointNearest(x, _) => Some(x),
+                SpatialQuery::PointIntersects(x, _)
+                | SpatialQuery::PointNearest(x, _)
+                | SpatialQuery::KNearest(x, _, _) => Some(x),
                 _ => None,
             }
         }
@@ -928,13 +1663,57 @@ mod wasm {
         #[wasm_bindgen(getter)]
         pub fn y(&self) -> Option<f64> {
             match self.inner {
-                SpatialQuery::PointIntersects(_, y) | SpatialQuery::PointNearest(_, y) => Some(y),
+                SpatialQuery::PointIntersects(_, y)
+                | SpatialQuery::PointNearest(_, y)
+                | SpatialQuery::KNearest(_, y, _) => Some(y),
+                _ => None,
+            }
+        }
+
+        /// Ring of a polygon query as given, an array of `[x, y]` pairs
+        #[wasm_bindgen(getter, unchecked_return_type = "[x: number, y: number][] | undefined")]
+        pub fn coordinates(&self) -> JsValue {
+            match &self.ring {
+                Some(ring) => ring
+                    .iter()
+                    .map(|[x, y]| {
+                        js_sys::Array::of2(&JsValue::from_f64(*x), &JsValue::from_f64(*y))
+                    })
+                    .collect::<js_sys::Array>()
+                    .into(),
+                None => JsValue::UNDEFINED,
+            }
+        }
+
+        #[wasm_bindgen(getter)]
+        pub fn k(&self) -> Option<usize> {
+            match self.inner {
+                SpatialQuery::PointNearest(_, _) => Some(1),
+                SpatialQuery::KNearest(_, _, k) => Some(k),
                 _ => None,
             }
         }
 
         // Method to get the query data in a JS-friendly format
+        #[wasm_bindgen(unchecked_return_type = "SpatialQueryInput")]
         pub fn to_js(&self) -> JsValue {
+            if let Some(ring) = &self.ring {
+                let obj = js_sys::Object::new();
+                js_sys::Reflect::set(
+                    &obj,
+                    &JsValue::from_str("type"),
+                    &JsValue::from_str("polygon"),
+                )
+                .unwrap();
+                let js_ring: js_sys::Array = ring
+                    .iter()
+                    .map(|[x, y]| {
+                        js_sys::Array::of2(&JsValue::from_f64(*x), &JsValue::from_f64(*y))
+                    })
+                    .collect();
+                js_sys::Reflect::set(&obj, &JsValue::from_str("ring"), &js_ring).unwrap();
+                return obj.into();
+            }
             match self.inner {
                 SpatialQuery::BBox(min_x, min_y, max_x, max_y) => {
                     let obj = js_sys::Object::new();
@@ -970,6 +1749,31 @@ mod wasm {
                     .unwrap();
                     obj.into()
                 }
+                SpatialQuery::BBox3D(min_x, min_y, min_z, max_x, max_y, max_z) => {
+                    let obj = js_sys::Object::new();
+                    js_sys::Reflect::set(
+                        &obj,
+                        &JsValue::from_str("type"),
+                        &JsValue::from_str("bbox3d"),
+                    )
+                    .unwrap();
+                    for (key, value) in [
+                        ("minX", min_x),
+                        ("minY", min_y),
+                        ("minZ", min_z),
+                        ("maxX", max_x),
+                        ("maxY", max_y),
+                        ("maxZ", max_z),
+                    ] {
+                        js_sys::Reflect::set(
+                            &obj,
+                            &JsValue::from_str(key),
+                            &JsValue::from_f64(value),
+                        )
+                        .unwrap();
+                    }
+                    obj.into()
+                }
                 SpatialQuery::PointIntersects(x, y) => {
                     let obj = js_sys::Object::new();
                     js_sys::Reflect::set(
@@ -998,6 +1802,26 @@ mod wasm {
                         .unwrap();
                     obj.into()
                 }
+                SpatialQuery::KNearest(x, y, k) => {
+                    let obj = js_sys::Object::new();
+                    js_sys::Reflect::set(
+                        &obj,
+                        &JsValue::from_str("type"),
+                        &JsValue::from_str("pointNearest"),
+                    )
+                    .unwrap();
+                    js_sys::Reflect::set(&obj, &JsValue::from_str("x"), &JsValue::from_f64(x))
+                        .unwrap();
+                    js_sys::Reflect::set(&obj, &JsValue::from_str("y"), &JsValue::from_f64(y))
+                        .unwrap();
+                    js_sys::Reflect::set(
+                        &obj,
+                        &JsValue::from_str("k"),
+                        &JsValue::from_f64(k as f64),
+                    )
+                    .unwrap();
+                    obj.into()
+                }
             }
         }
 
@@ -1007,12 +1831,63 @@ mod wasm {
                 SpatialQuery::BBox(min_x, min_y, max_x, max_y) => {
                     SpatialQuery::BBox(min_x, min_y, max_x, max_y)
                 }
+                SpatialQuery::BBox3D(min_x, min_y, min_z, max_x, max_y, max_z) => {
+                    SpatialQuery::BBox3D(min_x, min_y, min_z, max_x, max_y, max_z)
+                }
                 SpatialQuery::PointIntersects(x, y) => SpatialQuery::PointIntersects(x, y),
                 SpatialQuery::PointNearest(x, y) => SpatialQuery::PointNearest(x, y),
+                SpatialQuery::KNearest(x, y, k) => SpatialQuery::KNearest(x, y, k),
             }
         }
     }
 
+    /// Ranges of the features matched by the AND groups of a query, in file order and each
+    /// only once, also when several groups match it
+    fn feature_ranges(
+        items: impl IntoIterator<Item = fcb_core::static_btree::http::HttpSearchResultItem>,
+    ) -> Vec<HttpRange> {
+        let ranges = items
+            .into_iter()
+            .map(|item| (item.range.start(), item.range))
+            .collect::<BTreeMap<_, _>>();
+        ranges
+            .into_values()
+            .map(|range| match range {
+                fcb_core::static_btree::http::HttpRange::Range(range) => {
+                    HttpRange::Range(range.start..range.end)
+                }
+                fcb_core::static_btree::http::HttpRange::RangeFrom(range) => {
+                    HttpRange::RangeFrom(range.start..)
+                }
+            })
+            .collect()
+    }
+
+    // Helper function to extract an array of [x, y] pairs from JS objects
+    fn get_ring_property(obj: &js_sys::Object, property: &str) -> Result<Vec<[f64; 2]>, JsValue> {
+        let property_value = js_sys::Reflect::get(obj, &JsValue::from_str(property))
+            .map_err(|_| JsValue::from_str(&format!("Missing '{}' field", property)))?;
+        if !js_sys::Array::is_array(&property_value) {
+            return Err(JsValue::from_str(&format!(
+                "'{}' must be an array of [x, y] pairs",
+                property
+            )));
+        }
+        js_sys::Array::from(&property_value)
+            .iter()
+            .map(|pair| {
+                let pair = js_sys::Array::from(&pair);
+                match (pair.get(0).as_f64(), pair.get(1).as_f64()) {
+                    (Some(x), Some(y)) if pair.length() == 2 => Ok([x, y]),
+                    _ => Err(JsValue::from_str(&format!(
+                        "'{}' must be an array of [x, y] pairs",
+                        property
+                    ))),
+                }
+            })
+            .collect()
+    }
+
     // Helper function to extract number properties from JS objects
     fn get_number_property(obj: &js_sys::Object, property: &str) -> Result<f64, JsValue> {
         let property_value = js_sys::Reflect::get(obj, &JsValue::from_str(property))
@@ -1029,29 +1904,57 @@ mod wasm {
     #[derive(Debug)]
     pub struct WasmAttrQuery {
         inner: AttrQuery,
+        /// Connector of each condition to the previous one, the first entry is unused
+        logical_ops: Vec<LogicalOp>,
+    }
+
+    impl WasmAttrQuery {
+        /// Splits the conditions at each `Or` into groups of AND-ed conditions.
+        fn and_groups(&self) -> Vec<AttrQuery> {
+            let mut groups: Vec<AttrQuery> = Vec::new();
+            for (i, (condition, op)) in self.inner.iter().zip(&self.logical_ops).enumerate() {
+                match groups.last_mut() {
+                    Some(group) if i > 0 && *op == LogicalOp::And => group.push(condition.clone()),
+                    _ => groups.push(vec![condition.clone()]),
+                }
+            }
+            groups
+        }
     }
 
     #[wasm_bindgen]
     impl WasmAttrQuery {
         /// Creates a new WasmAttrQuery from a JS array of query tuples.
         ///
-        /// Each query tuple must be an array of three elements:
-        /// [field: string, operator: string, value: number | boolean | string | Date]
+        /// Each query tuple must be an array of three or four elements:
+        /// [field: string, operator: string, value: number | boolean | string | Date, logicalOp?: "And" | "Or"]
+        ///
+        /// The "IsNull" operator matches features without a value and takes no value,
+        /// e.g. `["roof_type", "IsNull"]` or `["roof_type", "IsNull", null, "Or"]`.
+        ///
+        /// The "Like" operator matches string values against a glob pattern, `*` matching any
+        /// sequence of characters and `?` a single one, e.g. `["identificatie", "Like", "NL.IMBAG*"]`.
+        ///
+        /// `logicalOp` connects a condition to the previous one and defaults to "And".
+        /// "And" binds tighter than "Or", and it is ignored on the first condition.
         ///
         /// For example, in JavaScript you could pass:
         /// `[ ["b3_h_dak_50p", "Gt", 2.0],
-        ///   ["identificatie", "Eq", "NL.IMBAG.Pand.0503100000012869"],
+        ///   ["identificatie", "Eq", "NL.IMBAG.Pand.0503100000012869", "Or"],
         ///   ["created", "Ge", new Date("2020-01-01T00:00:00Z")] ]`
         #[wasm_bindgen(constructor)]
-        pub fn new(js_value: &JsValue) -> Result<WasmAttrQuery, JsValue> {
+        pub fn new(
+            #[wasm_bindgen(unchecked_param_type = "AttrQueryTuple[]")] js_value: &JsValue,
+        ) -> Result<WasmAttrQuery, JsValue> {
             // Expect the JS value to be an array of query tuples.
             let arr = Array::from(js_value);
             let mut inner: AttrQuery = Vec::new();
+            let mut logical_ops = Vec::new();
 
             for tuple in arr.iter() {
                 // Each tuple is expected to be an array with at least 3 elements.
                 let tuple_arr = Array::from(&tuple);
-                if tuple_arr.length() < 3 {
+                if tuple_arr.length() < 2 {
                     return Err(JsValue::from_str("Each query tuple must have 3 elements"));
                 }
 
@@ -1073,12 +1976,18 @@ mod wasm {
                     "Lt" => Operator::Lt,
                     "Le" => Operator::Le,
                     "Ne" => Operator::Ne,
+                    "IsNull" => Operator::IsNull,
+                    "Like" => Operator::Like,
                     _ => return Err(JsValue::from_str("Invalid operator value")),
                 };
 
-                // Third element: the value
+                // Third element: the value, which IsNull does not need
                 let value_js = tuple_arr.get(2);
-                let bs_value = if let Some(b) = value_js.as_bool() {
+                let bs_value = if operator == Operator::IsNull {
+                    KeyType::Null
+                } else if tuple_arr.length() < 3 {
+                    return Err(JsValue::from_str("Each query tuple must have 3 elements"));
+                } else if let Some(b) = value_js.as_bool() {
                     // If boolean then use Bool
                     KeyType::Bool(b)
                 } else if value_js.is_instance_of::<js_sys::Date>() {
@@ -1103,18 +2012,31 @@ mod wasm {
                     return Err(JsValue::from_str("Unsupported value type in query tuple"));
                 };
 
+                // Optional fourth element: logical connector to the previous condition
+                let logical_op_js = tuple_arr.get(3);
+                let logical_op = if logical_op_js.is_undefined() || logical_op_js.is_null() {
+                    LogicalOp::And
+                } else {
+                    match logical_op_js.as_string().as_deref() {
+                        Some("And") => LogicalOp::And,
+                        Some("Or") => LogicalOp::Or,
+                        _ => return Err(JsValue::from_str("Invalid logical operator value")),
+                    }
+                };
+
                 inner.push((field, operator, bs_value));
+                logical_ops.push(logical_op);
             }
 
-            Ok(WasmAttrQuery { inner })
+            Ok(WasmAttrQuery { inner, logical_ops })
         }
 
         /// Returns the inner AttrQuery as a JsValue (an array of query tuples)
         /// useful for debugging.
-        #[wasm_bindgen(getter)]
+        #[wasm_bindgen(getter, unchecked_return_type = "AttrQueryTuple[]")]
         pub fn inner(&self) -> JsValue {
             let arr = Array::new();
-            for (field, op, val) in self.inner.iter() {
+            for ((field, op, val), logical_op) in self.inner.iter().zip(&self.logical_ops) {
                 let tuple = Array::new();
                 tuple.push(&JsValue::from_str(field));
                 let op_str = match op {
@@ -1124,6 +2046,8 @@ mod wasm {
                     Operator::Lt => "Lt",
                     Operator::Le => "Le",
                     Operator::Ne => "Ne",
+                    Operator::IsNull => "IsNull",
+                    Operator::Like => "Like",
                 };
                 tuple.push(&JsValue::from_str(op_str));
                 let val_js = match val {
@@ -1142,16 +2066,86 @@ mod wasm {
                     KeyType::StringKey50(s) => JsValue::from_str(&s.to_string_lossy()),
                     KeyType::StringKey20(s) => JsValue::from_str(&s.to_string_lossy()),
                     KeyType::DateTime(dt) => JsValue::from_str(&dt.to_rfc3339()),
+                    KeyType::Null => JsValue::NULL,
                 };
                 tuple.push(&val_js);
+                let logical_op_str = match logical_op {
+                    LogicalOp::And => "And",
+                    LogicalOp::Or => "Or",
+                };
+                tuple.push(&JsValue::from_str(logical_op_str));
                 arr.push(&tuple);
             }
             arr.into()
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use fcb_core::static_btree::http::{HttpRange as IndexRange, HttpSearchResultItem};
+        use wasm_bindgen_test::wasm_bindgen_test;
+
+        fn tuple(field: &str, op: &str, value: JsValue, logical_op: Option<&str>) -> Array {
+            let tuple = Array::of3(&JsValue::from_str(field), &JsValue::from_str(op), &value);
+            if let Some(logical_op) = logical_op {
+                tuple.push(&JsValue::from_str(logical_op));
+            }
+            tuple
+        }
+
+        #[wasm_bindgen_test]
+        fn multi_group_attr_query() {
+            // height > 10 AND roof = 'flat' OR id = 'a' OR height < 2
+            let tuples = Array::new();
+            tuples.push(&tuple("height", "Gt", JsValue::from_f64(10.0), None));
+            tuples.push(&tuple("roof", "Eq", JsValue::from_str("flat"), None));
+            tuples.push(&tuple("id", "Eq", JsValue::from_str("a"), Some("Or")));
+            tuples.push(&tuple("height", "Lt", JsValue::from_f64(2.0), Some("Or")));
+            let query = WasmAttrQuery::new(&tuples.into()).unwrap();
+
+            let groups = query
+                .and_groups()
+                .into_iter()
+                .map(|group| {
+                    group
+                        .into_iter()
+                        .map(|(field, op, _)| (field, op))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                groups,
+                vec![
+                    vec![
+                        ("height".to_string(), Operator::Gt),
+                        ("roof".to_string(), Operator::Eq)
+                    ],
+                    vec![("id".to_string(), Operator::Eq)],
+                    vec![("height".to_string(), Operator::Lt)],
+                ]
+            );
+
+            // the results of the groups, the first feature is matched by two of them
+            let item = |range| HttpSearchResultItem { range };
+            let results = vec![
+                item(IndexRange::Range(100..200)),
+                item(IndexRange::Range(0..100)),
+                item(IndexRange::Range(0..100)),
+                item(IndexRange::RangeFrom(200..)),
+            ];
+            let ranges = feature_ranges(results)
+                .iter()
+                .map(|range| (range.start(), range.end()))
+                .collect::<Vec<_>>();
+            assert_eq!(ranges, vec![(0, Some(100)), (100, Some(200)), (200, None)]);
+        }
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
 pub use util::convert_cityjson_to_obj;
 #[cfg(target_arch = "wasm32")]
 pub use wasm::*;
+#[cfg(target_arch = "wasm32")]
+pub use writer::WasmFcbWriter;
@@ -0,0 +1,103 @@
+use wasm_bindgen::prelude::*;
+
+// TypeScript types of the plain JS objects passed across the bindings. They are
+// referenced by the `unchecked_param_type`/`unchecked_return_type` annotations.
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** CityJSON metadata object, the first line of a CityJSONSeq stream */
+export interface CityJSON {
+  type: "CityJSON";
+  version: string;
+  transform: { scale: number[]; translate: number[] };
+  CityObjects: Record<string, any>;
+  vertices: number[][];
+  metadata?: Record<string, any>;
+  appearance?: Record<string, any>;
+  "geometry-templates"?: Record<string, any>;
+  extensions?: Record<string, any>;
+  [key: string]: any;
+}
+
+/** A single CityJSONFeature of a CityJSONSeq stream */
+export interface CityJSONFeature {
+  type: "CityJSONFeature";
+  id: string;
+  CityObjects: Record<string, any>;
+  vertices: number[][];
+  appearance?: Record<string, any>;
+  [key: string]: any;
+}
+
+export type ColumnType =
+  | "Byte" | "UByte" | "Bool" | "Short" | "UShort" | "Int" | "UInt" | "Long"
+  | "ULong" | "Float" | "Double" | "String" | "Json" | "DateTime" | "Binary";
+
+export interface Column {
+  index: number;
+  name: string;
+  type: ColumnType;
+  title?: string;
+  description?: string;
+  precision?: number;
+  scale?: number;
+  nullable?: boolean;
+  unique?: boolean;
+  primary_key?: boolean;
+  metadata?: string;
+  attrIndex?: boolean;
+}
+
+/** Attribute columns and feature count of an FCB file */
+export interface FcbMeta {
+  columns: Column[];
+  featureCount: number;
+}
+
+export interface HttpMetrics {
+  requestCount: number;
+  headRequestCount: number;
+  bytesRequested: number;
+  bytesReceived: number;
+  cacheHits: number;
+}
+
+export interface FcbProgressEvent {
+  featuresWritten: number;
+  bytesWritten: number;
+  totalFeatures?: number;
+}
+
+export type SpatialQueryInput =
+  | { type: "bbox"; minX: number; minY: number; maxX: number; maxY: number }
+  | { type: "bbox3d"; minX: number; minY: number; minZ: number; maxX: number; maxY: number; maxZ: number }
+  | { type: "pointIntersects"; x: number; y: number }
+  | { type: "pointNearest"; x: number; y: number; k?: number }
+  | { type: "polygon"; coordinates: [x: number, y: number][] }
+  | { type: "polygon"; ring: [x: number, y: number][] };
+
+export type AttrOperator = "Eq" | "Ne" | "Gt" | "Ge" | "Lt" | "Le" | "Like" | "IsNull";
+
+export type LogicalOp = "And" | "Or";
+
+/** Result of `convertFcbToObj`, a Wavefront OBJ model and its MTL material library */
+export interface ObjExport {
+  obj: string;
+  mtl: string;
+}
+
+export type AttrQueryTuple =
+  | [field: string, operator: AttrOperator, value: number | boolean | string | Date]
+  | [field: string, operator: AttrOperator, value: number | boolean | string | Date, logicalOp: LogicalOp]
+  | [field: string, operator: "IsNull", value?: null, logicalOp?: LogicalOp];
+
+export interface WriterOptions {
+  spatialIndex?: boolean;
+  indexNodeSize?: number;
+  attributeIndices?: string[];
+  branchingFactor?: number;
+}
+
+export interface AsyncFeatureIter {
+  [Symbol.asyncIterator](): AsyncFeatureIter;
+}
+"#;
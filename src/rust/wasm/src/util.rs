@@ -31,8 +31,11 @@ pub fn cjseq_to_cj(mut base_cj: CityJSON, features: Vec<CityJSONFeature>) -> Cit
 }
 
 #[cfg(target_arch = "wasm32")]
-#[wasm_bindgen(js_name = cjseqToCj)]
-pub fn cjseq_to_cj_wasm(base_cj: JsValue, features: JsValue) -> Result<JsValue, JsValue> {
+#[wasm_bindgen(js_name = cjseqToCj, unchecked_return_type = "CityJSON")]
+pub fn cjseq_to_cj_wasm(
+    #[wasm_bindgen(unchecked_param_type = "CityJSON")] base_cj: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "CityJSONFeature[]")] features: JsValue,
+) -> Result<JsValue, JsValue> {
     let base_cj: CityJSON = match from_value(base_cj) {
         Ok(cj) => cj,
         Err(e) => {
@@ -80,7 +83,10 @@ pub fn cjseq_to_cj_wasm(base_cj: JsValue, features: JsValue) -> Result<JsValue,
 /// A string containing the OBJ data or an error
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = cjToObj)]
-pub fn convert_cityjson_to_obj(city_json_js: &JsValue) -> Result<String, JsValue> {
+pub fn convert_cityjson_to_obj(
+    #[wasm_bindgen(unchecked_param_type = "CityJSON | [CityJSON, ...CityJSONFeature[]]")]
+    city_json_js: &JsValue,
+) -> Result<String, JsValue> {
     debug!("starting: convert_cityjson_to_obj");
 
     // Check if input is an array (CityJSONSeq format) or single object (CityJSON format)
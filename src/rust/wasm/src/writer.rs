@@ -0,0 +1,182 @@
+use cjseq::{CityJSON, CityJSONFeature};
+use fcb_core::attribute::{AttributeSchema, AttributeSchemaMethods};
+use fcb_core::header_writer::HeaderWriterOptions;
+use fcb_core::{FcbWriter, PackedRTree};
+use log::debug;
+use serde::Deserialize;
+use serde_wasm_bindgen::from_value;
+use std::io::Cursor;
+use wasm_bindgen::prelude::*;
+
+/// Options accepted by the `WasmFcbWriter` constructor
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct WriterOptions {
+    /// Write the spatial index (default true)
+    spatial_index: Option<bool>,
+    /// Node size of the spatial index
+    index_node_size: Option<u16>,
+    /// Attributes to build an index for
+    attribute_indices: Vec<String>,
+    /// Branching factor of the attribute indices
+    branching_factor: Option<u16>,
+}
+
+/// Builds an FCB file in memory from a CityJSON metadata object and CityJSONFeatures.
+///
+/// Features are collected until `finish()`, since the attribute schema and the
+/// spatial index can only be built once all features are known.
+#[wasm_bindgen]
+pub struct WasmFcbWriter {
+    cj: CityJSON,
+    options: WriterOptions,
+    features: Vec<CityJSONFeature>,
+}
+
+#[wasm_bindgen]
+impl WasmFcbWriter {
+    /// `options` is an optional object
+    /// `{ spatialIndex, indexNodeSize, attributeIndices, branchingFactor }`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        #[wasm_bindgen(unchecked_param_type = "CityJSON")] cityjson_js: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "WriterOptions | undefined")] options: JsValue,
+    ) -> Result<WasmFcbWriter, JsValue> {
+        let cj: CityJSON = from_value(cityjson_js)
+            .map_err(|e| JsValue::from_str(&format!("failed to parse cityjson: {}", e)))?;
+        let options: Option<WriterOptions> = from_value(options)
+            .map_err(|e| JsValue::from_str(&format!("failed to parse options: {}", e)))?;
+        Ok(WasmFcbWriter {
+            cj,
+            options: options.unwrap_or_default(),
+            features: Vec::new(),
+        })
+    }
+
+    #[wasm_bindgen(js_name = addFeature)]
+    pub fn add_feature(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "CityJSONFeature")] feature_js: JsValue,
+    ) -> Result<(), JsValue> {
+        let feature: CityJSONFeature = from_value(feature_js)
+            .map_err(|e| JsValue::from_str(&format!("failed to parse feature: {}", e)))?;
+        self.features.push(feature);
+        Ok(())
+    }
+
+    /// Write the FCB file and return its bytes
+    pub fn finish(self) -> Result<js_sys::Uint8Array, JsValue> {
+        debug!(
+            "starting: writing fcb with {} features",
+            self.features.len()
+        );
+        let mut attr_schema = AttributeSchema::new();
+        let mut semantic_attr_schema = AttributeSchema::new();
+        for feature in self.features.iter() {
+            for (_, co) in feature.city_objects.iter() {
+                if let Some(attributes) = &co.attributes {
+                    attr_schema.add_attributes(attributes);
+                }
+                for geom in co.geometry.iter().flatten() {
+                    let surfaces = geom.semantics.iter().flat_map(|s| s.surfaces.iter());
+                    for other in surfaces.filter_map(|sem_obj| sem_obj.other.as_ref()) {
+                        semantic_attr_schema.add_attributes(other);
+                    }
+                }
+            }
+        }
+
+        let attribute_indices = (!self.options.attribute_indices.is_empty()).then(|| {
+            self.options
+                .attribute_indices
+                .iter()
+                .map(|name| (name.clone(), self.options.branching_factor))
+                .collect()
+        });
+        let header_options = HeaderWriterOptions {
+            write_index: self.options.spatial_index.unwrap_or(true),
+            feature_count: self.features.len() as u64,
+            index_node_size: self
+                .options
+                .index_node_size
+                .unwrap_or(PackedRTree::DEFAULT_NODE_SIZE),
+            attribute_indices,
+            geographical_extent: None,
+            composite_indices: None,
+            coordinate_precision: None,
+            checksum: false,
+            shared_vertices: false,
+            feature_id_index: false,
+            compute_hash: false,
+        };
+
+        let to_js = |e: fcb_core::error::Error| JsValue::from_str(&e.to_string());
+        let mut writer = FcbWriter::new(
+            self.cj,
+            Some(header_options),
+            (!attr_schema.is_empty()).then_some(attr_schema),
+            (!semantic_attr_schema.is_empty()).then_some(semantic_attr_schema),
+        )
+        .map_err(to_js)?;
+        for feature in self.features.iter() {
+            writer.add_feature(feature).map_err(to_js)?;
+        }
+        let mut out = Cursor::new(Vec::new());
+        writer.write(&mut out).map_err(to_js)?;
+
+        debug!("completed: writing fcb");
+        Ok(js_sys::Uint8Array::from(out.into_inner().as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fcb_core::{read_cityjson_from_reader, CJType, CJTypeKind, FcbReader};
+    use serde::Serialize;
+    use std::collections::HashMap;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const SMALL: &str = include_str!("../../fcb_core/tests/data/small.city.jsonl");
+
+    #[wasm_bindgen_test]
+    fn write_round_trip() {
+        let seq = match read_cityjson_from_reader(SMALL.as_bytes(), CJTypeKind::Seq).unwrap() {
+            CJType::Seq(seq) => seq,
+            _ => panic!("Expected CityJSONSeq"),
+        };
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+
+        let mut writer =
+            WasmFcbWriter::new(seq.cj.serialize(&serializer).unwrap(), JsValue::UNDEFINED).unwrap();
+        for feature in seq.features.iter() {
+            writer
+                .add_feature(feature.serialize(&serializer).unwrap())
+                .unwrap();
+        }
+        let bytes = writer.finish().unwrap().to_vec();
+
+        // features are reordered along the Hilbert curve by the spatial index
+        let mut deserialized = HashMap::new();
+        let mut iter = FcbReader::open(Cursor::new(bytes))
+            .unwrap()
+            .select_all()
+            .unwrap();
+        while let Some(feat_buf) = iter.next().unwrap() {
+            let feature = feat_buf.cur_cj_feature().unwrap();
+            deserialized.insert(feature.id.clone(), feature);
+        }
+
+        assert_eq!(seq.features.len(), deserialized.len());
+        for orig_feat in seq.features.iter() {
+            let des_feat = &deserialized[&orig_feat.id];
+            assert_eq!(orig_feat.city_objects.len(), des_feat.city_objects.len());
+            assert_eq!(orig_feat.vertices, des_feat.vertices);
+            for (id, orig_co) in orig_feat.city_objects.iter() {
+                let des_co = &des_feat.city_objects[id];
+                assert_eq!(orig_co.thetype, des_co.thetype);
+                assert_eq!(orig_co.geometry, des_co.geometry);
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+/* tslint:disable */
+/* eslint-disable */
+/**
+ * Converts a CityJSON object or CityJSONSeq list to OBJ format.
+ *
+ * # Arguments
+ *
+ * * `city_json_js` - JsValue containing either:
+ *   - A CityJSON object (for backward compatibility), or
+ *   - An array where the first element is a CityJSON object and
+ *     the rest are CityJSONFeature objects (CityJSONSeq format)
+ *
+ * # Returns
+ *
+ * A string containing the OBJ data or an error
+ */
+export function cjToObj(city_json_js: CityJSON | [CityJSON, ...CityJSONFeature[]]): string;
+export function cjseqToCj(base_cj: CityJSON, features: CityJSONFeature[]): CityJSON;
+/**
+ * Converts the remaining features of an iterator into a Wavefront OBJ model and its MTL
+ * material library, resolving to `{ obj, mtl }`.
+ *
+ * Each city object becomes an OBJ group with the surfaces of its geometry with the highest
+ * LoD. The OBJ has no `mtllib` statement, add one referring to wherever the MTL is stored.
+ */
+export function convertFcbToObj(iter: AsyncFeatureIter): Promise<ObjExport>;
+
+/** CityJSON metadata object, the first line of a CityJSONSeq stream */
+export interface CityJSON {
+  type: "CityJSON";
+  version: string;
+  transform: { scale: number[]; translate: number[] };
+  CityObjects: Record<string, any>;
+  vertices: number[][];
+  metadata?: Record<string, any>;
+  appearance?: Record<string, any>;
+  "geometry-templates"?: Record<string, any>;
+  extensions?: Record<string, any>;
+  [key: string]: any;
+}
+
+/** A single CityJSONFeature of a CityJSONSeq stream */
+export interface CityJSONFeature {
+  type: "CityJSONFeature";
+  id: string;
+  CityObjects: Record<string, any>;
+  vertices: number[][];
+  appearance?: Record<string, any>;
+  [key: string]: any;
+}
+
+export type ColumnType =
+  | "Byte" | "UByte" | "Bool" | "Short" | "UShort" | "Int" | "UInt" | "Long"
+  | "ULong" | "Float" | "Double" | "String" | "Json" | "DateTime" | "Binary";
+
+export interface Column {
+  index: number;
+  name: string;
+  type: ColumnType;
+  title?: string;
+  description?: string;
+  precision?: number;
+  scale?: number;
+  nullable?: boolean;
+  unique?: boolean;
+  primary_key?: boolean;
+  metadata?: string;
+  attrIndex?: boolean;
+}
+
+/** Attribute columns and feature count of an FCB file */
+export interface FcbMeta {
+  columns: Column[];
+  featureCount: number;
+}
+
+export interface HttpMetrics {
+  requestCount: number;
+  headRequestCount: number;
+  bytesRequested: number;
+  bytesReceived: number;
+  cacheHits: number;
+}
+
+export interface FcbProgressEvent {
+  featuresWritten: number;
+  bytesWritten: number;
+  totalFeatures?: number;
+}
+
+export type SpatialQueryInput =
+  | { type: "bbox"; minX: number; minY: number; maxX: number; maxY: number }
+  | { type: "bbox3d"; minX: number; minY: number; minZ: number; maxX: number; maxY: number; maxZ: number }
+  | { type: "pointIntersects"; x: number; y: number }
+  | { type: "pointNearest"; x: number; y: number; k?: number }
+  | { type: "polygon"; coordinates: [x: number, y: number][] }
+  | { type: "polygon"; ring: [x: number, y: number][] };
+
+export type AttrOperator = "Eq" | "Ne" | "Gt" | "Ge" | "Lt" | "Le" | "Like" | "IsNull";
+
+export type LogicalOp = "And" | "Or";
+
+/** Result of `convertFcbToObj`, a Wavefront OBJ model and its MTL material library */
+export interface ObjExport {
+  obj: string;
+  mtl: string;
+}
+
+export type AttrQueryTuple =
+  | [field: string, operator: AttrOperator, value: number | boolean | string | Date]
+  | [field: string, operator: AttrOperator, value: number | boolean | string | Date, logicalOp: LogicalOp]
+  | [field: string, operator: "IsNull", value?: null, logicalOp?: LogicalOp];
+
+export interface WriterOptions {
+  spatialIndex?: boolean;
+  indexNodeSize?: number;
+  attributeIndices?: string[];
+  branchingFactor?: number;
+}
+
+export interface AsyncFeatureIter {
+  [Symbol.asyncIterator](): AsyncFeatureIter;
+}
+
+
+export class AsyncFeatureIter {
+  private constructor();
+  free(): void;
+  header(): CityJSON;
+  /**
+   * Number of selected features (might be unknown)
+   */
+  features_count(): number | undefined;
+  /**
+   * Counters of the requests issued so far, including those of the reader
+   */
+  getMetrics(): HttpMetrics;
+  /**
+   * Registers a JS callback that is called after each feature is read.
+   *
+   * The callback receives an object `{ featuresWritten, bytesWritten, totalFeatures }`.
+   */
+  withProgress(cb: (event: FcbProgressEvent) => void): void;
+  /**
+   * Stops the iteration once `signal` fires: `next` then resolves to `done: true`.
+   *
+   * A pending `next` or `prefetch` resolves as soon as the signal fires, without waiting
+   * for the response of the request in flight.
+   */
+  withCancellation(signal: AbortSignal): void;
+  /**
+   * Only yield features with at least one city object of the given types, e.g. `["Building"]`.
+   *
+   * Features of other types are still fetched but skipped without deserialization.
+   */
+  filterByType(types: string[]): void;
+  /**
+   * Only decode geometries of the given LODs, e.g. `["2.2"]`. Call before iterating.
+   *
+   * Other geometries are skipped before decoding, city objects without a matching
+   * geometry get an empty `geometry` array. Geometry instances are always kept.
+   */
+  setLodFilter(lods: string[]): void;
+  /**
+   * Fetch the next `n` selected features ahead of time, so that the following calls to
+   * `next` resolve without any request, e.g. to show the first screen of data right away.
+   */
+  prefetch(n: number): Promise<void>;
+  /**
+   * Read next feature.
+   *
+   * Follows the JS iterator protocol and resolves to `{ value: feature, done: false }`,
+   * or `{ value: undefined, done: true }` once all features are read.
+   *
+   * With `lod`, only geometries of that LOD are decoded, city objects without one get
+   * an empty `geometry` array. It narrows the LODs of `setLodFilter`.
+   */
+  next(lod?: string | null): Promise<IteratorResult<CityJSONFeature, undefined>>;
+  cur_cj_feature(): CityJSONFeature;
+}
+/**
+ * FlatCityBuf dataset HTTP reader
+ */
+export class HttpFcbReader {
+  free(): void;
+  /**
+   * `headers` is an optional object of extra request headers, e.g.
+   * `{ Authorization: "Bearer <token>" }`, sent with every range request.
+   * Transient request failures are retried up to `max_retries` times (default 3).
+   */
+  constructor(url: string, headers: Record<string, string> | undefined, max_retries?: number | null);
+  /**
+   * Opens the dataset unless it is unchanged since `cached_etag` was received, in which
+   * case `undefined` is returned and the cached results are still valid. The first
+   * request carries `If-None-Match: <cached_etag>` along with `headers`, the ETag of the
+   * opened dataset is then available as `etag`.
+   */
+  static openWithEtag(url: string, cached_etag: string | null | undefined, headers: Record<string, string> | undefined, max_retries?: number | null): Promise<HttpFcbReader | undefined>;
+  /**
+   * Counters of the requests issued so far, as
+   * `{ requestCount, headRequestCount, bytesRequested, bytesReceived, cacheHits }`
+   */
+  getMetrics(): HttpMetrics;
+  cityjson(): CityJSON;
+  meta(): FcbMeta;
+  /**
+   * Select all features.
+   */
+  select_all(): Promise<AsyncFeatureIter>;
+  /**
+   * Select features within a bounding box.
+   */
+  select_spatial(query: WasmSpatialQuery): Promise<AsyncFeatureIter>;
+  select_attr_query(query: WasmAttrQuery): Promise<AsyncFeatureIter>;
+  /**
+   * ETag of the dataset sent by the server, to pass to `openWithEtag` later
+   */
+  readonly etag: string | undefined;
+}
+/**
+ * A wasm‑friendly wrapper over `AttrQuery`, which is defined as:
+ * `pub type AttrQuery = Vec<(String, Operator, )>;`
+ */
+export class WasmAttrQuery {
+  free(): void;
+  /**
+   * Creates a new WasmAttrQuery from a JS array of query tuples.
+   *
+   * Each query tuple must be an array of three or four elements:
+   * [field: string, operator: string, value: number | boolean | string | Date, logicalOp?: "And" | "Or"]
+   *
+   * The "IsNull" operator matches features without a value and takes no value,
+   * e.g. `["roof_type", "IsNull"]` or `["roof_type", "IsNull", null, "Or"]`.
+   *
+   * The "Like" operator matches string values against a glob pattern, `*` matching any
+   * sequence of characters and `?` a single one, e.g. `["identificatie", "Like", "NL.IMBAG*"]`.
+   *
+   * `logicalOp` connects a condition to the previous one and defaults to "And".
+   * "And" binds tighter than "Or", and it is ignored on the first condition.
+   *
+   * For example, in JavaScript you could pass:
+   * `[ ["b3_h_dak_50p", "Gt", 2.0],
+   *   ["identificatie", "Eq", "NL.IMBAG.Pand.0503100000012869", "Or"],
+   *   ["created", "Ge", new Date("2020-01-01T00:00:00Z")] ]`
+   */
+  constructor(js_value: AttrQueryTuple[]);
+  /**
+   * Returns the inner AttrQuery as a JsValue (an array of query tuples)
+   * useful for debugging.
+   */
+  readonly inner: AttrQueryTuple[];
+}
+/**
+ * Builds an FCB file in memory from a CityJSON metadata object and CityJSONFeatures.
+ *
+ * Features are collected until `finish()`, since the attribute schema and the
+ * spatial index can only be built once all features are known.
+ */
+export class WasmFcbWriter {
+  free(): void;
+  /**
+   * `options` is an optional object
+   * `{ spatialIndex, indexNodeSize, attributeIndices, branchingFactor }`.
+   */
+  constructor(cityjson_js: CityJSON, options: WriterOptions | undefined);
+  addFeature(feature_js: CityJSONFeature): void;
+  /**
+   * Write the FCB file and return its bytes
+   */
+  finish(): Uint8Array;
+}
+export class WasmSpatialQuery {
+  free(): void;
+  constructor(js_value: SpatialQueryInput);
+  to_js(): SpatialQueryInput;
+  readonly query_type: string;
+  readonly min_x: number | undefined;
+  readonly min_y: number | undefined;
+  readonly min_z: number | undefined;
+  readonly max_x: number | undefined;
+  readonly max_y: number | undefined;
+  readonly max_z: number | undefined;
+  readonly x: number | undefined;
+  readonly y: number | undefined;
+  /**
+   * Ring of a polygon query as given, an array of `[x, y]` pairs
+   */
+  readonly coordinates: [x: number, y: number][] | undefined;
+  readonly k: number | undefined;
+}